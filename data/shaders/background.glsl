@@ -0,0 +1,16 @@
+#include <globals>
+
+layout(std140) uniform b_PostParams {
+    // user parameters of the background, `u_Params[0]` to `u_Params[3]`
+    mat4 u_Params;
+    // xy: size of the output in pixels, zw: size of a pixel in texture coordinates
+    vec4 u_Resolution;
+};
+
+// an additional texture set on the background, white by default
+uniform sampler2D t_Aux;
+
+// position on the screen, from (0, 0) at the bottom left to (1, 1)
+in vec2 v_TexCoord;
+// world space direction seen through the pixel, not normalized
+in vec3 v_Direction;
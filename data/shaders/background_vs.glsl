@@ -0,0 +1,19 @@
+#version 150 core
+#include <globals>
+
+out vec2 v_TexCoord;
+out vec3 v_Direction;
+
+void main() {
+    v_TexCoord = gl_VertexID==0 ? vec2(1.0, 0.0) :
+                 gl_VertexID==1 ? vec2(0.0, 0.0) :
+                 gl_VertexID==2 ? vec2(1.0, 1.0) :
+                                  vec2(0.0, 1.0) ;
+    // on the far plane, behind everything drawn before
+    vec4 position = vec4(2.0 * v_TexCoord - 1.0, 1.0, 1.0);
+
+    mat3 inverseView = transpose(mat3(u_View));
+    v_Direction = inverseView * (u_InverseProj * position).xyz;
+
+    gl_Position = position;
+}
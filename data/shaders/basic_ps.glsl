@@ -1,11 +1,13 @@
 #version 150 core
+#include <features>
 
 in vec2 v_TexCoord;
 in vec4 v_Color;
+in float v_ViewDepth;
 out vec4 Target0;
 
 uniform sampler2D t_Map;
 
 void main() {
-    Target0 = v_Color * texture(t_Map, v_TexCoord);
+    Target0 = apply_features(v_Color * texture(t_Map, v_TexCoord), v_ViewDepth);
 }
@@ -1,11 +1,15 @@
 #version 150 core
 #include <globals>
+#include <depth>
 
 in vec4 a_Position;
 in vec4 a_Normal;
 in vec2 a_TexCoord;
+in vec2 a_TexCoord1;
+in vec4 a_Color;
 out vec2 v_TexCoord;
 out vec4 v_Color;
+out float v_ViewDepth;
 
 in vec4 i_World0;
 in vec4 i_World1;
@@ -15,7 +19,16 @@ in vec4 i_UvRange;
 
 void main() {
     mat4 m_World = transpose(mat4(i_World0, i_World1, i_World2, vec4(0.0, 0.0, 0.0, 1.0)));
+#ifdef UV1
+    v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord1);
+#else
     v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord);
+#endif
     v_Color = i_Color;
-    gl_Position = u_ViewProj * m_World * a_Position;
+#ifdef VERTEX_COLORS
+    v_Color *= a_Color;
+#endif
+    vec4 world = m_World * a_Position;
+    v_ViewDepth = -(u_View * world).z;
+    gl_Position = encode_depth(u_ViewProj * world);
 }
@@ -0,0 +1,15 @@
+#ifndef DEPTH_GLSL
+#define DEPTH_GLSL
+
+#include <globals>
+
+// Remaps the depth of a clip space position logarithmically, when the
+// renderer asks for it, to keep precision over very large depth ranges.
+vec4 encode_depth(vec4 position) {
+    if (u_DepthParams.x > 0.0) {
+        position.z = (log2(max(1e-6, 1.0 + position.w)) * u_DepthParams.x - 1.0) * position.w;
+    }
+    return position;
+}
+
+#endif
@@ -0,0 +1,49 @@
+#ifndef DISPLACEMENT_GLSL
+#define DISPLACEMENT_GLSL
+
+#ifndef MAX_TARGETS
+#define MAX_TARGETS 8U
+#endif
+
+// Toggles displacement contributions to `a_Position/a_Normal/a_Tangent`.
+struct DisplacementContribution {
+    // position: 1.0 if morph target weights should influence a_Position
+    // normal: 1.0 if morph target weights should influence a_Normal
+    // tangent: 1.0 if morph target weights should influence a_Tangent
+    // weight: The weight to be applied.
+    float position, normal, tangent, weight;
+};
+
+layout(std140) uniform b_DisplacementContributions {
+    DisplacementContribution u_DisplacementContributions[MAX_TARGETS];
+};
+
+uniform sampler2D u_Displacements;
+
+// Number of morph targets of the mesh, zero for meshes without any.
+uint num_displacement_targets() {
+    return min(uvec2(textureSize(u_Displacements, 0)).y / 3U, MAX_TARGETS);
+}
+
+// Applies the weighted morph targets to the position of the current vertex.
+vec3 displace_position(vec3 position) {
+    for (uint i = 0U; i < num_displacement_targets(); ++i) {
+        DisplacementContribution disp = u_DisplacementContributions[i];
+        if (disp.weight == 0.0) continue;
+        position += disp.position * disp.weight * texelFetch(u_Displacements, ivec2(gl_VertexID, 3U*i+0U), 0).xyz;
+    }
+    return position;
+}
+
+// Applies the weighted morph targets to the current vertex.
+void displace(inout vec3 position, inout vec3 normal, inout vec3 tangent) {
+    for (uint i = 0U; i < num_displacement_targets(); ++i) {
+        DisplacementContribution disp = u_DisplacementContributions[i];
+        if (disp.weight == 0.0) continue;
+        position += disp.position * disp.weight * texelFetch(u_Displacements, ivec2(gl_VertexID, 3U*i+0U), 0).xyz;
+        normal   += disp.normal   * disp.weight * texelFetch(u_Displacements, ivec2(gl_VertexID, 3U*i+1U), 0).xyz;
+        tangent  += disp.tangent  * disp.weight * texelFetch(u_Displacements, ivec2(gl_VertexID, 3U*i+2U), 0).xyz;
+    }
+}
+
+#endif
@@ -0,0 +1,26 @@
+#ifndef FEATURES_GLSL
+#define FEATURES_GLSL
+
+#include <globals>
+
+#ifndef ALPHA_CUTOFF
+#define ALPHA_CUTOFF 0.5
+#endif
+
+// Applies the optional features enabled by the material to the final color
+// of a pixel at the view space distance `depth`.
+vec4 apply_features(vec4 color, float depth) {
+#ifdef ALPHA_TEST
+    if (color.a < ALPHA_CUTOFF) {
+        discard;
+    }
+#endif
+#ifdef FOG
+    float range = max(u_FogRange.y - u_FogRange.x, 1e-6);
+    float amount = clamp((depth - u_FogRange.x) / range, 0.0, 1.0) * u_FogColor.a;
+    color.rgb = mix(color.rgb, u_FogColor.rgb, amount);
+#endif
+    return color;
+}
+
+#endif
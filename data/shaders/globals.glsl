@@ -1,6 +1,18 @@
+#ifndef GLOBALS_GLSL
+#define GLOBALS_GLSL
+
 layout(std140) uniform b_Globals {
     mat4 u_ViewProj;
     mat4 u_InverseProj;
     mat4 u_View;
+    vec4 u_FogColor;
+    vec4 u_FogRange;
+    vec4 u_DepthParams;
+    mat4 u_ProbeA;
+    mat4 u_ProbeB;
+    vec4 u_ProbeC;
+    vec4 u_ReflectionParams;
     uint u_NumLights;
 };
+
+#endif
@@ -1,27 +1,34 @@
 #version 150 core
+#include <features>
+#include <shadow>
 
+in vec2 v_TexCoord;
+flat in vec3 v_Emissive;
 in vec4 v_ResultColor;
 flat in vec4 v_ResultColorFlat;
 flat in float v_Smooth;
 in vec4 v_LightEval[2];
 flat in vec4 v_LightEvalFlat[2];
 in vec4 v_ShadowCoord[2];
+in float v_ViewDepth;
 
-out vec4 Target0;
+uniform sampler2D t_Map;
 
-uniform sampler2DShadow t_Shadow0;
-uniform sampler2DShadow t_Shadow1;
+out vec4 Target0;
 
 void main() {
     Target0 = mix(v_ResultColorFlat, v_ResultColor, v_Smooth);
-    if (v_ShadowCoord[0].w != 0.0) {
-        vec3 coord = v_ShadowCoord[0].xyz / v_ShadowCoord[0].w;
-        float shadow = texture(t_Shadow0, 0.5 * coord + 0.5);
-        Target0 += shadow * mix(v_LightEvalFlat[0], v_LightEval[0], v_Smooth);
-    }
-    if (v_ShadowCoord[1].w != 0.0) {
-        vec3 coord = v_ShadowCoord[1].xyz / v_ShadowCoord[1].w;
-        float shadow = texture(t_Shadow1, 0.5 * coord + 0.5);
-        Target0 += shadow * mix(v_LightEvalFlat[1], v_LightEval[1], v_Smooth);
+    // the shadowed lights were left out by the vertex shader
+    for(uint i=0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
+        Light light = u_Lights[i];
+        if (light.shadow_params[0] == 0) {
+            float shadow = sample_shadow(light, v_ShadowCoord[0]);
+            Target0 += shadow * mix(v_LightEvalFlat[0], v_LightEval[0], v_Smooth);
+        } else if (light.shadow_params[0] == 1) {
+            float shadow = sample_shadow(light, v_ShadowCoord[1]);
+            Target0 += shadow * mix(v_LightEvalFlat[1], v_LightEval[1], v_Smooth);
+        }
     }
+    Target0 = Target0 * texture(t_Map, v_TexCoord) + vec4(v_Emissive, 0.0);
+    Target0 = apply_features(Target0, v_ViewDepth);
 }
@@ -1,35 +1,57 @@
 #version 150 core
 #include <lights>
 #include <globals>
+#include <depth>
+#include <probe>
 
 #define MAX_SHADOWS 2
 
 in vec4 a_Position;
 in vec4 a_Normal;
+in vec2 a_TexCoord;
+in vec2 a_TexCoord1;
+in vec4 a_Color;
+out vec2 v_TexCoord;
+flat out vec3 v_Emissive;
 out vec4 v_ResultColor;
 flat out vec4 v_ResultColorFlat;
 flat out float v_Smooth;
 out vec4 v_LightEval[MAX_SHADOWS];
 flat out vec4 v_LightEvalFlat[MAX_SHADOWS];
 out vec4 v_ShadowCoord[MAX_SHADOWS];
+out float v_ViewDepth;
 
 in vec4 i_World0;
 in vec4 i_World1;
 in vec4 i_World2;
+in vec4 i_Normal0;
+in vec4 i_Normal1;
+in vec4 i_Normal2;
 in vec4 i_MatParams;
 in vec4 i_Color;
 in vec4 i_UvRange;
 
 void main() {
+    vec4 albedo = i_Color;
+#ifdef VERTEX_COLORS
+    albedo *= a_Color;
+#endif
     mat4 m_World = transpose(mat4(i_World0, i_World1, i_World2, vec4(0.0, 0.0, 0.0, 1.0)));
     vec4 world = m_World * a_Position;
-    vec3 normal = normalize(mat3(m_World) * a_Normal.xyz);
+    mat3 m_Normal = transpose(mat3(i_Normal0.xyz, i_Normal1.xyz, i_Normal2.xyz));
+    vec3 normal = normalize(m_Normal * a_Normal.xyz);
     for(int i=0; i<MAX_SHADOWS; ++i) {
         v_ShadowCoord[i] = vec4(0.0);
         v_LightEval[i] = v_LightEvalFlat[i] = vec4(0.0);
     }
-    v_ResultColor = vec4(0.0);
+    v_ResultColor = vec4(probe_irradiance(normal), 0.0) * albedo;
     v_Smooth = i_MatParams.x;
+    v_Emissive = i_MatParams.yzw;
+#ifdef UV1
+    v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord1);
+#else
+    v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord);
+#endif
 
     for(uint i=0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
         Light light = u_Lights[i];
@@ -41,8 +63,8 @@ void main() {
             irradiance = mix(light.color_back, light.color, dot_nl*0.5 + 0.5);
             dot_nl = 0.0;
         }
-        v_ResultColor += light.intensity.x * i_Color * irradiance; //ambient
-        vec4 color = light.intensity.y * max(0.0, dot_nl) * i_Color * light.color;
+        v_ResultColor += light.intensity.x * albedo * irradiance; //ambient
+        vec4 color = light.intensity.y * max(0.0, dot_nl) * albedo * light.color;
         // compute shadow coordinates
         int shadow_index = light.shadow_params[0];
         if (0 <= shadow_index && shadow_index < MAX_SHADOWS) {
@@ -54,6 +76,8 @@ void main() {
         }
     }
 
+    v_ResultColor.a = albedo.a;
     v_ResultColorFlat = v_ResultColor;
-    gl_Position = u_ViewProj * world;
+    v_ViewDepth = -(u_View * world).z;
+    gl_Position = encode_depth(u_ViewProj * world);
 }
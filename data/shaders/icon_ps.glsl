@@ -0,0 +1,30 @@
+#version 150 core
+
+in vec2 v_TexCoord;
+out vec4 Target0;
+
+layout(std140) uniform b_IconParams {
+    vec4 u_Rect;
+    vec4 u_UvRange;
+    vec4 u_Color;
+    float u_Depth;
+    float u_DistanceRange;
+};
+
+uniform sampler2D t_Map;
+
+float median(float r, float g, float b) {
+    return max(min(r, g), min(max(r, g), b));
+}
+
+void main() {
+    vec4 texel = texture(t_Map, v_TexCoord);
+    if (u_DistanceRange > 0.0) {
+        // multi-channel signed distance field, in units of `u_DistanceRange` pixels
+        float distance = median(texel.r, texel.g, texel.b) - 0.5;
+        float coverage = clamp(distance * u_DistanceRange + 0.5, 0.0, 1.0);
+        Target0 = vec4(u_Color.rgb, u_Color.a * coverage);
+    } else {
+        Target0 = vec4(texel.rgb, texel.a * u_Color.a);
+    }
+}
@@ -0,0 +1,21 @@
+#version 150 core
+
+out vec2 v_TexCoord;
+
+layout(std140) uniform b_IconParams {
+    vec4 u_Rect;
+    vec4 u_UvRange;
+    vec4 u_Color;
+    float u_Depth;
+    float u_DistanceRange;
+};
+
+void main() {
+    vec2 corner = gl_VertexID==0 ? vec2(1.0, 0.0) :
+                  gl_VertexID==1 ? vec2(0.0, 0.0) :
+                  gl_VertexID==2 ? vec2(1.0, 1.0) :
+                                   vec2(0.0, 1.0) ;
+    v_TexCoord = mix(u_UvRange.xy, u_UvRange.zw, corner);
+    vec2 pos = mix(u_Rect.xy, u_Rect.zw, corner);
+    gl_Position = vec4(pos, u_Depth, 1.0);
+}
@@ -0,0 +1,41 @@
+#ifndef LIGHTING_GLSL
+#define LIGHTING_GLSL
+
+#include <lights>
+#include <globals>
+#include <shadow>
+#include <probe>
+
+// Lights a surface point the way the Phong material does, with shadows.
+// `world` and `normal` are in world space, `glossiness` of zero disables
+// specular highlights.
+vec4 compute_lighting(vec3 world, vec3 normal, vec4 color, float glossiness) {
+    normal = normalize(normal);
+    vec4 result = vec4(probe_irradiance(normal), 0.0) * color;
+    for(uint i=0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
+        Light light = u_Lights[i];
+        float shadow = sample_shadow(light, light.projection * vec4(world, 1.0));
+        if (shadow == 0.0) {
+            continue;
+        }
+        vec3 dir = light.pos.xyz - light.pos.w * world;
+        float dot_nl = dot(normal, normalize(dir));
+        // hemisphere light test
+        if (dot(light.color_back, light.color_back) > 0.0) {
+            vec4 irradiance = mix(light.color_back, light.color, dot_nl*0.5 + 0.5);
+            result += shadow * light.intensity.x * color * irradiance;
+        } else {
+            float kd = light.intensity.x + light.intensity.y * max(0.0, dot_nl);
+            result += shadow * kd * color * light.color;
+        }
+        if (dot_nl > 0.0 && glossiness > 0.0) {
+            float ks = dot(normal, normalize(normal + normalize(dir)));
+            if (ks > 0.0) {
+                result += shadow * pow(ks, glossiness) * light.color;
+            }
+        }
+    }
+    return vec4(result.rgb, color.a);
+}
+
+#endif
@@ -1,16 +1,24 @@
+#ifndef LIGHTS_GLSL
+#define LIGHTS_GLSL
+
 #define MAX_LIGHTS  4U
 
 struct Light {
     mat4 projection;
     vec4 pos;
     vec4 dir;
-    vec4 focus;
+    vec4 shadow_range;
     vec4 color;
     vec4 color_back;
     vec4 intensity;
     ivec4 shadow_params;
+    // columns are the scale and offset from the first cascade to each one
+    mat4 cascade_scale;
+    mat4 cascade_offset;
 };
 
 layout(std140) uniform b_Lights {
     Light u_Lights[MAX_LIGHTS];
 };
+
+#endif
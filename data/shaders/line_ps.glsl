@@ -0,0 +1,14 @@
+#version 150 core
+
+in vec4 v_Color;
+in float v_Distance;
+flat in vec2 v_Dash;
+out vec4 Target0;
+
+void main() {
+    // solid lines have no dash length
+    if (v_Dash.x > 0.0 && mod(v_Distance, v_Dash.x + v_Dash.y) > v_Dash.x) {
+        discard;
+    }
+    Target0 = v_Color;
+}
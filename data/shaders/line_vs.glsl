@@ -0,0 +1,56 @@
+#version 150 core
+#include <globals>
+#include <depth>
+
+in vec4 a_Position;
+in vec2 a_TexCoord;
+in vec4 a_JointWeights;
+out vec4 v_Color;
+out float v_Distance;
+flat out vec2 v_Dash;
+
+in vec4 i_World0;
+in vec4 i_World1;
+in vec4 i_World2;
+in vec4 i_Color;
+in vec4 i_MatParams;
+
+// Clip space W below which the end of a segment is behind the camera.
+const float MIN_W = 1e-4;
+
+// Each vertex holds its end of a line segment in `a_Position`, the other
+// end in `a_JointWeights.xyz`, and the side of the quad it is on in
+// `a_JointWeights.w`. `i_MatParams.xy` is the half width of the line in
+// clip space units, and `i_MatParams.zw` the lengths of dashes and gaps.
+void main() {
+    mat4 m_World = transpose(mat4(i_World0, i_World1, i_World2, vec4(0.0, 0.0, 0.0, 1.0)));
+    vec4 clip = u_ViewProj * m_World * a_Position;
+    vec4 clip_other = u_ViewProj * m_World * vec4(a_JointWeights.xyz, 1.0);
+
+    if (clip.w < MIN_W && clip_other.w < MIN_W) {
+        // the whole segment is behind the camera
+        gl_Position = vec4(0.0, 0.0, 2.0, 1.0);
+        return;
+    }
+    // cut the segment at the camera, so that both ends project correctly
+    vec4 this_end = clip;
+    vec4 other_end = clip_other;
+    if (clip.w < MIN_W) {
+        this_end = mix(clip, clip_other, (MIN_W - clip.w) / (clip_other.w - clip.w));
+    }
+    if (clip_other.w < MIN_W) {
+        other_end = mix(clip_other, clip, (MIN_W - clip_other.w) / (clip.w - clip_other.w));
+    }
+
+    // the direction is measured in units of the half width, which keeps
+    // the line equally wide in any direction on non-square targets
+    vec2 delta = (other_end.xy / other_end.w - this_end.xy / this_end.w) / i_MatParams.xy;
+    vec2 dir = length(delta) > 0.0 ? normalize(delta) : vec2(1.0, 0.0);
+    vec2 normal = vec2(-dir.y, dir.x);
+    this_end.xy += normal * i_MatParams.xy * a_JointWeights.w * this_end.w;
+
+    v_Color = i_Color;
+    v_Distance = a_TexCoord.x;
+    v_Dash = i_MatParams.zw;
+    gl_Position = encode_depth(this_end);
+}
@@ -1,6 +1,7 @@
 #version 150 core
 #include <lights>
 #include <globals>
+#include <probe>
 
 const int BASE_COLOR_MAP          = 1 << 0;
 const int NORMAL_MAP              = 1 << 1;
@@ -8,17 +9,20 @@ const int METALLIC_ROUGHNESS_MAP  = 1 << 2;
 const int EMISSIVE_MAP            = 1 << 3;
 const int OCCLUSION_MAP           = 1 << 4;
 const int DISPLACEMENT_BUFFER     = 1 << 5;
+const int EMISSIVE_ONLY           = 1 << 6;
 
 uniform sampler2D u_BaseColorSampler;
 uniform sampler2D u_NormalSampler;
 uniform sampler2D u_EmissiveSampler;
 uniform sampler2D u_MetallicRoughnessSampler;
 uniform sampler2D u_OcclusionSampler;
+uniform samplerCube t_Reflection;
 
 layout(std140) uniform b_PbrParams {
     vec4 u_BaseColorFactor;
     vec3 u_Camera;
     vec3 u_EmissiveFactor;
+    float u_BloomIntensity;
     vec2 u_MetallicRoughnessValues;
     float u_NormalScale;
     float u_OcclusionStrength;
@@ -28,6 +32,7 @@ layout(std140) uniform b_PbrParams {
 in vec3 v_Position;
 in vec2 v_TexCoord;
 in mat3 v_Tbn;
+in vec4 v_Color;
 
 out vec4 Target0;
 
@@ -80,6 +85,25 @@ float ggx(PbrInfo pbr) {
     return roughness_sq / (PI * f * f);
 }
 
+// The scene reflections seen in `direction`, blurred for the roughness.
+// Zero when the scene has no reflection probe.
+vec3 probe_reflection(vec3 direction, float perceptual_roughness) {
+    float lod = perceptual_roughness * u_ReflectionParams.y;
+    return textureLod(t_Reflection, direction, lod).rgb * u_ReflectionParams.x;
+}
+
+// Fraction of the reflections reaching the eye, integrating the specular
+// BRDF over the hemisphere. Analytic approximation from “Physically Based
+// Shading on Mobile” by Brian Karis
+vec3 environment_brdf(vec3 specular_color, float reflectance90, float perceptual_roughness, float ndotv) {
+    const vec4 c0 = vec4(-1.0, -0.0275, -0.572, 0.022);
+    const vec4 c1 = vec4(1.0, 0.0425, 1.04, -0.04);
+    vec4 r = perceptual_roughness * c0 + c1;
+    float a004 = min(r.x * r.x, exp2(-9.28 * ndotv)) * r.x + r.y;
+    vec2 ab = vec2(-1.04, 1.04) * a004 + r.zw;
+    return specular_color * ab.x + reflectance90 * ab.y;
+}
+
 bool available(int flag) {
     return (u_PbrFlags & flag) == flag;
 }
@@ -113,6 +137,8 @@ void main() {
     } else {
         base_color = u_BaseColorFactor;
     }
+    // the color of the instance, white unless set on an instanced mesh
+    base_color *= v_Color;
 
     vec3 f0 = vec3(0.04);
     vec3 diffuse_color = mix(base_color.rgb * (1.0 - f0), vec3(0.0, 0.0, 0.0), metallic);
@@ -131,7 +157,7 @@ void main() {
     // material roughness by squaring the perceptual roughness
     float alpha_roughness = perceptual_roughness * perceptual_roughness;
 
-    vec3 color = vec3(0.0);
+    vec3 color = probe_irradiance(n) * diffuse_color;
     for (uint i = 0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
         Light light = u_Lights[i];
         vec3 l = normalize(light.dir.xyz);
@@ -164,14 +190,24 @@ void main() {
         color += ndotl * light.intensity.y * light.color.rgb * (diffuse_contrib + spec_contrib);
     }
 
+    vec3 reflected = reflect(-v, n);
+    float ndotv = clamp(dot(n, v), 0.0, 1.0);
+    color += probe_reflection(reflected, perceptual_roughness) *
+        environment_brdf(specular_color, reflectance90, perceptual_roughness, ndotv);
+
     if (available(OCCLUSION_MAP)) {
         float ao = texture(u_OcclusionSampler, v_TexCoord).r;
         color = mix(color, color * ao, u_OcclusionStrength);
     }
 
+    vec3 emissive = u_EmissiveFactor;
     if (available(EMISSIVE_MAP)) {
-        vec3 emissive = texture(u_EmissiveSampler, v_TexCoord).rgb * u_EmissiveFactor;
-        color += emissive;
+        emissive *= texture(u_EmissiveSampler, v_TexCoord).rgb;
+    }
+    color += emissive;
+
+    if (available(EMISSIVE_ONLY)) {
+        color = emissive * u_BloomIntensity;
     }
 
     Target0 = vec4(color, base_color.a);
@@ -1,53 +1,62 @@
 #version 150 core
 #include <lights>
 #include <globals>
+#include <shadow>
+#include <probe>
+#include <features>
 
 in vec3 v_World;
 in vec3 v_Normal;
-in vec3 v_Half[MAX_LIGHTS];
+in vec4 v_Tangent;
+in vec2 v_TexCoord;
 in vec4 v_ShadowCoord[MAX_LIGHTS];
 
 in vec4 v_MatParams;
 in vec4 v_Color;
+in float v_ViewDepth;
 
-out vec4 Target0;
+uniform sampler2D t_Map;
+uniform sampler2D t_NormalMap;
+uniform sampler2D t_SpecularMap;
 
-uniform sampler2DShadow t_Shadow0;
-uniform sampler2DShadow t_Shadow1;
+out vec4 Target0;
 
 void main() {
-    vec4 color = vec4(0.0);
     vec3 normal = normalize(v_Normal);
+    // degenerate tangents leave the normal as is
+    if (dot(v_Tangent.xyz, v_Tangent.xyz) > 1e-6) {
+        vec3 tangent = normalize(v_Tangent.xyz - normal * dot(normal, v_Tangent.xyz));
+        vec3 bitangent = cross(normal, tangent) * v_Tangent.w;
+        vec3 n = 2.0 * texture(t_NormalMap, v_TexCoord).xyz - 1.0;
+        normal = normalize(mat3(tangent, bitangent, normal) * n);
+    }
+    vec4 albedo = v_Color * texture(t_Map, v_TexCoord);
+    vec3 specular = texture(t_SpecularMap, v_TexCoord).rgb;
+    vec4 color = vec4(probe_irradiance(normal), 0.0) * albedo;
     float glossiness = v_MatParams.x;
     for(uint i=0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
         Light light = u_Lights[i];
-        vec4 lit_space = v_ShadowCoord[i];
-        float shadow = 1.0;
-        if (light.shadow_params[0] == 0) {
-            shadow = texture(t_Shadow0, 0.5 * lit_space.xyz / lit_space.w + 0.5);
-        }
-        if (light.shadow_params[0] == 1) {
-            shadow = texture(t_Shadow1, 0.5 * lit_space.xyz / lit_space.w + 0.5);
-        }
+        float shadow = sample_shadow(light, v_ShadowCoord[i]);
         if (shadow == 0.0) {
             continue;
         }
-        vec3 dir = light.pos.xyz - light.pos.w * v_World.xyz;
-        float dot_nl = dot(normal, normalize(dir));
+        vec3 dir = normalize(light.pos.xyz - light.pos.w * v_World.xyz);
+        float dot_nl = dot(normal, dir);
         // hemisphere light test
         if (dot(light.color_back, light.color_back) > 0.0) {
             vec4 irradiance = mix(light.color_back, light.color, dot_nl*0.5 + 0.5);
-            color += shadow * light.intensity.x * v_Color * irradiance;
+            color += shadow * light.intensity.x * albedo * irradiance;
         } else {
             float kd = light.intensity.x + light.intensity.y * max(0.0, dot_nl);
-            color += shadow * kd * v_Color * light.color;
+            color += shadow * kd * albedo * light.color;
         }
         if (dot_nl > 0.0 && glossiness > 0.0) {
-            float ks = dot(normal, normalize(v_Half[i]));
+            float ks = dot(normal, normalize(normal + dir));
             if (ks > 0.0) {
-                color += shadow * pow(ks, glossiness) * light.color;
+                color.rgb += shadow * pow(ks, glossiness) * specular * light.color.rgb;
             }
         }
     }
-    Target0 = color;
+    color.rgb += v_MatParams.yzw;
+    Target0 = apply_features(vec4(color.rgb, albedo.a), v_ViewDepth);
 }
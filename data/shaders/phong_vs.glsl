@@ -1,34 +1,54 @@
 #version 150 core
 #include <lights>
 #include <globals>
+#include <depth>
 
 in vec4 a_Position;
 in vec4 a_Normal;
+in vec4 a_Tangent;
+in vec2 a_TexCoord;
+in vec2 a_TexCoord1;
+in vec4 a_Color;
 out vec3 v_World;
 out vec3 v_Normal;
-out vec3 v_Half[MAX_LIGHTS];
+out vec4 v_Tangent;
+out vec2 v_TexCoord;
 out vec4 v_ShadowCoord[MAX_LIGHTS];
 out vec4 v_MatParams;
 out vec4 v_Color;
+out float v_ViewDepth;
 
 in vec4 i_World0;
 in vec4 i_World1;
 in vec4 i_World2;
+in vec4 i_Normal0;
+in vec4 i_Normal1;
+in vec4 i_Normal2;
 in vec4 i_MatParams;
 in vec4 i_Color;
+in vec4 i_UvRange;
 
 void main() {
     mat4 m_World = transpose(mat4(i_World0, i_World1, i_World2, vec4(0.0, 0.0, 0.0, 1.0)));
     vec4 world = m_World * a_Position;
     v_World = world.xyz;
-    v_Normal = normalize(mat3(m_World) * a_Normal.xyz);
+    mat3 m_Normal = transpose(mat3(i_Normal0.xyz, i_Normal1.xyz, i_Normal2.xyz));
+    v_Normal = normalize(m_Normal * a_Normal.xyz);
+    // mirrored transforms flip the handedness of the tangent frame
+    v_Tangent = vec4(mat3(m_World) * a_Tangent.xyz, a_Tangent.w * i_Normal0.w);
+#ifdef UV1
+    v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord1);
+#else
+    v_TexCoord = mix(i_UvRange.xy, i_UvRange.zw, a_TexCoord);
+#endif
     for(uint i=0U; i < min(MAX_LIGHTS, u_NumLights); ++i) {
-        Light light = u_Lights[i];
-        vec3 dir = light.pos.xyz - light.pos.w * world.xyz;
-        v_Half[i] = normalize(v_Normal + normalize(dir));
-        v_ShadowCoord[i] = light.projection * world;
+        v_ShadowCoord[i] = u_Lights[i].projection * world;
     }
     v_Color = i_Color;
+#ifdef VERTEX_COLORS
+    v_Color *= a_Color;
+#endif
     v_MatParams = i_MatParams;
-    gl_Position = u_ViewProj * world;
+    v_ViewDepth = -(u_View * world).z;
+    gl_Position = encode_depth(u_ViewProj * world);
 }
@@ -0,0 +1,8 @@
+#version 150 core
+
+flat in vec4 v_Id;
+out vec4 Target0;
+
+void main() {
+    Target0 = v_Id;
+}
@@ -0,0 +1,29 @@
+#version 150 core
+#include <globals>
+#include <skinning>
+#include <displacement>
+
+in vec4 a_Position;
+in ivec4 a_JointIndices;
+in vec4 a_JointWeights;
+
+in vec4 i_World0;
+in vec4 i_World1;
+in vec4 i_World2;
+in vec4 i_Color;
+in vec4 i_MatParams;
+
+flat out vec4 v_Id;
+
+void main() {
+    vec3 local_position = a_Position.xyz;
+
+    // `i_MatParams.x` is set when the mesh has morph targets
+    if (i_MatParams.x != 0.0) {
+        local_position = displace_position(local_position);
+    }
+
+    mat4 mx_world = transpose(mat4(i_World0, i_World1, i_World2, vec4(0.0, 0.0, 0.0, 1.0)));
+    v_Id = i_Color;
+    gl_Position = u_ViewProj * mx_world * skin_transform(a_JointIndices, a_JointWeights) * vec4(local_position, a_Position.w);
+}
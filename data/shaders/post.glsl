@@ -0,0 +1,13 @@
+layout(std140) uniform b_PostParams {
+    // user parameters of the pass, `u_Params[0]` to `u_Params[3]`
+    mat4 u_Params;
+    // xy: size of the output in pixels, zw: size of a pixel in texture coordinates
+    vec4 u_Resolution;
+};
+
+// the output of the previous pass
+uniform sampler2D t_Input;
+// an additional texture set on the pass, white by default
+uniform sampler2D t_Aux;
+
+in vec2 v_TexCoord;
@@ -0,0 +1,11 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// u_Params[0]: x = strength of the bloom in `t_Aux`
+void main() {
+    vec4 color = texture(t_Input, v_TexCoord);
+    vec3 bloom = texture(t_Aux, v_TexCoord).rgb;
+    Target0 = vec4(color.rgb + bloom * u_Params[0].x, color.a);
+}
@@ -0,0 +1,16 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// u_Params[0]: xy = direction and length of a blur step, in pixels
+void main() {
+    // 9-tap gaussian, using linear filtering to read two texels at once
+    vec2 offset = u_Params[0].xy * u_Resolution.zw;
+    vec3 sum = texture(t_Input, v_TexCoord).rgb * 0.227027;
+    sum += texture(t_Input, v_TexCoord + offset * 1.384615).rgb * 0.316216;
+    sum += texture(t_Input, v_TexCoord - offset * 1.384615).rgb * 0.316216;
+    sum += texture(t_Input, v_TexCoord + offset * 3.230769).rgb * 0.070270;
+    sum += texture(t_Input, v_TexCoord - offset * 3.230769).rgb * 0.070270;
+    Target0 = vec4(sum, 1.0);
+}
@@ -0,0 +1,13 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// u_Params[0]: x = threshold, y = softness of the threshold
+void main() {
+    vec3 color = texture(t_Input, v_TexCoord).rgb;
+    float brightness = max(color.r, max(color.g, color.b));
+    float threshold = u_Params[0].x;
+    float weight = smoothstep(threshold - u_Params[0].y, threshold + u_Params[0].y, brightness);
+    Target0 = vec4(color * weight, 1.0);
+}
@@ -0,0 +1,8 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+void main() {
+    Target0 = texture(t_Input, v_TexCoord);
+}
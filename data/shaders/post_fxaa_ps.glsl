@@ -0,0 +1,41 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+const vec3 LUMA = vec3(0.299, 0.587, 0.114);
+const float REDUCE_MIN = 1.0 / 128.0;
+const float REDUCE_MUL = 1.0 / 8.0;
+const float SPAN_MAX = 8.0;
+
+// Fast approximate anti-aliasing, after Timothy Lottes.
+void main() {
+    vec2 pixel = u_Resolution.zw;
+    vec4 center = texture(t_Input, v_TexCoord);
+    float luma_nw = dot(texture(t_Input, v_TexCoord + vec2(-1.0, -1.0) * pixel).rgb, LUMA);
+    float luma_ne = dot(texture(t_Input, v_TexCoord + vec2(1.0, -1.0) * pixel).rgb, LUMA);
+    float luma_sw = dot(texture(t_Input, v_TexCoord + vec2(-1.0, 1.0) * pixel).rgb, LUMA);
+    float luma_se = dot(texture(t_Input, v_TexCoord + vec2(1.0, 1.0) * pixel).rgb, LUMA);
+    float luma_m = dot(center.rgb, LUMA);
+    float luma_min = min(luma_m, min(min(luma_nw, luma_ne), min(luma_sw, luma_se)));
+    float luma_max = max(luma_m, max(max(luma_nw, luma_ne), max(luma_sw, luma_se)));
+
+    // blur along the edge, perpendicular to the luma gradient
+    vec2 dir = vec2(
+        -((luma_nw + luma_ne) - (luma_sw + luma_se)),
+        (luma_nw + luma_sw) - (luma_ne + luma_se)
+    );
+    float dir_reduce = max((luma_nw + luma_ne + luma_sw + luma_se) * 0.25 * REDUCE_MUL, REDUCE_MIN);
+    float rcp_dir_min = 1.0 / (min(abs(dir.x), abs(dir.y)) + dir_reduce);
+    dir = clamp(dir * rcp_dir_min, vec2(-SPAN_MAX), vec2(SPAN_MAX)) * pixel;
+
+    vec3 rgb_a = 0.5 * (
+        texture(t_Input, v_TexCoord + dir * (1.0 / 3.0 - 0.5)).rgb +
+        texture(t_Input, v_TexCoord + dir * (2.0 / 3.0 - 0.5)).rgb);
+    vec3 rgb_b = rgb_a * 0.5 + 0.25 * (
+        texture(t_Input, v_TexCoord + dir * -0.5).rgb +
+        texture(t_Input, v_TexCoord + dir * 0.5).rgb);
+    float luma_b = dot(rgb_b, LUMA);
+    vec3 rgb = (luma_b < luma_min || luma_b > luma_max) ? rgb_a : rgb_b;
+    Target0 = vec4(rgb, center.a);
+}
@@ -0,0 +1,22 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// filmic curve fitted to ACES by Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+}
+
+// u_Params[0]: x = exposure, y = operator (0 = clamp, 1 = Reinhard, 2 = ACES)
+void main() {
+    vec4 color = texture(t_Input, v_TexCoord);
+    vec3 c = max(color.rgb, vec3(0.0)) * u_Params[0].x;
+    int op = int(u_Params[0].y + 0.5);
+    if (op == 1) {
+        c = c / (1.0 + c);
+    } else if (op == 2) {
+        c = aces(c);
+    }
+    Target0 = vec4(clamp(c, 0.0, 1.0), clamp(color.a, 0.0, 1.0));
+}
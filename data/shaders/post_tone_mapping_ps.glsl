@@ -0,0 +1,17 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// filmic curve fitted to ACES by Krzysztof Narkowicz
+vec3 aces(vec3 x) {
+    return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+}
+
+// u_Params[0]: x = exposure, y = gamma
+void main() {
+    vec4 color = texture(t_Input, v_TexCoord);
+    float gamma = u_Params[0].y;
+    vec3 linear = pow(color.rgb, vec3(gamma)) * u_Params[0].x;
+    Target0 = vec4(pow(aces(linear), vec3(1.0 / gamma)), color.a);
+}
@@ -0,0 +1,16 @@
+#version 150 core
+#include <post>
+
+out vec4 Target0;
+
+// u_Params[0]: x = darkness, y = radius, z = softness
+// u_Params[1]: rgb = color
+void main() {
+    vec4 color = texture(t_Input, v_TexCoord);
+    vec2 offset = v_TexCoord - 0.5;
+    offset.x *= u_Resolution.x * u_Resolution.w;
+    float radius = u_Params[0].y;
+    float edge = smoothstep(radius, radius - max(u_Params[0].z, 1e-3), length(offset));
+    float amount = u_Params[0].x * (1.0 - edge);
+    Target0 = vec4(mix(color.rgb, u_Params[1].rgb, amount), color.a);
+}
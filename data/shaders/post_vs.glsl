@@ -0,0 +1,11 @@
+#version 150 core
+
+out vec2 v_TexCoord;
+
+void main() {
+    v_TexCoord = gl_VertexID==0 ? vec2(1.0, 0.0) :
+                 gl_VertexID==1 ? vec2(0.0, 0.0) :
+                 gl_VertexID==2 ? vec2(1.0, 1.0) :
+                                  vec2(0.0, 1.0) ;
+    gl_Position = vec4(2.0 * v_TexCoord - 1.0, 0.0, 1.0);
+}
@@ -0,0 +1,23 @@
+#ifndef PROBE_GLSL
+#define PROBE_GLSL
+
+#include <globals>
+
+// Ambient light from the scene light probe reaching a surface facing
+// `normal`, in world space. Zero when the scene has no probe.
+vec3 probe_irradiance(vec3 normal) {
+    vec3 n = normalize(normal);
+    vec3 result =
+        u_ProbeA[0].rgb * 0.282095 +
+        u_ProbeA[1].rgb * 0.488603 * n.y +
+        u_ProbeA[2].rgb * 0.488603 * n.z +
+        u_ProbeA[3].rgb * 0.488603 * n.x +
+        u_ProbeB[0].rgb * 1.092548 * n.x * n.y +
+        u_ProbeB[1].rgb * 1.092548 * n.y * n.z +
+        u_ProbeB[2].rgb * 0.315392 * (3.0 * n.z * n.z - 1.0) +
+        u_ProbeB[3].rgb * 1.092548 * n.x * n.z +
+        u_ProbeC.rgb * 0.546274 * (n.x * n.x - n.y * n.y);
+    return max(result, vec3(0.0));
+}
+
+#endif
@@ -0,0 +1,77 @@
+#ifndef SHADOW_GLSL
+#define SHADOW_GLSL
+
+#include <lights>
+
+uniform sampler2DShadow t_Shadow0;
+uniform sampler2DShadow t_Shadow1;
+uniform samplerCubeShadow t_ShadowCube0;
+uniform samplerCubeShadow t_ShadowCube1;
+uniform sampler2DShadow t_Cascade0;
+uniform sampler2DShadow t_Cascade1;
+uniform sampler2DShadow t_Cascade2;
+uniform sampler2DShadow t_Cascade3;
+
+// Depth stored in the cube shadow map of a point light for a point at
+// `offset` from the light, as seen by the face along the major axis.
+float cube_shadow_depth(Light light, vec3 offset) {
+    float near = light.shadow_range.x;
+    float far = light.shadow_range.y;
+    vec3 a = abs(offset);
+    float z = max(a.x, max(a.y, a.z));
+    float ndc = (far + near) / (far - near) - 2.0 * far * near / ((far - near) * z);
+    return 0.5 * ndc + 0.5;
+}
+
+// Visibility of a point from the cascades of `light`, given the point in
+// the projection space of the first cascade. The finest cascade covering
+// the point is used, and points past the last one are lit.
+float sample_cascades(Light light, vec3 lit_space) {
+    for (int i = 0; i < light.shadow_params[2]; ++i) {
+        vec3 coord = 0.5 * (lit_space * light.cascade_scale[i].xyz + light.cascade_offset[i].xyz) + 0.5;
+        if (any(lessThan(coord.xy, vec2(0.0))) || any(greaterThan(coord.xy, vec2(1.0)))) {
+            continue;
+        }
+        // samplers can't be picked by a computed index
+        if (i == 0) {
+            return texture(t_Cascade0, coord);
+        }
+        if (i == 1) {
+            return texture(t_Cascade1, coord);
+        }
+        if (i == 2) {
+            return texture(t_Cascade2, coord);
+        }
+        return texture(t_Cascade3, coord);
+    }
+    return 1.0;
+}
+
+// Visibility of a point from `light`, given the point in the light
+// projection space, in the `[0, 1]` range. For point lights, the
+// projection space is the offset from the light.
+float sample_shadow(Light light, vec4 lit_space) {
+    if (light.shadow_params[1] == 1) {
+        vec4 coord = vec4(lit_space.xyz, cube_shadow_depth(light, lit_space.xyz));
+        if (light.shadow_params[0] == 0) {
+            return texture(t_ShadowCube0, coord);
+        }
+        if (light.shadow_params[0] == 1) {
+            return texture(t_ShadowCube1, coord);
+        }
+        return 1.0;
+    }
+    if (light.shadow_params[2] > 0) {
+        return sample_cascades(light, lit_space.xyz / lit_space.w);
+    }
+    vec3 coord = 0.5 * lit_space.xyz / lit_space.w + 0.5;
+    if (light.shadow_params[0] == 0) {
+        return texture(t_Shadow0, coord);
+    }
+    if (light.shadow_params[0] == 1) {
+        return texture(t_Shadow1, coord);
+    }
+    return 1.0;
+}
+
+#endif
@@ -0,0 +1,26 @@
+#ifndef SKINNING_GLSL
+#define SKINNING_GLSL
+
+uniform samplerBuffer b_JointTransforms;
+
+//TODO: store each join transform in 3 vectors, similar to `i_WorldX`
+
+mat4 fetch_joint_transform(int i) {
+    //Note: has to match `render::VECS_PER_BONE`
+    vec4 row0 = texelFetch(b_JointTransforms, 3 * i + 0);
+    vec4 row1 = texelFetch(b_JointTransforms, 3 * i + 1);
+    vec4 row2 = texelFetch(b_JointTransforms, 3 * i + 2);
+
+    return transpose(mat4(row0, row1, row2, vec4(0.0, 0.0, 0.0, 1.0)));
+}
+
+// Blends the transforms of the joints influencing a vertex.
+mat4 skin_transform(ivec4 indices, vec4 weights) {
+    return
+        weights.x * fetch_joint_transform(indices.x) +
+        weights.y * fetch_joint_transform(indices.y) +
+        weights.z * fetch_joint_transform(indices.z) +
+        weights.w * fetch_joint_transform(indices.w);
+}
+
+#endif
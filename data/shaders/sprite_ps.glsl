@@ -1,10 +1,12 @@
 #version 150 core
 
 in vec2 v_TexCoord;
+in float v_Opacity;
 out vec4 Target0;
 
 uniform sampler2D t_Map;
 
 void main() {
     Target0 = texture(t_Map, v_TexCoord);
+    Target0.a *= v_Opacity;
 }
@@ -0,0 +1,8 @@
+#ifndef USER_GLSL
+#define USER_GLSL
+
+layout(std140) uniform b_User {
+    mat4 u_UserParams;
+};
+
+#endif
@@ -114,7 +114,7 @@ fn main() {
     };
 
     let mesh = win.factory
-        .mesh_dynamic(geom, three::material::Wireframe { color: 0xFFFFFF });
+        .mesh_dynamic(geom, three::material::Wireframe { color: 0xFFFFFF, ..Default::default() });
     win.scene.add(&mesh);
 
     let (mut id0, mut id1) = (0, 1);
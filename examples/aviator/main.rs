@@ -47,6 +47,7 @@ fn main() {
         let material = three::material::Lambert {
             color: COLOR_BLUE,
             flat: true,
+            ..Default::default()
         };
         win.factory.mesh(geo, material)
     };
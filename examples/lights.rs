@@ -9,8 +9,10 @@ fn main() {
 
     let hemisphere_light = win.factory.hemisphere_light(0xffffff, 0x8080ff, 0.5);
     let ambient_light = win.factory.ambient_light(0xffffffff, 0.5);
-    let point_light = win.factory.point_light(0xffffff, 0.9);
+    let mut point_light = win.factory.point_light(0xffffff, 0.9);
     point_light.set_position([15.0, 35.0, 35.0]);
+    let shadow_cube_map = win.factory.shadow_cube_map(512);
+    point_light.set_shadow(shadow_cube_map, 1.0 .. 200.0);
 
     let mut dir_light = win.factory.directional_light(0xffffff, 0.9);
     dir_light.look_at([15.0, 35.0, 35.0], [0.0, 0.0, 2.0], None);
@@ -35,6 +37,7 @@ fn main() {
         let material = three::material::Phong {
             color: 0xffA0A0,
             glossiness: 80.0,
+            ..Default::default()
         };
         win.factory.mesh(geometry, material)
     };
@@ -46,6 +49,7 @@ fn main() {
         let material = three::material::Lambert {
             color: 0xA0ffA0,
             flat: false,
+            ..Default::default()
         };
         win.factory.mesh(geometry, material)
     };
@@ -17,18 +17,22 @@ fn main() {
         three::material::Basic {
             color: 0xFFFFFF,
             map: None,
+            ..Default::default()
         }.into(),
         three::material::Lambert {
             color: 0xFFFFFF,
             flat: true,
+            ..Default::default()
         }.into(),
         three::material::Lambert {
             color: 0xFFFFFF,
             flat: false,
+            ..Default::default()
         }.into(),
         three::material::Phong {
             color: 0xFFFFFF,
             glossiness: 80.0,
+            ..Default::default()
         }.into(),
         three::material::Pbr {
             base_color_factor: 0xFFFFFF,
@@ -37,6 +41,8 @@ fn main() {
             roughness_factor: 0.5,
             occlusion_strength: 0.2,
             emissive_factor: 0x000000,
+            emissive_intensity: 1.0,
+            bloom_intensity: 0.0,
             normal_scale: 1.0,
             base_color_map: None,
             normal_map: None,
@@ -49,7 +49,7 @@ fn main() {
         .build();
 
     let geometry = make_tetrahedron_geometry();
-    let material = three::material::Wireframe { color: 0xFFFF00 };
+    let material = three::material::Wireframe { color: 0xFFFF00, ..Default::default() };
     let mut mesh = win.factory.mesh_dynamic(geometry, material);
     let vertex_count = mesh.vertex_count();
     win.scene.add(&mesh);
@@ -12,7 +12,7 @@ fn main() {
 
     let mbox = {
         let geometry = three::Geometry::cuboid(3.0, 2.0, 1.0);
-        let material = three::material::Wireframe { color: 0x00FF00 };
+        let material = three::material::Wireframe { color: 0x00FF00, ..Default::default() };
         win.factory.mesh(geometry, material)
     };
     mbox.set_position([-3.0, -3.0, 0.0]);
@@ -20,7 +20,7 @@ fn main() {
 
     let mcyl = {
         let geometry = three::Geometry::cylinder(1.0, 2.0, 2.0, 5);
-        let material = three::material::Wireframe { color: 0xFF0000 };
+        let material = three::material::Wireframe { color: 0xFF0000, ..Default::default() };
         win.factory.mesh(geometry, material)
     };
     mcyl.set_position([3.0, -3.0, 0.0]);
@@ -28,7 +28,7 @@ fn main() {
 
     let msphere = {
         let geometry = three::Geometry::uv_sphere(2.0, 5, 5);
-        let material = three::material::Wireframe { color: 0xFF0000 };
+        let material = three::material::Wireframe { color: 0xFF0000, ..Default::default() };
         win.factory.mesh(geometry, material)
     };
     msphere.set_position([-3.0, 3.0, 0.0]);
@@ -41,12 +41,17 @@ fn main() {
     win.scene.add(&mbox);
 
     let mline = {
-        let geometry = three::Geometry::with_vertices(vec![
+        let geometry = three::Geometry::with_line_strip(vec![
             [-2.0, -1.0, 0.0].into(),
             [0.0, 1.0, 0.0].into(),
             [2.0, -1.0, 0.0].into(),
         ]);
-        let material = three::material::Line { color: 0x0000FF };
+        let material = three::material::Line {
+            color: 0x0000FF,
+            width: 4.0,
+            dash: Some(three::material::DashPattern { dash: 0.3, gap: 0.15 }),
+            ..Default::default()
+        };
         win.factory.mesh(geometry, material)
     };
     mline.set_position([3.0, 3.0, 0.0]);
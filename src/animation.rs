@@ -105,10 +105,14 @@
 //! [`Mixer::update`]: struct.Mixer.html#method.update
 
 use cgmath;
+use color;
 use froggy;
+use hub::{MaterialParam, Operation as HubOperation, ProjectionParam};
 use mint;
 use object::{Base, Object};
+use scene::SyncGuard;
 
+use std::collections::HashSet;
 use std::hash::{Hash, Hasher};
 use std::sync::mpsc;
 
@@ -159,18 +163,22 @@ pub enum LoopMode {
 pub enum Binding {
     /// Targets the position property of an [`Object`].
     ///
-    /// The corresponding keyframe values must be [`Vector3`].
+    /// The corresponding keyframe values must be [`Vector3`] or
+    /// [`QuantizedVector3`].
     ///
     /// [`Object`]: ../object/trait.Object.html
     /// [`Vector3`]: enum.Values.html#variant.Vector3
+    /// [`QuantizedVector3`]: enum.Values.html#variant.QuantizedVector3
     Position,
 
     /// Targets the orientation property of an [`Object`].
     ///
-    /// The corresponding keyframe values must be [`Quaternion`] or [`Euler`].
+    /// The corresponding keyframe values must be [`Quaternion`],
+    /// [`QuantizedQuaternion`] or [`Euler`].
     ///
     /// [`Object`]: ../object/trait.Object.html
     /// [`Quaternion`]: enum.Values.html#variant.Quaternion
+    /// [`QuantizedQuaternion`]: enum.Values.html#variant.QuantizedQuaternion
     /// [`Euler`]: enum.Values.html#variant.Euler
     Orientation,
 
@@ -187,13 +195,77 @@ pub enum Binding {
     /// [`Scalar`]: enum.Values.html#variant.Scalar
     Scale,
 
-    /// Targets the weights property of an [`Object`].
+    /// Targets the morph target weights of a [`Mesh`], or of the meshes in
+    /// a [`Group`], see [`Mesh::set_morph_weights`].
+    ///
+    /// The corresponding keyframe values must be [`Scalar`], with all the
+    /// values of the first morph target, then all the values of the second
+    /// one, and so on.
+    ///
+    /// [`Mesh`]: ../struct.Mesh.html
+    /// [`Group`]: ../struct.Group.html
+    /// [`Mesh::set_morph_weights`]: ../struct.Mesh.html#method.set_morph_weights
+    /// [`Scalar`]: enum.Values.html#variant.Scalar
+    Weights,
+
+    /// Targets the main color of a [`Mesh`] material, such as [`Basic::color`]
+    /// or [`Pbr::base_color_factor`].
+    ///
+    /// The corresponding keyframe values must be [`Vector3`] or
+    /// [`QuantizedVector3`] in linear RGB.
+    ///
+    /// [`Mesh`]: ../struct.Mesh.html
+    /// [`Basic::color`]: ../material/struct.Basic.html#structfield.color
+    /// [`Pbr::base_color_factor`]: ../material/struct.Pbr.html#structfield.base_color_factor
+    /// [`Vector3`]: enum.Values.html#variant.Vector3
+    /// [`QuantizedVector3`]: enum.Values.html#variant.QuantizedVector3
+    Color,
+
+    /// Targets the opacity property of an [`Object`], see [`Object::set_opacity`].
     ///
     /// The corresponding keyframe values must be [`Scalar`].
     ///
     /// [`Object`]: ../object/trait.Object.html
+    /// [`Object::set_opacity`]: ../object/trait.Object.html#method.set_opacity
     /// [`Scalar`]: enum.Values.html#variant.Scalar
-    Weights,
+    Opacity,
+
+    /// Targets the emissive intensity of a [`Mesh`] with a [`Pbr`] material.
+    ///
+    /// The corresponding keyframe values must be [`Scalar`].
+    ///
+    /// [`Mesh`]: ../struct.Mesh.html
+    /// [`Pbr`]: ../material/struct.Pbr.html#structfield.emissive_intensity
+    /// [`Scalar`]: enum.Values.html#variant.Scalar
+    EmissiveIntensity,
+
+    /// Targets the vertical field of view of a perspective [`Camera`], in degrees.
+    ///
+    /// The corresponding keyframe values must be [`Scalar`].
+    ///
+    /// [`Camera`]: ../camera/struct.Camera.html
+    /// [`Scalar`]: enum.Values.html#variant.Scalar
+    FovY,
+
+    /// Targets the vertical extent of an orthographic [`Camera`].
+    ///
+    /// The corresponding keyframe values must be [`Scalar`].
+    ///
+    /// [`Camera`]: ../camera/struct.Camera.html
+    /// [`Scalar`]: enum.Values.html#variant.Scalar
+    OrthoExtent,
+
+    /// Targets the parameter of a [`Mesh`] with a [`Custom`] basic material
+    /// named after one of its [`param_names`], such as the progress of a
+    /// dissolve effect.
+    ///
+    /// The corresponding keyframe values must be [`Scalar`].
+    ///
+    /// [`Mesh`]: ../struct.Mesh.html
+    /// [`Custom`]: ../material/basic/struct.Custom.html
+    /// [`param_names`]: ../material/basic/struct.Custom.html#structfield.param_names
+    /// [`Scalar`]: enum.Values.html#variant.Scalar
+    Param(&'static str),
 }
 
 /// An index into the frames of a track.
@@ -224,6 +296,25 @@ pub enum Values {
 
     /// 3D vector keyframes.
     Vector3(Vec<mint::Vector3<f32>>),
+
+    /// Quaternion keyframes stored as signed normalized 16-bit integers,
+    /// see [`Compression::quantize`].
+    ///
+    /// [`Compression::quantize`]: struct.Compression.html#structfield.quantize
+    QuantizedQuaternion(Vec<[i16; 4]>),
+
+    /// 3D vector keyframes stored as 16-bit steps of `scale` from `offset`,
+    /// see [`Compression::quantize`].
+    ///
+    /// [`Compression::quantize`]: struct.Compression.html#structfield.quantize
+    QuantizedVector3 {
+        /// The smallest value of each component.
+        offset: mint::Vector3<f32>,
+        /// The size of a step of each component.
+        scale: mint::Vector3<f32>,
+        /// The number of steps of each component.
+        values: Vec<[u16; 3]>,
+    },
 }
 
 /// Message data sent from `Action` to `Mixer` over a channel.
@@ -233,6 +324,7 @@ enum Operation {
     Pause,
     Play,
     SetLoopMode(LoopMode),
+    SetTime(f32),
 }
 
 /// Message type sent from `Action` to `Mixer`.
@@ -292,6 +384,10 @@ struct ActionData {
 
     /// Time scaling factor.
     pub local_time_scale: f32,
+
+    /// Set when `local_time` was changed directly, so that the new pose is
+    /// applied even if the action is paused.
+    pub seeked: bool,
     // Unimplemented properties
     // ------------------------
     // * weight
@@ -309,6 +405,563 @@ pub struct Clip {
     pub tracks: Vec<(Track, Target)>,
 }
 
+impl Clip {
+    /// The time of the last keyframe of all tracks, in seconds.
+    pub fn duration(&self) -> f32 {
+        self.tracks
+            .iter()
+            .filter_map(|&(ref track, _)| track.times.last().cloned())
+            .fold(0.0, f32::max)
+    }
+
+    /// Cuts out the part of the clip between `start` and `end` seconds,
+    /// shifted to start at zero. Tracks get keyframes interpolated at both
+    /// ends, so the cut poses match the original ones.
+    ///
+    /// Imported timelines often hold several animations one after another,
+    /// which can be separated this way:
+    ///
+    /// ```rust,no_run,ignore
+    /// # let mut window = three::Window::new("");
+    /// # let templates = window.factory.load_gltf("Character.gltf");
+    /// let (instance, clips) = window.factory.instantiate_template(&templates[0]);
+    /// let timeline = &clips[0];
+    /// let idle = timeline.subclip(0.0, 2.0);
+    /// let walk = timeline.subclip(2.0, 3.0);
+    /// let run = walk.with_speed(1.5);
+    /// ```
+    pub fn subclip(
+        &self,
+        start: f32,
+        end: f32,
+    ) -> Clip {
+        let tracks = self.tracks
+            .iter()
+            .filter(|&&(ref track, _)| !track.times.is_empty())
+            .map(|&(ref track, ref target)| {
+                let mut times = vec![start];
+                times.extend(track.times.iter().cloned().filter(|&t| start < t && t < end));
+                if end > start {
+                    times.push(end);
+                }
+                let values = track.values.resample(&track.times, &times, track.interpolation);
+                let times = times.into_iter().map(|t| t - start).collect();
+                let track = Track {
+                    times,
+                    values,
+                    ..track.clone()
+                };
+                (track, target.clone())
+            })
+            .collect();
+        Clip {
+            name: self.name.clone(),
+            tracks,
+        }
+    }
+
+    /// Splits the clip into named parts, given as `(name, start, end)` with
+    /// times in seconds. See [`subclip`](#method.subclip).
+    pub fn split(
+        &self,
+        parts: &[(&str, f32, f32)],
+    ) -> ClipSet {
+        parts
+            .iter()
+            .map(|&(name, start, end)| Clip {
+                name: Some(name.to_string()),
+                ..self.subclip(start, end)
+            })
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    /// Returns the clip played backwards, over the same duration.
+    ///
+    /// The values of discrete tracks move to the keyframe before them, since
+    /// each one is held over the interval which follows its keyframe.
+    pub fn reverse(&self) -> Clip {
+        let duration = self.duration();
+        let tracks = self.tracks
+            .iter()
+            .map(|&(ref track, ref target)| {
+                let discrete = track.interpolation == Interpolation::Discrete;
+                let (times, values) = match track.times.last() {
+                    // hold the last value until the end, so it gets reversed too
+                    Some(&last) if discrete && last < duration => {
+                        let mut times = track.times.clone();
+                        times.push(duration);
+                        let values = track.values.resample(&track.times, &times, track.interpolation);
+                        (times, values)
+                    }
+                    _ => (track.times.clone(), track.values.clone()),
+                };
+                let track = Track {
+                    times: times.iter().rev().map(|&t| duration - t).collect(),
+                    values: values.reversed(times.len(), discrete),
+                    ..track.clone()
+                };
+                (track, target.clone())
+            })
+            .collect();
+        Clip {
+            name: self.name.clone(),
+            tracks,
+        }
+    }
+
+    /// Returns the clip played `speed` times faster.
+    ///
+    /// Unlike the time scale of an [`Action`], this is baked into the
+    /// keyframes, so that all the actions of the clip share it.
+    ///
+    /// # Panics
+    /// Panics if `speed` isn't positive.
+    ///
+    /// [`Action`]: struct.Action.html
+    pub fn with_speed(
+        &self,
+        speed: f32,
+    ) -> Clip {
+        assert!(speed > 0.0, "Clip speed must be positive, got {}", speed);
+        let tracks = self.tracks
+            .iter()
+            .map(|&(ref track, ref target)| {
+                let track = Track {
+                    times: track.times.iter().map(|&t| t / speed).collect(),
+                    ..track.clone()
+                };
+                (track, target.clone())
+            })
+            .collect();
+        Clip {
+            name: self.name.clone(),
+            tracks,
+        }
+    }
+
+    /// Returns the clip with its keyframes reduced and quantized according
+    /// to `compression`. See [`Track::compress`].
+    ///
+    /// [`Track::compress`]: struct.Track.html#method.compress
+    pub fn compress(
+        &self,
+        compression: &Compression,
+    ) -> Clip {
+        let tracks = self.tracks
+            .iter()
+            .map(|&(ref track, ref target)| (track.compress(compression), target.clone()))
+            .collect();
+        Clip {
+            name: self.name.clone(),
+            tracks,
+        }
+    }
+}
+
+/// Samples each run of `times.len()` values at `new_times`.
+fn resample_runs<T: Copy, F: Fn(T, T, f32) -> T>(
+    values: &[T],
+    times: &[f32],
+    new_times: &[f32],
+    discrete: bool,
+    interpolate: F,
+) -> Vec<T> {
+    let mut result = Vec::with_capacity(values.len() / times.len() * new_times.len());
+    for run in values.chunks(times.len()) {
+        for &t in new_times {
+            let value = match times.iter().position(|&time| time > t) {
+                Some(0) => run[0],
+                Some(i) if discrete => run[i - 1],
+                Some(i) => {
+                    let s = (t - times[i - 1]) / (times[i] - times[i - 1]);
+                    interpolate(run[i - 1], run[i], s)
+                }
+                None => run[run.len() - 1],
+            };
+            result.push(value);
+        }
+    }
+    result
+}
+
+/// Reverses each run of `count` values. Discrete values are shifted to the
+/// keyframe before them, the first one being held until the end.
+fn reverse_runs<T: Copy>(
+    values: &[T],
+    count: usize,
+    discrete: bool,
+) -> Vec<T> {
+    values
+        .chunks(count.max(1))
+        .flat_map(|run| {
+            let reversed = run.iter().rev().cloned();
+            if discrete {
+                reversed.skip(1).chain(run.first().cloned()).collect::<Vec<_>>()
+            } else {
+                reversed.collect()
+            }
+        })
+        .collect()
+}
+
+/// Picks the `keep` indices out of each run of `count` values.
+fn select_runs<T: Copy>(
+    values: &[T],
+    count: usize,
+    keep: &[usize],
+) -> Vec<T> {
+    values
+        .chunks(count.max(1))
+        .flat_map(|run| keep.iter().map(move |&i| run[i]))
+        .collect()
+}
+
+/// Returns the largest difference over each run of `count` values between
+/// the value at `i` and the one interpolated at `s` between `a` and `b`.
+fn deviation_runs<T: Copy, F: Fn(T, T, T, f32) -> f32>(
+    values: &[T],
+    count: usize,
+    (a, b, i): (usize, usize, usize),
+    s: f32,
+    deviation: F,
+) -> f32 {
+    values
+        .chunks(count.max(1))
+        .map(|run| deviation(run[a], run[b], run[i], s))
+        .fold(0.0, f32::max)
+}
+
+/// The angle in radians between two rotations.
+fn angle_between(
+    a: cgmath::Quaternion<f32>,
+    b: cgmath::Quaternion<f32>,
+) -> f32 {
+    use cgmath::InnerSpace;
+    2.0 * a.dot(b).abs().min(1.0).acos()
+}
+
+fn euler_quaternion(euler: mint::EulerAngles<f32, mint::IntraXYZ>) -> cgmath::Quaternion<f32> {
+    cgmath::Quaternion::from(cgmath::Euler::new(
+        cgmath::Rad(euler.a),
+        cgmath::Rad(euler.b),
+        cgmath::Rad(euler.c),
+    ))
+}
+
+fn quantize_quaternion(q: mint::Quaternion<f32>) -> [i16; 4] {
+    let snorm = |x: f32| (x.max(-1.0).min(1.0) * 32767.0).round() as i16;
+    [snorm(q.v.x), snorm(q.v.y), snorm(q.v.z), snorm(q.s)]
+}
+
+fn dequantize_quaternion(q: [i16; 4]) -> cgmath::Quaternion<f32> {
+    use cgmath::InnerSpace;
+    let unorm = |x: i16| x as f32 / 32767.0;
+    cgmath::Quaternion::new(unorm(q[3]), unorm(q[0]), unorm(q[1]), unorm(q[2])).normalize()
+}
+
+fn dequantize_vector3(
+    offset: mint::Vector3<f32>,
+    scale: mint::Vector3<f32>,
+    q: [u16; 3],
+) -> cgmath::Vector3<f32> {
+    cgmath::Vector3::new(
+        offset.x + q[0] as f32 * scale.x,
+        offset.y + q[1] as f32 * scale.y,
+        offset.z + q[2] as f32 * scale.z,
+    )
+}
+
+/// Returns the indices of the keyframes to keep so that linearly
+/// interpolating between them stays within `tolerance` of the dropped ones.
+fn reduce_keyframes(
+    times: &[f32],
+    values: &Values,
+    tolerance: f32,
+) -> Vec<usize> {
+    let count = times.len();
+    if count < 3 {
+        return (0 .. count).collect();
+    }
+    let mut keep = vec![0];
+    let mut anchor = 0;
+    // extend the segment from the last kept keyframe as long as all the
+    // keyframes it skips can be interpolated
+    for end in 2 .. count {
+        let span = times[end] - times[anchor];
+        let fits = (anchor + 1 .. end).all(|i| {
+            let s = if span > 0.0 { (times[i] - times[anchor]) / span } else { 0.0 };
+            values.deviation(count, (anchor, end, i), s) <= tolerance
+        });
+        if !fits {
+            anchor = end - 1;
+            keep.push(anchor);
+        }
+    }
+    keep.push(count - 1);
+    keep
+}
+
+impl Values {
+    /// Interpolates the keyframes at `times` for `new_times`, holding the
+    /// previous keyframe value with `Interpolation::Discrete`.
+    fn resample(
+        &self,
+        times: &[f32],
+        new_times: &[f32],
+        interpolation: Interpolation,
+    ) -> Values {
+        let discrete = interpolation == Interpolation::Discrete;
+        let lerp = |a: f32, b: f32, s: f32| a * (1.0 - s) + b * s;
+        match *self {
+            Values::Euler(ref values) => Values::Euler(resample_runs(values, times, new_times, discrete, |a, b, s| {
+                mint::EulerAngles {
+                    a: lerp(a.a, b.a, s),
+                    b: lerp(a.b, b.b, s),
+                    c: lerp(a.c, b.c, s),
+                    marker: a.marker,
+                }
+            })),
+            Values::Quaternion(ref values) => Values::Quaternion(resample_runs(values, times, new_times, discrete, |a, b, s| {
+                let a: cgmath::Quaternion<f32> = a.into();
+                a.slerp(b.into(), s).into()
+            })),
+            Values::Scalar(ref values) => Values::Scalar(resample_runs(values, times, new_times, discrete, lerp)),
+            Values::Vector3(ref values) => Values::Vector3(resample_runs(values, times, new_times, discrete, |a, b, s| {
+                [lerp(a.x, b.x, s), lerp(a.y, b.y, s), lerp(a.z, b.z, s)].into()
+            })),
+            Values::QuantizedQuaternion(_) | Values::QuantizedVector3 { .. } => {
+                self.dequantized().resample(times, new_times, interpolation).quantized()
+            }
+        }
+    }
+
+    /// Reverses the order of keyframes, `count` being the number of times.
+    /// Discrete values are shifted, see `reverse_runs`.
+    fn reversed(
+        &self,
+        count: usize,
+        discrete: bool,
+    ) -> Values {
+        match *self {
+            Values::Euler(ref values) => Values::Euler(reverse_runs(values, count, discrete)),
+            Values::Quaternion(ref values) => Values::Quaternion(reverse_runs(values, count, discrete)),
+            Values::Scalar(ref values) => Values::Scalar(reverse_runs(values, count, discrete)),
+            Values::Vector3(ref values) => Values::Vector3(reverse_runs(values, count, discrete)),
+            Values::QuantizedQuaternion(ref values) => Values::QuantizedQuaternion(reverse_runs(values, count, discrete)),
+            Values::QuantizedVector3 { offset, scale, ref values } => Values::QuantizedVector3 {
+                offset,
+                scale,
+                values: reverse_runs(values, count, discrete),
+            },
+        }
+    }
+
+    /// Keeps the keyframes at the `keep` indices, `count` being the number
+    /// of times. Only used on values which aren't quantized.
+    fn select(
+        &self,
+        count: usize,
+        keep: &[usize],
+    ) -> Values {
+        match *self {
+            Values::Euler(ref values) => Values::Euler(select_runs(values, count, keep)),
+            Values::Quaternion(ref values) => Values::Quaternion(select_runs(values, count, keep)),
+            Values::Scalar(ref values) => Values::Scalar(select_runs(values, count, keep)),
+            Values::Vector3(ref values) => Values::Vector3(select_runs(values, count, keep)),
+            Values::QuantizedQuaternion(_) | Values::QuantizedVector3 { .. } => {
+                self.dequantized().select(count, keep)
+            }
+        }
+    }
+
+    /// How far the keyframe at `i` is from the value interpolated at `s`
+    /// between the keyframes at `a` and `b`, `count` being the number of
+    /// times. Orientations are compared by the angle between them.
+    fn deviation(
+        &self,
+        count: usize,
+        indices: (usize, usize, usize),
+        s: f32,
+    ) -> f32 {
+        use cgmath::InnerSpace;
+        match *self {
+            Values::Euler(ref values) => deviation_runs(values, count, indices, s, |a, b, x, s| {
+                let update = euler_quaternion(a).slerp(euler_quaternion(b), s);
+                angle_between(update, euler_quaternion(x))
+            }),
+            Values::Quaternion(ref values) => deviation_runs(values, count, indices, s, |a, b, x, s| {
+                let a: cgmath::Quaternion<f32> = a.into();
+                angle_between(a.slerp(b.into(), s), x.into())
+            }),
+            Values::Scalar(ref values) => deviation_runs(values, count, indices, s, |a, b, x, s| {
+                (a * (1.0 - s) + b * s - x).abs()
+            }),
+            Values::Vector3(ref values) => deviation_runs(values, count, indices, s, |a, b, x, s| {
+                let a: cgmath::Vector3<f32> = a.into();
+                (a.lerp(b.into(), s) - cgmath::Vector3::from(x)).magnitude()
+            }),
+            Values::QuantizedQuaternion(_) | Values::QuantizedVector3 { .. } => {
+                self.dequantized().deviation(count, indices, s)
+            }
+        }
+    }
+
+    /// Stores quaternions and 3D vectors as 16-bit integers. Other values
+    /// are left as they are.
+    fn quantized(&self) -> Values {
+        match *self {
+            Values::Quaternion(ref values) => {
+                Values::QuantizedQuaternion(values.iter().map(|&q| quantize_quaternion(q)).collect())
+            }
+            Values::Vector3(ref values) if !values.is_empty() => {
+                let (mut min, mut max) = (values[0], values[0]);
+                for v in values {
+                    min = [min.x.min(v.x), min.y.min(v.y), min.z.min(v.z)].into();
+                    max = [max.x.max(v.x), max.y.max(v.y), max.z.max(v.z)].into();
+                }
+                let step = |lo: f32, hi: f32| (hi - lo) / 65535.0;
+                let scale: mint::Vector3<f32> = [step(min.x, max.x), step(min.y, max.y), step(min.z, max.z)].into();
+                let steps = |x: f32, lo: f32, step: f32| {
+                    if step > 0.0 { ((x - lo) / step).round() as u16 } else { 0 }
+                };
+                let values = values
+                    .iter()
+                    .map(|v| [steps(v.x, min.x, scale.x), steps(v.y, min.y, scale.y), steps(v.z, min.z, scale.z)])
+                    .collect();
+                Values::QuantizedVector3 {
+                    offset: min,
+                    scale,
+                    values,
+                }
+            }
+            _ => self.clone(),
+        }
+    }
+
+    /// Converts quantized values back to floating point ones.
+    fn dequantized(&self) -> Values {
+        match *self {
+            Values::QuantizedQuaternion(ref values) => {
+                Values::Quaternion(values.iter().map(|&q| dequantize_quaternion(q).into()).collect())
+            }
+            Values::QuantizedVector3 { offset, scale, ref values } => {
+                Values::Vector3(values.iter().map(|&q| dequantize_vector3(offset, scale, q).into()).collect())
+            }
+            _ => self.clone(),
+        }
+    }
+}
+
+/// A collection of animation clips that can be looked up by name or by the
+/// objects they animate.
+///
+/// ```rust,no_run,ignore
+/// # let mut window = three::Window::new("");
+/// # let templates = window.factory.load_gltf("Characters.gltf");
+/// # let mut mixer = three::animation::Mixer::new();
+/// use three::animation::ClipSet;
+///
+/// let (instance, clips) = window.factory.instantiate_template(&templates[0]);
+/// window.scene.add(&instance);
+/// let clips = ClipSet::from(clips);
+///
+/// // play "Run" only on the first character
+/// let character = window.scene
+///     .sync_guard()
+///     .find_child_of_type_by_name::<three::Group>(&instance, "CharacterA")
+///     .unwrap();
+/// let run = clips
+///     .targeting(&window.scene.sync_guard(), &character)
+///     .get("Run")
+///     .cloned()
+///     .unwrap();
+/// mixer.action(run);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ClipSet {
+    clips: Vec<Clip>,
+}
+
+impl ClipSet {
+    /// Creates a clip set from a list of clips.
+    pub fn new(clips: Vec<Clip>) -> Self {
+        ClipSet { clips }
+    }
+
+    /// Finds the first clip named `name`.
+    pub fn get(
+        &self,
+        name: &str,
+    ) -> Option<&Clip> {
+        self.clips
+            .iter()
+            .find(|clip| clip.name.as_deref() == Some(name))
+    }
+
+    /// Returns the names of all the named clips.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.clips.iter().filter_map(|clip| clip.name.as_deref())
+    }
+
+    /// Returns an iterator over all the clips.
+    pub fn iter(&self) -> impl Iterator<Item = &Clip> {
+        self.clips.iter()
+    }
+
+    /// Restricts the clips to the tracks targeting `root` or any of its
+    /// descendants. Clips left without tracks are dropped.
+    pub fn targeting<T: Object>(
+        &self,
+        sync: &SyncGuard,
+        root: &T,
+    ) -> ClipSet {
+        let mut subtree = HashSet::new();
+        sync.hub.collect_subtree(&root.as_ref().node, &mut subtree);
+        let clips = self.clips
+            .iter()
+            .filter_map(|clip| {
+                let tracks: Vec<_> = clip.tracks
+                    .iter()
+                    .filter(|(_, target)| {
+                        subtree.contains(&(&sync.hub.nodes[&target.node] as *const _))
+                    })
+                    .cloned()
+                    .collect();
+                if tracks.is_empty() {
+                    None
+                } else {
+                    Some(Clip {
+                        name: clip.name.clone(),
+                        tracks,
+                    })
+                }
+            })
+            .collect();
+        ClipSet { clips }
+    }
+
+    /// Unwraps the list of clips.
+    pub fn into_vec(self) -> Vec<Clip> {
+        self.clips
+    }
+}
+
+impl From<Vec<Clip>> for ClipSet {
+    fn from(clips: Vec<Clip>) -> Self {
+        ClipSet::new(clips)
+    }
+}
+
+impl IntoIterator for ClipSet {
+    type Item = Clip;
+    type IntoIter = ::std::vec::IntoIter<Clip>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.clips.into_iter()
+    }
+}
+
 /// A track of animation keyframes.
 #[derive(Clone, Debug)]
 pub struct Track {
@@ -325,6 +978,37 @@ pub struct Track {
     pub interpolation: Interpolation,
 }
 
+/// Settings for cutting down the memory used by animation keyframes, see
+/// [`Track::compress`].
+///
+/// Imported clips can be compressed as they are loaded with
+/// [`Factory::set_animation_compression`].
+///
+/// [`Track::compress`]: struct.Track.html#method.compress
+/// [`Factory::set_animation_compression`]: ../struct.Factory.html#method.set_animation_compression
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Compression {
+    /// The largest error allowed when dropping keyframes which can be
+    /// interpolated from the ones around them, in the units of the values,
+    /// and in radians for orientations.
+    ///
+    /// Set to zero to keep all the keyframes.
+    pub tolerance: f32,
+
+    /// Specifies whether orientations and 3D vectors are stored as 16-bit
+    /// integers instead of floats, which halves their size.
+    pub quantize: bool,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            tolerance: 0.001,
+            quantize: true,
+        }
+    }
+}
+
 /// Scheduler for the playback of animation actions.
 ///
 /// Use this to update animation actions.
@@ -371,6 +1055,19 @@ impl Action {
     ) -> &mut Self {
         self.send(Operation::SetLoopMode(loop_mode))
     }
+
+    /// Jumps to the given local time in seconds.
+    ///
+    /// The pose at that time is applied on the next [`Mixer::update`], even if
+    /// the action is paused.
+    ///
+    /// [`Mixer::update`]: struct.Mixer.html#method.update
+    pub fn set_time(
+        &mut self,
+        time: f32,
+    ) -> &mut Self {
+        self.send(Operation::SetTime(time))
+    }
 }
 
 impl Mixer {
@@ -389,6 +1086,10 @@ impl Mixer {
                     action.enabled = true;
                 }
                 Operation::SetLoopMode(loop_mode) => action.loop_mode = loop_mode,
+                Operation::SetTime(time) => {
+                    action.local_time = time;
+                    action.seeked = true;
+                }
             }
         }
     }
@@ -433,7 +1134,14 @@ impl Mixer {
 }
 
 impl ActionData {
-    fn new(clip: Clip) -> Self {
+    fn new(mut clip: Clip) -> Self {
+        clip.tracks.retain(|&(ref track, _)| {
+            let supported = track.is_supported();
+            if !supported {
+                warn!("Ignoring a {:?} track with unsupported keyframe values", track.binding);
+            }
+            supported
+        });
         ActionData {
             clip: clip,
             enabled: true,
@@ -441,6 +1149,7 @@ impl ActionData {
             paused: false,
             local_time: 0.0,
             local_time_scale: 1.0,
+            seeked: false,
         }
     }
 
@@ -449,11 +1158,14 @@ impl ActionData {
         &mut self,
         delta_time: f32,
     ) {
-        if self.paused || !self.enabled {
+        if !self.enabled || (self.paused && !self.seeked) {
             return;
         }
 
-        self.local_time += delta_time * self.local_time_scale;
+        if !self.paused {
+            self.local_time += delta_time * self.local_time_scale;
+        }
+        self.seeked = false;
         let mut finish_count = 0;
         for &(ref track, ref target) in self.clip.tracks.iter() {
             let frame_index = match track.frame_at_time(self.local_time) {
@@ -498,6 +1210,12 @@ impl ActionData {
                     let update = frame_start_value.slerp(frame_end_value, s);
                     target.set_orientation(update);
                 }
+                (Binding::Orientation, &Values::QuantizedQuaternion(ref values)) => {
+                    let frame_start_value = dequantize_quaternion(values[frame_index]);
+                    let frame_end_value = dequantize_quaternion(values[frame_index + 1]);
+                    let update = frame_start_value.slerp(frame_end_value, s);
+                    target.set_orientation(update);
+                }
                 (Binding::Position, &Values::Vector3(ref values)) => {
                     use cgmath::{EuclideanSpace, InnerSpace};
                     let frame_start_value: cgmath::Vector3<f32> = values[frame_index].into();
@@ -505,6 +1223,13 @@ impl ActionData {
                     let update = frame_start_value.lerp(frame_end_value, s);
                     target.set_position(cgmath::Point3::from_vec(update));
                 }
+                (Binding::Position, &Values::QuantizedVector3 { offset, scale, ref values }) => {
+                    use cgmath::{EuclideanSpace, InnerSpace};
+                    let frame_start_value = dequantize_vector3(offset, scale, values[frame_index]);
+                    let frame_end_value = dequantize_vector3(offset, scale, values[frame_index + 1]);
+                    let update = frame_start_value.lerp(frame_end_value, s);
+                    target.set_position(cgmath::Point3::from_vec(update));
+                }
                 (Binding::Scale, &Values::Scalar(ref values)) => {
                     let frame_start_value = values[frame_index];
                     let frame_end_value = values[frame_index + 1];
@@ -523,7 +1248,54 @@ impl ActionData {
                         .collect();
                     target.set_weights(update);
                 }
-                _ => panic!("Unsupported (binding, value) pair"),
+                (Binding::Color, &Values::Vector3(ref values)) => {
+                    use cgmath::InnerSpace;
+                    let frame_start_value: cgmath::Vector3<f32> = values[frame_index].into();
+                    let frame_end_value: cgmath::Vector3<f32> = values[frame_index + 1].into();
+                    let update = frame_start_value.lerp(frame_end_value, s);
+                    let color = color::from_linear_rgb(update.into());
+                    target.send(HubOperation::SetMaterialParam(MaterialParam::Color(color)));
+                }
+                (Binding::Color, &Values::QuantizedVector3 { offset, scale, ref values }) => {
+                    use cgmath::InnerSpace;
+                    let frame_start_value = dequantize_vector3(offset, scale, values[frame_index]);
+                    let frame_end_value = dequantize_vector3(offset, scale, values[frame_index + 1]);
+                    let update = frame_start_value.lerp(frame_end_value, s);
+                    let color = color::from_linear_rgb(update.into());
+                    target.send(HubOperation::SetMaterialParam(MaterialParam::Color(color)));
+                }
+                (Binding::Opacity, &Values::Scalar(ref values)) => {
+                    let frame_start_value = values[frame_index];
+                    let frame_end_value = values[frame_index + 1];
+                    let update = frame_start_value * (1.0 - s) + frame_end_value * s;
+                    target.set_opacity(update);
+                }
+                (Binding::EmissiveIntensity, &Values::Scalar(ref values)) => {
+                    let frame_start_value = values[frame_index];
+                    let frame_end_value = values[frame_index + 1];
+                    let update = frame_start_value * (1.0 - s) + frame_end_value * s;
+                    target.send(HubOperation::SetMaterialParam(MaterialParam::EmissiveIntensity(update)));
+                }
+                (Binding::FovY, &Values::Scalar(ref values)) => {
+                    let frame_start_value = values[frame_index];
+                    let frame_end_value = values[frame_index + 1];
+                    let update = frame_start_value * (1.0 - s) + frame_end_value * s;
+                    target.send(HubOperation::SetProjectionParam(ProjectionParam::FovY(update)));
+                }
+                (Binding::OrthoExtent, &Values::Scalar(ref values)) => {
+                    let frame_start_value = values[frame_index];
+                    let frame_end_value = values[frame_index + 1];
+                    let update = frame_start_value * (1.0 - s) + frame_end_value * s;
+                    target.send(HubOperation::SetProjectionParam(ProjectionParam::ExtentY(update)));
+                }
+                (Binding::Param(name), &Values::Scalar(ref values)) => {
+                    let frame_start_value = values[frame_index];
+                    let frame_end_value = values[frame_index + 1];
+                    let update = frame_start_value * (1.0 - s) + frame_end_value * s;
+                    target.send(HubOperation::SetMaterialParam(MaterialParam::Param(name, update)));
+                }
+                // filtered out by `ActionData::new`
+                _ => unreachable!(),
             }
         }
 
@@ -546,6 +1318,67 @@ impl ActionData {
 }
 
 impl Track {
+    /// Returns the track with its keyframes reduced and quantized according
+    /// to `compression`.
+    ///
+    /// Only linearly interpolated tracks get keyframes dropped. The first
+    /// and last keyframes are always kept.
+    ///
+    /// ```rust
+    /// use three::animation::{Binding, Compression, Interpolation, Track, Values};
+    ///
+    /// let track = Track {
+    ///     binding: Binding::Scale,
+    ///     interpolation: Interpolation::Linear,
+    ///     times: vec![0.0, 0.5, 1.0, 2.0],
+    ///     values: Values::Scalar(vec![1.0, 1.5, 2.0, 2.0]),
+    /// };
+    /// let compressed = track.compress(&Compression::default());
+    /// assert_eq!(compressed.times, vec![0.0, 1.0, 2.0]);
+    /// ```
+    pub fn compress(
+        &self,
+        compression: &Compression,
+    ) -> Track {
+        let values = self.values.dequantized();
+        let (times, values) = if self.interpolation == Interpolation::Linear && compression.tolerance > 0.0 {
+            let keep = reduce_keyframes(&self.times, &values, compression.tolerance);
+            let times = keep.iter().map(|&i| self.times[i]).collect();
+            (times, values.select(self.times.len(), &keep))
+        } else {
+            (self.times.clone(), values)
+        };
+        Track {
+            times,
+            values: if compression.quantize { values.quantized() } else { values },
+            ..self.clone()
+        }
+    }
+
+    /// Whether the mixer can apply the values of the track to its binding.
+    fn is_supported(&self) -> bool {
+        match (self.binding, &self.values) {
+            (Binding::Orientation, &Values::Euler(_)) |
+            (Binding::Orientation, &Values::Quaternion(_)) |
+            (Binding::Orientation, &Values::QuantizedQuaternion(_)) |
+            (Binding::Position, &Values::Vector3(_)) |
+            (Binding::Position, &Values::QuantizedVector3 { .. }) |
+            (Binding::Color, &Values::Vector3(_)) |
+            (Binding::Color, &Values::QuantizedVector3 { .. }) |
+            (Binding::Scale, &Values::Scalar(_)) |
+            (Binding::Opacity, &Values::Scalar(_)) |
+            (Binding::EmissiveIntensity, &Values::Scalar(_)) |
+            (Binding::FovY, &Values::Scalar(_)) |
+            (Binding::OrthoExtent, &Values::Scalar(_)) |
+            (Binding::Param(_), &Values::Scalar(_)) => true,
+            // a run of values per morph target
+            (Binding::Weights, &Values::Scalar(ref values)) => {
+                !self.times.is_empty() && values.len() % self.times.len() == 0
+            }
+            _ => false,
+        }
+    }
+
     fn frame_at_time(
         &self,
         t: f32,
@@ -568,3 +1401,270 @@ impl Track {
         FrameRef::InProgress(i)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hub::{Hub, SubNode};
+
+    fn vectors(values: &Values) -> Vec<mint::Vector3<f32>> {
+        match values.dequantized() {
+            Values::Vector3(values) => values,
+            _ => panic!("expected 3D vector keyframes"),
+        }
+    }
+
+    fn quaternions(values: &Values) -> Vec<cgmath::Quaternion<f32>> {
+        match values.dequantized() {
+            Values::Quaternion(values) => values.into_iter().map(cgmath::Quaternion::from).collect(),
+            _ => panic!("expected quaternion keyframes"),
+        }
+    }
+
+    fn distance(
+        a: mint::Vector3<f32>,
+        b: mint::Vector3<f32>,
+    ) -> f32 {
+        ((a.x - b.x).powi(2) + (a.y - b.y).powi(2) + (a.z - b.z).powi(2)).sqrt()
+    }
+
+    fn wave_track() -> Track {
+        let times: Vec<f32> = (0 .. 100).map(|i| i as f32 * 0.05).collect();
+        let values = times
+            .iter()
+            .map(|&t| [t.sin(), 2.0 * t.cos(), 0.5 * t].into())
+            .collect();
+        Track {
+            binding: Binding::Position,
+            times,
+            values: Values::Vector3(values),
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    fn spin_track() -> Track {
+        use cgmath::Rotation3;
+        let times: Vec<f32> = (0 .. 100).map(|i| i as f32 * 0.05).collect();
+        let values = times
+            .iter()
+            .map(|&t| {
+                let axis = cgmath::Vector3::new(t.cos(), 1.0, t.sin()) / 2f32.sqrt();
+                cgmath::Quaternion::from_axis_angle(axis, cgmath::Rad(t)).into()
+            })
+            .collect();
+        Track {
+            binding: Binding::Orientation,
+            times,
+            values: Values::Quaternion(values),
+            interpolation: Interpolation::Linear,
+        }
+    }
+
+    #[test]
+    fn reduced_vectors_sample_within_tolerance() {
+        let track = wave_track();
+        let compression = Compression { tolerance: 0.01, quantize: false };
+        let reduced = track.compress(&compression);
+        assert!(reduced.times.len() < track.times.len());
+        assert_eq!(reduced.times.first(), track.times.first());
+        assert_eq!(reduced.times.last(), track.times.last());
+
+        let resampled = vectors(&reduced.values.resample(&reduced.times, &track.times, Interpolation::Linear));
+        for (a, b) in vectors(&track.values).into_iter().zip(resampled) {
+            assert!(distance(a, b) <= compression.tolerance + 1e-5);
+        }
+    }
+
+    #[test]
+    fn straight_line_reduces_to_ends() {
+        let track = Track {
+            binding: Binding::Position,
+            times: vec![0.0, 1.0, 2.0, 3.0, 4.0],
+            values: Values::Vector3((0 .. 5).map(|i| [i as f32, 0.0, -2.0 * i as f32].into()).collect()),
+            interpolation: Interpolation::Linear,
+        };
+        let reduced = track.compress(&Compression { tolerance: 1e-4, quantize: false });
+        assert_eq!(reduced.times, vec![0.0, 4.0]);
+        assert_eq!(vectors(&reduced.values).len(), 2);
+    }
+
+    #[test]
+    fn non_linear_tracks_keep_all_keyframes() {
+        let track = Track {
+            interpolation: Interpolation::Discrete,
+            ..wave_track()
+        };
+        let reduced = track.compress(&Compression { tolerance: 0.01, quantize: false });
+        assert_eq!(reduced.times, track.times);
+    }
+
+    #[test]
+    fn quantized_vectors_round_trip() {
+        let track = wave_track();
+        let quantized = track.values.quantized();
+        let scale = match quantized {
+            Values::QuantizedVector3 { scale, ref values, .. } => {
+                assert_eq!(values.len(), track.times.len());
+                scale
+            }
+            _ => panic!("expected quantized 3D vector keyframes"),
+        };
+        for (a, b) in vectors(&track.values).into_iter().zip(vectors(&quantized)) {
+            assert!((a.x - b.x).abs() <= scale.x * 0.5 + 1e-6);
+            assert!((a.y - b.y).abs() <= scale.y * 0.5 + 1e-6);
+            assert!((a.z - b.z).abs() <= scale.z * 0.5 + 1e-6);
+        }
+    }
+
+    #[test]
+    fn quantized_quaternions_round_trip() {
+        let track = spin_track();
+        let quantized = track.values.quantized();
+        match quantized {
+            Values::QuantizedQuaternion(ref values) => assert_eq!(values.len(), track.times.len()),
+            _ => panic!("expected quantized quaternion keyframes"),
+        }
+        for (a, b) in quaternions(&track.values).into_iter().zip(quaternions(&quantized)) {
+            assert!(angle_between(a, b) < 1e-3);
+        }
+    }
+
+    #[test]
+    fn compressed_quaternions_sample_within_tolerance() {
+        let track = spin_track();
+        let compression = Compression { tolerance: 0.01, quantize: true };
+        let compressed = track.compress(&compression);
+        assert!(compressed.times.len() < track.times.len());
+
+        let resampled = quaternions(&compressed.values.resample(&compressed.times, &track.times, Interpolation::Linear));
+        for (a, b) in quaternions(&track.values).into_iter().zip(resampled) {
+            assert!(angle_between(a, b) <= compression.tolerance + 1e-3);
+        }
+    }
+
+    fn scalars(values: &Values) -> Vec<f32> {
+        match *values {
+            Values::Scalar(ref values) => values.clone(),
+            _ => panic!("expected scalar keyframes"),
+        }
+    }
+
+    fn fade_clip() -> Clip {
+        let hub = Hub::new();
+        let target = hub.lock().unwrap().spawn(SubNode::Group {
+            first_child: None,
+            last_child: None,
+        });
+        let fade = Track {
+            binding: Binding::Opacity,
+            times: vec![0.0, 1.0, 2.0, 4.0],
+            values: Values::Scalar(vec![0.0, 1.0, 0.5, 1.5]),
+            interpolation: Interpolation::Linear,
+        };
+        // two morph targets, with a run of keyframe values each
+        let weights = Track {
+            binding: Binding::Weights,
+            times: vec![0.0, 3.0],
+            values: Values::Scalar(vec![0.0, 1.0, 1.0, 0.0]),
+            interpolation: Interpolation::Linear,
+        };
+        Clip {
+            name: Some("fade".to_string()),
+            tracks: vec![(fade, target.clone()), (weights, target)],
+        }
+    }
+
+    fn stepped_clip() -> Clip {
+        let mut clip = fade_clip();
+        let step = Track {
+            binding: Binding::Scale,
+            times: vec![0.0, 1.0, 2.0],
+            values: Values::Scalar(vec![1.0, 2.0, 3.0]),
+            interpolation: Interpolation::Discrete,
+        };
+        let target = clip.tracks[0].1.clone();
+        clip.tracks.push((step, target));
+        clip
+    }
+
+    #[test]
+    fn subclip_interpolates_ends() {
+        let clip = fade_clip().subclip(0.5, 3.0);
+        assert_eq!(clip.name.as_ref().map(String::as_str), Some("fade"));
+        assert_eq!(clip.duration(), 2.5);
+
+        let fade = &clip.tracks[0].0;
+        assert_eq!(fade.times, vec![0.0, 0.5, 1.5, 2.5]);
+        assert_eq!(scalars(&fade.values), vec![0.5, 1.0, 0.5, 1.0]);
+
+        let weights = &clip.tracks[1].0;
+        assert_eq!(weights.times, vec![0.0, 2.5]);
+        let values = scalars(&weights.values);
+        assert_eq!(values.len(), 4);
+        for (a, b) in values.into_iter().zip(vec![1.0 / 6.0, 1.0, 5.0 / 6.0, 0.0]) {
+            assert!((a - b).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn split_names_parts() {
+        let clips = fade_clip().split(&[("in", 0.0, 1.0), ("out", 2.0, 4.0)]);
+        assert_eq!(clips.names().collect::<Vec<_>>(), vec!["in", "out"]);
+
+        let fade_in = &clips.get("in").unwrap().tracks[0].0;
+        assert_eq!(fade_in.times, vec![0.0, 1.0]);
+        assert_eq!(scalars(&fade_in.values), vec![0.0, 1.0]);
+
+        let fade_out = &clips.get("out").unwrap().tracks[0].0;
+        assert_eq!(fade_out.times, vec![0.0, 2.0]);
+        assert_eq!(scalars(&fade_out.values), vec![0.5, 1.5]);
+    }
+
+    #[test]
+    fn reverse_mirrors_keyframes() {
+        let clip = fade_clip().reverse();
+        assert_eq!(clip.duration(), 4.0);
+
+        let fade = &clip.tracks[0].0;
+        assert_eq!(fade.times, vec![0.0, 2.0, 3.0, 4.0]);
+        assert_eq!(scalars(&fade.values), vec![1.5, 0.5, 1.0, 0.0]);
+
+        // each morph target run is reversed on its own
+        let weights = &clip.tracks[1].0;
+        assert_eq!(weights.times, vec![1.0, 4.0]);
+        assert_eq!(scalars(&weights.values), vec![1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn with_speed_scales_times() {
+        let clip = fade_clip().with_speed(2.0);
+        assert_eq!(clip.duration(), 2.0);
+
+        let fade = &clip.tracks[0].0;
+        assert_eq!(fade.times, vec![0.0, 0.5, 1.0, 2.0]);
+        assert_eq!(scalars(&fade.values), vec![0.0, 1.0, 0.5, 1.5]);
+    }
+
+    #[test]
+    fn subclip_holds_discrete_values() {
+        let clip = stepped_clip().subclip(0.5, 1.5);
+        let step = &clip.tracks[2].0;
+        assert_eq!(step.times, vec![0.0, 0.5, 1.0]);
+        assert_eq!(scalars(&step.values), vec![1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn reverse_shifts_discrete_values() {
+        let clip = stepped_clip().reverse();
+        let step = &clip.tracks[2].0;
+        // the last value is held from 2 to the end of the clip at 4
+        assert_eq!(step.times, vec![0.0, 2.0, 3.0, 4.0]);
+        assert_eq!(scalars(&step.values), vec![3.0, 2.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn with_speed_rejects_zero() {
+        fade_clip().with_speed(0.0);
+    }
+}
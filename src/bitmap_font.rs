@@ -0,0 +1,245 @@
+//! Bitmap fonts in the BMFont format, for pixel-art text.
+
+use mint;
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use geometry::Geometry;
+use texture::Texture;
+
+/// A single character of a bitmap font, in pixels.
+#[derive(Clone, Copy, Debug, Default)]
+struct Glyph {
+    x: u16,
+    y: u16,
+    width: u16,
+    height: u16,
+    offset: [i16; 2],
+    advance: i16,
+    page: usize,
+}
+
+/// The contents of a BMFont descriptor, before its pages are loaded.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Descriptor {
+    line_height: u16,
+    page_size: [u16; 2],
+    pub(crate) pages: Vec<PathBuf>,
+    glyphs: HashMap<char, Glyph>,
+    kernings: HashMap<(char, char), i16>,
+}
+
+/// Splits a descriptor line into its tag and `key=value` pairs, keeping
+/// quoted values containing spaces together.
+fn tokenize(line: &str) -> (&str, Vec<(&str, &str)>) {
+    let line = line.trim();
+    let tag_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let mut pairs = Vec::new();
+    let mut rest = line[tag_end ..].trim_start();
+    while let Some(eq) = rest.find('=') {
+        let key = rest[.. eq].trim();
+        let after = &rest[eq + 1 ..];
+        let (value, next) = if after.starts_with('"') {
+            let end = after[1 ..].find('"').map_or(after.len(), |pos| pos + 1);
+            (&after[1 .. end], after.get(end + 1 ..).unwrap_or(""))
+        } else {
+            let end = after.find(char::is_whitespace).unwrap_or(after.len());
+            (&after[.. end], &after[end ..])
+        };
+        pairs.push((key, value));
+        rest = next.trim_start();
+    }
+    (&line[.. tag_end], pairs)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+impl Descriptor {
+    /// Parses the text variant of the BMFont format. Page file names are
+    /// resolved relative to `dir`.
+    pub(crate) fn parse(
+        code: &str,
+        dir: &Path,
+    ) -> io::Result<Self> {
+        let mut descriptor = Descriptor::default();
+        for (number, line) in code.lines().enumerate() {
+            let (tag, pairs) = tokenize(line);
+            let get = |key: &str| -> io::Result<i32> {
+                let value = pairs
+                    .iter()
+                    .find(|&&(k, _)| k == key)
+                    .map(|&(_, v)| v)
+                    .ok_or_else(|| invalid(format!("line {}: `{}` misses `{}`", number + 1, tag, key)))?;
+                value
+                    .parse()
+                    .map_err(|_| invalid(format!("line {}: invalid `{}` value {:?}", number + 1, key, value)))
+            };
+            match tag {
+                "common" => {
+                    descriptor.line_height = get("lineHeight")? as u16;
+                    descriptor.page_size = [get("scaleW")? as u16, get("scaleH")? as u16];
+                }
+                "page" => {
+                    let id = get("id")? as usize;
+                    let file = pairs
+                        .iter()
+                        .find(|&&(k, _)| k == "file")
+                        .ok_or_else(|| invalid(format!("line {}: page misses `file`", number + 1)))?
+                        .1;
+                    if descriptor.pages.len() <= id {
+                        descriptor.pages.resize(id + 1, PathBuf::new());
+                    }
+                    descriptor.pages[id] = dir.join(file);
+                }
+                "char" => {
+                    let id = get("id")?;
+                    let character = match ::std::char::from_u32(id as u32) {
+                        Some(character) => character,
+                        None => continue,
+                    };
+                    descriptor.glyphs.insert(character, Glyph {
+                        x: get("x")? as u16,
+                        y: get("y")? as u16,
+                        width: get("width")? as u16,
+                        height: get("height")? as u16,
+                        offset: [get("xoffset")? as i16, get("yoffset")? as i16],
+                        advance: get("xadvance")? as i16,
+                        page: get("page")? as usize,
+                    });
+                }
+                "kerning" => {
+                    let first = ::std::char::from_u32(get("first")? as u32);
+                    let second = ::std::char::from_u32(get("second")? as u32);
+                    if let (Some(first), Some(second)) = (first, second) {
+                        descriptor.kernings.insert((first, second), get("amount")? as i16);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if descriptor.pages.is_empty() || descriptor.page_size[0] == 0 || descriptor.page_size[1] == 0 {
+            return Err(invalid("not a text BMFont descriptor".to_string()));
+        }
+        Ok(descriptor)
+    }
+}
+
+/// A font of pre-rendered characters, loaded from a BMFont descriptor.
+///
+/// Unlike [`Font`], which renders outlines smoothly at any size, bitmap
+/// fonts draw their characters exactly as designed, which suits pixel-art
+/// games. Text is built into meshes by [`Factory::bitmap_text`].
+///
+/// [`Font`]: ../struct.Font.html
+/// [`Factory::bitmap_text`]: ../struct.Factory.html#method.bitmap_text
+#[derive(Clone, Debug)]
+pub struct BitmapFont {
+    descriptor: Descriptor,
+    pub(crate) pages: Vec<Texture<[f32; 4]>>,
+}
+
+impl BitmapFont {
+    pub(crate) fn new(
+        descriptor: Descriptor,
+        pages: Vec<Texture<[f32; 4]>>,
+    ) -> Self {
+        BitmapFont { descriptor, pages }
+    }
+
+    /// The distance between two lines of text, in pixels.
+    pub fn line_height(&self) -> u16 {
+        self.descriptor.line_height
+    }
+
+    /// The size of `text` in pixels, as the widest line and the height of
+    /// all lines.
+    pub fn measure(
+        &self,
+        text: &str,
+    ) -> mint::Vector2<u32> {
+        let mut width = 0;
+        let mut lines = 0;
+        for line in text.lines() {
+            let mut x = 0i32;
+            let mut previous = None;
+            for character in line.chars() {
+                if let Some(glyph) = self.descriptor.glyphs.get(&character) {
+                    x += self.kerning(previous, character) + glyph.advance as i32;
+                }
+                previous = Some(character);
+            }
+            width = width.max(x.max(0) as u32);
+            lines += 1;
+        }
+        [width, lines * self.descriptor.line_height as u32].into()
+    }
+
+    fn kerning(
+        &self,
+        previous: Option<char>,
+        character: char,
+    ) -> i32 {
+        previous
+            .and_then(|previous| self.descriptor.kernings.get(&(previous, character)))
+            .map_or(0, |&amount| amount as i32)
+    }
+
+    /// Lays out `text` into one geometry per font page, one quad per visible
+    /// character. Units are pixels, with the origin at the top-left corner of
+    /// the text and Y pointing up.
+    pub(crate) fn layout(
+        &self,
+        text: &str,
+    ) -> Vec<Geometry> {
+        let mut geometries = vec![Geometry::default(); self.pages.len()];
+        let [page_width, page_height] = self.descriptor.page_size;
+        let (page_width, page_height) = (page_width as f32, page_height as f32);
+        for (row, line) in text.lines().enumerate() {
+            let top = -(row as i32 * self.descriptor.line_height as i32);
+            let mut x = 0i32;
+            let mut previous = None;
+            for character in line.chars() {
+                let glyph = match self.descriptor.glyphs.get(&character) {
+                    Some(glyph) => *glyph,
+                    None => continue,
+                };
+                x += self.kerning(previous, character);
+                previous = Some(character);
+                if glyph.width > 0 && glyph.height > 0 && glyph.page < geometries.len() {
+                    let left = (x + glyph.offset[0] as i32) as f32;
+                    let top = (top - glyph.offset[1] as i32) as f32;
+                    let (right, bottom) = (left + glyph.width as f32, top - glyph.height as f32);
+                    let u0 = glyph.x as f32 / page_width;
+                    let u1 = (glyph.x + glyph.width) as f32 / page_width;
+                    // pages are flipped vertically on load
+                    let v0 = 1.0 - glyph.y as f32 / page_height;
+                    let v1 = 1.0 - (glyph.y + glyph.height) as f32 / page_height;
+
+                    let geometry = &mut geometries[glyph.page];
+                    let base = geometry.base.vertices.len() as u32;
+                    geometry.base.vertices.extend_from_slice(&[
+                        [left, top, 0.0].into(),
+                        [left, bottom, 0.0].into(),
+                        [right, bottom, 0.0].into(),
+                        [right, top, 0.0].into(),
+                    ]);
+                    geometry.base.normals.extend_from_slice(&[[0.0, 0.0, 1.0].into(); 4]);
+                    geometry.tex_coords.extend_from_slice(&[
+                        [u0, v0].into(),
+                        [u0, v1].into(),
+                        [u1, v1].into(),
+                        [u1, v0].into(),
+                    ]);
+                    geometry.faces.push([base, base + 1, base + 2]);
+                    geometry.faces.push([base, base + 2, base + 3]);
+                }
+                x += glyph.advance as i32;
+            }
+        }
+        geometries
+    }
+}
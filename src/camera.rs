@@ -50,16 +50,37 @@
 //! # };
 //! ```
 //!
+//! ## Frustum
+//!
+//! The volume visible through a camera is described by a [`Frustum`], which
+//! can be used to cull objects before they are added to the scene or to
+//! visualize a camera in an editor.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! let frustum = {
+//!     let sync = window.scene.sync_guard();
+//!     camera.frustum(&sync, window.renderer.aspect_ratio())
+//! };
+//! if frustum.intersects_sphere([0.0, 0.0, -10.0], 1.0) {
+//!     println!("the sphere is visible");
+//! }
+//! ```
+//!
 //! [`Factory::orthographic_camera`]: ../factory/struct.Factory.html#method.orthographic_camera
 //! [`Factory::perspective_camera`]: ../factory/struct.Factory.html#method.perspective_camera
+//! [`Frustum`]: struct.Frustum.html
 //! [`object::Base`]: ../object/struct.Base.html
 //! [`Orthographic`]: struct.Orthographic.html
 //! [`Perspective`]: struct.Perspective.html
 
 use cgmath;
+use cgmath::{InnerSpace, Matrix, Matrix4, Transform, Vector3, Vector4};
 use mint;
 
-use hub::{Hub, Operation, SubNode};
+use hub::{Hub, Operation, ProjectionParam, SubNode};
+use node::TransformInternal;
 use object::{Base, DowncastObject, Object, ObjectType};
 use scene::SyncGuard;
 
@@ -130,6 +151,142 @@ impl Camera {
     pub fn set_projection<P: Into<Projection>>(&self, projection: P) {
         self.as_ref().send(Operation::SetProjection(projection.into()));
     }
+
+    /// Projects a point from world space into normalized device coordinates,
+    /// where the visible area spans from `(-1, -1)` to `(1, 1)` and `z` is the depth.
+    ///
+    /// Returns `None` if the point is behind the camera.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    pub fn world_to_ndc<P: Into<mint::Point3<f32>>>(
+        &self,
+        sync: &SyncGuard,
+        aspect_ratio: f32,
+        point: P,
+    ) -> Option<mint::Point3<f32>> {
+        let clip = self.world_to_clip(sync, aspect_ratio, point.into());
+        if clip.w <= 0.0 {
+            return None;
+        }
+        Some([clip.x / clip.w, clip.y / clip.w, clip.z / clip.w].into())
+    }
+
+    pub(crate) fn world_to_clip(
+        &self,
+        sync: &SyncGuard,
+        aspect_ratio: f32,
+        point: mint::Point3<f32>,
+    ) -> cgmath::Vector4<f32> {
+        let mx_vp = Matrix4::from(self.view_projection_matrix(sync, aspect_ratio));
+        mx_vp * Vector4::new(point.x, point.y, point.z, 1.0)
+    }
+
+    /// Computes the matrix transforming world space into the camera's view space.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    pub fn view_matrix(
+        &self,
+        sync: &SyncGuard,
+    ) -> mint::ColumnMatrix4<f32> {
+        let transform: TransformInternal = sync.resolve_world(self).transform.into();
+        Matrix4::from(transform.inverse_transform().unwrap()).into()
+    }
+
+    /// Computes the matrix transforming world space into clip space, as used
+    /// by the renderer.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    pub fn view_projection_matrix(
+        &self,
+        sync: &SyncGuard,
+        aspect_ratio: f32,
+    ) -> mint::ColumnMatrix4<f32> {
+        let mx_view = Matrix4::from(self.view_matrix(sync));
+        let mx_proj = Matrix4::from(sync.resolve_data(self).matrix(aspect_ratio));
+        (mx_proj * mx_view).into()
+    }
+
+    /// Computes the world space [`Frustum`] visible through the camera.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    ///
+    /// [`Frustum`]: struct.Frustum.html
+    pub fn frustum(
+        &self,
+        sync: &SyncGuard,
+        aspect_ratio: f32,
+    ) -> Frustum {
+        Frustum::from_matrix(self.view_projection_matrix(sync, aspect_ratio))
+    }
+
+    /// Sets the vertical field of view in degrees, keeping the rest of the projection.
+    ///
+    /// Has no effect on orthographic cameras.
+    pub fn set_fov_y(&self, fov_y: f32) {
+        self.as_ref().send(Operation::SetProjectionParam(ProjectionParam::FovY(fov_y)));
+    }
+
+    /// Sets the vertical extent of an orthographic projection, keeping the rest of
+    /// the projection.
+    ///
+    /// Has no effect on perspective cameras.
+    pub fn set_extent_y(&self, extent_y: f32) {
+        self.as_ref().send(Operation::SetProjectionParam(ProjectionParam::ExtentY(extent_y)));
+    }
+
+    /// Sets the zoom factor of the projection, keeping the rest of it.
+    /// Values above 1 magnify the view around its center.
+    pub fn set_zoom(&self, zoom: f32) {
+        self.as_ref().send(Operation::SetProjectionParam(ProjectionParam::Zoom(zoom)));
+    }
+
+    /// Restricts the projection to the `width` by `height` rectangle at
+    /// `(x, y)` of a larger `full_width` by `full_height` view, with `(0, 0)`
+    /// at its top left corner.
+    ///
+    /// This is used to render one large view in tiles, for example across
+    /// several monitors, each one rendering its own part with the same camera.
+    /// See [`ViewOffset`] for details.
+    ///
+    /// # Examples
+    ///
+    /// The left screen of two 1920x1080 monitors placed side by side.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// camera.set_view_offset(3840.0, 1080.0, 0.0, 0.0, 1920.0, 1080.0);
+    /// ```
+    ///
+    /// [`ViewOffset`]: struct.ViewOffset.html
+    pub fn set_view_offset(
+        &self,
+        full_width: f32,
+        full_height: f32,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    ) {
+        let offset = ViewOffset {
+            full_size: [full_width, full_height].into(),
+            offset: [x, y].into(),
+            size: [width, height].into(),
+        };
+        self.as_ref().send(Operation::SetProjectionParam(ProjectionParam::ViewOffset(Some(offset))));
+    }
+
+    /// Removes the view offset set by [`set_view_offset`], rendering the
+    /// whole view again.
+    ///
+    /// [`set_view_offset`]: #method.set_view_offset
+    pub fn clear_view_offset(&self) {
+        self.as_ref().send(Operation::SetProjectionParam(ProjectionParam::ViewOffset(None)));
+    }
 }
 
 impl DowncastObject for Camera {
@@ -156,6 +313,8 @@ impl Projection {
             center,
             extent_y,
             range,
+            zoom: 1.0,
+            view_offset: None,
         })
     }
 
@@ -170,6 +329,8 @@ impl Projection {
         Projection::Perspective(Perspective {
             fov_y,
             zrange: range.into(),
+            zoom: 1.0,
+            view_offset: None,
         })
     }
 
@@ -195,6 +356,10 @@ pub struct Orthographic {
     pub extent_y: f32,
     /// Distance to the clipping planes.
     pub range: ops::Range<f32>,
+    /// Magnification around the center, dividing the extents.
+    pub zoom: f32,
+    /// Part of a larger view rendered by the projection, if any.
+    pub view_offset: Option<ViewOffset>,
 }
 
 impl Orthographic {
@@ -203,15 +368,17 @@ impl Orthographic {
         &self,
         aspect_ratio: f32,
     ) -> mint::ColumnMatrix4<f32> {
+        let aspect_ratio = full_aspect_ratio(&self.view_offset, aspect_ratio);
         let extent_x = aspect_ratio * self.extent_y;
-        cgmath::ortho(
+        let matrix = cgmath::ortho(
             self.center.x - extent_x,
             self.center.x + extent_x,
             self.center.y - self.extent_y,
             self.center.y + self.extent_y,
             self.range.start,
             self.range.end,
-        ).into()
+        );
+        zoomed_matrix(matrix, self.zoom, &self.view_offset)
     }
 }
 
@@ -223,6 +390,10 @@ pub struct Perspective {
     pub fov_y: f32,
     /// The distance to the clipping planes.
     pub zrange: ZRange,
+    /// Magnification around the center, narrowing the field of view.
+    pub zoom: f32,
+    /// Part of a larger view rendered by the projection, if any.
+    pub view_offset: Option<ViewOffset>,
 }
 
 impl Perspective {
@@ -231,13 +402,14 @@ impl Perspective {
         &self,
         aspect_ratio: f32,
     ) -> mint::ColumnMatrix4<f32> {
-        match self.zrange {
+        let aspect_ratio = full_aspect_ratio(&self.view_offset, aspect_ratio);
+        let matrix = match self.zrange {
             ZRange::Finite(ref range) => cgmath::perspective(
                 cgmath::Deg(self.fov_y),
                 aspect_ratio,
                 range.start,
                 range.end,
-            ).into(),
+            ),
             ZRange::Infinite(ref range) => {
                 let f = 1.0 / (0.5 * self.fov_y.to_radians()).tan();
 
@@ -256,6 +428,151 @@ impl Perspective {
 
                 m.into()
             }
+        };
+        zoomed_matrix(matrix, self.zoom, &self.view_offset)
+    }
+}
+
+/// A rectangle of a larger view, in pixels with `(0, 0)` at the top left.
+///
+/// A projection with a view offset renders only this rectangle of the view,
+/// stretched over the whole target. The aspect ratio of the full view is
+/// taken from `full_size`, so the one of the target is ignored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewOffset {
+    /// Size of the full view.
+    pub full_size: mint::Vector2<f32>,
+    /// Top left corner of the rendered rectangle.
+    pub offset: mint::Point2<f32>,
+    /// Size of the rendered rectangle.
+    pub size: mint::Vector2<f32>,
+}
+
+fn full_aspect_ratio(
+    view_offset: &Option<ViewOffset>,
+    aspect_ratio: f32,
+) -> f32 {
+    match *view_offset {
+        Some(ref view) => view.full_size.x / view.full_size.y,
+        None => aspect_ratio,
+    }
+}
+
+/// Applies zoom and a view offset to a projection matrix, by scaling and
+/// moving the selected part of clip space to cover all of it.
+fn zoomed_matrix(
+    matrix: Matrix4<f32>,
+    zoom: f32,
+    view_offset: &Option<ViewOffset>,
+) -> mint::ColumnMatrix4<f32> {
+    let (mut scale_x, mut scale_y, mut move_x, mut move_y) = (zoom, zoom, 0.0, 0.0);
+    if let Some(ref view) = *view_offset {
+        // center of the rectangle in normalized device coordinates
+        let center_x = 2.0 * (view.offset.x + 0.5 * view.size.x) / view.full_size.x - 1.0;
+        let center_y = 1.0 - 2.0 * (view.offset.y + 0.5 * view.size.y) / view.full_size.y;
+        scale_x *= view.full_size.x / view.size.x;
+        scale_y *= view.full_size.y / view.size.y;
+        move_x = -center_x * view.full_size.x / view.size.x;
+        move_y = -center_y * view.full_size.y / view.size.y;
+    }
+    let ndc = Matrix4::new(
+        scale_x, 0.0, 0.0, 0.0,
+        0.0, scale_y, 0.0, 0.0,
+        0.0, 0.0, 1.0, 0.0,
+        move_x, move_y, 0.0, 1.0,
+    );
+    (ndc * matrix).into()
+}
+
+/// A convex volume bounded by the six clipping planes of a projection.
+///
+/// The planes are stored as `(normal, distance)` pairs with normals pointing
+/// inwards, so a point `p` is inside the plane if `dot(normal, p) + distance >= 0`.
+/// They are normalized, making the tests below return true distances.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frustum {
+    /// The left, right, bottom, top, near and far planes, in that order.
+    ///
+    /// The far plane of an infinite projection has a zero normal and a
+    /// positive distance, so it contains every point.
+    pub planes: [(mint::Vector3<f32>, f32); 6],
+}
+
+impl Frustum {
+    /// Extracts the clipping planes of a projection, or view-projection, matrix.
+    ///
+    /// The planes are expressed in the space the matrix transforms from, so
+    /// using [`Camera::view_projection_matrix`] yields a world space frustum.
+    ///
+    /// [`Camera::view_projection_matrix`]: struct.Camera.html#method.view_projection_matrix
+    pub fn from_matrix<M: Into<mint::ColumnMatrix4<f32>>>(matrix: M) -> Self {
+        let m = Matrix4::from(matrix.into());
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        let plane = |v: Vector4<f32>| {
+            let normal = v.truncate();
+            let length = normal.magnitude();
+            if length > 0.0 {
+                ((normal / length).into(), v.w / length)
+            } else {
+                (normal.into(), v.w)
+            }
+        };
+        Frustum {
+            planes: [
+                plane(r3 + r0),
+                plane(r3 - r0),
+                plane(r3 + r1),
+                plane(r3 - r1),
+                plane(r3 + r2),
+                plane(r3 - r2),
+            ],
         }
     }
+
+    /// Checks if the frustum contains `point`.
+    pub fn contains_point<P: Into<mint::Point3<f32>>>(
+        &self,
+        point: P,
+    ) -> bool {
+        self.intersects_sphere(point, 0.0)
+    }
+
+    /// Checks if the sphere at `center` with `radius` is at least partially
+    /// inside the frustum.
+    ///
+    /// Spheres close to the corners outside of the frustum may be reported as
+    /// intersecting, which is safe for culling.
+    pub fn intersects_sphere<P: Into<mint::Point3<f32>>>(
+        &self,
+        center: P,
+        radius: f32,
+    ) -> bool {
+        let center = center.into();
+        let center = Vector3::new(center.x, center.y, center.z);
+        self.planes
+            .iter()
+            .all(|&(n, d)| Vector3::from(n).dot(center) + d >= -radius)
+    }
+
+    /// Checks if the axis-aligned box spanning from `min` to `max` is at least
+    /// partially inside the frustum.
+    ///
+    /// Boxes close to the corners outside of the frustum may be reported as
+    /// intersecting, which is safe for culling.
+    pub fn intersects_aabb<P: Into<mint::Point3<f32>>>(
+        &self,
+        min: P,
+        max: P,
+    ) -> bool {
+        let (min, max) = (min.into(), max.into());
+        self.planes.iter().all(|&(n, d)| {
+            // the corner furthest along the plane normal
+            let corner = Vector3::new(
+                if n.x >= 0.0 { max.x } else { min.x },
+                if n.y >= 0.0 { max.y } else { min.y },
+                if n.z >= 0.0 { max.z } else { min.z },
+            );
+            Vector3::from(n).dot(corner) + d >= 0.0
+        })
+    }
 }
@@ -0,0 +1,411 @@
+//! Simple geometric queries for collision detection.
+//!
+//! The queries work on plain shapes in world space, such as the bounds of
+//! objects returned by [`world_bounds`], and cover what simple games need
+//! without pulling in a physics engine: rays against boxes, spheres and
+//! capsules, overlap tests, and sweeping a moving sphere against another.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let wall = window.factory.mesh(three::Geometry::cuboid(1.0, 1.0, 1.0), three::material::Basic::default());
+//! use three::collision::{self, Sphere};
+//! use three::raycast::Raycaster;
+//!
+//! let player = Sphere::new([0.0, 1.0, 0.0], 0.5);
+//! let ray = Raycaster::new([0.0, 1.0, 5.0], [0.0, 0.0, -1.0]).ray;
+//! let sync = window.scene.sync_guard();
+//! if let Some(bounds) = collision::world_bounds(&sync, &wall) {
+//!     if let Some(distance) = collision::ray_aabb(&ray, &bounds) {
+//!         println!("wall is {} ahead", distance);
+//!     }
+//!     if bounds.intersects_sphere(&player) {
+//!         println!("player touches the wall");
+//!     }
+//! }
+//! ```
+//!
+//! [`world_bounds`]: fn.world_bounds.html
+
+use cgmath::{InnerSpace, Transform, Vector3};
+use mint;
+
+use hub::{Hub, SubNode};
+use node::{NodePointer, TransformInternal};
+use object::Object;
+use raycast::{self, Ray};
+use scene::SyncGuard;
+
+use std::ptr;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    /// Corner with the smallest coordinates.
+    pub min: mint::Point3<f32>,
+    /// Corner with the largest coordinates.
+    pub max: mint::Point3<f32>,
+}
+
+/// Sphere given by its center and radius.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Sphere {
+    /// Center of the sphere.
+    pub center: mint::Point3<f32>,
+    /// Radius of the sphere.
+    pub radius: f32,
+}
+
+/// Capsule, the set of points within `radius` of the segment from `start`
+/// to `end`. Commonly used for characters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Capsule {
+    /// First end of the inner segment.
+    pub start: mint::Point3<f32>,
+    /// Second end of the inner segment.
+    pub end: mint::Point3<f32>,
+    /// Radius around the segment.
+    pub radius: f32,
+}
+
+fn vec<P: Into<mint::Point3<f32>>>(point: P) -> Vector3<f32> {
+    let point: [f32; 3] = point.into().into();
+    Vector3::from(point)
+}
+
+fn point(v: Vector3<f32>) -> mint::Point3<f32> {
+    [v.x, v.y, v.z].into()
+}
+
+/// Closest point to `p` on the segment from `a` to `b`.
+fn closest_on_segment(
+    a: Vector3<f32>,
+    b: Vector3<f32>,
+    p: Vector3<f32>,
+) -> Vector3<f32> {
+    let ab = b - a;
+    let length2 = ab.magnitude2();
+    if length2 == 0.0 {
+        return a;
+    }
+    let t = ((p - a).dot(ab) / length2).clamp(0.0, 1.0);
+    a + ab * t
+}
+
+impl Aabb {
+    /// Creates a box from its two extreme corners.
+    pub fn new<P: Into<mint::Point3<f32>>>(
+        min: P,
+        max: P,
+    ) -> Self {
+        Aabb {
+            min: min.into(),
+            max: max.into(),
+        }
+    }
+
+    /// The center of the box.
+    pub fn center(&self) -> mint::Point3<f32> {
+        point((vec(self.min) + vec(self.max)) * 0.5)
+    }
+
+    /// The point of the box closest to `p`, which is `p` itself if it lies
+    /// inside.
+    pub fn closest_point<P: Into<mint::Point3<f32>>>(
+        &self,
+        p: P,
+    ) -> mint::Point3<f32> {
+        let p = p.into();
+        [
+            p.x.max(self.min.x).min(self.max.x),
+            p.y.max(self.min.y).min(self.max.y),
+            p.z.max(self.min.z).min(self.max.z),
+        ].into()
+    }
+
+    /// Checks whether `p` lies inside the box.
+    pub fn contains<P: Into<mint::Point3<f32>>>(
+        &self,
+        p: P,
+    ) -> bool {
+        let p = p.into();
+        self.min.x <= p.x && p.x <= self.max.x &&
+            self.min.y <= p.y && p.y <= self.max.y &&
+            self.min.z <= p.z && p.z <= self.max.z
+    }
+
+    /// Checks whether the box overlaps `other`.
+    pub fn intersects(
+        &self,
+        other: &Aabb,
+    ) -> bool {
+        self.min.x <= other.max.x && other.min.x <= self.max.x &&
+            self.min.y <= other.max.y && other.min.y <= self.max.y &&
+            self.min.z <= other.max.z && other.min.z <= self.max.z
+    }
+
+    /// Checks whether the box overlaps `sphere`.
+    pub fn intersects_sphere(
+        &self,
+        sphere: &Sphere,
+    ) -> bool {
+        let closest = vec(self.closest_point(sphere.center));
+        (closest - vec(sphere.center)).magnitude2() <= sphere.radius * sphere.radius
+    }
+}
+
+impl Sphere {
+    /// Creates a sphere.
+    pub fn new<P: Into<mint::Point3<f32>>>(
+        center: P,
+        radius: f32,
+    ) -> Self {
+        Sphere {
+            center: center.into(),
+            radius,
+        }
+    }
+
+    /// Checks whether `p` lies inside the sphere.
+    pub fn contains<P: Into<mint::Point3<f32>>>(
+        &self,
+        p: P,
+    ) -> bool {
+        (vec(p) - vec(self.center)).magnitude2() <= self.radius * self.radius
+    }
+
+    /// Checks whether the sphere overlaps `other`.
+    pub fn intersects(
+        &self,
+        other: &Sphere,
+    ) -> bool {
+        let radius = self.radius + other.radius;
+        (vec(other.center) - vec(self.center)).magnitude2() <= radius * radius
+    }
+}
+
+impl Capsule {
+    /// Creates a capsule around the segment from `start` to `end`.
+    pub fn new<P: Into<mint::Point3<f32>>>(
+        start: P,
+        end: P,
+        radius: f32,
+    ) -> Self {
+        Capsule {
+            start: start.into(),
+            end: end.into(),
+            radius,
+        }
+    }
+
+    /// Checks whether `p` lies inside the capsule.
+    pub fn contains<P: Into<mint::Point3<f32>>>(
+        &self,
+        p: P,
+    ) -> bool {
+        let p = vec(p);
+        let closest = closest_on_segment(vec(self.start), vec(self.end), p);
+        (p - closest).magnitude2() <= self.radius * self.radius
+    }
+
+    /// Checks whether the capsule overlaps `sphere`.
+    pub fn intersects_sphere(
+        &self,
+        sphere: &Sphere,
+    ) -> bool {
+        let center = vec(sphere.center);
+        let closest = closest_on_segment(vec(self.start), vec(self.end), center);
+        let radius = self.radius + sphere.radius;
+        (center - closest).magnitude2() <= radius * radius
+    }
+}
+
+/// Distance along `ray` to the first point inside `aabb`, or `None` if the
+/// ray misses it. Rays starting inside the box hit it at zero distance.
+pub fn ray_aabb(
+    ray: &Ray,
+    aabb: &Aabb,
+) -> Option<f32> {
+    let origin = vec(ray.origin);
+    let dir = Vector3::from(ray.direction);
+    let (min, max) = (vec(aabb.min), vec(aabb.max));
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+    for axis in 0 .. 3 {
+        if dir[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) / dir[axis];
+        let t2 = (max[axis] - origin[axis]) / dir[axis];
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+        if t_min > t_max {
+            return None;
+        }
+    }
+    Some(t_min)
+}
+
+/// Distance along `ray` to the first point inside `sphere`, or `None` if the
+/// ray misses it. Rays starting inside the sphere hit it at zero distance.
+pub fn ray_sphere(
+    ray: &Ray,
+    sphere: &Sphere,
+) -> Option<f32> {
+    let m = vec(ray.origin) - vec(sphere.center);
+    let dir = Vector3::from(ray.direction);
+    let b = m.dot(dir);
+    let c = m.magnitude2() - sphere.radius * sphere.radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    if b > 0.0 {
+        return None;
+    }
+    let discriminant = b * b - c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    Some(-b - discriminant.sqrt())
+}
+
+/// Distance along `ray` to the first point inside `capsule`, or `None` if
+/// the ray misses it. Rays starting inside the capsule hit it at zero
+/// distance.
+pub fn ray_capsule(
+    ray: &Ray,
+    capsule: &Capsule,
+) -> Option<f32> {
+    if capsule.contains(ray.origin) {
+        return Some(0.0);
+    }
+    let (a, b) = (vec(capsule.start), vec(capsule.end));
+    let caps = [
+        ray_sphere(ray, &Sphere::new(capsule.start, capsule.radius)),
+        ray_sphere(ray, &Sphere::new(capsule.end, capsule.radius)),
+    ];
+    let mut best = caps.iter().filter_map(|&t| t).fold(None, |best: Option<f32>, t| {
+        Some(best.map_or(t, |best| best.min(t)))
+    });
+
+    // the side of the cylinder between the caps
+    let length = (b - a).magnitude();
+    if length > 0.0 {
+        let axis = (b - a) / length;
+        let dir = Vector3::from(ray.direction);
+        let offset = vec(ray.origin) - a;
+        let dir_flat = dir - axis * dir.dot(axis);
+        let offset_flat = offset - axis * offset.dot(axis);
+        let qa = dir_flat.magnitude2();
+        let qb = offset_flat.dot(dir_flat);
+        let qc = offset_flat.magnitude2() - capsule.radius * capsule.radius;
+        let discriminant = qb * qb - qa * qc;
+        if qa > 0.0 && discriminant >= 0.0 {
+            let t = (-qb - discriminant.sqrt()) / qa;
+            let along = (offset + dir * t).dot(axis);
+            if t >= 0.0 && along >= 0.0 && along <= length {
+                best = Some(best.map_or(t, |best| best.min(t)));
+            }
+        }
+    }
+    best
+}
+
+/// Sweeps the sphere `a` along `a_motion` and the sphere `b` along
+/// `b_motion`, returning the fraction of the motion in `0.0 ..= 1.0` at
+/// which they first touch, or `None` if they stay apart.
+///
+/// Spheres that overlap at the start touch at zero.
+///
+/// ```rust
+/// use three::collision::{self, Sphere};
+///
+/// let a = Sphere::new([0.0, 0.0, 0.0], 1.0);
+/// let b = Sphere::new([4.0, 0.0, 0.0], 1.0);
+/// let hit = collision::sweep_sphere_sphere(&a, [4.0, 0.0, 0.0], &b, [0.0, 0.0, 0.0]);
+/// assert_eq!(hit, Some(0.5));
+/// ```
+pub fn sweep_sphere_sphere<V: Into<mint::Vector3<f32>>>(
+    a: &Sphere,
+    a_motion: V,
+    b: &Sphere,
+    b_motion: V,
+) -> Option<f32> {
+    let offset = vec(a.center) - vec(b.center);
+    let motion = Vector3::from(a_motion.into()) - Vector3::from(b_motion.into());
+    let radius = a.radius + b.radius;
+    let c = offset.magnitude2() - radius * radius;
+    if c <= 0.0 {
+        return Some(0.0);
+    }
+    let qa = motion.magnitude2();
+    let qb = offset.dot(motion);
+    if qa == 0.0 || qb >= 0.0 {
+        return None;
+    }
+    let discriminant = qb * qb - qa * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t = (-qb - discriminant.sqrt()) / qa;
+    if t <= 1.0 {
+        Some(t)
+    } else {
+        None
+    }
+}
+
+/// The world space bounds of the meshes in `object` and its children.
+///
+/// Returns `None` if the object isn't in the scene or has no visible
+/// meshes. Skinned and morphed meshes are bounded in their bind pose.
+pub fn world_bounds<T: Object>(
+    sync: &SyncGuard,
+    object: &T,
+) -> Option<Aabb> {
+    let internal = &sync.hub[object];
+    let root = sync.hub
+        .walk_all(&sync.scene.first_child)
+        .find(|w| ptr::eq(w.node, internal))?;
+    let mut bounds = raycast::Aabb::empty();
+    subtree_bounds(&sync.hub, &root.node_ptr, &root.world_transform, &mut bounds);
+    if bounds.is_empty() {
+        None
+    } else {
+        Some(Aabb {
+            min: point(bounds.min),
+            max: point(bounds.max),
+        })
+    }
+}
+
+fn subtree_bounds(
+    hub: &Hub,
+    ptr: &NodePointer,
+    world_transform: &TransformInternal,
+    bounds: &mut raycast::Aabb,
+) {
+    let node = &hub.nodes[ptr];
+    if !node.visible {
+        return;
+    }
+    match node.sub_node {
+        SubNode::Visual(_, ref gpu_data, _) => {
+            if let Some((mesh_bounds, _)) = gpu_data.bounds() {
+                *bounds = bounds.union(&mesh_bounds.transformed(world_transform));
+            }
+        }
+        SubNode::Group { ref first_child, .. } => {
+            let mut child = first_child.clone();
+            while let Some(child_ptr) = child {
+                let child_node = &hub.nodes[&child_ptr];
+                let transform = world_transform.concat(&child_node.transform);
+                subtree_bounds(hub, &child_ptr, &transform, bounds);
+                child = child_node.next_sibling.clone();
+            }
+        }
+        _ => {}
+    }
+}
@@ -53,9 +53,10 @@ pub fn to_linear_rgb(c: Color) -> [f32; 3] {
 /// Implementation taken from https://en.wikipedia.org/wiki/SRGB
 pub fn from_linear_rgb(c: [f32; 3]) -> Color {
     let f = |x: f32| -> u32 {
+        let x = x.max(0.0).min(1.0);
         let y = if x > 0.0031308 {
             let a = 0.055;
-            (1.0 + a) * x.powf(-2.4) - a
+            (1.0 + a) * x.powf(1.0 / 2.4) - a
         } else {
             12.92 * x
         };
@@ -0,0 +1,65 @@
+use cgmath::{InnerSpace, Point3};
+
+use object::Base;
+use raycast::{Intersection, Raycaster, SceneBvh};
+use scene::SyncGuard;
+
+use std::collections::HashSet;
+
+/// Geometry a controlled camera is kept from clipping through.
+///
+/// Only meshes keeping their triangles for ray casts are considered, see
+/// [`Factory::set_raycast_triangles`].
+///
+/// [`Factory::set_raycast_triangles`]: ../struct.Factory.html#method.set_raycast_triangles
+#[derive(Clone, Copy, Debug)]
+pub enum Colliders<'a> {
+    /// Every visible mesh in the scene.
+    Scene,
+    /// The given objects and their children.
+    Objects(&'a [Base]),
+    /// The meshes tracked by a scene BVH, which must be up to date.
+    Bvh(&'a SceneBvh),
+}
+
+/// Moves `position` towards `target` so that the segment between them does
+/// not cross any of the `colliders`, keeping `margin` away from the surface.
+///
+/// Meshes within the subtree of `ignore` are not considered.
+pub(crate) fn pull_in(
+    sync: &SyncGuard,
+    colliders: Colliders,
+    ignore: Option<&Base>,
+    target: Point3<f32>,
+    position: Point3<f32>,
+    margin: f32,
+) -> Point3<f32> {
+    let offset = position - target;
+    let distance = offset.magnitude();
+    if distance == 0.0 {
+        return position;
+    }
+    let mut raycaster = Raycaster::new(target, offset / distance);
+    raycaster.far = distance + margin;
+
+    let hits: Vec<Intersection> = match colliders {
+        Colliders::Scene => raycaster.intersect_scene(sync),
+        Colliders::Objects(objects) => objects
+            .iter()
+            .flat_map(|object| raycaster.intersect_object(sync, object, true))
+            .collect(),
+        Colliders::Bvh(bvh) => raycaster.intersect_bvh(bvh, sync),
+    };
+    let mut ignored = HashSet::new();
+    if let Some(base) = ignore {
+        sync.hub.collect_subtree(&base.node, &mut ignored);
+    }
+    let closest = hits
+        .iter()
+        .filter(|hit| !ignored.contains(&(&sync.hub[&hit.object] as *const _)))
+        .map(|hit| hit.distance)
+        .fold(distance + margin, f32::min);
+
+    let allowed = (closest - margin).max(0.0).min(distance);
+    target + offset * (allowed / distance)
+}
@@ -8,11 +8,17 @@ use input::{axis, Input, Key};
 use object::Object;
 use std::f32::consts::PI;
 
+/// How many raw mouse units per frame a fully tilted look stick turns as
+/// fast as.
+const STICK_LOOK_SCALE: f32 = 6.0;
+
 #[derive(Clone, Debug, PartialEq)]
 struct Axes {
     pub forward: Option<axis::Key>,
     pub strafing: Option<axis::Key>,
     pub vertical: Option<axis::Key>,
+    pub move_stick: Option<[axis::Analog; 2]>,
+    pub look_stick: Option<[axis::Analog; 2]>,
 }
 
 impl Default for Axes {
@@ -27,6 +33,8 @@ impl Default for Axes {
                 neg: Key::A,
             }),
             vertical: None,
+            move_stick: Some([axis::LEFT_STICK_X, axis::LEFT_STICK_Y]),
+            look_stick: Some([axis::RIGHT_STICK_X, axis::RIGHT_STICK_Y]),
         }
     }
 }
@@ -44,6 +52,7 @@ pub struct FirstPerson {
     axes: Axes,
     vertical_move: bool,
     vertical_look: bool,
+    require_grab: bool,
 }
 
 /// Constructs custom [`FirstPerson`](struct.FirstPerson.html) controls.
@@ -59,6 +68,7 @@ pub struct Builder {
     axes: Axes,
     vertical_move: bool,
     vertical_look: bool,
+    require_grab: bool,
 }
 
 impl Builder {
@@ -75,6 +85,7 @@ impl Builder {
             axes: Axes::default(),
             vertical_move: true,
             vertical_look: true,
+            require_grab: false,
         }
     }
 
@@ -170,6 +181,20 @@ impl Builder {
         self
     }
 
+    /// Setup whether the mouse turns the controlled object only while the
+    /// cursor is grabbed with [`Window::set_cursor_grab`].
+    ///
+    /// Defaults to false.
+    ///
+    /// [`Window::set_cursor_grab`]: ../struct.Window.html#method.set_cursor_grab
+    pub fn require_grab(
+        &mut self,
+        value: bool,
+    ) -> &mut Self {
+        self.require_grab = value;
+        self
+    }
+
     /// Setup key axis for moving forward/backward.
     ///
     /// Defaults to `W` and `S` keys.
@@ -203,6 +228,28 @@ impl Builder {
         self
     }
 
+    /// Setup gamepad axes for strafing and moving forward/backward.
+    ///
+    /// Defaults to the left stick.
+    pub fn move_stick(
+        &mut self,
+        axes: Option<[axis::Analog; 2]>,
+    ) -> &mut Self {
+        self.axes.move_stick = axes;
+        self
+    }
+
+    /// Setup gamepad axes for turning and looking up/down.
+    ///
+    /// Defaults to the right stick.
+    pub fn look_stick(
+        &mut self,
+        axes: Option<[axis::Analog; 2]>,
+    ) -> &mut Self {
+        self.axes.look_stick = axes;
+        self
+    }
+
     /// Finalize builder and create new `FirstPerson` controls.
     pub fn build(&mut self) -> FirstPerson {
         FirstPerson {
@@ -216,6 +263,7 @@ impl Builder {
             axes: self.axes.clone(),
             vertical_move: self.vertical_move,
             vertical_look: self.vertical_look,
+            require_grab: self.require_grab,
         }
     }
 }
@@ -307,6 +355,18 @@ impl FirstPerson {
         self
     }
 
+    /// Specifies whether the mouse turns the controlled object only while the
+    /// cursor is grabbed with [`Window::set_cursor_grab`].
+    ///
+    /// [`Window::set_cursor_grab`]: ../struct.Window.html#method.set_cursor_grab
+    pub fn set_require_grab(
+        &mut self,
+        value: bool,
+    ) -> &mut Self {
+        self.require_grab = value;
+        self
+    }
+
     /// Sets the key axis for moving forward/backward.
     pub fn set_axis_forward(
         &mut self,
@@ -334,6 +394,24 @@ impl FirstPerson {
         self
     }
 
+    /// Sets the gamepad axes for strafing and moving forward/backward.
+    pub fn set_move_stick(
+        &mut self,
+        axes: Option<[axis::Analog; 2]>,
+    ) -> &mut Self {
+        self.axes.move_stick = axes;
+        self
+    }
+
+    /// Sets the gamepad axes for turning and looking up/down.
+    pub fn set_look_stick(
+        &mut self,
+        axes: Option<[axis::Analog; 2]>,
+    ) -> &mut Self {
+        self.axes.look_stick = axes;
+        self
+    }
+
     /// Updates the position, yaw, and pitch of the controlled object according to
     /// the last frame input.
     pub fn update(
@@ -341,11 +419,20 @@ impl FirstPerson {
         input: &Input,
     ) {
         let dlook = input.delta_time() * self.look_speed;
-        let mouse = input.mouse_delta_raw();
+        let mut look = if self.require_grab && !input.is_cursor_grabbed() {
+            [0.0, 0.0].into()
+        } else {
+            input.mouse_delta_raw()
+        };
+        if let Some([x, y]) = self.axes.look_stick {
+            // sticks point up when positive, unlike the mouse
+            look.x += STICK_LOOK_SCALE * input.delta(x).unwrap_or(0.0);
+            look.y -= STICK_LOOK_SCALE * input.delta(y).unwrap_or(0.0);
+        }
 
-        self.yaw += dlook * mouse.x;
+        self.yaw += dlook * look.x;
         if self.vertical_look {
-            self.pitch += dlook * mouse.y;
+            self.pitch += dlook * look.y;
             if let Some(range) = self.pitch_range.as_ref() {
                 if self.pitch < range.start {
                     self.pitch = range.start;
@@ -362,24 +449,37 @@ impl FirstPerson {
             }
         });
 
-        self.axes.forward.map(|a| {
-            if let Some(diff) = input.timed(a) {
-                self.position.x += self.move_speed * diff * self.yaw.sin();
-                self.position.z -= self.move_speed * diff * self.yaw.cos();
-                if self.vertical_move {
-                    self.position.y -= self.move_speed * diff * self.pitch.sin();
-                }
-            }
-        });
-        self.axes.strafing.map(|a| {
-            if let Some(diff) = input.timed(a) {
-                self.position.x += self.move_speed * diff * self.yaw.cos();
-                self.position.z += self.move_speed * diff * self.yaw.sin();
+        let (stick_strafing, stick_forward) = match self.axes.move_stick {
+            Some([x, y]) => (input.timed(x), input.timed(y)),
+            None => (None, None),
+        };
+        let forward = add_timed(self.axes.forward.and_then(|a| input.timed(a)), stick_forward);
+        if let Some(diff) = forward {
+            self.position.x += self.move_speed * diff * self.yaw.sin();
+            self.position.z -= self.move_speed * diff * self.yaw.cos();
+            if self.vertical_move {
+                self.position.y -= self.move_speed * diff * self.pitch.sin();
             }
-        });
+        }
+        let strafing = add_timed(self.axes.strafing.and_then(|a| input.timed(a)), stick_strafing);
+        if let Some(diff) = strafing {
+            self.position.x += self.move_speed * diff * self.yaw.cos();
+            self.position.z += self.move_speed * diff * self.yaw.sin();
+        }
 
         let yrot = cgmath::Quaternion::from_angle_y(cgmath::Rad(-self.yaw));
         let xrot = cgmath::Quaternion::from_angle_x(cgmath::Rad(-self.pitch));
         self.object.set_transform(self.position, yrot * xrot, 1.0);
     }
 }
+
+/// Sums the movements of a key axis and a stick, either of which may be idle.
+fn add_timed(
+    a: Option<f32>,
+    b: Option<f32>,
+) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, b) => a.or(b),
+    }
+}
@@ -10,6 +10,9 @@
 //!  * Uses mouse movement to rotate the object around its target.
 //!  * Uses the mouse scroll wheel to move the object closer to or further
 //!    from its target.
+//!  * Uses the right stick of a gamepad to rotate the object as well.
+//!  * Optionally keeps the object from clipping through walls between it and
+//!    its target, see [`Colliders`].
 //!
 //! ### First-person
 //!
@@ -17,22 +20,46 @@
 //!  * Uses the A and D keys to strafe left or right.
 //!  * Uses mouse movement to rotate the object when the right mouse button
 //!    is held down.
+//!  * Uses the left and right sticks of a gamepad to move and rotate the
+//!    object.
 //!
+//! ### Third-person
+//!
+//!  * Follows a target object from behind at a configurable offset, with
+//!    optional smoothing.
+//!  * Uses mouse movement to orbit around the target and the mouse scroll
+//!    wheel to adjust the distance.
+//!  * Optionally keeps the object from clipping through walls, like the
+//!    orbital controls.
+//!
+//! [`Colliders`]: enum.Colliders.html
 //! [`Object`]: ../object/trait.Object.html
 
+mod collision;
+
 /// First person controls.
 pub mod first_person;
 
 /// Mouse orbit controls.
 pub mod orbit;
 
+/// Third person controls.
+pub mod third_person;
+
+#[doc(inline)]
+pub use self::collision::Colliders;
+
 #[doc(inline)]
 pub use self::first_person::FirstPerson;
 
 #[doc(inline)]
 pub use self::orbit::Orbit;
 
+#[doc(inline)]
+pub use self::third_person::ThirdPerson;
+
 pub use input::{axis,
-    Button, Delta, Hit, HitCount, Key, Input, Timer, MouseButton,
+    Button, Delta, Hit, HitCount, Key, Input, Modifiers, ScanCode, Timer, MouseButton,
+    Gamepad, GamepadAxis, GamepadButton, GamepadId,
     AXIS_DOWN_UP, AXIS_LEFT_RIGHT, KEY_ESCAPE, KEY_SPACE, MOUSE_LEFT, MOUSE_RIGHT,
 };
@@ -3,15 +3,24 @@ use cgmath::{EuclideanSpace, InnerSpace, Rotation, Rotation3, Transform as Trans
 use mint;
 use object;
 
-use input::{Button, Input, MOUSE_LEFT};
+use controls::collision::{self, Colliders};
+use input::{axis, Button, Input, MOUSE_LEFT};
 use node::TransformInternal;
 use object::Object;
+use scene::SyncGuard;
+
+/// Radians per second a fully tilted stick rotates by at a speed of 1.
+const STICK_SPEED: f32 = 2.0;
 
 /// Simple controls for Orbital Camera.
 ///
 /// Camera is rotating around the fixed point without any restrictions.
 /// By default, it uses left mouse button as control button (hold it to rotate) and mouse wheel
-/// to adjust distance to the central point.
+/// to adjust distance to the central point. The right stick of a gamepad rotates it as well.
+///
+/// Use [`update_with_collision`](#method.update_with_collision) instead of
+/// [`update`](#method.update) to pull the object in front of any geometry
+/// between it and the target.
 #[derive(Clone, Debug)]
 pub struct Orbit {
     object: object::Base,
@@ -19,7 +28,9 @@ pub struct Orbit {
     initial_transform: TransformInternal,
     target: Point3<f32>,
     button: Button,
+    stick: Option<[axis::Analog; 2]>,
     speed: f32,
+    collision_margin: f32,
 }
 
 /// Helper struct to construct [`Orbit`](struct.Orbit.html) with desired settings.
@@ -30,7 +41,9 @@ pub struct Builder {
     up: mint::Vector3<f32>,
     target: mint::Point3<f32>,
     button: Button,
+    stick: Option<[axis::Analog; 2]>,
     speed: f32,
+    collision_margin: f32,
 }
 
 impl Builder {
@@ -42,7 +55,9 @@ impl Builder {
             up: [0.0, 0.0, 1.0].into(),
             target: [0.0, 0.0, 0.0].into(),
             button: MOUSE_LEFT,
+            stick: Some([axis::RIGHT_STICK_X, axis::RIGHT_STICK_Y]),
             speed: 1.0,
+            collision_margin: 0.1,
         }
     }
 
@@ -106,6 +121,28 @@ impl Builder {
         self
     }
 
+    /// Setup gamepad axes rotating around the target horizontally and
+    /// vertically. Default is the right stick.
+    pub fn stick(
+        &mut self,
+        axes: Option<[axis::Analog; 2]>,
+    ) -> &mut Self {
+        self.stick = axes;
+        self
+    }
+
+    /// Setup the distance kept between the object and colliding geometry
+    /// in [`Orbit::update_with_collision`]. Default value is 0.1
+    ///
+    /// [`Orbit::update_with_collision`]: struct.Orbit.html#method.update_with_collision
+    pub fn collision_margin(
+        &mut self,
+        margin: f32,
+    ) -> &mut Self {
+        self.collision_margin = margin;
+        self
+    }
+
     /// Finalize builder and create new `OrbitControls`.
     pub fn build(&mut self) -> Orbit {
         let dir = (Point3::from(self.position) - Point3::from(self.target)).normalize();
@@ -125,7 +162,9 @@ impl Builder {
             initial_transform: transform,
             target: self.target.into(),
             button: self.button,
+            stick: self.stick,
             speed: self.speed,
+            collision_margin: self.collision_margin,
         }
     }
 }
@@ -141,11 +180,48 @@ impl Orbit {
         &mut self,
         input: &Input,
     ) {
-        let mouse_delta = if input.hit(self.button) {
+        self.advance(input);
+        let pf: mint::Vector3<f32> = self.transform.disp.into();
+        self.object.set_transform(pf, self.transform.rot, 1.0);
+    }
+
+    /// Same as [`update`](#method.update), but moves the object closer to the
+    /// target when any of the `colliders` is in between.
+    ///
+    /// The orbit distance is kept, so the object moves back out once the
+    /// view is clear again.
+    pub fn update_with_collision(
+        &mut self,
+        input: &Input,
+        sync: &SyncGuard,
+        colliders: Colliders,
+    ) {
+        self.advance(input);
+        let position = collision::pull_in(
+            sync,
+            colliders,
+            None,
+            self.target,
+            Point3::from_vec(self.transform.disp),
+            self.collision_margin,
+        );
+        let pf: mint::Point3<f32> = position.into();
+        self.object.set_transform(pf, self.transform.rot, 1.0);
+    }
+
+    fn advance(
+        &mut self,
+        input: &Input,
+    ) {
+        let mut mouse_delta = if input.hit(self.button) {
             input.mouse_delta_ndc()
         } else {
             [0.0, 0.0].into()
         };
+        if let Some([x, y]) = self.stick {
+            mouse_delta.x += STICK_SPEED * input.timed(x).unwrap_or(0.0);
+            mouse_delta.y += STICK_SPEED * input.timed(y).unwrap_or(0.0);
+        }
         let pre = Decomposed {
             disp: -self.target.to_vec(),
             ..Decomposed::one()
@@ -159,8 +235,6 @@ impl Orbit {
             disp: self.target.to_vec(),
         };
         self.transform = post.concat(&pre.concat(&self.transform));
-        let pf: mint::Vector3<f32> = self.transform.disp.into();
-        self.object.set_transform(pf, self.transform.rot, 1.0);
     }
 
     /// Reset the current position and orientation of the controlled object to their initial values.
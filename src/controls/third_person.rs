@@ -0,0 +1,378 @@
+use cgmath::{InnerSpace, Point3, Quaternion, Rotation, Vector3};
+use mint;
+use object;
+use std::ops;
+
+use controls::collision::{self, Colliders};
+use input::{Button, Input, MOUSE_RIGHT};
+use object::Object;
+use scene::SyncGuard;
+
+/// Controls for a chase camera following a target object.
+///
+/// The controlled object orbits a point above the target at a fixed distance.
+/// Moving the mouse while the control button is held rotates around the
+/// target, and the mouse wheel adjusts the distance.
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+/// # let player = window.factory.group();
+/// # let level = window.factory.group();
+/// use three::Object;
+/// use three::controls::{Colliders, ThirdPerson};
+///
+/// let walls = [level.upcast()];
+/// let mut controls = ThirdPerson::builder(&camera, &player)
+///     .offset([0.0, 1.5, 0.0])
+///     .distance(6.0)
+///     .smoothing(0.1)
+///     .build();
+/// while window.update() {
+///     {
+///         let sync = window.scene.sync_guard();
+///         controls.update_with_collision(&window.input, &sync, Colliders::Objects(&walls));
+///     }
+///     window.render(&camera);
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ThirdPerson {
+    object: object::Base,
+    target: object::Base,
+    offset: mint::Vector3<f32>,
+    distance: f32,
+    distance_range: ops::Range<f32>,
+    yaw: f32,
+    pitch: f32,
+    pitch_range: ops::Range<f32>,
+    look_speed: f32,
+    smoothing: f32,
+    button: Option<Button>,
+    collision_margin: f32,
+    position: Option<Point3<f32>>,
+}
+
+/// Constructs custom [`ThirdPerson`](struct.ThirdPerson.html) controls.
+#[derive(Clone, Debug)]
+pub struct Builder {
+    object: object::Base,
+    target: object::Base,
+    offset: mint::Vector3<f32>,
+    distance: f32,
+    distance_range: ops::Range<f32>,
+    yaw: f32,
+    pitch: f32,
+    pitch_range: ops::Range<f32>,
+    look_speed: f32,
+    smoothing: f32,
+    button: Option<Button>,
+    collision_margin: f32,
+}
+
+impl Builder {
+    /// Create new `Builder` with default parameters.
+    pub fn new<T: Object, U: Object>(
+        object: &T,
+        target: &U,
+    ) -> Self {
+        Builder {
+            object: object.upcast(),
+            target: target.upcast(),
+            offset: [0.0, 1.0, 0.0].into(),
+            distance: 5.0,
+            distance_range: 1.0 .. 20.0,
+            yaw: 0.0,
+            pitch: 0.3,
+            pitch_range: -0.5 .. 1.4,
+            look_speed: 1.0,
+            smoothing: 0.0,
+            button: Some(MOUSE_RIGHT),
+            collision_margin: 0.1,
+        }
+    }
+
+    /// Set the offset from the target position to the point looked at.
+    ///
+    /// Defaults to one unit along the `y` axis.
+    pub fn offset<V>(
+        &mut self,
+        offset: V,
+    ) -> &mut Self
+    where
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.offset = offset.into();
+        self
+    }
+
+    /// Set the initial distance to the point looked at.
+    ///
+    /// Defaults to 5.0.
+    pub fn distance(
+        &mut self,
+        distance: f32,
+    ) -> &mut Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Set the range the distance can be adjusted in with the mouse wheel.
+    ///
+    /// Defaults to `1.0 .. 20.0`.
+    pub fn distance_range(
+        &mut self,
+        range: ops::Range<f32>,
+    ) -> &mut Self {
+        self.distance_range = range;
+        self
+    }
+
+    /// Set the initial yaw angle in radians.
+    ///
+    /// At 0.0 the object is placed along the positive `z` axis of the target.
+    pub fn yaw(
+        &mut self,
+        yaw: f32,
+    ) -> &mut Self {
+        self.yaw = yaw;
+        self
+    }
+
+    /// Set the initial pitch angle in radians. Positive values look down at
+    /// the target.
+    ///
+    /// Defaults to 0.3.
+    pub fn pitch(
+        &mut self,
+        pitch: f32,
+    ) -> &mut Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Set the pitch range in radians.
+    ///
+    /// Defaults to `-0.5 .. 1.4`.
+    pub fn pitch_range(
+        &mut self,
+        range: ops::Range<f32>,
+    ) -> &mut Self {
+        self.pitch_range = range;
+        self
+    }
+
+    /// Setup mouse sensitivity.
+    ///
+    /// Defaults to 1.0
+    pub fn look_speed(
+        &mut self,
+        speed: f32,
+    ) -> &mut Self {
+        self.look_speed = speed;
+        self
+    }
+
+    /// Setup how long in seconds the object lags behind the target.
+    ///
+    /// Defaults to 0.0, which follows the target exactly.
+    pub fn smoothing(
+        &mut self,
+        smoothing: f32,
+    ) -> &mut Self {
+        self.smoothing = smoothing;
+        self
+    }
+
+    /// Setup the button to hold for orbiting. With `None`, any mouse
+    /// movement orbits.
+    ///
+    /// Defaults to right mouse button (`MOUSE_RIGHT`).
+    pub fn button(
+        &mut self,
+        button: Option<Button>,
+    ) -> &mut Self {
+        self.button = button;
+        self
+    }
+
+    /// Setup the distance kept between the object and colliding geometry
+    /// in [`ThirdPerson::update_with_collision`].
+    ///
+    /// Defaults to 0.1
+    ///
+    /// [`ThirdPerson::update_with_collision`]: struct.ThirdPerson.html#method.update_with_collision
+    pub fn collision_margin(
+        &mut self,
+        margin: f32,
+    ) -> &mut Self {
+        self.collision_margin = margin;
+        self
+    }
+
+    /// Finalize builder and create new `ThirdPerson` controls.
+    pub fn build(&mut self) -> ThirdPerson {
+        ThirdPerson {
+            object: self.object.clone(),
+            target: self.target.clone(),
+            offset: self.offset,
+            distance: self.distance,
+            distance_range: self.distance_range.clone(),
+            yaw: self.yaw,
+            pitch: self.pitch,
+            pitch_range: self.pitch_range.clone(),
+            look_speed: self.look_speed,
+            smoothing: self.smoothing,
+            button: self.button,
+            collision_margin: self.collision_margin,
+            position: None,
+        }
+    }
+}
+
+impl ThirdPerson {
+    /// Create a `Builder`.
+    pub fn builder<T: Object, U: Object>(
+        object: &T,
+        target: &U,
+    ) -> Builder {
+        Builder::new(object, target)
+    }
+
+    /// Create `ThirdPerson` controls with default parameters.
+    pub fn default<T: Object, U: Object>(
+        object: &T,
+        target: &U,
+    ) -> Self {
+        Self::builder(object, target).build()
+    }
+
+    /// Sets the object to follow.
+    pub fn set_target<T: Object>(
+        &mut self,
+        target: &T,
+    ) -> &mut Self {
+        self.target = target.upcast();
+        self
+    }
+
+    /// Sets the distance to the point looked at.
+    pub fn set_distance(
+        &mut self,
+        distance: f32,
+    ) -> &mut Self {
+        self.distance = distance;
+        self
+    }
+
+    /// Sets the yaw angle in radians.
+    pub fn set_yaw(
+        &mut self,
+        yaw: f32,
+    ) -> &mut Self {
+        self.yaw = yaw;
+        self
+    }
+
+    /// Sets the pitch angle in radians.
+    pub fn set_pitch(
+        &mut self,
+        pitch: f32,
+    ) -> &mut Self {
+        self.pitch = pitch;
+        self
+    }
+
+    /// Moves the object to its resting place right away, skipping the
+    /// smoothing for the next update.
+    pub fn snap(&mut self) {
+        self.position = None;
+    }
+
+    /// Updates the position and orientation of the controlled object
+    /// according to the last frame input and the target position.
+    pub fn update(
+        &mut self,
+        input: &Input,
+        sync: &SyncGuard,
+    ) {
+        let (focus, position) = self.advance(input, sync);
+        self.place(focus, position);
+    }
+
+    /// Same as [`update`](#method.update), but moves the object closer to the
+    /// target when any of the `colliders` is in between.
+    ///
+    /// Meshes belonging to the target are never considered as colliders.
+    pub fn update_with_collision(
+        &mut self,
+        input: &Input,
+        sync: &SyncGuard,
+        colliders: Colliders,
+    ) {
+        let (focus, position) = self.advance(input, sync);
+        let position = collision::pull_in(
+            sync,
+            colliders,
+            Some(&self.target),
+            focus,
+            position,
+            self.collision_margin,
+        );
+        self.place(focus, position);
+    }
+
+    /// Applies the input and returns the point looked at along with the
+    /// smoothed position of the object.
+    fn advance(
+        &mut self,
+        input: &Input,
+        sync: &SyncGuard,
+    ) -> (Point3<f32>, Point3<f32>) {
+        let orbiting = match self.button {
+            Some(button) => input.hit(button),
+            None => true,
+        };
+        if orbiting {
+            let delta = input.mouse_delta_ndc();
+            self.yaw -= self.look_speed * delta.x;
+            self.pitch -= self.look_speed * delta.y;
+        }
+        self.pitch = self.pitch.max(self.pitch_range.start).min(self.pitch_range.end);
+        self.distance *= 1.0 + input.mouse_wheel() / 1000.0;
+        self.distance = self.distance.max(self.distance_range.start).min(self.distance_range.end);
+
+        let target = Point3::from(sync.resolve_world(&self.target).transform.position);
+        let focus = target + Vector3::from(self.offset);
+        let direction = Vector3::new(
+            self.yaw.sin() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.cos() * self.pitch.cos(),
+        );
+        let desired = focus + direction * self.distance;
+
+        let position = match self.position {
+            Some(previous) if self.smoothing > 0.0 => {
+                let factor = 1.0 - (-input.delta_time() / self.smoothing).exp();
+                previous + (desired - previous) * factor
+            }
+            _ => desired,
+        };
+        self.position = Some(position);
+        (focus, position)
+    }
+
+    fn place(
+        &self,
+        focus: Point3<f32>,
+        position: Point3<f32>,
+    ) {
+        let dir = position - focus;
+        if dir.magnitude2() == 0.0 {
+            return;
+        }
+        let rot = Quaternion::look_at(dir.normalize(), Vector3::unit_y()).invert();
+        let pf: mint::Point3<f32> = position.into();
+        self.object.set_transform(pf, rot, 1.0);
+    }
+}
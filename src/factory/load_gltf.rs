@@ -13,10 +13,10 @@ use material;
 use mint;
 use std::collections::HashMap;
 
-use camera::{Orthographic, Perspective, Projection};
+use camera::{Projection, ZRange};
 use std::path::Path;
 
-use {Material, Texture};
+use {ColorSpace, Material, Texture};
 use geometry::{Geometry, Shape};
 use image::{DynamicImage, ImageBuffer};
 use node::Transform;
@@ -26,17 +26,42 @@ use template::{
     BoneTemplate,
     CameraTemplate,
     InstancedGeometry,
+    LightTemplate,
     MeshTemplate,
     ObjectTemplate,
     Template,
 };
 
+/// Textures of the document by index, uploaded in each color space they are
+/// sampled in.
+type Textures = HashMap<(usize, ColorSpace), Texture<[f32; 4]>>;
+
+/// Finds the color spaces each texture is sampled in by the materials.
+///
+/// Base color and emissive maps hold sRGB colors, while normal,
+/// metallic-roughness and occlusion maps hold linear data.
+fn texture_color_spaces(document: &gltf::Document) -> Vec<(usize, ColorSpace)> {
+    let mut usages = Vec::new();
+    for mat in document.materials() {
+        let pbr = mat.pbr_metallic_roughness();
+        usages.extend(pbr.base_color_texture().map(|t| (t.texture().index(), ColorSpace::Srgb)));
+        usages.extend(mat.emissive_texture().map(|t| (t.texture().index(), ColorSpace::Srgb)));
+        usages.extend(mat.normal_texture().map(|t| (t.texture().index(), ColorSpace::Linear)));
+        usages.extend(pbr.metallic_roughness_texture().map(|t| (t.texture().index(), ColorSpace::Linear)));
+        usages.extend(mat.occlusion_texture().map(|t| (t.texture().index(), ColorSpace::Linear)));
+    }
+    usages.sort_by_key(|&(index, space)| (index, space == ColorSpace::Linear));
+    usages.dedup();
+    usages
+}
+
 fn load_textures(
     factory: &mut Factory,
     document: &gltf::Document,
     images: Vec<gltf::image::Data>,
-) -> Vec<Texture<[f32; 4]>> {
-    let mut textures = Vec::new();
+) -> Textures {
+    let color_spaces = texture_color_spaces(document);
+    let mut textures = HashMap::new();
     for (texture, data) in document.textures().zip(images.into_iter()) {
         let (width, height) = (data.width, data.height);
         let image = match data.format {
@@ -105,35 +130,44 @@ fn load_textures(
             WrappingMode::Repeat => WrapMode::Tile,
         };
         let sampler = factory.sampler(mag_filter, wrap_s, wrap_t);
-        let texture = factory.load_texture_from_memory(width as u16, height as u16, &image, sampler);
-        textures.push(texture);
+        let index = texture.index();
+        for &(_, color_space) in color_spaces.iter().filter(|&&(i, _)| i == index) {
+            let texture = factory.load_texture_from_memory_with_color_space(
+                width as u16,
+                height as u16,
+                &image,
+                sampler.clone(),
+                color_space,
+            );
+            textures.insert((index, color_space), texture);
+        }
     }
     textures
 }
 
 fn load_material<'a>(
     mat: gltf::Material<'a>,
-    textures: &[Texture<[f32; 4]>],
+    textures: &Textures,
 ) -> Material {
     let pbr = mat.pbr_metallic_roughness();
     let mut is_basic_material = true;
     let base_color_map = pbr.base_color_texture()
-        .map(|t| textures[t.as_ref().index()].clone());
+        .map(|t| textures[&(t.as_ref().index(), ColorSpace::Srgb)].clone());
     let normal_map = mat.normal_texture().map(|t| {
         is_basic_material = false;
-        textures[t.as_ref().index()].clone()
+        textures[&(t.as_ref().index(), ColorSpace::Linear)].clone()
     });
     let emissive_map = mat.emissive_texture().map(|t| {
         is_basic_material = false;
-        textures[t.as_ref().index()].clone()
+        textures[&(t.as_ref().index(), ColorSpace::Srgb)].clone()
     });
     let metallic_roughness_map = pbr.metallic_roughness_texture().map(|t| {
         is_basic_material = false;
-        textures[t.as_ref().index()].clone()
+        textures[&(t.as_ref().index(), ColorSpace::Linear)].clone()
     });
     let occlusion_map = mat.occlusion_texture().map(|t| {
         is_basic_material = false;
-        textures[t.as_ref().index()].clone()
+        textures[&(t.as_ref().index(), ColorSpace::Linear)].clone()
     });
     let (base_color_factor, base_color_alpha) = {
         let x = pbr.base_color_factor();
@@ -144,6 +178,7 @@ fn load_material<'a>(
         material::Basic {
             color: base_color_factor,
             map: base_color_map,
+            .. Default::default()
         }.into()
     } else {
         material::Pbr {
@@ -155,6 +190,8 @@ fn load_material<'a>(
                 t.strength()
             }),
             emissive_factor: color::from_linear_rgb(mat.emissive_factor()),
+            emissive_intensity: 1.0,
+            bloom_intensity: 0.0,
             normal_scale: mat.normal_texture().map_or(1.0, |t| {
                 t.scale()
             }),
@@ -171,7 +208,7 @@ fn load_primitive<'a>(
     factory: &mut Factory,
     primitive: gltf::Primitive<'a>,
     buffers: &[gltf::buffer::Data],
-    textures: &[Texture<[f32; 4]>],
+    textures: &Textures,
 ) -> (InstancedGeometry, Material) {
     use itertools::Itertools;
 
@@ -201,6 +238,16 @@ fn load_primitive<'a>(
     } else {
         Vec::new()
     };
+    let tex_coords1 = if let Some(iter) = reader.read_tex_coords(1) {
+        iter.into_f32().map(|x| x.into()).collect()
+    } else {
+        Vec::new()
+    };
+    let colors = if let Some(iter) = reader.read_colors(0) {
+        iter.into_rgba_f32().map(|x| x.into()).collect()
+    } else {
+        Vec::new()
+    };
     let joint_indices = if let Some(iter) = reader.read_joints(0) {
         iter.into_u16()
             .map(|x| [x[0] as i32, x[1] as i32, x[2] as i32, x[3] as i32])
@@ -238,6 +285,8 @@ fn load_primitive<'a>(
             tangents,
         },
         tex_coords,
+        tex_coords1,
+        colors,
         faces,
         shapes,
         joints: geometry::Joints {
@@ -319,6 +368,7 @@ fn load_animation<'a>(
     animation: gltf::Animation<'a>,
     buffers: &[gltf::buffer::Data],
     groups: &[usize],
+    compression: Option<animation::Compression>,
 ) -> AnimationTemplate {
     use gltf::animation::Interpolation::*;
 
@@ -380,12 +430,16 @@ fn load_animation<'a>(
                 (Binding::Weights, Values::Scalar(values))
             }
         };
+        let track = Track {
+            binding,
+            interpolation,
+            times,
+            values,
+        };
         tracks.push((
-            Track {
-                binding,
-                interpolation,
-                times,
-                values,
+            match compression {
+                Some(ref compression) => track.compress(compression),
+                None => track,
             },
 
             // Target the object for the group that corresponds to the target node.
@@ -424,6 +478,7 @@ fn load_node<'a>(
     objects: &mut Vec<ObjectTemplate>,
     meshes: &mut Vec<MeshTemplate>,
     cameras: &mut Vec<CameraTemplate>,
+    lights: &mut Vec<LightTemplate>,
     mesh_map: &HashMap<usize, Vec<usize>>,
     primitives: &[(InstancedGeometry, Material)],
 ) -> usize {
@@ -487,9 +542,39 @@ fn load_node<'a>(
         });
     }
 
+    // Create a light node as a child if there's a punctual light associated with this glTF node.
+    if let Some(light) = node.light() {
+        let object = objects.len();
+        objects.push(ObjectTemplate {
+            parent: Some(node.index()),
+            .. Default::default()
+        });
+        lights.push(load_light(object, light));
+    }
+
     object_index
 }
 
+/// Converts a `KHR_lights_punctual` light. The intensity is used unchanged as the
+/// unitless intensity of three lights, and spot lights become point lights.
+fn load_light<'a>(
+    object: usize,
+    light: gltf::khr_lights_punctual::Light<'a>,
+) -> LightTemplate {
+    use gltf::khr_lights_punctual::Kind;
+
+    let color = color::from_linear_rgb(light.color());
+    let intensity = light.intensity();
+    match light.kind() {
+        Kind::Directional => LightTemplate::directional(object, color, intensity),
+        Kind::Point => LightTemplate::point(object, color, intensity),
+        Kind::Spot { .. } => {
+            warn!("Spot lights are not supported, loading {:?} as a point light", light.name());
+            LightTemplate::point(object, color, intensity)
+        }
+    }
+}
+
 fn load_camera<'a>(
     entry: gltf::Camera<'a>,
 ) -> Projection {
@@ -498,17 +583,17 @@ fn load_camera<'a>(
             let center = mint::Point2::<f32>::from([0.0, 0.0]);
             let extent_y = values.ymag();
             let range = values.znear() .. values.zfar();
-            Projection::Orthographic(Orthographic { center, extent_y, range })
+            Projection::orthographic(center, extent_y, range)
         }
 
         gltf::camera::Projection::Perspective(values) => {
             let fov_y = values.yfov().to_degrees();
             let near = values.znear();
-            let zrange = match values.zfar() {
+            let zrange: ZRange = match values.zfar() {
                 Some(far) => (near .. far).into(),
                 None => (near ..).into(),
             };
-            Projection::Perspective(Perspective { fov_y, zrange })
+            Projection::perspective(fov_y, zrange)
         }
     }
 }
@@ -535,6 +620,11 @@ impl super::Factory {
     /// Each scene in the glTF file results in a separate [`Template`]. Any animations that
     /// reference nodes in a scene will be included in that scene's [`Template`].
     ///
+    /// Lights of the `KHR_lights_punctual` extension are loaded into [`Template::lights`]. Their
+    /// intensity is used as is, so files exported with physical light units usually need it
+    /// scaled down. Spot lights are loaded as point lights, and shadows can be enabled by setting
+    /// [`LightTemplate::shadow`] before instantiating the template.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -557,6 +647,8 @@ impl super::Factory {
     ///
     /// [`template`]: ./template/index.html
     /// [`Template`]: ./template/struct.Template.html
+    /// [`Template::lights`]: ./template/struct.Template.html#structfield.lights
+    /// [`LightTemplate::shadow`]: ./template/struct.LightTemplate.html#structfield.shadow
     /// [`Factory::instantiate_template`]: #method.instantiate_template
     pub fn load_gltf(
         &mut self,
@@ -604,12 +696,13 @@ impl super::Factory {
         let mut objects = Vec::with_capacity(gltf.nodes().len());
         let mut meshes = Vec::new();
         let mut cameras = Vec::new();
+        let mut lights = Vec::new();
 
         // Create template nodes from each of the glTF nodes.
         let groups: Vec<_> = gltf
             .nodes()
             .map(|node| {
-                load_node(node, &mut objects, &mut meshes, &mut cameras, &mesh_map, &primitives)
+                load_node(node, &mut objects, &mut meshes, &mut cameras, &mut lights, &mesh_map, &primitives)
             })
             .collect();
 
@@ -636,7 +729,7 @@ impl super::Factory {
         // Create an animation template from any animations in the glTF file.
         let animations = gltf
             .animations()
-            .map(|anim| load_animation(anim, &buffers, &groups))
+            .map(|anim| load_animation(anim, &buffers, &groups, self.animation_compression))
             .collect();
 
         let raw_template = Template {
@@ -645,7 +738,7 @@ impl super::Factory {
             groups,
             cameras,
             meshes,
-            lights: Vec::new(),
+            lights,
             bones,
             skeletons,
             animations,
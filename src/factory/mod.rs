@@ -3,12 +3,12 @@ mod load_gltf;
 
 use std::{cmp, fs, io, iter, ops};
 use std::borrow::Cow;
-use std::collections::HashSet;
 use std::collections::hash_map::{Entry, HashMap};
 use std::io::Read;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use cgmath::{Vector3};
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
 use gfx;
 use gfx::format::I8Norm;
 use gfx::traits::{Factory as Factory_, FactoryExt};
@@ -22,20 +22,26 @@ use obj;
 use audio;
 
 use animation;
+use bitmap_font::{self, BitmapFont};
 use camera::{Camera, Projection, ZRange};
-use color::{BLACK, Color};
-use geometry::Geometry;
+use color::{BLACK, WHITE, Color};
+use geometry::{Geometry, Shape};
 use hub::{Hub, HubPtr, LightData, SubLight, SubNode};
-use light::{Ambient, Directional, Hemisphere, Point, ShadowMap};
+use jobs::JobPool;
+use light::{Ambient, Directional, Hemisphere, LightProbe, Point, ReflectionProbe, ShadowMap};
 use material::{self, Material};
-use mesh::{DynamicMesh, Mesh};
+use mesh::{DynamicMesh, InstancedMesh, Mesh};
 use object::{self, Group, Object};
-use render::{basic_pipe,
-    BackendFactory, BackendResources, BasicPipelineState, DisplacementContribution,
-    DynamicData, GpuData, Instance, InstanceCacheKey, PipelineCreationError, ShadowFormat, Source, Vertex,
+use particles;
+use render::{background_pipeline, basic_pipe, color_output, diagnostics, post_pipeline,
+    BackendFactory, BackendResources, BasicPipelineState, DataBuffer, DataCopy, DisplacementContribution,
+    DynamicData, GpuData, Instance, InstanceCacheKey, InstanceList, PipelineCreationError, PostPipelineState, RenderTarget,
+    ShadowFormat, Source, Vertex, VertexAttributes,
     DEFAULT_VERTEX, VECS_PER_BONE, ZEROED_DISPLACEMENT_CONTRIBUTION,
 };
-use scene::{Background, Scene};
+use render::composer::ShaderPass;
+use raycast::Triangles;
+use scene::{Background, BackgroundShader, Scene};
 use sprite::Sprite;
 use skeleton::{Bone, InverseBindMatrix, Skeleton};
 use template::{
@@ -45,7 +51,9 @@ use template::{
     Template,
 };
 use text::{Font, Text, TextData};
-use texture::{CubeMap, CubeMapPath, FilterMethod, Sampler, Texture, WrapMode};
+use texture::{ColorSpace, CubeMap, CubeMapPath, FilterMethod, ImageDecoder, Sampler, StandardImageDecoder, Texture, WrapMode};
+use trail;
+use util;
 
 const TANGENT_X: [I8Norm; 4] = [I8Norm(1), I8Norm(0), I8Norm(0), I8Norm(1)];
 const NORMAL_Z: [I8Norm; 4] = [I8Norm(0), I8Norm(0), I8Norm(1), I8Norm(0)];
@@ -81,8 +89,45 @@ pub struct Factory {
     pub(crate) backend: BackendFactory,
     hub: HubPtr,
     quad_buf: gfx::handle::Buffer<BackendResources, Vertex>,
-    texture_cache: HashMap<PathBuf, Texture<[f32; 4]>>,
+    texture_cache: HashMap<(PathBuf, ColorSpace), Texture<[f32; 4]>>,
     default_sampler: gfx::handle::Sampler<BackendResources>,
+    shader_include_paths: Vec<PathBuf>,
+    jobs: JobPool,
+    image_decoders: Vec<Arc<dyn ImageDecoder>>,
+    animation_compression: Option<animation::Compression>,
+    raycast_triangles: bool,
+}
+
+/// Line height in pixels the glyphs of text in the scene are rasterized at.
+const TEXT_3D_RESOLUTION: f32 = 64.0;
+
+/// A `width` by `height` quad with texture coordinates, centered on the
+/// origin of the XY plane and facing the positive Z axis.
+fn textured_quad(
+    width: f32,
+    height: f32,
+) -> Geometry {
+    let (x, y) = (0.5 * width, 0.5 * height);
+    Geometry {
+        base: Shape {
+            vertices: vec![
+                [-x, -y, 0.0].into(),
+                [x, -y, 0.0].into(),
+                [x, y, 0.0].into(),
+                [-x, y, 0.0].into(),
+            ],
+            normals: vec![[0.0, 0.0, 1.0].into(); 4],
+            .. Shape::default()
+        },
+        tex_coords: vec![
+            [0.0, 0.0].into(),
+            [1.0, 0.0].into(),
+            [1.0, 1.0].into(),
+            [0.0, 1.0].into(),
+        ],
+        faces: vec![[0, 1, 2], [0, 2, 3]],
+        .. Geometry::default()
+    }
 }
 
 fn f2i(x: f32) -> I8Norm {
@@ -104,6 +149,11 @@ impl Factory {
 
     fn create_gpu_data(&mut self, geometry: Geometry) -> GpuData {
         let vertices = Self::mesh_vertices(&geometry);
+        let triangles = Triangles::new(
+            geometry.base.vertices.iter().map(|&p| Point3::from(p).to_vec()).collect(),
+            geometry.faces.clone(),
+            self.raycast_triangles,
+        );
         let (vbuf, mut slice) = if geometry.faces.is_empty() {
             self.backend.create_vertex_buffer_with_slice(&vertices, ())
         } else {
@@ -165,6 +215,10 @@ impl Factory {
             pending: None,
             instance_cache_key: None,
             displacement_contributions,
+            triangles: Some(triangles),
+            line_quads: false,
+            attributes: VertexAttributes::of(&geometry),
+            instance_list: None,
         }
     }
 
@@ -177,6 +231,11 @@ impl Factory {
             quad_buf,
             texture_cache: HashMap::new(),
             default_sampler: default_sampler,
+            shader_include_paths: Vec::new(),
+            jobs: JobPool::default(),
+            image_decoders: vec![Arc::new(StandardImageDecoder)],
+            animation_compression: None,
+            raycast_triangles: false,
         }
     }
 
@@ -187,14 +246,20 @@ impl Factory {
         Scene {
             hub,
             first_child: None,
+            last_child: None,
             background,
+            fog: None,
+            light_probe: None,
+            reflection_probe: None,
+            parallax: Vec::new(),
         }
     }
 
     /// Creates an instance of all the objects described in the template.
     ///
     /// Returns a [`Group`] that is the root object for all objects created from the template, as
-    /// well as a list of all animation clips instantiated from the template.
+    /// well as a list of all animation clips instantiated from the template. The clips can be
+    /// wrapped in an [`animation::ClipSet`] to look them up by name or by the objects they animate.
     ///
     /// See the module documentation for [`template`] for more information on the template
     /// system.
@@ -213,6 +278,7 @@ impl Factory {
     /// ```
     ///
     /// [`Group`]: ./struct.Group.html
+    /// [`animation::ClipSet`]: ./animation/struct.ClipSet.html
     /// [`template`]: ./template/index.html
     /// [`Factory::group`]: #method.group
     pub fn instantiate_template(&mut self, template: &Template) -> (Group, Vec<animation::Clip>) {
@@ -242,16 +308,6 @@ impl Factory {
             })
             .collect();
 
-        // HACK: Keep track of which base objects belong to a `Skeleton` so that we can defer
-        // adding them to their parent `Group` until after all other objects have been setup.
-        // This is to work around https://github.com/three-rs/three/issues/202, which causes
-        // a crash if the renderer walks to a `Bone` before walking to its `Skeleton`. Groups
-        // internally list their children in reverse the order in which they were added, so
-        // adding the `Skeletons` last ensure they're listed earlier than any of their bones and
-        // will be visited first by the renderer. Note that this only solves the case where a
-        // skeleton and its bones are direct siblings, it will not help the case where a skeleton
-        // is lower in the hierarchy than its bones.
-        let mut skeleton_objects = HashSet::with_capacity(template.skeletons.len());
         let skeletons: Vec<_> = template
             .skeletons
             .iter()
@@ -271,7 +327,6 @@ impl Factory {
                     .collect();
                 let skeleton = self.skeleton(bones);
                 objects.insert(object, skeleton.upcast());
-                skeleton_objects.insert(object);
                 skeleton
             })
             .collect();
@@ -295,16 +350,28 @@ impl Factory {
         }
 
         for &template in &template.lights {
-            let LightTemplate { object, color, intensity, sub_light } = template;
+            let LightTemplate { object, color, intensity, sub_light, shadow } = template;
             let light = match sub_light {
                 SubLightTemplate::Ambient =>
                     self.ambient_light(color, intensity).upcast(),
-                SubLightTemplate::Directional =>
-                    self.directional_light(color, intensity).upcast(),
+                SubLightTemplate::Directional => {
+                    let mut light = self.directional_light(color, intensity);
+                    if let Some(shadow) = shadow {
+                        let map = self.shadow_map(shadow.map_size, shadow.map_size);
+                        light.set_shadow(map, shadow.extent_y, shadow.near .. shadow.far);
+                    }
+                    light.upcast()
+                }
                 SubLightTemplate::Hemisphere { ground } =>
                     self.hemisphere_light(color, ground, intensity).upcast(),
-                SubLightTemplate::Point =>
-                    self.point_light(color, intensity).upcast(),
+                SubLightTemplate::Point => {
+                    let mut light = self.point_light(color, intensity);
+                    if let Some(shadow) = shadow {
+                        let map = self.shadow_cube_map(shadow.map_size);
+                        light.set_shadow(map, shadow.near .. shadow.far);
+                    }
+                    light.upcast()
+                }
             };
             objects.insert(object, light.clone());
         }
@@ -323,21 +390,6 @@ impl Factory {
                 base.set_name(name);
             }
 
-            // HACK: We need to add any `Skeleton` objects to their parent group *last*, so
-            // we skip them. See note above for more details.
-            if skeleton_objects.contains(&index) { continue; }
-
-            match template.parent {
-                Some(parent) => groups[parent].add(base),
-                None => root.add(base),
-            }
-        }
-
-        // HACK: Add `Skeleton` objects to their parent group after all other objects have been
-        // added to their parent groups. See note above for more details.
-        for index in skeleton_objects {
-            let base = &objects[&index];
-            let template = &template.objects[index];
             match template.parent {
                 Some(parent) => groups[parent].add(base),
                 None => root.add(base),
@@ -356,7 +408,7 @@ impl Factory {
                     .collect();
 
                 animation::Clip {
-                    name: template.name.clone(),
+                    name: animation.name.clone(),
                     tracks,
                 }
             })
@@ -489,6 +541,16 @@ impl Factory {
         } else {
             Either::Right(geometry.tex_coords.iter().map(|uv| [uv.x, uv.y]))
         };
+        let uv1_iter = if geometry.tex_coords1.is_empty() {
+            Either::Left(iter::repeat([0.0, 0.0]))
+        } else {
+            Either::Right(geometry.tex_coords1.iter().map(|uv| [uv.x, uv.y]))
+        };
+        let color_iter = if geometry.colors.is_empty() {
+            Either::Left(iter::repeat([1.0, 1.0, 1.0, 1.0]))
+        } else {
+            Either::Right(geometry.colors.iter().map(|c| [c.x, c.y, c.z, c.w]))
+        };
         let tangent_iter = if geometry.base.tangents.is_empty() {
             // TODO: Generate tangents if texture coordinates are provided.
             // (Use mikktspace algorithm or otherwise.)
@@ -516,15 +578,19 @@ impl Factory {
             normal_iter,
             tangent_iter,
             uv_iter,
+            uv1_iter,
+            color_iter,
             joint_indices_iter,
             joint_weights_iter,
         )
-            .map(|(pos, normal, tangent, uv, joint_indices, joint_weights)| {
+            .map(|(pos, normal, tangent, uv, uv1, color, joint_indices, joint_weights)| {
                 Vertex {
                     pos: [pos.x, pos.y, pos.z, 1.0],
                     normal,
                     uv,
+                    uv1,
                     tangent,
+                    color,
                     joint_indices,
                     joint_weights,
                 }
@@ -532,6 +598,73 @@ impl Factory {
             .collect()
     }
 
+    /// Expands each segment of the line strip in `geometry` into a quad of
+    /// two triangles, whose corners are pushed apart on the screen by the
+    /// line shader.
+    ///
+    /// Every vertex holds its end of the segment in `pos`, the other end in
+    /// the XYZ of `joint_weights` and the side of the quad in W, which flips
+    /// along with the direction towards the other end. The distance along
+    /// the strip goes into `uv`, for dashing.
+    fn line_vertices(geometry: &Geometry) -> Vec<Vertex> {
+        let points: Vec<Vector3<f32>> = if geometry.faces.is_empty() {
+            geometry.base.vertices.iter().map(|&p| Point3::from(p).to_vec()).collect()
+        } else {
+            geometry.faces
+                .iter()
+                .flat_map(|face| face.iter())
+                .map(|&i| Point3::from(geometry.base.vertices[i as usize]).to_vec())
+                .collect()
+        };
+        let mut vertices = Vec::with_capacity(6 * points.len().saturating_sub(1));
+        let mut distance = 0.0;
+        for segment in points.windows(2) {
+            let (a, b) = (segment[0], segment[1]);
+            let length = (b - a).magnitude();
+            let corner = |this: Vector3<f32>, other: Vector3<f32>, distance: f32, side: f32| Vertex {
+                pos: [this.x, this.y, this.z, 1.0],
+                uv: [distance, 0.0],
+                uv1: [0.0, 0.0],
+                normal: NORMAL_Z,
+                tangent: TANGENT_X,
+                color: [1.0, 1.0, 1.0, 1.0],
+                joint_indices: [0; 4],
+                joint_weights: [other.x, other.y, other.z, side],
+            };
+            let a_left = corner(a, b, distance, 1.0);
+            let a_right = corner(a, b, distance, -1.0);
+            let b_left = corner(b, a, distance + length, -1.0);
+            let b_right = corner(b, a, distance + length, 1.0);
+            vertices.extend_from_slice(&[a_left, a_right, b_left, a_right, b_right, b_left]);
+            distance += length;
+        }
+        vertices
+    }
+
+    fn create_line_gpu_data(&mut self, geometry: &Geometry) -> GpuData {
+        let vertices = Self::line_vertices(geometry);
+        let triangles = Triangles::new(
+            vertices.iter().map(|v| Vector3::new(v.pos[0], v.pos[1], v.pos[2])).collect(),
+            Vec::new(),
+            false,
+        );
+        let (vbuf, mut slice) = self.backend.create_vertex_buffer_with_slice(&vertices, ());
+        slice.instances = Some((1, 0));
+        GpuData {
+            slice,
+            vertices: vbuf,
+            instances: self.create_instance_buffer(),
+            displacements: None,
+            pending: None,
+            instance_cache_key: None,
+            displacement_contributions: ZEROED_DISPLACEMENT_CONTRIBUTION.to_vec(),
+            triangles: Some(triangles),
+            line_quads: true,
+            attributes: VertexAttributes::GENERATED,
+            instance_list: None,
+        }
+    }
+
     /// Uploads geometry data to the GPU so that it can be reused for instanced rendering.
     ///
     /// See the module documentation in [`template`] for information on mesh instancing and
@@ -558,6 +691,7 @@ impl Factory {
     /// let material = three::material::Basic {
     ///     color: 0xFFFF00,
     ///     map: None,
+    ///     .. Default::default()
     /// };
     /// let first = window.factory.create_instanced_mesh(&upload_geometry, material.clone());
     /// let second = window.factory.create_instanced_mesh(&upload_geometry, material.clone());
@@ -579,11 +713,15 @@ impl Factory {
         geometry: Geometry,
         material: M,
     ) -> Mesh {
-        let gpu_data = self.create_gpu_data(geometry);
+        let material = material.into();
+        let gpu_data = match material {
+            Material::Line(_) => self.create_line_gpu_data(&geometry),
+            _ => self.create_gpu_data(geometry),
+        };
 
         Mesh {
             object: self.hub.lock().unwrap().spawn_visual(
-                material.into(),
+                material,
                 gpu_data,
                 None,
             ),
@@ -616,6 +754,7 @@ impl Factory {
     /// let material = three::material::Basic {
     ///     color: 0xFFFF00,
     ///     map: None,
+    ///     .. Default::default()
     /// };
     /// let first = window.factory.create_instanced_mesh(&upload_geometry, material.clone());
     /// let second = window.factory.create_instanced_mesh(&upload_geometry, material.clone());
@@ -648,7 +787,136 @@ impl Factory {
         }
     }
 
+    /// Create an [`InstancedMesh`] drawing up to `capacity` copies of
+    /// `geometry` in a single draw call, each with its own transform and
+    /// optionally its own color. It draws nothing until its instances are set.
+    ///
+    /// Unlike meshes created with [`create_instanced_mesh`], the instances
+    /// are not separate objects, which scales to many thousands of them.
+    /// They are culled together, by the bounds of all the instances.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::Object;
+    ///
+    /// let trunk = three::Geometry::cylinder(0.1, 0.2, 2.0, 6);
+    /// let material = three::material::Lambert { color: 0x8B5A2B, .. Default::default() };
+    /// let forest = window.factory.instanced_mesh(trunk, material, 10_000);
+    ///
+    /// let transforms = (0 .. 10_000)
+    ///     .map(|i| {
+    ///         let (x, z) = ((i % 100) as f32 * 3.0, (i / 100) as f32 * 3.0);
+    ///         [
+    ///             [1.0, 0.0, 0.0, 0.0],
+    ///             [0.0, 1.0, 0.0, 0.0],
+    ///             [0.0, 0.0, 1.0, 0.0],
+    ///             [x, 1.0, z, 1.0],
+    ///         ].into()
+    ///     })
+    ///     .collect::<Vec<_>>();
+    /// forest.set_instances(&transforms);
+    /// window.scene.add(&forest);
+    /// ```
+    ///
+    /// [`InstancedMesh`]: struct.InstancedMesh.html
+    /// [`create_instanced_mesh`]: #method.create_instanced_mesh
+    pub fn instanced_mesh<M: Into<Material>>(
+        &mut self,
+        geometry: Geometry,
+        material: M,
+        capacity: usize,
+    ) -> InstancedMesh {
+        let material = material.into();
+        let mut gpu_data = match material {
+            Material::Line(_) => self.create_line_gpu_data(&geometry),
+            _ => self.create_gpu_data(geometry),
+        };
+        let capacity = capacity.max(1);
+        gpu_data.instances = self.backend
+            .create_buffer(
+                capacity,
+                gfx::buffer::Role::Vertex,
+                gfx::memory::Usage::Dynamic,
+                gfx::memory::Bind::TRANSFER_DST,
+            )
+            .unwrap();
+        // the triangles of a single copy are placed at each instance
+        gpu_data.instance_list = Some(InstanceList::default());
+
+        InstancedMesh {
+            object: self.hub.lock().unwrap().spawn_visual(
+                material,
+                gpu_data,
+                None,
+            ),
+            capacity,
+        }
+    }
+
+    /// Create a particle [`Emitter`] drawing up to `capacity` particles as
+    /// unit quads of `material` facing the camera. The quads have texture
+    /// coordinates, and the colors of the particles replace the material
+    /// color. See the [`particles`] module for an example.
+    ///
+    /// [`Emitter`]: particles/struct.Emitter.html
+    /// [`particles`]: particles/index.html
+    pub fn emitter<M: Into<Material>>(
+        &mut self,
+        material: M,
+        capacity: usize,
+        settings: particles::Settings,
+    ) -> particles::Emitter {
+        let mesh = self.instanced_mesh(textured_quad(1.0, 1.0), material, capacity);
+        mesh.set_billboard(true);
+        particles::Emitter::new(mesh, settings)
+    }
+
+    /// Create a [`Trail`] following `target`, drawn in `color` through up to
+    /// `capacity` of its positions, at least two. See the [`trail`] module
+    /// for an example.
+    ///
+    /// [`Trail`]: trail/struct.Trail.html
+    /// [`trail`]: trail/index.html
+    pub fn trail<T: Object>(
+        &mut self,
+        target: &T,
+        color: Color,
+        capacity: usize,
+        settings: trail::Settings,
+    ) -> trail::Trail {
+        let capacity = capacity.max(2);
+        let mut geometry = Geometry::with_vertices(vec![[0.0, 0.0, 0.0].into(); 2 * capacity]);
+        geometry.tex_coords = vec![[0.0, 0.0].into(); 2 * capacity];
+        geometry.faces = (0 .. capacity as u32 - 1)
+            .flat_map(|i| vec![[2 * i, 2 * i + 1, 2 * i + 2], [2 * i + 1, 2 * i + 3, 2 * i + 2]])
+            .collect();
+        // the opacity over the age of the positions, along the ribbon
+        let pixels = (0 .. trail::OPACITY_TEXELS)
+            .flat_map(|i| {
+                let time = i as f32 / (trail::OPACITY_TEXELS - 1) as f32;
+                let alpha = settings.opacity.sample(time).max(0.0).min(1.0);
+                vec![0xFF, 0xFF, 0xFF, (alpha * 255.0).round() as u8]
+            })
+            .collect::<Vec<_>>();
+        let sampler = self.sampler(FilterMethod::Bilinear, WrapMode::Clamp, WrapMode::Clamp);
+        let map = self.load_texture_from_memory(trail::OPACITY_TEXELS as u16, 1, &pixels, sampler);
+        let material = material::Basic {
+            color,
+            map: Some(map),
+            transparency: material::Transparency::Blend,
+            depth_write: false,
+            .. Default::default()
+        };
+        let mesh = self.mesh_dynamic(geometry, material);
+        trail::Trail::new(mesh, target.as_ref().clone(), settings)
+    }
+
     /// Create a new `DynamicMesh` with desired `Geometry` and `Material`.
+    ///
+    /// Its vertices are only updated on the GPU, so it has no bounds for
+    /// culling and is skipped by the [`raycast`](../raycast/index.html) module.
     pub fn mesh_dynamic<M: Into<Material>>(
         &mut self,
         geometry: Geometry,
@@ -692,6 +960,10 @@ impl Factory {
                     pending: None,
                     instance_cache_key: None,
                     displacement_contributions: ZEROED_DISPLACEMENT_CONTRIBUTION.to_vec(),
+                    triangles: None,
+                    line_quads: false,
+                    attributes: VertexAttributes::of(&geometry),
+                    instance_list: None,
                 },
                 None,
             ),
@@ -775,6 +1047,10 @@ impl Factory {
                 pending: None,
                 instance_cache_key: None,
                 displacement_contributions: ZEROED_DISPLACEMENT_CONTRIBUTION.to_vec(),
+                triangles: None,
+                line_quads: false,
+                attributes: VertexAttributes::GENERATED,
+                instance_list: None,
             },
             None,
         ))
@@ -815,6 +1091,7 @@ impl Factory {
             intensity,
             sub_light: SubLight::Ambient,
             shadow: None,
+            shadow_distance: f32::INFINITY,
         }))
     }
 
@@ -829,6 +1106,7 @@ impl Factory {
             intensity,
             sub_light: SubLight::Directional,
             shadow: None,
+            shadow_distance: f32::INFINITY,
         }))
     }
 
@@ -846,6 +1124,7 @@ impl Factory {
                 ground: ground_color,
             },
             shadow: None,
+            shadow_distance: f32::INFINITY,
         }))
     }
 
@@ -860,6 +1139,7 @@ impl Factory {
             intensity,
             sub_light: SubLight::Point,
             shadow: None,
+            shadow_distance: f32::INFINITY,
         }))
     }
 
@@ -891,7 +1171,7 @@ impl Factory {
         Sampler(inner)
     }
 
-    /// Create new `ShadowMap`.
+    /// Create new `ShadowMap` for a directional light.
     pub fn shadow_map(
         &mut self,
         width: u16,
@@ -900,10 +1180,319 @@ impl Factory {
         let (_, resource, target) = self.backend
             .create_depth_stencil::<ShadowFormat>(width, height)
             .unwrap();
-        ShadowMap { resource, target }
+        ShadowMap {
+            resource,
+            targets: vec![target],
+        }
+    }
+
+    /// Create new cube `ShadowMap` for a point light, with faces of `size`
+    /// by `size` pixels.
+    pub fn shadow_cube_map(
+        &mut self,
+        size: u16,
+    ) -> ShadowMap {
+        use gfx::format::{ChannelTyped, Formatted, Swizzle};
+        use gfx::memory::{Bind, Usage};
+        use gfx::texture as t;
+        let channel = <<ShadowFormat as Formatted>::Channel as ChannelTyped>::get_channel_type();
+        let texture = self.backend
+            .create_texture(
+                t::Kind::Cube(size),
+                1,
+                Bind::SHADER_RESOURCE | Bind::DEPTH_STENCIL,
+                Usage::Data,
+                Some(channel),
+            )
+            .unwrap();
+        let resource = self.backend
+            .view_texture_as_shader_resource::<ShadowFormat>(&texture, (0, 0), Swizzle::new())
+            .unwrap();
+        let targets = (0 .. 6)
+            .map(|face| {
+                self.backend
+                    .view_texture_as_depth_stencil::<ShadowFormat>(&texture, 0, Some(face), t::DepthStencilFlags::empty())
+                    .unwrap()
+            })
+            .collect();
+        ShadowMap { resource, targets }
+    }
+
+    /// Create a texture reading the depth stored in a flat [`ShadowMap`],
+    /// from 0 at the near plane to 1 at the far one, in its red channel.
+    ///
+    /// Useful to read depth drawn by [`Renderer::render_depth`] in a
+    /// [`ShaderPass`] or a custom material.
+    ///
+    /// # Panics
+    /// Panics if `map` is a cube shadow map.
+    ///
+    /// [`ShadowMap`]: ../light/struct.ShadowMap.html
+    /// [`Renderer::render_depth`]: ../render/struct.Renderer.html#method.render_depth
+    /// [`ShaderPass`]: ../render/composer/struct.ShaderPass.html
+    pub fn depth_texture(
+        &mut self,
+        map: &ShadowMap,
+    ) -> Texture<[f32; 4]> {
+        use gfx::memory::Typed;
+        assert!(!map.is_cube(), "Cube shadow maps can't be read as textures");
+        let (width, height, _, _) = map.to_targets()[0].get_dimensions();
+        let view = Typed::new(map.to_resource().raw().clone());
+        Texture::new(view, self.default_sampler.clone(), [width as u32, height as u32])
+    }
+
+    /// Create a new [`RenderTarget`] of the given size in pixels.
+    ///
+    /// Scenes are drawn into it with [`Renderer::render_to`], after which
+    /// its texture can be shown like any other, for example on a security
+    /// camera screen:
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// use three::Object;
+    ///
+    /// let target = window.factory.render_target(256, 256);
+    /// let security_camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// security_camera.look_at([5.0, 3.0, 5.0], [0.0, 0.0, 0.0], None);
+    /// window.scene.add(&security_camera);
+    ///
+    /// let screen = window.factory.mesh(
+    ///     three::Geometry::plane(1.6, 1.2),
+    ///     three::material::Basic {
+    ///         map: Some(target.texture().clone()),
+    ///         .. Default::default()
+    ///     },
+    /// );
+    /// window.scene.add(&screen);
+    ///
+    /// while window.update() {
+    ///     window.renderer.render_to(&window.scene, &security_camera, &target);
+    ///     window.render(&camera);
+    /// }
+    /// ```
+    ///
+    /// [`RenderTarget`]: ../render/struct.RenderTarget.html
+    /// [`Renderer::render_to`]: ../render/struct.Renderer.html#method.render_to
+    pub fn render_target(
+        &mut self,
+        width: u16,
+        height: u16,
+    ) -> RenderTarget {
+        RenderTarget::new(&mut self.backend, self.default_sampler.clone(), width, height)
+    }
+
+    /// Create a [`DataBuffer`] holding `data`, for custom shaders to read
+    /// through the `data` of a [`Custom`] material.
+    ///
+    /// A crowd can play vertex animations baked into a buffer, each mesh
+    /// starting at its own frame passed in the material params:
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let frames: Vec<[f32; 4]> = Vec::new();
+    /// # let pipeline: three::render::BasicPipelineState = unimplemented!();
+    /// let data = window.factory.data_buffer(&frames);
+    /// let material = three::material::basic::Custom {
+    ///     color: three::color::WHITE,
+    ///     map: None,
+    ///     pipeline,
+    ///     stencil_reference: 0,
+    ///     params: [0.0; 4],
+    ///     param_names: ["frame", "", "", ""],
+    ///     data: Some(data),
+    ///     user_params: None,
+    /// };
+    /// ```
+    ///
+    /// The vertex shader then reads the position of its vertex:
+    ///
+    /// ```glsl
+    /// uniform samplerBuffer b_UserData;
+    ///
+    /// vec4 position = texelFetch(b_UserData, int(i_MatParams.x) * VERTEX_COUNT + gl_VertexID);
+    /// ```
+    ///
+    /// [`DataBuffer`]: render/struct.DataBuffer.html
+    /// [`Custom`]: material/basic/struct.Custom.html
+    pub fn data_buffer(
+        &mut self,
+        data: &[[f32; 4]],
+    ) -> DataBuffer {
+        let buffer = self.backend
+            .create_buffer_immutable(
+                data,
+                gfx::buffer::Role::Constant,
+                gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::TRANSFER_DST,
+            )
+            .expect("create GPU data buffer");
+        let view = self.backend
+            .view_buffer_as_shader_resource(&buffer)
+            .expect("create shader resource view for GPU data buffer");
+        let upload = self.backend.create_upload_buffer(data.len()).unwrap();
+        self.backend
+            .write_mapping(&upload)
+            .unwrap()
+            .copy_from_slice(data);
+        DataBuffer { buffer, view, upload }
+    }
+
+    /// Overwrites the values of `buffer` from `offset` on with `data`. The
+    /// new values are uploaded at the next render.
+    ///
+    /// # Panics
+    /// Panics if `data` goes past the end of the buffer.
+    pub fn update_data_buffer(
+        &mut self,
+        buffer: &DataBuffer,
+        offset: usize,
+        data: &[[f32; 4]],
+    ) {
+        assert!(offset + data.len() <= buffer.len(), "Data goes past the end of the buffer");
+        {
+            let mut mapping = self.backend.write_mapping(&buffer.upload).unwrap();
+            mapping[offset .. offset + data.len()].copy_from_slice(data);
+        }
+        self.hub.lock().unwrap().data_copies.push(DataCopy {
+            buffer: buffer.clone(),
+            offset,
+            count: data.len(),
+        });
+    }
+
+    /// The pool of worker threads the factory decodes assets on, which
+    /// applications can share for their own jobs. See [`jobs`].
+    ///
+    /// [`jobs`]: jobs/index.html
+    pub fn job_pool(&self) -> &JobPool {
+        &self.jobs
+    }
+
+    /// Replaces the pool of worker threads, for example with one shared by
+    /// the rest of the application.
+    pub fn set_job_pool(
+        &mut self,
+        pool: JobPool,
+    ) {
+        self.jobs = pool;
+    }
+
+    /// Adds a decoder for image files loaded as textures, cubemaps or light
+    /// probes. Decoders added last are tried first, and the
+    /// [`StandardImageDecoder`] after all of them.
+    ///
+    /// See [`ImageDecoder`] for an example.
+    ///
+    /// [`ImageDecoder`]: trait.ImageDecoder.html
+    /// [`StandardImageDecoder`]: struct.StandardImageDecoder.html
+    pub fn add_image_decoder<D: ImageDecoder + 'static>(
+        &mut self,
+        decoder: D,
+    ) {
+        self.image_decoders.insert(0, Arc::new(decoder));
+    }
+
+    /// Sets how the animations of glTF files loaded afterwards are
+    /// compressed, or `None` to keep every keyframe as it is, which is the
+    /// default.
+    ///
+    /// Motion captured animations typically have a keyframe for every frame
+    /// of every bone, most of which can be dropped without visible changes.
+    ///
+    /// ```rust,no_run,ignore
+    /// # let mut window = three::Window::new("");
+    /// use three::animation::Compression;
+    ///
+    /// window.factory.set_animation_compression(Some(Compression {
+    ///     tolerance: 0.0005,
+    ///     quantize: true,
+    /// }));
+    /// let templates = window.factory.load_gltf("Dancer.gltf");
+    /// ```
+    pub fn set_animation_compression(
+        &mut self,
+        compression: Option<animation::Compression>,
+    ) {
+        self.animation_compression = compression;
+    }
+
+    /// Sets whether the static meshes created afterwards keep a copy of
+    /// their triangles on the CPU, which the [`raycast`] module and the
+    /// camera [`collision`] of the controls test rays against. Rays pass
+    /// through the meshes created without it.
+    ///
+    /// Disabled by default, as the copy takes as much memory as the
+    /// geometry itself.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// window.factory.set_raycast_triangles(true);
+    /// let pickable = window.factory.mesh(three::Geometry::cuboid(1.0, 1.0, 1.0), three::material::Basic::default());
+    /// window.factory.set_raycast_triangles(false);
+    /// ```
+    ///
+    /// [`raycast`]: raycast/index.html
+    /// [`collision`]: controls/enum.Colliders.html
+    pub fn set_raycast_triangles(
+        &mut self,
+        enabled: bool,
+    ) {
+        self.raycast_triangles = enabled;
+    }
+
+    /// Adds a directory searched for `#include "file"` directives of custom
+    /// shaders, after the directory of the including file and any
+    /// previously added directories.
+    pub fn add_shader_include_path<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) {
+        self.shader_include_paths.push(path.into());
     }
 
     /// Create a basic mesh pipeline using a custom shader.
+    ///
+    /// Shaders can `#include <user>` to read the parameters set with
+    /// [`Renderer::set_user_params`](struct.Renderer.html#method.set_user_params),
+    /// shared by every mesh, while the [`params`] of each mesh material are
+    /// read from the `vec4 i_MatParams` vertex attribute and their
+    /// [`data`] from `uniform samplerBuffer b_UserData`.
+    /// Other files are included with `#include "file"`, see
+    /// [`add_shader_include_path`](#method.add_shader_include_path).
+    ///
+    /// The chunks used by the built-in materials can be included as well,
+    /// so that custom shaders keep up with the engine features:
+    ///
+    /// * `<globals>`: camera matrices and the number of lights.
+    /// * `<depth>`: `vec4 encode_depth(vec4 position)`, to be applied to
+    ///   `gl_Position` so that the mesh follows the
+    ///   [`DepthMode`](../render/enum.DepthMode.html) of the renderer.
+    /// * `<lights>`: the `Light` structure and the scene lights.
+    /// * `<shadow>`: `float sample_shadow(Light light, vec4 lit_space)`,
+    ///   the visibility of a point in the light projection space, which is
+    ///   `light.projection` times the world position for directional and
+    ///   point lights alike.
+    /// * `<lighting>`: `vec4 compute_lighting(vec3 world, vec3 normal, vec4 color, float glossiness)`,
+    ///   the shadowed lighting of the [`Phong`] material, evaluated per pixel.
+    /// * `<probe>`: `vec3 probe_irradiance(vec3 normal)`, the ambient light of
+    ///   the scene [`LightProbe`] for a world space normal.
+    /// * `<skinning>`: `mat4 skin_transform(ivec4 indices, vec4 weights)`,
+    ///   for vertex shaders of skinned meshes.
+    /// * `<displacement>`: `void displace(inout vec3 position, inout vec3 normal, inout vec3 tangent)`
+    ///   and `vec3 displace_position(vec3 position)`, applying the morph
+    ///   targets in vertex shaders. Meshes without targets are left unchanged.
+    ///
+    /// Each chunk can be included any number of times.
+    ///
+    /// Compiler errors quote the offending lines of the preprocessed source,
+    /// and shader inputs that the pipeline doesn't provide are reported by
+    /// name. Pipeline inputs that the shader doesn't use are logged at the
+    /// `info` level.
+    ///
+    /// [`LightProbe`]: ../light/struct.LightProbe.html
+    /// [`Phong`]: ../material/struct.Phong.html
+    /// [`params`]: ../material/basic/struct.Custom.html#structfield.params
+    /// [`data`]: ../material/basic/struct.Custom.html#structfield.data
     pub fn basic_pipeline<P: AsRef<Path>>(
         &mut self,
         dir: P,
@@ -915,20 +1504,70 @@ impl Factory {
         depth_state: gfx::state::Depth,
         stencil_state: gfx::state::Stencil,
     ) -> Result<BasicPipelineState, PipelineCreationError> {
-        let vs = Source::user(&dir, name, "vs")?;
-        let ps = Source::user(&dir, name, "ps")?;
+        let vs = Source::user_with_include_paths(&dir, &self.shader_include_paths, name, "vs")?;
+        let ps = Source::user_with_include_paths(&dir, &self.shader_include_paths, name, "ps")?;
         let shaders = self.backend
-            .create_shader_set(vs.0.as_bytes(), ps.0.as_bytes())?;
+            .create_shader_set(vs.0.as_bytes(), ps.0.as_bytes())
+            .map_err(|err| diagnostics::program_error(err, &vs.0, &ps.0))?;
+        let program = self.backend
+            .create_program(&shaders)
+            .map_err(|err| PipelineCreationError::Link(err.to_string()))?;
+        diagnostics::log_unused_basic_inputs(name, program.get_info());
         let init = basic_pipe::Init {
-            out_color: ("Target0", color_mask, blend_state),
+            out_color: color_output(color_mask, Some(blend_state)),
             out_depth: (depth_state, stencil_state),
             ..basic_pipe::new()
         };
         let pso = self.backend
-            .create_pipeline_state(&shaders, primitive, rasterizer, init)?;
+            .create_pipeline_from_program(&program, primitive, rasterizer, init)
+            .map_err(diagnostics::state_error)?;
         Ok(pso)
     }
 
+    /// Create a post-processing pass from the custom pixel shader
+    /// `<name>_ps.glsl` found in `dir`, for use with a [`Composer`].
+    ///
+    /// The shader reads its inputs with `#include <post>`, see the
+    /// [`composer`] module. Other files are included like for
+    /// [`basic_pipeline`](#method.basic_pipeline).
+    ///
+    /// [`Composer`]: render/composer/struct.Composer.html
+    /// [`composer`]: render/composer/index.html
+    pub fn shader_pass<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        name: &str,
+    ) -> Result<ShaderPass, PipelineCreationError> {
+        let ps = Source::user_with_include_paths(&dir, &self.shader_include_paths, name, "ps")?;
+        self.post_pipeline(&ps).map(ShaderPass::new)
+    }
+
+    /// Create a procedural background from the custom pixel shader
+    /// `<name>_ps.glsl` found in `dir`, shown with [`Background::Custom`].
+    ///
+    /// The shader reads its inputs with `#include <background>`, see
+    /// [`BackgroundShader`]. Other files are included like for
+    /// [`basic_pipeline`](#method.basic_pipeline).
+    ///
+    /// [`Background::Custom`]: scene/enum.Background.html#variant.Custom
+    /// [`BackgroundShader`]: struct.BackgroundShader.html
+    pub fn background_shader<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+        name: &str,
+    ) -> Result<BackgroundShader, PipelineCreationError> {
+        let ps = Source::user_with_include_paths(&dir, &self.shader_include_paths, name, "ps")?;
+        background_pipeline(&mut self.backend, &ps).map(BackgroundShader::new)
+    }
+
+    /// Creates a full-screen pipeline drawing the pixel shader `ps`.
+    pub(crate) fn post_pipeline(
+        &mut self,
+        ps: &Source,
+    ) -> Result<PostPipelineState, PipelineCreationError> {
+        post_pipeline(&mut self.backend, ps)
+    }
+
     /// Create new UI (on-screen) text. See [`Text`](struct.Text.html) for default settings.
     pub fn ui_text<S: Into<String>>(
         &mut self,
@@ -1011,44 +1650,238 @@ impl Factory {
         Font::new(buffer, String::from("Embedded Karla-Regular.ttf"), self.backend.clone())
     }
 
-    fn parse_texture_format(path: &Path) -> image::ImageFormat {
-        use image::ImageFormat as F;
-        let extension = path.extension()
-            .expect("no extension for an image?")
-            .to_string_lossy()
-            .to_lowercase();
-        match extension.as_str() {
-            "png" => F::Png,
-            "jpg" | "jpeg" => F::Jpeg,
-            "gif" => F::Gif,
-            "webp" => F::WebP,
-            "ppm" => F::Pnm,
-            "tiff" => F::Tiff,
-            "tga" => F::Tga,
-            "bmp" => F::Bmp,
-            "ico" => F::Ico,
-            "hdr" => F::Hdr,
-            _ => panic!("Unrecognized image extension: {}", extension),
+    /// Renders `text` into a texture, with lines `size` pixels high, for
+    /// name tags and signs on meshes.
+    ///
+    /// The glyphs are white on a transparent background, so the color of the
+    /// material tints them. The texture is as large as the text.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::material::{self, ShaderFeatures};
+    ///
+    /// let font = window.factory.load_font_karla();
+    /// let label = window.factory.text_texture(&font, "Shop", 64.0);
+    /// let [width, height] = label.size();
+    /// let sign = window.factory.mesh(
+    ///     three::Geometry::plane(width as f32 / height as f32, 1.0),
+    ///     material::Basic {
+    ///         color: 0xFFD700,
+    ///         map: Some(label),
+    ///         features: ShaderFeatures::ALPHA_TEST,
+    ///         .. Default::default()
+    ///     },
+    /// );
+    /// window.scene.add(&sign);
+    /// ```
+    pub fn text_texture(
+        &mut self,
+        font: &Font,
+        text: &str,
+        size: f32,
+    ) -> Texture<[f32; 4]> {
+        let ([width, height], pixels) = font.rasterize(text, size);
+        let sampler = self.sampler(FilterMethod::Bilinear, WrapMode::Clamp, WrapMode::Clamp);
+        self.load_texture_from_memory(width, height, &pixels, sampler)
+    }
+
+    /// Creates a quad one unit high showing `text`, centered on the origin
+    /// of the XY plane and facing the positive Z axis.
+    fn text_quad(
+        &mut self,
+        font: &Font,
+        text: &str,
+    ) -> (Geometry, Texture<[f32; 4]>) {
+        let texture = self.text_texture(font, text, TEXT_3D_RESOLUTION);
+        let [width, height] = texture.size();
+        let geometry = textured_quad(width as f32 / height as f32, 1.0);
+        (geometry, texture)
+    }
+
+    /// Create a mesh showing `text` in the scene, with lines one unit high,
+    /// centered on its origin and readable from the positive Z axis.
+    ///
+    /// Unlike [`ui_text`](#method.ui_text), the text is hidden by the
+    /// objects in front of it, and follows the transforms of the mesh and
+    /// the lights of the scene. See [`text_billboard`](#method.text_billboard)
+    /// for labels always turned to the camera.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::Object;
+    ///
+    /// let font = window.factory.load_font_karla();
+    /// let sign = window.factory.text_3d(&font, "EXIT");
+    /// sign.set_transform([0.0, 2.5, -4.0], [0.0, 0.0, 0.0, 1.0], 0.5);
+    /// window.scene.add(&sign);
+    /// ```
+    pub fn text_3d(
+        &mut self,
+        font: &Font,
+        text: &str,
+    ) -> Mesh {
+        let (geometry, texture) = self.text_quad(font, text);
+        let material = material::Pbr {
+            base_color_map: Some(texture),
+            metallic_factor: 0.0,
+            .. Default::default()
+        };
+        self.mesh(geometry, material)
+    }
+
+    /// Create a mesh showing `text` in the scene like [`text_3d`], turned to
+    /// face the camera when drawn, and unaffected by lights.
+    ///
+    /// Only the position and the scale of the mesh are used, which suits
+    /// labels above objects.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let player = window.factory.group();
+    /// use three::Object;
+    ///
+    /// let font = window.factory.load_font_karla();
+    /// let label = window.factory.text_billboard(&font, "Player 1");
+    /// label.set_position([0.0, 2.2, 0.0]);
+    /// label.set_scale(0.3);
+    /// player.add(&label);
+    /// ```
+    ///
+    /// [`text_3d`]: #method.text_3d
+    pub fn text_billboard(
+        &mut self,
+        font: &Font,
+        text: &str,
+    ) -> Mesh {
+        let (geometry, texture) = self.text_quad(font, text);
+        let material = material::Basic {
+            color: WHITE,
+            map: Some(texture),
+            features: material::ShaderFeatures::ALPHA_TEST,
+            .. Default::default()
+        };
+        let mesh = self.mesh(geometry, material);
+        mesh.as_ref().send(hub::Operation::SetBillboard(true));
+        mesh
+    }
+
+    /// Load a bitmap font from a BMFont descriptor (.fnt) in the text format,
+    /// along with its page images.
+    ///
+    /// The pages are sampled with nearest-neighbor filtering, to keep the
+    /// pixels of the characters sharp.
+    pub fn load_bitmap_font<P: AsRef<Path>>(
+        &mut self,
+        file_path: P,
+    ) -> io::Result<BitmapFont> {
+        let file_path = file_path.as_ref();
+        let code = util::read_file_to_string(file_path)?;
+        let dir = file_path.parent().unwrap_or(Path::new(""));
+        let descriptor = bitmap_font::Descriptor::parse(&code, dir)?;
+        let sampler = self.sampler(FilterMethod::Scale, WrapMode::Clamp, WrapMode::Clamp);
+        let pages = descriptor
+            .pages
+            .iter()
+            .map(|path| Self::load_texture_impl(path, sampler.clone(), ColorSpace::Srgb, &self.image_decoders, &mut self.backend))
+            .collect();
+        Ok(BitmapFont::new(descriptor, pages))
+    }
+
+    /// Create a group of meshes showing `text` in a bitmap font, tinted with
+    /// `color`, with one mesh per font page.
+    ///
+    /// Units are pixels, with the origin at the top-left corner of the text
+    /// and Y pointing up, so that an orthographic camera spanning the window
+    /// height in pixels shows the characters at their original size. Keep the
+    /// group at integer positions for sharp pixels.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::Object;
+    ///
+    /// let font = window.factory.load_bitmap_font("fonts/pixel.fnt").unwrap();
+    /// let score = window.factory.bitmap_text(&font, "SCORE 100", three::color::WHITE);
+    /// score.set_position([-300.0, 200.0, 0.0]);
+    /// window.scene.add(&score);
+    /// ```
+    pub fn bitmap_text(
+        &mut self,
+        font: &BitmapFont,
+        text: &str,
+        color: Color,
+    ) -> Group {
+        let group = self.group();
+        for (geometry, page) in font.layout(text).into_iter().zip(&font.pages) {
+            if geometry.faces.is_empty() {
+                continue;
+            }
+            let material = material::Basic {
+                color,
+                map: Some(page.clone()),
+                features: material::ShaderFeatures::ALPHA_TEST,
+                .. Default::default()
+            };
+            let mesh = self.mesh(geometry, material);
+            group.add(&mesh);
         }
+        group
+    }
+
+    /// Reads and decodes an image file with the first decoder supporting
+    /// its extension.
+    fn decode_image(
+        decoders: &[Arc<dyn ImageDecoder>],
+        path: &Path,
+    ) -> Result<image::DynamicImage, String> {
+        let extension = path.extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .ok_or_else(|| format!("No extension for the image {}", path.display()))?;
+        let decoder = decoders
+            .iter()
+            .find(|decoder| decoder.supports(&extension))
+            .ok_or_else(|| format!("Unrecognized image extension: {}", extension))?;
+        let data = fs::read(path).map_err(|e| format!("Unable to open {}: {:?}", path.display(), e))?;
+        decoder
+            .decode(path, &data)
+            .map_err(|e| format!("Unable to decode {}: {}", path.display(), e))
+    }
+
+    fn create_texture_u8(
+        factory: &mut BackendFactory,
+        kind: gfx::texture::Kind,
+        pixels: &[u8],
+        color_space: ColorSpace,
+    ) -> Result<gfx::handle::ShaderResourceView<BackendResources, [f32; 4]>, gfx::CombinedError> {
+        use gfx::texture::Mipmap;
+        let view = match color_space {
+            ColorSpace::Srgb => factory
+                .create_texture_immutable_u8::<gfx::format::Srgba8>(kind, Mipmap::Provided, &[pixels])?
+                .1,
+            ColorSpace::Linear => factory
+                .create_texture_immutable_u8::<gfx::format::Rgba8>(kind, Mipmap::Provided, &[pixels])?
+                .1,
+        };
+        Ok(view)
     }
 
     fn load_texture_impl(
         path: &Path,
         sampler: Sampler,
+        color_space: ColorSpace,
+        decoders: &[Arc<dyn ImageDecoder>],
         factory: &mut BackendFactory,
     ) -> Texture<[f32; 4]> {
         use gfx::texture as t;
         //TODO: generate mipmaps
-        let format = Factory::parse_texture_format(path);
-        let file = fs::File::open(path).unwrap_or_else(|e| panic!("Unable to open {}: {:?}", path.display(), e));
-        let img = image::load(io::BufReader::new(file), format)
-            .unwrap_or_else(|e| panic!("Unable to decode {}: {:?}", path.display(), e))
+        let img = Factory::decode_image(decoders, path)
+            .unwrap_or_else(|message| panic!("{}", message))
             .flipv()
             .to_rgba();
         let (width, height) = img.dimensions();
         let kind = t::Kind::D2(width as t::Size, height as t::Size, t::AaMode::Single);
-        let (_, view) = factory
-            .create_texture_immutable_u8::<gfx::format::Srgba8>(kind, t::Mipmap::Provided, &[&img])
+        let view = Factory::create_texture_u8(factory, kind, &img, color_space)
             .unwrap_or_else(|e| {
                 panic!(
                     "Unable to create GPU texture for {}: {:?}",
@@ -1059,23 +1892,30 @@ impl Factory {
         Texture::new(view, sampler.0, [width, height])
     }
 
-    fn load_cubemap_impl<P: AsRef<Path>>(
+    /// Decodes the six images of a cubemap in parallel on the job pool.
+    fn decode_cube_faces<P: AsRef<Path>>(
+        &self,
         paths: &CubeMapPath<P>,
+    ) -> Vec<image::DynamicImage> {
+        let paths = paths
+            .as_array()
+            .iter()
+            .map(|path| path.as_ref().to_owned())
+            .collect::<Vec<_>>();
+        let decoders = self.image_decoders.clone();
+        self.jobs
+            .map(paths, move |path| Factory::decode_image(&decoders, &path))
+            .into_iter()
+            .map(|result| result.unwrap_or_else(|message| panic!("{}", message)))
+            .collect()
+    }
+
+    fn create_cubemap(
+        images: &[image::RgbaImage],
         sampler: Sampler,
         factory: &mut BackendFactory,
     ) -> CubeMap<[f32; 4]> {
         use gfx::texture as t;
-        let images = paths
-            .as_array()
-            .iter()
-            .map(|path| {
-                let format = Factory::parse_texture_format(path.as_ref());
-                let file = fs::File::open(path).unwrap_or_else(|e| panic!("Unable to open {}: {:?}", path.as_ref().display(), e));
-                image::load(io::BufReader::new(file), format)
-                    .unwrap_or_else(|e| panic!("Unable to decode {}: {:?}", path.as_ref().display(), e))
-                    .to_rgba()
-            })
-            .collect::<Vec<_>>();
         let data: [&[u8]; 6] = [
             &images[0], &images[1], &images[2], &images[3], &images[4], &images[5]
         ];
@@ -1093,11 +1933,18 @@ impl Factory {
         &mut self,
         path: P,
         sampler: Sampler,
+        color_space: ColorSpace,
     ) -> Texture<[f32; 4]> {
-        match self.texture_cache.entry(path.as_ref().to_owned()) {
+        match self.texture_cache.entry((path.as_ref().to_owned(), color_space)) {
             Entry::Occupied(e) => e.get().clone(),
             Entry::Vacant(e) => {
-                let tex = Self::load_texture_impl(path.as_ref(), sampler, &mut self.backend);
+                let tex = Self::load_texture_impl(
+                    path.as_ref(),
+                    sampler,
+                    color_space,
+                    &self.image_decoders,
+                    &mut self.backend,
+                );
                 e.insert(tex.clone());
                 tex
             }
@@ -1115,25 +1962,46 @@ impl Factory {
             c.iter()
                 .fold(0, |u, &v| (u << 8) + cmp::min((v * 255.0) as u32, 0xFF))
         };
+        let mut texture = |name: &Option<String>, color_space| match (has_uv, name) {
+            (true, &Some(ref name)) => {
+                let sampler = self.default_sampler();
+                Some(self.request_texture(&concat_path(obj_dir, name), sampler, color_space))
+            },
+            _ => None,
+        };
         match *mat {
             obj::Material {
                 kd: Some(color),
                 ns: Some(glossiness),
+                ref ke,
+                ref map_kd,
+                ref map_ks,
+                ref map_bump,
                 ..
             } if has_normals =>
             {
                 material::Phong {
                     color: cf2u(color),
+                    map: texture(map_kd, ColorSpace::Srgb),
+                    normal_map: texture(map_bump, ColorSpace::Linear),
+                    specular_map: texture(map_ks, ColorSpace::Srgb),
+                    emissive: ke.map_or(0, cf2u),
                     glossiness,
+                    .. Default::default()
                 }.into()
             }
             obj::Material {
-                kd: Some(color), ..
+                kd: Some(color),
+                ref ke,
+                ref map_kd,
+                ..
             } if has_normals =>
             {
                 material::Lambert {
                     color: cf2u(color),
-                    flat: false,
+                    map: texture(map_kd, ColorSpace::Srgb),
+                    emissive: ke.map_or(0, cf2u),
+                    .. Default::default()
                 }.into()
             }
             obj::Material {
@@ -1142,33 +2010,40 @@ impl Factory {
                 ..
             } => material::Basic {
                 color: cf2u(color),
-                map: match (has_uv, map_kd) {
-                    (true, &Some(ref name)) => {
-                        let sampler = self.default_sampler();
-                        Some(self.request_texture(&concat_path(obj_dir, name), sampler))
-                    },
-                    _ => None,
-                },
+                map: texture(map_kd, ColorSpace::Srgb),
+                .. Default::default()
             }.into(),
             _ => material::Basic {
                 color: 0xffffff,
                 map: None,
+                .. Default::default()
             }.into(),
         }
     }
 
-    /// Load texture from pre-loaded data.
+    /// Load texture from pre-loaded RGBA data in sRGB.
     pub fn load_texture_from_memory(
         &mut self,
         width: u16,
         height: u16,
         pixels: &[u8],
         sampler: Sampler,
+    ) -> Texture<[f32; 4]> {
+        self.load_texture_from_memory_with_color_space(width, height, pixels, sampler, ColorSpace::Srgb)
+    }
+
+    /// Load texture from pre-loaded RGBA data in the given `ColorSpace`.
+    pub fn load_texture_from_memory_with_color_space(
+        &mut self,
+        width: u16,
+        height: u16,
+        pixels: &[u8],
+        sampler: Sampler,
+        color_space: ColorSpace,
     ) -> Texture<[f32; 4]> {
         use gfx::texture as t;
         let kind = t::Kind::D2(width, height, t::AaMode::Single);
-        let (_, view) = self.backend
-            .create_texture_immutable_u8::<gfx::format::Srgba8>(kind, t::Mipmap::Provided, &[pixels])
+        let view = Factory::create_texture_u8(&mut self.backend, kind, pixels, color_space)
             .unwrap_or_else(|e| {
                 panic!("Unable to create GPU texture from memory: {:?}", e);
             });
@@ -1176,32 +2051,161 @@ impl Factory {
     }
 
     /// Load texture from file, with default `Sampler`.
-    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
     pub fn load_texture<P: AsRef<Path>>(
         &mut self,
         path_str: P,
     ) -> Texture<[f32; 4]> {
         let sampler = self.default_sampler();
-        self.request_texture(path_str, sampler)
+        self.request_texture(path_str, sampler, ColorSpace::Srgb)
     }
 
     /// Load texture from file, with custom `Sampler`.
-    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
     pub fn load_texture_with_sampler<P: AsRef<Path>>(
         &mut self,
         path_str: P,
         sampler: Sampler,
     ) -> Texture<[f32; 4]> {
-        self.request_texture(path_str, sampler)
+        self.request_texture(path_str, sampler, ColorSpace::Srgb)
+    }
+
+    /// Load texture from file, with custom `Sampler` and `ColorSpace`.
+    /// Normal, metallic-roughness and occlusion maps need `ColorSpace::Linear`.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// let sampler = window.factory.default_sampler();
+    /// let normal_map = window.factory.load_texture_with_color_space(
+    ///     "brick_normal.png",
+    ///     sampler,
+    ///     three::ColorSpace::Linear,
+    /// );
+    /// ```
+    pub fn load_texture_with_color_space<P: AsRef<Path>>(
+        &mut self,
+        path_str: P,
+        sampler: Sampler,
+        color_space: ColorSpace,
+    ) -> Texture<[f32; 4]> {
+        self.request_texture(path_str, sampler, color_space)
     }
 
     /// Load cubemap from files.
-    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
     pub fn load_cubemap<P: AsRef<Path>>(
         &mut self,
         paths: &CubeMapPath<P>,
     ) -> CubeMap<[f32; 4]> {
-        Factory::load_cubemap_impl(paths, self.default_sampler(), &mut self.backend)
+        let images = self.decode_cube_faces(paths)
+            .into_iter()
+            .map(|image| image.to_rgba8())
+            .collect::<Vec<_>>();
+        Factory::create_cubemap(&images, self.default_sampler(), &mut self.backend)
+    }
+
+    /// Create a cubemap from six square images in sRGB, in the order
+    /// `+X, -X, +Y, -Y, +Z, -Z`.
+    pub(crate) fn cubemap_from_images(
+        &mut self,
+        images: &[image::RgbaImage],
+    ) -> CubeMap<[f32; 4]> {
+        Factory::create_cubemap(images, self.default_sampler(), &mut self.backend)
+    }
+
+    /// Load a [`LightProbe`] from the six images of a cubemap, usually the
+    /// ones of the skybox.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// let paths = three::CubeMapPath {
+    ///     front: "sky/posz.jpg",
+    ///     back: "sky/negz.jpg",
+    ///     left: "sky/negx.jpg",
+    ///     right: "sky/posx.jpg",
+    ///     up: "sky/posy.jpg",
+    ///     down: "sky/negy.jpg",
+    /// };
+    /// let skybox = window.factory.load_cubemap(&paths);
+    /// window.scene.background = three::Background::Skybox(skybox);
+    /// window.scene.light_probe = Some(window.factory.load_light_probe(&paths));
+    /// ```
+    ///
+    /// [`LightProbe`]: ../light/struct.LightProbe.html
+    pub fn load_light_probe<P: AsRef<Path>>(
+        &mut self,
+        paths: &CubeMapPath<P>,
+    ) -> LightProbe {
+        let faces = self.decode_cube_faces(paths)
+            .into_iter()
+            .map(|image| image.to_rgb8())
+            .collect::<Vec<_>>();
+        LightProbe::from_cube_faces(&faces)
+    }
+
+    /// Load a [`ReflectionProbe`] from the six images of a cubemap, usually
+    /// the ones of the skybox. The reflections for every roughness are
+    /// computed here, which takes a moment.
+    /// Supported file formats are: PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO, HDR,
+    /// and those of the [added image decoders](#method.add_image_decoder).
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// let paths = three::CubeMapPath {
+    ///     front: "sky/posz.jpg",
+    ///     back: "sky/negz.jpg",
+    ///     left: "sky/negx.jpg",
+    ///     right: "sky/posx.jpg",
+    ///     up: "sky/posy.jpg",
+    ///     down: "sky/negy.jpg",
+    /// };
+    /// window.scene.light_probe = Some(window.factory.load_light_probe(&paths));
+    /// window.scene.reflection_probe = Some(window.factory.load_reflection_probe(&paths));
+    /// ```
+    ///
+    /// [`ReflectionProbe`]: ../light/struct.ReflectionProbe.html
+    pub fn load_reflection_probe<P: AsRef<Path>>(
+        &mut self,
+        paths: &CubeMapPath<P>,
+    ) -> ReflectionProbe {
+        let faces = self.decode_cube_faces(paths)
+            .into_iter()
+            .map(|image| image.to_rgb8())
+            .collect::<Vec<_>>();
+        self.reflection_probe_from_faces(&faces)
+    }
+
+    /// Create a [`ReflectionProbe`] from six square images in sRGB, in the
+    /// order `+X, -X, +Y, -Y, +Z, -Z`.
+    ///
+    /// [`ReflectionProbe`]: ../light/struct.ReflectionProbe.html
+    pub(crate) fn reflection_probe_from_faces(
+        &mut self,
+        faces: &[image::RgbImage],
+    ) -> ReflectionProbe {
+        use gfx::texture as t;
+        let images = ReflectionProbe::prefilter(faces);
+        let data = images.iter().map(|image| &**image).collect::<Vec<&[u8]>>();
+        let kind = t::Kind::Cube(images[0].width() as t::Size);
+        let (_, view) = self.backend
+            .create_texture_immutable_u8::<gfx::format::Srgba8>(kind, t::Mipmap::Provided, &data)
+            .unwrap_or_else(|e| {
+                panic!("Unable to create GPU texture for reflection probe: {:?}", e);
+            });
+        let sampler = self.backend
+            .create_sampler(t::SamplerInfo::new(t::FilterMethod::Trilinear, t::WrapMode::Clamp));
+        ReflectionProbe {
+            cubemap: CubeMap::new(view, sampler),
+            levels: (images.len() / 6) as u32,
+            intensity: 1.0,
+        }
     }
 
     /// Load mesh from Wavefront Obj format.
@@ -1276,10 +2280,19 @@ impl Factory {
                     _ => material::Basic {
                         color: 0xFFFFFF,
                         map: None,
+                        .. Default::default()
                     }.into(),
                 };
                 info!("\t{:?}", material);
 
+                let triangles = Triangles::new(
+                    vertices.iter().map(|v| Vector3::new(v.pos[0], v.pos[1], v.pos[2])).collect(),
+                    indices
+                        .chunks(3)
+                        .map(|f| [f[0] as u32, f[1] as u32, f[2] as u32])
+                        .collect(),
+                    self.raycast_triangles,
+                );
                 let (vertices, mut slice) = self.backend
                     .create_vertex_buffer_with_slice(&vertices, &indices[..]);
                 slice.instances = Some((1, 0));
@@ -1302,6 +2315,10 @@ impl Factory {
                             pending: None,
                             instance_cache_key: None,
                             displacement_contributions: ZEROED_DISPLACEMENT_CONTRIBUTION.to_vec(),
+                            triangles: Some(triangles),
+                            line_quads: false,
+                            attributes: VertexAttributes { normals: num_normals != 0, tex_coords: num_uvs != 0, joints: false },
+                            instance_list: None,
                         },
                         None,
                     ),
@@ -4,6 +4,11 @@ use genmesh::{EmitTriangles, Triangulate, Vertex as GenVertex};
 use genmesh::generators::{self, IndexedPolygon, SharedVertex};
 use mint;
 
+use collision::{Aabb, Sphere};
+
+use std::{fs, io, path};
+use std::io::Write;
+
 /// A collection of vertices, their normals, and faces that defines the
 /// shape of a polyhedral object.
 ///
@@ -43,7 +48,7 @@ use mint;
 /// ```
 /// # Notes
 ///
-/// * If any vertex normals, tangents, or texture co-ordinates are provided,
+/// * If any vertex normals, tangents, texture co-ordinates, or colors are provided,
 ///   the number of entries in each array must match the number of entries
 ///   in `vertices`.
 /// * If joints are provided, the number of entries in `joints.indices` must
@@ -54,6 +59,16 @@ pub struct Geometry {
     pub base: Shape,
     /// Texture co-ordinates.
     pub tex_coords: Vec<mint::Point2<f32>>,
+    /// Second set of texture co-ordinates, used by the materials with
+    /// [`ShaderFeatures::UV1`].
+    ///
+    /// [`ShaderFeatures::UV1`]: ../material/struct.ShaderFeatures.html#associatedconstant.UV1
+    pub tex_coords1: Vec<mint::Point2<f32>>,
+    /// Linear RGBA vertex colors, used by the materials with
+    /// [`ShaderFeatures::VERTEX_COLORS`].
+    ///
+    /// [`ShaderFeatures::VERTEX_COLORS`]: ../material/struct.ShaderFeatures.html#associatedconstant.VERTEX_COLORS
+    pub colors: Vec<mint::Vector4<f32>>,
     /// Face indices.
     ///
     /// When omitted, the vertex order `[[0, 1, 2], [3, 4, 5], ...]` is
@@ -110,6 +125,16 @@ impl Geometry {
         }
     }
 
+    /// Create `Geometry` for a line strip going through `points` in order,
+    /// to be drawn with [`material::Line`].
+    ///
+    /// See [`material::Line`] for an example.
+    ///
+    /// [`material::Line`]: material/struct.Line.html
+    pub fn with_line_strip(points: Vec<mint::Point3<f32>>) -> Self {
+        Self::with_vertices(points)
+    }
+
     fn generate<P, G, Fpos, Fnor>(
         gen: G,
         fpos: Fpos,
@@ -262,4 +287,161 @@ impl Geometry {
             |v| v.normal.into(),
         )
     }
+
+    /// Returns the axis-aligned box enclosing the idle shape, or `None` if
+    /// there are no vertices.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let geometry = three::Geometry::cuboid(2.0, 1.0, 1.0);
+    /// let bounds = geometry.bounding_box().unwrap();
+    /// assert_eq!(bounds.max.x, 1.0);
+    /// ```
+    pub fn bounding_box(&self) -> Option<Aabb> {
+        let mut vertices = self.base.vertices.iter();
+        let first = *vertices.next()?;
+        let (mut min, mut max) = (first, first);
+        for v in vertices {
+            min = [min.x.min(v.x), min.y.min(v.y), min.z.min(v.z)].into();
+            max = [max.x.max(v.x), max.y.max(v.y), max.z.max(v.z)].into();
+        }
+        Some(Aabb { min, max })
+    }
+
+    /// Returns a sphere enclosing the idle shape, centered on its bounding
+    /// box, or `None` if there are no vertices.
+    pub fn bounding_sphere(&self) -> Option<Sphere> {
+        let bounds = self.bounding_box()?;
+        let center: mint::Point3<f32> = [
+            0.5 * (bounds.min.x + bounds.max.x),
+            0.5 * (bounds.min.y + bounds.max.y),
+            0.5 * (bounds.min.z + bounds.max.z),
+        ].into();
+        let radius_sq = self.base.vertices
+            .iter()
+            .map(|v| {
+                let (dx, dy, dz) = (v.x - center.x, v.y - center.y, v.z - center.z);
+                dx * dx + dy * dy + dz * dz
+            })
+            .fold(0.0, f32::max);
+        Some(Sphere {
+            center,
+            radius: radius_sq.sqrt(),
+        })
+    }
+
+    /// Face indices, filled in with the implicit vertex order if omitted.
+    fn face_list(&self) -> Vec<[u32; 3]> {
+        if self.faces.is_empty() {
+            (0 .. self.base.vertices.len() as u32 / 3)
+                .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+                .collect()
+        } else {
+            self.faces.clone()
+        }
+    }
+
+    /// Save the idle shape in Wavefront OBJ format, including normals and
+    /// texture co-ordinates when present.
+    ///
+    /// Blend shapes and joints are not saved.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// let geometry = three::Geometry::uv_sphere(1.0, 12, 12);
+    /// geometry.save_obj("sphere.obj").unwrap();
+    /// ```
+    pub fn save_obj<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        let shape = &self.base;
+        for v in &shape.vertices {
+            writeln!(out, "v {} {} {}", v.x, v.y, v.z)?;
+        }
+        for t in &self.tex_coords {
+            writeln!(out, "vt {} {}", t.x, t.y)?;
+        }
+        for n in &shape.normals {
+            writeln!(out, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        let (has_uv, has_normal) = (!self.tex_coords.is_empty(), !shape.normals.is_empty());
+        for face in self.face_list() {
+            write!(out, "f")?;
+            for &index in &face {
+                // OBJ indices start from 1
+                let i = index + 1;
+                match (has_uv, has_normal) {
+                    (false, false) => write!(out, " {}", i)?,
+                    (true, false) => write!(out, " {}/{}", i, i)?,
+                    (false, true) => write!(out, " {}//{}", i, i)?,
+                    (true, true) => write!(out, " {}/{}/{}", i, i, i)?,
+                }
+            }
+            writeln!(out)?;
+        }
+        out.flush()
+    }
+
+    /// Save the idle shape in ASCII PLY format, including normals and
+    /// texture co-ordinates when present.
+    ///
+    /// Blend shapes and joints are not saved.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// let geometry = three::Geometry::cuboid(1.0, 2.0, 3.0);
+    /// geometry.save_ply("cuboid.ply").unwrap();
+    /// ```
+    pub fn save_ply<P: AsRef<path::Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<()> {
+        let mut out = io::BufWriter::new(fs::File::create(path)?);
+        let shape = &self.base;
+        let faces = self.face_list();
+        let (has_uv, has_normal) = (!self.tex_coords.is_empty(), !shape.normals.is_empty());
+
+        writeln!(out, "ply")?;
+        writeln!(out, "format ascii 1.0")?;
+        writeln!(out, "comment exported by three")?;
+        writeln!(out, "element vertex {}", shape.vertices.len())?;
+        for name in &["x", "y", "z"] {
+            writeln!(out, "property float {}", name)?;
+        }
+        if has_normal {
+            for name in &["nx", "ny", "nz"] {
+                writeln!(out, "property float {}", name)?;
+            }
+        }
+        if has_uv {
+            for name in &["s", "t"] {
+                writeln!(out, "property float {}", name)?;
+            }
+        }
+        writeln!(out, "element face {}", faces.len())?;
+        writeln!(out, "property list uchar uint vertex_indices")?;
+        writeln!(out, "end_header")?;
+
+        for (i, v) in shape.vertices.iter().enumerate() {
+            write!(out, "{} {} {}", v.x, v.y, v.z)?;
+            if has_normal {
+                let n = shape.normals[i];
+                write!(out, " {} {} {}", n.x, n.y, n.z)?;
+            }
+            if has_uv {
+                let t = self.tex_coords[i];
+                write!(out, " {} {}", t.x, t.y)?;
+            }
+            writeln!(out)?;
+        }
+        for face in &faces {
+            writeln!(out, "3 {} {} {}", face[0], face[1], face[2])?;
+        }
+        out.flush()
+    }
 }
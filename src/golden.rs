@@ -0,0 +1,359 @@
+//! Golden-image tests of the renderer.
+//!
+//! Renders scenes offscreen and compares the results with reference PNG
+//! images, so that changes to the renderer that alter its output are
+//! noticed. Requires the `golden-tests` feature.
+//!
+//! A missing reference is an error. References are written, replacing the
+//! outdated ones, by running the tests with the `THREE_UPDATE_GOLDEN`
+//! environment variable set:
+//!
+//! ```sh
+//! THREE_UPDATE_GOLDEN=1 cargo test --features golden-tests --test golden
+//! ```
+//!
+//! On a mismatch, the rendered image and a difference image are saved next
+//! to the reference, as `<name>.actual.png` and `<name>.diff.png`.
+//!
+//! [`Headless`] renders without a window, so the tests also run on machines
+//! without a display, such as CI servers.
+//!
+//! ```rust,no_run
+//! use three::Object;
+//! use three::golden::{Golden, Headless};
+//!
+//! let mut headless = Headless::new([256, 256]).unwrap();
+//! let mut golden = Golden::new(&mut headless.factory, "tests/golden", [256, 256]);
+//! let camera = headless.factory.perspective_camera(60.0, 0.1 .. 10.0);
+//! camera.set_position([0.0, 0.0, 3.0]);
+//! golden
+//!     .check(&mut headless.renderer, &headless.scene, &camera, "empty")
+//!     .unwrap();
+//! ```
+//!
+//! [`Headless`]: struct.Headless.html
+
+use image::{self, Rgba, RgbaImage};
+
+use std::{env, fs};
+use std::path::{Path, PathBuf};
+
+use camera::Camera;
+use factory::Factory;
+use render::{source, RenderTarget, Renderer};
+use scene::Scene;
+
+/// Environment variable that makes [`Golden::check`] write the references.
+///
+/// [`Golden::check`]: struct.Golden.html#method.check
+pub const UPDATE_VARIABLE: &str = "THREE_UPDATE_GOLDEN";
+
+quick_error! {
+    #[doc = "Failure of a golden-image check."]
+    #[derive(Debug)]
+    pub enum GoldenError {
+        #[doc = "The reference image doesn't exist."]
+        MissingReference(path: PathBuf) {
+            description("missing reference image")
+            display("missing reference image {:?}, run with {} set to create it", path, UPDATE_VARIABLE)
+        }
+
+        #[doc = "The rendered image differs from the reference by more than"]
+        #[doc = "the tolerances. Holds the number of differing pixels and the"]
+        #[doc = "largest difference of a channel."]
+        Mismatch(path: PathBuf, pixels: usize, max_difference: u8) {
+            description("rendered image differs from the reference")
+            display("{} pixels differ from {:?}, by up to {}", pixels, path, max_difference)
+        }
+
+        #[doc = "The rendered image and the reference have different sizes."]
+        Size(path: PathBuf, actual: (u32, u32), expected: (u32, u32)) {
+            description("rendered image size differs from the reference")
+            display("rendered image is {:?}, but {:?} is {:?}", actual, path, expected)
+        }
+
+        #[doc = "No OpenGL context could be created for rendering without a"]
+        #[doc = "window."]
+        Context(reason: String) {
+            description("no headless OpenGL context")
+            display("no headless OpenGL context: {}", reason)
+        }
+
+        #[doc = "Error reading or writing an image."]
+        Image(err: image::ImageError) {
+            from()
+            description("image error")
+            display("image error: {}", err)
+            cause(err)
+        }
+    }
+}
+
+/// A renderer without a window, drawing offscreen with an OpenGL context of
+/// its own.
+///
+/// The context is created with the surfaceless platform of EGL, which needs
+/// Mesa but neither a display nor a GPU. It is unavailable on other
+/// platforms.
+pub struct Headless {
+    /// Factory of the renderer.
+    pub factory: Factory,
+    /// Renderer drawing into a target of the size of the headless context.
+    pub renderer: Renderer,
+    /// Scene created with the factory.
+    pub scene: Scene,
+    // dropped last, after the resources of the renderer
+    _context: egl::Context,
+}
+
+impl Headless {
+    /// Creates a context and a renderer drawing into targets of `size` pixels.
+    pub fn new(size: [u16; 2]) -> Result<Self, GoldenError> {
+        let context = egl::Context::new().map_err(GoldenError::Context)?;
+        let (renderer, mut factory) = Renderer::headless(|name| context.proc_address(name), size, &source::Set::default());
+        let scene = factory.scene();
+        Ok(Headless {
+            factory,
+            renderer,
+            scene,
+            _context: context,
+        })
+    }
+}
+
+/// Renders scenes offscreen and compares them with reference images.
+///
+/// See the [module documentation](index.html) for an example.
+pub struct Golden {
+    dir: PathBuf,
+    target: RenderTarget,
+    update: bool,
+    /// Largest difference of a color channel that still counts as a match,
+    /// to absorb small variations between drivers.
+    ///
+    /// Default: 2.
+    pub channel_tolerance: u8,
+    /// Fraction of pixels allowed to exceed `channel_tolerance`.
+    ///
+    /// Default: 0.001.
+    pub pixel_tolerance: f32,
+}
+
+impl Golden {
+    /// Creates a harness rendering at `size` pixels, with the reference
+    /// images stored in `dir`.
+    pub fn new<P: AsRef<Path>>(
+        factory: &mut Factory,
+        dir: P,
+        size: [u16; 2],
+    ) -> Self {
+        Golden {
+            dir: dir.as_ref().to_owned(),
+            target: factory.render_target(size[0], size[1]),
+            update: env::var_os(UPDATE_VARIABLE).is_some(),
+            channel_tolerance: 2,
+            pixel_tolerance: 0.001,
+        }
+    }
+
+    /// Renders `scene` by `camera` and compares the result with the
+    /// reference image `<name>.png`.
+    pub fn check(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        camera: &Camera,
+        name: &str,
+    ) -> Result<(), GoldenError> {
+        renderer.render_to(scene, camera, &self.target);
+        let actual = renderer.read_target(&self.target);
+        let path = self.dir.join(format!("{}.png", name));
+
+        if self.update {
+            info!("Writing reference image {:?}", path);
+            fs::create_dir_all(&self.dir).map_err(image::ImageError::IoError)?;
+            actual.save(&path)?;
+            return Ok(());
+        }
+        if !path.exists() {
+            return Err(GoldenError::MissingReference(path));
+        }
+        let expected = image::open(&path)?.to_rgba8();
+        if actual.dimensions() != expected.dimensions() {
+            return Err(GoldenError::Size(path, actual.dimensions(), expected.dimensions()));
+        }
+
+        let (diff, pixels, max_difference) = compare(&actual, &expected, self.channel_tolerance);
+        let total = (actual.width() * actual.height()) as f32;
+        if pixels as f32 > self.pixel_tolerance * total {
+            actual.save(self.dir.join(format!("{}.actual.png", name)))?;
+            diff.save(self.dir.join(format!("{}.diff.png", name)))?;
+            return Err(GoldenError::Mismatch(path, pixels, max_difference));
+        }
+        Ok(())
+    }
+}
+
+/// Compares two images of the same size. Returns an image highlighting the
+/// differing pixels in red over a faded copy of `actual`, the number of
+/// pixels differing by more than `tolerance`, and the largest difference.
+fn compare(
+    actual: &RgbaImage,
+    expected: &RgbaImage,
+    tolerance: u8,
+) -> (RgbaImage, usize, u8) {
+    let mut diff = RgbaImage::new(actual.width(), actual.height());
+    let mut pixels = 0;
+    let mut max_difference = 0;
+    for (x, y, a) in actual.enumerate_pixels() {
+        let e = expected.get_pixel(x, y);
+        let difference = a.0
+            .iter()
+            .zip(e.0.iter())
+            .map(|(&a, &e)| a.abs_diff(e))
+            .max()
+            .unwrap_or(0);
+        max_difference = max_difference.max(difference);
+        let pixel = if difference > tolerance {
+            pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let luma = (a.0[0] as u32 + a.0[1] as u32 + a.0[2] as u32) / 12;
+            Rgba([luma as u8, luma as u8, luma as u8, 255])
+        };
+        diff.put_pixel(x, y, pixel);
+    }
+    (diff, pixels, max_difference)
+}
+
+/// A surfaceless OpenGL context of Mesa, made current on creation.
+#[cfg(target_os = "linux")]
+mod egl {
+    use std::ffi::CString;
+    use std::mem;
+    use std::os::raw::{c_char, c_void};
+    use std::ptr;
+
+    const NONE: i32 = 0x3038;
+    const OPENGL_API: u32 = 0x30A2;
+    const CONTEXT_MAJOR_VERSION: i32 = 0x3098;
+    const CONTEXT_MINOR_VERSION: i32 = 0x30FB;
+    const CONTEXT_OPENGL_PROFILE_MASK: i32 = 0x30FD;
+    const CONTEXT_OPENGL_CORE_PROFILE_BIT: i32 = 0x1;
+    const PLATFORM_SURFACELESS_MESA: u32 = 0x31DD;
+
+    #[link(name = "EGL")]
+    extern "C" {
+        fn eglGetProcAddress(name: *const c_char) -> *const c_void;
+        fn eglGetError() -> i32;
+        fn eglInitialize(display: *mut c_void, major: *mut i32, minor: *mut i32) -> u32;
+        fn eglTerminate(display: *mut c_void) -> u32;
+        fn eglBindAPI(api: u32) -> u32;
+        fn eglCreateContext(
+            display: *mut c_void,
+            config: *mut c_void,
+            share_context: *mut c_void,
+            attributes: *const i32,
+        ) -> *mut c_void;
+        fn eglDestroyContext(display: *mut c_void, context: *mut c_void) -> u32;
+        fn eglMakeCurrent(
+            display: *mut c_void,
+            draw: *mut c_void,
+            read: *mut c_void,
+            context: *mut c_void,
+        ) -> u32;
+    }
+
+    type GetPlatformDisplay = extern "C" fn(u32, *mut c_void, *const i32) -> *mut c_void;
+
+    pub struct Context {
+        display: *mut c_void,
+        context: *mut c_void,
+    }
+
+    impl Context {
+        pub fn new() -> Result<Self, String> {
+            let error = |call: &str| format!("{} failed with EGL error 0x{:X}", call, unsafe { eglGetError() });
+            unsafe {
+                let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+                let get_platform_display = eglGetProcAddress(name.as_ptr());
+                if get_platform_display.is_null() {
+                    return Err("EGL_EXT_platform_base is unsupported".to_string());
+                }
+                let get_platform_display: GetPlatformDisplay = mem::transmute(get_platform_display);
+                let display = get_platform_display(PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+                if display.is_null() {
+                    return Err(error("eglGetPlatformDisplayEXT"));
+                }
+                let (mut major, mut minor) = (0, 0);
+                if eglInitialize(display, &mut major, &mut minor) == 0 {
+                    return Err(error("eglInitialize"));
+                }
+                if eglBindAPI(OPENGL_API) == 0 {
+                    let err = error("eglBindAPI");
+                    eglTerminate(display);
+                    return Err(err);
+                }
+                // the shaders need OpenGL 3.2, as windows request
+                let attributes = [
+                    CONTEXT_MAJOR_VERSION, 3,
+                    CONTEXT_MINOR_VERSION, 2,
+                    CONTEXT_OPENGL_PROFILE_MASK, CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                    NONE,
+                ];
+                let context = eglCreateContext(display, ptr::null_mut(), ptr::null_mut(), attributes.as_ptr());
+                if context.is_null() {
+                    let err = error("eglCreateContext");
+                    eglTerminate(display);
+                    return Err(err);
+                }
+                if eglMakeCurrent(display, ptr::null_mut(), ptr::null_mut(), context) == 0 {
+                    let err = error("eglMakeCurrent");
+                    eglDestroyContext(display, context);
+                    eglTerminate(display);
+                    return Err(err);
+                }
+                Ok(Context { display, context })
+            }
+        }
+
+        pub fn proc_address(
+            &self,
+            name: &str,
+        ) -> *const c_void {
+            let name = CString::new(name).unwrap();
+            unsafe { eglGetProcAddress(name.as_ptr()) }
+        }
+    }
+
+    impl Drop for Context {
+        fn drop(&mut self) {
+            unsafe {
+                eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+                eglDestroyContext(self.display, self.context);
+                eglTerminate(self.display);
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod egl {
+    use std::os::raw::c_void;
+    use std::ptr;
+
+    pub struct Context;
+
+    impl Context {
+        pub fn new() -> Result<Self, String> {
+            Err("headless rendering is only supported on Linux".to_string())
+        }
+
+        pub fn proc_address(
+            &self,
+            _name: &str,
+        ) -> *const c_void {
+            ptr::null()
+        }
+    }
+}
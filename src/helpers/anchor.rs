@@ -0,0 +1,165 @@
+//! Keeps screen-space elements positioned over world-space objects.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let enemy = window.factory.group();
+//! # let font = window.factory.load_font_karla();
+//! use three::Object;
+//! use three::helpers::ScreenAnchor;
+//!
+//! let mut label = window.factory.ui_text(&font, "Enemy");
+//! window.scene.add(&label);
+//!
+//! let mut anchor = ScreenAnchor::new(&enemy);
+//! anchor.world_offset = [0.0, 2.0, 0.0].into();
+//! anchor.edge_margin = Some(20.0);
+//!
+//! while window.update() {
+//!     {
+//!         let sync = window.scene.sync_guard();
+//!         anchor.update_text(&sync, &camera, &window.renderer, &mut label);
+//!     }
+//!     window.render(&camera);
+//! }
+//! ```
+
+use cgmath::{InnerSpace, Vector2};
+use mint;
+
+use std::f32;
+
+use camera::Camera;
+use object::{Base, Object};
+use render::Renderer;
+use scene::SyncGuard;
+use text::Text;
+
+/// The screen placement of a [`ScreenAnchor`](struct.ScreenAnchor.html).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnchorPosition {
+    /// Position in pixels from the top-left corner of the screen.
+    pub position: mint::Point2<f32>,
+    /// `true` if the target is off-screen or behind the camera, and the
+    /// position was clamped to the screen edges.
+    pub clamped: bool,
+    /// Unit direction in screen space from the center of the screen towards
+    /// the target, with Y pointing down. Useful to rotate off-screen indicators.
+    pub direction: mint::Vector2<f32>,
+}
+
+/// Tracks the screen position of an object.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Clone, Debug)]
+pub struct ScreenAnchor {
+    target: Base,
+    /// Offset from the origin of the target in world space, for example to
+    /// place a label above a character.
+    ///
+    /// Default: `(0, 0, 0)`.
+    pub world_offset: mint::Vector3<f32>,
+    /// Offset in pixels applied after projection.
+    ///
+    /// Default: `(0, 0)`.
+    pub screen_offset: mint::Vector2<f32>,
+    /// When set, targets outside of the screen are clamped to its edges,
+    /// keeping this margin in pixels. Otherwise they are not placed at all.
+    ///
+    /// Default: `None`.
+    pub edge_margin: Option<f32>,
+}
+
+impl ScreenAnchor {
+    /// Creates an anchor tracking `target`.
+    pub fn new<T: Object>(target: &T) -> Self {
+        ScreenAnchor {
+            target: target.upcast(),
+            world_offset: [0.0, 0.0, 0.0].into(),
+            screen_offset: [0.0, 0.0].into(),
+            edge_margin: None,
+        }
+    }
+
+    /// Computes the screen position of the target as seen by `camera`.
+    ///
+    /// Returns `None` if the target is not on screen and `edge_margin` is not set.
+    ///
+    /// # Panics
+    /// Panics if the target or the camera isn't in the scene.
+    pub fn resolve(
+        &self,
+        sync: &SyncGuard,
+        camera: &Camera,
+        renderer: &Renderer,
+    ) -> Option<AnchorPosition> {
+        let origin = sync.resolve_world(&self.target).transform.position;
+        let point = [
+            origin.x + self.world_offset.x,
+            origin.y + self.world_offset.y,
+            origin.z + self.world_offset.z,
+        ].into();
+        let clip = camera.world_to_clip(sync, renderer.aspect_ratio(), point);
+        let size = renderer.map_from_ndc([1.0, -1.0]);
+        let center = Vector2::new(0.5 * size.x, 0.5 * size.y);
+
+        if clip.w > 0.0 {
+            let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+            if x.abs() <= 1.0 && y.abs() <= 1.0 {
+                let p = renderer.map_from_ndc([x, y]);
+                let position = Vector2::new(p.x + self.screen_offset.x, p.y + self.screen_offset.y);
+                let offset = position - center;
+                let direction = if offset.magnitude2() > 0.0 {
+                    offset.normalize()
+                } else {
+                    Vector2::new(0.0, 1.0)
+                };
+                return Some(AnchorPosition {
+                    position: [position.x, position.y].into(),
+                    clamped: false,
+                    direction: direction.into(),
+                });
+            }
+        }
+
+        let margin = self.edge_margin?;
+        // points behind the camera project mirrored, so flip them back
+        let sign = if clip.w < 0.0 { -1.0 } else { 1.0 };
+        let mut direction = Vector2::new(sign * clip.x * size.x, -sign * clip.y * size.y);
+        if direction.magnitude2() == 0.0 {
+            direction = Vector2::new(0.0, 1.0);
+        }
+        let direction = direction.normalize();
+        let half_x = (center.x - margin).max(0.0);
+        let half_y = (center.y - margin).max(0.0);
+        let scale_x = if direction.x != 0.0 { half_x / direction.x.abs() } else { f32::INFINITY };
+        let scale_y = if direction.y != 0.0 { half_y / direction.y.abs() } else { f32::INFINITY };
+        let position = center + direction * scale_x.min(scale_y);
+        Some(AnchorPosition {
+            position: [position.x, position.y].into(),
+            clamped: true,
+            direction: direction.into(),
+        })
+    }
+
+    /// Moves `text` over the target, hiding it if the target can't be placed.
+    ///
+    /// Returns the computed position, see [`resolve`](#method.resolve).
+    pub fn update_text(
+        &self,
+        sync: &SyncGuard,
+        camera: &Camera,
+        renderer: &Renderer,
+        text: &mut Text,
+    ) -> Option<AnchorPosition> {
+        let result = self.resolve(sync, camera, renderer);
+        match result {
+            Some(anchor) => {
+                text.set_pos(anchor.position);
+                text.set_visible(true);
+            }
+            None => text.set_visible(false),
+        }
+        result
+    }
+}
@@ -0,0 +1,137 @@
+//! Floating origin for scenes larger than `f32` precision allows.
+//!
+//! Object positions are stored as `f32`, which leaves centimeter precision
+//! only up to about a hundred kilometers from the origin. Far beyond, meshes
+//! jitter as the camera moves. A floating origin keeps the viewer near the
+//! origin instead: whenever it strays too far, the whole scene is shifted
+//! back, and the accumulated shift is kept in double precision so that
+//! absolute positions remain available.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 1.0 ..);
+//! # let planet = window.factory.group();
+//! use three::Object;
+//! use three::helpers::FloatingOrigin;
+//!
+//! let mut origin = FloatingOrigin::new();
+//! window.scene.add(&camera);
+//! window.scene.add(&planet);
+//! // place the planet 1000 km away, relative to the current origin
+//! planet.set_position(origin.to_local([1.0e6, 0.0, 0.0]));
+//!
+//! while window.update() {
+//!     // ... fly the camera around ...
+//!     if let Some(shift) = origin.update(&mut window.scene.sync_guard(), &camera) {
+//!         println!("scene shifted by {:?}", shift);
+//!     }
+//!     window.render(&camera);
+//! }
+//! ```
+
+use cgmath::{InnerSpace, Vector3};
+use mint;
+
+use object::Object;
+use scene::SyncGuard;
+
+/// Tracks the absolute position of the scene origin and rebases the scene
+/// around a focus object.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Clone, Debug)]
+pub struct FloatingOrigin {
+    origin: [f64; 3],
+    /// Distance of the focus object from the origin that triggers a rebase.
+    ///
+    /// Default: 1000.
+    pub threshold: f32,
+}
+
+impl FloatingOrigin {
+    /// Creates a floating origin at the absolute position zero.
+    pub fn new() -> Self {
+        FloatingOrigin {
+            origin: [0.0; 3],
+            threshold: 1000.0,
+        }
+    }
+
+    /// The absolute position of the current scene origin.
+    pub fn origin(&self) -> mint::Point3<f64> {
+        self.origin.into()
+    }
+
+    /// Converts an absolute position to a position in the scene.
+    pub fn to_local<P: Into<mint::Point3<f64>>>(
+        &self,
+        absolute: P,
+    ) -> mint::Point3<f32> {
+        let p = absolute.into();
+        [
+            (p.x - self.origin[0]) as f32,
+            (p.y - self.origin[1]) as f32,
+            (p.z - self.origin[2]) as f32,
+        ].into()
+    }
+
+    /// Converts a position in the scene to an absolute position.
+    pub fn to_absolute<P: Into<mint::Point3<f32>>>(
+        &self,
+        local: P,
+    ) -> mint::Point3<f64> {
+        let p = local.into();
+        [
+            self.origin[0] + p.x as f64,
+            self.origin[1] + p.y as f64,
+            self.origin[2] + p.z as f64,
+        ].into()
+    }
+
+    /// Rebases the scene if `focus`, usually the camera or the player, is
+    /// further than `threshold` from the origin, moving the origin to it.
+    ///
+    /// Returns the offset that was subtracted from all positions, or `None`
+    /// if the scene wasn't moved. Positions kept outside of the scene, such
+    /// as the target of orbit controls, need to be shifted by the caller.
+    pub fn update<T: Object>(
+        &mut self,
+        sync: &mut SyncGuard,
+        focus: &T,
+    ) -> Option<mint::Vector3<f32>> {
+        let position: [f32; 3] = sync.resolve_world(focus).transform.position.into();
+        let position = Vector3::from(position);
+        if position.magnitude() <= self.threshold {
+            return None;
+        }
+        self.shift(sync, position);
+        Some([position.x, position.y, position.z].into())
+    }
+
+    /// Moves the origin by `offset`, subtracting it from the position of
+    /// every object at the root of the scene. Cameras in the scene move
+    /// along, so the rendered image doesn't change.
+    pub fn shift<V: Into<mint::Vector3<f32>>>(
+        &mut self,
+        sync: &mut SyncGuard,
+        offset: V,
+    ) {
+        let offset = Vector3::from(offset.into());
+        self.origin[0] += offset.x as f64;
+        self.origin[1] += offset.y as f64;
+        self.origin[2] += offset.z as f64;
+        let hub = &mut sync.hub;
+        let mut child = sync.scene.first_child.clone();
+        while let Some(ptr) = child {
+            let node = &mut hub.nodes[&ptr];
+            node.transform.disp -= offset;
+            child = node.next_sibling.clone();
+        }
+    }
+}
+
+impl Default for FloatingOrigin {
+    fn default() -> Self {
+        FloatingOrigin::new()
+    }
+}
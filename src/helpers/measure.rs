@@ -0,0 +1,289 @@
+//! Measurements annotated in the scene, like the dimensions of CAD drawings.
+//!
+//! A [`Measurement`] draws the measured distance or angle with lines in world
+//! space, and a label showing its value at the end of a short leader line.
+//! The label and the leader keep the same size on screen at any distance,
+//! once [`update`] is called before each frame.
+//!
+//! Points usually come from ray casting at the mouse position, against
+//! meshes created with [`Factory::set_raycast_triangles`] enabled:
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! use three::helpers::measure::{Measurement, Style};
+//! use three::raycast::Raycaster;
+//!
+//! let font = window.factory.load_font_karla();
+//! let mut points = Vec::new();
+//! let mut measurements = Vec::new();
+//!
+//! while window.update() {
+//!     if window.input.hit(three::MOUSE_LEFT) {
+//!         let hit = {
+//!             let sync = window.scene.sync_guard();
+//!             let ndc = window.input.mouse_pos_ndc();
+//!             let raycaster = Raycaster::from_camera(&camera, &sync, ndc, window.renderer.aspect_ratio());
+//!             raycaster.intersect_scene(&sync).into_iter().next()
+//!         };
+//!         if let Some(hit) = hit {
+//!             points.push(hit.point);
+//!             if points.len() == 2 {
+//!                 let m = Measurement::distance(&mut window.factory, &font, points[0], points[1], &Style::default());
+//!                 window.scene.add(&m);
+//!                 measurements.push(m);
+//!                 points.clear();
+//!             }
+//!         }
+//!     }
+//!     {
+//!         let sync = window.scene.sync_guard();
+//!         for m in &measurements {
+//!             m.update(&sync, &camera, &window.renderer);
+//!         }
+//!     }
+//!     window.render(&camera);
+//! }
+//! ```
+//!
+//! [`Measurement`]: struct.Measurement.html
+//! [`update`]: struct.Measurement.html#method.update
+//! [`Factory::set_raycast_triangles`]: ../../struct.Factory.html#method.set_raycast_triangles
+
+use cgmath::{InnerSpace, Quaternion, Rotation, Vector3};
+use mint;
+
+use camera::Camera;
+use color::{self, Color};
+use factory::Factory;
+use geometry::Geometry;
+use hub::{MaterialParam, Operation};
+use material;
+use mesh::Mesh;
+use object::{Group, Object};
+use render::Renderer;
+use scene::SyncGuard;
+use text::Font;
+
+/// Number of segments of the arcs of angle measurements.
+const ARC_SEGMENTS: usize = 24;
+
+/// Look of a [`Measurement`](struct.Measurement.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Style {
+    /// Color of the lines and the label.
+    ///
+    /// Default: `WHITE`.
+    pub color: Color,
+
+    /// Height of the label in pixels.
+    ///
+    /// Default: `18.0`.
+    pub label_height: f32,
+
+    /// Length of the leader line between the measured feature and the
+    /// label, in pixels.
+    ///
+    /// Default: `24.0`.
+    pub leader_length: f32,
+
+    /// Number of decimals of the shown value.
+    ///
+    /// Default: `2`.
+    pub decimals: usize,
+
+    /// Unit appended to distances, such as `" m"`. Angles are shown in
+    /// degrees.
+    ///
+    /// Default: empty.
+    pub unit: String,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            color: color::WHITE,
+            label_height: 18.0,
+            leader_length: 24.0,
+            decimals: 2,
+            unit: String::new(),
+        }
+    }
+}
+
+/// A measured distance or angle with its annotation.
+///
+/// The points are in the space of the parent of the measurement, so it
+/// should be added to the root of the scene for world space points. See the
+/// [module documentation](index.html) for an example.
+#[derive(Clone, Debug)]
+pub struct Measurement {
+    group: Group,
+    lines: Vec<Mesh>,
+    leader: Mesh,
+    label: Mesh,
+    anchor: Vector3<f32>,
+    value: f32,
+    style: Style,
+}
+three_object!(Measurement::group);
+
+impl Measurement {
+    /// Measures the distance between `a` and `b`, with a line between them
+    /// and the label above its middle.
+    pub fn distance<P: Into<mint::Point3<f32>>>(
+        factory: &mut Factory,
+        font: &Font,
+        a: P,
+        b: P,
+        style: &Style,
+    ) -> Self {
+        let (a, b) = (a.into(), b.into());
+        let (va, vb) = (Vector3::new(a.x, a.y, a.z), Vector3::new(b.x, b.y, b.z));
+        let value = (vb - va).magnitude();
+        let text = format!("{:.*}{}", style.decimals, value, style.unit);
+        Measurement::new(factory, font, vec![vec![a, b]], (va + vb) * 0.5, value, &text, style)
+    }
+
+    /// Measures the angle in degrees at `vertex` between the edges going to
+    /// `a` and to `b`, with the edges and an arc between them, and the label
+    /// above the middle of the arc.
+    pub fn angle<P: Into<mint::Point3<f32>>>(
+        factory: &mut Factory,
+        font: &Font,
+        vertex: P,
+        a: P,
+        b: P,
+        style: &Style,
+    ) -> Self {
+        let (vertex, a, b) = (vertex.into(), a.into(), b.into());
+        let v = Vector3::new(vertex.x, vertex.y, vertex.z);
+        let (ea, eb) = (Vector3::new(a.x, a.y, a.z) - v, Vector3::new(b.x, b.y, b.z) - v);
+        let (u, w) = (ea.normalize(), eb.normalize());
+        let theta = u.dot(w).max(-1.0).min(1.0).acos();
+        let radius = 0.5 * ea.magnitude().min(eb.magnitude());
+
+        // slerp between the edge directions, at the radius of the arc
+        let arc_point = |t: f32| {
+            let s = theta.sin();
+            let direction = if s > 1e-4 {
+                (u * ((1.0 - t) * theta).sin() + w * (t * theta).sin()) / s
+            } else {
+                (u * (1.0 - t) + w * t).normalize()
+            };
+            v + direction * radius
+        };
+        let arc = (0 ..= ARC_SEGMENTS)
+            .map(|i| {
+                let p = arc_point(i as f32 / ARC_SEGMENTS as f32);
+                [p.x, p.y, p.z].into()
+            })
+            .collect();
+        let value = theta.to_degrees();
+        let text = format!("{:.*}\u{b0}", style.decimals, value);
+        Measurement::new(factory, font, vec![vec![a, vertex, b], arc], arc_point(0.5), value, &text, style)
+    }
+
+    fn new(
+        factory: &mut Factory,
+        font: &Font,
+        strips: Vec<Vec<mint::Point3<f32>>>,
+        anchor: Vector3<f32>,
+        value: f32,
+        text: &str,
+        style: &Style,
+    ) -> Self {
+        let group = factory.group();
+        let line_material = material::Line {
+            color: style.color,
+            .. Default::default()
+        };
+        let lines = strips
+            .into_iter()
+            .map(|points| factory.mesh(Geometry::with_line_strip(points), line_material.clone()))
+            .collect::<Vec<_>>();
+        // a unit segment along Y, turned and scaled to the camera each frame
+        let leader = factory.mesh(
+            Geometry::with_line_strip(vec![[0.0, 0.0, 0.0].into(), [0.0, 1.0, 0.0].into()]),
+            line_material,
+        );
+        let label = factory.text_billboard(font, text);
+        label.as_ref().send(Operation::SetMaterialParam(MaterialParam::Color(style.color)));
+        for line in &lines {
+            group.add(line);
+        }
+        group.add(&leader);
+        group.add(&label);
+        leader.set_position([anchor.x, anchor.y, anchor.z]);
+        label.set_position([anchor.x, anchor.y, anchor.z]);
+
+        Measurement {
+            group,
+            lines,
+            leader,
+            label,
+            anchor,
+            value,
+            style: style.clone(),
+        }
+    }
+
+    /// The group holding the lines and the label.
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// The measured distance, or angle in degrees.
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+
+    /// The lines showing the measured feature.
+    pub fn lines(&self) -> &[Mesh] {
+        &self.lines
+    }
+
+    /// The label showing the value.
+    pub fn label(&self) -> &Mesh {
+        &self.label
+    }
+
+    /// Turns the leader line towards the top of the screen, and scales it
+    /// and the label to their size in pixels as seen by `camera`.
+    ///
+    /// The annotation is hidden while its anchor is behind the camera.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    pub fn update(
+        &self,
+        sync: &SyncGuard,
+        camera: &Camera,
+        renderer: &Renderer,
+    ) {
+        let orientation = Quaternion::from(sync.resolve_world(camera).transform.orientation);
+        let up = orientation.rotate_vector(Vector3::unit_y());
+        let anchor = self.anchor;
+        let above = anchor + up;
+        let screen = |p: Vector3<f32>| renderer.world_to_screen(sync, camera, [p.x, p.y, p.z]);
+        let pixels_per_unit = match (screen(anchor), screen(above)) {
+            (Some(a), Some(b)) => ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt(),
+            _ => 0.0,
+        };
+        if pixels_per_unit <= 0.0 {
+            self.leader.set_visible(false);
+            self.label.set_visible(false);
+            return;
+        }
+        self.leader.set_visible(true);
+        self.label.set_visible(true);
+
+        let units = 1.0 / pixels_per_unit;
+        let leader_length = self.style.leader_length * units;
+        self.leader.set_transform([anchor.x, anchor.y, anchor.z], orientation, leader_length.max(1e-6));
+        let label_height = self.style.label_height * units;
+        let position = anchor + up * (leader_length + 0.6 * label_height);
+        self.label.set_position([position.x, position.y, position.z]);
+        self.label.set_scale(label_height);
+    }
+}
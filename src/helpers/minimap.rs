@@ -0,0 +1,230 @@
+//! Top-down map of the scene drawn on the HUD.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let level = window.factory.group();
+//! # let player = window.factory.group();
+//! # let icon = window.factory.load_texture("player.png");
+//! use three::helpers::Minimap;
+//!
+//! let mut minimap = Minimap::new(&mut window.factory, [256, 256]);
+//! minimap.position = [-16, 16];
+//! minimap.set_extent(40.0);
+//! minimap.add_layer(&level);
+//! minimap.follow(&player);
+//! minimap.add_marker(&player, &icon, [16, 16]);
+//!
+//! while window.update() {
+//!     minimap.update(&mut window.renderer, &mut window.scene);
+//!     window.render(&camera);
+//! }
+//! ```
+
+use mint;
+
+use camera::{Camera, Projection};
+use factory::Factory;
+use object::{Base, Object};
+use render::{DebugQuadHandle, RenderTarget, Renderer};
+use scene::Scene;
+use texture::Texture;
+
+use std::collections::HashSet;
+
+/// An icon drawn over the minimap at the position of an object.
+struct Marker {
+    object: Base,
+    texture: Texture<[f32; 4]>,
+    size: [u16; 2],
+    quad: Option<DebugQuadHandle>,
+}
+
+/// Renders a top-down orthographic view of the scene into a HUD quad.
+///
+/// The map looks down the negative Y axis, with the negative Z axis pointing
+/// up on screen. See the [module documentation](index.html) for an example.
+pub struct Minimap {
+    camera: Camera,
+    target: RenderTarget,
+    quad: Option<(DebugQuadHandle, [i16; 2])>,
+    layers: Vec<Base>,
+    markers: Vec<Marker>,
+    follow: Option<Base>,
+    center: mint::Point3<f32>,
+    extent: f32,
+    /// Position of the map on screen in pixels from the top-left corner.
+    /// Negative coordinates are measured from the opposite edges.
+    ///
+    /// Default: `(16, 16)`.
+    pub position: [i16; 2],
+    /// Height of the map camera above the center point.
+    ///
+    /// Default: `100.0`.
+    pub height: f32,
+}
+
+impl Minimap {
+    /// Creates a minimap rendered at `size` pixels.
+    pub fn new(
+        factory: &mut Factory,
+        size: [u16; 2],
+    ) -> Self {
+        let extent = 20.0;
+        let height = 100.0;
+        Minimap {
+            camera: factory.orthographic_camera([0.0, 0.0], extent, 0.0 .. 2.0 * height),
+            target: factory.render_target(size[0], size[1]),
+            quad: None,
+            layers: Vec::new(),
+            markers: Vec::new(),
+            follow: None,
+            center: [0.0, 0.0, 0.0].into(),
+            extent,
+            position: [16, 16],
+            height,
+        }
+    }
+
+    /// Shows `root` and its children on the map. If no layers are added,
+    /// the whole scene is shown.
+    pub fn add_layer<T: Object>(
+        &mut self,
+        root: &T,
+    ) {
+        self.layers.push(root.upcast());
+    }
+
+    /// Removes all the layers, showing the whole scene.
+    pub fn clear_layers(&mut self) {
+        self.layers.clear();
+    }
+
+    /// Keeps the map centered on `object`.
+    pub fn follow<T: Object>(
+        &mut self,
+        object: &T,
+    ) {
+        self.follow = Some(object.upcast());
+    }
+
+    /// Centers the map on a fixed point, stopping to follow any object.
+    pub fn set_center<P: Into<mint::Point3<f32>>>(
+        &mut self,
+        center: P,
+    ) {
+        self.follow = None;
+        self.center = center.into();
+    }
+
+    /// Sets the zoom as the distance in world units from the center of the
+    /// map to its top edge.
+    pub fn set_extent(
+        &mut self,
+        extent: f32,
+    ) {
+        self.extent = extent;
+    }
+
+    /// Draws `texture` over the map at the position of `object`.
+    pub fn add_marker<T: Object>(
+        &mut self,
+        object: &T,
+        texture: &Texture<[f32; 4]>,
+        size: [u16; 2],
+    ) {
+        self.markers.push(Marker {
+            object: object.upcast(),
+            texture: texture.clone(),
+            size,
+            quad: None,
+        });
+    }
+
+    /// Removes the markers of `object`.
+    pub fn remove_marker<T: Object>(
+        &mut self,
+        object: &T,
+    ) {
+        let base = object.as_ref();
+        self.markers.retain(|marker| &marker.object != base);
+    }
+
+    /// The texture the map is rendered into.
+    pub fn texture(&self) -> &Texture<[f32; 4]> {
+        self.target.texture()
+    }
+
+    /// Renders the map and places the markers. Call once per frame before
+    /// rendering the main view.
+    pub fn update(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &mut Scene,
+    ) {
+        let (only, marker_positions) = {
+            let sync = scene.sync_guard();
+            if let Some(ref object) = self.follow {
+                self.center = sync.resolve_world(object).transform.position;
+            }
+            let only = if self.layers.is_empty() {
+                None
+            } else {
+                let mut set = HashSet::new();
+                for layer in &self.layers {
+                    sync.hub.collect_subtree(&layer.node, &mut set);
+                }
+                Some(set)
+            };
+            let positions: Vec<_> = self.markers
+                .iter()
+                .map(|marker| sync.resolve_world(&marker.object).transform.position)
+                .collect();
+            (only, positions)
+        };
+
+        let center = self.center;
+        self.camera.set_projection(Projection::orthographic(
+            [0.0, 0.0],
+            self.extent,
+            0.0 .. 2.0 * self.height,
+        ));
+        self.camera.look_at(
+            [center.x, center.y + self.height, center.z],
+            center,
+            Some([0.0, 0.0, -1.0].into()),
+        );
+        renderer.render_offscreen(scene, &self.camera, &self.target, only);
+
+        let size = self.target.size();
+        if self.quad.as_ref().map(|&(_, position)| position) != Some(self.position) {
+            let quad = renderer.texture_quad(self.target.texture(), self.position, size);
+            self.quad = Some((quad, self.position));
+        }
+
+        // place the markers relative to the top-left corner of the map
+        let screen = renderer.map_from_ndc([1.0, -1.0]);
+        let left = if self.position[0] >= 0 {
+            self.position[0] as f32
+        } else {
+            screen.x + self.position[0] as f32 - size[0] as f32
+        };
+        let top = if self.position[1] >= 0 {
+            self.position[1] as f32
+        } else {
+            screen.y + self.position[1] as f32 - size[1] as f32
+        };
+        let aspect = size[0] as f32 / size[1] as f32;
+        for (marker, position) in self.markers.iter_mut().zip(marker_positions) {
+            let x = (position.x - center.x) / (self.extent * aspect);
+            let y = (center.z - position.z) / self.extent;
+            marker.quad = if x.abs() <= 1.0 && y.abs() <= 1.0 {
+                let px = left + 0.5 * (x + 1.0) * size[0] as f32 - 0.5 * marker.size[0] as f32;
+                let py = top + 0.5 * (1.0 - y) * size[1] as f32 - 0.5 * marker.size[1] as f32;
+                Some(renderer.texture_quad(&marker.texture, [px as i16, py as i16], marker.size))
+            } else {
+                None
+            };
+        }
+    }
+}
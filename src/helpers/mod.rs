@@ -0,0 +1,112 @@
+//! Higher-level helpers built on top of the core engine.
+//!
+//! ### Screen anchors
+//!
+//!  * Keep UI elements positioned over objects in the 3D world.
+//!  * Optionally clamp off-screen targets to the screen edges, with a direction
+//!    for drawing indicator arrows.
+//!
+//! ### Floating origin
+//!
+//!  * Shifts the scene back around the viewer when it moves far away, to keep
+//!    `f32` positions precise in very large worlds.
+//!  * Keeps the absolute position of the origin in double precision.
+//!
+//! ### Measurements
+//!
+//!  * Measures distances between points and angles between edges.
+//!  * Annotates them with leader lines and labels of a constant size on
+//!    screen, like the dimensions of CAD drawings.
+//!
+//! ### Minimap
+//!
+//!  * Renders a top-down view of selected parts of the scene into a HUD quad.
+//!  * Draws marker icons over the map at the position of objects.
+//!
+//! ### Pixel camera
+//!
+//!  * Maps one world unit to a whole number of pixels, for crisp 2D games.
+//!  * Letterboxes a virtual resolution and snaps sprites to the pixel grid.
+//!
+//! ### Material previews
+//!
+//!  * Renders materials on a lit sphere or plane into an offscreen target.
+//!  * Saves the result to disk, for thumbnails in asset browsers.
+//!
+//! ### Environment probes
+//!
+//!  * Captures the scene in every direction from a point into a cubemap.
+//!  * Saves captures to KTX files and loads them back as environments.
+//!
+//! ### Studio lighting
+//!
+//!  * Sets up a three-point light rig that orbits around the origin.
+//!  * Generates gradient skyboxes with matching ambient lighting.
+//!
+//! ### Day cycle
+//!
+//!  * Computes the direction of the sun from the time of day and latitude.
+//!  * Drives a sun light and a gradient sky from daylight through sunset
+//!    into the night.
+//!
+//! ### Transform snapshots
+//!
+//!  * Buffers timestamped transforms of remote objects, for networked games.
+//!  * Interpolates between them, and extrapolates when they stop arriving.
+
+/// Tracking of world-space objects in screen space.
+pub mod anchor;
+
+/// Rebasing of very large scenes.
+pub mod floating_origin;
+
+/// Distance and angle annotations.
+pub mod measure;
+
+/// Top-down minimap.
+pub mod minimap;
+
+/// Pixel-perfect 2D camera.
+pub mod pixel_camera;
+
+/// Material thumbnails.
+pub mod preview;
+
+/// Baked environment probes.
+pub mod probe;
+
+/// Default lighting and environments.
+pub mod studio;
+
+/// Interpolation of networked transforms.
+pub mod snapshot;
+
+/// Sun and sky over the day.
+pub mod sun;
+
+#[doc(inline)]
+pub use self::anchor::{AnchorPosition, ScreenAnchor};
+
+#[doc(inline)]
+pub use self::floating_origin::FloatingOrigin;
+
+#[doc(inline)]
+pub use self::minimap::Minimap;
+
+#[doc(inline)]
+pub use self::pixel_camera::PixelCamera;
+
+#[doc(inline)]
+pub use self::preview::{MaterialPreview, PreviewShape};
+
+#[doc(inline)]
+pub use self::probe::CubeCapture;
+
+#[doc(inline)]
+pub use self::studio::{procedural_skybox, studio_lighting, Environment, StudioLighting};
+
+#[doc(inline)]
+pub use self::snapshot::{Snapshot, SnapshotBuffer};
+
+#[doc(inline)]
+pub use self::sun::{sun_direction, DayCycle};
@@ -0,0 +1,213 @@
+//! Pixel-perfect orthographic camera for 2D games.
+//!
+//! One world unit maps to one pixel, scaled up by a whole number so that
+//! every pixel of a sprite covers the same number of screen pixels. With a
+//! virtual resolution, the largest whole scale that fits the window is
+//! picked and the remaining area is covered by black letterbox bars.
+//!
+//! The camera lives in a group that is moved around like any other object.
+//! The group keeps its precise position, while the camera inside snaps to
+//! the pixel grid, so scrolling never blurs or shimmers.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let map = window.factory.load_texture("hero.png");
+//! # let hero = window.factory.sprite(three::material::Sprite { map });
+//! use three::Object;
+//! use three::helpers::PixelCamera;
+//!
+//! let mut pixel_camera = PixelCamera::new(&mut window.factory, -10.0 .. 10.0);
+//! pixel_camera.set_virtual_resolution(Some([320, 180]));
+//! window.scene.add(pixel_camera.group());
+//! window.scene.add(&hero);
+//!
+//! while window.update() {
+//!     // ... move the hero and the camera group around ...
+//!     pixel_camera.update(&window.renderer, &mut window.scene);
+//!     pixel_camera.snap_sprites(&mut window.scene.sync_guard());
+//!     window.render(pixel_camera.camera());
+//! }
+//! ```
+
+use mint;
+
+use camera::{Camera, Projection};
+use color;
+use factory::Factory;
+use geometry::Geometry;
+use hub::SubNode;
+use material::{self, Material};
+use mesh::Mesh;
+use object::{Group, Object};
+use render::Renderer;
+use scene::{Scene, SyncGuard};
+
+use std::ops;
+
+/// An orthographic camera mapping one world unit to a whole number of
+/// pixels, with optional letterboxing to a virtual resolution.
+///
+/// The camera looks down the negative Z axis with Y pointing up. See the
+/// [module documentation](index.html) for an example.
+pub struct PixelCamera {
+    group: Group,
+    view: Group,
+    camera: Camera,
+    bars: [Mesh; 4],
+    range: ops::Range<f32>,
+    virtual_size: Option<[u32; 2]>,
+    scale: u32,
+    /// Number of pixels per world unit when no virtual resolution is set.
+    ///
+    /// Default: 1.
+    pub zoom: u32,
+}
+
+impl PixelCamera {
+    /// Creates a pixel camera seeing objects within `range` of its Z
+    /// position, in the negative Z direction.
+    pub fn new(
+        factory: &mut Factory,
+        range: ops::Range<f32>,
+    ) -> Self {
+        let group = factory.group();
+        let view = factory.group();
+        let camera = factory.orthographic_camera([0.0, 0.0], 1.0, range.clone());
+        let mut bar = || {
+            let mesh = factory.mesh(
+                Geometry::plane(1.0, 1.0),
+                material::Basic {
+                    color: color::BLACK,
+                    .. Default::default()
+                },
+            );
+            mesh.set_visible(false);
+            view.add(&mesh);
+            mesh
+        };
+        let bars = [bar(), bar(), bar(), bar()];
+        view.add(&camera);
+        group.add(&view);
+        PixelCamera {
+            group,
+            view,
+            camera,
+            bars,
+            range,
+            virtual_size: None,
+            scale: 1,
+            zoom: 1,
+        }
+    }
+
+    /// The group holding the camera. Add it to the scene and move it to
+    /// scroll the view.
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// The camera to render the scene with.
+    pub fn camera(&self) -> &Camera {
+        &self.camera
+    }
+
+    /// Sets the resolution in world units that is always fully visible,
+    /// or `None` to show as much as the window fits at `zoom`.
+    pub fn set_virtual_resolution(
+        &mut self,
+        size: Option<[u32; 2]>,
+    ) {
+        self.virtual_size = size;
+    }
+
+    /// The number of pixels per world unit, as of the last update.
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Rounds the X and Y coordinates of `position` to whole world units,
+    /// the pixels of the virtual resolution.
+    pub fn snap<P: Into<mint::Point3<f32>>>(
+        &self,
+        position: P,
+    ) -> mint::Point3<f32> {
+        let p = position.into();
+        [p.x.round(), p.y.round(), p.z].into()
+    }
+
+    /// Rounds the local X and Y position of every sprite in the scene to
+    /// whole world units. Groups holding sprites need to stay at whole
+    /// positions for the sprites to end up on the pixel grid.
+    pub fn snap_sprites(
+        &self,
+        sync: &mut SyncGuard,
+    ) {
+        for node in sync.hub.nodes.iter_mut() {
+            if let SubNode::Visual(Material::Sprite(_), _, _) = node.sub_node {
+                let disp = &mut node.transform.disp;
+                disp.x = disp.x.round();
+                disp.y = disp.y.round();
+            }
+        }
+    }
+
+    /// Fits the projection and the letterbox bars to the size of the
+    /// renderer, and snaps the camera to the pixel grid. Call once per
+    /// frame before rendering.
+    pub fn update(
+        &mut self,
+        renderer: &Renderer,
+        scene: &mut Scene,
+    ) {
+        let size = renderer.size();
+        let (width, height) = (size.x.max(1.0) as u32, size.y.max(1.0) as u32);
+        self.scale = match self.virtual_size {
+            Some([vw, vh]) => (width / vw.max(1)).min(height / vh.max(1)).max(1),
+            None => self.zoom.max(1),
+        };
+        let scale = self.scale as f32;
+
+        // Odd window sizes put the center in the middle of a pixel, which
+        // is shifted to keep the pixel edges on the grid.
+        let center = [
+            (width % 2) as f32 * 0.5 / scale,
+            (height % 2) as f32 * 0.5 / scale,
+        ];
+        self.camera.set_projection(Projection::orthographic(
+            center,
+            0.5 * height as f32 / scale,
+            self.range.clone(),
+        ));
+
+        let position = scene.sync_guard().resolve_world(&self.group).transform.position;
+        self.view.set_position([
+            position.x.round() - position.x,
+            position.y.round() - position.y,
+            0.0,
+        ]);
+
+        match self.virtual_size {
+            Some([vw, vh]) => {
+                let (half_w, half_h) = (0.5 * vw as f32, 0.5 * vh as f32);
+                // bars are squares large enough to cover the whole window
+                let side = width.max(height) as f32 / scale + 2.0;
+                let z = -(self.range.start + 1.0e-3 * (self.range.end - self.range.start));
+                let offsets = [
+                    [-half_w - 0.5 * side, 0.0],
+                    [half_w + 0.5 * side, 0.0],
+                    [0.0, -half_h - 0.5 * side],
+                    [0.0, half_h + 0.5 * side],
+                ];
+                for (bar, offset) in self.bars.iter().zip(&offsets) {
+                    bar.set_transform([offset[0], offset[1], z], [0.0, 0.0, 0.0, 1.0], side);
+                    bar.set_visible(true);
+                }
+            }
+            None => {
+                for bar in &self.bars {
+                    bar.set_visible(false);
+                }
+            }
+        }
+    }
+}
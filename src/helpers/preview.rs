@@ -0,0 +1,143 @@
+//! Offscreen previews of materials, for thumbnails in asset browsers.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! use three::helpers::{MaterialPreview, PreviewShape};
+//!
+//! let mut preview = MaterialPreview::new(&mut window.factory, [128, 128]);
+//! let material = three::material::Phong {
+//!     color: 0xB03030,
+//!     glossiness: 40.0,
+//!     .. Default::default()
+//! };
+//! preview
+//!     .save(&mut window.renderer, material, PreviewShape::Sphere, "phong.png")
+//!     .unwrap();
+//! ```
+
+use image;
+
+use std::path::Path;
+
+use camera::Camera;
+use color;
+use factory::Factory;
+use geometry::Geometry;
+use light::{Ambient, Directional};
+use material::{self, Material};
+use mesh::Mesh;
+use object::Object;
+use render::{RenderTarget, Renderer};
+use scene::{Background, Scene};
+use texture::Texture;
+
+/// The geometry a material is previewed on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PreviewShape {
+    /// A sphere, showing the material under varying light directions.
+    Sphere,
+    /// A square facing the camera, showing the material flat.
+    Plane,
+}
+
+/// Renders materials applied to a lit sphere or plane into an offscreen
+/// target. See the [module documentation](index.html) for an example.
+///
+/// The preview has its own scene, which is never shown in the window.
+pub struct MaterialPreview {
+    scene: Scene,
+    camera: Camera,
+    target: RenderTarget,
+    sphere: Mesh,
+    plane: Mesh,
+    _lights: (Ambient, Directional),
+}
+
+impl MaterialPreview {
+    /// Creates a preview rendered at `size` pixels.
+    pub fn new(
+        factory: &mut Factory,
+        size: [u16; 2],
+    ) -> Self {
+        let mut scene = factory.scene();
+        scene.background = Background::Color(color::BLACK);
+
+        let camera = factory.perspective_camera(45.0, 0.1 .. 10.0);
+        camera.set_position([0.0, 0.0, 3.0]);
+        scene.add(&camera);
+
+        let ambient = factory.ambient_light(color::WHITE, 0.3);
+        scene.add(&ambient);
+        let directional = factory.directional_light(color::WHITE, 0.7);
+        directional.look_at([1.0, 2.0, 3.0], [0.0, 0.0, 0.0], None);
+        scene.add(&directional);
+
+        let placeholder = material::Basic::default();
+        let sphere = factory.mesh(Geometry::uv_sphere(1.0, 32, 32), placeholder.clone());
+        scene.add(&sphere);
+        let plane = factory.mesh(Geometry::plane(2.0, 2.0), placeholder);
+        plane.set_visible(false);
+        scene.add(&plane);
+
+        MaterialPreview {
+            scene,
+            camera,
+            target: factory.render_target(size[0], size[1]),
+            sphere,
+            plane,
+            _lights: (ambient, directional),
+        }
+    }
+
+    /// Sets the color behind the previewed shape.
+    ///
+    /// Default: black.
+    pub fn set_background(
+        &mut self,
+        color: color::Color,
+    ) {
+        self.scene.background = Background::Color(color);
+    }
+
+    /// The texture holding the last rendered preview, which can be shown
+    /// directly, for example with [`Renderer::texture_quad`].
+    ///
+    /// [`Renderer::texture_quad`]: ../render/struct.Renderer.html#method.texture_quad
+    pub fn texture(&self) -> &Texture<[f32; 4]> {
+        self.target.texture()
+    }
+
+    /// Renders `material` applied to `shape` and returns the resulting image.
+    pub fn render<M: Into<Material>>(
+        &mut self,
+        renderer: &mut Renderer,
+        material: M,
+        shape: PreviewShape,
+    ) -> image::RgbaImage {
+        let (shown, hidden) = match shape {
+            PreviewShape::Sphere => (&self.sphere, &self.plane),
+            PreviewShape::Plane => (&self.plane, &self.sphere),
+        };
+        shown.set_material(material);
+        shown.set_visible(true);
+        hidden.set_visible(false);
+        renderer.render_to(&self.scene, &self.camera, &self.target);
+        renderer.read_target(&self.target)
+    }
+
+    /// Renders `material` applied to `shape` and saves the image to `path`.
+    /// The image format is derived from the file extension.
+    pub fn save<M, P>(
+        &mut self,
+        renderer: &mut Renderer,
+        material: M,
+        shape: PreviewShape,
+        path: P,
+    ) -> image::ImageResult<()>
+    where
+        M: Into<Material>,
+        P: AsRef<Path>,
+    {
+        self.render(renderer, material, shape).save(path)
+    }
+}
@@ -0,0 +1,216 @@
+//! Environments captured from a point of the scene and baked to files.
+//!
+//! Capturing renders the scene six times, once for each face of a cubemap,
+//! which is too slow to repeat on every launch of an application. Probes can
+//! instead be captured by a tool step, saved as KTX files, and loaded back
+//! as skyboxes with matching ambient lighting and reflections.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! use three::helpers::CubeCapture;
+//!
+//! let capture = CubeCapture::capture(
+//!     &mut window.factory,
+//!     &mut window.renderer,
+//!     &window.scene,
+//!     [0.0, 1.5, 0.0],
+//!     256,
+//!     0.1 .. 100.0,
+//! );
+//! capture.save_ktx("hall.ktx").unwrap();
+//!
+//! // later on, without capturing again
+//! let environment = CubeCapture::load_ktx("hall.ktx")
+//!     .unwrap()
+//!     .to_environment(&mut window.factory);
+//! window.scene.light_probe = Some(environment.light_probe);
+//! window.scene.reflection_probe = Some(environment.reflection_probe);
+//! ```
+
+use image;
+use mint;
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::ops;
+use std::path::Path;
+
+use factory::Factory;
+use helpers::studio::Environment;
+use light::LightProbe;
+use object::Object;
+use render::Renderer;
+use scene::Scene;
+
+/// The file identifier of KTX 1.
+const KTX_IDENTIFIER: [u8; 12] = [0xAB, 0x4B, 0x54, 0x58, 0x20, 0x31, 0x31, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A];
+const KTX_ENDIANNESS: u32 = 0x0403_0201;
+const GL_UNSIGNED_BYTE: u32 = 0x1401;
+const GL_RGBA: u32 = 0x1908;
+const GL_RGBA8: u32 = 0x8058;
+const GL_SRGB8_ALPHA8: u32 = 0x8C43;
+
+/// Viewing direction and up vector of the camera for each cubemap face, in
+/// the order `+X, -X, +Y, -Y, +Z, -Z`.
+const FACES: [([f32; 3], [f32; 3]); 6] = [
+    ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([-1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+    ([0.0, 1.0, 0.0], [0.0, 0.0, -1.0]),
+    ([0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+    ([0.0, 0.0, 1.0], [0.0, 1.0, 0.0]),
+    ([0.0, 0.0, -1.0], [0.0, 1.0, 0.0]),
+];
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The six square faces of a cubemap in sRGB, in the order
+/// `+X, -X, +Y, -Y, +Z, -Z`. See the [module documentation](index.html) for
+/// an example.
+#[derive(Clone, Debug)]
+pub struct CubeCapture {
+    faces: Vec<image::RgbaImage>,
+}
+
+impl CubeCapture {
+    /// Creates a capture from six square faces of the same size.
+    ///
+    /// # Panics
+    /// Panics if there aren't six faces, or if they aren't square and of the
+    /// same size.
+    pub fn from_faces(faces: Vec<image::RgbaImage>) -> Self {
+        assert_eq!(faces.len(), 6, "A cubemap needs six faces");
+        let (width, height) = faces[0].dimensions();
+        assert!(
+            width == height && faces.iter().all(|face| face.dimensions() == (width, height)),
+            "Cubemap faces need to be square and of the same size",
+        );
+        CubeCapture { faces }
+    }
+
+    /// Renders `scene` in every direction from `position` into faces of
+    /// `size` pixels, drawing what lies within `range` of it.
+    pub fn capture<P: Into<mint::Point3<f32>>>(
+        factory: &mut Factory,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        position: P,
+        size: u16,
+        range: ops::Range<f32>,
+    ) -> Self {
+        let position = position.into();
+        let camera = factory.perspective_camera(90.0, range);
+        let target = factory.render_target(size, size);
+        let faces = FACES
+            .iter()
+            .map(|&(forward, up)| {
+                let look = [position.x + forward[0], position.y + forward[1], position.z + forward[2]];
+                camera.look_at(position, look, Some(up.into()));
+                renderer.render_to(scene, &camera, &target);
+                // cubemap faces are seen from the inside, which mirrors them
+                let mut face = image::imageops::flip_horizontal(&renderer.read_target(&target));
+                for pixel in face.pixels_mut() {
+                    pixel[3] = 0xFF;
+                }
+                face
+            })
+            .collect();
+        CubeCapture { faces }
+    }
+
+    /// The width and height of each face in pixels.
+    pub fn size(&self) -> u32 {
+        self.faces[0].width()
+    }
+
+    /// The faces in the order `+X, -X, +Y, -Y, +Z, -Z`.
+    pub fn faces(&self) -> &[image::RgbaImage] {
+        &self.faces
+    }
+
+    /// Uploads the faces as a skybox and computes the ambient lighting and
+    /// reflections coming from them.
+    pub fn to_environment(
+        &self,
+        factory: &mut Factory,
+    ) -> Environment {
+        let faces = self.faces
+            .iter()
+            .map(|face| image::DynamicImage::ImageRgba8(face.clone()).to_rgb8())
+            .collect::<Vec<_>>();
+        Environment {
+            skybox: factory.cubemap_from_images(&self.faces),
+            light_probe: LightProbe::from_cube_faces(&faces),
+            reflection_probe: factory.reflection_probe_from_faces(&faces),
+        }
+    }
+
+    /// Saves the faces as an sRGB cubemap in the KTX format.
+    pub fn save_ktx<P: AsRef<Path>>(
+        &self,
+        path: P,
+    ) -> io::Result<()> {
+        let size = self.size();
+        let face_bytes = size * size * 4;
+        let header = [
+            KTX_ENDIANNESS,
+            GL_UNSIGNED_BYTE,
+            1,
+            GL_RGBA,
+            GL_SRGB8_ALPHA8,
+            GL_RGBA,
+            size,
+            size,
+            0,
+            0,
+            6,
+            1,
+            0,
+        ];
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        file.write_all(&KTX_IDENTIFIER)?;
+        for value in header.iter().chain(&[face_bytes]) {
+            file.write_all(&value.to_le_bytes())?;
+        }
+        // rows of four byte texels need no padding
+        for face in &self.faces {
+            file.write_all(face)?;
+        }
+        file.flush()
+    }
+
+    /// Loads a cubemap saved by [`save_ktx`](#method.save_ktx), or any KTX
+    /// cubemap of 8-bit RGBA texels. Only the first mipmap level is read.
+    pub fn load_ktx<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut data = Vec::new();
+        io::BufReader::new(fs::File::open(path)?).read_to_end(&mut data)?;
+        if data.len() < 68 || data[.. 12] != KTX_IDENTIFIER {
+            return Err(invalid_data("Not a KTX file"));
+        }
+        let word = |index: usize| {
+            let offset = 12 + 4 * index;
+            u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+        };
+        if word(0) != KTX_ENDIANNESS {
+            return Err(invalid_data("Big-endian KTX files are not supported"));
+        }
+        if word(1) != GL_UNSIGNED_BYTE || word(3) != GL_RGBA || ![GL_RGBA8, GL_SRGB8_ALPHA8].contains(&word(4)) {
+            return Err(invalid_data("Only 8-bit RGBA KTX files are supported"));
+        }
+        let (width, height) = (word(6), word(7));
+        if word(10) != 6 || word(9) > 1 || width != height || width == 0 {
+            return Err(invalid_data("The KTX file doesn't hold a square cubemap"));
+        }
+        let face_bytes = (width * height * 4) as usize;
+        let start = 64 + word(12) as usize + 4;
+        if data.len() < start + 6 * face_bytes {
+            return Err(invalid_data("The KTX file is truncated"));
+        }
+        let faces = data[start .. start + 6 * face_bytes]
+            .chunks(face_bytes)
+            .map(|bytes| image::RgbaImage::from_raw(width, height, bytes.to_vec()).unwrap())
+            .collect();
+        Ok(CubeCapture { faces })
+    }
+}
@@ -0,0 +1,204 @@
+//! Timestamped transform snapshots of remote objects.
+//!
+//! A networked game receives the transforms of remote objects at a lower
+//! rate than it renders, and with jitter. Buffering the snapshots and
+//! showing the objects slightly in the past allows to interpolate between
+//! two received states instead of jumping from one to the next. When the
+//! snapshots stop arriving, the motion is extrapolated for a short while.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let remote_player = window.factory.group();
+//! # fn receive() -> Option<(f64, three::Transform)> { None }
+//! use three::helpers::SnapshotBuffer;
+//!
+//! // show remote objects 100 ms in the past
+//! let delay = 0.1;
+//! let mut clock = 0.0;
+//! let mut snapshots = SnapshotBuffer::new();
+//!
+//! while window.update() {
+//!     clock += window.input.delta_time() as f64;
+//!     while let Some((time, transform)) = receive() {
+//!         snapshots.push(time, transform);
+//!     }
+//!     snapshots.apply(&remote_player, clock - delay);
+//!     snapshots.discard_before(clock - delay);
+//!     window.render(&camera);
+//! }
+//! ```
+
+use cgmath::{InnerSpace, One, Quaternion, Rotation, Vector3};
+use mint;
+
+use node::Transform;
+use object::Object;
+use scene::SyncGuard;
+
+/// The transform of an object at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// Time of the snapshot, in seconds.
+    pub time: f64,
+    /// Local transform of the object.
+    pub transform: Transform,
+}
+
+/// Snapshots of a single object, ordered by time.
+///
+/// See the [module documentation](index.html) for an example.
+#[derive(Clone, Debug)]
+pub struct SnapshotBuffer {
+    snapshots: Vec<Snapshot>,
+    /// Number of snapshots kept, dropping the oldest ones first.
+    ///
+    /// Default: 32.
+    pub capacity: usize,
+    /// Longest time past the newest snapshot the motion is extrapolated
+    /// for, in seconds. Later samples stay at the extrapolated transform.
+    ///
+    /// Default: 0.25.
+    pub max_extrapolation: f64,
+}
+
+impl SnapshotBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        SnapshotBuffer {
+            snapshots: Vec::new(),
+            capacity: 32,
+            max_extrapolation: 0.25,
+        }
+    }
+
+    /// Adds the `transform` of the object at `time`.
+    ///
+    /// Snapshots may arrive out of order. A snapshot at the time of an
+    /// existing one replaces it.
+    pub fn push(
+        &mut self,
+        time: f64,
+        transform: Transform,
+    ) {
+        let snapshot = Snapshot { time, transform };
+        match self.snapshots.iter().position(|s| s.time >= time) {
+            Some(i) if self.snapshots[i].time == time => self.snapshots[i] = snapshot,
+            Some(i) => self.snapshots.insert(i, snapshot),
+            None => self.snapshots.push(snapshot),
+        }
+        if self.snapshots.len() > self.capacity {
+            let excess = self.snapshots.len() - self.capacity;
+            self.snapshots.drain(.. excess);
+        }
+    }
+
+    /// Adds the current local transform of `object`, as a snapshot at `time`.
+    /// This is the sending side of [`push`](#method.push).
+    pub fn capture<T: Object>(
+        &mut self,
+        sync: &SyncGuard,
+        object: &T,
+        time: f64,
+    ) {
+        let transform = sync.resolve(object).transform;
+        self.push(time, transform);
+    }
+
+    /// Drops the snapshots that are no longer needed to sample at `time` or
+    /// later, keeping the last one before it.
+    pub fn discard_before(
+        &mut self,
+        time: f64,
+    ) {
+        let older = self.snapshots.iter().take_while(|s| s.time <= time).count();
+        if older > 1 {
+            self.snapshots.drain(.. older - 1);
+        }
+    }
+
+    /// Removes all snapshots.
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    /// The stored snapshots, ordered by time.
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// The most recent snapshot, if any.
+    pub fn latest(&self) -> Option<&Snapshot> {
+        self.snapshots.last()
+    }
+
+    /// Returns the transform at `time`, interpolated between the surrounding
+    /// snapshots or extrapolated from the last two.
+    ///
+    /// Times before the oldest snapshot return its transform. Returns `None`
+    /// if the buffer is empty.
+    pub fn sample(
+        &self,
+        time: f64,
+    ) -> Option<Transform> {
+        let first = self.snapshots.first()?;
+        if time <= first.time || self.snapshots.len() == 1 {
+            return Some(first.transform.clone());
+        }
+        let next = self.snapshots
+            .iter()
+            .position(|s| s.time >= time)
+            .unwrap_or(self.snapshots.len() - 1);
+        let (a, b) = (&self.snapshots[next - 1], &self.snapshots[next]);
+        let time = time.min(b.time + self.max_extrapolation);
+        let t = ((time - a.time) / (b.time - a.time)) as f32;
+        Some(blend(&a.transform, &b.transform, t))
+    }
+
+    /// Sets the transform of `object` to the one sampled at `time`.
+    /// Returns `false`, leaving the object untouched, if the buffer is empty.
+    pub fn apply<T: Object>(
+        &self,
+        object: &T,
+        time: f64,
+    ) -> bool {
+        match self.sample(time) {
+            Some(tf) => {
+                object.set_transform(tf.position, tf.orientation, tf.scale);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for SnapshotBuffer {
+    fn default() -> Self {
+        SnapshotBuffer::new()
+    }
+}
+
+/// Interpolates from `a` to `b` by `t`, extrapolating for `t > 1`.
+fn blend(
+    a: &Transform,
+    b: &Transform,
+    t: f32,
+) -> Transform {
+    let pos_a: mint::Vector3<f32> = a.position.into();
+    let pos_b: mint::Vector3<f32> = b.position.into();
+    let position = Vector3::from(pos_a).lerp(pos_b.into(), t);
+    let rot_a = Quaternion::from(a.orientation);
+    let rot_b = Quaternion::from(b.orientation);
+    let orientation = if t <= 1.0 {
+        rot_a.slerp(rot_b, t)
+    } else {
+        // continue the rotation from `a` to `b` past `b`
+        let step = rot_b * rot_a.invert();
+        Quaternion::one().slerp(step, t - 1.0) * rot_b
+    };
+    Transform {
+        position: [position.x, position.y, position.z].into(),
+        orientation: orientation.into(),
+        scale: a.scale + (b.scale - a.scale) * t,
+    }
+}
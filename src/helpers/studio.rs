@@ -0,0 +1,201 @@
+//! Ready-made lighting and environments, for quick viewers and prototypes.
+//!
+//! A freshly created scene has no lights and a black background, so models
+//! rendered with lit materials show up black. The studio rig is a classic
+//! three-point setup with a key, a fill and a rim light, together with a
+//! neutral gradient sky providing soft ambient lighting.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! let studio = three::helpers::studio_lighting(&mut window.factory);
+//! studio.apply(&mut window.scene);
+//!
+//! let mut angle = 0.0;
+//! while window.update() {
+//!     // slowly turn the lights around the model
+//!     angle += 0.2 * window.input.delta_time();
+//!     studio.orbit(angle);
+//!     window.render(&camera);
+//! }
+//! ```
+
+use cgmath::{Deg, Euler, Quaternion};
+use image;
+
+use color::{self, Color};
+use factory::Factory;
+use light::{Directional, LightProbe, ReflectionProbe};
+use object::{Group, Object};
+use scene::{Background, Scene};
+use texture::CubeMap;
+
+/// Size in pixels of each face of a procedural skybox.
+const SKYBOX_SIZE: u32 = 64;
+
+/// A skybox and the matching ambient lighting and reflections.
+#[derive(Clone, Debug)]
+pub struct Environment {
+    /// The cubemap to show as the scene background.
+    pub skybox: CubeMap<[f32; 4]>,
+    /// The ambient lighting coming from the skybox.
+    pub light_probe: LightProbe,
+    /// The reflections of the skybox on PBR materials.
+    pub reflection_probe: ReflectionProbe,
+}
+
+impl Environment {
+    /// Sets the skybox as the background of `scene`, and the probes as its
+    /// ambient lighting and reflections.
+    pub fn apply(
+        &self,
+        scene: &mut Scene,
+    ) {
+        scene.background = Background::Skybox(self.skybox.clone());
+        scene.light_probe = Some(self.light_probe.clone());
+        scene.reflection_probe = Some(self.reflection_probe.clone());
+    }
+}
+
+/// Creates a skybox with a vertical gradient from `ground` below the
+/// horizon, through `horizon`, to `sky` straight above.
+pub fn procedural_skybox(
+    factory: &mut Factory,
+    sky: Color,
+    horizon: Color,
+    ground: Color,
+) -> Environment {
+    let [sky, horizon, ground] = [
+        color::to_linear_rgb(sky),
+        color::to_linear_rgb(horizon),
+        color::to_linear_rgb(ground),
+    ];
+    let mix = |a: [f32; 3], b: [f32; 3], t: f32| {
+        [
+            a[0] + (b[0] - a[0]) * t,
+            a[1] + (b[1] - a[1]) * t,
+            a[2] + (b[2] - a[2]) * t,
+        ]
+    };
+
+    let faces = (0 .. 6)
+        .map(|face| {
+            image::RgbImage::from_fn(SKYBOX_SIZE, SKYBOX_SIZE, |px, py| {
+                let u = 2.0 * (px as f32 + 0.5) / SKYBOX_SIZE as f32 - 1.0;
+                let v = 2.0 * (py as f32 + 0.5) / SKYBOX_SIZE as f32 - 1.0;
+                // only the vertical component of the direction matters
+                let y = match face {
+                    2 => 1.0,
+                    3 => -1.0,
+                    _ => -v,
+                };
+                let elevation = y / (1.0 + u * u + v * v).sqrt();
+                let rgb = if elevation >= 0.0 {
+                    mix(horizon, sky, elevation.sqrt())
+                } else {
+                    mix(horizon, ground, (-elevation).sqrt())
+                };
+                let c = color::from_linear_rgb(rgb);
+                image::Rgb([(c >> 16) as u8, (c >> 8) as u8, c as u8])
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let light_probe = LightProbe::from_cube_faces(&faces);
+    let reflection_probe = factory.reflection_probe_from_faces(&faces);
+    let images = faces
+        .into_iter()
+        .map(|face| image::DynamicImage::ImageRgb8(face).to_rgba8())
+        .collect::<Vec<_>>();
+    Environment {
+        skybox: factory.cubemap_from_images(&images),
+        light_probe,
+        reflection_probe,
+    }
+}
+
+/// A three-point light rig around the origin with a neutral environment.
+/// See the [module documentation](index.html) for an example.
+pub struct StudioLighting {
+    group: Group,
+    key: Directional,
+    fill: Directional,
+    rim: Directional,
+    environment: Environment,
+}
+
+impl StudioLighting {
+    /// The group holding the lights.
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// The main light, from the front right and above.
+    pub fn key(&self) -> &Directional {
+        &self.key
+    }
+
+    /// The dimmer light softening the shadows, from the front left.
+    pub fn fill(&self) -> &Directional {
+        &self.fill
+    }
+
+    /// The light from behind, outlining the silhouette.
+    pub fn rim(&self) -> &Directional {
+        &self.rim
+    }
+
+    /// The skybox and ambient lighting of the studio.
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// Adds the lights to `scene` and sets up its environment.
+    pub fn apply(
+        &self,
+        scene: &mut Scene,
+    ) {
+        scene.add(&self.group);
+        self.environment.apply(scene);
+    }
+
+    /// Turns the lights around the vertical axis by `angle` radians,
+    /// keeping their arrangement relative to each other.
+    pub fn orbit(
+        &self,
+        angle: f32,
+    ) {
+        let rotation: Quaternion<f32> = Euler::new(Deg(0.0), Deg(angle.to_degrees()), Deg(0.0)).into();
+        self.group.set_orientation(rotation);
+    }
+}
+
+/// Creates a three-point light rig with a neutral gray environment, which
+/// shows models of any size centered at the origin.
+pub fn studio_lighting(factory: &mut Factory) -> StudioLighting {
+    let group = factory.group();
+
+    let key = factory.directional_light(0xFFF4E5, 0.8);
+    key.look_at([3.0, 4.0, 5.0], [0.0, 0.0, 0.0], None);
+    group.add(&key);
+
+    let fill = factory.directional_light(0xE5EEFF, 0.35);
+    fill.look_at([-5.0, 1.5, 3.0], [0.0, 0.0, 0.0], None);
+    group.add(&fill);
+
+    let rim = factory.directional_light(color::WHITE, 0.5);
+    rim.look_at([0.0, 3.0, -5.0], [0.0, 0.0, 0.0], None);
+    group.add(&rim);
+
+    let mut environment = procedural_skybox(factory, 0xB4B9C0, 0x9A9A9A, 0x505050);
+    environment.light_probe.intensity = 0.6;
+    environment.reflection_probe.intensity = 0.6;
+
+    StudioLighting {
+        group,
+        key,
+        fill,
+        rim,
+        environment,
+    }
+}
@@ -0,0 +1,229 @@
+//! Sun light and sky following the time of day.
+//!
+//! The position of the sun is computed from the time of day, the latitude
+//! and the day of the year. It drives the direction, color and intensity of
+//! a directional light, together with a gradient sky whose ambient lighting
+//! fades from daylight through sunset colors into the night.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! let mut day = three::helpers::DayCycle::new(&mut window.factory, 6.0);
+//! day.latitude = 52.0;
+//! day.apply(&mut window.scene);
+//!
+//! let mut hours = 6.0;
+//! while window.update() {
+//!     // a full day passes in four minutes
+//!     hours = (hours + 0.1 * window.input.delta_time()) % 24.0;
+//!     day.set_time(&mut window.factory, &mut window.scene, hours);
+//!     window.render(&camera);
+//! }
+//! ```
+
+use cgmath::{InnerSpace, Vector3};
+use mint;
+
+use color::{self, Color};
+use factory::Factory;
+use helpers::studio::{procedural_skybox, Environment};
+use light::{Directional, Light};
+use object::Object;
+use scene::Scene;
+
+use std::f32::consts::PI;
+
+/// Change of the sun elevation in degrees after which the sky is generated
+/// again, since uploading it every frame would be wasteful.
+const SKY_UPDATE_DEGREES: f32 = 1.0;
+
+/// Colors of the sky and the sun at increasing sun elevations in degrees:
+/// `(elevation, sky, horizon, ground, sun)`.
+const KEYFRAMES: [(f32, Color, Color, Color, Color); 5] = [
+    (-18.0, 0x0B1026, 0x1B2440, 0x05070D, 0x3A4A80),
+    (-6.0, 0x1E2B5C, 0x6A4C6E, 0x151520, 0xA05A50),
+    (0.0, 0x4A6FA5, 0xFF8C4A, 0x3A302A, 0xFF7A3A),
+    (10.0, 0x5B8FD0, 0xF5C9A0, 0x5A5550, 0xFFD9A8),
+    (30.0, 0x3E7FD8, 0xB8D4F0, 0x6A6660, 0xFFF8EE),
+];
+
+/// Returns the unit direction towards the sun, with Y pointing up, X to the
+/// east and negative Z to the north.
+///
+/// `latitude` is in degrees, positive in the northern hemisphere, `hours` is
+/// the local solar time, with the sun highest at 12, and `day_of_year`
+/// counts from 0 on the 1st of January.
+pub fn sun_direction(
+    latitude: f32,
+    day_of_year: u32,
+    hours: f32,
+) -> mint::Vector3<f32> {
+    let declination = (-23.44f32).to_radians() * (2.0 * PI * (day_of_year as f32 + 10.0) / 365.0).cos();
+    let hour_angle = (15.0 * (hours - 12.0)).to_radians();
+    let latitude = latitude.to_radians();
+    let east = -declination.cos() * hour_angle.sin();
+    let north = latitude.cos() * declination.sin() - latitude.sin() * declination.cos() * hour_angle.cos();
+    let up = latitude.sin() * declination.sin() + latitude.cos() * declination.cos() * hour_angle.cos();
+    Vector3::new(east, up, -north).normalize().into()
+}
+
+/// Angle of `direction` above the horizon, in degrees.
+fn elevation(direction: mint::Vector3<f32>) -> f32 {
+    direction.y.max(-1.0).min(1.0).asin().to_degrees()
+}
+
+/// Sky and sun colors for the sun at `elevation` degrees above the horizon.
+fn colors(elevation: f32) -> [Color; 4] {
+    let last = KEYFRAMES.len() - 1;
+    let index = KEYFRAMES
+        .iter()
+        .position(|key| key.0 > elevation)
+        .unwrap_or(last + 1);
+    let (a, b, t) = match index {
+        0 => (0, 0, 0.0),
+        i if i > last => (last, last, 0.0),
+        i => {
+            let (lo, hi) = (KEYFRAMES[i - 1].0, KEYFRAMES[i].0);
+            (i - 1, i, (elevation - lo) / (hi - lo))
+        }
+    };
+    let mix = |x: Color, y: Color| {
+        let (x, y) = (color::to_linear_rgb(x), color::to_linear_rgb(y));
+        color::from_linear_rgb([
+            x[0] + (y[0] - x[0]) * t,
+            x[1] + (y[1] - x[1]) * t,
+            x[2] + (y[2] - x[2]) * t,
+        ])
+    };
+    let (ka, kb) = (KEYFRAMES[a], KEYFRAMES[b]);
+    [mix(ka.1, kb.1), mix(ka.2, kb.2), mix(ka.3, kb.3), mix(ka.4, kb.4)]
+}
+
+/// A directional sun light and a sky driven by the time of day.
+/// See the [module documentation](index.html) for an example.
+pub struct DayCycle {
+    sun: Directional,
+    environment: Environment,
+    sky_elevation: f32,
+    hours: f32,
+    /// Latitude in degrees, positive in the northern hemisphere.
+    ///
+    /// Default: 45.
+    pub latitude: f32,
+    /// Day of the year counting from 0, which sets how high the sun goes.
+    ///
+    /// Default: 172, the June solstice.
+    pub day_of_year: u32,
+    /// Intensity of the sun light when it is well above the horizon.
+    ///
+    /// Default: 1.
+    pub sun_intensity: f32,
+    /// Point the sun light is aimed at, which is the center of its shadow
+    /// projection.
+    ///
+    /// Default: the origin.
+    pub focus: mint::Point3<f32>,
+    /// Distance of the sun light from `focus`, which needs to cover the
+    /// near clipping plane of its shadow projection.
+    ///
+    /// Default: 50.
+    pub distance: f32,
+}
+
+impl DayCycle {
+    /// Creates the sun and the sky at `hours` local solar time.
+    pub fn new(
+        factory: &mut Factory,
+        hours: f32,
+    ) -> Self {
+        let (latitude, day_of_year) = (45.0, 172);
+        let sky_elevation = elevation(sun_direction(latitude, day_of_year, hours));
+        let [sky, horizon, ground, _] = colors(sky_elevation);
+        let mut cycle = DayCycle {
+            sun: factory.directional_light(color::WHITE, 1.0),
+            environment: procedural_skybox(factory, sky, horizon, ground),
+            sky_elevation,
+            hours,
+            latitude,
+            day_of_year,
+            sun_intensity: 1.0,
+            focus: [0.0, 0.0, 0.0].into(),
+            distance: 50.0,
+        };
+        cycle.update(factory);
+        cycle
+    }
+
+    /// The directional light of the sun.
+    pub fn sun(&self) -> &Directional {
+        &self.sun
+    }
+
+    /// The current sky and its ambient lighting.
+    pub fn environment(&self) -> &Environment {
+        &self.environment
+    }
+
+    /// The local solar time in hours.
+    pub fn time(&self) -> f32 {
+        self.hours
+    }
+
+    /// The direction towards the sun at the current time.
+    /// See [`sun_direction`](fn.sun_direction.html).
+    pub fn sun_direction(&self) -> mint::Vector3<f32> {
+        sun_direction(self.latitude, self.day_of_year, self.hours)
+    }
+
+    /// Adds the sun to `scene` and sets up its sky.
+    pub fn apply(
+        &self,
+        scene: &mut Scene,
+    ) {
+        scene.add(&self.sun);
+        self.environment.apply(scene);
+    }
+
+    /// Moves the sun to `hours` local solar time and updates the sky of
+    /// `scene` when it changed noticeably.
+    pub fn set_time(
+        &mut self,
+        factory: &mut Factory,
+        scene: &mut Scene,
+        hours: f32,
+    ) {
+        self.hours = hours;
+        if self.update(factory) {
+            self.environment.apply(scene);
+        }
+    }
+
+    /// Updates the sun light, and generates the sky again if needed.
+    /// Returns `true` if the sky changed.
+    fn update(
+        &mut self,
+        factory: &mut Factory,
+    ) -> bool {
+        let direction = self.sun_direction();
+        let elevation = elevation(direction);
+        let [sky, horizon, ground, sun] = colors(elevation);
+
+        // the sun fades out as it sets, leaving the sky to light the scene
+        let t = ((elevation + 2.0) / 12.0).max(0.0).min(1.0);
+        self.sun.set_color(sun);
+        self.sun.set_intensity(self.sun_intensity * t * t * (3.0 - 2.0 * t));
+        let position = [
+            self.focus.x + direction.x * self.distance,
+            self.focus.y + direction.y * self.distance,
+            self.focus.z + direction.z * self.distance,
+        ];
+        self.sun.look_at(position, self.focus, None);
+
+        if (elevation - self.sky_elevation).abs() < SKY_UPDATE_DEGREES {
+            return false;
+        }
+        self.sky_elevation = elevation;
+        self.environment = procedural_skybox(factory, sky, horizon, ground);
+        true
+    }
+}
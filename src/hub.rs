@@ -1,23 +1,25 @@
 #[cfg(feature = "audio")]
 use audio::{AudioData, Operation as AudioOperation};
 
-use camera::Projection;
+use camera::{Frustum, Projection, ViewOffset};
 use color::{self, Color};
 use light::{LightOperation, ShadowMap, ShadowProjection};
 use material::Material;
 use mesh::DynamicMesh;
 use node::{NodeInternal, NodePointer, TransformInternal};
 use object::Base;
-use render::{BackendResources, GpuData};
+use raycast::Aabb;
+use render::{BackendResources, DataCopy, GpuData};
 use skeleton::{Bone, Skeleton};
-use text::{Operation as TextOperation, TextData};
+use text::{Operation as TextOperation, TextData, DEPTH_ON_TOP};
 
 use cgmath::Transform;
 use froggy;
 use gfx;
 use mint;
 
-use std::{mem, ops};
+use std::ops;
+use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc;
 
@@ -36,6 +38,7 @@ pub(crate) struct LightData {
     pub intensity: f32,
     pub sub_light: SubLight,
     pub shadow: Option<(ShadowMap, ShadowProjection)>,
+    pub shadow_distance: f32,
 }
 
 #[derive(Clone, Debug)]
@@ -56,8 +59,12 @@ pub(crate) struct VisualData {
 pub(crate) enum SubNode {
     /// Camera for rendering a scene.
     Camera(Projection),
-    /// Group can be a parent to other objects.
-    Group { first_child: Option<NodePointer> },
+    /// Group can be a parent to other objects, kept as a list of siblings
+    /// with its last child for appending.
+    Group {
+        first_child: Option<NodePointer>,
+        last_child: Option<NodePointer>,
+    },
     #[cfg(feature = "audio")]
     /// Audio data.
     Audio(AudioData),
@@ -82,6 +89,7 @@ pub(crate) enum Operation {
     #[cfg(feature = "audio")]
     SetAudio(AudioOperation),
     SetVisible(bool),
+    SetOpacity(f32),
     SetLight(LightOperation),
     SetText(TextOperation),
     SetTransform(
@@ -89,13 +97,40 @@ pub(crate) enum Operation {
         Option<mint::Quaternion<f32>>,
         Option<f32>,
     ),
+    SetVelocity(
+        Option<mint::Vector3<f32>>,
+        Option<mint::Vector3<f32>>,
+    ),
     SetMaterial(Material),
+    SetMaterialParam(MaterialParam),
     SetSkeleton(Skeleton),
     SetShadow(ShadowMap, ShadowProjection),
     SetTexelRange(mint::Point2<i16>, mint::Vector2<u16>),
     SetWeights(Vec<f32>),
     SetName(String),
+    SetBounds(Option<Aabb>),
+    SetBillboard(bool),
+    SetInstances(Vec<mint::ColumnMatrix4<f32>>),
+    SetInstanceColors(Vec<Color>),
     SetProjection(Projection),
+    SetProjectionParam(ProjectionParam),
+}
+
+/// A single material parameter, used to animate materials without replacing them.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MaterialParam {
+    Color(Color),
+    EmissiveIntensity(f32),
+    Param(&'static str, f32),
+}
+
+/// A single camera projection parameter, used to animate zoom.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ProjectionParam {
+    FovY(f32),
+    ExtentY(f32),
+    Zoom(f32),
+    ViewOffset(Option<ViewOffset>),
 }
 
 pub(crate) type HubPtr = Arc<Mutex<Hub>>;
@@ -104,6 +139,7 @@ pub(crate) struct Hub {
     pub(crate) nodes: froggy::Storage<NodeInternal>,
     pub(crate) message_tx: mpsc::Sender<Message>,
     message_rx: mpsc::Receiver<Message>,
+    pub(crate) data_copies: Vec<DataCopy>,
 }
 
 impl<T: AsRef<Base>> ops::Index<T> for Hub {
@@ -128,6 +164,7 @@ impl Hub {
             nodes: froggy::Storage::new(),
             message_tx: tx,
             message_rx: rx,
+            data_copies: Vec::new(),
         };
         Arc::new(Mutex::new(hub))
     }
@@ -189,6 +226,9 @@ impl Hub {
                 Operation::SetVisible(visible) => {
                     self.nodes[&ptr].visible = visible;
                 }
+                Operation::SetOpacity(opacity) => {
+                    self.nodes[&ptr].opacity = opacity;
+                }
                 Operation::SetTransform(pos, rot, scale) => {
                     let transform = &mut self.nodes[&ptr].transform;
                     if let Some(pos) = pos {
@@ -202,26 +242,50 @@ impl Hub {
                         transform.scale = scale;
                     }
                 }
+                Operation::SetVelocity(linear, angular) => {
+                    let node = &mut self.nodes[&ptr];
+                    if let Some(linear) = linear {
+                        node.velocity = linear.into();
+                    }
+                    if let Some(angular) = angular {
+                        node.angular_velocity = angular.into();
+                    }
+                }
                 Operation::AddChild(child_ptr) => {
-                    let sibling = match self.nodes[&ptr].sub_node {
-                        SubNode::Group { ref mut first_child } =>
-                            mem::replace(first_child, Some(child_ptr.clone())),
+                    {
+                        let child = &mut self.nodes[&child_ptr];
+                        if child.next_sibling.is_some() {
+                            error!("Element {:?} is added to a group while still having old parent - {}",
+                                child.sub_node, "discarding siblings");
+                        }
+                    }
+                    let last = match self.nodes[&ptr].sub_node {
+                        SubNode::Group { ref last_child, .. } => last_child.clone(),
                         _ => unreachable!(),
                     };
-                    let child = &mut self.nodes[&child_ptr];
-                    if child.next_sibling.is_some() {
-                        error!("Element {:?} is added to a group while still having old parent - {}",
-                            child.sub_node, "discarding siblings");
+                    self.append_sibling(last.as_ref(), &child_ptr);
+                    match self.nodes[&ptr].sub_node {
+                        SubNode::Group { ref mut first_child, ref mut last_child } => {
+                            if first_child.is_none() {
+                                *first_child = Some(child_ptr.clone());
+                            }
+                            *last_child = Some(child_ptr);
+                        }
+                        _ => unreachable!(),
                     }
-                    child.next_sibling = sibling;
                 }
                 Operation::RemoveChild(child_ptr) => {
                     let next_sibling = self.nodes[&child_ptr].next_sibling.clone();
-                    let target_maybe = Some(child_ptr);
+                    let target_maybe = Some(child_ptr.clone());
                     let mut cur_ptr = match self.nodes[&ptr].sub_node {
-                        SubNode::Group { ref mut first_child } => {
+                        SubNode::Group { ref mut first_child, ref mut last_child } => {
                             if *first_child == target_maybe {
                                 *first_child = next_sibling;
+                                if *last_child == target_maybe {
+                                    *last_child = None;
+                                }
+                                // the unlinked node must not keep its siblings, to be added again
+                                self.nodes[&child_ptr].next_sibling = None;
                                 continue;
                             }
                             first_child.clone()
@@ -231,18 +295,24 @@ impl Hub {
 
                     //TODO: consolidate the code with `Scene::remove()`
                     loop {
-                        let node = match cur_ptr.take() {
-                            Some(next_ptr) => &mut self.nodes[&next_ptr],
+                        let prev_ptr = match cur_ptr.take() {
+                            Some(next_ptr) => next_ptr,
                             None => {
                                 error!("Unable to find child for removal");
                                 break;
                             }
                         };
-                        if node.next_sibling == target_maybe {
-                            node.next_sibling = next_sibling;
+                        if self.nodes[&prev_ptr].next_sibling == target_maybe {
+                            self.nodes[&child_ptr].next_sibling = None;
+                            self.nodes[&prev_ptr].next_sibling = next_sibling;
+                            if let SubNode::Group { ref mut last_child, .. } = self.nodes[&ptr].sub_node {
+                                if *last_child == target_maybe {
+                                    *last_child = Some(prev_ptr);
+                                }
+                            }
                             break;
                         }
-                        cur_ptr = node.next_sibling.clone(); //TODO: avoid clone
+                        cur_ptr = self.nodes[&prev_ptr].next_sibling.clone(); //TODO: avoid clone
                     }
                 }
                 Operation::SetLight(operation) => {
@@ -269,6 +339,37 @@ impl Hub {
                         _ => unreachable!()
                     }
                 }
+                Operation::SetMaterialParam(param) => {
+                    fn set_param(
+                        material: &mut Material,
+                        param: MaterialParam,
+                    ) {
+                        match param {
+                            MaterialParam::Color(color) => material.set_color(color),
+                            MaterialParam::EmissiveIntensity(intensity) => {
+                                material.set_emissive_intensity(intensity)
+                            }
+                            MaterialParam::Param(name, value) => material.set_param(name, value),
+                        }
+                    }
+
+                    // meshes loaded from glTF are groups of primitives
+                    let mut x = match self.nodes[&ptr].sub_node {
+                        SubNode::Visual(ref mut mat, _, _) => {
+                            set_param(mat, param);
+                            continue;
+                        }
+                        SubNode::Group { ref first_child, .. } => first_child.clone(),
+                        _ => continue,
+                    };
+
+                    while let Some(ptr) = x {
+                        if let SubNode::Visual(ref mut mat, _, _) = self.nodes[&ptr].sub_node {
+                            set_param(mat, param);
+                        }
+                        x = self.nodes[&ptr].next_sibling.clone();
+                    }
+                }
                 Operation::SetSkeleton(sleketon) => {
                     match self.nodes[&ptr].sub_node {
                         SubNode::Visual(_, _, ref mut skel) => {
@@ -312,7 +413,7 @@ impl Hub {
                             set_weights(gpu_data, &weights);
                             continue;
                         }
-                        SubNode::Group { ref first_child } => first_child.clone(),
+                        SubNode::Group { ref first_child, .. } => first_child.clone(),
                         _ => continue,
                     };
 
@@ -326,6 +427,38 @@ impl Hub {
                 Operation::SetName(name) => {
                     self.nodes[&ptr].name = Some(name);
                 }
+                Operation::SetBounds(bounds) => {
+                    self.nodes[&ptr].bounds = bounds;
+                }
+                Operation::SetBillboard(billboard) => {
+                    let node = &mut self.nodes[&ptr];
+                    match node.sub_node {
+                        // the instances face the camera rather than the mesh
+                        SubNode::Visual(_, GpuData { instance_list: Some(ref mut list), ref triangles, .. }, _) => {
+                            list.billboard = billboard;
+                            list.update_bounds(triangles.as_ref());
+                        }
+                        _ => node.billboard = billboard,
+                    }
+                }
+                Operation::SetInstances(transforms) => {
+                    match self.nodes[&ptr].sub_node {
+                        SubNode::Visual(_, ref mut gpu_data, _) => {
+                            let list = gpu_data.instance_list.as_mut().unwrap();
+                            list.transforms = transforms;
+                            list.update_bounds(gpu_data.triangles.as_ref());
+                        }
+                        _ => unreachable!()
+                    }
+                }
+                Operation::SetInstanceColors(colors) => {
+                    match self.nodes[&ptr].sub_node {
+                        SubNode::Visual(_, ref mut gpu_data, _) => {
+                            gpu_data.instance_list.as_mut().unwrap().colors = colors;
+                        }
+                        _ => unreachable!()
+                    }
+                }
                 Operation::SetProjection(projection) => {
                     match self.nodes[&ptr].sub_node {
                         SubNode::Camera(ref mut internal_projection) => {
@@ -334,6 +467,32 @@ impl Hub {
                         _ => unreachable!()
                     }
                 }
+                Operation::SetProjectionParam(param) => {
+                    match self.nodes[&ptr].sub_node {
+                        SubNode::Camera(ref mut projection) => match (projection, param) {
+                            (&mut Projection::Perspective(ref mut p), ProjectionParam::FovY(fov_y)) => {
+                                p.fov_y = fov_y;
+                            }
+                            (&mut Projection::Orthographic(ref mut o), ProjectionParam::ExtentY(extent_y)) => {
+                                o.extent_y = extent_y;
+                            }
+                            (&mut Projection::Perspective(ref mut p), ProjectionParam::Zoom(zoom)) => {
+                                p.zoom = zoom;
+                            }
+                            (&mut Projection::Orthographic(ref mut o), ProjectionParam::Zoom(zoom)) => {
+                                o.zoom = zoom;
+                            }
+                            (&mut Projection::Perspective(ref mut p), ProjectionParam::ViewOffset(offset)) => {
+                                p.view_offset = offset;
+                            }
+                            (&mut Projection::Orthographic(ref mut o), ProjectionParam::ViewOffset(offset)) => {
+                                o.view_offset = offset;
+                            }
+                            _ => {}
+                        },
+                        _ => unreachable!()
+                    }
+                }
             }
         }
 
@@ -361,6 +520,7 @@ impl Hub {
         match operation {
             LightOperation::Color(color) => data.color = color,
             LightOperation::Intensity(intensity) => data.intensity = intensity,
+            LightOperation::ShadowDistance(distance) => data.shadow_distance = distance,
         }
     }
 
@@ -370,6 +530,7 @@ impl Hub {
     ) {
         use gfx_glyph::Scale;
         match operation {
+            TextOperation::Depth(depth) => data.section.z = depth.unwrap_or(DEPTH_ON_TOP),
             TextOperation::Color(color) => {
                 let rgb = color::to_linear_rgb(color);
                 data.section.text[0].color = [rgb[0], rgb[1], rgb[2], 1.0];
@@ -395,25 +556,85 @@ impl Hub {
         }
     }
 
-    fn walk_impl(
-        &self, base: &Option<NodePointer>, only_visible: bool
-    ) -> TreeWalker {
+    fn walk_impl<'a>(
+        &'a self, base: &Option<NodePointer>, only_visible: bool, frustum: Option<&'a Frustum>
+    ) -> TreeWalker<'a> {
         let default_stack_size = 10;
         let mut walker = TreeWalker {
             hub: self,
             only_visible,
+            frustum,
             stack: Vec::with_capacity(default_stack_size),
         };
         walker.descend(base);
         walker
     }
 
+    /// Moves and rotates every node with a velocity by `dt` seconds.
+    pub(crate) fn integrate_velocities(
+        &mut self,
+        dt: f32,
+    ) {
+        use cgmath::{InnerSpace, Quaternion, Rad, Rotation3, Zero};
+        for node in self.nodes.iter_mut() {
+            if !node.velocity.is_zero() {
+                node.transform.disp += node.velocity * dt;
+            }
+            let speed = node.angular_velocity.magnitude();
+            if speed > 0.0 {
+                let axis = node.angular_velocity / speed;
+                let rotation = Quaternion::from_axis_angle(axis, Rad(speed * dt));
+                node.transform.rot = (rotation * node.transform.rot).normalize();
+            }
+        }
+    }
+
     pub(crate) fn walk(&self, base: &Option<NodePointer>) -> TreeWalker {
-        self.walk_impl(base, true)
+        self.walk_impl(base, true, None)
     }
 
     pub(crate) fn walk_all(&self, base: &Option<NodePointer>) -> TreeWalker {
-        self.walk_impl(base, false)
+        self.walk_impl(base, false, None)
+    }
+
+    /// Walks the visible nodes like `walk`, skipping the children of groups
+    /// whose bounds are outside of `frustum`.
+    pub(crate) fn walk_culled<'a>(
+        &'a self,
+        base: &Option<NodePointer>,
+        frustum: &'a Frustum,
+    ) -> TreeWalker<'a> {
+        self.walk_impl(base, true, Some(frustum))
+    }
+
+    /// Links `child` after `last`, the end of a sibling list, so that it
+    /// becomes the new end. The caller keeps track of the ends of the list.
+    pub(crate) fn append_sibling(
+        &mut self,
+        last: Option<&NodePointer>,
+        child: &NodePointer,
+    ) {
+        self.nodes[child].next_sibling = None;
+        if let Some(last) = last {
+            self.nodes[last].next_sibling = Some(child.clone());
+        }
+    }
+
+    /// Collects `ptr` and all of its descendants, without walking its siblings.
+    pub(crate) fn collect_subtree(
+        &self,
+        ptr: &NodePointer,
+        set: &mut HashSet<*const NodeInternal>,
+    ) {
+        set.insert(&self.nodes[ptr] as *const _);
+        let mut child = match self.nodes[ptr].sub_node {
+            SubNode::Group { ref first_child, .. } => first_child.clone(),
+            _ => None,
+        };
+        while let Some(ptr) = child {
+            self.collect_subtree(&ptr, set);
+            child = self.nodes[&ptr].next_sibling.clone();
+        }
     }
 }
 
@@ -422,12 +643,14 @@ pub(crate) struct WalkedNode<'a> {
     pub(crate) node_ptr: NodePointer,
     pub(crate) node: &'a NodeInternal,
     pub(crate) world_visible: bool,
+    pub(crate) world_opacity: f32,
     pub(crate) world_transform: TransformInternal,
 }
 
 pub(crate) struct TreeWalker<'a> {
     hub: &'a Hub,
     only_visible: bool,
+    frustum: Option<&'a Frustum>,
     stack: Vec<WalkedNode<'a>>,
 }
 
@@ -446,23 +669,33 @@ impl<'a> TreeWalker<'a> {
                     node_ptr: ptr.clone(),
                     node,
                     world_visible: parent.world_visible && node.visible,
+                    world_opacity: parent.world_opacity * node.opacity,
                     world_transform: parent.world_transform.concat(&node.transform),
                 },
                 None => WalkedNode {
                     node_ptr: ptr.clone(),
                     node,
                     world_visible: node.visible,
+                    world_opacity: node.opacity,
                     world_transform: node.transform,
                 },
             };
+            let culled = match (self.frustum, node.bounds) {
+                (Some(frustum), Some(ref bounds)) => {
+                    let bounds = bounds.transformed(&wn.world_transform);
+                    let (min, max): ([f32; 3], [f32; 3]) = (bounds.min.into(), bounds.max.into());
+                    !frustum.intersects_aabb(min, max)
+                }
+                _ => false,
+            };
             self.stack.push(wn);
 
-            if self.only_visible && !node.visible {
+            if self.only_visible && !node.visible || culled {
                 break;
             }
 
             match node.sub_node {
-                SubNode::Group { first_child: Some(ref child_ptr) } => {
+                SubNode::Group { first_child: Some(ref child_ptr), .. } => {
                     ptr = child_ptr;
                     node = &self.hub.nodes[&ptr];
                 },
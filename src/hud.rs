@@ -0,0 +1,404 @@
+//! Immediate-mode 2D vector drawing over the window.
+//!
+//! Shapes are queued on the [`Hud`] of the renderer every frame, drawn over
+//! the scene by the next render to the window, and forgotten afterwards. They
+//! suit charts, debugging overlays and simple vector UI that doesn't need
+//! textures.
+//!
+//! Coordinates are in physical pixels from the top left corner of the
+//! window, like [`Input::mouse_pos`]. Shapes are drawn in the order they are
+//! queued, below the UI text. Edges are only smoothed by multisampling, see
+//! [`Builder::multisampling`].
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! use three::hud::{Path, Style};
+//!
+//! let panel = Style {
+//!     fill: Some(0x202020),
+//!     stroke: Some(0xFFFFFF),
+//!     stroke_width: 2.0,
+//!     opacity: 0.8,
+//! };
+//! let chart = Style {
+//!     stroke: Some(0x40C040),
+//!     .. Style::default()
+//! };
+//! let mut samples = Vec::new();
+//! while window.update() {
+//!     samples.push(window.input.delta_time());
+//!     let mut path = Path::new();
+//!     for (i, &dt) in samples.iter().rev().take(100).enumerate() {
+//!         path.line_to([210.0 - 2.0 * i as f32, 110.0 - 1000.0 * dt]);
+//!     }
+//!     window.renderer.hud.draw_rect([10.0, 10.0], [200.0, 100.0], &panel);
+//!     window.renderer.hud.draw_path(&path, &chart);
+//!     window.render(&camera);
+//! }
+//! ```
+//!
+//! [`Hud`]: struct.Hud.html
+//! [`Input::mouse_pos`]: ../struct.Input.html#method.mouse_pos
+//! [`Builder::multisampling`]: ../window/struct.Builder.html#method.multisampling
+
+use mint;
+
+use std::f32::consts::PI;
+
+use color::{self, Color};
+use render::HudVertex;
+
+/// Number of line segments approximating each curve of a path.
+const CURVE_SEGMENTS: usize = 16;
+
+/// How a shape is filled and outlined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Style {
+    /// Color of the inside of the shape, or `None` to leave it empty.
+    ///
+    /// Default: `None`.
+    pub fill: Option<Color>,
+
+    /// Color of the outline of the shape, or `None` to draw no outline.
+    ///
+    /// Default: `WHITE`.
+    pub stroke: Option<Color>,
+
+    /// Width of the outline in pixels, centered on the edge of the shape.
+    ///
+    /// Default: `1.0`.
+    pub stroke_width: f32,
+
+    /// Opacity of both the fill and the outline, from `0.0` to `1.0`.
+    ///
+    /// Default: `1.0`.
+    pub opacity: f32,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            fill: None,
+            stroke: Some(color::WHITE),
+            stroke_width: 1.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// A sequence of lines and curves, made of one or more open or closed
+/// sub-paths.
+///
+/// Sub-paths are filled on their own with [`Hud::draw_path`], so they can't
+/// cut holes into each other, and self-intersecting ones aren't filled
+/// correctly. Outlines have bevelled corners and flat ends.
+///
+/// [`Hud::draw_path`]: struct.Hud.html#method.draw_path
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    sub_paths: Vec<(Vec<[f32; 2]>, bool)>,
+}
+
+impl Path {
+    /// Creates an empty path.
+    pub fn new() -> Self {
+        Path::default()
+    }
+
+    /// Creates a closed path through `points`.
+    pub fn polygon<P: Into<mint::Point2<f32>> + Copy>(points: &[P]) -> Self {
+        let mut path = Path::new();
+        for &point in points {
+            path.line_to(point);
+        }
+        path.close();
+        path
+    }
+
+    /// Starts a new sub-path at `point`.
+    pub fn move_to<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        point: P,
+    ) -> &mut Self {
+        let point = point.into();
+        self.sub_paths.push((vec![[point.x, point.y]], false));
+        self
+    }
+
+    /// Adds a straight line from the current point to `point`. Starts a new
+    /// sub-path at `point` if there is no current point.
+    pub fn line_to<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        point: P,
+    ) -> &mut Self {
+        let point = point.into();
+        match self.sub_paths.last_mut() {
+            Some(&mut (ref mut points, false)) => points.push([point.x, point.y]),
+            _ => self.sub_paths.push((vec![[point.x, point.y]], false)),
+        }
+        self
+    }
+
+    /// Adds a quadratic Bézier curve from the current point to `point`,
+    /// bending towards `control`.
+    pub fn quadratic_to<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        control: P,
+        point: P,
+    ) -> &mut Self {
+        let (c, p) = (control.into(), point.into());
+        let start = self.current_point().unwrap_or([c.x, c.y]);
+        for i in 1 ..= CURVE_SEGMENTS {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            let (a, b, d) = ((1.0 - t) * (1.0 - t), 2.0 * (1.0 - t) * t, t * t);
+            self.line_to([
+                a * start[0] + b * c.x + d * p.x,
+                a * start[1] + b * c.y + d * p.y,
+            ]);
+        }
+        self
+    }
+
+    /// Adds a cubic Bézier curve from the current point to `point`, leaving
+    /// towards `control1` and arriving from `control2`.
+    pub fn cubic_to<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        control1: P,
+        control2: P,
+        point: P,
+    ) -> &mut Self {
+        let (c1, c2, p) = (control1.into(), control2.into(), point.into());
+        let start = self.current_point().unwrap_or([c1.x, c1.y]);
+        for i in 1 ..= CURVE_SEGMENTS {
+            let t = i as f32 / CURVE_SEGMENTS as f32;
+            let s = 1.0 - t;
+            let (a, b, c, d) = (s * s * s, 3.0 * s * s * t, 3.0 * s * t * t, t * t * t);
+            self.line_to([
+                a * start[0] + b * c1.x + c * c2.x + d * p.x,
+                a * start[1] + b * c1.y + c * c2.y + d * p.y,
+            ]);
+        }
+        self
+    }
+
+    /// Closes the current sub-path with a line back to its start. The next
+    /// line starts a new sub-path.
+    pub fn close(&mut self) -> &mut Self {
+        if let Some(&mut (_, ref mut closed)) = self.sub_paths.last_mut() {
+            *closed = true;
+        }
+        self
+    }
+
+    fn current_point(&self) -> Option<[f32; 2]> {
+        match self.sub_paths.last() {
+            Some(&(ref points, false)) => points.last().cloned(),
+            _ => None,
+        }
+    }
+}
+
+/// Queues 2D shapes drawn over the window by the next render, see the
+/// [module documentation](index.html).
+#[derive(Clone, Debug, Default)]
+pub struct Hud {
+    pub(crate) vertices: Vec<HudVertex>,
+}
+
+impl Hud {
+    /// Draws a rectangle with its top left corner at `position`.
+    pub fn draw_rect<P: Into<mint::Point2<f32>>, S: Into<mint::Vector2<f32>>>(
+        &mut self,
+        position: P,
+        size: S,
+        style: &Style,
+    ) {
+        let (p, s) = (position.into(), size.into());
+        let path = Path::polygon(&[
+            [p.x, p.y],
+            [p.x + s.x, p.y],
+            [p.x + s.x, p.y + s.y],
+            [p.x, p.y + s.y],
+        ]);
+        self.draw_path(&path, style);
+    }
+
+    /// Draws a circle around `center`.
+    pub fn draw_circle<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        center: P,
+        radius: f32,
+        style: &Style,
+    ) {
+        let c = center.into();
+        // segments of about four pixels
+        let segments = ((2.0 * PI * radius / 4.0).ceil() as usize).max(12).min(128);
+        let points = (0 .. segments)
+            .map(|i| {
+                let angle = 2.0 * PI * i as f32 / segments as f32;
+                [c.x + radius * angle.cos(), c.y + radius * angle.sin()]
+            })
+            .collect::<Vec<_>>();
+        self.draw_path(&Path::polygon(&points), style);
+    }
+
+    /// Draws a straight line from `start` to `end`, with the stroke of
+    /// `style`.
+    pub fn draw_line<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        start: P,
+        end: P,
+        style: &Style,
+    ) {
+        let mut path = Path::new();
+        path.move_to(start).line_to(end);
+        self.draw_path(&path, style);
+    }
+
+    /// Draws `path`. Open sub-paths are filled as if they were closed.
+    pub fn draw_path(
+        &mut self,
+        path: &Path,
+        style: &Style,
+    ) {
+        let opacity = style.opacity.max(0.0).min(1.0);
+        if let Some(fill) = style.fill {
+            let color = rgba(fill, opacity);
+            for &(ref points, _) in &path.sub_paths {
+                for triangle in triangulate(points) {
+                    for &index in &triangle {
+                        self.push(points[index], color);
+                    }
+                }
+            }
+        }
+        if let Some(stroke) = style.stroke {
+            let color = rgba(stroke, opacity);
+            let half_width = 0.5 * style.stroke_width;
+            for &(ref points, closed) in &path.sub_paths {
+                self.stroke(points, closed, half_width, color);
+            }
+        }
+    }
+
+    /// Removes the shapes queued since the last render.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    fn push(
+        &mut self,
+        pos: [f32; 2],
+        color: [f32; 4],
+    ) {
+        self.vertices.push(HudVertex { pos, color });
+    }
+
+    fn push_quad(
+        &mut self,
+        corners: [[f32; 2]; 4],
+        color: [f32; 4],
+    ) {
+        for &index in &[0, 1, 2, 0, 2, 3] {
+            self.push(corners[index], color);
+        }
+    }
+
+    /// Outlines a polyline with a quad per segment, filling the gaps at the
+    /// corners with triangles.
+    fn stroke(
+        &mut self,
+        points: &[[f32; 2]],
+        closed: bool,
+        half_width: f32,
+        color: [f32; 4],
+    ) {
+        let count = points.len();
+        let segments = if closed && count > 2 { count } else { count.saturating_sub(1) };
+        let normals = (0 .. segments)
+            .map(|i| {
+                let (a, b) = (points[i], points[(i + 1) % count]);
+                let (dx, dy) = (b[0] - a[0], b[1] - a[1]);
+                let length = (dx * dx + dy * dy).sqrt().max(::std::f32::EPSILON);
+                [-dy / length * half_width, dx / length * half_width]
+            })
+            .collect::<Vec<_>>();
+        for (i, n) in normals.iter().enumerate() {
+            let (a, b) = (points[i], points[(i + 1) % count]);
+            self.push_quad(
+                [
+                    [a[0] + n[0], a[1] + n[1]],
+                    [b[0] + n[0], b[1] + n[1]],
+                    [b[0] - n[0], b[1] - n[1]],
+                    [a[0] - n[0], a[1] - n[1]],
+                ],
+                color,
+            );
+        }
+        let joints = if closed { segments } else { segments.saturating_sub(1) };
+        for i in 0 .. joints {
+            let (n0, n1) = (normals[i], normals[(i + 1) % segments]);
+            let p = points[(i + 1) % count];
+            // the outer side of the corner is the one the normals turn away from
+            let sign = if n0[0] * n1[1] - n0[1] * n1[0] > 0.0 { -1.0 } else { 1.0 };
+            self.push(p, color);
+            self.push([p[0] + sign * n0[0], p[1] + sign * n0[1]], color);
+            self.push([p[0] + sign * n1[0], p[1] + sign * n1[1]], color);
+        }
+    }
+}
+
+/// Converts a color and opacity for the shaders.
+fn rgba(
+    color: Color,
+    opacity: f32,
+) -> [f32; 4] {
+    let [r, g, b] = color::to_linear_rgb(color);
+    [r, g, b, opacity]
+}
+
+/// Splits a simple polygon into triangles by ear clipping, returning the
+/// indices of their corners.
+fn triangulate(points: &[[f32; 2]]) -> Vec<[usize; 3]> {
+    let cross = |o: [f32; 2], a: [f32; 2], b: [f32; 2]| {
+        (a[0] - o[0]) * (b[1] - o[1]) - (a[1] - o[1]) * (b[0] - o[0])
+    };
+    let area: f32 = (0 .. points.len())
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % points.len()]);
+            a[0] * b[1] - b[0] * a[1]
+        })
+        .sum();
+    // walk the polygon counter-clockwise in a Y-up sense
+    let mut remaining = (0 .. points.len()).collect::<Vec<_>>();
+    if area < 0.0 {
+        remaining.reverse();
+    }
+
+    let mut triangles = Vec::new();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0 .. n).find(|&i| {
+            let (a, b, c) = (
+                points[remaining[(i + n - 1) % n]],
+                points[remaining[i]],
+                points[remaining[(i + 1) % n]],
+            );
+            cross(a, b, c) > 0.0 &&
+                remaining.iter().all(|&j| {
+                    let p = points[j];
+                    p == a || p == b || p == c ||
+                        cross(a, b, p) < 0.0 || cross(b, c, p) < 0.0 || cross(c, a, p) < 0.0
+                })
+        });
+        // degenerate polygons have no ear left, cut off any corner
+        let i = ear.unwrap_or(0);
+        triangles.push([remaining[(i + n - 1) % n], remaining[i], remaining[(i + 1) % n]]);
+        remaining.remove(i);
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
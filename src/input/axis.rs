@@ -1,6 +1,7 @@
 //! Axes for handling input.
 
 use glutin::VirtualKeyCode as KeyCode;
+use input::GamepadAxis;
 
 /// Two buttons responsible for opposite directions along specific axis.
 #[derive(Clone, Copy, Debug, PartialEq, Hash)]
@@ -25,6 +26,18 @@ pub struct Raw {
     pub id: u8,
 }
 
+/// Analog axis of the connected gamepads.
+///
+/// Values within `dead_zone` of zero are ignored, as sticks rarely rest
+/// exactly at the center.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Analog {
+    /// Gamepad axis.
+    pub axis: GamepadAxis,
+    /// Largest value considered at rest.
+    pub dead_zone: f32,
+}
+
 /// Axis for left and right arrow keys.
 pub const AXIS_LEFT_RIGHT: Key = Key {
     neg: KeyCode::Left,
@@ -35,3 +48,23 @@ pub const AXIS_DOWN_UP: Key = Key {
     neg: KeyCode::Down,
     pos: KeyCode::Up,
 };
+/// Horizontal axis of the left gamepad stick.
+pub const LEFT_STICK_X: Analog = Analog {
+    axis: GamepadAxis::LeftStickX,
+    dead_zone: 0.15,
+};
+/// Vertical axis of the left gamepad stick.
+pub const LEFT_STICK_Y: Analog = Analog {
+    axis: GamepadAxis::LeftStickY,
+    dead_zone: 0.15,
+};
+/// Horizontal axis of the right gamepad stick.
+pub const RIGHT_STICK_X: Analog = Analog {
+    axis: GamepadAxis::RightStickX,
+    dead_zone: 0.15,
+};
+/// Vertical axis of the right gamepad stick.
+pub const RIGHT_STICK_Y: Analog = Analog {
+    axis: GamepadAxis::RightStickY,
+    dead_zone: 0.15,
+};
@@ -0,0 +1,360 @@
+//! Gamepads and other game controllers.
+
+#[cfg(feature = "gamepad")]
+use gilrs;
+
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc;
+#[cfg(feature = "gamepad")]
+use std::time;
+
+/// Identifies a connected gamepad. Ids of disconnected gamepads may be
+/// reused when the same gamepad is connected again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct GamepadId(pub usize);
+
+/// Button of a gamepad, named after its position on a standard layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    /// Bottom button of the action pad, like A on Xbox controllers.
+    South,
+    /// Right button of the action pad, like B on Xbox controllers.
+    East,
+    /// Top button of the action pad, like Y on Xbox controllers.
+    North,
+    /// Left button of the action pad, like X on Xbox controllers.
+    West,
+    /// Upper left shoulder button.
+    LeftBumper,
+    /// Lower left shoulder button, which is usually also the
+    /// [`GamepadAxis::LeftTrigger`](enum.GamepadAxis.html#variant.LeftTrigger) axis.
+    LeftTrigger,
+    /// Upper right shoulder button.
+    RightBumper,
+    /// Lower right shoulder button, which is usually also the
+    /// [`GamepadAxis::RightTrigger`](enum.GamepadAxis.html#variant.RightTrigger) axis.
+    RightTrigger,
+    /// Left menu button, like Back or Select.
+    Select,
+    /// Right menu button, like Start.
+    Start,
+    /// Central menu button, like the Xbox or PS button.
+    Mode,
+    /// Left stick pressed down.
+    LeftThumb,
+    /// Right stick pressed down.
+    RightThumb,
+    /// Up on the directional pad.
+    DPadUp,
+    /// Down on the directional pad.
+    DPadDown,
+    /// Left on the directional pad.
+    DPadLeft,
+    /// Right on the directional pad.
+    DPadRight,
+}
+
+/// Analog axis of a gamepad.
+///
+/// Sticks range from -1 to 1, positive being right and up. Triggers range
+/// from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    /// Horizontal axis of the left stick.
+    LeftStickX,
+    /// Vertical axis of the left stick.
+    LeftStickY,
+    /// Horizontal axis of the right stick.
+    RightStickX,
+    /// Vertical axis of the right stick.
+    RightStickY,
+    /// Lower left shoulder button.
+    LeftTrigger,
+    /// Lower right shoulder button.
+    RightTrigger,
+}
+
+/// A rumble requested with [`Gamepad::rumble`](struct.Gamepad.html#method.rumble).
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+pub(crate) struct Rumble {
+    id: GamepadId,
+    strength: f32,
+    duration: f32,
+}
+
+/// The state of a connected gamepad, see
+/// [`Input::gamepads`](struct.Input.html#method.gamepads).
+#[derive(Clone, Debug)]
+pub struct Gamepad {
+    id: GamepadId,
+    name: String,
+    pub(crate) buttons_pressed: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+    rumble_tx: mpsc::Sender<Rumble>,
+}
+
+impl Gamepad {
+    pub(crate) fn new(
+        id: GamepadId,
+        name: String,
+        rumble_tx: mpsc::Sender<Rumble>,
+    ) -> Self {
+        Gamepad {
+            id,
+            name,
+            buttons_pressed: HashSet::new(),
+            axes: HashMap::new(),
+            rumble_tx,
+        }
+    }
+
+    /// The id of the gamepad.
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    /// The name of the gamepad model, as reported by the system.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns whether `button` is held down.
+    pub fn is_pressed(
+        &self,
+        button: GamepadButton,
+    ) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// The current value of `axis`, zero until it is first moved.
+    pub fn axis(
+        &self,
+        axis: GamepadAxis,
+    ) -> f32 {
+        self.axes.get(&axis).cloned().unwrap_or(0.0)
+    }
+
+    /// Vibrates the gamepad at `strength`, from 0 to 1, for `duration`
+    /// seconds, replacing the rumble it may be playing already.
+    ///
+    /// The rumble starts on the next
+    /// [`Window::update`](../struct.Window.html#method.update). Gamepads
+    /// without force feedback ignore it.
+    pub fn rumble(
+        &self,
+        strength: f32,
+        duration: f32,
+    ) {
+        let _ = self.rumble_tx.send(Rumble {
+            id: self.id,
+            strength,
+            duration,
+        });
+    }
+
+    pub(crate) fn set_axis(
+        &mut self,
+        axis: GamepadAxis,
+        value: f32,
+    ) {
+        self.axes.insert(axis, value);
+    }
+}
+
+#[cfg(feature = "gamepad")]
+fn map_button(button: gilrs::Button) -> Option<GamepadButton> {
+    use gilrs::Button as B;
+    Some(match button {
+        B::South => GamepadButton::South,
+        B::East => GamepadButton::East,
+        B::North => GamepadButton::North,
+        B::West => GamepadButton::West,
+        B::LeftTrigger => GamepadButton::LeftBumper,
+        B::LeftTrigger2 => GamepadButton::LeftTrigger,
+        B::RightTrigger => GamepadButton::RightBumper,
+        B::RightTrigger2 => GamepadButton::RightTrigger,
+        B::Select => GamepadButton::Select,
+        B::Start => GamepadButton::Start,
+        B::Mode => GamepadButton::Mode,
+        B::LeftThumb => GamepadButton::LeftThumb,
+        B::RightThumb => GamepadButton::RightThumb,
+        B::DPadUp => GamepadButton::DPadUp,
+        B::DPadDown => GamepadButton::DPadDown,
+        B::DPadLeft => GamepadButton::DPadLeft,
+        B::DPadRight => GamepadButton::DPadRight,
+        B::C | B::Z | B::Unknown => return None,
+    })
+}
+
+#[cfg(feature = "gamepad")]
+fn map_axis(axis: gilrs::Axis) -> Option<GamepadAxis> {
+    use gilrs::Axis as A;
+    Some(match axis {
+        A::LeftStickX => GamepadAxis::LeftStickX,
+        A::LeftStickY => GamepadAxis::LeftStickY,
+        A::RightStickX => GamepadAxis::RightStickX,
+        A::RightStickY => GamepadAxis::RightStickY,
+        A::LeftZ => GamepadAxis::LeftTrigger,
+        A::RightZ => GamepadAxis::RightTrigger,
+        A::DPadX | A::DPadY | A::Unknown => return None,
+    })
+}
+
+/// Opens the controller subsystem and adds the gamepads connected already.
+#[cfg(feature = "gamepad")]
+pub(crate) fn init(input: &mut super::Input) -> Option<gilrs::Gilrs> {
+    match gilrs::Gilrs::new() {
+        Ok(gilrs) => {
+            for (id, gamepad) in gilrs.gamepads() {
+                input.gamepad_connected(GamepadId(id.into()), gamepad.name().to_string());
+            }
+            Some(gilrs)
+        }
+        Err(err) => {
+            warn!("Gamepads are unavailable: {}", err);
+            None
+        }
+    }
+}
+
+/// Feeds the events of the connected controllers into `input`.
+#[cfg(feature = "gamepad")]
+pub(crate) fn poll(
+    gilrs: &mut gilrs::Gilrs,
+    input: &mut super::Input,
+) {
+    use gilrs::EventType;
+    while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+        let gamepad_id = GamepadId(id.into());
+        match event {
+            EventType::Connected => {
+                let name = gilrs.gamepad(id).name().to_string();
+                input.gamepad_connected(gamepad_id, name);
+            }
+            EventType::Disconnected => input.gamepad_disconnected(gamepad_id),
+            EventType::ButtonPressed(button, _) => {
+                if let Some(button) = map_button(button) {
+                    input.gamepad_button(gamepad_id, button, true);
+                }
+            }
+            EventType::ButtonReleased(button, _) => {
+                if let Some(button) = map_button(button) {
+                    input.gamepad_button(gamepad_id, button, false);
+                }
+            }
+            // analog triggers are reported as buttons with a value
+            EventType::ButtonChanged(gilrs::Button::LeftTrigger2, value, _) => {
+                input.gamepad_axis(gamepad_id, GamepadAxis::LeftTrigger, value);
+            }
+            EventType::ButtonChanged(gilrs::Button::RightTrigger2, value, _) => {
+                input.gamepad_axis(gamepad_id, GamepadAxis::RightTrigger, value);
+            }
+            EventType::AxisChanged(axis, value, _) => {
+                if let Some(axis) = map_axis(axis) {
+                    input.gamepad_axis(gamepad_id, axis, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A rumble being played, stopped when dropped.
+#[cfg(feature = "gamepad")]
+pub(crate) struct Playing {
+    id: GamepadId,
+    _effect: gilrs::ff::Effect,
+    end: time::Instant,
+}
+
+/// Starts the rumbles requested since the last call, and stops the ones
+/// which have played for their duration.
+#[cfg(feature = "gamepad")]
+pub(crate) fn rumble(
+    gilrs: &mut gilrs::Gilrs,
+    input: &super::Input,
+    playing: &mut Vec<Playing>,
+) {
+    use gilrs::ff::{BaseEffect, BaseEffectType, EffectBuilder, Replay, Ticks};
+
+    let now = time::Instant::now();
+    playing.retain(|rumble| rumble.end > now);
+    for rumble in input.rumbles.try_iter() {
+        playing.retain(|playing| playing.id != rumble.id);
+        let strength = rumble.strength.max(0.0).min(1.0);
+        let millis = (rumble.duration.max(0.0) * 1000.0) as u32;
+        if strength == 0.0 || millis == 0 {
+            continue;
+        }
+        let gamepad = gilrs
+            .gamepads()
+            .find(|&(id, ref gamepad)| GamepadId(id.into()) == rumble.id && gamepad.is_ff_supported())
+            .map(|(id, _)| id);
+        let gamepad = match gamepad {
+            Some(gamepad) => gamepad,
+            None => continue,
+        };
+
+        // both motors, played once for the requested duration
+        let magnitude = (strength * u16::max_value() as f32) as u16;
+        let scheduling = Replay {
+            play_for: Ticks::from_ms(millis),
+            .. Default::default()
+        };
+        let effect = EffectBuilder::new()
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Strong { magnitude },
+                scheduling,
+                .. Default::default()
+            })
+            .add_effect(BaseEffect {
+                kind: BaseEffectType::Weak { magnitude },
+                scheduling,
+                .. Default::default()
+            })
+            .gamepads(&[gamepad])
+            .finish(gilrs)
+            .and_then(|effect| effect.play().map(|()| effect));
+        match effect {
+            Ok(effect) => playing.push(Playing {
+                id: rumble.id,
+                _effect: effect,
+                end: now + time::Duration::from_millis(millis as u64),
+            }),
+            Err(err) => warn!("Unable to rumble the gamepad: {}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::Input;
+
+    #[test]
+    fn reconnecting_replaces_gamepad() {
+        let mut input = Input::new();
+        input.gamepad_connected(GamepadId(0), "first".to_string());
+        input.gamepad_connected(GamepadId(1), "second".to_string());
+        input.gamepad_connected(GamepadId(0), "again".to_string());
+        let names: Vec<_> = input.gamepads().iter().map(|gamepad| gamepad.name()).collect();
+        assert_eq!(names, vec!["second", "again"]);
+
+        input.gamepad_disconnected(GamepadId(1));
+        assert!(input.gamepad(GamepadId(1)).is_none());
+        assert_eq!(input.gamepad(GamepadId(0)).map(Gamepad::name), Some("again"));
+    }
+
+    #[test]
+    fn rumble_is_queued_for_the_window() {
+        let mut input = Input::new();
+        input.gamepad_connected(GamepadId(3), "pad".to_string());
+        input.gamepad(GamepadId(3)).unwrap().rumble(0.5, 0.25);
+
+        let rumbles: Vec<_> = input.rumbles.try_iter().collect();
+        assert_eq!(rumbles.len(), 1);
+        assert_eq!(rumbles[0].id, GamepadId(3));
+        assert_eq!((rumbles[0].strength, rumbles[0].duration), (0.5, 0.25));
+    }
+}
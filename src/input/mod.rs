@@ -1,14 +1,17 @@
-use glutin::{ElementState, MouseScrollDelta};
-pub use glutin::{MouseButton, VirtualKeyCode as Key};
+use glutin::{ElementState, KeyboardInput, MouseScrollDelta};
+pub use glutin::{ModifiersState as Modifiers, MouseButton, VirtualKeyCode as Key};
 use mint;
 
 use std::collections::HashSet;
+use std::sync::mpsc;
 use std::time;
 
 mod timer;
 pub mod axis;
+pub(crate) mod gamepad;
 
 pub use self::axis::{AXIS_DOWN_UP, AXIS_LEFT_RIGHT};
+pub use self::gamepad::{Gamepad, GamepadAxis, GamepadButton, GamepadId};
 
 pub use self::timer::Timer;
 
@@ -16,29 +19,47 @@ const PIXELS_PER_LINE: f32 = 38.0;
 
 pub type TimerDuration = f32;
 
+/// Platform-specific code of a physical keyboard key.
+///
+/// Unlike [`Key`], it identifies the key by its location rather than its
+/// label, so bindings like WASD work with any keyboard layout.
+///
+/// [`Key`]: enum.VirtualKeyCode.html
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ScanCode(pub u32);
+
 struct State {
     time_moment: time::Instant,
     is_focused: bool,
+    is_cursor_grabbed: bool,
     keys_pressed: HashSet<Key>,
+    scancodes_pressed: HashSet<ScanCode>,
+    modifiers: Modifiers,
     mouse_pressed: HashSet<MouseButton>,
     mouse_pos: mint::Point2<f32>,
     mouse_pos_ndc: mint::Point2<f32>,
+    gamepads: Vec<Gamepad>,
 }
 
 struct Diff {
     time_delta: TimerDuration,
     keys_hit: Vec<Key>,
+    scancodes_hit: Vec<ScanCode>,
     mouse_moves: Vec<mint::Vector2<f32>>,
     mouse_moves_ndc: Vec<mint::Vector2<f32>>,
     axes_raw: Vec<(u8, f32)>,
     mouse_hit: Vec<MouseButton>,
     mouse_wheel: Vec<f32>,
+    gamepad_hit: Vec<(GamepadId, GamepadButton)>,
 }
 
-/// Controls user and system input from keyboard, mouse and system clock.
+/// Controls user and system input from keyboard, mouse, gamepads and system clock.
 pub struct Input {
     state: State,
     delta: Diff,
+    rumble_tx: mpsc::Sender<gamepad::Rumble>,
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub(crate) rumbles: mpsc::Receiver<gamepad::Rumble>,
 }
 
 impl Input {
@@ -46,21 +67,33 @@ impl Input {
         let state = State {
             time_moment: time::Instant::now(),
             is_focused: true,
+            is_cursor_grabbed: false,
             keys_pressed: HashSet::new(),
+            scancodes_pressed: HashSet::new(),
+            modifiers: Modifiers::default(),
             mouse_pressed: HashSet::new(),
             mouse_pos: [0.0; 2].into(),
             mouse_pos_ndc: [0.0; 2].into(),
+            gamepads: Vec::new(),
         };
         let delta = Diff {
             time_delta: 0.0,
             keys_hit: Vec::new(),
+            scancodes_hit: Vec::new(),
             mouse_moves: Vec::new(),
             mouse_moves_ndc: Vec::new(),
             axes_raw: Vec::new(),
             mouse_hit: Vec::new(),
             mouse_wheel: Vec::new(),
+            gamepad_hit: Vec::new(),
         };
-        Input { state, delta }
+        let (rumble_tx, rumbles) = mpsc::channel();
+        Input {
+            state,
+            delta,
+            rumble_tx,
+            rumbles,
+        }
     }
 
     /// Manually reset current `Input` state.
@@ -76,11 +109,13 @@ impl Input {
         self.state.time_moment = now;
         self.delta.time_delta = dt.as_secs() as TimerDuration + 1e-9 * dt.subsec_nanos() as TimerDuration;
         self.delta.keys_hit.clear();
+        self.delta.scancodes_hit.clear();
         self.delta.mouse_moves.clear();
         self.delta.mouse_moves_ndc.clear();
         self.delta.axes_raw.clear();
         self.delta.mouse_hit.clear();
         self.delta.mouse_wheel.clear();
+        self.delta.gamepad_hit.clear();
     }
 
     /// Get current delta time (time since previous frame) in seconds.
@@ -93,6 +128,17 @@ impl Input {
         &self.delta.keys_hit
     }
 
+    /// Get list of all physical keys hit since the last frame.
+    pub fn scancodes_hit(&self) -> &[ScanCode] {
+        &self.delta.scancodes_hit
+    }
+
+    /// Get the state of the modifier keys (shift, ctrl, alt and logo)
+    /// as of the last keyboard or mouse button event.
+    pub fn modifiers(&self) -> Modifiers {
+        self.state.modifiers
+    }
+
     /// Get current mouse pointer position in pixels from top-left.
     pub fn mouse_pos(&self) -> mint::Point2<f32> {
         self.state.mouse_pos
@@ -169,6 +215,27 @@ impl Input {
             .into()
     }
 
+    /// Get the connected gamepads, in the order they were connected.
+    ///
+    /// Gamepads are only polled when the `gamepad` feature is enabled.
+    /// Otherwise the list stays empty.
+    pub fn gamepads(&self) -> &[Gamepad] {
+        &self.state.gamepads
+    }
+
+    /// Get the connected gamepad with the given id.
+    pub fn gamepad(
+        &self,
+        id: GamepadId,
+    ) -> Option<&Gamepad> {
+        self.state.gamepads.iter().find(|gamepad| gamepad.id() == id)
+    }
+
+    /// Get list of all gamepad buttons hit since the last frame.
+    pub fn gamepad_buttons_hit(&self) -> &[(GamepadId, GamepadButton)] {
+        &self.delta.gamepad_hit
+    }
+
     /// Return whether [`Window`](struct.Window.html) is in focus or not.
     pub fn is_focused(&self) -> bool {
         self.state.is_focused
@@ -181,20 +248,41 @@ impl Input {
         self.state.is_focused = state;
     }
 
+    /// Return whether the cursor is grabbed with
+    /// [`Window::set_cursor_grab`](struct.Window.html#method.set_cursor_grab) or not.
+    pub fn is_cursor_grabbed(&self) -> bool {
+        self.state.is_cursor_grabbed
+    }
+
+    pub(crate) fn cursor_grab(
+        &mut self,
+        state: bool,
+    ) {
+        self.state.is_cursor_grabbed = state;
+    }
+
     pub(crate) fn keyboard_input(
         &mut self,
-        state: ElementState,
-        key: Key,
+        input: KeyboardInput,
     ) {
-        match state {
+        let scancode = ScanCode(input.scancode);
+        self.state.modifiers = input.modifiers;
+        match input.state {
             ElementState::Pressed => {
-                if !self.state.keys_pressed.contains(&key) {
-                    self.state.keys_pressed.insert(key);
-                    self.delta.keys_hit.push(key);
+                if self.state.scancodes_pressed.insert(scancode) {
+                    self.delta.scancodes_hit.push(scancode);
+                }
+                if let Some(key) = input.virtual_keycode {
+                    if self.state.keys_pressed.insert(key) {
+                        self.delta.keys_hit.push(key);
+                    }
                 }
             }
             ElementState::Released => {
-                self.state.keys_pressed.remove(&key);
+                self.state.scancodes_pressed.remove(&scancode);
+                if let Some(key) = input.virtual_keycode {
+                    self.state.keys_pressed.remove(&key);
+                }
             }
         }
     }
@@ -203,7 +291,9 @@ impl Input {
         &mut self,
         state: ElementState,
         button: MouseButton,
+        modifiers: Modifiers,
     ) {
+        self.state.modifiers = modifiers;
         match state {
             ElementState::Pressed => {
                 self.state.mouse_pressed.insert(button);
@@ -239,6 +329,51 @@ impl Input {
         self.delta.axes_raw.push((axis, value));
     }
 
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub(crate) fn gamepad_connected(
+        &mut self,
+        id: GamepadId,
+        name: String,
+    ) {
+        self.gamepad_disconnected(id);
+        self.state.gamepads.push(Gamepad::new(id, name, self.rumble_tx.clone()));
+    }
+
+    pub(crate) fn gamepad_disconnected(
+        &mut self,
+        id: GamepadId,
+    ) {
+        self.state.gamepads.retain(|gamepad| gamepad.id() != id);
+    }
+
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub(crate) fn gamepad_button(
+        &mut self,
+        id: GamepadId,
+        button: GamepadButton,
+        pressed: bool,
+    ) {
+        if let Some(gamepad) = self.state.gamepads.iter_mut().find(|gamepad| gamepad.id() == id) {
+            if !pressed {
+                gamepad.buttons_pressed.remove(&button);
+            } else if gamepad.buttons_pressed.insert(button) {
+                self.delta.gamepad_hit.push((id, button));
+            }
+        }
+    }
+
+    #[cfg_attr(not(feature = "gamepad"), allow(dead_code))]
+    pub(crate) fn gamepad_axis(
+        &mut self,
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    ) {
+        if let Some(gamepad) = self.state.gamepads.iter_mut().find(|gamepad| gamepad.id() == id) {
+            gamepad.set_axis(axis, value);
+        }
+    }
+
     pub(crate) fn mouse_wheel_input(
         &mut self,
         delta: MouseScrollDelta,
@@ -275,9 +410,15 @@ impl Input {
     ///  * `None` when no updates to the axis are received and
     ///  * `Some(x)` where `x` is the sum of positive and negative inputs otherwise.
     ///
+    /// [`axis::Analog`]
+    ///
+    ///  * `None` when the axis of every gamepad is within the dead zone and
+    ///  * `Some(x)` where `x` is the value furthest from zero otherwise.
+    ///
     /// [`Window::update`]: window/struct.Window.html#method.update
     /// [`axis::Key`]: input/axis/struct.Key.html
     /// [`axis::Raw`]: input/axis/struct.Raw.html
+    /// [`axis::Analog`]: input/axis/struct.Analog.html
     pub fn delta<D: Delta>(
         &self,
         delta: D,
@@ -310,13 +451,17 @@ impl Input {
     }
 }
 
-/// Keyboard or mouse button.
+/// Keyboard, mouse or gamepad button.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Button {
     /// Keyboard button.
     Key(Key),
+    /// Keyboard button at a physical location.
+    Scan(ScanCode),
     /// Mouse button.
     Mouse(MouseButton),
+    /// Button of any connected gamepad.
+    Gamepad(GamepadButton),
 }
 
 /// Trait for [`Buttons`](enum.Button.html).
@@ -335,7 +480,9 @@ impl Hit for Button {
     ) -> bool {
         match *self {
             Button::Key(button) => button.hit(input),
+            Button::Scan(button) => button.hit(input),
             Button::Mouse(button) => button.hit(input),
+            Button::Gamepad(button) => button.hit(input),
         }
     }
 }
@@ -349,6 +496,15 @@ impl Hit for Key {
     }
 }
 
+impl Hit for ScanCode {
+    fn hit(
+        &self,
+        input: &Input,
+    ) -> bool {
+        input.state.scancodes_pressed.contains(self)
+    }
+}
+
 impl Hit for MouseButton {
     fn hit(
         &self,
@@ -358,6 +514,15 @@ impl Hit for MouseButton {
     }
 }
 
+impl Hit for GamepadButton {
+    fn hit(
+        &self,
+        input: &Input,
+    ) -> bool {
+        input.state.gamepads.iter().any(|gamepad| gamepad.is_pressed(*self))
+    }
+}
+
 impl Hit for axis::Key {
     fn hit(
         &self,
@@ -369,6 +534,15 @@ impl Hit for axis::Key {
     }
 }
 
+impl Hit for axis::Analog {
+    fn hit(
+        &self,
+        input: &Input,
+    ) -> bool {
+        self.delta(input).is_some()
+    }
+}
+
 impl Hit for axis::Raw {
     fn hit(
         &self,
@@ -409,6 +583,13 @@ impl HitCount for Button {
                 .filter(|&&key| key == button)
                 .take(MAX as usize)
                 .count() as Self::Output,
+            Button::Scan(button) => input
+                .delta
+                .scancodes_hit
+                .iter()
+                .filter(|&&key| key == button)
+                .take(u8::MAX as usize)
+                .count() as Self::Output,
             Button::Mouse(button) => input
                 .delta
                 .mouse_hit
@@ -416,6 +597,13 @@ impl HitCount for Button {
                 .filter(|&&key| key == button)
                 .take(MAX as usize)
                 .count() as Self::Output,
+            Button::Gamepad(button) => input
+                .delta
+                .gamepad_hit
+                .iter()
+                .filter(|&&(_, key)| key == button)
+                .take(MAX as usize)
+                .count() as Self::Output,
         }
     }
 }
@@ -446,7 +634,8 @@ impl HitCount for axis::Key {
     }
 }
 
-/// Trait for [`axis::Key`](struct.Key.html) and [`axis::Raw`](struct.Raw.html).
+/// Trait for [`axis::Key`](struct.Key.html), [`axis::Raw`](struct.Raw.html)
+/// and [`axis::Analog`](struct.Analog.html).
 pub trait Delta {
     /// Output type.
     type Output;
@@ -521,6 +710,34 @@ impl Delta for axis::Raw {
     }
 }
 
+impl Delta for axis::Analog {
+    type Output = Option<f32>;
+
+    fn delta(
+        &self,
+        input: &Input,
+    ) -> Self::Output {
+        input
+            .state
+            .gamepads
+            .iter()
+            .map(|gamepad| gamepad.axis(self.axis))
+            .filter(|value| value.abs() > self.dead_zone)
+            .fold(None, |max: Option<f32>, value| match max {
+                Some(max) if max.abs() >= value.abs() => Some(max),
+                _ => Some(value),
+            })
+    }
+
+    fn timed(
+        &self,
+        input: &Input,
+    ) -> Option<TimerDuration> {
+        self.delta(input)
+            .map(|v| v as TimerDuration * input.delta_time())
+    }
+}
+
 /// `Escape` keyboard button.
 pub const KEY_ESCAPE: Button = Button::Key(Key::Escape);
 /// `Space` keyboard button.
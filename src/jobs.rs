@@ -0,0 +1,196 @@
+//! A small pool of worker threads, shared by the engine and applications.
+//!
+//! The [`Factory`] runs work such as decoding the images of a cubemap on its
+//! pool. Applications can run their own jobs on the same pool instead of
+//! spawning more threads, or give the factory a pool of their own with
+//! [`Factory::set_job_pool`].
+//!
+//! The threads are only started when the first job is queued.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! let pool = window.factory.job_pool().clone();
+//! let handle = pool.run(|| (0 .. 1_000_000u64).sum::<u64>());
+//! // ... do something else meanwhile
+//! println!("{}", handle.wait());
+//! ```
+//!
+//! [`Factory`]: ../struct.Factory.html
+//! [`Factory::set_job_pool`]: ../struct.Factory.html#method.set_job_pool
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+struct Shared {
+    threads: usize,
+    sender: Mutex<Option<mpsc::Sender<Job>>>,
+}
+
+/// A handle to a pool of worker threads. Clones share the same threads,
+/// which exit once every handle is dropped and the queued jobs are done.
+///
+/// Jobs waiting for other jobs of the same pool may wait forever when all
+/// of its threads are busy doing the same.
+#[derive(Clone)]
+pub struct JobPool {
+    shared: Arc<Shared>,
+}
+
+impl fmt::Debug for JobPool {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        f.debug_struct("JobPool").field("threads", &self.shared.threads).finish()
+    }
+}
+
+impl Default for JobPool {
+    /// Creates a pool with one thread per CPU core.
+    fn default() -> Self {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(4);
+        JobPool::new(threads)
+    }
+}
+
+impl JobPool {
+    /// Creates a pool of `threads` worker threads, at least one.
+    pub fn new(threads: usize) -> Self {
+        JobPool {
+            shared: Arc::new(Shared {
+                threads: threads.max(1),
+                sender: Mutex::new(None),
+            }),
+        }
+    }
+
+    /// The number of worker threads.
+    pub fn threads(&self) -> usize {
+        self.shared.threads
+    }
+
+    /// Queues `job` to run on one of the threads.
+    ///
+    /// A panicking job is stopped without taking its thread down.
+    pub fn spawn<F>(
+        &self,
+        job: F,
+    ) where
+        F: FnOnce() + Send + 'static,
+    {
+        let mut sender = self.shared.sender.lock().unwrap();
+        if sender.is_none() {
+            *sender = Some(self.start());
+        }
+        let _ = sender.as_ref().unwrap().send(Box::new(job));
+    }
+
+    /// Queues `job` and returns a handle to its result.
+    pub fn run<T, F>(
+        &self,
+        job: F,
+    ) -> JobHandle<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> T + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        self.spawn(move || {
+            let _ = sender.send(job());
+        });
+        JobHandle {
+            receiver,
+            done: false,
+        }
+    }
+
+    /// Applies `fun` to every item in parallel and returns the results in
+    /// the order of the items.
+    ///
+    /// # Panics
+    /// Panics if `fun` panics for any of the items.
+    pub fn map<T, R, F>(
+        &self,
+        items: Vec<T>,
+        fun: F,
+    ) -> Vec<R>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: Fn(T) -> R + Send + Sync + 'static,
+    {
+        let fun = Arc::new(fun);
+        let handles = items
+            .into_iter()
+            .map(|item| {
+                let fun = fun.clone();
+                self.run(move || fun(item))
+            })
+            .collect::<Vec<_>>();
+        handles.into_iter().map(JobHandle::wait).collect()
+    }
+
+    /// Starts the threads, returning the sender of their queue.
+    fn start(&self) -> mpsc::Sender<Job> {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        for index in 0 .. self.shared.threads {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("three-jobs-{}", index))
+                .spawn(move || loop {
+                    let job = match receiver.lock().unwrap().recv() {
+                        Ok(job) => job,
+                        Err(_) => break,
+                    };
+                    if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                        error!("A job panicked");
+                    }
+                })
+                .expect("Unable to spawn a job thread");
+        }
+        sender
+    }
+}
+
+/// The result of a job queued with [`JobPool::run`](struct.JobPool.html#method.run).
+#[derive(Debug)]
+pub struct JobHandle<T> {
+    receiver: mpsc::Receiver<T>,
+    done: bool,
+}
+
+impl<T> JobHandle<T> {
+    /// Blocks until the job is done and returns its result.
+    ///
+    /// # Panics
+    /// Panics if the job panicked, or if its result was already taken by
+    /// [`try_wait`](#method.try_wait).
+    pub fn wait(self) -> T {
+        assert!(!self.done, "The result of the job was already taken");
+        self.receiver.recv().expect("The job panicked")
+    }
+
+    /// Returns the result if the job is done, without blocking. The result
+    /// is only returned once.
+    ///
+    /// # Panics
+    /// Panics if the job panicked.
+    pub fn try_wait(&mut self) -> Option<T> {
+        if self.done {
+            return None;
+        }
+        match self.receiver.try_recv() {
+            Ok(value) => {
+                self.done = true;
+                Some(value)
+            }
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => panic!("The job panicked"),
+        }
+    }
+}
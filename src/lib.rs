@@ -250,6 +250,8 @@ extern crate cgmath;
 extern crate derivative;
 extern crate froggy;
 extern crate genmesh;
+#[cfg(feature = "gamepad")]
+extern crate gilrs;
 #[macro_use]
 extern crate gfx;
 extern crate gfx_glyph;
@@ -273,6 +275,8 @@ extern crate vec_map;
 #[cfg(feature = "opengl")]
 extern crate gfx_device_gl;
 #[cfg(feature = "opengl")]
+extern crate gfx_gl;
+#[cfg(feature = "opengl")]
 extern crate gfx_window_glutin;
 #[cfg(feature = "opengl")]
 extern crate glutin;
@@ -284,32 +288,49 @@ mod macros;
 pub mod audio;
 
 pub mod animation;
+mod bitmap_font;
 pub mod camera;
+pub mod collision;
 pub mod color;
 pub mod controls;
 pub mod custom;
 mod data;
 mod factory;
 mod geometry;
+#[cfg(feature = "golden-tests")]
+pub mod golden;
+pub mod helpers;
 mod hub;
+pub mod hud;
 mod input;
+pub mod jobs;
 pub mod light;
 pub mod material;
 mod mesh;
+pub mod nav;
 mod node;
 pub mod object;
+pub mod particles;
+pub mod plot;
+pub mod portal;
+pub mod raycast;
 pub mod render;
 pub mod scene;
+pub mod sequencer;
 pub mod skeleton;
 mod sprite;
 pub mod template;
 mod text;
 mod texture;
+pub mod trail;
 mod util;
 
 #[cfg(feature = "opengl")]
 pub mod window;
 
+#[doc(inline)]
+pub use bitmap_font::BitmapFont;
+
 #[doc(inline)]
 pub use color::Color;
 
@@ -317,7 +338,7 @@ pub use color::Color;
 pub use controls::{AXIS_DOWN_UP, AXIS_LEFT_RIGHT, KEY_ESCAPE, KEY_SPACE, MOUSE_LEFT, MOUSE_RIGHT};
 
 #[doc(inline)]
-pub use controls::{Button, MouseButton, Input, Timer};
+pub use controls::{Button, Modifiers, MouseButton, Input, ScanCode, Timer};
 
 #[doc(inline)]
 pub use factory::Factory;
@@ -333,10 +354,10 @@ pub use glutin::VirtualKeyCode as Key;
 //pub use group::Group;
 
 #[doc(inline)]
-pub use material::Material;
+pub use material::{Material, MaterialHandle};
 
 #[doc(inline)]
-pub use mesh::{DynamicMesh, Mesh};
+pub use mesh::{DynamicMesh, InstancedMesh, Mesh};
 
 #[doc(inline)]
 pub use node::{Node, Transform, Local, World};
@@ -348,16 +369,18 @@ pub use object::{Group, Object};
 pub use render::Renderer;
 
 #[doc(inline)]
-pub use scene::{Background, Scene};
+pub use scene::{Background, BackgroundShader, Fog, ParallaxLayer, Scene, Statistics, Warning};
 
 #[doc(inline)]
 pub use sprite::Sprite;
 
 #[doc(inline)]
-pub use text::{Align, Font, Layout, Text};
+pub use text::{Align, Font, IconAtlas, IconFormat, Layout, Text};
 
 #[doc(inline)]
-pub use texture::{CubeMap, CubeMapPath, FilterMethod, Sampler, Texture, WrapMode};
+pub use texture::{
+    ColorSpace, CubeMap, CubeMapPath, FilterMethod, ImageDecoder, Sampler, StandardImageDecoder, Texture, WrapMode,
+};
 
 #[cfg(feature = "opengl")]
 #[doc(inline)]
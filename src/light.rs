@@ -1,19 +1,24 @@
 //! Contains different types of light sources.
 
+use cgmath::{InnerSpace, Vector3};
 use gfx;
+use image;
+use mint;
 use object::{Base, Object, ObjectType};
 use std::ops;
 
 use camera::Orthographic;
-use color::Color;
+use color::{self, Color};
 use hub::{self, Operation, SubLight, SubNode};
 use render::{BackendResources, ShadowFormat};
 use scene::SyncGuard;
+use texture::CubeMap;
 
 #[derive(Debug)]
 pub(crate) enum LightOperation {
     Color(Color),
     Intensity(f32),
+    ShadowDistance(f32),
 }
 
 /// Marks light sources and implements their common methods.
@@ -42,27 +47,50 @@ impl Light for Directional {}
 impl Light for Hemisphere {}
 impl Light for Point {}
 
-/// `ShadowMap` is used to render shadows from [`PointLight`](struct.PointLight.html)
-/// and [`DirectionalLight`](struct.DirectionalLight.html).
+/// `ShadowMap` is used to render shadows from [`Point`](struct.Point.html)
+/// and [`Directional`](struct.Directional.html) lights.
+///
+/// Directional lights use flat maps, created with
+/// [`Factory::shadow_map`](../struct.Factory.html#method.shadow_map), while
+/// point lights use cube maps, created with
+/// [`Factory::shadow_cube_map`](../struct.Factory.html#method.shadow_cube_map).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct ShadowMap {
     pub(crate) resource: gfx::handle::ShaderResourceView<BackendResources, f32>,
-    pub(crate) target: gfx::handle::DepthStencilView<BackendResources, ShadowFormat>,
+    /// One target for flat maps, or one per face for cube maps, in the
+    /// order `+X, -X, +Y, -Y, +Z, -Z`.
+    pub(crate) targets: Vec<gfx::handle::DepthStencilView<BackendResources, ShadowFormat>>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) enum ShadowProjection {
     Orthographic(Orthographic),
+    /// Perspective projections of the six faces of a cube around the light,
+    /// seeing from `range.start` to `range.end` away from it.
+    Cube(ops::Range<f32>),
+    /// Orthographic projections fitted every frame around consecutive
+    /// slices of the camera frustum, ending at the given distances from the
+    /// camera, one map per slice.
+    Cascades(Vec<ShadowMap>, Vec<f32>),
 }
 
+/// The maximum number of cascades of a directional light, see
+/// [`Directional::set_shadow_cascades`](struct.Directional.html#method.set_shadow_cascades).
+pub const MAX_SHADOW_CASCADES: usize = 4;
+
 impl ShadowMap {
-    pub(crate) fn to_target(&self) -> gfx::handle::DepthStencilView<BackendResources, ShadowFormat> {
-        self.target.clone()
+    pub(crate) fn to_targets(&self) -> &[gfx::handle::DepthStencilView<BackendResources, ShadowFormat>] {
+        &self.targets
     }
 
     pub(crate) fn to_resource(&self) -> gfx::handle::ShaderResourceView<BackendResources, f32> {
         self.resource.clone()
     }
+
+    /// Whether this is a cube map, for the shadows of point lights.
+    pub fn is_cube(&self) -> bool {
+        self.targets.len() == 6
+    }
 }
 
 /// Omni-directional, fixed-intensity and fixed-color light source that affects
@@ -111,6 +139,9 @@ impl Directional {
     }
 
     /// Adds or updates the shadow map for this light source.
+    ///
+    /// The map needs to be a flat one, created with
+    /// [`Factory::shadow_map`](../struct.Factory.html#method.shadow_map).
     pub fn set_shadow(
         &mut self,
         map: ShadowMap,
@@ -121,10 +152,73 @@ impl Directional {
             center: [0.0; 2].into(),
             extent_y,
             range,
+            zoom: 1.0,
+            view_offset: None,
         });
         let msg = Operation::SetShadow(map, sp);
         let _ = self.object.tx.send((self.object.node.downgrade(), msg));
     }
+
+    /// Splits the shadow of this light into cascades, each covering a slice
+    /// of the view of the camera with its own shadow map.
+    ///
+    /// The first cascade covers from the camera to `splits[0]` units away
+    /// from it, the second from there to `splits[1]`, and so on. The closest
+    /// slices are small, so their shadows get sharp, while the far ones can
+    /// use smaller maps. The projections follow the camera and are fitted to
+    /// the slices every frame, replacing the fixed one of
+    /// [`set_shadow`](#method.set_shadow). Shadows end at the last split.
+    ///
+    /// Shadow casters are included up to the last split distance from the
+    /// slices towards the light. Only one light of a scene can use cascades,
+    /// others only get their first cascade.
+    ///
+    /// The maps need to be flat ones, created with
+    /// [`Factory::shadow_map`](../struct.Factory.html#method.shadow_map).
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// let mut sun = window.factory.directional_light(0xFFFFFF, 1.0);
+    /// let maps = [2048, 1024, 1024]
+    ///     .iter()
+    ///     .map(|&size| window.factory.shadow_map(size, size))
+    ///     .collect();
+    /// sun.set_shadow_cascades(maps, &[8.0, 30.0, 120.0]);
+    /// window.scene.add(&sun);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if there are no maps, more than [`MAX_SHADOW_CASCADES`], or a
+    /// different number of splits, or if the splits aren't increasing.
+    ///
+    /// [`MAX_SHADOW_CASCADES`]: constant.MAX_SHADOW_CASCADES.html
+    pub fn set_shadow_cascades(
+        &mut self,
+        maps: Vec<ShadowMap>,
+        splits: &[f32],
+    ) {
+        assert!(!maps.is_empty() && maps.len() <= MAX_SHADOW_CASCADES, "A light has 1 to {} cascades", MAX_SHADOW_CASCADES);
+        assert_eq!(maps.len(), splits.len(), "Each cascade needs one split");
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]), "Cascade splits must be increasing");
+        let first = maps[0].clone();
+        let msg = Operation::SetShadow(first, ShadowProjection::Cascades(maps, splits.to_vec()));
+        let _ = self.object.tx.send((self.object.node.downgrade(), msg));
+    }
+
+    /// Limits the shadow casters to the ones within `distance` from the
+    /// camera, so that far away objects don't cost shadow map draws.
+    /// See [`Renderer::shadow_culling`].
+    ///
+    /// Default: infinite.
+    ///
+    /// [`Renderer::shadow_culling`]: ../struct.Renderer.html#structfield.shadow_culling
+    pub fn set_shadow_distance(
+        &self,
+        distance: f32,
+    ) {
+        let msg = Operation::SetLight(LightOperation::ShadowDistance(distance));
+        let _ = self.object.tx.send((self.object.node.downgrade(), msg));
+    }
 }
 
 impl AsRef<Base> for Directional {
@@ -191,6 +285,32 @@ impl Point {
     pub(crate) fn new(object: Base) -> Self {
         Point { object }
     }
+
+    /// Adds or updates the shadow map for this light source, casting
+    /// shadows in all directions from objects between `range.start` and
+    /// `range.end` away from the light.
+    ///
+    /// The map needs to be a cube one, created with
+    /// [`Factory::shadow_cube_map`](../struct.Factory.html#method.shadow_cube_map).
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::Object;
+    ///
+    /// let mut lamp = window.factory.point_light(0xFFE0B0, 1.0);
+    /// lamp.set_position([0.0, 2.5, 0.0]);
+    /// let map = window.factory.shadow_cube_map(512);
+    /// lamp.set_shadow(map, 0.1 .. 20.0);
+    /// window.scene.add(&lamp);
+    /// ```
+    pub fn set_shadow(
+        &mut self,
+        map: ShadowMap,
+        range: ops::Range<f32>,
+    ) {
+        let msg = Operation::SetShadow(map, ShadowProjection::Cube(range));
+        let _ = self.object.tx.send((self.object.node.downgrade(), msg));
+    }
 }
 
 impl AsRef<Base> for Point {
@@ -261,3 +381,342 @@ impl<'a> From<&'a hub::LightData> for HemisphereLightData {
         }
     }
 }
+
+/// Ambient light varying with the surface direction, stored as second-order
+/// spherical harmonics.
+///
+/// Probes are usually computed from the same cubemap as the skybox with
+/// [`Factory::load_light_probe`], and set as the [`Scene::light_probe`].
+/// Lambert, Phong and PBR materials add the probe to their ambient lighting.
+///
+/// [`Factory::load_light_probe`]: ../struct.Factory.html#method.load_light_probe
+/// [`Scene::light_probe`]: ../struct.Scene.html#structfield.light_probe
+#[derive(Clone, Debug, PartialEq)]
+pub struct LightProbe {
+    /// The irradiance in linear RGB, convolved for diffuse surfaces and
+    /// divided by π, as coefficients of the basis functions in the order
+    /// `Y00, Y1-1, Y10, Y11, Y2-2, Y2-1, Y20, Y21, Y22`.
+    pub coefficients: [[f32; 3]; 9],
+
+    /// Multiplier of the ambient light.
+    ///
+    /// Default: `1.0`.
+    pub intensity: f32,
+}
+
+/// The direction through the point at `u, v`, in `-1.0 .. 1.0`, of a
+/// cubemap face in the order `+X, -X, +Y, -Y, +Z, -Z`. Not normalized.
+fn cube_direction(
+    face: usize,
+    u: f32,
+    v: f32,
+) -> [f32; 3] {
+    match face {
+        0 => [1.0, -v, -u],
+        1 => [-1.0, -v, u],
+        2 => [u, 1.0, v],
+        3 => [u, -1.0, -v],
+        4 => [u, -v, 1.0],
+        _ => [-u, -v, -1.0],
+    }
+}
+
+/// The cubemap face seen in `direction`, and the point of it in
+/// `0.0 .. 1.0`. The inverse of `cube_direction`.
+fn cube_face_point(direction: Vector3<f32>) -> (usize, f32, f32) {
+    let Vector3 { x, y, z } = direction;
+    let (ax, ay, az) = (x.abs(), y.abs(), z.abs());
+    let (face, u, v) = if ax >= ay && ax >= az {
+        if x > 0.0 { (0, -z / ax, -y / ax) } else { (1, z / ax, -y / ax) }
+    } else if ay >= az {
+        if y > 0.0 { (2, x / ay, z / ay) } else { (3, x / ay, -z / ay) }
+    } else if z > 0.0 {
+        (4, x / az, -y / az)
+    } else {
+        (5, -x / az, -y / az)
+    };
+    (face, 0.5 * (u + 1.0), 0.5 * (v + 1.0))
+}
+
+/// The basis functions of the spherical harmonics up to the second order.
+fn sh_basis(d: [f32; 3]) -> [f32; 9] {
+    let [x, y, z] = d;
+    [
+        0.282095,
+        0.488603 * y,
+        0.488603 * z,
+        0.488603 * x,
+        1.092548 * x * y,
+        1.092548 * y * z,
+        0.315392 * (3.0 * z * z - 1.0),
+        1.092548 * x * z,
+        0.546274 * (x * x - y * y),
+    ]
+}
+
+impl LightProbe {
+    /// Creates a probe lighting every direction with the same `color`.
+    pub fn uniform(color: Color) -> Self {
+        let rgb = color::to_linear_rgb(color);
+        let mut coefficients = [[0.0; 3]; 9];
+        for (c, &value) in coefficients[0].iter_mut().zip(&rgb) {
+            *c = value / 0.282095;
+        }
+        LightProbe {
+            coefficients,
+            intensity: 1.0,
+        }
+    }
+
+    /// Projects the six faces of a cubemap onto spherical harmonics. Faces
+    /// are in the order `+X, -X, +Y, -Y, +Z, -Z`, in sRGB.
+    pub(crate) fn from_cube_faces(faces: &[image::RgbImage]) -> Self {
+        let to_linear: Vec<f32> = (0 .. 256u32).map(|c| color::to_linear_rgb(c)[2]).collect();
+        let mut radiance = [[0.0f32; 3]; 9];
+        let mut total_weight = 0.0;
+        for (face, image) in faces.iter().enumerate() {
+            let (width, height) = image.dimensions();
+            for (px, py, pixel) in image.enumerate_pixels() {
+                let u = 2.0 * (px as f32 + 0.5) / width as f32 - 1.0;
+                let v = 2.0 * (py as f32 + 0.5) / height as f32 - 1.0;
+                let d = cube_direction(face, u, v);
+                let length_sq = d[0] * d[0] + d[1] * d[1] + d[2] * d[2];
+                let length = length_sq.sqrt();
+                // solid angle covered by the texel, up to a constant factor
+                let weight = 1.0 / (length_sq * length);
+                total_weight += weight;
+                let basis = sh_basis([d[0] / length, d[1] / length, d[2] / length]);
+                for (coefficient, &y) in radiance.iter_mut().zip(&basis) {
+                    for (c, &channel) in coefficient.iter_mut().zip(&pixel.0) {
+                        *c += to_linear[channel as usize] * y * weight;
+                    }
+                }
+            }
+        }
+
+        // The texel weights sum up to the whole sphere, 4π. Convolving with
+        // the cosine lobe scales the bands by π, 2π/3 and π/4, and the
+        // division by π leaves 1, 2/3 and 1/4.
+        let scale = 4.0 * ::std::f32::consts::PI / total_weight.max(::std::f32::EPSILON);
+        let bands = [1.0, 2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 0.25, 0.25, 0.25, 0.25, 0.25];
+        let mut coefficients = [[0.0; 3]; 9];
+        for ((out, coefficient), band) in coefficients.iter_mut().zip(&radiance).zip(&bands) {
+            for (o, &c) in out.iter_mut().zip(coefficient) {
+                *o = c * scale * band;
+            }
+        }
+        LightProbe {
+            coefficients,
+            intensity: 1.0,
+        }
+    }
+
+    /// Computes the ambient light reaching a surface facing `normal`, in
+    /// linear RGB.
+    pub fn irradiance<V: Into<mint::Vector3<f32>>>(
+        &self,
+        normal: V,
+    ) -> [f32; 3] {
+        let n = normal.into();
+        let length = (n.x * n.x + n.y * n.y + n.z * n.z).sqrt().max(::std::f32::EPSILON);
+        let basis = sh_basis([n.x / length, n.y / length, n.z / length]);
+        let mut result = [0.0; 3];
+        for (coefficient, &y) in self.coefficients.iter().zip(&basis) {
+            for (r, &c) in result.iter_mut().zip(coefficient) {
+                *r += c * y * self.intensity;
+            }
+        }
+        [result[0].max(0.0), result[1].max(0.0), result[2].max(0.0)]
+    }
+
+    /// Packs the coefficients, scaled by the intensity, into vectors for
+    /// the shaders.
+    pub(crate) fn to_vectors(&self) -> [[f32; 4]; 9] {
+        let mut vectors = [[0.0; 4]; 9];
+        for (v, c) in vectors.iter_mut().zip(&self.coefficients) {
+            *v = [c[0] * self.intensity, c[1] * self.intensity, c[2] * self.intensity, 0.0];
+        }
+        vectors
+    }
+}
+
+/// Size in pixels of the faces of the sharpest level of reflection probes.
+const REFLECTION_SIZE: u32 = 128;
+/// Size in pixels of the faces of the blurriest level of reflection probes.
+const REFLECTION_MIN_SIZE: u32 = 4;
+/// Number of directions averaged for each texel of the blurred levels.
+const REFLECTION_SAMPLES: u32 = 64;
+
+/// Reflections of the surroundings, stored as a cubemap blurred more in each
+/// mipmap level, for surfaces of increasing roughness.
+///
+/// Probes are usually computed from the same cubemap as the skybox with
+/// [`Factory::load_reflection_probe`], and set as the
+/// [`Scene::reflection_probe`]. PBR materials add the reflections to their
+/// lighting, so metals mirror their surroundings. Together with a
+/// [`LightProbe`] for the diffuse part, they light a model by its
+/// environment alone.
+///
+/// [`Factory::load_reflection_probe`]: ../struct.Factory.html#method.load_reflection_probe
+/// [`Scene::reflection_probe`]: ../struct.Scene.html#structfield.reflection_probe
+/// [`LightProbe`]: struct.LightProbe.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReflectionProbe {
+    pub(crate) cubemap: CubeMap<[f32; 4]>,
+    pub(crate) levels: u32,
+
+    /// Multiplier of the reflections.
+    ///
+    /// Default: `1.0`.
+    pub intensity: f32,
+}
+
+/// The six faces of a cubemap in linear RGB, in the order
+/// `+X, -X, +Y, -Y, +Z, -Z`.
+struct LinearCube {
+    size: u32,
+    texels: Vec<[f32; 3]>,
+}
+
+impl LinearCube {
+    /// The texel seen in `direction`, without filtering.
+    fn fetch(
+        &self,
+        direction: Vector3<f32>,
+    ) -> [f32; 3] {
+        let (face, u, v) = cube_face_point(direction);
+        let px = ((u * self.size as f32) as u32).min(self.size - 1);
+        let py = ((v * self.size as f32) as u32).min(self.size - 1);
+        self.texels[((face as u32 * self.size + py) * self.size + px) as usize]
+    }
+
+    /// Halves the size of the faces, averaging blocks of four texels.
+    fn downsample(&self) -> Self {
+        let size = (self.size / 2).max(1);
+        let mut texels = Vec::with_capacity((6 * size * size) as usize);
+        for face in 0 .. 6 {
+            for py in 0 .. size {
+                for px in 0 .. size {
+                    let mut sum = [0.0; 3];
+                    for &(dx, dy) in &[(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let x = (2 * px + dx).min(self.size - 1);
+                        let y = (2 * py + dy).min(self.size - 1);
+                        let texel = self.texels[((face * self.size + y) * self.size + x) as usize];
+                        for (s, &t) in sum.iter_mut().zip(&texel) {
+                            *s += 0.25 * t;
+                        }
+                    }
+                    texels.push(sum);
+                }
+            }
+        }
+        LinearCube { size, texels }
+    }
+}
+
+impl ReflectionProbe {
+    /// Blurs the six faces of a cubemap in sRGB, in the order
+    /// `+X, -X, +Y, -Y, +Z, -Z`, into the mipmap levels of a probe. The
+    /// levels are returned face after face, as the texture upload expects.
+    ///
+    /// Each level reflects the cubemap off a surface of increasing roughness,
+    /// from a mirror to fully rough, by importance sampling the GGX
+    /// distribution. Samples are read from smaller copies of the cubemap
+    /// where they are sparse, which avoids noise with few of them.
+    pub(crate) fn prefilter(faces: &[image::RgbImage]) -> Vec<image::RgbaImage> {
+        use std::f32::consts::PI;
+
+        let mut size = REFLECTION_SIZE;
+        while size > faces[0].width().max(1) {
+            size /= 2;
+        }
+        let to_linear: Vec<f32> = (0 .. 256u32).map(|c| color::to_linear_rgb(c)[2]).collect();
+        let mut texels = Vec::with_capacity((6 * size * size) as usize);
+        for face in faces {
+            let face = image::imageops::resize(face, size, size, image::imageops::FilterType::Triangle);
+            texels.extend(face.pixels().map(|pixel| {
+                [
+                    to_linear[pixel[0] as usize],
+                    to_linear[pixel[1] as usize],
+                    to_linear[pixel[2] as usize],
+                ]
+            }));
+        }
+        let mut sources = vec![LinearCube { size, texels }];
+        while sources.last().unwrap().size > 1 {
+            let smaller = sources.last().unwrap().downsample();
+            sources.push(smaller);
+        }
+
+        let mut num_levels = 1;
+        while size >> num_levels >= REFLECTION_MIN_SIZE {
+            num_levels += 1;
+        }
+        let texel_angle = 4.0 * PI / (6 * size * size) as f32;
+        let levels = (0 .. num_levels)
+            .map(|level| {
+                let roughness = level as f32 / (num_levels - 1).max(1) as f32;
+                let alpha_sq = roughness.powi(4);
+                // Reflected directions around +Z for a view along it, their
+                // weight and the copy to read them from.
+                let samples = (0 .. REFLECTION_SAMPLES)
+                    .filter_map(|i| {
+                        if level == 0 {
+                            return if i == 0 { Some((Vector3::unit_z(), 1.0, 0)) } else { None };
+                        }
+                        let e1 = (i as f32 + 0.5) / REFLECTION_SAMPLES as f32;
+                        let e2 = i.reverse_bits() as f32 / 4_294_967_296.0;
+                        let phi = 2.0 * PI * e2;
+                        let cos_theta = ((1.0 - e1) / (1.0 + (alpha_sq - 1.0) * e1)).sqrt();
+                        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+                        let l = Vector3::new(
+                            2.0 * cos_theta * sin_theta * phi.cos(),
+                            2.0 * cos_theta * sin_theta * phi.sin(),
+                            2.0 * cos_theta * cos_theta - 1.0,
+                        );
+                        if l.z <= 0.0 {
+                            return None;
+                        }
+                        // the probability of `l` is a quarter of the GGX
+                        // distribution of the half vector
+                        let f = cos_theta * cos_theta * (alpha_sq - 1.0) + 1.0;
+                        let pdf = alpha_sq / (4.0 * PI * f * f);
+                        let sample_angle = 1.0 / (REFLECTION_SAMPLES as f32 * pdf);
+                        let lod = 0.5 * (sample_angle / texel_angle).log2() + 1.0;
+                        let source = (lod.max(0.0).round() as usize).min(sources.len() - 1);
+                        Some((l, l.z, source))
+                    })
+                    .collect::<Vec<_>>();
+                let total_weight: f32 = samples.iter().map(|&(_, weight, _)| weight).sum();
+
+                let level_size = size >> level;
+                (0 .. 6)
+                    .map(|face| {
+                        image::RgbaImage::from_fn(level_size, level_size, |px, py| {
+                            let u = 2.0 * (px as f32 + 0.5) / level_size as f32 - 1.0;
+                            let v = 2.0 * (py as f32 + 0.5) / level_size as f32 - 1.0;
+                            let n = Vector3::from(cube_direction(face, u, v)).normalize();
+                            let up = if n.z.abs() < 0.999 { Vector3::unit_z() } else { Vector3::unit_x() };
+                            let tangent = up.cross(n).normalize();
+                            let bitangent = n.cross(tangent);
+                            let mut sum = [0.0; 3];
+                            for &(l, weight, source) in &samples {
+                                let direction = tangent * l.x + bitangent * l.y + n * l.z;
+                                let texel = sources[source].fetch(direction);
+                                for (s, &t) in sum.iter_mut().zip(&texel) {
+                                    *s += t * weight / total_weight;
+                                }
+                            }
+                            let c = color::from_linear_rgb(sum);
+                            image::Rgba([(c >> 16) as u8, (c >> 8) as u8, c as u8, 0xFF])
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        (0 .. 6)
+            .flat_map(|face| levels.iter().map(move |faces| faces[face].clone()))
+            .collect()
+    }
+}
@@ -1,12 +1,23 @@
 //! Material parameters for mesh rendering.
 
 use color;
+use gfx;
 
 use color::Color;
-use render::BasicPipelineState;
+use froggy::WeakPointer;
+use gfx::state::{Comparison, StencilOp};
+use hub::{Message, Operation};
+use node::NodeInternal;
+use object::{Base, Object};
+use render::{BasicPipelineState, DataBuffer};
 use texture::Texture;
 use util;
 
+use std::cell::RefCell;
+use std::hash::{Hash, Hasher};
+use std::rc::Rc;
+use std::sync::mpsc;
+
 #[doc(inline)]
 pub use self::basic::Basic;
 
@@ -28,6 +39,27 @@ pub mod basic {
         ///
         /// Default: `None`.
         pub map: Option<Texture<[f32; 4]>>,
+
+        /// Stencil test and operations.
+        ///
+        /// Default: `None` (the stencil buffer is ignored).
+        pub stencil: Option<Stencil>,
+
+        /// Optional shader features.
+        ///
+        /// Default: none.
+        pub features: ShaderFeatures,
+
+        /// How the alpha of the material is used.
+        ///
+        /// Default: `Opaque`.
+        pub transparency: Transparency,
+
+        /// Whether the material writes to the depth buffer, hiding what is
+        /// drawn behind it afterwards. Blended materials usually turn it off.
+        ///
+        /// Default: `true`.
+        pub depth_write: bool,
     }
 
     impl Default for Basic {
@@ -35,6 +67,10 @@ pub mod basic {
             Self {
                 color: color::WHITE,
                 map: None,
+                stencil: None,
+                features: ShaderFeatures::empty(),
+                transparency: Transparency::Opaque,
+                depth_write: true,
             }
         }
     }
@@ -43,7 +79,53 @@ pub mod basic {
     ///
     /// Renders triangle meshes with a custom pipeline with a basic material as
     /// its input.
-    #[derive(Clone, Debug, PartialEq, Hash)]
+    ///
+    /// # Examples
+    ///
+    /// A dissolve effect driven by an animation track. The vertex shader
+    /// passes `i_MatParams.x` on to the pixel shader, which discards the
+    /// pixels whose noise is below it.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let geometry = three::Geometry::cuboid(1.0, 1.0, 1.0);
+    /// use three::Object;
+    /// use three::animation::{Binding, Clip, Track, Values};
+    ///
+    /// let pipeline = window.factory.basic_pipeline(
+    ///     "shaders",
+    ///     "dissolve",
+    ///     three::custom::Primitive::TriangleList,
+    ///     three::custom::state::Rasterizer::new_fill(),
+    ///     three::custom::state::ColorMask::all(),
+    ///     three::custom::preset::blend::ALPHA,
+    ///     three::custom::preset::depth::LESS_EQUAL_WRITE,
+    ///     three::custom::state::Stencil::default(),
+    /// ).unwrap();
+    /// let material = three::material::basic::Custom {
+    ///     color: three::color::WHITE,
+    ///     map: None,
+    ///     pipeline,
+    ///     stencil_reference: 0,
+    ///     params: [0.0; 4],
+    ///     param_names: ["dissolve", "", "", ""],
+    ///     data: None,
+    ///     user_params: None,
+    /// };
+    /// let mesh = window.factory.mesh(geometry, material);
+    ///
+    /// let track = Track {
+    ///     binding: Binding::Param("dissolve"),
+    ///     interpolation: three::animation::Interpolation::Linear,
+    ///     times: vec![0.0, 2.0],
+    ///     values: Values::Scalar(vec![0.0, 1.0]),
+    /// };
+    /// let clip = Clip { name: None, tracks: vec![(track, mesh.upcast())] };
+    /// let mut mixer = three::animation::Mixer::new();
+    /// mixer.action(clip);
+    /// ```
+    #[derive(Derivative)]
+    #[derivative(Clone, Debug, PartialEq, Hash)]
     pub struct Custom {
         /// Solid color applied in the absense of `map`.
         pub color: Color,
@@ -53,11 +135,219 @@ pub mod basic {
 
         /// The custom pipeline state object to be applied to the mesh.
         pub pipeline: BasicPipelineState,
+
+        /// Reference value for the stencil state of the pipeline.
+        pub stencil_reference: u8,
+
+        /// Values of each mesh, read by the shaders as the `i_MatParams`
+        /// instance attribute.
+        #[derivative(Hash(hash_with = "util::hash_f32_slice"))]
+        pub params: [f32; 4],
+
+        /// Names of the `params`, targeted by [`Binding::Param`] animation
+        /// tracks. Unused parameters can be left with empty names.
+        ///
+        /// [`Binding::Param`]: ../../animation/enum.Binding.html#variant.Param
+        pub param_names: [&'static str; 4],
+
+        /// Buffer read by the shaders as `uniform samplerBuffer b_UserData`,
+        /// empty without one.
+        pub data: Option<DataBuffer>,
+
+        /// Overrides the user parameters of [`Renderer::set_user_params`]
+        /// for the meshes of this material, `None` to use the global ones.
+        ///
+        /// [`Renderer::set_user_params`]: ../../struct.Renderer.html#method.set_user_params
+        #[derivative(Hash(hash_with = "util::hash_f32_slice_option"))]
+        pub user_params: Option<[f32; 16]>,
     }
 
     impl Eq for Custom {}
 }
 
+bitflags! {
+    /// Optional features of the built-in shaders, enabled per material.
+    ///
+    /// Every combination of features is compiled into its own pipeline the
+    /// first time a material uses it, so that materials only pay for the
+    /// features they enable.
+    ///
+    /// Only the [`Basic`], [`Lambert`] and [`Phong`] materials have shader
+    /// features; the PBR, sprite and custom pipelines ignore them.
+    ///
+    /// [`Basic`]: basic/struct.Basic.html
+    /// [`Lambert`]: struct.Lambert.html
+    /// [`Phong`]: struct.Phong.html
+    ///
+    /// ```rust
+    /// use three::material::{self, ShaderFeatures};
+    ///
+    /// let leaves = material::Lambert {
+    ///     features: ShaderFeatures::ALPHA_TEST | ShaderFeatures::FOG,
+    ///     .. Default::default()
+    /// };
+    /// ```
+    pub struct ShaderFeatures: u32 {
+        /// Discards the pixels whose alpha is below one half, for cut-out
+        /// textures such as foliage. [`Transparency::Mask`] sets another
+        /// cutoff.
+        ///
+        /// [`Transparency::Mask`]: enum.Transparency.html#variant.Mask
+        const ALPHA_TEST    = 1 << 0;
+        /// Fades the color into the [`Scene::fog`] with the distance from
+        /// the camera.
+        ///
+        /// [`Scene::fog`]: ../scene/struct.Scene.html#structfield.fog
+        const FOG           = 1 << 1;
+        /// Multiplies the color by the [`Geometry::colors`] of the vertices.
+        ///
+        /// [`Geometry::colors`]: ../struct.Geometry.html#structfield.colors
+        const VERTEX_COLORS = 1 << 2;
+        /// Samples the textures with the second set of texture coordinates,
+        /// [`Geometry::tex_coords1`], such as the ones of baked light maps.
+        ///
+        /// [`Geometry::tex_coords1`]: ../struct.Geometry.html#structfield.tex_coords1
+        const UV1           = 1 << 3;
+    }
+}
+
+impl ShaderFeatures {
+    /// The preprocessor definitions enabling the features in the shaders.
+    pub(crate) fn defines(self) -> String {
+        let mut defines = String::new();
+        if self.contains(ShaderFeatures::ALPHA_TEST) {
+            defines.push_str("#define ALPHA_TEST\n");
+        }
+        if self.contains(ShaderFeatures::FOG) {
+            defines.push_str("#define FOG\n");
+        }
+        if self.contains(ShaderFeatures::VERTEX_COLORS) {
+            defines.push_str("#define VERTEX_COLORS\n");
+        }
+        if self.contains(ShaderFeatures::UV1) {
+            defines.push_str("#define UV1\n");
+        }
+        defines
+    }
+}
+
+/// How the alpha of a material is used.
+///
+/// Objects faded by [`Object::set_opacity`] are blended whatever the
+/// transparency of their material.
+///
+/// [`Object::set_opacity`]: ../trait.Object.html#method.set_opacity
+///
+/// ```rust
+/// use three::material::{self, Transparency};
+///
+/// let glass = material::Phong {
+///     color: 0x80C0FF,
+///     transparency: Transparency::Blend,
+///     depth_write: false,
+///     .. Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transparency {
+    /// Drawn with the opaque objects, in the order of the scene, replacing
+    /// what is behind regardless of the alpha of the color and map.
+    Opaque,
+
+    /// Blended over what is behind, with the alpha of the color and map.
+    /// Blended objects are drawn after the opaque ones, from back to front
+    /// by the distance of their center to the camera.
+    Blend,
+
+    /// Drawn with the opaque objects, discarding the pixels whose alpha is
+    /// below the cutoff, for cut-out textures such as foliage.
+    Mask(f32),
+}
+
+impl Eq for Transparency {}
+
+impl Hash for Transparency {
+    fn hash<H: Hasher>(
+        &self,
+        state: &mut H,
+    ) {
+        match *self {
+            Transparency::Opaque => 0u8.hash(state),
+            Transparency::Blend => 1u8.hash(state),
+            Transparency::Mask(cutoff) => {
+                2u8.hash(state);
+                util::hash_f32(&cutoff, state);
+            }
+        }
+    }
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Transparency::Opaque
+    }
+}
+
+/// Stencil test and operations applied when drawing a mesh, for effects
+/// such as mirrors, portals and outlines.
+///
+/// The stencil buffer is cleared to zero at the start of every frame.
+///
+/// ```rust
+/// use three::custom::state::Comparison;
+/// use three::material::{self, Stencil};
+///
+/// // mark the pixels covered by a mirror
+/// let mask = material::Basic {
+///     stencil: Some(Stencil::write(1)),
+///     .. Default::default()
+/// };
+/// // draw the reflected scene only inside them
+/// let reflected = material::Phong {
+///     stencil: Some(Stencil::test(Comparison::Equal, 1)),
+///     .. Default::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+pub struct Stencil {
+    /// Comparison, masks and operations for front and back faces.
+    pub state: gfx::state::Stencil,
+
+    /// Value compared against the stencil buffer, and written to it by
+    /// `StencilOp::Replace`.
+    pub reference: u8,
+}
+
+impl Stencil {
+    /// Writes `reference` into the stencil buffer wherever the mesh is drawn.
+    pub fn write(reference: u8) -> Self {
+        Stencil {
+            state: gfx::state::Stencil::new(
+                Comparison::Always,
+                !0,
+                (StencilOp::Keep, StencilOp::Keep, StencilOp::Replace),
+            ),
+            reference,
+        }
+    }
+
+    /// Draws the mesh only where `reference` compares with `fun` to the
+    /// stencil buffer, leaving the buffer unchanged.
+    pub fn test(
+        fun: Comparison,
+        reference: u8,
+    ) -> Self {
+        Stencil {
+            state: gfx::state::Stencil::new(
+                fun,
+                !0,
+                (StencilOp::Keep, StencilOp::Keep, StencilOp::Keep),
+            ),
+            reference,
+        }
+    }
+}
+
 /// Parameters for a Lamberian diffusion reflection model.
 ///
 /// Renders triangle meshes with the Gouraud illumination model.
@@ -68,36 +358,142 @@ pub struct Lambert {
     /// Default: `WHITE`.
     pub color: Color,
 
+    /// Texture applied using the mesh texture co-ordinates, multiplied by
+    /// `color`.
+    ///
+    /// Default: `None`.
+    pub map: Option<Texture<[f32; 4]>>,
+
+    /// Color emitted by the surface regardless of the lights.
+    ///
+    /// Default: `BLACK`.
+    pub emissive: Color,
+
     /// Specifies whether lighting should be constant over faces.
     ///
     /// Default: `false` (lighting is interpolated across faces).
     pub flat: bool,
+
+    /// Stencil test and operations.
+    ///
+    /// Default: `None` (the stencil buffer is ignored).
+    pub stencil: Option<Stencil>,
+
+    /// Optional shader features.
+    ///
+    /// Default: none.
+    pub features: ShaderFeatures,
+
+    /// How the alpha of the material is used.
+    ///
+    /// Default: `Opaque`.
+    pub transparency: Transparency,
+
+    /// Whether the material writes to the depth buffer, hiding what is
+    /// drawn behind it afterwards. Blended materials usually turn it off.
+    ///
+    /// Default: `true`.
+    pub depth_write: bool,
 }
 
 impl Default for Lambert {
     fn default() -> Self {
         Self {
             color: color::WHITE,
+            map: None,
+            emissive: color::BLACK,
             flat: false,
+            stencil: None,
+            features: ShaderFeatures::empty(),
+            transparency: Transparency::Opaque,
+            depth_write: true,
         }
     }
 }
 
+/// Lengths of the dashes of a [`Line`](struct.Line.html) and the gaps
+/// between them, in the local units of the mesh.
+#[derive(Derivative)]
+#[derivative(Clone, Copy, Debug, PartialEq, Hash, Eq)]
+pub struct DashPattern {
+    /// Length of the drawn dashes.
+    #[derivative(Hash(hash_with = "util::hash_f32"))]
+    pub dash: f32,
+
+    /// Length of the gaps between the dashes.
+    #[derivative(Hash(hash_with = "util::hash_f32"))]
+    pub gap: f32,
+}
+
 /// Parameters for a line material.
 ///
-/// Renders line strip meshes with a solid color and unit width.
-#[derive(Clone, Hash, Debug, PartialEq, Eq)]
+/// Renders line strip meshes with a solid color, such as the ones created
+/// with [`Geometry::with_line_strip`]. Each segment of the strip is drawn as
+/// a quad facing the screen, so sharp corners of very wide lines show small
+/// notches.
+///
+/// Only meshes created by [`Factory::mesh`] with a line material support
+/// `width` and `dash`. Others, such as dynamic meshes, are drawn as solid
+/// lines one pixel wide.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// use three::material::{DashPattern, Line};
+///
+/// let outline = window.factory.mesh(
+///     three::Geometry::with_line_strip(vec![
+///         [-1.0, -1.0, 0.0].into(),
+///         [1.0, -1.0, 0.0].into(),
+///         [1.0, 1.0, 0.0].into(),
+///         [-1.0, 1.0, 0.0].into(),
+///         [-1.0, -1.0, 0.0].into(),
+///     ]),
+///     Line {
+///         color: 0x2080FF,
+///         width: 3.0,
+///         dash: Some(DashPattern { dash: 0.2, gap: 0.1 }),
+///         .. Default::default()
+///     },
+/// );
+/// window.scene.add(&outline);
+/// ```
+///
+/// [`Geometry::with_line_strip`]: ../struct.Geometry.html#method.with_line_strip
+/// [`Factory::mesh`]: ../struct.Factory.html#method.mesh
+#[derive(Derivative)]
+#[derivative(Clone, Debug, PartialEq, Hash, Eq)]
 pub struct Line {
     /// Solid line color.
     ///
     /// Default: `0xFFFFFF` (white).
     pub color: Color,
+
+    /// Width of the line in pixels.
+    ///
+    /// Default: `1.0`.
+    #[derivative(Hash(hash_with = "util::hash_f32"))]
+    pub width: f32,
+
+    /// Draws the line in dashes instead of solid.
+    ///
+    /// Default: `None`.
+    pub dash: Option<DashPattern>,
+
+    /// Stencil test and operations.
+    ///
+    /// Default: `None` (the stencil buffer is ignored).
+    pub stencil: Option<Stencil>,
 }
 
 impl Default for Line {
     fn default() -> Self {
         Self {
             color: color::WHITE,
+            width: 1.0,
+            dash: None,
+            stencil: None,
         }
     }
 }
@@ -147,6 +543,22 @@ pub struct Pbr {
     /// Default: `BLACK`.
     pub emissive_factor: Color,
 
+    /// Scalar multiplier applied to the emissive color.
+    ///
+    /// Default: `1.0`.
+    #[derivative(Hash(hash_with = "util::hash_f32"))]
+    pub emissive_intensity: f32,
+
+    /// Scalar multiplier applied to the emissive color feeding a selective
+    /// [`Bloom`] pass. Materials with a value of 0 don't glow, however
+    /// bright they are.
+    ///
+    /// Default: `0.0`.
+    ///
+    /// [`Bloom`]: ../render/composer/struct.Bloom.html
+    #[derivative(Hash(hash_with = "util::hash_f32"))]
+    pub bloom_intensity: f32,
+
     /// Scalar multiplier applied to each normal vector of the `normal_map`.
     ///
     /// This value is ignored in the absense of `normal_map`.
@@ -190,6 +602,8 @@ impl Default for Pbr {
             roughness_factor: 1.0,
             occlusion_strength: 1.0,
             emissive_factor: color::BLACK,
+            emissive_intensity: 1.0,
+            bloom_intensity: 0.0,
             normal_scale: 1.0,
             base_color_map: None,
             normal_map: None,
@@ -203,6 +617,9 @@ impl Default for Pbr {
 /// Parameters for a Phong reflection model.
 ///
 /// Renders triangle meshes with the Phong illumination model.
+///
+/// All the maps are sampled with the texture co-ordinates of the mesh,
+/// within the texel range of `map` when it has one.
 #[derive(Derivative)]
 #[derivative(Clone, Debug, PartialEq, Hash, Eq)]
 pub struct Phong {
@@ -211,6 +628,30 @@ pub struct Phong {
     /// Default: `WHITE`.
     pub color: Color,
 
+    /// Texture applied using the mesh texture co-ordinates, multiplied by
+    /// `color`.
+    ///
+    /// Default: `None`.
+    pub map: Option<Texture<[f32; 4]>>,
+
+    /// Tangent space normal map, with X, Y and Z in the red, green and blue
+    /// channels. It should be loaded with `ColorSpace::Linear`, and the
+    /// geometry needs tangents.
+    ///
+    /// Default: `None`.
+    pub normal_map: Option<Texture<[f32; 4]>>,
+
+    /// Texture whose color multiplies the specular highlights, dimming them
+    /// on the rough parts of the surface.
+    ///
+    /// Default: `None`.
+    pub specular_map: Option<Texture<[f32; 4]>>,
+
+    /// Color emitted by the surface regardless of the lights.
+    ///
+    /// Default: `BLACK`.
+    pub emissive: Color,
+
     /// Determines the sharpness of specular highlights.
     ///
     /// Higher values result in sharper highlights to produce a glossy effect.
@@ -218,13 +659,42 @@ pub struct Phong {
     /// Default: `30.0`.
     #[derivative(Hash(hash_with = "util::hash_f32"))]
     pub glossiness: f32,
+
+    /// Stencil test and operations.
+    ///
+    /// Default: `None` (the stencil buffer is ignored).
+    pub stencil: Option<Stencil>,
+
+    /// Optional shader features.
+    ///
+    /// Default: none.
+    pub features: ShaderFeatures,
+
+    /// How the alpha of the material is used.
+    ///
+    /// Default: `Opaque`.
+    pub transparency: Transparency,
+
+    /// Whether the material writes to the depth buffer, hiding what is
+    /// drawn behind it afterwards. Blended materials usually turn it off.
+    ///
+    /// Default: `true`.
+    pub depth_write: bool,
 }
 
 impl Default for Phong {
     fn default() -> Self {
         Self {
             color: color::WHITE,
+            map: None,
+            normal_map: None,
+            specular_map: None,
+            emissive: color::BLACK,
             glossiness: 30.0,
+            stencil: None,
+            features: ShaderFeatures::empty(),
+            transparency: Transparency::Opaque,
+            depth_write: true,
         }
     }
 }
@@ -249,6 +719,20 @@ pub struct Wireframe {
     ///
     /// Default: `WHITE`.
     pub color: Color,
+
+    /// Stencil test and operations.
+    ///
+    /// Default: `None` (the stencil buffer is ignored).
+    pub stencil: Option<Stencil>,
+}
+
+impl Default for Wireframe {
+    fn default() -> Self {
+        Self {
+            color: color::WHITE,
+            stencil: None,
+        }
+    }
 }
 
 /// Specifies the appearance of a [`Mesh`](struct.Mesh.html).
@@ -261,7 +745,8 @@ pub enum Material {
     /// its input.
     CustomBasic(basic::Custom),
 
-    /// Renders line strip meshes with a solid color and unit width.
+    /// Renders line strip meshes with a solid color, in any width and
+    /// optionally dashed.
     Line(Line),
 
     /// Renders triangle meshes with the Gouraud illumination model.
@@ -283,6 +768,149 @@ pub enum Material {
     Wireframe(Wireframe),
 }
 
+impl Material {
+    /// Sets the main color of the material, if it has one.
+    pub(crate) fn set_color(
+        &mut self,
+        color: Color,
+    ) {
+        match *self {
+            Material::Basic(ref mut params) => params.color = color,
+            Material::CustomBasic(ref mut params) => params.color = color,
+            Material::Line(ref mut params) => params.color = color,
+            Material::Lambert(ref mut params) => params.color = color,
+            Material::Phong(ref mut params) => params.color = color,
+            Material::Pbr(ref mut params) => params.base_color_factor = color,
+            Material::Wireframe(ref mut params) => params.color = color,
+            Material::Sprite(_) => {}
+        }
+    }
+
+    /// Returns the material with the parameters that each instance carries
+    /// on its own (color, texel range and the material parameter) reset,
+    /// so that meshes differing only in those are drawn in one batch.
+    pub(crate) fn batch_key(&self) -> Material {
+        let mut key = self.clone();
+        match key {
+            Material::Basic(ref mut params) => {
+                params.color = 0;
+                params.map = params.map.as_ref().map(Texture::full_range);
+            }
+            Material::CustomBasic(ref mut params) => {
+                params.color = 0;
+                params.map = params.map.as_ref().map(Texture::full_range);
+                params.params = [0.0; 4];
+            }
+            Material::Line(ref mut params) => params.color = 0,
+            Material::Lambert(ref mut params) => {
+                params.color = 0;
+                params.map = params.map.as_ref().map(Texture::full_range);
+                params.emissive = 0;
+                params.flat = false;
+            }
+            Material::Phong(ref mut params) => {
+                params.color = 0;
+                params.map = params.map.as_ref().map(Texture::full_range);
+                params.emissive = 0;
+                params.glossiness = 0.0;
+            }
+            Material::Sprite(ref mut params) => params.map = params.map.full_range(),
+            Material::Wireframe(ref mut params) => params.color = 0,
+            Material::Pbr(_) => {}
+        }
+        key
+    }
+
+    /// The stencil state of a built-in basic material, if set.
+    pub(crate) fn stencil(&self) -> Option<Stencil> {
+        match *self {
+            Material::Basic(ref params) => params.stencil,
+            Material::Line(ref params) => params.stencil,
+            Material::Lambert(ref params) => params.stencil,
+            Material::Phong(ref params) => params.stencil,
+            Material::Wireframe(ref params) => params.stencil,
+            Material::CustomBasic(_) |
+            Material::Pbr(_) |
+            Material::Sprite(_) => None,
+        }
+    }
+
+    /// The optional shader features of a built-in basic material, with the
+    /// alpha test of masked materials.
+    pub(crate) fn features(&self) -> ShaderFeatures {
+        let features = match *self {
+            Material::Basic(ref params) => params.features,
+            Material::Lambert(ref params) => params.features,
+            Material::Phong(ref params) => params.features,
+            _ => ShaderFeatures::empty(),
+        };
+        match self.transparency() {
+            Transparency::Mask(_) => features | ShaderFeatures::ALPHA_TEST,
+            _ => features,
+        }
+    }
+
+    /// How the alpha of a built-in basic material is used.
+    pub(crate) fn transparency(&self) -> Transparency {
+        match *self {
+            Material::Basic(ref params) => params.transparency,
+            Material::Lambert(ref params) => params.transparency,
+            Material::Phong(ref params) => params.transparency,
+            _ => Transparency::Opaque,
+        }
+    }
+
+    /// The alpha below which the alpha test discards pixels.
+    pub(crate) fn alpha_cutoff(&self) -> f32 {
+        match self.transparency() {
+            Transparency::Mask(cutoff) => cutoff,
+            _ => 0.5,
+        }
+    }
+
+    /// Whether a built-in basic material writes to the depth buffer.
+    pub(crate) fn depth_write(&self) -> bool {
+        match *self {
+            Material::Basic(ref params) => params.depth_write,
+            Material::Lambert(ref params) => params.depth_write,
+            Material::Phong(ref params) => params.depth_write,
+            _ => true,
+        }
+    }
+
+    /// The reference value for the stencil test.
+    pub(crate) fn stencil_reference(&self) -> u8 {
+        match *self {
+            Material::CustomBasic(ref params) => params.stencil_reference,
+            _ => self.stencil().map_or(0, |stencil| stencil.reference),
+        }
+    }
+
+    /// Sets the custom parameter called `name`, if the material has one.
+    pub(crate) fn set_param(
+        &mut self,
+        name: &str,
+        value: f32,
+    ) {
+        if let Material::CustomBasic(ref mut params) = *self {
+            match params.param_names.iter().position(|&n| n == name) {
+                Some(index) => params.params[index] = value,
+                None => error!("Unknown material parameter {:?}", name),
+            }
+        }
+    }
+
+    /// Sets the emissive intensity of the material, if it has one.
+    pub(crate) fn set_emissive_intensity(
+        &mut self,
+        intensity: f32,
+    ) {
+        if let Material::Pbr(ref mut params) = *self {
+            params.emissive_intensity = intensity;
+        }
+    }
+}
+
 impl From<Basic> for Material {
     fn from(params: Basic) -> Self {
         Material::Basic(params)
@@ -330,3 +958,129 @@ impl From<Wireframe> for Material {
         Material::Wireframe(params)
     }
 }
+
+/// A material shared by several meshes.
+///
+/// Meshes normally keep their own copy of the material they were given.
+/// Meshes attached to a handle instead follow it: changing the material
+/// through the handle updates all of them on the next frame. Clones of the
+/// handle refer to the same material.
+///
+/// Like the objects it is attached to, the handle is neither `Send` nor
+/// `Sync`, and stays on the thread of the window.
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// use three::Object;
+/// use three::material::{self, MaterialHandle};
+///
+/// let handle = MaterialHandle::new(material::Phong {
+///     color: three::color::RED,
+///     glossiness: 40.0,
+///     .. Default::default()
+/// });
+/// for x in 0 .. 10 {
+///     let cube = window.factory.mesh(
+///         three::Geometry::cuboid(1.0, 1.0, 1.0),
+///         material::Basic::default(),
+///     );
+///     cube.set_material_handle(&handle);
+///     cube.set_position([x as f32 * 2.0, 0.0, 0.0]);
+///     window.scene.add(&cube);
+/// }
+///
+/// // turns all cubes green
+/// handle.set_color(three::color::GREEN);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MaterialHandle {
+    inner: Rc<RefCell<SharedMaterial>>,
+}
+
+#[derive(Debug)]
+struct SharedMaterial {
+    material: Material,
+    users: Vec<(WeakPointer<NodeInternal>, mpsc::Sender<Message>)>,
+}
+
+impl MaterialHandle {
+    /// Creates a handle to `material`, not used by any mesh yet.
+    pub fn new<M: Into<Material>>(material: M) -> Self {
+        MaterialHandle {
+            inner: Rc::new(RefCell::new(SharedMaterial {
+                material: material.into(),
+                users: Vec::new(),
+            })),
+        }
+    }
+
+    /// Returns a copy of the current material.
+    pub fn material(&self) -> Material {
+        self.inner.borrow().material.clone()
+    }
+
+    /// Replaces the material of all attached meshes.
+    pub fn set<M: Into<Material>>(
+        &self,
+        material: M,
+    ) {
+        self.update(|current| *current = material.into());
+    }
+
+    /// Sets the main color of the material, if it has one.
+    pub fn set_color(
+        &self,
+        color: Color,
+    ) {
+        self.update(|material| material.set_color(color));
+    }
+
+    /// Edits the material in place, for changing its maps or parameters.
+    ///
+    /// ```rust
+    /// # use three::material::{self, Material, MaterialHandle};
+    /// let handle = MaterialHandle::new(material::Phong::default());
+    /// handle.update(|material| if let Material::Phong(ref mut params) = *material {
+    ///     params.glossiness = 10.0;
+    /// });
+    /// ```
+    pub fn update<F: FnOnce(&mut Material)>(
+        &self,
+        fun: F,
+    ) {
+        let mut shared = self.inner.borrow_mut();
+        fun(&mut shared.material);
+        let material = shared.material.clone();
+        shared.users.retain(|user| {
+            let (ref node, ref tx) = *user;
+            node.upgrade().is_ok() && tx.send((node.clone(), Operation::SetMaterial(material.clone()))).is_ok()
+        });
+    }
+
+    /// Makes the mesh of `base` follow the handle, starting with its current
+    /// material.
+    pub(crate) fn attach(
+        &self,
+        base: &Base,
+    ) {
+        let mut shared = self.inner.borrow_mut();
+        let node = base.node.downgrade();
+        base.send(Operation::SetMaterial(shared.material.clone()));
+        if !shared.users.iter().any(|user| user.0 == node) {
+            shared.users.push((node, base.tx.clone()));
+        }
+    }
+
+    /// Stops `mesh` from following the handle. The mesh keeps the material
+    /// it has.
+    ///
+    /// Materials set directly on an attached mesh are overridden by the next
+    /// change of the handle, unless the mesh is detached first.
+    pub fn detach<T: Object>(
+        &self,
+        mesh: &T,
+    ) {
+        let node = mesh.as_ref().node.downgrade();
+        self.inner.borrow_mut().users.retain(|user| user.0 != node);
+    }
+}
@@ -1,10 +1,13 @@
+use color::Color;
 use geometry::Geometry;
 use hub::Operation;
-use material::Material;
+use material::{Material, MaterialHandle};
 use object::{self, DowncastObject, ObjectType};
 use render::DynamicData;
 use skeleton::Skeleton;
 
+use mint;
+
 use std::hash::{Hash, Hasher};
 
 /// [`Geometry`](struct.Geometry.html) with some [`Material`](struct.Material.html).
@@ -22,7 +25,7 @@ use std::hash::{Hash, Hasher};
 ///     [ 0.5, -0.5, 0.0].into(),
 /// ];
 /// let geometry = three::Geometry::with_vertices(vertices);
-/// let red_material = three::material::Basic { color: three::color::RED, map: None };
+/// let red_material = three::material::Basic { color: three::color::RED, .. Default::default() };
 /// let mesh = factory.mesh(geometry, red_material);
 /// # let _ = mesh;
 /// ```
@@ -38,7 +41,7 @@ use std::hash::{Hash, Hasher};
 /// #     [ 0.5, -0.5, 0.0].into(),
 /// # ];
 /// # let geometry = three::Geometry::with_vertices(vertices);
-/// # let red_material = three::material::Basic { color: three::color::RED, map: None };
+/// # let red_material = three::material::Basic { color: three::color::RED, .. Default::default() };
 /// # let mesh = factory.mesh(geometry, red_material);
 /// use three::Object;
 /// let mut duplicate = factory.mesh_instance(&mesh);
@@ -57,9 +60,9 @@ use std::hash::{Hash, Hasher};
 /// #     [ 0.5, -0.5, 0.0].into(),
 /// # ];
 /// # let geometry = three::Geometry::with_vertices(vertices);
-/// # let red_material = three::material::Basic { color: three::color::RED, map: None };
+/// # let red_material = three::material::Basic { color: three::color::RED, .. Default::default() };
 /// # let mesh = factory.mesh(geometry, red_material);
-/// let yellow_material = three::material::Wireframe { color: three::color::YELLOW };
+/// let yellow_material = three::material::Wireframe { color: three::color::YELLOW, .. Default::default() };
 /// # use three::Object;
 /// let mut duplicate = factory.mesh_instance_with_material(&mesh, yellow_material);
 /// duplicate.set_position([1.2, 3.4, 5.6]);
@@ -114,6 +117,25 @@ impl Hash for DynamicMesh {
     }
 }
 
+/// A mesh drawn many times in a single draw call, once for each of its
+/// instances, such as the trees of a forest or the members of a crowd.
+///
+/// Created with [`Factory::instanced_mesh`]. The transforms of the instances
+/// are relative to the mesh, which is positioned like any other object.
+///
+/// Instanced meshes are culled as a whole, in the view and in shadow maps,
+/// by the bounds of all of their instances. Rays hit every instance but
+/// billboards, and report which one in [`Intersection::instance`].
+///
+/// [`Factory::instanced_mesh`]: struct.Factory.html#method.instanced_mesh
+/// [`Intersection::instance`]: raycast/struct.Intersection.html#structfield.instance
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct InstancedMesh {
+    pub(crate) object: object::Base,
+    pub(crate) capacity: usize,
+}
+three_object!(InstancedMesh::object);
+
 impl Mesh {
     /// Set mesh material.
     pub fn set_material<M: Into<Material>>(
@@ -123,6 +145,29 @@ impl Mesh {
         self.as_ref().send(Operation::SetMaterial(material.into()));
     }
 
+    /// Makes the mesh follow a shared material, see [`MaterialHandle`].
+    ///
+    /// [`MaterialHandle`]: ../material/struct.MaterialHandle.html
+    pub fn set_material_handle(
+        &self,
+        handle: &MaterialHandle,
+    ) {
+        handle.attach(self.as_ref());
+    }
+
+    /// Sets the weights of the morph targets of the mesh, in the order of
+    /// the [`shapes`] of its geometry. Missing weights are zero.
+    ///
+    /// Only the first eight morph targets are drawn.
+    ///
+    /// [`shapes`]: struct.Geometry.html#structfield.shapes
+    pub fn set_morph_weights(
+        &self,
+        weights: &[f32],
+    ) {
+        self.as_ref().send(Operation::SetWeights(weights.to_vec()));
+    }
+
     /// Bind a skeleton to the mesh.
     pub fn set_skeleton(
         &self,
@@ -132,6 +177,57 @@ impl Mesh {
     }
 }
 
+impl InstancedMesh {
+    /// The maximum number of instances drawn.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Replaces the instances by one for every transform, drawing nothing
+    /// when empty. Transforms past the capacity are ignored.
+    pub fn set_instances(
+        &self,
+        transforms: &[mint::ColumnMatrix4<f32>],
+    ) {
+        if transforms.len() > self.capacity {
+            error!("{} instances exceed the capacity of {}", transforms.len(), self.capacity);
+        }
+        let count = transforms.len().min(self.capacity);
+        self.as_ref().send(Operation::SetInstances(transforms[.. count].to_vec()));
+    }
+
+    /// Sets the colors of the instances, in the same order as their
+    /// transforms. They replace the color of `Basic`, `Lambert` and `Phong`
+    /// materials, and multiply the base color of `Pbr` ones.
+    ///
+    /// Instances past the end of `colors` keep the material color.
+    pub fn set_instance_colors(
+        &self,
+        colors: &[Color],
+    ) {
+        let count = colors.len().min(self.capacity);
+        self.as_ref().send(Operation::SetInstanceColors(colors[.. count].to_vec()));
+    }
+
+    /// Turns every instance to face the camera when drawn, keeping its
+    /// position and its scale along the X axis, like particles or foliage
+    /// impostors.
+    pub fn set_billboard(
+        &self,
+        billboard: bool,
+    ) {
+        self.as_ref().send(Operation::SetBillboard(billboard));
+    }
+
+    /// Set mesh material.
+    pub fn set_material<M: Into<Material>>(
+        &self,
+        material: M,
+    ) {
+        self.as_ref().send(Operation::SetMaterial(material.into()));
+    }
+}
+
 impl DynamicMesh {
     /// Returns the number of vertices of the geometry base shape.
     pub fn vertex_count(&self) -> usize {
@@ -145,4 +241,14 @@ impl DynamicMesh {
     ) {
         self.as_ref().send(Operation::SetMaterial(material.into()));
     }
+
+    /// Makes the mesh follow a shared material, see [`MaterialHandle`].
+    ///
+    /// [`MaterialHandle`]: ../material/struct.MaterialHandle.html
+    pub fn set_material_handle(
+        &self,
+        handle: &MaterialHandle,
+    ) {
+        handle.attach(self.as_ref());
+    }
 }
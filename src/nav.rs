@@ -0,0 +1,519 @@
+//! Navigation meshes and path finding.
+//!
+//! A [`NavMesh`] is a set of walkable triangles, either baked by hand or
+//! extracted from level [`Geometry`] by slope. Paths are found with A* over
+//! the triangle adjacency graph and straightened with the funnel algorithm,
+//! assuming the Y axis points up.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let level = three::Geometry::cuboid(20.0, 1.0, 20.0);
+//! # let agent = window.factory.group();
+//! use three::Object;
+//!
+//! let navmesh = three::nav::NavMesh::from_geometry(&level, 45.0);
+//! let path = navmesh.find_path([-5.0, 0.0, -5.0], [5.0, 0.0, 5.0]).unwrap();
+//!
+//! // visualize the path
+//! let line = window.factory.mesh(
+//!     three::nav::path_geometry(&path),
+//!     three::material::Line { color: 0xFF0000, .. Default::default() },
+//! );
+//! window.scene.add(&line);
+//!
+//! let mut follower = three::nav::PathFollower::new(path, 2.0);
+//! while window.update() && !follower.is_finished() {
+//!     agent.set_position(follower.update(window.input.delta_time()));
+//! }
+//! ```
+//!
+//! [`NavMesh`]: struct.NavMesh.html
+//! [`Geometry`]: ../struct.Geometry.html
+
+use cgmath::{EuclideanSpace, InnerSpace, Point3, Vector3};
+use mint;
+
+use geometry::Geometry;
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::f32;
+
+/// Walkable surface made of triangles.
+#[derive(Clone, Debug)]
+pub struct NavMesh {
+    vertices: Vec<Point3<f32>>,
+    triangles: Vec<[u32; 3]>,
+    /// Neighbouring triangle across each edge, edge `i` going from vertex `i` to `i + 1`.
+    neighbours: Vec<[Option<usize>; 3]>,
+}
+
+impl NavMesh {
+    /// Creates a navigation mesh from baked vertices and triangles.
+    ///
+    /// Triangles are connected where they share an edge, so vertices must not
+    /// be duplicated.
+    pub fn new(
+        vertices: Vec<mint::Point3<f32>>,
+        triangles: Vec<[u32; 3]>,
+    ) -> Self {
+        let mut edges = HashMap::new();
+        let mut neighbours: Vec<[Option<usize>; 3]> = vec![[None; 3]; triangles.len()];
+        for (t, tri) in triangles.iter().enumerate() {
+            for e in 0 .. 3 {
+                let (a, b) = (tri[e], tri[(e + 1) % 3]);
+                let key = (a.min(b), a.max(b));
+                match edges.get(&key).cloned() {
+                    Some((other, other_edge)) => {
+                        neighbours[t][e] = Some(other);
+                        neighbours[other][other_edge] = Some(t);
+                    }
+                    None => {
+                        edges.insert(key, (t, e));
+                    }
+                }
+            }
+        }
+        NavMesh {
+            vertices: vertices.into_iter().map(Point3::from).collect(),
+            triangles,
+            neighbours,
+        }
+    }
+
+    /// Extracts a navigation mesh from the triangles of `geometry` whose slope
+    /// is at most `max_slope` degrees. Coincident vertices are welded together.
+    pub fn from_geometry(
+        geometry: &Geometry,
+        max_slope: f32,
+    ) -> Self {
+        const WELD_PRECISION: f32 = 1.0e3;
+        let min_up = max_slope.to_radians().cos();
+        let faces = if geometry.faces.is_empty() {
+            (0 .. geometry.base.vertices.len() as u32 / 3)
+                .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+                .collect()
+        } else {
+            geometry.faces.clone()
+        };
+
+        let mut welded = HashMap::new();
+        let mut vertices = Vec::new();
+        let mut triangles = Vec::new();
+        for face in faces {
+            let p = [
+                Point3::from(geometry.base.vertices[face[0] as usize]),
+                Point3::from(geometry.base.vertices[face[1] as usize]),
+                Point3::from(geometry.base.vertices[face[2] as usize]),
+            ];
+            let normal = (p[1] - p[0]).cross(p[2] - p[0]);
+            if normal.magnitude2() == 0.0 || normal.normalize().y < min_up {
+                continue;
+            }
+            let mut tri = [0; 3];
+            for (out, v) in tri.iter_mut().zip(p.iter()) {
+                let key = (
+                    (v.x * WELD_PRECISION).round() as i64,
+                    (v.y * WELD_PRECISION).round() as i64,
+                    (v.z * WELD_PRECISION).round() as i64,
+                );
+                *out = *welded.entry(key).or_insert_with(|| {
+                    vertices.push((*v).into());
+                    vertices.len() as u32 - 1
+                });
+            }
+            triangles.push(tri);
+        }
+        NavMesh::new(vertices, triangles)
+    }
+
+    /// Returns the number of triangles.
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Creates a geometry of the navigation mesh for debug drawing, for
+    /// example with a [`Wireframe`](../material/struct.Wireframe.html) material.
+    pub fn debug_geometry(&self) -> Geometry {
+        let mut geometry = Geometry::with_vertices(self.vertices.iter().map(|&v| v.into()).collect());
+        geometry.faces = self.triangles.clone();
+        geometry
+    }
+
+    fn corners(
+        &self,
+        t: usize,
+    ) -> [Point3<f32>; 3] {
+        let tri = self.triangles[t];
+        [
+            self.vertices[tri[0] as usize],
+            self.vertices[tri[1] as usize],
+            self.vertices[tri[2] as usize],
+        ]
+    }
+
+    fn centroid(
+        &self,
+        t: usize,
+    ) -> Point3<f32> {
+        let c = self.corners(t);
+        Point3::centroid(&c)
+    }
+
+    /// Finds the triangle under `point`, or the closest one if there is none.
+    fn locate(
+        &self,
+        point: Point3<f32>,
+    ) -> Option<usize> {
+        let mut best = None;
+        let mut best_distance = f32::INFINITY;
+        for t in 0 .. self.triangles.len() {
+            let c = self.corners(t);
+            let distance = if contains_xz(&c, point) {
+                // prefer the closest floor vertically
+                let height = (c[0].y + c[1].y + c[2].y) / 3.0;
+                (height - point.y).abs()
+            } else {
+                // penalize triangles the point is not above
+                1.0e6 + (self.centroid(t) - point).magnitude()
+            };
+            if distance < best_distance {
+                best_distance = distance;
+                best = Some(t);
+            }
+        }
+        best
+    }
+
+    /// Finds a path from `start` to `goal`.
+    ///
+    /// Returns the list of way points including both ends, or `None` if the
+    /// goal can't be reached.
+    pub fn find_path<P>(
+        &self,
+        start: P,
+        goal: P,
+    ) -> Option<Vec<mint::Point3<f32>>>
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        let start = Point3::from(start.into());
+        let goal = Point3::from(goal.into());
+        let start_tri = self.locate(start)?;
+        let goal_tri = self.locate(goal)?;
+        let corridor = self.find_corridor(start_tri, goal_tri, goal)?;
+
+        let mut portals = vec![(start, start)];
+        for pair in corridor.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let edge = (0 .. 3).find(|&e| self.neighbours[from][e] == Some(to)).unwrap();
+            let tri = self.triangles[from];
+            let a = self.vertices[tri[edge] as usize];
+            let b = self.vertices[tri[(edge + 1) % 3] as usize];
+            let c = self.centroid(from);
+            // orient the portal as (left, right) seen from inside `from`
+            if area_xz(c, a, b) >= 0.0 {
+                portals.push((b, a));
+            } else {
+                portals.push((a, b));
+            }
+        }
+        portals.push((goal, goal));
+
+        Some(funnel(&portals).into_iter().map(Into::into).collect())
+    }
+
+    /// A* search over the triangle graph.
+    fn find_corridor(
+        &self,
+        start: usize,
+        goal: usize,
+        goal_point: Point3<f32>,
+    ) -> Option<Vec<usize>> {
+        let mut open = BinaryHeap::new();
+        let mut came_from = HashMap::new();
+        let mut cost = vec![f32::INFINITY; self.triangles.len()];
+        cost[start] = 0.0;
+        open.push(Candidate {
+            estimate: (self.centroid(start) - goal_point).magnitude(),
+            triangle: start,
+        });
+
+        while let Some(Candidate { triangle, .. }) = open.pop() {
+            if triangle == goal {
+                let mut corridor = vec![goal];
+                let mut current = goal;
+                while let Some(&prev) = came_from.get(&current) {
+                    corridor.push(prev);
+                    current = prev;
+                }
+                corridor.reverse();
+                return Some(corridor);
+            }
+            let center = self.centroid(triangle);
+            for next in self.neighbours[triangle].iter().filter_map(|n| *n) {
+                let next_center = self.centroid(next);
+                let new_cost = cost[triangle] + (next_center - center).magnitude();
+                if new_cost < cost[next] {
+                    cost[next] = new_cost;
+                    came_from.insert(next, triangle);
+                    open.push(Candidate {
+                        estimate: new_cost + (next_center - goal_point).magnitude(),
+                        triangle: next,
+                    });
+                }
+            }
+        }
+        None
+    }
+}
+
+struct Candidate {
+    estimate: f32,
+    triangle: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(
+        &self,
+        other: &Self,
+    ) -> bool {
+        self.estimate == other.estimate
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(
+        &self,
+        other: &Self,
+    ) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        // reversed, so that the heap pops the lowest estimate first
+        other.estimate.partial_cmp(&self.estimate).unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Twice the signed area of a triangle projected onto the XZ plane.
+fn area_xz(
+    a: Point3<f32>,
+    b: Point3<f32>,
+    c: Point3<f32>,
+) -> f32 {
+    (b.x - a.x) * (c.z - a.z) - (c.x - a.x) * (b.z - a.z)
+}
+
+fn contains_xz(
+    c: &[Point3<f32>; 3],
+    p: Point3<f32>,
+) -> bool {
+    let d0 = area_xz(c[0], c[1], p);
+    let d1 = area_xz(c[1], c[2], p);
+    let d2 = area_xz(c[2], c[0], p);
+    let negative = d0 < 0.0 || d1 < 0.0 || d2 < 0.0;
+    let positive = d0 > 0.0 || d1 > 0.0 || d2 > 0.0;
+    !(negative && positive)
+}
+
+/// Simple stupid funnel algorithm over `(left, right)` portals.
+fn funnel(portals: &[(Point3<f32>, Point3<f32>)]) -> Vec<Point3<f32>> {
+    // restarting from an apex may reach a corner that was already pushed
+    fn push(
+        points: &mut Vec<Point3<f32>>,
+        point: Point3<f32>,
+    ) {
+        if points.last() != Some(&point) {
+            points.push(point);
+        }
+    }
+
+    let mut points = vec![portals[0].0];
+    let (mut apex, mut left, mut right) = (portals[0].0, portals[0].0, portals[0].1);
+    let (mut left_index, mut right_index) = (0, 0);
+
+    let mut i = 1;
+    while i < portals.len() {
+        let (new_left, new_right) = portals[i];
+
+        // tighten the right side
+        if area_xz(apex, right, new_right) >= 0.0 {
+            if apex == right || area_xz(apex, left, new_right) < 0.0 {
+                right = new_right;
+                right_index = i;
+            } else {
+                // right crossed over left, left becomes the new apex
+                push(&mut points, left);
+                apex = left;
+                right = apex;
+                right_index = left_index;
+                i = left_index + 1;
+                continue;
+            }
+        }
+
+        // tighten the left side
+        if area_xz(apex, left, new_left) <= 0.0 {
+            if apex == left || area_xz(apex, right, new_left) > 0.0 {
+                left = new_left;
+                left_index = i;
+            } else {
+                // left crossed over right, right becomes the new apex
+                push(&mut points, right);
+                apex = right;
+                left = apex;
+                left_index = right_index;
+                i = right_index + 1;
+                continue;
+            }
+        }
+
+        i += 1;
+    }
+
+    push(&mut points, portals[portals.len() - 1].0);
+    points
+}
+
+/// Creates a line strip geometry through the points of a path, to be used
+/// with a [`Line`](../material/struct.Line.html) material.
+pub fn path_geometry(path: &[mint::Point3<f32>]) -> Geometry {
+    Geometry::with_vertices(path.to_vec())
+}
+
+/// Moves along a path at constant speed.
+#[derive(Clone, Debug)]
+pub struct PathFollower {
+    path: Vec<Point3<f32>>,
+    speed: f32,
+    segment: usize,
+    position: Point3<f32>,
+}
+
+impl PathFollower {
+    /// Creates a follower at the start of `path`, moving with `speed` units per second.
+    pub fn new(
+        path: Vec<mint::Point3<f32>>,
+        speed: f32,
+    ) -> Self {
+        let path: Vec<Point3<f32>> = path.into_iter().map(Point3::from).collect();
+        let position = path.first().cloned().unwrap_or_else(Point3::origin);
+        PathFollower {
+            path,
+            speed,
+            segment: 0,
+            position,
+        }
+    }
+
+    /// Current position on the path.
+    pub fn position(&self) -> mint::Point3<f32> {
+        self.position.into()
+    }
+
+    /// Returns `true` once the end of the path has been reached.
+    pub fn is_finished(&self) -> bool {
+        self.segment + 1 >= self.path.len()
+    }
+
+    /// Advances the follower by `dt` seconds and returns the new position.
+    pub fn update(
+        &mut self,
+        dt: f32,
+    ) -> mint::Point3<f32> {
+        let mut remaining = self.speed * dt;
+        while remaining > 0.0 && !self.is_finished() {
+            let target = self.path[self.segment + 1];
+            let to_target: Vector3<f32> = target - self.position;
+            let distance = to_target.magnitude();
+            if distance <= remaining {
+                self.position = target;
+                self.segment += 1;
+                remaining -= distance;
+            } else {
+                self.position += to_target * (remaining / distance);
+                remaining = 0.0;
+            }
+        }
+        self.position.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three unit squares in an L shape around the vertex at `(1, 0, 1)`.
+    fn l_shape() -> NavMesh {
+        let vertices = vec![
+            [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [2.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0], [1.0, 0.0, 1.0], [2.0, 0.0, 1.0],
+            [1.0, 0.0, 2.0], [2.0, 0.0, 2.0],
+        ];
+        let triangles = vec![
+            [0, 1, 4], [0, 4, 3],
+            [1, 2, 5], [1, 5, 4],
+            [4, 5, 7], [4, 7, 6],
+        ];
+        NavMesh::new(vertices.into_iter().map(Into::into).collect(), triangles)
+    }
+
+    #[test]
+    fn path_bends_around_corner() {
+        let path = l_shape().find_path([0.2, 0.0, 0.5], [1.5, 0.0, 1.8]).unwrap();
+        let expected: Vec<mint::Point3<f32>> = vec![
+            [0.2, 0.0, 0.5].into(),
+            [1.0, 0.0, 1.0].into(),
+            [1.5, 0.0, 1.8].into(),
+        ];
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn path_is_straight_in_sight() {
+        let path = l_shape().find_path([0.2, 0.0, 0.5], [1.8, 0.0, 0.2]).unwrap();
+        let expected: Vec<mint::Point3<f32>> = vec![[0.2, 0.0, 0.5].into(), [1.8, 0.0, 0.2].into()];
+        assert_eq!(path, expected);
+    }
+
+    #[test]
+    fn path_within_triangle() {
+        let path = l_shape().find_path([0.6, 0.0, 0.2], [0.8, 0.0, 0.4]).unwrap();
+        assert_eq!(path.len(), 2);
+    }
+
+    #[test]
+    fn path_to_disconnected_triangle() {
+        let vertices = vec![
+            [0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 0.0, 1.0],
+            [5.0, 0.0, 5.0], [6.0, 0.0, 5.0], [5.0, 0.0, 6.0],
+        ];
+        let navmesh = NavMesh::new(vertices.into_iter().map(Into::into).collect(), vec![[0, 2, 1], [3, 5, 4]]);
+        assert!(navmesh.find_path([0.2, 0.0, 0.2], [5.2, 0.0, 5.2]).is_none());
+    }
+
+    #[test]
+    fn funnel_skips_repeated_corners() {
+        let p = |x, z| Point3::new(x, 0.0, z);
+        // the corridor of `path_bends_around_corner`, all portals sharing
+        // the corner on their left
+        let portals = [
+            (p(0.2, 0.5), p(0.2, 0.5)),
+            (p(1.0, 1.0), p(0.0, 0.0)),
+            (p(1.0, 1.0), p(1.0, 0.0)),
+            (p(1.0, 1.0), p(2.0, 1.0)),
+            (p(1.0, 1.0), p(2.0, 2.0)),
+            (p(1.5, 1.8), p(1.5, 1.8)),
+        ];
+        let points = funnel(&portals);
+        assert_eq!(points, vec![p(0.2, 0.5), p(1.0, 1.0), p(1.5, 1.8)]);
+    }
+}
@@ -4,6 +4,7 @@ use mint;
 
 use hub::SubNode;
 use material::Material;
+use raycast::Aabb;
 
 use std::marker::PhantomData;
 
@@ -20,6 +21,9 @@ pub(crate) struct NodeInternal {
     /// `true` if this node (and its children) are visible to cameras.
     pub(crate) visible: bool,
 
+    /// Opacity of this node, multiplied into the opacity of its children.
+    pub(crate) opacity: f32,
+
     /// A user-defined name for the node.
     ///
     /// Not used internally to implement functionality. This is used by users to identify nodes
@@ -32,6 +36,20 @@ pub(crate) struct NodeInternal {
     /// The transform relative to the scene root.
     pub(crate) world_transform: TransformInternal,
 
+    /// Linear velocity in units per second, in the space of the parent.
+    pub(crate) velocity: cgmath::Vector3<f32>,
+
+    /// Angular velocity around its axis in radians per second, in the space
+    /// of the parent.
+    pub(crate) angular_velocity: cgmath::Vector3<f32>,
+
+    /// Bounds enclosing the children of a group in its local space, used to
+    /// cull all of them at once.
+    pub(crate) bounds: Option<Aabb>,
+
+    /// `true` if the node is turned to face the camera when drawn.
+    pub(crate) billboard: bool,
+
     /// Pointer to the next sibling.
     pub(crate) next_sibling: Option<NodePointer>,
 
@@ -44,6 +62,7 @@ impl NodeInternal {
         Node {
             transform: self.transform.into(),
             visible: self.visible,
+            opacity: self.opacity,
             name: self.name.clone(),
             material: match self.sub_node {
                 SubNode::Visual(ref mat, _, _) => Some(mat.clone()),
@@ -58,9 +77,14 @@ impl From<SubNode> for NodeInternal {
     fn from(sub: SubNode) -> Self {
         NodeInternal {
             visible: true,
+            opacity: 1.0,
             name: None,
             transform: cgmath::Transform::one(),
             world_transform: cgmath::Transform::one(),
+            velocity: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            angular_velocity: cgmath::Vector3::new(0.0, 0.0, 0.0),
+            bounds: None,
+            billboard: false,
             next_sibling: None,
             sub_node: sub,
         }
@@ -114,6 +138,16 @@ impl From<TransformInternal> for Transform {
     }
 }
 
+impl From<Transform> for TransformInternal {
+    fn from(tf: Transform) -> Self {
+        cgmath::Decomposed {
+            disp: cgmath::Vector3::new(tf.position.x, tf.position.y, tf.position.z),
+            rot: tf.orientation.into(),
+            scale: tf.scale,
+        }
+    }
+}
+
 /// Local space, defined relative to the parent node.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Local {}
@@ -128,6 +162,9 @@ pub struct Node<Space> {
     /// Is `Node` visible by cameras or not?
     pub visible: bool,
 
+    /// Opacity of the node, in range `[0.0, 1.0]`.
+    pub opacity: f32,
+
     /// The name of the node, if any.
     pub name: Option<String>,
 
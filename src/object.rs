@@ -1,5 +1,6 @@
 //! Items in the scene heirarchy.
 
+use std::collections::HashSet;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::sync::mpsc;
@@ -13,8 +14,9 @@ use camera::Camera;
 use hub::{Hub, Message, Operation, SubLight, SubNode};
 use light;
 use mesh::Mesh;
-use node::NodePointer;
-use scene::SyncGuard;
+use node::{NodePointer, TransformInternal};
+use raycast::Aabb;
+use scene::{Scene, SyncGuard};
 use skeleton::{Bone, Skeleton};
 use sprite::Sprite;
 use text::Text;
@@ -76,6 +78,19 @@ pub trait Object: AsRef<Base> {
         self.as_ref().send(Operation::SetVisible(visible));
     }
 
+    /// Sets the opacity of the object, in range `[0.0, 1.0]`.
+    ///
+    /// Opacity is multiplied through the hierarchy, so fading a group fades
+    /// all of its children. Fully transparent objects are not rendered.
+    /// Partially transparent ones are blended after the opaque objects, from
+    /// back to front, without writing depth.
+    fn set_opacity(
+        &self,
+        opacity: f32,
+    ) {
+        self.as_ref().send(Operation::SetOpacity(opacity));
+    }
+
     /// Sets the name of the object.
     fn set_name<S: Into<String>>(
         &self,
@@ -98,6 +113,138 @@ pub trait Object: AsRef<Base> {
         self.as_ref().send(Operation::SetTransform(Some(pos.into()), Some(rot.into()), Some(scale)));
     }
 
+    /// Set position, orientation and scale in world space, by setting the
+    /// local transform that gives them under the current parent of the
+    /// object in the scene of `sync`.
+    ///
+    /// Objects that aren't in the scene are positioned as if they were
+    /// added to it directly. Parents with zero scale leave the object
+    /// unchanged.
+    ///
+    /// # Examples
+    ///
+    /// Attaching a sword to a hand without moving it.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let hand = window.factory.group();
+    /// # let sword = window.factory.group();
+    /// # window.scene.add(&hand);
+    /// # window.scene.add(&sword);
+    /// use three::Object;
+    ///
+    /// let world = window.scene.sync_guard().resolve_world(&sword).transform;
+    /// window.scene.remove(&sword);
+    /// hand.add(&sword);
+    ///
+    /// let sync = window.scene.sync_guard();
+    /// sword.set_world_transform(&sync, world.position, world.orientation, world.scale);
+    /// ```
+    fn set_world_transform<P, Q>(
+        &self,
+        sync: &SyncGuard,
+        pos: P,
+        rot: Q,
+        scale: f32,
+    ) where
+        Self: Sized,
+        P: Into<mint::Point3<f32>>,
+        Q: Into<mint::Quaternion<f32>>,
+    {
+        use cgmath::{Decomposed, Transform};
+        let parent = parent_world_transform(sync, self.as_ref());
+        let world = Decomposed {
+            disp: mint::Vector3::from(pos.into()).into(),
+            rot: rot.into().into(),
+            scale,
+        };
+        let local = match parent.inverse_transform() {
+            Some(inverse) => inverse.concat(&world),
+            None => return,
+        };
+        let disp: mint::Vector3<f32> = local.disp.into();
+        self.set_transform([disp.x, disp.y, disp.z], local.rot, local.scale);
+    }
+
+    /// Moves the object from its current parent, which may be the scene
+    /// itself, into the group `parent`.
+    ///
+    /// With `keep_world_transform`, the local transform is rewritten so that
+    /// the object stays where it is in the world, like an item picked up by a
+    /// hand. Otherwise the local transform is kept and the object jumps along
+    /// with its new parent. Attaching an object to itself or to one of its
+    /// descendants does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let hand = window.factory.group();
+    /// # let sword = window.factory.group();
+    /// # window.scene.add(&hand);
+    /// # window.scene.add(&sword);
+    /// use three::Object;
+    ///
+    /// sword.attach_to(&mut window.scene, &hand, true);
+    /// ```
+    fn attach_to(
+        &self,
+        scene: &mut Scene,
+        parent: &Group,
+        keep_world_transform: bool,
+    ) where
+        Self: Sized,
+    {
+        use cgmath::Transform;
+        let base = self.as_ref();
+        let (old_parent, in_scene, world, parent_world) = {
+            let sync = scene.sync_guard();
+            let mut subtree = HashSet::new();
+            sync.hub.collect_subtree(&base.node, &mut subtree);
+            if subtree.contains(&(&sync.hub[parent] as *const _)) {
+                error!("Unable to attach {:?} to itself or its descendant", base);
+                return;
+            }
+            let old_parent = find_parent(&sync, base);
+            let old_world = match old_parent {
+                Some((_, transform)) => transform,
+                None => TransformInternal::one(),
+            };
+            let mut in_scene = false;
+            let mut child = sync.scene.first_child.clone();
+            while let Some(ptr) = child {
+                in_scene |= ptr == base.node;
+                child = sync.hub.nodes[&ptr].next_sibling.clone();
+            }
+            let parent_world = parent_world_transform(&sync, parent.as_ref())
+                .concat(&sync.hub[parent].transform);
+            (
+                old_parent.map(|(ptr, _)| ptr),
+                in_scene,
+                old_world.concat(&sync.hub[base].transform),
+                parent_world,
+            )
+        };
+
+        match old_parent {
+            Some(node) => {
+                let group = Base { node, tx: base.tx.clone() };
+                group.send(Operation::RemoveChild(base.node.clone()));
+            }
+            None if in_scene => scene.remove(base),
+            None => {}
+        }
+        parent.add(self);
+
+        if keep_world_transform {
+            if let Some(inverse) = parent_world.inverse_transform() {
+                let local = inverse.concat(&world);
+                let disp: mint::Vector3<f32> = local.disp.into();
+                self.set_transform([disp.x, disp.y, disp.z], local.rot, local.scale);
+            }
+        }
+    }
+
     /// Set position.
     fn set_position<P>(
         &self,
@@ -128,6 +275,33 @@ pub trait Object: AsRef<Base> {
         self.as_ref().send(Operation::SetTransform(None, None, Some(scale)));
     }
 
+    /// Sets the velocity of the object in units per second, in the space of
+    /// its parent. The object is moved by it on every
+    /// [`Window::update`](../struct.Window.html#method.update).
+    fn set_velocity<V>(
+        &self,
+        velocity: V,
+    ) where
+        Self: Sized,
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.as_ref().send(Operation::SetVelocity(Some(velocity.into()), None));
+    }
+
+    /// Sets the angular velocity of the object, in the space of its parent.
+    /// The object rotates around the direction of the vector by its length
+    /// in radians per second on every
+    /// [`Window::update`](../struct.Window.html#method.update).
+    fn set_angular_velocity<V>(
+        &self,
+        angular_velocity: V,
+    ) where
+        Self: Sized,
+        V: Into<mint::Vector3<f32>>,
+    {
+        self.as_ref().send(Operation::SetVelocity(None, Some(angular_velocity.into())));
+    }
+
     /// Set weights.
     //Note: needed for animations
     fn set_weights(
@@ -163,6 +337,39 @@ pub trait Object: AsRef<Base> {
     }
 }
 
+/// The group holding `base` in the scene of `sync` with its world transform,
+/// or `None` if `base` is added to the scene directly or not at all.
+fn find_parent(
+    sync: &SyncGuard,
+    base: &Base,
+) -> Option<(NodePointer, TransformInternal)> {
+    for w in sync.hub.walk_all(&sync.scene.first_child) {
+        if let SubNode::Group { ref first_child, .. } = w.node.sub_node {
+            let mut child = first_child.clone();
+            while let Some(ptr) = child {
+                if ptr == base.node {
+                    return Some((w.node_ptr.clone(), w.world_transform));
+                }
+                child = sync.hub.nodes[&ptr].next_sibling.clone();
+            }
+        }
+    }
+    None
+}
+
+/// World transform of the group holding `base` in the scene of `sync`, or
+/// the identity if `base` is added to the scene directly or not at all.
+fn parent_world_transform(
+    sync: &SyncGuard,
+    base: &Base,
+) -> TransformInternal {
+    use cgmath::Transform;
+    match find_parent(sync, base) {
+        Some((_, transform)) => transform,
+        None => TransformInternal::one(),
+    }
+}
+
 impl PartialEq for Base {
     fn eq(
         &self,
@@ -329,6 +536,10 @@ pub trait DowncastObject: Object + Sized {
 
 /// Groups are used to combine several other objects or groups to work with them
 /// as with a single entity.
+///
+/// Children are kept in the order they were added, which is also the order
+/// they are traversed and rendered in. Removing a child keeps the order of
+/// the others.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Group {
     object: Base,
@@ -344,7 +555,7 @@ impl Object for Group {
     fn resolve_data(&self, sync_guard: &SyncGuard) -> Vec<Base> {
         let mut children = Vec::new();
         let mut child = match &sync_guard.hub[self].sub_node {
-            SubNode::Group { ref first_child } => first_child.clone(),
+            SubNode::Group { ref first_child, .. } => first_child.clone(),
             sub_node @ _ => panic!("`Group` had a bad sub node type: {:?}", sub_node),
         };
 
@@ -365,7 +576,7 @@ derive_DowncastObject!(Group => ObjectType::Group);
 
 impl Group {
     pub(crate) fn new(hub: &mut Hub) -> Self {
-        let sub = SubNode::Group { first_child: None };
+        let sub = SubNode::Group { first_child: None, last_child: None };
         Group {
             object: hub.spawn(sub),
         }
@@ -388,4 +599,73 @@ impl Group {
         let node = child.as_ref().node.clone();
         self.as_ref().send(Operation::RemoveChild(node));
     }
+
+    /// Sets bounds enclosing all of the children, in the local space of the
+    /// group, letting the renderer skip the whole group when they are
+    /// outside of the camera view or of a shadow map.
+    ///
+    /// Splitting large sets of instances, such as grass or asteroid fields,
+    /// into groups with bounds makes culling them scale with the number of
+    /// groups rather than with the number of instances.
+    ///
+    /// The bounds are not derived from the children: they must be set again
+    /// whenever children are added or move. Children sticking out of the
+    /// bounds may be culled while in view, and [`Scene::validate`] reports
+    /// the groups where this happens.
+    ///
+    /// # Examples
+    ///
+    /// Grass planted in chunks of 10x10 units.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let blade = window.factory.mesh(three::Geometry::plane(0.1, 0.5), three::material::Basic::default());
+    /// use three::Object;
+    ///
+    /// for cx in -10 .. 10 {
+    ///     for cz in -10 .. 10 {
+    ///         let chunk = window.factory.group();
+    ///         chunk.set_position([cx as f32 * 10.0, 0.0, cz as f32 * 10.0]);
+    ///         chunk.set_bounds([0.0, 0.0, 0.0], [10.0, 0.5, 10.0]);
+    ///         for i in 0 .. 100 {
+    ///             let instance = window.factory.mesh_instance(&blade);
+    ///             instance.set_position([(i % 10) as f32, 0.25, (i / 10) as f32]);
+    ///             chunk.add(&instance);
+    ///         }
+    ///         window.scene.add(&chunk);
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Scene::validate`]: ../struct.Scene.html#method.validate
+    pub fn set_bounds<P: Into<mint::Point3<f32>>>(
+        &self,
+        min: P,
+        max: P,
+    ) {
+        let (min, max): ([f32; 3], [f32; 3]) = (min.into().into(), max.into().into());
+        let bounds = Aabb {
+            min: min.into(),
+            max: max.into(),
+        };
+        self.as_ref().send(Operation::SetBounds(Some(bounds)));
+    }
+
+    /// Removes the bounds set by [`set_bounds`](#method.set_bounds), so that
+    /// the children are culled one by one again.
+    pub fn clear_bounds(&self) {
+        self.as_ref().send(Operation::SetBounds(None));
+    }
+
+    /// Returns the children of the group, in the order they were added.
+    ///
+    /// This is the same as [`SyncGuard::resolve_data`].
+    ///
+    /// [`SyncGuard::resolve_data`]: ../scene/struct.SyncGuard.html#method.resolve_data
+    pub fn children(
+        &self,
+        sync: &SyncGuard,
+    ) -> Vec<Base> {
+        self.resolve_data(sync)
+    }
 }
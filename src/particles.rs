@@ -0,0 +1,408 @@
+//! Particle effects such as smoke, sparks, rain or snow.
+//!
+//! An [`Emitter`] spawns particles at a steady rate or in bursts, moves them
+//! and draws all of them as camera-facing quads of a single
+//! [`InstancedMesh`], in one draw call. Their size and color follow curves
+//! over their life.
+//!
+//! Particles are simulated on the CPU by [`Emitter::update`], in the local
+//! space of the emitter, so they move along with it.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! use three::particles::{Curve, Gradient, Settings};
+//!
+//! let texture = window.factory.load_texture("smoke.png");
+//! let material = three::material::Basic {
+//!     map: Some(texture),
+//!     features: three::material::ShaderFeatures::ALPHA_TEST,
+//!     .. Default::default()
+//! };
+//! let mut smoke = window.factory.emitter(material, 500, Settings {
+//!     rate: 40.0,
+//!     lifetime: 2.0 .. 4.0,
+//!     velocity: [0.0, 0.8, 0.0].into(),
+//!     size: Curve::linear(0.2, 1.0),
+//!     color: Gradient::linear(0x808080, 0x202020),
+//!     .. Default::default()
+//! });
+//! window.scene.add(&smoke);
+//!
+//! while window.update() {
+//!     smoke.update(window.input.delta_time());
+//!     window.render(&camera);
+//! }
+//! ```
+//!
+//! [`Emitter`]: struct.Emitter.html
+//! [`Emitter::update`]: struct.Emitter.html#method.update
+//! [`InstancedMesh`]: ../struct.InstancedMesh.html
+
+use cgmath::{InnerSpace, Vector3};
+use mint;
+
+use std::ops;
+
+use color::{self, Color};
+use mesh::InstancedMesh;
+
+/// A value changing over the life of a particle, from `0.0` at its birth to
+/// `1.0` at its death, linearly interpolated between keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Curve {
+    keys: Vec<(f32, f32)>,
+}
+
+impl Curve {
+    /// Creates a curve from `(time, value)` keys.
+    ///
+    /// # Panics
+    /// Panics if there are no keys, or if they aren't sorted by time.
+    pub fn new(keys: Vec<(f32, f32)>) -> Self {
+        assert!(!keys.is_empty(), "A curve needs at least one key");
+        assert!(keys.windows(2).all(|pair| pair[0].0 <= pair[1].0), "Curve keys must be sorted by time");
+        Curve { keys }
+    }
+
+    /// Creates a curve keeping the same value.
+    pub fn constant(value: f32) -> Self {
+        Curve { keys: vec![(0.0, value)] }
+    }
+
+    /// Creates a curve going from `start` to `end`.
+    pub fn linear(
+        start: f32,
+        end: f32,
+    ) -> Self {
+        Curve { keys: vec![(0.0, start), (1.0, end)] }
+    }
+
+    /// The value at `time`.
+    pub fn sample(
+        &self,
+        time: f32,
+    ) -> f32 {
+        let (index, s) = locate(&self.keys, time, |key| key.0);
+        match self.keys.get(index + 1) {
+            Some(next) => self.keys[index].1 * (1.0 - s) + next.1 * s,
+            None => self.keys[index].1,
+        }
+    }
+}
+
+/// A color changing over the life of a particle, like [`Curve`], blended in
+/// linear space.
+///
+/// [`Curve`]: struct.Curve.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    keys: Vec<(f32, Color)>,
+}
+
+impl Gradient {
+    /// Creates a gradient from `(time, color)` keys.
+    ///
+    /// # Panics
+    /// Panics if there are no keys, or if they aren't sorted by time.
+    pub fn new(keys: Vec<(f32, Color)>) -> Self {
+        assert!(!keys.is_empty(), "A gradient needs at least one key");
+        assert!(keys.windows(2).all(|pair| pair[0].0 <= pair[1].0), "Gradient keys must be sorted by time");
+        Gradient { keys }
+    }
+
+    /// Creates a gradient keeping the same color.
+    pub fn constant(color: Color) -> Self {
+        Gradient { keys: vec![(0.0, color)] }
+    }
+
+    /// Creates a gradient going from `start` to `end`.
+    pub fn linear(
+        start: Color,
+        end: Color,
+    ) -> Self {
+        Gradient { keys: vec![(0.0, start), (1.0, end)] }
+    }
+
+    /// The color at `time`.
+    pub fn sample(
+        &self,
+        time: f32,
+    ) -> Color {
+        let (index, s) = locate(&self.keys, time, |key| key.0);
+        match self.keys.get(index + 1) {
+            Some(next) => {
+                let a = Vector3::from(color::to_linear_rgb(self.keys[index].1));
+                let b = Vector3::from(color::to_linear_rgb(next.1));
+                color::from_linear_rgb(a.lerp(b, s).into())
+            }
+            None => self.keys[index].1,
+        }
+    }
+}
+
+/// Finds the key at or before `time`, and how far `time` is towards the
+/// next key.
+fn locate<T, F: Fn(&T) -> f32>(
+    keys: &[T],
+    time: f32,
+    key_time: F,
+) -> (usize, f32) {
+    let index = keys.iter().rposition(|key| key_time(key) <= time).unwrap_or(0);
+    match keys.get(index + 1) {
+        Some(next) => {
+            let (start, end) = (key_time(&keys[index]), key_time(next));
+            let s = if end > start { (time - start) / (end - start) } else { 0.0 };
+            (index, s.max(0.0).min(1.0))
+        }
+        None => (index, 0.0),
+    }
+}
+
+/// How an [`Emitter`] spawns and moves its particles.
+///
+/// [`Emitter`]: struct.Emitter.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Particles spawned per second.
+    ///
+    /// Default: `20.0`.
+    pub rate: f32,
+
+    /// Range of the life durations in seconds, picked at random for each
+    /// particle.
+    ///
+    /// Default: `1.0 .. 2.0`.
+    pub lifetime: ops::Range<f32>,
+
+    /// Radius of the sphere around the emitter in which particles spawn.
+    ///
+    /// Default: `0.0`.
+    pub spawn_radius: f32,
+
+    /// Velocity of the particles when spawned, in units per second.
+    ///
+    /// Default: `[0.0, 1.0, 0.0]`.
+    pub velocity: mint::Vector3<f32>,
+
+    /// Length of a random velocity in any direction added to `velocity`.
+    ///
+    /// Default: `0.5`.
+    pub velocity_spread: f32,
+
+    /// Acceleration of the particles, such as gravity.
+    ///
+    /// Default: zero.
+    pub acceleration: mint::Vector3<f32>,
+
+    /// Factor of the velocity over the life of particles, for example to
+    /// slow them down.
+    ///
+    /// Default: `1.0`.
+    pub speed: Curve,
+
+    /// Width and height of the particles over their life.
+    ///
+    /// Default: `0.1`.
+    pub size: Curve,
+
+    /// Color of the particles over their life, replacing the color of the
+    /// material.
+    ///
+    /// Default: `WHITE`.
+    pub color: Gradient,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            rate: 20.0,
+            lifetime: 1.0 .. 2.0,
+            spawn_radius: 0.0,
+            velocity: [0.0, 1.0, 0.0].into(),
+            velocity_spread: 0.5,
+            acceleration: [0.0, 0.0, 0.0].into(),
+            speed: Curve::constant(1.0),
+            size: Curve::constant(0.1),
+            color: Gradient::constant(color::WHITE),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Particle {
+    position: Vector3<f32>,
+    velocity: Vector3<f32>,
+    age: f32,
+    lifetime: f32,
+}
+
+/// Spawns, moves and draws particles. Created with [`Factory::emitter`].
+///
+/// See the [module documentation](index.html) for an example.
+///
+/// [`Factory::emitter`]: ../struct.Factory.html#method.emitter
+#[derive(Clone, Debug)]
+pub struct Emitter {
+    mesh: InstancedMesh,
+    /// How particles are spawned and moved, used from the next update.
+    pub settings: Settings,
+    particles: Vec<Particle>,
+    emitting: bool,
+    pending: f32,
+    seed: u32,
+}
+three_object!(Emitter::mesh);
+
+impl Emitter {
+    pub(crate) fn new(
+        mesh: InstancedMesh,
+        settings: Settings,
+    ) -> Self {
+        Emitter {
+            mesh,
+            settings,
+            particles: Vec::new(),
+            emitting: true,
+            pending: 0.0,
+            seed: 0x9E37_79B9,
+        }
+    }
+
+    /// The mesh drawing the particles.
+    pub fn mesh(&self) -> &InstancedMesh {
+        &self.mesh
+    }
+
+    /// The number of living particles.
+    pub fn count(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// The maximum number of living particles. No particles are spawned
+    /// while there are that many.
+    pub fn capacity(&self) -> usize {
+        self.mesh.capacity()
+    }
+
+    /// Whether particles are spawned at the [`rate`] of the settings.
+    ///
+    /// [`rate`]: struct.Settings.html#structfield.rate
+    pub fn is_emitting(&self) -> bool {
+        self.emitting
+    }
+
+    /// Starts or stops spawning particles at the [`rate`] of the settings.
+    /// Living particles carry on until the end of their life.
+    ///
+    /// [`rate`]: struct.Settings.html#structfield.rate
+    pub fn set_emitting(
+        &mut self,
+        emitting: bool,
+    ) {
+        self.emitting = emitting;
+        self.pending = 0.0;
+    }
+
+    /// Spawns `count` particles at once, such as for an explosion.
+    pub fn burst(
+        &mut self,
+        count: usize,
+    ) {
+        for _ in 0 .. count {
+            self.spawn();
+        }
+    }
+
+    /// Removes every living particle.
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.mesh.set_instances(&[]);
+    }
+
+    /// Advances the particles by `dt` seconds, spawning and removing them,
+    /// and updates the mesh drawing them.
+    pub fn update(
+        &mut self,
+        dt: f32,
+    ) {
+        let acceleration = Vector3::from(self.settings.acceleration);
+        {
+            let speed = &self.settings.speed;
+            self.particles.retain(|particle| particle.age + dt < particle.lifetime);
+            for particle in &mut self.particles {
+                particle.age += dt;
+                particle.velocity += acceleration * dt;
+                let factor = speed.sample(particle.age / particle.lifetime);
+                particle.position += particle.velocity * factor * dt;
+            }
+        }
+
+        if self.emitting {
+            self.pending += self.settings.rate.max(0.0) * dt;
+            while self.pending >= 1.0 {
+                self.pending -= 1.0;
+                self.spawn();
+            }
+        }
+
+        let mut transforms = Vec::with_capacity(self.particles.len());
+        let mut colors = Vec::with_capacity(self.particles.len());
+        for particle in &self.particles {
+            let time = particle.age / particle.lifetime;
+            let size = self.settings.size.sample(time);
+            let p = particle.position;
+            transforms.push(mint::ColumnMatrix4::from([
+                [size, 0.0, 0.0, 0.0],
+                [0.0, size, 0.0, 0.0],
+                [0.0, 0.0, size, 0.0],
+                [p.x, p.y, p.z, 1.0],
+            ]));
+            colors.push(self.settings.color.sample(time));
+        }
+        self.mesh.set_instances(&transforms);
+        self.mesh.set_instance_colors(&colors);
+    }
+
+    /// Adds a particle, unless the emitter is full.
+    fn spawn(&mut self) {
+        if self.particles.len() >= self.capacity() {
+            return;
+        }
+        let lifetime = {
+            let range = self.settings.lifetime.clone();
+            range.start + (range.end - range.start) * self.random()
+        };
+        let position = self.random_in_sphere() * self.settings.spawn_radius;
+        let velocity = Vector3::from(self.settings.velocity) + self.random_in_sphere() * self.settings.velocity_spread;
+        self.particles.push(Particle {
+            position,
+            velocity,
+            age: 0.0,
+            lifetime: lifetime.max(0.0),
+        });
+    }
+
+    /// A pseudo-random number in `0.0 .. 1.0`.
+    fn random(&mut self) -> f32 {
+        // xorshift32
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32
+    }
+
+    /// A pseudo-random point in the unit sphere.
+    fn random_in_sphere(&mut self) -> Vector3<f32> {
+        loop {
+            let point = Vector3::new(
+                2.0 * self.random() - 1.0,
+                2.0 * self.random() - 1.0,
+                2.0 * self.random() - 1.0,
+            );
+            if point.magnitude2() <= 1.0 {
+                return point;
+            }
+        }
+    }
+}
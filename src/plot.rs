@@ -0,0 +1,338 @@
+//! Building blocks for data visualization: scatter plots, line graphs and
+//! labeled axes.
+//!
+//! Plots live in the scene like any other object, so they can be moved,
+//! scaled and looked at from any side with the usual cameras and controls.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! use three::plot::{self, AxesSettings};
+//!
+//! let font = window.factory.load_font_karla();
+//! let axes = plot::axes(&mut window.factory, &font, &AxesSettings {
+//!     min: [-5.0, 0.0, -5.0].into(),
+//!     max: [5.0, 3.0, 5.0].into(),
+//!     .. Default::default()
+//! });
+//! window.scene.add(&axes);
+//!
+//! let points = (0 .. 200)
+//!     .map(|i| {
+//!         let t = i as f32 * 0.1;
+//!         [4.0 * t.cos() * (-0.05 * t).exp(), 0.1 * t, 4.0 * t.sin() * (-0.05 * t).exp()].into()
+//!     })
+//!     .collect::<Vec<_>>();
+//! let scatter = plot::scatter(&mut window.factory, &points, 0.05, 0xFF8020);
+//! window.scene.add(&scatter);
+//!
+//! let wave = plot::function_graph(&mut window.factory, -5.0 .. 5.0, 200, |x| 1.5 + x.sin(), 0x20A0FF, 2.0);
+//! window.scene.add(&wave);
+//!
+//! while window.update() {
+//!     window.render(&camera);
+//! }
+//! ```
+
+use mint;
+
+use std::ops;
+
+use color::Color;
+use factory::Factory;
+use geometry::Geometry;
+use material;
+use mesh::{InstancedMesh, Mesh};
+use object::{Group, Object};
+use text::Font;
+
+/// Points of a scatter plot, drawn as spheres in a single draw call.
+/// Created with [`scatter`](fn.scatter.html).
+#[derive(Clone, Debug)]
+pub struct Scatter {
+    mesh: InstancedMesh,
+}
+three_object!(Scatter::mesh);
+
+impl Scatter {
+    /// The mesh drawing the points.
+    pub fn mesh(&self) -> &InstancedMesh {
+        &self.mesh
+    }
+
+    /// Replaces the points. Points past the number the plot was created
+    /// with are ignored.
+    pub fn set_points(
+        &self,
+        points: &[mint::Point3<f32>],
+    ) {
+        let transforms = points
+            .iter()
+            .map(|p| {
+                mint::ColumnMatrix4::from([
+                    [1.0, 0.0, 0.0, 0.0],
+                    [0.0, 1.0, 0.0, 0.0],
+                    [0.0, 0.0, 1.0, 0.0],
+                    [p.x, p.y, p.z, 1.0],
+                ])
+            })
+            .collect::<Vec<_>>();
+        self.mesh.set_instances(&transforms);
+    }
+
+    /// Gives each point its own color, such as to show a fourth dimension
+    /// of the data. Points past the end of `colors` keep the plot color.
+    pub fn set_colors(
+        &self,
+        colors: &[Color],
+    ) {
+        self.mesh.set_instance_colors(colors);
+    }
+}
+
+/// Creates a scatter plot showing each of `points` as a sphere of `radius`.
+pub fn scatter(
+    factory: &mut Factory,
+    points: &[mint::Point3<f32>],
+    radius: f32,
+    color: Color,
+) -> Scatter {
+    let geometry = Geometry::uv_sphere(radius, 12, 8);
+    let material = material::Lambert {
+        color,
+        .. Default::default()
+    };
+    let scatter = Scatter {
+        mesh: factory.instanced_mesh(geometry, material, points.len()),
+    };
+    scatter.set_points(points);
+    scatter
+}
+
+/// Creates a line going through `points` in order, `width` pixels wide.
+pub fn line_graph(
+    factory: &mut Factory,
+    points: &[mint::Point3<f32>],
+    color: Color,
+    width: f32,
+) -> Mesh {
+    let material = material::Line {
+        color,
+        width,
+        .. Default::default()
+    };
+    factory.mesh(Geometry::with_line_strip(points.to_vec()), material)
+}
+
+/// Creates a line graph of `y = f(x)` in the XY plane, sampling `f` at
+/// `samples` evenly spaced values of `x` over `range`, at least two.
+pub fn function_graph<F: Fn(f32) -> f32>(
+    factory: &mut Factory,
+    range: ops::Range<f32>,
+    samples: usize,
+    f: F,
+    color: Color,
+    width: f32,
+) -> Mesh {
+    let samples = samples.max(2);
+    let points = (0 .. samples)
+        .map(|i| {
+            let x = range.start + (range.end - range.start) * i as f32 / (samples - 1) as f32;
+            [x, f(x), 0.0].into()
+        })
+        .collect::<Vec<_>>();
+    line_graph(factory, &points, color, width)
+}
+
+/// Extent and look of [`Axes`](struct.Axes.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct AxesSettings {
+    /// Corner of the plotted box with the lowest coordinates, where the
+    /// axes start.
+    ///
+    /// Default: `[0.0, 0.0, 0.0]`.
+    pub min: mint::Point3<f32>,
+
+    /// Corner of the plotted box with the highest coordinates, where the
+    /// axes end.
+    ///
+    /// Default: `[10.0, 10.0, 10.0]`.
+    pub max: mint::Point3<f32>,
+
+    /// Distance between the ticks of the axes and the lines of the grid.
+    ///
+    /// Default: `1.0`.
+    pub step: f32,
+
+    /// Draws a grid on the bottom of the plotted box.
+    ///
+    /// Default: `true`.
+    pub grid: bool,
+
+    /// Color of the grid lines.
+    ///
+    /// Default: `0x404040`.
+    pub grid_color: Color,
+
+    /// Colors of the X, Y and Z axes.
+    ///
+    /// Default: red, green and blue.
+    pub axis_colors: [Color; 3],
+
+    /// Height of the tick labels, or zero for no labels.
+    ///
+    /// Default: `0.25`.
+    pub label_size: f32,
+}
+
+impl Default for AxesSettings {
+    fn default() -> Self {
+        AxesSettings {
+            min: [0.0, 0.0, 0.0].into(),
+            max: [10.0, 10.0, 10.0].into(),
+            step: 1.0,
+            grid: true,
+            grid_color: 0x404040,
+            axis_colors: [0xFF4040, 0x40E040, 0x4080FF],
+            label_size: 0.25,
+        }
+    }
+}
+
+/// Axes along the edges of a box, with labeled ticks and a grid. Created
+/// with [`axes`](fn.axes.html).
+#[derive(Clone, Debug)]
+pub struct Axes {
+    group: Group,
+    lines: [Mesh; 3],
+    grid: Vec<Mesh>,
+    labels: Vec<Mesh>,
+}
+three_object!(Axes::group);
+
+impl Axes {
+    /// The group holding the lines and labels.
+    pub fn group(&self) -> &Group {
+        &self.group
+    }
+
+    /// The lines of the X, Y and Z axes.
+    pub fn lines(&self) -> &[Mesh; 3] {
+        &self.lines
+    }
+
+    /// The lines of the grid, empty without one.
+    pub fn grid(&self) -> &[Mesh] {
+        &self.grid
+    }
+
+    /// The tick labels, each a billboard facing the camera.
+    pub fn labels(&self) -> &[Mesh] {
+        &self.labels
+    }
+}
+
+/// The multiples of `step` between `min` and `max`.
+fn ticks(
+    min: f32,
+    max: f32,
+    step: f32,
+) -> Vec<f32> {
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first ..= last).map(|i| i as f32 * step).collect()
+}
+
+/// Formats `value` with as many decimals as `step` needs.
+fn format_tick(
+    value: f32,
+    step: f32,
+) -> String {
+    let decimals = (-step.log10().floor()).max(0.0) as usize;
+    // avoid printing "-0"
+    let value = if value.abs() < 0.5 * step { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+/// Creates axes along the edges of the box from `settings.min` to
+/// `settings.max` starting at its lowest corner, with tick labels written
+/// in `font`.
+///
+/// # Panics
+/// Panics if `settings.step` isn't positive.
+pub fn axes(
+    factory: &mut Factory,
+    font: &Font,
+    settings: &AxesSettings,
+) -> Axes {
+    assert!(settings.step > 0.0, "The step of the axes must be positive");
+    let group = factory.group();
+    let min: [f32; 3] = settings.min.into();
+    let max: [f32; 3] = settings.max.into();
+
+    let axis_line = |factory: &mut Factory, axis: usize| {
+        let mut end = min;
+        end[axis] = max[axis];
+        line_graph(factory, &[min.into(), end.into()], settings.axis_colors[axis], 2.0)
+    };
+    let lines = [axis_line(factory, 0), axis_line(factory, 1), axis_line(factory, 2)];
+    for line in &lines {
+        group.add(line);
+    }
+
+    let mut grid = Vec::new();
+    if settings.grid {
+        // the lines along each axis are joined into one strip, zigzagging
+        // over the edges of the grid which are drawn anyway
+        let positions = |axis: usize| {
+            let mut values = vec![min[axis]];
+            values.extend(ticks(min[axis], max[axis], settings.step).into_iter().filter(|&v| v > min[axis] && v < max[axis]));
+            values.push(max[axis]);
+            values
+        };
+        for &(along, across) in &[(0, 2), (2, 0)] {
+            let mut points = Vec::new();
+            for (i, value) in positions(across).into_iter().enumerate() {
+                let (start, end) = if i % 2 == 0 { (min[along], max[along]) } else { (max[along], min[along]) };
+                for &position in &[start, end] {
+                    let mut point = [0.0, min[1], 0.0];
+                    point[along] = position;
+                    point[across] = value;
+                    points.push(point.into());
+                }
+            }
+            let mesh = line_graph(factory, &points, settings.grid_color, 1.0);
+            group.add(&mesh);
+            grid.push(mesh);
+        }
+    }
+
+    let mut labels = Vec::new();
+    if settings.label_size > 0.0 {
+        // labels sit just outside the box, next to their tick
+        let offset = settings.label_size;
+        for axis in 0 .. 3 {
+            for value in ticks(min[axis], max[axis], settings.step) {
+                let label = factory.text_billboard(font, &format_tick(value, settings.step));
+                let mut position = min;
+                position[axis] = value;
+                if axis == 0 {
+                    position[2] -= offset;
+                } else {
+                    position[0] -= offset;
+                }
+                label.set_position(position);
+                label.set_scale(settings.label_size);
+                group.add(&label);
+                labels.push(label);
+            }
+        }
+    }
+
+    Axes {
+        group,
+        lines,
+        grid,
+        labels,
+    }
+}
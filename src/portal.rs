@@ -0,0 +1,455 @@
+//! Portal-based visibility culling for indoor scenes.
+//!
+//! The world is split into convex [`Room`]s connected by [`Portal`] polygons,
+//! such as doorways and windows. Every frame the renderer finds the room
+//! containing the camera and walks through the portals that are in view,
+//! narrowing the visible screen area at each step. Objects placed in rooms
+//! that are not reached this way are not drawn.
+//!
+//! Objects that were never added to a room are always considered visible.
+//! Rooms don't keep their objects alive: an object dropped everywhere else is
+//! removed from the scene as usual.
+//!
+//! Only the main pass of the renderer is culled. Shadow maps still render the
+//! objects of hidden rooms, whose shadows may fall into the visible ones, and
+//! [`Renderer::pick`] and ray casts still find them.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let hall = window.factory.group();
+//! # let kitchen = window.factory.group();
+//! let mut portals = three::portal::PortalSystem::new();
+//! let a = portals.add_box_room([0.0, 0.0, 0.0], [10.0, 3.0, 10.0]);
+//! let b = portals.add_box_room([10.0, 0.0, 0.0], [20.0, 3.0, 10.0]);
+//! portals.add_portal(a, b, vec![
+//!     [10.0, 0.0, 4.0].into(),
+//!     [10.0, 0.0, 6.0].into(),
+//!     [10.0, 2.5, 6.0].into(),
+//!     [10.0, 2.5, 4.0].into(),
+//! ]);
+//! portals.add_object(a, &hall);
+//! portals.add_object(b, &kitchen);
+//! window.renderer.portals = Some(portals);
+//! ```
+//!
+//! [`Room`]: struct.Room.html
+//! [`Portal`]: struct.Portal.html
+//! [`Renderer::pick`]: ../struct.Renderer.html#method.pick
+
+use cgmath::{InnerSpace, Matrix4, Point3, Vector3, Vector4};
+use mint;
+
+use froggy::WeakPointer;
+use hub::Hub;
+use node::NodeInternal;
+use object::Object;
+
+use std::collections::HashSet;
+
+/// Handle to a room of a [`PortalSystem`](struct.PortalSystem.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct RoomId(usize);
+
+/// Handle to a portal of a [`PortalSystem`](struct.PortalSystem.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PortalId(usize);
+
+/// A convex volume of space, bounded by planes.
+#[derive(Clone, Debug)]
+pub struct Room {
+    /// Bounding planes as `(normal, distance)` pairs, with normals pointing inwards.
+    ///
+    /// A point `p` is inside the room if `dot(normal, p) + distance >= 0` for every plane.
+    pub planes: Vec<(mint::Vector3<f32>, f32)>,
+    objects: Vec<WeakPointer<NodeInternal>>,
+}
+
+impl Room {
+    /// Returns `true` if `point` lies inside of the room.
+    pub fn contains<P>(
+        &self,
+        point: P,
+    ) -> bool
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        let p = Point3::from(point.into());
+        self.planes.iter().all(|&(n, d)| {
+            Vector3::from(n).dot(Vector3::new(p.x, p.y, p.z)) + d >= 0.0
+        })
+    }
+}
+
+/// A polygon connecting two rooms, through which one room can see another.
+#[derive(Clone, Debug)]
+pub struct Portal {
+    /// The rooms connected by this portal.
+    pub rooms: [RoomId; 2],
+    /// Vertices of the portal polygon in world space.
+    pub vertices: Vec<mint::Point3<f32>>,
+    /// Closed portals block visibility, like a shut door.
+    pub open: bool,
+}
+
+/// A set of rooms and portals used to cull objects hidden behind walls.
+///
+/// Rooms hold weak handles to their objects, and only the main pass is culled,
+/// not shadow maps or picking. See the [module documentation](index.html) for
+/// an example.
+#[derive(Clone, Debug, Default)]
+pub struct PortalSystem {
+    rooms: Vec<Room>,
+    portals: Vec<Portal>,
+}
+
+impl PortalSystem {
+    /// Creates an empty portal system.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds a convex room bounded by `planes`, see [`Room::planes`](struct.Room.html#structfield.planes).
+    pub fn add_room(
+        &mut self,
+        planes: Vec<(mint::Vector3<f32>, f32)>,
+    ) -> RoomId {
+        self.rooms.push(Room {
+            planes,
+            objects: Vec::new(),
+        });
+        RoomId(self.rooms.len() - 1)
+    }
+
+    /// Adds an axis-aligned box room spanning from `min` to `max`.
+    pub fn add_box_room<P>(
+        &mut self,
+        min: P,
+        max: P,
+    ) -> RoomId
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        let (min, max) = (min.into(), max.into());
+        self.add_room(vec![
+            ([1.0, 0.0, 0.0].into(), -min.x),
+            ([0.0, 1.0, 0.0].into(), -min.y),
+            ([0.0, 0.0, 1.0].into(), -min.z),
+            ([-1.0, 0.0, 0.0].into(), max.x),
+            ([0.0, -1.0, 0.0].into(), max.y),
+            ([0.0, 0.0, -1.0].into(), max.z),
+        ])
+    }
+
+    /// Connects rooms `a` and `b` with a portal polygon.
+    pub fn add_portal(
+        &mut self,
+        a: RoomId,
+        b: RoomId,
+        vertices: Vec<mint::Point3<f32>>,
+    ) -> PortalId {
+        self.portals.push(Portal {
+            rooms: [a, b],
+            vertices,
+            open: true,
+        });
+        PortalId(self.portals.len() - 1)
+    }
+
+    /// Opens or closes a portal.
+    pub fn set_portal_open(
+        &mut self,
+        portal: PortalId,
+        open: bool,
+    ) {
+        self.portals[portal.0].open = open;
+    }
+
+    /// Places an object in a room. The object and all of its children are only
+    /// drawn when the room is visible. An object may be placed in several rooms.
+    ///
+    /// The room doesn't keep the object alive.
+    pub fn add_object<T: Object>(
+        &mut self,
+        room: RoomId,
+        object: &T,
+    ) {
+        self.rooms[room.0].objects.push(object.as_ref().node.downgrade());
+    }
+
+    /// Removes an object from a room.
+    pub fn remove_object<T: Object>(
+        &mut self,
+        room: RoomId,
+        object: &T,
+    ) {
+        let weak = object.as_ref().node.downgrade();
+        self.rooms[room.0].objects.retain(|o| *o != weak);
+    }
+
+    /// Returns a room by its handle.
+    pub fn room(
+        &self,
+        room: RoomId,
+    ) -> &Room {
+        &self.rooms[room.0]
+    }
+
+    /// Returns a portal by its handle.
+    pub fn portal(
+        &self,
+        portal: PortalId,
+    ) -> &Portal {
+        &self.portals[portal.0]
+    }
+
+    /// Finds the first room containing `point`.
+    pub fn room_at<P>(
+        &self,
+        point: P,
+    ) -> Option<RoomId>
+    where
+        P: Into<mint::Point3<f32>>,
+    {
+        let point = point.into();
+        self.rooms
+            .iter()
+            .position(|room| room.contains(point))
+            .map(RoomId)
+    }
+
+    /// Computes the rooms visible from a camera at `position` with the given
+    /// view-projection matrix.
+    ///
+    /// If the camera is not inside any room, all the rooms are considered visible.
+    pub fn visible_rooms<P, M>(
+        &self,
+        position: P,
+        view_projection: M,
+    ) -> Vec<RoomId>
+    where
+        P: Into<mint::Point3<f32>>,
+        M: Into<mint::ColumnMatrix4<f32>>,
+    {
+        let start = match self.room_at(position) {
+            Some(room) => room,
+            None => return (0 .. self.rooms.len()).map(RoomId).collect(),
+        };
+        let mx_vp = Matrix4::from(view_projection.into());
+        let mut reached = vec![None; self.rooms.len()];
+        reached[start.0] = Some([-1.0, -1.0, 1.0, 1.0]);
+        self.traverse(&mx_vp, start, &mut reached);
+
+        reached
+            .iter()
+            .enumerate()
+            .filter(|&(_, rect)| rect.is_some())
+            .map(|(i, _)| RoomId(i))
+            .collect()
+    }
+
+    /// Walks the portals out of `current`, growing the screen rectangle
+    /// through which each room is seen.
+    ///
+    /// A room is only walked again when its rectangle grows, which bounds
+    /// the work in rooms forming cycles: rectangles only take coordinates of
+    /// the portals and of the screen.
+    fn traverse(
+        &self,
+        mx_vp: &Matrix4<f32>,
+        current: RoomId,
+        reached: &mut [Option<[f32; 4]>],
+    ) {
+        let rect = reached[current.0].unwrap();
+        for portal in &self.portals {
+            if !portal.open {
+                continue;
+            }
+            let next = if portal.rooms[0] == current {
+                portal.rooms[1]
+            } else if portal.rooms[1] == current {
+                portal.rooms[0]
+            } else {
+                continue;
+            };
+            let portal_rect = match Self::screen_rect(mx_vp, &portal.vertices) {
+                Some(r) => r,
+                None => continue,
+            };
+            let clipped = [
+                rect[0].max(portal_rect[0]),
+                rect[1].max(portal_rect[1]),
+                rect[2].min(portal_rect[2]),
+                rect[3].min(portal_rect[3]),
+            ];
+            if clipped[0] >= clipped[2] || clipped[1] >= clipped[3] {
+                continue;
+            }
+            let grown = match reached[next.0] {
+                Some(old) => {
+                    let union = [
+                        old[0].min(clipped[0]),
+                        old[1].min(clipped[1]),
+                        old[2].max(clipped[2]),
+                        old[3].max(clipped[3]),
+                    ];
+                    if union == old {
+                        continue;
+                    }
+                    union
+                }
+                None => clipped,
+            };
+            reached[next.0] = Some(grown);
+            self.traverse(mx_vp, next, reached);
+        }
+    }
+
+    /// Projects a portal polygon into an NDC rectangle.
+    ///
+    /// Returns `None` if the polygon is completely behind the camera. Polygons
+    /// crossing the camera plane cover the whole screen.
+    fn screen_rect(
+        mx_vp: &Matrix4<f32>,
+        vertices: &[mint::Point3<f32>],
+    ) -> Option<[f32; 4]> {
+        const EPSILON: f32 = 1.0e-5;
+        let mut rect = [1.0f32, 1.0, -1.0, -1.0];
+        let mut behind = 0;
+        for v in vertices {
+            let clip = mx_vp * Vector4::new(v.x, v.y, v.z, 1.0);
+            if clip.w <= EPSILON {
+                behind += 1;
+                continue;
+            }
+            let (x, y) = (clip.x / clip.w, clip.y / clip.w);
+            rect = [rect[0].min(x), rect[1].min(y), rect[2].max(x), rect[3].max(y)];
+        }
+        if behind == vertices.len() {
+            None
+        } else if behind > 0 {
+            Some([-1.0, -1.0, 1.0, 1.0])
+        } else {
+            Some(rect)
+        }
+    }
+
+    /// Collects the nodes that must not be drawn this frame.
+    pub(crate) fn culled_nodes(
+        &self,
+        hub: &Hub,
+        position: mint::Point3<f32>,
+        view_projection: mint::ColumnMatrix4<f32>,
+    ) -> HashSet<*const NodeInternal> {
+        let visible = self.visible_rooms(position, view_projection);
+        let mut shown = HashSet::new();
+        let mut hidden = HashSet::new();
+        for (i, room) in self.rooms.iter().enumerate() {
+            let set = if visible.contains(&RoomId(i)) {
+                &mut shown
+            } else {
+                &mut hidden
+            };
+            // dropped objects are gone from the scene already
+            for node in room.objects.iter().filter_map(|object| object.upgrade().ok()) {
+                hub.collect_subtree(&node, set);
+            }
+        }
+        hidden.difference(&shown).cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cgmath::{Deg, PerspectiveFov};
+
+    const SIZE: usize = 6;
+
+    /// A grid of box rooms with a doorway in the middle of every inner wall,
+    /// so the rooms form many cycles.
+    fn grid() -> PortalSystem {
+        let mut portals = PortalSystem::new();
+        for i in 0 .. SIZE * SIZE {
+            let (x, z) = ((i % SIZE) as f32 * 10.0, (i / SIZE) as f32 * 10.0);
+            portals.add_box_room([x, 0.0, z], [x + 10.0, 3.0, z + 10.0]);
+        }
+        for i in 0 .. SIZE * SIZE {
+            let (x, z) = ((i % SIZE) as f32 * 10.0, (i / SIZE) as f32 * 10.0);
+            if i % SIZE + 1 < SIZE {
+                portals.add_portal(RoomId(i), RoomId(i + 1), vec![
+                    [x + 10.0, 0.0, z + 4.0].into(),
+                    [x + 10.0, 0.0, z + 6.0].into(),
+                    [x + 10.0, 2.5, z + 6.0].into(),
+                    [x + 10.0, 2.5, z + 4.0].into(),
+                ]);
+            }
+            if i / SIZE + 1 < SIZE {
+                portals.add_portal(RoomId(i), RoomId(i + SIZE), vec![
+                    [x + 4.0, 0.0, z + 10.0].into(),
+                    [x + 6.0, 0.0, z + 10.0].into(),
+                    [x + 6.0, 2.5, z + 10.0].into(),
+                    [x + 4.0, 2.5, z + 10.0].into(),
+                ]);
+            }
+        }
+        portals
+    }
+
+    fn view_projection(
+        eye: Point3<f32>,
+        target: Point3<f32>,
+    ) -> Matrix4<f32> {
+        let projection = PerspectiveFov {
+            fovy: Deg(90.0).into(),
+            aspect: 1.0,
+            near: 0.1,
+            far: 100.0,
+        };
+        Matrix4::from(projection) * Matrix4::look_at(eye, target, Vector3::unit_y())
+    }
+
+    #[test]
+    fn cyclic_rooms_see_down_the_corridor() {
+        let portals = grid();
+        let eye = Point3::new(7.0, 1.5, 5.0);
+        let mx_vp = view_projection(eye, Point3::new(50.0, 1.5, 5.0));
+        let visible = portals.visible_rooms(eye, mx_vp);
+        assert_eq!(visible, (0 .. SIZE).map(RoomId).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn parallel_portals_are_walked_once_per_growth() {
+        // every pair of rooms is joined by a doorway and a window, which
+        // makes 2^31 distinct paths down the corridor
+        const LENGTH: usize = 32;
+        let mut portals = PortalSystem::new();
+        for i in 0 .. LENGTH {
+            let x = i as f32 * 10.0;
+            portals.add_box_room([x, 0.0, 0.0], [x + 10.0, 3.0, 10.0]);
+        }
+        for i in 0 .. LENGTH - 1 {
+            let x = (i + 1) as f32 * 10.0;
+            for &(y0, y1, z0, z1) in &[(0.0, 2.5, 4.0, 6.0), (1.0, 2.0, 4.5, 5.5)] {
+                portals.add_portal(RoomId(i), RoomId(i + 1), vec![
+                    [x, y0, z0].into(),
+                    [x, y0, z1].into(),
+                    [x, y1, z1].into(),
+                    [x, y1, z0].into(),
+                ]);
+            }
+        }
+        let eye = Point3::new(2.0, 1.5, 5.0);
+        let mx_vp = view_projection(eye, Point3::new(100.0, 1.5, 5.0));
+        let visible = portals.visible_rooms(eye, mx_vp);
+        assert_eq!(visible, (0 .. LENGTH).map(RoomId).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn closed_portals_hide_rooms() {
+        let mut portals = grid();
+        portals.set_portal_open(PortalId(0), false);
+        let eye = Point3::new(7.0, 1.5, 5.0);
+        let mx_vp = view_projection(eye, Point3::new(50.0, 1.5, 5.0));
+        assert_eq!(portals.visible_rooms(eye, mx_vp), vec![RoomId(0)]);
+    }
+}
@@ -0,0 +1,760 @@
+//! Ray casting against meshes in the scene.
+//!
+//! Rays are tested against a copy of the triangles of static meshes kept on
+//! the CPU, which is only made for the meshes created after enabling
+//! [`Factory::set_raycast_triangles`]. Rays pass through the other meshes.
+//! Meshes with many triangles also build a bounding-volume hierarchy (BVH)
+//! the first time a ray reaches their bounds, so a ray only tests the
+//! triangles it can actually hit, and meshes that are never picked don't pay
+//! for it.
+//!
+//! For scenes with many objects, a [`SceneBvh`] over the world space bounds
+//! of all meshes can be kept between frames and refitted as objects move.
+//!
+//! Skinned and morphed meshes are tested in their bind pose. Dynamic meshes
+//! update their vertices on the GPU only, so rays pass through them.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! window.factory.set_raycast_triangles(true);
+//! // create the meshes to pick...
+//! let raycaster = three::raycast::Raycaster::new([0.0, 0.0, 5.0], [0.0, 0.0, -1.0]);
+//! let mut bvh = three::raycast::SceneBvh::new();
+//! while window.update() {
+//!     let sync = window.scene.sync_guard();
+//!     bvh.update(&sync);
+//!     if let Some(hit) = raycaster.intersect_bvh(&bvh, &sync).first() {
+//!         println!("hit {:?} at {}", hit.object, hit.distance);
+//!     }
+//! }
+//! ```
+//!
+//! [`SceneBvh`]: struct.SceneBvh.html
+//! [`Factory::set_raycast_triangles`]: ../struct.Factory.html#method.set_raycast_triangles
+
+use cgmath::{ElementWise, EuclideanSpace, InnerSpace, Matrix4, Point3, SquareMatrix, Transform as Transform_, Vector3, Vector4};
+use mint;
+
+use camera::Camera;
+use hub::{Hub, SubNode};
+use material::Material;
+use node::{NodePointer, TransformInternal};
+use object::{Base, Object};
+use scene::SyncGuard;
+
+use std::{f32, ptr};
+use std::sync::{Arc, Mutex};
+
+/// Meshes with at least this many triangles get a BVH.
+const BVH_MIN_TRIANGLES: usize = 32;
+/// Maximum number of items in a BVH leaf.
+const BVH_LEAF_SIZE: usize = 4;
+
+/// Axis-aligned bounding box.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct Aabb {
+    pub(crate) min: Vector3<f32>,
+    pub(crate) max: Vector3<f32>,
+}
+
+impl Aabb {
+    pub(crate) fn empty() -> Self {
+        Aabb {
+            min: Vector3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Vector3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.min.x > self.max.x
+    }
+
+    pub(crate) fn extend(
+        &mut self,
+        p: Vector3<f32>,
+    ) {
+        self.min = Vector3::new(self.min.x.min(p.x), self.min.y.min(p.y), self.min.z.min(p.z));
+        self.max = Vector3::new(self.max.x.max(p.x), self.max.y.max(p.y), self.max.z.max(p.z));
+    }
+
+    pub(crate) fn union(
+        &self,
+        other: &Aabb,
+    ) -> Aabb {
+        if other.is_empty() {
+            return *self;
+        }
+        let mut result = *self;
+        result.extend(other.min);
+        result.extend(other.max);
+        result
+    }
+
+    pub(crate) fn center(&self) -> Vector3<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub(crate) fn corners(&self) -> [Vector3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Vector3::new(a.x, a.y, a.z),
+            Vector3::new(b.x, a.y, a.z),
+            Vector3::new(a.x, b.y, a.z),
+            Vector3::new(b.x, b.y, a.z),
+            Vector3::new(a.x, a.y, b.z),
+            Vector3::new(b.x, a.y, b.z),
+            Vector3::new(a.x, b.y, b.z),
+            Vector3::new(b.x, b.y, b.z),
+        ]
+    }
+
+    /// Returns `true` if `other` lies inside of this box, allowing for
+    /// rounding errors.
+    pub(crate) fn contains(
+        &self,
+        other: &Aabb,
+    ) -> bool {
+        if other.is_empty() {
+            return true;
+        }
+        let size = self.max - self.min;
+        let epsilon = 1.0e-4 * size.x.max(size.y).max(size.z).max(1.0);
+        other.min.x >= self.min.x - epsilon && other.min.y >= self.min.y - epsilon &&
+            other.min.z >= self.min.z - epsilon && other.max.x <= self.max.x + epsilon &&
+            other.max.y <= self.max.y + epsilon && other.max.z <= self.max.z + epsilon
+    }
+
+    /// Bounds of this box after applying `transform`.
+    pub(crate) fn transformed(
+        &self,
+        transform: &TransformInternal,
+    ) -> Aabb {
+        let mut result = Aabb::empty();
+        if !self.is_empty() {
+            for &c in self.corners().iter() {
+                result.extend(transform.transform_vector(c) + transform.disp);
+            }
+        }
+        result
+    }
+
+    /// Slab test, returns the entry distance along the ray.
+    fn intersect(
+        &self,
+        origin: Vector3<f32>,
+        inv_dir: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<f32> {
+        let t1 = (self.min - origin).mul_element_wise(inv_dir);
+        let t2 = (self.max - origin).mul_element_wise(inv_dir);
+        let t_min = t1.x.min(t2.x).max(t1.y.min(t2.y)).max(t1.z.min(t2.z));
+        let t_max = t1.x.max(t2.x).min(t1.y.max(t2.y)).min(t1.z.max(t2.z));
+        if t_max >= t_min.max(0.0) && t_min <= max_distance {
+            Some(t_min.max(0.0))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum BvhNodeKind {
+    Leaf { start: usize, count: usize },
+    Inner { left: usize, right: usize },
+}
+
+#[derive(Clone, Debug)]
+struct BvhNode {
+    bounds: Aabb,
+    kind: BvhNodeKind,
+}
+
+/// Binary tree of bounding boxes over a set of items.
+///
+/// Children are always stored after their parents, which makes refitting a
+/// single reverse pass over the nodes.
+#[derive(Clone, Debug)]
+pub(crate) struct Bvh {
+    nodes: Vec<BvhNode>,
+    items: Vec<usize>,
+}
+
+impl Bvh {
+    pub(crate) fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::new(),
+            items: (0 .. bounds.len()).collect(),
+        };
+        if !bounds.is_empty() {
+            bvh.build_node(bounds, 0, bounds.len());
+        }
+        bvh
+    }
+
+    fn build_node(
+        &mut self,
+        bounds: &[Aabb],
+        start: usize,
+        count: usize,
+    ) -> usize {
+        let index = self.nodes.len();
+        let mut node_bounds = Aabb::empty();
+        let mut centroids = Aabb::empty();
+        for &item in &self.items[start .. start + count] {
+            node_bounds = node_bounds.union(&bounds[item]);
+            centroids.extend(bounds[item].center());
+        }
+        self.nodes.push(BvhNode {
+            bounds: node_bounds,
+            kind: BvhNodeKind::Leaf { start, count },
+        });
+        if count <= BVH_LEAF_SIZE {
+            return index;
+        }
+
+        // split at the median along the longest axis of the centroids
+        let extent = centroids.max - centroids.min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+        self.items[start .. start + count].sort_by(|&a, &b| {
+            let ca = bounds[a].center()[axis];
+            let cb = bounds[b].center()[axis];
+            ca.partial_cmp(&cb).unwrap_or(::std::cmp::Ordering::Equal)
+        });
+        let half = count / 2;
+        let left = self.build_node(bounds, start, half);
+        let right = self.build_node(bounds, start + half, count - half);
+        self.nodes[index].kind = BvhNodeKind::Inner { left, right };
+        index
+    }
+
+    /// Updates node bounds for moved items, keeping the tree topology.
+    pub(crate) fn refit(
+        &mut self,
+        bounds: &[Aabb],
+    ) {
+        for i in (0 .. self.nodes.len()).rev() {
+            let new_bounds = match self.nodes[i].kind {
+                BvhNodeKind::Leaf { start, count } => self.items[start .. start + count]
+                    .iter()
+                    .fold(Aabb::empty(), |acc, &item| acc.union(&bounds[item])),
+                BvhNodeKind::Inner { left, right } => self.nodes[left].bounds.union(&self.nodes[right].bounds),
+            };
+            self.nodes[i].bounds = new_bounds;
+        }
+    }
+
+    /// Calls `visit` for every item whose leaf is hit by the ray.
+    fn traverse<F>(
+        &self,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+        mut visit: F,
+    ) where
+        F: FnMut(usize),
+    {
+        if self.nodes.is_empty() {
+            return;
+        }
+        let inv_dir = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if node.bounds.intersect(origin, inv_dir, max_distance).is_none() {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, count } => {
+                    for &item in &self.items[start .. start + count] {
+                        visit(item);
+                    }
+                }
+                BvhNodeKind::Inner { left, right } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+    }
+}
+
+/// Bounds of a mesh, used for culling, and a CPU copy of its triangles,
+/// used for ray casting.
+#[derive(Debug)]
+pub(crate) struct Triangles {
+    /// Empty unless the triangles are kept for ray casting.
+    pub(crate) positions: Vec<Vector3<f32>>,
+    /// Empty unless the triangles are kept for ray casting.
+    pub(crate) faces: Vec<[u32; 3]>,
+    pub(crate) bounds: Aabb,
+    /// Radius of the bounding sphere around the center of `bounds`.
+    pub(crate) radius: f32,
+    /// Built by the first ray reaching `bounds`, if there are enough faces.
+    bvh: Mutex<Option<Bvh>>,
+}
+
+impl Triangles {
+    /// Computes the bounds of the mesh, keeping `positions` and `faces` only
+    /// when `keep` is set.
+    pub(crate) fn new(
+        mut positions: Vec<Vector3<f32>>,
+        mut faces: Vec<[u32; 3]>,
+        keep: bool,
+    ) -> Arc<Self> {
+        if faces.is_empty() {
+            faces = (0 .. positions.len() as u32 / 3)
+                .map(|i| [3 * i, 3 * i + 1, 3 * i + 2])
+                .collect();
+        }
+        let mut bounds = Aabb::empty();
+        for &p in &positions {
+            bounds.extend(p);
+        }
+        let center = bounds.center();
+        let radius = positions
+            .iter()
+            .map(|&p| (p - center).magnitude2())
+            .fold(0.0, f32::max)
+            .sqrt();
+        if !keep {
+            positions = Vec::new();
+            faces = Vec::new();
+        }
+        Arc::new(Triangles {
+            positions,
+            faces,
+            bounds,
+            radius,
+            bvh: Mutex::new(None),
+        })
+    }
+
+    fn build_bvh(&self) -> Bvh {
+        let face_bounds = self.faces
+            .iter()
+            .map(|f| {
+                let mut b = Aabb::empty();
+                for &i in f {
+                    b.extend(self.positions[i as usize]);
+                }
+                b
+            })
+            .collect::<Vec<_>>();
+        Bvh::build(&face_bounds)
+    }
+
+    fn intersect_face(
+        &self,
+        face: usize,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+    ) -> Option<(f32, [f32; 3])> {
+        const EPSILON: f32 = 1.0e-7;
+        let [i0, i1, i2] = self.faces[face];
+        let v0 = self.positions[i0 as usize];
+        let e1 = self.positions[i1 as usize] - v0;
+        let e2 = self.positions[i2 as usize] - v0;
+        let p = direction.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() < EPSILON {
+            return None;
+        }
+        let inv_det = 1.0 / det;
+        let s = origin - v0;
+        let u = s.dot(p) * inv_det;
+        if !(0.0 ..= 1.0).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = direction.dot(q) * inv_det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = e2.dot(q) * inv_det;
+        if t < 0.0 {
+            return None;
+        }
+        Some((t, [1.0 - u - v, u, v]))
+    }
+
+    /// Finds the closest face hit by a ray given in local space.
+    fn intersect(
+        &self,
+        origin: Vector3<f32>,
+        direction: Vector3<f32>,
+        max_distance: f32,
+    ) -> Option<(f32, usize, [f32; 3])> {
+        let mut best: Option<(f32, usize, [f32; 3])> = None;
+        {
+            let test = |face: usize| {
+                if let Some((t, bary)) = self.intersect_face(face, origin, direction) {
+                    let limit = best.map_or(max_distance, |b| b.0);
+                    if t <= limit {
+                        best = Some((t, face, bary));
+                    }
+                }
+            };
+            if self.faces.len() >= BVH_MIN_TRIANGLES {
+                let mut bvh = self.bvh.lock().unwrap();
+                bvh.get_or_insert_with(|| self.build_bvh())
+                    .traverse(origin, direction, max_distance, test);
+            } else {
+                (0 .. self.faces.len()).for_each(test);
+            }
+        }
+        best
+    }
+}
+
+/// A ray in world space.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    /// Starting point of the ray.
+    pub origin: mint::Point3<f32>,
+    /// Normalized direction of the ray.
+    pub direction: mint::Vector3<f32>,
+}
+
+/// An intersection of a ray with a mesh.
+#[derive(Clone, Debug)]
+pub struct Intersection {
+    /// The mesh that was hit.
+    pub object: Base,
+    /// Distance from the ray origin to the hit point.
+    pub distance: f32,
+    /// Hit point in world space.
+    pub point: mint::Point3<f32>,
+    /// Index of the triangle that was hit.
+    pub face_index: usize,
+    /// Barycentric coordinates of the hit point within the triangle.
+    pub barycentric: [f32; 3],
+    /// Index of the instance that was hit, in the order of the transforms
+    /// given to [`InstancedMesh::set_instances`], or `None` for other meshes.
+    ///
+    /// [`InstancedMesh::set_instances`]: ../struct.InstancedMesh.html#method.set_instances
+    pub instance: Option<usize>,
+}
+
+/// Casts rays into the scene to find the meshes they hit.
+#[derive(Clone, Debug)]
+pub struct Raycaster {
+    /// The ray to cast.
+    pub ray: Ray,
+    /// Hits closer than this distance are ignored.
+    pub near: f32,
+    /// Hits further than this distance are ignored.
+    pub far: f32,
+}
+
+impl Raycaster {
+    /// Creates a raycaster shooting from `origin` towards `direction`.
+    pub fn new<P, V>(
+        origin: P,
+        direction: V,
+    ) -> Self
+    where
+        P: Into<mint::Point3<f32>>,
+        V: Into<mint::Vector3<f32>>,
+    {
+        let direction = Vector3::from(direction.into()).normalize();
+        Raycaster {
+            ray: Ray {
+                origin: origin.into(),
+                direction: direction.into(),
+            },
+            near: 0.0,
+            far: f32::INFINITY,
+        }
+    }
+
+    /// Creates a raycaster shooting from `camera` through the point `ndc`
+    /// of the screen, in normalized device coordinates as returned by
+    /// [`Input::mouse_pos_ndc`]. The ray starts on the near plane of the
+    /// camera.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// use three::raycast::Raycaster;
+    ///
+    /// while window.update() {
+    ///     if window.input.hit(three::MOUSE_LEFT) {
+    ///         let sync = window.scene.sync_guard();
+    ///         let aspect_ratio = window.renderer.aspect_ratio();
+    ///         let raycaster = Raycaster::from_camera(&camera, &sync, window.input.mouse_pos_ndc(), aspect_ratio);
+    ///         if let Some(hit) = raycaster.intersect_scene(&sync).first() {
+    ///             println!("clicked {:?}, triangle {}", hit.object, hit.face_index);
+    ///         }
+    ///     }
+    ///     window.render(&camera);
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    ///
+    /// [`Input::mouse_pos_ndc`]: ../struct.Input.html#method.mouse_pos_ndc
+    pub fn from_camera<P: Into<mint::Point2<f32>>>(
+        camera: &Camera,
+        sync: &SyncGuard,
+        ndc: P,
+        aspect_ratio: f32,
+    ) -> Self {
+        let ndc = ndc.into();
+        let mx_vp = Matrix4::from(camera.view_projection_matrix(sync, aspect_ratio));
+        let mx_inverse = mx_vp.invert().unwrap_or(Matrix4::identity());
+        // the middle of the depth range stays finite for infinite projections
+        let unproject = |z: f32| {
+            let p = mx_inverse * Vector4::new(ndc.x, ndc.y, z, 1.0);
+            Point3::from_homogeneous(p)
+        };
+        let near = unproject(-1.0);
+        let middle = unproject(0.0);
+        Raycaster::new(near, middle - near)
+    }
+
+    /// Intersects all the visible meshes in the scene.
+    ///
+    /// Returns the hits sorted by distance, closest first.
+    pub fn intersect_scene(
+        &self,
+        sync: &SyncGuard,
+    ) -> Vec<Intersection> {
+        let mut hits = Vec::new();
+        for w in sync.hub.walk(&sync.scene.first_child) {
+            self.intersect_node(&sync.hub, &w.node_ptr, &w.world_transform, &mut hits);
+        }
+        Self::sort(&mut hits);
+        hits
+    }
+
+    /// Intersects a single object, and its children if `recursive` is set.
+    ///
+    /// Returns the hits sorted by distance, closest first.
+    pub fn intersect_object<T: Object>(
+        &self,
+        sync: &SyncGuard,
+        object: &T,
+        recursive: bool,
+    ) -> Vec<Intersection> {
+        let internal = &sync.hub[object];
+        let mut hits = Vec::new();
+        let mut walker = sync.hub.walk_all(&sync.scene.first_child);
+        let root = match walker.find(|w| ptr::eq(w.node, internal)) {
+            Some(w) => w,
+            None => return hits,
+        };
+        if recursive {
+            self.intersect_subtree(&sync.hub, &root.node_ptr, &root.world_transform, &mut hits);
+        } else {
+            self.intersect_node(&sync.hub, &root.node_ptr, &root.world_transform, &mut hits);
+        }
+        Self::sort(&mut hits);
+        hits
+    }
+
+    /// Intersects the meshes tracked by a scene BVH.
+    ///
+    /// Returns the hits sorted by distance, closest first.
+    pub fn intersect_bvh(
+        &self,
+        bvh: &SceneBvh,
+        sync: &SyncGuard,
+    ) -> Vec<Intersection> {
+        let mut hits = Vec::new();
+        let origin = Point3::from(self.ray.origin).to_vec();
+        let direction = Vector3::from(self.ray.direction);
+        bvh.bvh.traverse(origin, direction, self.far, |item| {
+            let (ref base, ref transform) = bvh.objects[item];
+            self.intersect_node(&sync.hub, &base.node, transform, &mut hits);
+        });
+        Self::sort(&mut hits);
+        hits
+    }
+
+    fn sort(hits: &mut [Intersection]) {
+        hits.sort_by(|a, b| a.distance.partial_cmp(&b.distance).unwrap_or(::std::cmp::Ordering::Equal));
+    }
+
+    fn intersect_subtree(
+        &self,
+        hub: &Hub,
+        ptr: &NodePointer,
+        world_transform: &TransformInternal,
+        hits: &mut Vec<Intersection>,
+    ) {
+        let node = &hub.nodes[ptr];
+        if !node.visible {
+            return;
+        }
+        self.intersect_node(hub, ptr, world_transform, hits);
+        if let SubNode::Group { ref first_child, .. } = node.sub_node {
+            let mut child = first_child.clone();
+            while let Some(child_ptr) = child {
+                let child_node = &hub.nodes[&child_ptr];
+                let transform = world_transform.concat(&child_node.transform);
+                self.intersect_subtree(hub, &child_ptr, &transform, hits);
+                child = child_node.next_sibling.clone();
+            }
+        }
+    }
+
+    fn intersect_node(
+        &self,
+        hub: &Hub,
+        ptr: &NodePointer,
+        world_transform: &TransformInternal,
+        hits: &mut Vec<Intersection>,
+    ) {
+        let gpu_data = match hub.nodes[ptr].sub_node {
+            SubNode::Visual(Material::Line(_), _, _) => return,
+            SubNode::Visual(_, ref gpu_data, _) => gpu_data,
+            _ => return,
+        };
+        let triangles = match gpu_data.triangles {
+            Some(ref triangles) => triangles,
+            None => return,
+        };
+        let mx_world = Matrix4::from(*world_transform);
+        let list = match gpu_data.instance_list {
+            Some(ref list) => list,
+            None => {
+                if let Some(hit) = self.intersect_triangles(triangles, mx_world) {
+                    hits.push(self.intersection(hub, ptr, hit, None));
+                }
+                return;
+            }
+        };
+
+        // billboards only face the camera when drawn
+        if list.billboard {
+            return;
+        }
+        // skip the instances when the ray misses all of them
+        let (bounds, (origin, direction, far, _)) = match (list.bounds, self.local_ray(mx_world)) {
+            (Some((bounds, _)), Some(ray)) => (bounds, ray),
+            _ => return,
+        };
+        let inv_dir = Vector3::new(1.0 / direction.x, 1.0 / direction.y, 1.0 / direction.z);
+        if bounds.intersect(origin, inv_dir, far).is_none() {
+            return;
+        }
+        for (index, &mx) in list.transforms.iter().enumerate() {
+            if let Some(hit) = self.intersect_triangles(triangles, mx_world * Matrix4::from(mx)) {
+                hits.push(self.intersection(hub, ptr, hit, Some(index)));
+            }
+        }
+    }
+
+    /// Transforms the ray into the space of `mx_local`, returning its origin,
+    /// normalized direction, far distance, and the scale of distances.
+    fn local_ray(
+        &self,
+        mx_local: Matrix4<f32>,
+    ) -> Option<(Vector3<f32>, Vector3<f32>, f32, f32)> {
+        let inverse = mx_local.invert()?;
+        let local_origin = inverse.transform_point(Point3::from(self.ray.origin)).to_vec();
+        let local_dir = inverse.transform_vector(Vector3::from(self.ray.direction));
+        let scale = local_dir.magnitude();
+        if scale == 0.0 {
+            return None;
+        }
+        Some((local_origin, local_dir / scale, self.far * scale, scale))
+    }
+
+    /// Intersects `triangles` placed by `mx_local`, returning the distance
+    /// along the ray, the face and the barycentric coordinates of the hit.
+    fn intersect_triangles(
+        &self,
+        triangles: &Triangles,
+        mx_local: Matrix4<f32>,
+    ) -> Option<(f32, usize, [f32; 3])> {
+        let (local_origin, local_dir, local_far, scale) = self.local_ray(mx_local)?;
+        let inv_dir = Vector3::new(1.0 / local_dir.x, 1.0 / local_dir.y, 1.0 / local_dir.z);
+        triangles.bounds.intersect(local_origin, inv_dir, local_far)?;
+        let (t, face_index, barycentric) = triangles.intersect(local_origin, local_dir, local_far)?;
+        let distance = t / scale;
+        if distance < self.near || distance > self.far {
+            return None;
+        }
+        Some((distance, face_index, barycentric))
+    }
+
+    fn intersection(
+        &self,
+        hub: &Hub,
+        ptr: &NodePointer,
+        (distance, face_index, barycentric): (f32, usize, [f32; 3]),
+        instance: Option<usize>,
+    ) -> Intersection {
+        let point = Point3::from(self.ray.origin) + Vector3::from(self.ray.direction) * distance;
+        Intersection {
+            object: hub.upgrade_ptr(ptr.clone()),
+            distance,
+            point: point.into(),
+            face_index,
+            barycentric,
+            instance,
+        }
+    }
+}
+
+/// Bounding-volume hierarchy over the world space bounds of meshes in a scene.
+///
+/// Call [`update`](#method.update) once per frame before casting rays. When
+/// the set of visible meshes is unchanged, the hierarchy is only refitted to
+/// the new object positions, which is much cheaper than rebuilding it.
+#[derive(Clone, Debug)]
+pub struct SceneBvh {
+    objects: Vec<(Base, TransformInternal)>,
+    bvh: Bvh,
+}
+
+impl Default for SceneBvh {
+    fn default() -> Self {
+        SceneBvh {
+            objects: Vec::new(),
+            bvh: Bvh::build(&[]),
+        }
+    }
+}
+
+impl SceneBvh {
+    /// Creates an empty hierarchy.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Rebuilds or refits the hierarchy to match the current scene.
+    pub fn update(
+        &mut self,
+        sync: &SyncGuard,
+    ) {
+        let hub = &sync.hub;
+        let mut objects = Vec::with_capacity(self.objects.len());
+        let mut bounds = Vec::with_capacity(self.objects.len());
+        for w in hub.walk(&sync.scene.first_child) {
+            if let SubNode::Visual(_, ref gpu_data, _) = w.node.sub_node {
+                if let Some((mesh_bounds, _)) = gpu_data.bounds() {
+                    bounds.push(mesh_bounds.transformed(&w.world_transform));
+                    objects.push((hub.upgrade_ptr(w.node_ptr.clone()), w.world_transform));
+                }
+            }
+        }
+
+        let same_objects = objects.len() == self.objects.len() &&
+            objects.iter().zip(&self.objects).all(|(a, b)| a.0 == b.0);
+        if same_objects {
+            self.bvh.refit(&bounds);
+        } else {
+            self.bvh = Bvh::build(&bounds);
+        }
+        self.objects = objects;
+    }
+}
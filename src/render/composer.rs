@@ -0,0 +1,509 @@
+//! Post-processing of rendered frames.
+//!
+//! A [`Composer`] renders the scene into an offscreen target, then runs a
+//! chain of full-screen [`Pass`]es over the result. Each pass reads the
+//! output of the previous one, and the last pass draws into the window.
+//! UI text and debug quads are drawn over the final image.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! use three::render::composer::{Bloom, Composer, Fxaa, ToneMapping, Vignette};
+//!
+//! let mut composer = Composer::new(&mut window.factory);
+//! composer.add_pass(Bloom::new(&mut window.factory));
+//! composer.add_pass(ToneMapping::new(&mut window.factory));
+//! composer.add_pass(Vignette::new(&mut window.factory));
+//! composer.add_pass(Fxaa::new(&mut window.factory));
+//!
+//! while window.update() {
+//!     composer.render(&mut window.renderer, &window.scene, &camera);
+//! }
+//! ```
+//!
+//! Each composer holds its own chain, so different cameras can use different
+//! effects. A composer can also render into a [`RenderTarget`], for example
+//! a minimap or a character portrait shown by the main view:
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let map_camera = window.factory.orthographic_camera([0.0, 0.0], 20.0, -10.0 .. 10.0);
+//! use three::render::composer::{Composer, Fxaa, ToneMapping, Vignette};
+//!
+//! let mut main_view = Composer::new(&mut window.factory);
+//! main_view.add_pass(ToneMapping::new(&mut window.factory));
+//! main_view.add_pass(Fxaa::new(&mut window.factory));
+//!
+//! let minimap = window.factory.render_target(256, 256);
+//! let mut minimap_view = Composer::new(&mut window.factory);
+//! minimap_view.add_pass(Vignette::new(&mut window.factory));
+//! let _quad = window.renderer.texture_quad(minimap.texture(), [-266, 10], [256, 256]);
+//!
+//! while window.update() {
+//!     minimap_view.render_to(&mut window.renderer, &window.scene, &map_camera, &minimap);
+//!     main_view.render(&mut window.renderer, &window.scene, &camera);
+//! }
+//! ```
+//!
+//! Custom passes either implement [`Pass`] directly, or are pixel shaders
+//! loaded with [`Factory::shader_pass`]. Such shaders `#include <post>`,
+//! which declares:
+//!
+//! * `t_Input`: the output of the previous pass.
+//! * `t_Aux`: the [`aux`] texture of the pass, white by default.
+//! * `v_TexCoord`: the texture coordinates of the pixel.
+//! * `u_Params`: a `mat4` holding the [`params`] of the pass, one `vec4`
+//!   per column.
+//! * `u_Resolution`: the size of the output in pixels in `xy`, and the size
+//!   of one pixel in texture coordinates in `zw`.
+//!
+//! [`Composer`]: struct.Composer.html
+//! [`RenderTarget`]: ../struct.RenderTarget.html
+//! [`Pass`]: trait.Pass.html
+//! [`Factory::shader_pass`]: ../../struct.Factory.html#method.shader_pass
+//! [`aux`]: struct.ShaderPass.html#structfield.aux
+//! [`params`]: struct.ShaderPass.html#structfield.params
+
+use camera::Camera;
+use color::{self, Color};
+use factory::Factory;
+use scene::Scene;
+use texture::Texture;
+
+use super::{PostPipelineState, RenderTarget, Renderer, Source};
+
+/// A full-screen effect applied to a rendered frame.
+pub trait Pass {
+    /// Draws the effect, reading from `input` and writing into `output`, or
+    /// into the window when `output` is `None`.
+    ///
+    /// Every pixel of the output needs to be written, as it may hold the
+    /// result of a previous frame.
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    );
+
+    /// Called by the [`Composer`] before rendering the scene, for passes
+    /// needing their own renders of it. `size` is the size in pixels of the
+    /// frame. Does nothing by default.
+    ///
+    /// [`Composer`]: struct.Composer.html
+    fn prepare(
+        &mut self,
+        _renderer: &mut Renderer,
+        _scene: &Scene,
+        _camera: &Camera,
+        _size: [u16; 2],
+    ) {
+    }
+}
+
+/// A pass drawing a pixel shader over the whole output.
+///
+/// Created with [`Factory::shader_pass`]. See the
+/// [module documentation](index.html) for the inputs of the shader.
+///
+/// [`Factory::shader_pass`]: ../../struct.Factory.html#method.shader_pass
+pub struct ShaderPass {
+    pso: PostPipelineState,
+    /// Parameters read by the shader as `u_Params[0]` to `u_Params[3]`.
+    ///
+    /// Default: all zeros.
+    pub params: [[f32; 4]; 4],
+    /// Texture read by the shader as `t_Aux`, white when `None`.
+    ///
+    /// Default: `None`.
+    pub aux: Option<Texture<[f32; 4]>>,
+}
+
+impl ShaderPass {
+    pub(crate) fn new(pso: PostPipelineState) -> Self {
+        ShaderPass {
+            pso,
+            params: [[0.0; 4]; 4],
+            aux: None,
+        }
+    }
+
+    /// Creates a pass from the built-in `post_<name>_ps.glsl` shader.
+    fn builtin(
+        factory: &mut Factory,
+        name: &str,
+    ) -> Self {
+        let ps = Source::default(&format!("post_{}", name), "ps").unwrap();
+        let pso = factory
+            .post_pipeline(&ps)
+            .unwrap_or_else(|e| panic!("Unable to create the {} pass: {}", name, e));
+        ShaderPass::new(pso)
+    }
+
+    /// Draws the shader reading from `input` into `output`, or into the
+    /// window when `output` is `None`. Useful for passes made of several
+    /// steps.
+    pub fn draw(
+        &self,
+        renderer: &mut Renderer,
+        input: &Texture<[f32; 4]>,
+        output: Option<&RenderTarget>,
+    ) {
+        renderer.draw_post(&self.pso, self.params, input, self.aux.as_ref(), output);
+    }
+}
+
+impl Pass for ShaderPass {
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    ) {
+        self.draw(renderer, input.texture(), output);
+    }
+}
+
+/// Fast approximate anti-aliasing, smoothing the jagged edges of polygons.
+///
+/// Usually the last pass, as it works best on the final colors.
+pub struct Fxaa {
+    pass: ShaderPass,
+}
+
+impl Fxaa {
+    /// Creates the pass.
+    pub fn new(factory: &mut Factory) -> Self {
+        Fxaa {
+            pass: ShaderPass::builtin(factory, "fxaa"),
+        }
+    }
+}
+
+impl Pass for Fxaa {
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    ) {
+        self.pass.render(renderer, input, output);
+    }
+}
+
+/// Darkens the corners of the frame.
+pub struct Vignette {
+    pass: ShaderPass,
+    /// How much the corners are darkened, from 0 to 1.
+    ///
+    /// Default: 0.5.
+    pub darkness: f32,
+    /// Distance from the center where the darkening ends, relative to the
+    /// frame height.
+    ///
+    /// Default: 0.8.
+    pub radius: f32,
+    /// Width of the transition towards the center, relative to the frame
+    /// height.
+    ///
+    /// Default: 0.5.
+    pub softness: f32,
+    /// The color the corners fade to.
+    ///
+    /// Default: black.
+    pub color: Color,
+}
+
+impl Vignette {
+    /// Creates the pass with the default settings.
+    pub fn new(factory: &mut Factory) -> Self {
+        Vignette {
+            pass: ShaderPass::builtin(factory, "vignette"),
+            darkness: 0.5,
+            radius: 0.8,
+            softness: 0.5,
+            color: color::BLACK,
+        }
+    }
+}
+
+impl Pass for Vignette {
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    ) {
+        // the frame holds display colors, so the color isn't made linear
+        let color = self.color;
+        let channel = |shift: u32| ((color >> shift) & 0xFF) as f32 / 255.0;
+        self.pass.params[0] = [self.darkness, self.radius, self.softness, 0.0];
+        self.pass.params[1] = [channel(16), channel(8), channel(0), 1.0];
+        self.pass.render(renderer, input, output);
+    }
+}
+
+/// Compresses bright colors with a filmic curve instead of clipping them.
+pub struct ToneMapping {
+    pass: ShaderPass,
+    /// Multiplier of the linear colors before the curve.
+    ///
+    /// Default: 1.0.
+    pub exposure: f32,
+    /// Gamma used to convert the colors of the frame to linear space.
+    ///
+    /// Default: 2.2.
+    pub gamma: f32,
+}
+
+impl ToneMapping {
+    /// Creates the pass with the default settings.
+    pub fn new(factory: &mut Factory) -> Self {
+        ToneMapping {
+            pass: ShaderPass::builtin(factory, "tone_mapping"),
+            exposure: 1.0,
+            gamma: 2.2,
+        }
+    }
+}
+
+impl Pass for ToneMapping {
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    ) {
+        self.pass.params[0] = [self.exposure, self.gamma.max(0.1), 0.0, 0.0];
+        self.pass.render(renderer, input, output);
+    }
+}
+
+/// Makes bright parts of the frame glow into their surroundings.
+///
+/// The bright parts are blurred at half the resolution of the frame, then
+/// added on top of it.
+///
+/// In selective mode, only materials with a [`bloom_intensity`] glow,
+/// with their emissive color scaled by it. The threshold doesn't apply, so
+/// neon signs can glow without the rest of a bright scene blowing out.
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// use three::render::composer::{Bloom, Composer};
+///
+/// let sign = window.factory.mesh(
+///     three::Geometry::cuboid(2.0, 0.5, 0.1),
+///     three::material::Pbr {
+///         emissive_factor: 0xFF40C0,
+///         bloom_intensity: 2.0,
+///         .. Default::default()
+///     },
+/// );
+/// window.scene.add(&sign);
+///
+/// let mut bloom = Bloom::new(&mut window.factory);
+/// bloom.selective = true;
+/// let mut composer = Composer::new(&mut window.factory);
+/// composer.add_pass(bloom);
+/// ```
+///
+/// [`bloom_intensity`]: ../../material/struct.Pbr.html#structfield.bloom_intensity
+pub struct Bloom {
+    bright: ShaderPass,
+    blur: ShaderPass,
+    combine: ShaderPass,
+    targets: Option<[RenderTarget; 2]>,
+    /// Brightness above which colors start to glow, from 0 to 1.
+    ///
+    /// Default: 0.8.
+    pub threshold: f32,
+    /// Range of brightness around the threshold where the glow fades in.
+    ///
+    /// Default: 0.1.
+    pub softness: f32,
+    /// Multiplier of the glow added to the frame.
+    ///
+    /// Default: 0.8.
+    pub strength: f32,
+    /// Spread of the glow, in pixels of the blurred image.
+    ///
+    /// Default: 1.5.
+    pub radius: f32,
+    /// Whether only materials with a bloom intensity glow, instead of
+    /// everything above the threshold.
+    ///
+    /// Default: `false`.
+    pub selective: bool,
+}
+
+impl Bloom {
+    /// Creates the pass with the default settings.
+    pub fn new(factory: &mut Factory) -> Self {
+        Bloom {
+            bright: ShaderPass::builtin(factory, "bright"),
+            blur: ShaderPass::builtin(factory, "blur"),
+            combine: ShaderPass::builtin(factory, "bloom"),
+            targets: None,
+            threshold: 0.8,
+            softness: 0.1,
+            strength: 0.8,
+            radius: 1.5,
+            selective: false,
+        }
+    }
+
+    /// Makes sure the blur targets are half of `size`.
+    fn resize(
+        &mut self,
+        renderer: &mut Renderer,
+        size: [u16; 2],
+    ) {
+        let half = [(size[0] / 2).max(1), (size[1] / 2).max(1)];
+        if self.targets.as_ref().map_or(true, |targets| targets[0].size() != half) {
+            self.targets = Some([
+                renderer.render_target(half[0], half[1]),
+                renderer.render_target(half[0], half[1]),
+            ]);
+        }
+    }
+}
+
+impl Pass for Bloom {
+    fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        input: &RenderTarget,
+        output: Option<&RenderTarget>,
+    ) {
+        self.resize(renderer, input.size());
+        let targets = self.targets.as_ref().unwrap();
+
+        // in selective mode, the glowing parts were rendered by `prepare`
+        if !self.selective {
+            self.bright.params[0] = [self.threshold, self.softness.max(1.0e-3), 0.0, 0.0];
+            self.bright.draw(renderer, input.texture(), Some(&targets[0]));
+        }
+        self.blur.params[0] = [self.radius, 0.0, 0.0, 0.0];
+        self.blur.draw(renderer, targets[0].texture(), Some(&targets[1]));
+        self.blur.params[0] = [0.0, self.radius, 0.0, 0.0];
+        self.blur.draw(renderer, targets[1].texture(), Some(&targets[0]));
+
+        self.combine.params[0] = [self.strength, 0.0, 0.0, 0.0];
+        self.combine.aux = Some(targets[0].texture().clone());
+        self.combine.draw(renderer, input.texture(), output);
+    }
+
+    fn prepare(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        camera: &Camera,
+        size: [u16; 2],
+    ) {
+        if !self.selective {
+            return;
+        }
+        self.resize(renderer, size);
+        renderer.render_emissive_to(scene, camera, &self.targets.as_ref().unwrap()[0]);
+    }
+}
+
+/// Renders scenes through a chain of post-processing passes. See the
+/// [module documentation](index.html) for an example.
+pub struct Composer {
+    copy: ShaderPass,
+    targets: Vec<RenderTarget>,
+    /// The passes, applied in order.
+    pub passes: Vec<Box<dyn Pass>>,
+}
+
+impl Composer {
+    /// Creates a composer without passes, which shows the scene unchanged.
+    pub fn new(factory: &mut Factory) -> Self {
+        Composer {
+            copy: ShaderPass::builtin(factory, "copy"),
+            targets: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Appends `pass` to the chain.
+    pub fn add_pass<P: Pass + 'static>(
+        &mut self,
+        pass: P,
+    ) {
+        self.passes.push(Box::new(pass));
+    }
+
+    /// Renders `scene` by `camera` through the passes into the window. Use
+    /// instead of [`Window::render`].
+    ///
+    /// [`Window::render`]: ../../struct.Window.html#method.render
+    pub fn render(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        camera: &Camera,
+    ) {
+        let size = renderer.size();
+        let size = [(size.x as u16).max(1), (size.y as u16).max(1)];
+        self.run(renderer, scene, camera, size, None);
+        renderer.present_overlays(scene, true);
+    }
+
+    /// Renders `scene` by `camera` through the passes into `target`.
+    ///
+    /// UI text and debug quads are not drawn into render targets, and the
+    /// frame isn't presented: the window still needs to be rendered
+    /// afterwards, with [`render`](#method.render) or [`Window::render`].
+    ///
+    /// [`Window::render`]: ../../struct.Window.html#method.render
+    pub fn render_to(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        camera: &Camera,
+        target: &RenderTarget,
+    ) {
+        self.run(renderer, scene, camera, target.size(), Some(target));
+    }
+
+    /// Renders the scene at `size` and runs the passes, the last one
+    /// drawing into `output`, or into the window when `None`.
+    fn run(
+        &mut self,
+        renderer: &mut Renderer,
+        scene: &Scene,
+        camera: &Camera,
+        size: [u16; 2],
+        output: Option<&RenderTarget>,
+    ) {
+        if self.targets.first().map_or(true, |target| target.size() != size) {
+            self.targets = vec![
+                renderer.render_target(size[0], size[1]),
+                renderer.render_target(size[0], size[1]),
+            ];
+        }
+        for pass in &mut self.passes {
+            pass.prepare(renderer, scene, camera, size);
+        }
+        renderer.render_to(scene, camera, &self.targets[0]);
+
+        let count = self.passes.len();
+        if count == 0 {
+            self.copy.draw(renderer, self.targets[0].texture(), output);
+        }
+        let mut current = 0;
+        for (i, pass) in self.passes.iter_mut().enumerate() {
+            let next = if i + 1 == count {
+                output
+            } else {
+                Some(&self.targets[1 - current])
+            };
+            pass.render(renderer, &self.targets[current], next);
+            current = 1 - current;
+        }
+    }
+}
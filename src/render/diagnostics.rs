@@ -0,0 +1,206 @@
+//! Readable reports for errors in user shaders, and the problems the
+//! renderer runs into while drawing.
+
+use gfx;
+use gfx::pso::InitError;
+use gfx::shade::ProgramError;
+use gfx::shade::core::CreateShaderError;
+
+use std::fmt;
+
+use material::ShaderFeatures;
+use object::Base;
+
+use super::PipelineCreationError;
+
+/// A problem the renderer ran into, passed to the callback set with
+/// [`Renderer::set_diagnostics_fn`] as well as logged.
+///
+/// Problems found while drawing are reported on every frame they occur.
+///
+/// [`Renderer::set_diagnostics_fn`]: struct.Renderer.html#method.set_diagnostics_fn
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnostic {
+    /// The window was created without multisampling, which the driver
+    /// doesn't provide, with the error of the first attempt.
+    MultisamplingUnavailable(String),
+    /// The scene has more lights than the renderer supports. The lights past
+    /// the first `max` ones are ignored.
+    TooManyLights {
+        /// The number of lights the renderer supports.
+        max: usize,
+    },
+    /// A light has the wrong kind of shadow map: point lights need cube
+    /// maps and directional lights flat ones. It casts no shadows.
+    ShadowMapMismatch(Base),
+    /// A mesh blends more morph targets than the renderer supports. The
+    /// targets past the first `max` ones are ignored.
+    TooManyMorphTargets {
+        /// The number of blended targets.
+        count: usize,
+        /// The number of targets the renderer supports.
+        max: usize,
+    },
+    /// The built-in shaders failed to compile with the features of a
+    /// material, with the compiler error. Meshes using it are drawn
+    /// without the features.
+    ShaderFeatures(ShaderFeatures, String),
+    /// A variant of a built-in pipeline, for the stencil state or the
+    /// features of a material, failed to build, with the error. Meshes
+    /// using it are drawn with the default pipeline.
+    PipelineVariant(String),
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match *self {
+            Diagnostic::MultisamplingUnavailable(ref err) => {
+                write!(f, "Unable to create a multisampled window ({}), disabling multisampling", err)
+            }
+            Diagnostic::TooManyLights { max } => write!(f, "Max number of lights ({}) reached", max),
+            Diagnostic::ShadowMapMismatch(ref base) => write!(
+                f,
+                "{:?}: point lights need cube shadow maps, and directional lights flat ones",
+                base,
+            ),
+            Diagnostic::TooManyMorphTargets { count, max } => {
+                write!(f, "Too many mesh targets ({}), only {} are supported", count, max)
+            }
+            Diagnostic::ShaderFeatures(features, ref err) => {
+                write!(f, "Shader features {:?} failed to compile: {}", features, err)
+            }
+            Diagnostic::PipelineVariant(ref err) => write!(f, "Pipeline variant creation failed: {}", err),
+        }
+    }
+}
+
+/// Vertex attributes provided by `basic_pipe`.
+const BASIC_ATTRIBUTES: &[&str] = &[
+    "a_Position",
+    "a_TexCoord",
+    "a_Normal",
+    "a_Tangent",
+    "a_JointIndices",
+    "a_JointWeights",
+    "i_World0",
+    "i_World1",
+    "i_World2",
+    "i_Normal0",
+    "i_Normal1",
+    "i_Normal2",
+    "i_Color",
+    "i_MatParams",
+    "i_UvRange",
+];
+
+/// Uniform blocks provided by `basic_pipe`.
+const BASIC_CONSTANT_BUFFERS: &[&str] = &["b_Lights", "b_Globals", "b_User", "b_DisplacementContributions"];
+
+/// Textures provided by `basic_pipe`.
+const BASIC_TEXTURES: &[&str] = &["t_Map", "t_Shadow0", "t_Shadow1", "t_ShadowCube0", "t_ShadowCube1", "b_JointTransforms", "u_Displacements"];
+
+/// Finds the source line referred to by a compiler log message, such as
+/// `0:12(3): error` (Mesa), `0(12) : error` (NVIDIA) or `ERROR: 0:12:` (AMD, Intel).
+fn line_number(message: &str) -> Option<usize> {
+    let start = message.find("0:").or_else(|| message.find("0("))? + 2;
+    let digits: String = message[start ..]
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+/// Follows every message of the compiler `log` with the numbered source line
+/// it refers to.
+fn annotate(
+    log: &str,
+    source: &str,
+) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let mut result = String::new();
+    for message in log.lines() {
+        result.push_str(message);
+        result.push('\n');
+        match line_number(message) {
+            Some(line) if line >= 1 && line <= lines.len() => {
+                result += &format!("{:5} | {}\n", line, lines[line - 1]);
+            }
+            _ => {}
+        }
+    }
+    result
+}
+
+/// Converts a program error, quoting the failing lines of the preprocessed
+/// vertex and pixel shader sources.
+pub(crate) fn program_error(
+    err: ProgramError,
+    vs: &str,
+    ps: &str,
+) -> PipelineCreationError {
+    match err {
+        ProgramError::Vertex(CreateShaderError::CompilationFailed(log)) => {
+            PipelineCreationError::Shader("Vertex", annotate(&log, vs))
+        }
+        ProgramError::Pixel(CreateShaderError::CompilationFailed(log)) => {
+            PipelineCreationError::Shader("Pixel", annotate(&log, ps))
+        }
+        ProgramError::Link(err) => PipelineCreationError::Link(err.to_string()),
+        other => PipelineCreationError::Compilation(other),
+    }
+}
+
+/// Converts a pipeline state error, naming the shader input that doesn't
+/// match the pipeline.
+pub(crate) fn state_error(err: gfx::PipelineStateError<&str>) -> PipelineCreationError {
+    let input = match err {
+        gfx::PipelineStateError::DescriptorInit(ref init) => Some(match *init {
+            InitError::VertexImport(name, ref format) => ("attribute", name, format.is_some()),
+            InitError::ConstantBuffer(name, ref element) => ("uniform block", name, element.is_some()),
+            InitError::GlobalConstant(name, ref compatibility) => ("uniform", name, compatibility.is_some()),
+            InitError::ResourceView(name, ref found) => ("texture", name, found.is_some()),
+            InitError::UnorderedView(name, ref found) => ("unordered view", name, found.is_some()),
+            InitError::Sampler(name, ref found) => ("sampler", name, found.is_some()),
+            InitError::PixelExport(name, ref format) => ("output", name, format.is_some()),
+        }),
+        _ => None,
+    };
+    match input {
+        Some((kind, name, true)) => PipelineCreationError::Input(
+            kind,
+            name.to_string(),
+            "doesn't match the type provided by the pipeline",
+        ),
+        Some((kind, name, false)) => PipelineCreationError::Input(
+            kind,
+            name.to_string(),
+            "isn't provided by the pipeline",
+        ),
+        None => PipelineCreationError::State(err.into()),
+    }
+}
+
+/// Logs the inputs of `basic_pipe` that the program of the pipeline `name`
+/// doesn't use, which usually points to a misspelled declaration.
+pub(crate) fn log_unused_basic_inputs(
+    name: &str,
+    info: &gfx::ProgramInfo,
+) {
+    let unused: Vec<&str> = BASIC_ATTRIBUTES
+        .iter()
+        .filter(|&&input| !info.vertex_attributes.iter().any(|var| var.name == input))
+        .chain(BASIC_CONSTANT_BUFFERS
+            .iter()
+            .filter(|&&input| !info.constant_buffers.iter().any(|var| var.name == input)))
+        .chain(BASIC_TEXTURES
+            .iter()
+            .filter(|&&input| !info.textures.iter().any(|var| var.name == input)))
+        .cloned()
+        .collect();
+    if !unused.is_empty() {
+        info!("Pipeline `{}` doesn't use the inputs: {}", name, unused.join(", "));
+    }
+}
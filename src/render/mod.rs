@@ -1,42 +1,56 @@
 //! The renderer.
 
-use cgmath::{Matrix as Matrix_, Matrix4, SquareMatrix, Transform as Transform_, Vector3};
+use cgmath::{ortho, perspective, Array, Deg, EuclideanSpace, InnerSpace, Matrix as Matrix_, Matrix3, Matrix4, Point3, Quaternion, SquareMatrix, Transform as Transform_, Vector3, Vector4};
 use froggy;
 use gfx;
-use gfx::format::I8Norm;
+use gfx::format::{Formatted, I8Norm};
 use gfx::handle as h;
 use gfx::memory::Typed;
 use gfx::traits::{Factory as Factory_, FactoryExt};
 #[cfg(feature = "opengl")]
 use gfx_device_gl as back;
+use gfx_gl as gl;
 #[cfg(feature = "opengl")]
 use gfx_window_glutin;
 #[cfg(feature = "opengl")]
 use glutin;
+use image;
 use mint;
 
+pub mod composer;
 pub mod source;
+pub(crate) mod diagnostics;
 mod pso_data;
 
 use color;
 
-use std::{io, str};
-use std::collections::HashMap;
+use std::{cmp, io, mem, ptr, slice, str};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::collections::{HashMap, HashSet};
 
 pub use self::back::CommandBuffer as BackendCommandBuffer;
 pub use self::back::Factory as BackendFactory;
 pub use self::back::Resources as BackendResources;
+pub use self::diagnostics::Diagnostic;
 pub use self::source::Source;
 
 use self::pso_data::{PbrFlags, PsoData};
-use camera::Camera;
+use camera::{Camera, Frustum, Projection, ZRange};
 use factory::Factory;
-use hub::{SubLight, SubNode};
-use light::{ShadowMap, ShadowProjection};
-use material::Material;
-use scene::{Background, Scene};
-use text::Font;
-use texture::Texture;
+use geometry::Geometry;
+use hub::{Hub, SubLight, SubNode, WalkedNode};
+use hud::Hud;
+use light::{ShadowMap, ShadowProjection, MAX_SHADOW_CASCADES};
+use material::{self, Material, ShaderFeatures, Transparency};
+use node::{NodeInternal, Transform, TransformInternal};
+use object::Base;
+use portal::PortalSystem;
+use raycast::{Aabb, Triangles};
+use scene::{Background, Scene, SyncGuard};
+use skeleton::{self, Skeleton};
+use text::{Font, DEPTH_ON_TOP};
+use texture::{CubeMap, Texture};
 use glutin::{ContextCurrentState, NotCurrent, Window, ContextWrapper, PossiblyCurrent};
 
 /// The format of the back buffer color requested from the windowing system.
@@ -45,8 +59,24 @@ pub type ColorFormat = gfx::format::Rgba8;
 pub type DepthFormat = gfx::format::DepthStencil;
 /// The format of the shadow buffer.
 pub type ShadowFormat = gfx::format::Depth32F;
+/// The format of the buffer HDR frames are rendered into.
+type HdrColorFormat = (gfx::format::R16_G16_B16_A16, gfx::format::Float);
 /// The concrete type of a basic pipeline.
 pub type BasicPipelineState = gfx::PipelineState<back::Resources, basic_pipe::Meta>;
+/// The concrete type of a post-processing pipeline.
+pub(crate) type PostPipelineState = gfx::PipelineState<back::Resources, post_pipe::Meta>;
+/// The concrete type of a custom background pipeline.
+pub(crate) type BackgroundPipelineState = gfx::PipelineState<back::Resources, background_pipe::Meta>;
+
+/// Init of the color output of the pipelines, declared with the window
+/// format. The output is raw, so that the same pipelines also draw into
+/// half float HDR targets, whose format their `RenderTarget` records.
+pub(crate) fn color_output(
+    mask: gfx::state::ColorMask,
+    blend: Option<gfx::state::Blend>,
+) -> (&'static str, gfx::format::Format, gfx::state::ColorMask, Option<gfx::state::Blend>) {
+    ("Target0", ColorFormat::get_format(), mask, blend)
+}
 
 pub(crate) const MAX_LIGHTS: usize = 4;
 pub(crate) const MAX_TARGETS: usize = 8;
@@ -74,6 +104,26 @@ quick_error! {
             cause(err)
         }
 
+        #[doc = "GLSL compiler error, with the compiler log followed by the"]
+        #[doc = "numbered source lines it refers to."]
+        Shader(stage: &'static str, log: String) {
+            description("GLSL shader compilation error")
+            display("{} shader compilation error:\n{}", stage, log)
+        }
+
+        #[doc = "GLSL linker error."]
+        Link(log: String) {
+            description("GLSL program link error")
+            display("GLSL program link error:\n{}", log)
+        }
+
+        #[doc = "A shader input, named by the second field, is missing from the"]
+        #[doc = "pipeline or has a different type."]
+        Input(kind: &'static str, name: String, reason: &'static str) {
+            description("Shader input mismatch")
+            display("Shader {} `{}` {}", kind, name, reason)
+        }
+
         #[doc = "Pipeline state error."]
         State(err: gfx::PipelineStateError<String>) {
             from()
@@ -96,8 +146,10 @@ quick_error! {
 pub const DEFAULT_VERTEX: Vertex = Vertex {
     pos: [0.0, 0.0, 0.0, 1.0],
     uv: [0.0, 0.0],
+    uv1: [0.0, 0.0],
     normal: [I8Norm(0), I8Norm(127), I8Norm(0), I8Norm(0)],
     tangent: [I8Norm(127), I8Norm(0), I8Norm(0), I8Norm(0)],
+    color: [1.0, 1.0, 1.0, 1.0],
     joint_indices: [0, 0, 0, 0],
     joint_weights: [1.0, 1.0, 1.0, 1.0],
 };
@@ -126,8 +178,10 @@ gfx_defines! {
     vertex Vertex {
         pos: [f32; 4] = "a_Position",
         uv: [f32; 2] = "a_TexCoord",
+        uv1: [f32; 2] = "a_TexCoord1",
         normal: [gfx::format::I8Norm; 4] = "a_Normal",
         tangent: [gfx::format::I8Norm; 4] = "a_Tangent",
+        color: [f32; 4] = "a_Color",
         joint_indices: [i32; 4] = "a_JointIndices",
         joint_weights: [f32; 4] = "a_JointWeights",
     }
@@ -136,6 +190,9 @@ gfx_defines! {
         world0: [f32; 4] = "i_World0",
         world1: [f32; 4] = "i_World1",
         world2: [f32; 4] = "i_World2",
+        normal0: [f32; 4] = "i_Normal0",
+        normal1: [f32; 4] = "i_Normal1",
+        normal2: [f32; 4] = "i_Normal2",
         color: [f32; 4] = "i_Color",
         mat_params: [f32; 4] = "i_MatParams",
         uv_range: [f32; 4] = "i_UvRange",
@@ -145,30 +202,56 @@ gfx_defines! {
         projection: [[f32; 4]; 4] = "projection",
         pos: [f32; 4] = "pos",
         dir: [f32; 4] = "dir",
-        focus: [f32; 4] = "focus",
+        shadow_range: [f32; 4] = "shadow_range",
         color: [f32; 4] = "color",
         color_back: [f32; 4] = "color_back",
         intensity: [f32; 4] = "intensity",
         shadow_params: [i32; 4] = "shadow_params",
+        cascade_scale: [[f32; 4]; 4] = "cascade_scale",
+        cascade_offset: [[f32; 4]; 4] = "cascade_offset",
     }
 
     constant Globals {
         mx_vp: [[f32; 4]; 4] = "u_ViewProj",
         mx_inv_proj: [[f32; 4]; 4] = "u_InverseProj",
         mx_view: [[f32; 4]; 4] = "u_View",
+        fog_color: [f32; 4] = "u_FogColor",
+        fog_range: [f32; 4] = "u_FogRange",
+        depth_params: [f32; 4] = "u_DepthParams",
+        probe_a: [[f32; 4]; 4] = "u_ProbeA",
+        probe_b: [[f32; 4]; 4] = "u_ProbeB",
+        probe_c: [f32; 4] = "u_ProbeC",
+        reflection_params: [f32; 4] = "u_ReflectionParams",
         num_lights: u32 = "u_NumLights",
     }
 
+    constant UserParams {
+        params: [[f32; 4]; 4] = "u_UserParams",
+    }
+
     pipeline basic_pipe {
         vbuf: gfx::VertexBuffer<Vertex> = (),
         inst_buf: gfx::InstanceBuffer<Instance> = (),
         cb_lights: gfx::ConstantBuffer<LightParam> = "b_Lights",
         cb_globals: gfx::ConstantBuffer<Globals> = "b_Globals",
+        cb_user: gfx::ConstantBuffer<UserParams> = "b_User",
         tex_map: gfx::TextureSampler<[f32; 4]> = "t_Map",
+        normal_map: gfx::TextureSampler<[f32; 4]> = "t_NormalMap",
+        specular_map: gfx::TextureSampler<[f32; 4]> = "t_SpecularMap",
         shadow_map0: gfx::TextureSampler<f32> = "t_Shadow0",
         shadow_map1: gfx::TextureSampler<f32> = "t_Shadow1",
-        out_color: gfx::BlendTarget<ColorFormat> =
-            ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::REPLACE),
+        shadow_cube0: gfx::TextureSampler<f32> = "t_ShadowCube0",
+        shadow_cube1: gfx::TextureSampler<f32> = "t_ShadowCube1",
+        cascade0: gfx::TextureSampler<f32> = "t_Cascade0",
+        cascade1: gfx::TextureSampler<f32> = "t_Cascade1",
+        cascade2: gfx::TextureSampler<f32> = "t_Cascade2",
+        cascade3: gfx::TextureSampler<f32> = "t_Cascade3",
+        displacement_contributions: gfx::ConstantBuffer<DisplacementContribution> = "b_DisplacementContributions",
+        joint_transforms: gfx::ShaderResource<[f32; 4]> = "b_JointTransforms",
+        displacements: gfx::TextureSampler<[f32; 4]> = "u_Displacements",
+        user_data: gfx::ShaderResource<[f32; 4]> = "b_UserData",
+        out_color: gfx::RawRenderTarget =
+            color_output(gfx::state::ColorMask::all(), Some(gfx::preset::blend::REPLACE)),
         out_depth: gfx::DepthStencilTarget<DepthFormat> =
             (gfx::preset::depth::LESS_EQUAL_WRITE, gfx::state::Stencil {
                 front: STENCIL_SIDE, back: STENCIL_SIDE,
@@ -179,10 +262,25 @@ gfx_defines! {
         vbuf: gfx::VertexBuffer<Vertex> = (),
         inst_buf: gfx::InstanceBuffer<Instance> = (),
         cb_globals: gfx::ConstantBuffer<Globals> = "b_Globals",
+        displacement_contributions: gfx::ConstantBuffer<DisplacementContribution> = "b_DisplacementContributions",
+        joint_transforms: gfx::ShaderResource<[f32; 4]> = "b_JointTransforms",
+        displacements: gfx::TextureSampler<[f32; 4]> = "u_Displacements",
         target: gfx::DepthTarget<ShadowFormat> =
             gfx::preset::depth::LESS_EQUAL_WRITE,
     }
 
+    pipeline pick_pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        inst_buf: gfx::InstanceBuffer<Instance> = (),
+        globals: gfx::ConstantBuffer<Globals> = "b_Globals",
+        displacement_contributions: gfx::ConstantBuffer<DisplacementContribution> = "b_DisplacementContributions",
+        joint_transforms: gfx::ShaderResource<[f32; 4]> = "b_JointTransforms",
+        displacements: gfx::TextureSampler<[f32; 4]> = "u_Displacements",
+        target: gfx::RenderTarget<ColorFormat> = "Target0",
+        depth_target: gfx::DepthTarget<DepthFormat> =
+            gfx::preset::depth::LESS_EQUAL_WRITE,
+    }
+
     constant QuadParams {
         rect: [f32; 4] = "u_Rect",
         depth: f32 = "u_Depth",
@@ -193,7 +291,56 @@ gfx_defines! {
         globals: gfx::ConstantBuffer<Globals> = "b_Globals",
         resource: gfx::RawShaderResource = "t_Input",
         sampler: gfx::Sampler = "t_Input",
-        target: gfx::RenderTarget<ColorFormat> = "Target0",
+        target: gfx::RawRenderTarget = color_output(gfx::state::ColorMask::all(), None),
+        depth_target: gfx::DepthTarget<DepthFormat> =
+            gfx::preset::depth::LESS_EQUAL_TEST,
+    }
+
+    constant PostParams {
+        params: [[f32; 4]; 4] = "u_Params",
+        resolution: [f32; 4] = "u_Resolution",
+    }
+
+    pipeline post_pipe {
+        params: gfx::ConstantBuffer<PostParams> = "b_PostParams",
+        input: gfx::TextureSampler<[f32; 4]> = "t_Input",
+        aux: gfx::TextureSampler<[f32; 4]> = "t_Aux",
+        target: gfx::RawRenderTarget = color_output(gfx::state::ColorMask::all(), None),
+    }
+
+    pipeline background_pipe {
+        params: gfx::ConstantBuffer<PostParams> = "b_PostParams",
+        globals: gfx::ConstantBuffer<Globals> = "b_Globals",
+        aux: gfx::TextureSampler<[f32; 4]> = "t_Aux",
+        target: gfx::RawRenderTarget = color_output(gfx::state::ColorMask::all(), None),
+        depth_target: gfx::DepthTarget<DepthFormat> =
+            gfx::preset::depth::LESS_EQUAL_TEST,
+    }
+
+    vertex HudVertex {
+        pos: [f32; 2] = "a_Position",
+        color: [f32; 4] = "a_Color",
+    }
+
+    pipeline hud_pipe {
+        vbuf: gfx::VertexBuffer<HudVertex> = (),
+        target: gfx::RawRenderTarget =
+            color_output(gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)),
+    }
+
+    constant IconParams {
+        rect: [f32; 4] = "u_Rect",
+        uv_range: [f32; 4] = "u_UvRange",
+        color: [f32; 4] = "u_Color",
+        depth: f32 = "u_Depth",
+        distance_range: f32 = "u_DistanceRange",
+    }
+
+    pipeline icon_pipe {
+        params: gfx::ConstantBuffer<IconParams> = "b_IconParams",
+        map: gfx::TextureSampler<[f32; 4]> = "t_Map",
+        target: gfx::RawRenderTarget =
+            color_output(gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)),
         depth_target: gfx::DepthTarget<DepthFormat> =
             gfx::preset::depth::LESS_EQUAL_TEST,
     }
@@ -203,7 +350,7 @@ gfx_defines! {
         camera: [f32; 3] = "u_Camera",
         _padding0: f32 = "_padding0",
         emissive_factor: [f32; 3] = "u_EmissiveFactor",
-        _padding1: f32 = "_padding1",
+        bloom_intensity: f32 = "u_BloomIntensity",
         metallic_roughness: [f32; 2] = "u_MetallicRoughnessValues",
         normal_scale: f32 = "u_NormalScale",
         occlusion_strength: f32 = "u_OcclusionStrength",
@@ -237,11 +384,25 @@ gfx_defines! {
 
         occlusion_map: gfx::TextureSampler<[f32; 4]> = "u_OcclusionSampler",
 
-        color_target: gfx::RenderTarget<ColorFormat> = "Target0",
+        reflection_map: gfx::TextureSampler<[f32; 4]> = "t_Reflection",
+
+        color_target: gfx::RawRenderTarget = color_output(gfx::state::ColorMask::all(), None),
         depth_target: gfx::DepthTarget<DepthFormat> = gfx::preset::depth::LESS_EQUAL_WRITE,
     }
 }
 
+impl From<[f32; 16]> for UserParams {
+    fn from(params: [f32; 16]) -> Self {
+        let mut columns = [[0.0; 4]; 4];
+        for (column, chunk) in columns.iter_mut().zip(params.chunks(4)) {
+            column.copy_from_slice(chunk);
+        }
+        UserParams { params: columns }
+    }
+}
+
+/// Identifies the meshes drawn in one instanced batch. The material is the
+/// batch key of the mesh material, see `Material::batch_key`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub(crate) struct InstanceCacheKey {
     pub(crate) material: Material,
@@ -249,21 +410,47 @@ pub(crate) struct InstanceCacheKey {
 }
 
 impl Instance {
+    /// Computes the rows of the matrix transforming normals to world space,
+    /// i.e. the inverse transpose of the world matrix.
+    ///
+    /// The `w` component of the first row holds the handedness of the
+    /// transform: `-1.0` if it mirrors the geometry, `1.0` otherwise.
+    fn normal_matrix(mx_world: &mint::RowMatrix4<f32>) -> [[f32; 4]; 3] {
+        let mx = Matrix3::from_cols(
+            Vector3::new(mx_world.x.x, mx_world.y.x, mx_world.z.x),
+            Vector3::new(mx_world.x.y, mx_world.y.y, mx_world.z.y),
+            Vector3::new(mx_world.x.z, mx_world.y.z, mx_world.z.z),
+        );
+        let handedness = if mx.determinant() < 0.0 { -1.0 } else { 1.0 };
+        // rows of the inverse transpose are the columns of the inverse
+        let inverse = mx.invert().unwrap_or_else(Matrix3::identity);
+        [
+            inverse.x.extend(handedness).into(),
+            inverse.y.extend(0.0).into(),
+            inverse.z.extend(0.0).into(),
+        ]
+    }
+
     #[inline]
     fn basic(
         mx_world: mint::RowMatrix4<f32>,
         color: u32,
+        opacity: f32,
         uv_range: [f32; 4],
         param: f32,
     ) -> Self {
+        let normal = Self::normal_matrix(&mx_world);
         Instance {
             world0: mx_world.x.into(),
             world1: mx_world.y.into(),
             world2: mx_world.z.into(),
+            normal0: normal[0],
+            normal1: normal[1],
+            normal2: normal[2],
             color: {
                 // TODO: add alpha parameter for `to_linear_rgb`
                 let rgb = color::to_linear_rgb(color);
-                [rgb[0], rgb[1], rgb[2], 0.0]
+                [rgb[0], rgb[1], rgb[2], opacity]
             },
             mat_params: [param, 0.0, 0.0, 0.0],
             uv_range,
@@ -272,15 +459,37 @@ impl Instance {
 
     #[inline]
     fn pbr(mx_world: mint::RowMatrix4<f32>) -> Self {
+        let normal = Self::normal_matrix(&mx_world);
         Instance {
             world0: mx_world.x.into(),
             world1: mx_world.y.into(),
             world2: mx_world.z.into(),
-            color: [0.0; 4],
+            normal0: normal[0],
+            normal1: normal[1],
+            normal2: normal[2],
+            // multiplies the base color
+            color: [1.0; 4],
             mat_params: [0.0; 4],
             uv_range: [0.0; 4],
         }
     }
+
+    /// Returns a copy with the world matrix replaced by `mx_world`.
+    fn moved(
+        &self,
+        mx_world: mint::RowMatrix4<f32>,
+    ) -> Self {
+        let normal = Self::normal_matrix(&mx_world);
+        Instance {
+            world0: mx_world.x.into(),
+            world1: mx_world.y.into(),
+            world2: mx_world.z.into(),
+            normal0: normal[0],
+            normal1: normal[1],
+            normal2: normal[2],
+            .. *self
+        }
+    }
 }
 
 impl DisplacementContribution {
@@ -302,6 +511,140 @@ pub(crate) struct GpuData {
     pub pending: Option<DynamicData>,
     pub instance_cache_key: Option<InstanceCacheKey>,
     pub displacement_contributions: Vec<DisplacementContribution>,
+    pub triangles: Option<Arc<Triangles>>,
+    /// Set when the vertices are the segments of a line strip expanded into
+    /// quads, which `material::Line` draws in any width.
+    pub line_quads: bool,
+    /// The vertex attributes provided by the geometry, as opposed to the
+    /// defaults filled in for the missing ones.
+    pub attributes: VertexAttributes,
+    /// Copies drawn in one call by `InstancedMesh`, instead of the mesh
+    /// itself. The `instances` buffer holds as many as it can draw.
+    pub instance_list: Option<InstanceList>,
+}
+
+impl GpuData {
+    /// Bounds of what the mesh draws, relative to the mesh, and the radius
+    /// of the sphere around their center. Instanced meshes are bounded by
+    /// all of their instances.
+    pub fn bounds(&self) -> Option<(Aabb, f32)> {
+        match self.instance_list {
+            Some(ref list) => list.bounds,
+            None => self.triangles.as_ref().map(|triangles| (triangles.bounds, triangles.radius)),
+        }
+    }
+}
+
+/// The instances of an `InstancedMesh`.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct InstanceList {
+    /// Transforms of the instances relative to the mesh.
+    pub transforms: Vec<mint::ColumnMatrix4<f32>>,
+    /// Colors replacing the material color, for the first instances.
+    pub colors: Vec<color::Color>,
+    /// Turns every instance to face the camera, keeping its position and
+    /// the scale along its X axis.
+    pub billboard: bool,
+    /// Bounds of all the instances relative to the mesh, and the radius of
+    /// the sphere around their center, updated with the transforms.
+    pub bounds: Option<(Aabb, f32)>,
+}
+
+impl InstanceList {
+    /// Recomputes `bounds` from the bounds of a single copy, `copy`.
+    /// Billboards may face any direction, so each of them is bounded by a
+    /// cube around its position.
+    pub fn update_bounds(
+        &mut self,
+        copy: Option<&Arc<Triangles>>,
+    ) {
+        let copy = match copy {
+            Some(copy) => copy,
+            None => return,
+        };
+        let mut bounds = Aabb::empty();
+        if !copy.bounds.is_empty() {
+            let reach = copy.bounds.center().magnitude() + copy.radius;
+            for &mx in &self.transforms {
+                let mx = Matrix4::from(mx);
+                if self.billboard {
+                    let offset = Vector3::from_value(reach * mx.x.truncate().magnitude());
+                    let position = mx.w.truncate();
+                    bounds.extend(position - offset);
+                    bounds.extend(position + offset);
+                } else {
+                    for &corner in copy.bounds.corners().iter() {
+                        bounds.extend(mx.transform_point(Point3::from_vec(corner)).to_vec());
+                    }
+                }
+            }
+        }
+        let radius = if bounds.is_empty() {
+            0.0
+        } else {
+            (bounds.max - bounds.min).magnitude() * 0.5
+        };
+        self.bounds = Some((bounds, radius));
+    }
+
+
+    /// Copies `template` for every instance, moved from the transform of the
+    /// mesh, `mx_node`, by the transform of the instance. Billboards are
+    /// turned to `view_rotation`.
+    fn place(
+        &self,
+        template: &Instance,
+        mx_node: Matrix4<f32>,
+        view_rotation: Quaternion<f32>,
+    ) -> Vec<Instance> {
+        self.transforms
+            .iter()
+            .map(|&mx| {
+                let mut mx_world = mx_node * Matrix4::from(mx);
+                if self.billboard {
+                    let scale = mx_world.x.truncate().magnitude();
+                    mx_world = Matrix4 {
+                        w: mx_world.w,
+                        .. Matrix4::from(Matrix3::from(view_rotation) * scale)
+                    };
+                }
+                let mx_world: mint::ColumnMatrix4<_> = mx_world.into();
+                template.moved(mx_world.into())
+            })
+            .collect()
+    }
+
+    /// Replaces the colors of `instances` by the colors of the instances.
+    fn tint(
+        &self,
+        instances: &mut [Instance],
+    ) {
+        for (instance, &color) in instances.iter_mut().zip(&self.colors) {
+            let [r, g, b] = color::to_linear_rgb(color);
+            instance.color = [r, g, b, instance.color[3]];
+        }
+    }
+}
+
+/// Vertex attributes a geometry provided when it was uploaded.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct VertexAttributes {
+    pub normals: bool,
+    pub tex_coords: bool,
+    pub joints: bool,
+}
+
+impl VertexAttributes {
+    /// Attributes of quads and line strips, which the engine generates in full.
+    pub const GENERATED: Self = VertexAttributes { normals: true, tex_coords: true, joints: false };
+
+    pub fn of(geometry: &Geometry) -> Self {
+        VertexAttributes {
+            normals: !geometry.base.normals.is_empty(),
+            tex_coords: !geometry.tex_coords.is_empty(),
+            joints: !geometry.joints.indices.is_empty(),
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -309,6 +652,8 @@ struct InstanceData {
     slice: gfx::Slice<back::Resources>,
     vertices: h::Buffer<back::Resources, Vertex>,
     material: Material,
+    mirrored: bool,
+    line_quads: bool,
     list: Vec<Instance>,
 }
 
@@ -318,6 +663,70 @@ pub(crate) struct DynamicData {
     pub buffer: h::Buffer<back::Resources, Vertex>,
 }
 
+/// Far distance assumed by logarithmic depth for infinite projections.
+const LOGARITHMIC_DEPTH_FAR: f32 = 1.0e9;
+
+/// Number of viewport targets kept between frames, enough for split-screen
+/// with four players.
+const MAX_VIEWPORT_TARGETS: usize = 4;
+
+/// How the depth of the scene is stored in the depth buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DepthMode {
+    /// Standard perspective depth, which is most precise near the camera.
+    Standard,
+    /// Logarithmic depth, which keeps a stable precision over depth ranges
+    /// of many orders of magnitude, such as in flight or space scenes.
+    ///
+    /// Only applies to perspective cameras and the built-in materials.
+    /// Custom basic pipelines follow it by passing their positions through
+    /// `encode_depth` from `#include <depth>`, and the depth of UI text is
+    /// converted when rendering the scene. The depth is only exact at
+    /// vertices, so very large triangles close to the camera may still
+    /// intersect incorrectly.
+    Logarithmic,
+}
+
+/// Converts a standard perspective depth in normalized device coordinates
+/// into the logarithmic depth that `encode_depth` writes in the shaders.
+fn logarithmic_depth(
+    zrange: &ZRange,
+    scale: f32,
+    depth: f32,
+) -> f32 {
+    // recover the view distance from the standard depth
+    let distance = match *zrange {
+        ZRange::Finite(ref range) => {
+            2.0 * range.start * range.end / (range.end + range.start - depth * (range.end - range.start))
+        }
+        ZRange::Infinite(ref range) => 2.0 * range.start / (1.0 - depth),
+    };
+    (1.0 + distance).max(1e-6).log2() * scale - 1.0
+}
+
+/// How the colors of an HDR frame are brought into the range of the
+/// display, see [`Renderer::set_hdr`](struct.Renderer.html#method.set_hdr).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ToneMapOperator {
+    /// Colors above 1 are clipped, like without HDR.
+    Clamp,
+    /// Compresses bright colors smoothly, desaturating the highlights.
+    Reinhard,
+    /// Filmic curve fitted to ACES, with more contrast in the mid tones.
+    Aces,
+}
+
+impl ToneMapOperator {
+    /// The value selecting the operator in the tone mapping shaders.
+    pub(crate) fn shader_index(&self) -> f32 {
+        match *self {
+            ToneMapOperator::Clamp => 0.0,
+            ToneMapOperator::Reinhard => 1.0,
+            ToneMapOperator::Aces => 2.0,
+        }
+    }
+}
+
 /// Shadow type is used to specify shadow's rendering algorithm.
 pub enum ShadowType {
     /// Force no shadows.
@@ -334,6 +743,78 @@ struct DebugQuad {
     size: [i32; 2],
 }
 
+/// The built-in pipelines based on `basic_pipe`, which have variants for
+/// custom stencil state and mirrored transforms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum BasicKind {
+    Fill,
+    Line,
+    LineQuads,
+    Wireframe,
+    Gouraud,
+    Phong,
+    Sprite,
+}
+
+impl BasicKind {
+    fn of(
+        material: &Material,
+        line_quads: bool,
+    ) -> Option<Self> {
+        match *material {
+            Material::Basic(_) => Some(BasicKind::Fill),
+            Material::Line(_) if line_quads => Some(BasicKind::LineQuads),
+            Material::Line(_) => Some(BasicKind::Line),
+            Material::Wireframe(_) => Some(BasicKind::Wireframe),
+            Material::Lambert(_) => Some(BasicKind::Gouraud),
+            Material::Phong(_) => Some(BasicKind::Phong),
+            Material::Sprite(_) => Some(BasicKind::Sprite),
+            _ => None,
+        }
+    }
+
+    fn primitive(self) -> gfx::Primitive {
+        match self {
+            BasicKind::Line => gfx::Primitive::LineStrip,
+            BasicKind::Sprite => gfx::Primitive::TriangleStrip,
+            _ => gfx::Primitive::TriangleList,
+        }
+    }
+
+    fn rasterizer(self) -> gfx::state::Rasterizer {
+        let rast_fill = gfx::state::Rasterizer {
+            samples: Some(gfx::state::MultiSample),
+            ..gfx::state::Rasterizer::new_fill()
+        }.with_cull_back();
+        match self {
+            BasicKind::Wireframe => gfx::state::Rasterizer {
+                method: gfx::state::RasterMethod::Line(1),
+                ..rast_fill
+            },
+            // the quads of a line face either way depending on the view
+            BasicKind::LineQuads => gfx::state::Rasterizer {
+                cull_face: gfx::state::CullFace::Nothing,
+                ..rast_fill
+            },
+            _ => rast_fill,
+        }
+    }
+}
+
+/// Flips the winding of front faces, for drawing objects whose transform
+/// mirrors them inside-out.
+fn mirrored(rasterizer: gfx::state::Rasterizer) -> gfx::state::Rasterizer {
+    gfx::state::Rasterizer {
+        front_face: gfx::state::FrontFace::Clockwise,
+        ..rasterizer
+    }
+}
+
+/// Identifies a variant of a basic pipeline by its stencil state, whether it
+/// draws mirrored objects, its shader features with the bits of the alpha
+/// cutoff, whether it writes depth and whether it blends.
+type VariantKey = (BasicKind, gfx::state::Stencil, bool, ShaderFeatures, u32, bool, bool);
+
 /// All pipeline state objects used by the `three` renderer.
 pub struct PipelineStates<R: gfx::Resources> {
     /// Corresponds to `Material::Basic`.
@@ -342,6 +823,9 @@ pub struct PipelineStates<R: gfx::Resources> {
     /// Corresponds to `Material::Line`.
     line_basic: gfx::PipelineState<R, basic_pipe::Meta>,
 
+    /// Corresponds to `Material::Line` on meshes with `GpuData::line_quads`.
+    line_quads: gfx::PipelineState<R, basic_pipe::Meta>,
+
     /// Corresponds to `Material::Wireframe`.
     mesh_basic_wireframe: gfx::PipelineState<R, basic_pipe::Meta>,
 
@@ -357,14 +841,51 @@ pub struct PipelineStates<R: gfx::Resources> {
     /// Used internally for shadow casting.
     shadow: gfx::PipelineState<R, shadow_pipe::Meta>,
 
+    /// Used internally for shadow casting of mirrored objects.
+    shadow_mirrored: gfx::PipelineState<R, shadow_pipe::Meta>,
+
+    /// Used internally for picking objects.
+    pick: gfx::PipelineState<R, pick_pipe::Meta>,
+
+    /// Used internally for picking mirrored objects.
+    pick_mirrored: gfx::PipelineState<R, pick_pipe::Meta>,
+
     /// Used internally for rendering sprites.
     quad: gfx::PipelineState<R, quad_pipe::Meta>,
 
+    /// Used internally for rendering icons embedded in UI text.
+    icon: gfx::PipelineState<R, icon_pipe::Meta>,
+
+    /// Used internally for rendering the shapes of the `Hud`.
+    hud: gfx::PipelineState<R, hud_pipe::Meta>,
+
     /// Corresponds to `Material::Pbr`.
     pbr: gfx::PipelineState<R, pbr_pipe::Meta>,
 
+    /// Corresponds to `Material::Pbr` on mirrored objects.
+    pbr_mirrored: gfx::PipelineState<R, pbr_pipe::Meta>,
+
+    /// Corresponds to `Material::Pbr` on faded objects.
+    pbr_blend: gfx::PipelineState<R, pbr_pipe::Meta>,
+
+    /// Corresponds to `Material::Pbr` on faded mirrored objects.
+    pbr_blend_mirrored: gfx::PipelineState<R, pbr_pipe::Meta>,
+
     /// Used internally for rendering `Background::Skybox`.
     skybox: gfx::PipelineState<R, quad_pipe::Meta>,
+
+    /// Programs of the basic, Gouraud, Phong, sprite and line pipelines.
+    basic_programs: [gfx::ShaderSet<R>; 5],
+
+    /// Vertex and pixel shader sources of `basic_programs`.
+    basic_sources: [(source::Source, source::Source); 5],
+
+    /// Programs of `basic_programs` compiled with shader features, by index,
+    /// features and the bits of the alpha cutoff, built on demand.
+    feature_programs: HashMap<(usize, ShaderFeatures, u32), gfx::ShaderSet<R>>,
+
+    /// Variants of the basic pipelines, built on demand.
+    variants: HashMap<VariantKey, gfx::PipelineState<R, basic_pipe::Meta>>,
 }
 
 impl PipelineStates<back::Resources> {
@@ -379,10 +900,19 @@ impl PipelineStates<back::Resources> {
     pub(crate) fn pso_by_material<'a>(
         &'a self,
         material: &'a Material,
+        mirrored: bool,
+        line_quads: bool,
+        faded: bool,
     ) -> &'a BasicPipelineState {
+        if let Some(key) = Self::variant_key(material, mirrored, line_quads, faded) {
+            if let Some(pso) = self.variants.get(&key) {
+                return pso;
+            }
+        }
         match *material {
             Material::Basic(_) => &self.mesh_basic_fill,
             Material::CustomBasic(ref b) => &b.pipeline,
+            Material::Line(_) if line_quads => &self.line_quads,
             Material::Line(_) => &self.line_basic,
             Material::Wireframe(_) => &self.mesh_basic_wireframe,
             Material::Lambert(_) => &self.mesh_gouraud,
@@ -391,6 +921,111 @@ impl PipelineStates<back::Resources> {
             _ => unreachable!(),
         }
     }
+
+    /// The key of the pipeline variant needed by `material`, or `None` if
+    /// the default pipeline applies.
+    ///
+    /// Objects `faded` by their opacity are blended and don't write depth,
+    /// whatever their material.
+    fn variant_key(
+        material: &Material,
+        mirrored: bool,
+        line_quads: bool,
+        faded: bool,
+    ) -> Option<VariantKey> {
+        let kind = BasicKind::of(material, line_quads)?;
+        let stencil = material.stencil();
+        let features = material.features();
+        let cutoff = material.alpha_cutoff();
+        let depth_write = material.depth_write() && !faded;
+        let blend = faded || material.transparency() == Transparency::Blend;
+        if stencil.is_none() && !mirrored && features.is_empty() && depth_write && !blend {
+            return None;
+        }
+        let state = match stencil {
+            Some(stencil) => stencil.state,
+            None => gfx::state::Stencil {
+                front: STENCIL_SIDE,
+                back: STENCIL_SIDE,
+            },
+        };
+        // the cutoff only matters to the alpha test
+        let cutoff = if features.contains(ShaderFeatures::ALPHA_TEST) { cutoff } else { 0.5 };
+        Some((kind, state, mirrored, features, cutoff.to_bits(), depth_write, blend))
+    }
+
+    /// Builds the pipeline variant needed by `material`, unless it already
+    /// exists. Failures leave the default pipeline in use.
+    pub(crate) fn prepare_variant(
+        &mut self,
+        backend: &mut back::Factory,
+        material: &Material,
+        mirrored: bool,
+        line_quads: bool,
+        faded: bool,
+    ) -> Result<(), Diagnostic> {
+        let key = match Self::variant_key(material, mirrored, line_quads, faded) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        if self.variants.contains_key(&key) {
+            return Ok(());
+        }
+        let (kind, stencil, mirrored, features, cutoff, depth_write, blend) = key;
+        let index = match kind {
+            BasicKind::Fill | BasicKind::Line | BasicKind::Wireframe => 0,
+            BasicKind::Gouraud => 1,
+            BasicKind::Phong => 2,
+            BasicKind::Sprite => 3,
+            BasicKind::LineQuads => 4,
+        };
+        if !features.is_empty() && !self.feature_programs.contains_key(&(index, features, cutoff)) {
+            let (ref vs, ref ps) = self.basic_sources[index];
+            let mut defines = features.defines();
+            if features.contains(ShaderFeatures::ALPHA_TEST) {
+                defines.push_str(&format!("#define ALPHA_CUTOFF {:?}\n", f32::from_bits(cutoff)));
+            }
+            let vs = vs.with_defines(&defines);
+            let ps = ps.with_defines(&defines);
+            match backend.create_shader_set(&vs, &ps) {
+                Ok(program) => {
+                    self.feature_programs.insert((index, features, cutoff), program);
+                }
+                Err(err) => return Err(Diagnostic::ShaderFeatures(features, err.to_string())),
+            }
+        }
+        let program = if features.is_empty() {
+            &self.basic_programs[index]
+        } else {
+            &self.feature_programs[&(index, features, cutoff)]
+        };
+        let rasterizer = if mirrored {
+            self::mirrored(kind.rasterizer())
+        } else {
+            kind.rasterizer()
+        };
+        let depth = if depth_write {
+            gfx::preset::depth::LESS_EQUAL_WRITE
+        } else {
+            gfx::preset::depth::LESS_EQUAL_TEST
+        };
+        // sprites always blend
+        let blend = if blend || kind == BasicKind::Sprite {
+            gfx::preset::blend::ALPHA
+        } else {
+            gfx::preset::blend::REPLACE
+        };
+        let init = basic_pipe::Init {
+            out_color: color_output(gfx::state::ColorMask::all(), Some(blend)),
+            out_depth: (depth, stencil),
+            ..basic_pipe::new()
+        };
+        let pso = backend
+            .create_pipeline_state(program, kind.primitive(), rasterizer, init)
+            .map_err(|err| Diagnostic::PipelineVariant(format!("{:?}", err)))?;
+        self.variants.insert(key, pso);
+        Ok(())
+    }
 }
 
 impl<R: gfx::Resources> PipelineStates<R> {
@@ -403,8 +1038,12 @@ impl<R: gfx::Resources> PipelineStates<R> {
         let gouraud = backend.create_shader_set(&src.gouraud.vs, &src.gouraud.ps)?;
         let phong = backend.create_shader_set(&src.phong.vs, &src.phong.ps)?;
         let sprite = backend.create_shader_set(&src.sprite.vs, &src.sprite.ps)?;
+        let line = backend.create_shader_set(&src.line.vs, &src.line.ps)?;
         let shadow = backend.create_shader_set(&src.shadow.vs, &src.shadow.ps)?;
+        let pick = backend.create_shader_set(&src.pick.vs, &src.pick.ps)?;
         let quad = backend.create_shader_set(&src.quad.vs, &src.quad.ps)?;
+        let icon = backend.create_shader_set(&src.icon.vs, &src.icon.ps)?;
+        let hud = backend.create_shader_set(&src.hud.vs, &src.hud.ps)?;
         let pbr = backend.create_shader_set(&src.pbr.vs, &src.pbr.ps)?;
         let skybox = backend.create_shader_set(&src.skybox.vs, &src.skybox.ps)?;
 
@@ -434,6 +1073,12 @@ impl<R: gfx::Resources> PipelineStates<R> {
             rast_fill,
             basic_pipe::new(),
         )?;
+        let pso_line_quads = backend.create_pipeline_state(
+            &line,
+            gfx::Primitive::TriangleList,
+            BasicKind::LineQuads.rasterizer(),
+            basic_pipe::new(),
+        )?;
         let pso_mesh_basic_wireframe = backend.create_pipeline_state(
             &basic,
             gfx::Primitive::TriangleList,
@@ -457,7 +1102,7 @@ impl<R: gfx::Resources> PipelineStates<R> {
             gfx::Primitive::TriangleStrip,
             rast_fill,
             basic_pipe::Init {
-                out_color: ("Target0", gfx::state::ColorMask::all(), gfx::preset::blend::ALPHA),
+                out_color: color_output(gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)),
                 ..basic_pipe::new()
             },
         )?;
@@ -467,12 +1112,42 @@ impl<R: gfx::Resources> PipelineStates<R> {
             rast_shadow,
             shadow_pipe::new(),
         )?;
+        let pso_shadow_mirrored = backend.create_pipeline_state(
+            &shadow,
+            gfx::Primitive::TriangleList,
+            mirrored(rast_shadow),
+            shadow_pipe::new(),
+        )?;
+        let pso_pick = backend.create_pipeline_state(
+            &pick,
+            gfx::Primitive::TriangleList,
+            rast_fill,
+            pick_pipe::new(),
+        )?;
+        let pso_pick_mirrored = backend.create_pipeline_state(
+            &pick,
+            gfx::Primitive::TriangleList,
+            mirrored(rast_fill),
+            pick_pipe::new(),
+        )?;
         let pso_quad = backend.create_pipeline_state(
             &quad,
             gfx::Primitive::TriangleStrip,
             rast_quad,
             quad_pipe::new(),
         )?;
+        let pso_icon = backend.create_pipeline_state(
+            &icon,
+            gfx::Primitive::TriangleStrip,
+            rast_quad,
+            icon_pipe::new(),
+        )?;
+        let pso_hud = backend.create_pipeline_state(
+            &hud,
+            gfx::Primitive::TriangleList,
+            rast_quad,
+            hud_pipe::new(),
+        )?;
         let pso_skybox = backend.create_pipeline_state(
             &skybox,
             gfx::Primitive::TriangleStrip,
@@ -485,81 +1160,589 @@ impl<R: gfx::Resources> PipelineStates<R> {
             rast_fill,
             pbr_pipe::new(),
         )?;
+        let pso_pbr_mirrored = backend.create_pipeline_state(
+            &pbr,
+            gfx::Primitive::TriangleList,
+            mirrored(rast_fill),
+            pbr_pipe::new(),
+        )?;
+        let pbr_blend = || pbr_pipe::Init {
+            color_target: color_output(gfx::state::ColorMask::all(), Some(gfx::preset::blend::ALPHA)),
+            depth_target: gfx::preset::depth::LESS_EQUAL_TEST,
+            ..pbr_pipe::new()
+        };
+        let pso_pbr_blend = backend.create_pipeline_state(
+            &pbr,
+            gfx::Primitive::TriangleList,
+            rast_fill,
+            pbr_blend(),
+        )?;
+        let pso_pbr_blend_mirrored = backend.create_pipeline_state(
+            &pbr,
+            gfx::Primitive::TriangleList,
+            mirrored(rast_fill),
+            pbr_blend(),
+        )?;
 
         Ok(PipelineStates {
             mesh_basic_fill: pso_mesh_basic_fill,
             line_basic: pso_line_basic,
+            line_quads: pso_line_quads,
             mesh_basic_wireframe: pso_mesh_basic_wireframe,
             mesh_gouraud: pso_mesh_gouraud,
             mesh_phong: pso_mesh_phong,
             sprite: pso_sprite,
             shadow: pso_shadow,
+            shadow_mirrored: pso_shadow_mirrored,
+            pick: pso_pick,
+            pick_mirrored: pso_pick_mirrored,
             quad: pso_quad,
+            icon: pso_icon,
+            hud: pso_hud,
             pbr: pso_pbr,
+            pbr_mirrored: pso_pbr_mirrored,
+            pbr_blend: pso_pbr_blend,
+            pbr_blend_mirrored: pso_pbr_blend_mirrored,
             skybox: pso_skybox,
+            basic_programs: [basic, gouraud, phong, sprite, line],
+            basic_sources: [
+                (src.basic.vs.clone(), src.basic.ps.clone()),
+                (src.gouraud.vs.clone(), src.gouraud.ps.clone()),
+                (src.phong.vs.clone(), src.phong.ps.clone()),
+                (src.sprite.vs.clone(), src.sprite.ps.clone()),
+                (src.line.vs.clone(), src.line.ps.clone()),
+            ],
+            feature_programs: HashMap::new(),
+            variants: HashMap::new(),
         })
     }
 }
 
-/// Handle for additional viewport to render some relevant debug information.
-/// See [`Renderer::debug_shadow_quad`](struct.Renderer.html#method.debug_shadow_quad).
+/// Information about an object about to be drawn, passed to the visibility callback.
+///
+/// See [`Renderer::set_visibility_fn`](struct.Renderer.html#method.set_visibility_fn).
+#[derive(Clone, Debug)]
+pub struct ObjectInfo<'a> {
+    /// The object itself.
+    pub object: Base,
+    /// The name of the object, if any.
+    pub name: Option<&'a str>,
+    /// Transformation of the object in world space.
+    pub world_transform: Transform,
+}
+
+type VisibilityFn = Box<dyn FnMut(&ObjectInfo) -> bool>;
+type DiagnosticsFn = Box<dyn FnMut(&Diagnostic)>;
+
+/// The textures of the scene sampled by lit materials.
+///
+/// Shadow maps are by shadow index: flat maps of directional lights and cube
+/// maps of point lights. Unused slots hold maps without shadows. The
+/// cascades are the maps of the light with cascaded shadows, if any. The
+/// reflection cubemap is black when the scene has no reflection probe. The
+/// flat normal map stands in for the missing normal maps of materials, and
+/// the empty buffer for the missing data buffers of custom materials.
+struct SceneTextures {
+    sampler: h::Sampler<back::Resources>,
+    flat: [h::ShaderResourceView<back::Resources, f32>; 2],
+    cube: [h::ShaderResourceView<back::Resources, f32>; 2],
+    cascades: [h::ShaderResourceView<back::Resources, f32>; MAX_SHADOW_CASCADES],
+    reflection: CubeMap<[f32; 4]>,
+    flat_normal: Texture<[f32; 4]>,
+    empty_buffer: h::ShaderResourceView<back::Resources, [f32; 4]>,
+}
+
+/// A buffer of `vec4` values read by custom shaders as a buffer texture,
+/// for per-object data too large for uniforms, such as the frames of
+/// vertex animation textures.
+///
+/// Created with [`Factory::data_buffer`], updated with
+/// [`Factory::update_data_buffer`] and bound to a pipeline as the `data` of
+/// a [`Custom`] material, where shaders read it with
+/// `texelFetch(b_UserData, index)` from `uniform samplerBuffer b_UserData`.
+///
+/// [`Factory::data_buffer`]: ../struct.Factory.html#method.data_buffer
+/// [`Factory::update_data_buffer`]: ../struct.Factory.html#method.update_data_buffer
+/// [`Custom`]: ../material/basic/struct.Custom.html
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct DebugQuadHandle(froggy::Pointer<DebugQuad>);
+pub struct DataBuffer {
+    pub(crate) buffer: h::Buffer<back::Resources, [f32; 4]>,
+    pub(crate) view: h::ShaderResourceView<back::Resources, [f32; 4]>,
+    pub(crate) upload: h::Buffer<back::Resources, [f32; 4]>,
+}
 
-/// Renders [`Scene`](struct.Scene.html) by [`Camera`](struct.Camera.html).
+impl DataBuffer {
+    /// The number of `vec4` values in the buffer.
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+    /// Whether the buffer holds no values.
+    pub fn is_empty(&self) -> bool {
+        self.buffer.len() == 0
+    }
+}
+
+/// A range of a [`DataBuffer`] written by the factory, copied to the GPU
+/// at the next render.
+#[derive(Clone, Debug)]
+pub(crate) struct DataCopy {
+    pub buffer: DataBuffer,
+    pub offset: usize,
+    pub count: usize,
+}
+
+/// An offscreen color and depth buffer that scenes can be rendered into.
 ///
-/// See [Window::render](struct.Window.html#method.render).
-pub struct Renderer {
-    device: back::Device,
-    encoder: gfx::Encoder<back::Resources, back::CommandBuffer>,
-    factory: back::Factory,
-    const_buf: h::Buffer<back::Resources, Globals>,
-    quad_buf: h::Buffer<back::Resources, QuadParams>,
-    inst_buf: h::Buffer<back::Resources, Instance>,
-    light_buf: h::Buffer<back::Resources, LightParam>,
-    pbr_buf: h::Buffer<back::Resources, PbrParams>,
-    out_color: h::RenderTargetView<back::Resources, ColorFormat>,
-    out_depth: h::DepthStencilView<back::Resources, DepthFormat>,
-    displacement_contributions_buf: gfx::handle::Buffer<back::Resources, DisplacementContribution>,
-    default_joint_buffer_view: gfx::handle::ShaderResourceView<back::Resources, [f32; 4]>,
-    default_displacement_buffer_view: gfx::handle::ShaderResourceView<back::Resources, [f32; 4]>,
-    pso: PipelineStates<back::Resources>,
-    map_default: Texture<[f32; 4]>,
-    shadow_default: Texture<f32>,
-    debug_quads: froggy::Storage<DebugQuad>,
-    size: glutin::dpi::LogicalSize,
-    dpi: f64,
-    font_cache: HashMap<String, Font>,
-    instance_cache: HashMap<InstanceCacheKey, InstanceData>,
-    /// `ShadowType` of this `Renderer`.
-    pub shadow: ShadowType,
+/// Created with [`Factory::render_target`] and rendered to with
+/// [`Renderer::render_to`]. The result can be used as a regular texture,
+/// for example as the `map` of a material.
+///
+/// [`Factory::render_target`]: ../struct.Factory.html#method.render_target
+/// [`Renderer::render_to`]: struct.Renderer.html#method.render_to
+#[derive(Clone, Debug)]
+pub struct RenderTarget {
+    pub(crate) raw: h::RawTexture<back::Resources>,
+    pub(crate) color: h::RawRenderTargetView<back::Resources>,
+    pub(crate) format: gfx::format::Format,
+    pub(crate) depth: h::DepthStencilView<back::Resources, DepthFormat>,
+    pub(crate) texture: Texture<[f32; 4]>,
+    pub(crate) size: [u16; 2],
 }
 
-impl Renderer {
-    #[cfg(feature = "opengl")]
+impl RenderTarget {
     pub(crate) fn new(
-        builder: glutin::WindowBuilder,
-        context: glutin::ContextBuilder<NotCurrent>,
+        factory: &mut back::Factory,
+        sampler: h::Sampler<back::Resources>,
+        width: u16,
+        height: u16,
+    ) -> Self {
+        use gfx::texture as t;
+
+        // like `create_render_target`, but copyable for reading it back
+        let raw = factory
+            .create_texture::<<ColorFormat as Formatted>::Surface>(
+                t::Kind::D2(width, height, t::AaMode::Single),
+                1,
+                gfx::memory::Bind::SHADER_RESOURCE | gfx::memory::Bind::RENDER_TARGET | gfx::memory::Bind::TRANSFER_SRC,
+                gfx::memory::Usage::Data,
+                Some(gfx::format::ChannelType::Unorm),
+            )
+            .unwrap();
+        let resource = factory
+            .view_texture_as_shader_resource::<ColorFormat>(&raw, (0, 0), gfx::format::Swizzle::new())
+            .unwrap();
+        let color = factory
+            .view_texture_as_render_target::<ColorFormat>(&raw, 0, None)
+            .unwrap();
+        let (_, _, depth) = factory
+            .create_depth_stencil::<DepthFormat>(width, height)
+            .unwrap();
+        RenderTarget {
+            raw: raw.raw().clone(),
+            color: color.raw().clone(),
+            format: ColorFormat::get_format(),
+            depth,
+            texture: Texture::new(resource, sampler, [width as u32, height as u32]),
+            size: [width, height],
+        }
+    }
+
+    /// Creates a target with half float colors, which hold values above 1
+    /// for tone mapping. Not meant to be read back.
+    fn new_hdr(
+        factory: &mut back::Factory,
+        sampler: h::Sampler<back::Resources>,
+        width: u16,
+        height: u16,
+    ) -> Self {
+        let (raw, resource, color) = factory
+            .create_render_target::<HdrColorFormat>(width, height)
+            .unwrap();
+        let (_, _, depth) = factory
+            .create_depth_stencil::<DepthFormat>(width, height)
+            .unwrap();
+        RenderTarget {
+            raw: raw.raw().clone(),
+            color: color.raw().clone(),
+            format: HdrColorFormat::get_format(),
+            depth,
+            texture: Texture::new(resource, sampler, [width as u32, height as u32]),
+            size: [width, height],
+        }
+    }
+
+    /// The texture holding the rendered colors.
+    pub fn texture(&self) -> &Texture<[f32; 4]> {
+        &self.texture
+    }
+
+    /// Size of the target in pixels.
+    pub fn size(&self) -> [u16; 2] {
+        self.size
+    }
+}
+
+/// Details of the graphics device, returned by [`Renderer::device_info`].
+///
+/// [`Renderer::device_info`]: struct.Renderer.html#method.device_info
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceInfo {
+    /// The company responsible for the driver.
+    pub vendor: String,
+    /// The name of the GPU or of the software renderer.
+    pub renderer: String,
+    /// The OpenGL version, e.g. `"3.3"` or `"ES 3.0"`.
+    pub version: String,
+    /// The GLSL version, in the same format as `version`.
+    pub shading_language: String,
+    /// The largest supported width and height of textures, in pixels.
+    pub max_texture_size: usize,
+    /// The largest supported number of multisampling samples, or 0 if unknown.
+    pub max_samples: u32,
+    /// The number of samples of the window, which may differ from the one
+    /// requested with [`Builder::multisampling`], or 0 if it isn't
+    /// multisampled.
+    ///
+    /// [`Builder::multisampling`]: ../window/struct.Builder.html#method.multisampling
+    pub samples: u32,
+    /// Whether instances can be drawn from an offset into their buffer.
+    pub instance_base_supported: bool,
+    /// Whether rendering into sRGB color buffers is supported.
+    pub srgb_color_supported: bool,
+    /// Whether shaders can read and write buffers and textures directly.
+    pub unordered_access_view_supported: bool,
+    /// The names of the supported OpenGL extensions, sorted.
+    pub extensions: Vec<String>,
+}
+
+impl DeviceInfo {
+    fn new(
+        device: &back::Device,
+        max_samples: u32,
+        samples: u32,
+    ) -> Self {
+        use gfx::Device;
+        let info = device.get_info();
+        let caps = device.get_capabilities();
+        let version = |v: &back::Version| {
+            format!("{}{}.{}", if v.is_embedded { "ES " } else { "" }, v.major, v.minor)
+        };
+        let mut extensions: Vec<String> = info.extensions.iter().map(|e| e.to_string()).collect();
+        extensions.sort();
+        DeviceInfo {
+            vendor: info.platform_name.vendor.to_string(),
+            renderer: info.platform_name.renderer.to_string(),
+            version: version(&info.version),
+            shading_language: version(&info.shading_language),
+            max_texture_size: caps.max_texture_size,
+            max_samples,
+            samples,
+            instance_base_supported: caps.instance_base_supported,
+            srgb_color_supported: caps.srgb_color_supported,
+            unordered_access_view_supported: caps.unordered_access_view_supported,
+            extensions,
+        }
+    }
+
+    /// Checks if the OpenGL extension `name` is supported.
+    pub fn has_extension(
+        &self,
+        name: &str,
+    ) -> bool {
+        self.extensions.binary_search_by(|e| e.as_str().cmp(name)).is_ok()
+    }
+}
+
+/// Single pixel target that object IDs are rendered into for picking.
+struct PickTarget {
+    texture: h::Texture<back::Resources, gfx::format::R8_G8_B8_A8>,
+    color: h::RenderTargetView<back::Resources, ColorFormat>,
+    depth: h::DepthStencilView<back::Resources, DepthFormat>,
+    download: h::Buffer<back::Resources, [u8; 4]>,
+}
+
+/// Number of download buffers asynchronous readbacks rotate through, so a
+/// request issued every frame never copies into the buffer of the previous
+/// one while the GPU may still be filling it.
+const READBACK_RING_SIZE: usize = 2;
+
+/// A download buffer that asynchronous readbacks copy into. It is in use
+/// while a request holds a clone of its token.
+struct ReadbackSlot {
+    buffer: h::Buffer<back::Resources, [u8; 4]>,
+    token: Rc<()>,
+    /// Signaled by the GPU once the copy into `buffer` is complete.
+    fence: Option<gl::types::GLsync>,
+}
+
+/// The part of a request identifying the download buffer.
+#[derive(Debug)]
+struct PendingReadback {
+    slot: usize,
+    _token: Rc<()>,
+}
+
+/// The state of an asynchronous readback from the GPU.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Readback<T> {
+    /// The GPU may still be working on the request, try again after the next
+    /// frame.
+    Pending,
+    /// The result of the request.
+    Ready(T),
+}
+
+/// A pick requested with [`Renderer::pick_async`].
+///
+/// [`Renderer::pick_async`]: struct.Renderer.html#method.pick_async
+#[derive(Debug)]
+pub struct PickRequest {
+    readback: PendingReadback,
+    objects: Vec<Base>,
+}
+
+/// A read of a render target requested with [`Renderer::read_target_async`].
+///
+/// [`Renderer::read_target_async`]: struct.Renderer.html#method.read_target_async
+#[derive(Debug)]
+pub struct ReadRequest {
+    readback: PendingReadback,
+    size: [u32; 2],
+}
+
+/// Handle for additional viewport to render some relevant debug information.
+/// See [`Renderer::debug_shadow_quad`](struct.Renderer.html#method.debug_shadow_quad).
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DebugQuadHandle(froggy::Pointer<DebugQuad>);
+
+/// Renders [`Scene`](struct.Scene.html) by [`Camera`](struct.Camera.html).
+///
+/// See [Window::render](struct.Window.html#method.render).
+pub struct Renderer {
+    device: back::Device,
+    encoder: gfx::Encoder<back::Resources, back::CommandBuffer>,
+    factory: back::Factory,
+    const_buf: h::Buffer<back::Resources, Globals>,
+    quad_buf: h::Buffer<back::Resources, QuadParams>,
+    icon_buf: h::Buffer<back::Resources, IconParams>,
+    post_buf: h::Buffer<back::Resources, PostParams>,
+    inst_buf: h::Buffer<back::Resources, Instance>,
+    hud_buf: h::Buffer<back::Resources, HudVertex>,
+    light_buf: h::Buffer<back::Resources, LightParam>,
+    pbr_buf: h::Buffer<back::Resources, PbrParams>,
+    user_buf: h::Buffer<back::Resources, UserParams>,
+    user_params: UserParams,
+    window_color: h::RenderTargetView<back::Resources, ColorFormat>,
+    out_color: h::RawRenderTargetView<back::Resources>,
+    out_format: gfx::format::Format,
+    out_depth: h::DepthStencilView<back::Resources, DepthFormat>,
+    displacement_contributions_buf: gfx::handle::Buffer<back::Resources, DisplacementContribution>,
+    default_joint_buffer_view: gfx::handle::ShaderResourceView<back::Resources, [f32; 4]>,
+    default_displacement_buffer_view: gfx::handle::ShaderResourceView<back::Resources, [f32; 4]>,
+    pso: PipelineStates<back::Resources>,
+    map_default: Texture<[f32; 4]>,
+    normal_default: Texture<[f32; 4]>,
+    shadow_default: Texture<f32>,
+    shadow_cube_default: h::ShaderResourceView<back::Resources, f32>,
+    reflection_default: CubeMap<[f32; 4]>,
+    debug_quads: froggy::Storage<DebugQuad>,
+    pick_target: PickTarget,
+    readbacks: Vec<ReadbackSlot>,
+    /// The slot of the ring the next readback starts looking from.
+    next_readback: usize,
+    size: glutin::dpi::LogicalSize,
+    dpi: f64,
+    font_cache: HashMap<String, Font>,
+    instance_cache: HashMap<(InstanceCacheKey, bool), InstanceData>,
+    visibility_fn: Option<VisibilityFn>,
+    diagnostics_fn: Option<DiagnosticsFn>,
+    /// Distinct diagnostics reported before a callback was set.
+    pending_diagnostics: Vec<Diagnostic>,
+    /// Set while rendering into a `RenderTarget`, to skip the window overlays.
+    offscreen: bool,
+    /// Set when the window is blended with the desktop behind it.
+    pub(crate) transparent: bool,
+    /// When set, only these nodes are drawn.
+    only_nodes: Option<HashSet<*const NodeInternal>>,
+    /// Set while rendering the glowing parts of a scene for bloom.
+    emissive_only: bool,
+    render_scale: f32,
+    frustum_culling: bool,
+    device_info: DeviceInfo,
+    /// Target the scene is drawn into when `render_scale` isn't 1 or with
+    /// HDR enabled.
+    scaled_target: Option<RenderTarget>,
+    hdr: bool,
+    exposure: f32,
+    tone_mapping: ToneMapOperator,
+    /// Tone maps HDR frames into the window, created when first needed.
+    hdr_pso: Option<PostPipelineState>,
+    /// Targets viewports were recently drawn into, most recent first.
+    viewport_targets: Vec<RenderTarget>,
+    /// `ShadowType` of this `Renderer`.
+    pub shadow: ShadowType,
+    /// Skips shadow casters outside of the light's projection, or further
+    /// from the camera than the light's shadow distance. Skinned and morphed
+    /// meshes are always drawn into shadow maps. Instanced meshes are
+    /// skipped when all of their instances are, and groups with [`bounds`]
+    /// with all of their children.
+    ///
+    /// [`bounds`]: ../object/struct.Group.html#method.set_bounds
+    ///
+    /// Default: `true`.
+    pub shadow_culling: bool,
+    /// `DepthMode` of this `Renderer`.
+    ///
+    /// Default: `DepthMode::Standard`.
+    pub depth: DepthMode,
+    /// Optional portal system used to cull objects in rooms hidden from the camera.
+    pub portals: Option<PortalSystem>,
+    /// 2D shapes drawn over the window by the next render, see the
+    /// [`hud`](../hud/index.html) module.
+    pub hud: Hud,
+}
+
+impl Renderer {
+    #[cfg(feature = "opengl")]
+    pub(crate) fn new(
+        builder: glutin::WindowBuilder,
+        context: glutin::ContextBuilder<NotCurrent>,
         event_loop: &glutin::EventsLoop,
         source: &source::Set,
     ) -> (Self, glutin::WindowedContext<PossiblyCurrent>, Factory) {
+        let mut pending_diagnostics = Vec::new();
+        let targets = match gfx_window_glutin::init(builder.clone(), context.clone(), event_loop) {
+            Err(err) if context.pf_reqs.multisampling.is_some() => {
+                // drivers without multisampled pixel formats still get a window
+                let diagnostic = Diagnostic::MultisamplingUnavailable(err.to_string());
+                warn!("{}", diagnostic);
+                pending_diagnostics.push(diagnostic);
+                gfx_window_glutin::init(builder, context.with_multisampling(0), event_loop)
+            }
+            result => result,
+        };
+        let (windowedContext, device, gl_factory, out_color, out_depth) = targets.unwrap();
+        let max_samples = Self::max_samples(|name| windowedContext.get_proc_address(name) as *const _);
+        let samples = windowedContext.get_pixel_format().multisampling.unwrap_or(0) as u32;
+        let (size, dpi) = {
+            let window = windowedContext.window();
+            (window.get_inner_size().unwrap(), window.get_hidpi_factor())
+        };
+        let (renderer, factory) = Self::with_targets(
+            device,
+            gl_factory,
+            (out_color, out_depth),
+            (samples, max_samples),
+            (size, dpi),
+            source,
+            pending_diagnostics,
+        );
+        (renderer, windowedContext, factory)
+    }
+
+    /// Creates a renderer drawing into offscreen targets of `size` pixels
+    /// instead of a window, with the OpenGL context current on this thread,
+    /// whose functions are loaded by `loader`.
+    #[cfg(feature = "opengl")]
+    pub(crate) fn headless<F>(
+        loader: F,
+        size: [u16; 2],
+        source: &source::Set,
+    ) -> (Self, Factory)
+    where
+        F: FnMut(&str) -> *const std::os::raw::c_void,
+    {
+        let (device, mut gl_factory) = back::create(loader);
+        let (_, _, out_color) = gl_factory
+            .create_render_target::<ColorFormat>(size[0], size[1])
+            .unwrap();
+        let (_, _, out_depth) = gl_factory
+            .create_depth_stencil::<DepthFormat>(size[0], size[1])
+            .unwrap();
+        let size = glutin::dpi::LogicalSize::new(size[0] as f64, size[1] as f64);
+        Self::with_targets(
+            device,
+            gl_factory,
+            (out_color, out_depth),
+            (0, 0),
+            (size, 1.0),
+            source,
+            Vec::new(),
+        )
+    }
+
+    /// Queries the largest number of samples of multisampled render targets
+    /// with the functions of `loader`.
+    #[cfg(feature = "opengl")]
+    fn max_samples<F>(mut loader: F) -> u32
+    where
+        F: FnMut(&str) -> *const std::os::raw::c_void,
+    {
+        const MAX_SAMPLES: u32 = 0x8D57;
+        let mut value = 0i32;
+        let get_integer = loader("glGetIntegerv");
+        let get_error = loader("glGetError");
+        if !get_integer.is_null() && !get_error.is_null() {
+            let get_integer: extern "system" fn(u32, *mut i32) = unsafe { mem::transmute(get_integer) };
+            let get_error: extern "system" fn() -> u32 = unsafe { mem::transmute(get_error) };
+            get_integer(MAX_SAMPLES, &mut value);
+            // the query isn't supported by OpenGL ES 2, don't leave the
+            // error for gfx to find
+            while get_error() != 0 {}
+        }
+        value.max(0) as u32
+    }
+
+    /// Creates the resources of the renderer drawing into `targets`, with
+    /// the `samples` of the targets and `max_samples` of the device, and
+    /// the `size` and `dpi` of the window.
+    #[cfg(feature = "opengl")]
+    fn with_targets(
+        device: back::Device,
+        mut gl_factory: back::Factory,
+        (out_color, out_depth): (h::RenderTargetView<back::Resources, ColorFormat>, h::DepthStencilView<back::Resources, DepthFormat>),
+        (samples, max_samples): (u32, u32),
+        (size, dpi): (glutin::dpi::LogicalSize, f64),
+        source: &source::Set,
+        pending_diagnostics: Vec<Diagnostic>,
+    ) -> (Self, Factory) {
         use gfx::texture as t;
 
-        let (windowedContext, device, mut gl_factory, out_color, out_depth) = gfx_window_glutin::init(builder, context, event_loop).unwrap();
-        let window = windowedContext.window();
+        let device_info = DeviceInfo::new(&device, max_samples, samples);
         let (_, srv_white) = gl_factory
             .create_texture_immutable::<gfx::format::Rgba8>(
                 t::Kind::D2(1, 1, t::AaMode::Single),
                 t::Mipmap::Provided,
                 &[&[[0xFF; 4]]]
             ).unwrap();
+        let (_, srv_flat_normal) = gl_factory
+            .create_texture_immutable::<gfx::format::Rgba8>(
+                t::Kind::D2(1, 1, t::AaMode::Single),
+                t::Mipmap::Provided,
+                &[&[[0x80, 0x80, 0xFF, 0xFF]]]
+            ).unwrap();
         let (_, srv_shadow) = gl_factory
             .create_texture_immutable::<(gfx::format::R32, gfx::format::Float)>(
                 t::Kind::D2(1, 1, t::AaMode::Single),
                 t::Mipmap::Provided,
                 &[&[0x3F800000]],
             ).unwrap();
+        let shadow_face: &[u32] = &[0x3F800000];
+        let (_, srv_shadow_cube) = gl_factory
+            .create_texture_immutable::<(gfx::format::R32, gfx::format::Float)>(
+                t::Kind::Cube(1),
+                t::Mipmap::Provided,
+                &[shadow_face; 6],
+            ).unwrap();
+        let reflection_face: &[[u8; 4]] = &[[0, 0, 0, 0xFF]];
+        let (_, srv_reflection) = gl_factory
+            .create_texture_immutable::<gfx::format::Srgba8>(
+                t::Kind::Cube(1),
+                t::Mipmap::Provided,
+                &[reflection_face; 6],
+            ).unwrap();
         let sampler = gl_factory.create_sampler_linear();
+        let reflection_default = CubeMap::new(srv_reflection, sampler.clone());
         let sampler_shadow = gl_factory.create_sampler(t::SamplerInfo {
             comparison: Some(gfx::state::Comparison::Less),
             border: t::PackedColor(!0), // clamp to 1.0
@@ -602,8 +1785,11 @@ impl Renderer {
         let encoder = gl_factory.create_command_buffer().into();
         let const_buf = gl_factory.create_constant_buffer(1);
         let quad_buf = gl_factory.create_constant_buffer(1);
+        let icon_buf = gl_factory.create_constant_buffer(1);
+        let post_buf = gl_factory.create_constant_buffer(1);
         let light_buf = gl_factory.create_constant_buffer(MAX_LIGHTS);
         let pbr_buf = gl_factory.create_constant_buffer(1);
+        let user_buf = gl_factory.create_constant_buffer(1);
         let inst_buf = gl_factory
             .create_buffer(
                 1,
@@ -612,35 +1798,97 @@ impl Renderer {
                 gfx::memory::Bind::TRANSFER_DST,
             )
             .unwrap();
+        let hud_buf = gl_factory
+            .create_buffer(
+                1,
+                gfx::buffer::Role::Vertex,
+                gfx::memory::Usage::Dynamic,
+                gfx::memory::Bind::TRANSFER_DST,
+            )
+            .unwrap();
         let displacement_contributions_buf = gl_factory.create_constant_buffer(MAX_TARGETS);
         let pso = PipelineStates::init(source, &mut gl_factory).unwrap();
+        let pick_texture = gl_factory
+            .create_texture(
+                t::Kind::D2(1, 1, t::AaMode::Single),
+                1,
+                gfx::memory::Bind::RENDER_TARGET | gfx::memory::Bind::TRANSFER_SRC,
+                gfx::memory::Usage::Data,
+                Some(gfx::format::ChannelType::Unorm),
+            )
+            .unwrap();
+        let pick_target = PickTarget {
+            color: gl_factory
+                .view_texture_as_render_target(&pick_texture, 0, None)
+                .unwrap(),
+            depth: gl_factory
+                .create_depth_stencil::<DepthFormat>(1, 1)
+                .unwrap()
+                .2,
+            download: gl_factory.create_download_buffer(1).unwrap(),
+            texture: pick_texture,
+        };
 
-        let renderer = Renderer {
+        let mut renderer = Renderer {
             device,
             factory: gl_factory.clone(),
             encoder,
             const_buf,
             quad_buf,
+            icon_buf,
+            post_buf,
             light_buf,
             inst_buf,
+            hud_buf,
             pbr_buf,
+            user_buf,
+            user_params: UserParams::from([0.0; 16]),
             displacement_contributions_buf,
-            out_color,
+            out_color: out_color.raw().clone(),
+            out_format: ColorFormat::get_format(),
+            window_color: out_color,
             out_depth,
             pso,
             default_joint_buffer_view,
             default_displacement_buffer_view,
-            map_default: Texture::new(srv_white, sampler, [1, 1]),
+            map_default: Texture::new(srv_white, sampler.clone(), [1, 1]),
+            normal_default: Texture::new(srv_flat_normal, sampler, [1, 1]),
             shadow_default: Texture::new(srv_shadow, sampler_shadow, [1, 1]),
+            shadow_cube_default: srv_shadow_cube,
+            reflection_default,
             instance_cache: HashMap::new(),
+            visibility_fn: None,
+            diagnostics_fn: None,
+            pending_diagnostics,
             shadow: ShadowType::Basic,
+            shadow_culling: true,
+            depth: DepthMode::Standard,
+            portals: None,
+            hud: Hud::default(),
+            offscreen: false,
+            transparent: false,
+            only_nodes: None,
+            emissive_only: false,
+            render_scale: 1.0,
+            frustum_culling: true,
+            device_info,
+            scaled_target: None,
+            hdr: false,
+            exposure: 1.0,
+            tone_mapping: ToneMapOperator::Aces,
+            hdr_pso: None,
+            viewport_targets: Vec::new(),
             debug_quads: froggy::Storage::new(),
+            pick_target,
+            readbacks: Vec::new(),
+            next_readback: 0,
             font_cache: HashMap::new(),
-            size: window.get_inner_size().unwrap(),
-            dpi: window.get_hidpi_factor(),
+            size,
+            dpi,
         };
+        renderer.set_user_params([0.0; 16]);
         let factory = Factory::new(gl_factory);
-        (renderer, windowedContext, factory)
+        (renderer, factory)
     }
 
     /// Reloads the shaders.
@@ -663,7 +1911,8 @@ impl Renderer {
         }
 
         self.size = size;
-        gfx_window_glutin::update_views(window, &mut self.out_color, &mut self.out_depth);
+        gfx_window_glutin::update_views(window, &mut self.window_color, &mut self.out_depth);
+        self.out_color = self.window_color.raw().clone();
     }
 
     pub(crate) fn dpi_change(
@@ -672,7 +1921,8 @@ impl Renderer {
         dpi: f64,
     ) {
         self.dpi = dpi;
-        gfx_window_glutin::update_views(window, &mut self.out_color, &mut self.out_depth);
+        gfx_window_glutin::update_views(window, &mut self.window_color, &mut self.out_depth);
+        self.out_color = self.window_color.raw().clone();
     }
 
     /// Returns current viewport aspect ratio, i.e. width / height.
@@ -680,6 +1930,12 @@ impl Renderer {
         self.size.to_physical(self.dpi).width as f32 / self.size.to_physical(self.dpi).height as f32
     }
 
+    /// Returns the size of the current render target in physical pixels.
+    pub fn size(&self) -> mint::Vector2<f32> {
+        let size = self.size.to_physical(self.dpi);
+        [size.width as f32, size.height as f32].into()
+    }
+
     /// Map screen pixel coordinates to Normalized Display Coordinates.
     /// The lower left corner corresponds to (-1,-1), and the upper right corner
     /// corresponds to (1,1).
@@ -694,12 +1950,264 @@ impl Renderer {
         }
     }
 
+    /// Map Normalized Display Coordinates to screen pixel coordinates, the
+    /// inverse of [`map_to_ndc`](#method.map_to_ndc).
+    pub fn map_from_ndc<P: Into<mint::Point2<f32>>>(
+        &self,
+        point: P,
+    ) -> mint::Point2<f32> {
+        let point = point.into();
+        let size = self.size.to_physical(self.dpi);
+        mint::Point2 {
+            x: 0.5 * (point.x + 1.0) * size.width as f32,
+            y: 0.5 * (1.0 - point.y) * size.height as f32,
+        }
+    }
+
+    /// Projects a point from world space to screen pixel coordinates as seen
+    /// by `camera`. Returns `None` if the point is behind the camera.
+    ///
+    /// # Panics
+    /// Panics if the camera isn't in the scene.
+    pub fn world_to_screen<P: Into<mint::Point3<f32>>>(
+        &self,
+        sync: &SyncGuard,
+        camera: &Camera,
+        point: P,
+    ) -> Option<mint::Point2<f32>> {
+        camera
+            .world_to_ndc(sync, self.aspect_ratio(), point)
+            .map(|ndc| self.map_from_ndc([ndc.x, ndc.y]))
+    }
+
+    /// Sets the user parameters available to custom basic pipelines.
+    ///
+    /// Shaders access them as the `u_UserParams` matrix after an
+    /// `#include <user>` directive, for passing values such as time or mouse
+    /// position. The parameters stay set until changed and are zero by default.
+    ///
+    /// Materials override them with [`Custom::user_params`].
+    ///
+    /// [`Custom::user_params`]: material/basic/struct.Custom.html#structfield.user_params
+    pub fn set_user_params(
+        &mut self,
+        params: [f32; 16],
+    ) {
+        self.user_params = UserParams::from(params);
+        self.encoder.update_constant_buffer(&self.user_buf, &self.user_params);
+    }
+
+    /// Drops the instance batches kept between frames.
+    ///
+    /// Batches are reused to avoid reallocating their buffers, and they keep
+    /// their geometry and material alive. Call this after removing many
+    /// instanced meshes to release them.
+    pub fn clear_instance_cache(&mut self) {
+        self.instance_cache.clear();
+    }
+
+    /// Sets a callback deciding whether an object should be drawn this frame.
+    ///
+    /// The callback is invoked for every visible mesh in the scene before it is
+    /// drawn, and returning `false` skips drawing it. This allows applications
+    /// to implement their own culling schemes, such as portals or potentially
+    /// visible sets. Shadow casting is not affected.
+    pub fn set_visibility_fn<F>(
+        &mut self,
+        fun: F,
+    ) where
+        F: 'static + FnMut(&ObjectInfo) -> bool,
+    {
+        self.visibility_fn = Some(Box::new(fun));
+    }
+
+    /// Removes the callback set by [`set_visibility_fn`](#method.set_visibility_fn).
+    pub fn clear_visibility_fn(&mut self) {
+        self.visibility_fn = None;
+    }
+
+    /// Sets a callback receiving the problems the renderer runs into, such
+    /// as too many lights, so that applications can show them in their own
+    /// UI. They are logged as well.
+    ///
+    /// The problems reported before the callback was set, such as while
+    /// creating the window, are passed to it right away.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// window.renderer.set_diagnostics_fn(|diagnostic| {
+    ///     println!("Renderer warning: {}", diagnostic);
+    /// });
+    /// ```
+    pub fn set_diagnostics_fn<F>(
+        &mut self,
+        fun: F,
+    ) where
+        F: 'static + FnMut(&Diagnostic),
+    {
+        let mut fun = Box::new(fun);
+        for diagnostic in self.pending_diagnostics.drain(..) {
+            fun(&diagnostic);
+        }
+        self.diagnostics_fn = Some(fun);
+    }
+
+    /// Removes the callback set by [`set_diagnostics_fn`](#method.set_diagnostics_fn).
+    pub fn clear_diagnostics_fn(&mut self) {
+        self.diagnostics_fn = None;
+    }
+
+    /// Logs `diagnostic` and passes it to the diagnostics callback, or keeps
+    /// it for the callback set later.
+    fn report(
+        &mut self,
+        diagnostic: Diagnostic,
+    ) {
+        error!("{}", diagnostic);
+        match self.diagnostics_fn {
+            Some(ref mut fun) => fun(&diagnostic),
+            None => {
+                if !self.pending_diagnostics.contains(&diagnostic) {
+                    self.pending_diagnostics.push(diagnostic);
+                }
+            }
+        }
+    }
+
+    /// Returns the vendor, version and capabilities of the graphics device,
+    /// for choosing quality settings or including in bug reports.
+    pub fn device_info(&self) -> &DeviceInfo {
+        &self.device_info
+    }
+
+    /// Sets the resolution the scene is rendered at, relative to the window.
+    ///
+    /// Below 1, the scene is drawn into a smaller offscreen buffer that is
+    /// stretched over the window, trading sharpness for speed in heavy scenes.
+    /// Above 1, the scene is supersampled. UI text and debug quads are always
+    /// drawn at the full window resolution. Scaled scenes don't use the
+    /// multisampling of the window, but supersampling antialiases them too.
+    ///
+    /// Default: 1.
+    pub fn set_render_scale(
+        &mut self,
+        scale: f32,
+    ) {
+        self.render_scale = scale.max(0.1).min(2.0);
+        if self.render_scale == 1.0 {
+            self.scaled_target = None;
+        }
+    }
+
+    /// Returns the resolution the scene is rendered at, relative to the window.
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /// Enables rendering the scene with a high dynamic range.
+    ///
+    /// The scene is then drawn into an offscreen buffer of half floats,
+    /// which keeps colors brighter than white instead of clipping them, and
+    /// brought into the range of the display with the
+    /// [exposure](#method.set_exposure) and the
+    /// [tone mapping operator](#method.set_tone_mapping). Bright lights and
+    /// reflections on PBR materials keep their highlights, instead of
+    /// flattening into white.
+    ///
+    /// Only applies to the window: [`RenderTarget`]s and the passes of a
+    /// [`Composer`] hold clipped colors, the latter having a [`ToneMapping`]
+    /// pass of its own.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::render::ToneMapOperator;
+    ///
+    /// window.renderer.set_hdr(true);
+    /// window.renderer.set_exposure(1.5);
+    /// window.renderer.set_tone_mapping(ToneMapOperator::Reinhard);
+    /// ```
+    ///
+    /// Default: `false`.
+    ///
+    /// [`RenderTarget`]: struct.RenderTarget.html
+    /// [`Composer`]: composer/struct.Composer.html
+    /// [`ToneMapping`]: composer/struct.ToneMapping.html
+    pub fn set_hdr(
+        &mut self,
+        enabled: bool,
+    ) {
+        self.hdr = enabled;
+        self.scaled_target = None;
+    }
+
+    /// Returns whether the scene is rendered with a high dynamic range.
+    pub fn is_hdr(&self) -> bool {
+        self.hdr
+    }
+
+    /// Sets the multiplier of the colors of HDR frames before tone mapping,
+    /// brightening the frame above 1 and darkening it below.
+    ///
+    /// Default: 1.
+    pub fn set_exposure(
+        &mut self,
+        exposure: f32,
+    ) {
+        self.exposure = exposure.max(0.0);
+    }
+
+    /// Returns the multiplier of the colors of HDR frames.
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Sets how HDR frames are brought into the range of the display.
+    ///
+    /// Default: `ToneMapOperator::Aces`.
+    pub fn set_tone_mapping(
+        &mut self,
+        operator: ToneMapOperator,
+    ) {
+        self.tone_mapping = operator;
+    }
+
+    /// Returns how HDR frames are brought into the range of the display.
+    pub fn tone_mapping(&self) -> ToneMapOperator {
+        self.tone_mapping
+    }
+
+    /// Enables skipping meshes whose bounds are entirely outside of the
+    /// camera view. Skinned and morphed meshes, sprites and dynamic meshes
+    /// are always drawn, as their vertices move out of the rest bounds.
+    /// Instanced meshes are skipped when all of their instances are outside.
+    /// Groups with [`bounds`] are skipped with all of their children.
+    ///
+    /// [`bounds`]: ../object/struct.Group.html#method.set_bounds
+    ///
+    /// Default: `true`.
+    pub fn set_frustum_culling(
+        &mut self,
+        enabled: bool,
+    ) {
+        self.frustum_culling = enabled;
+    }
+
+    /// Returns `true` if meshes outside of the camera view are skipped.
+    pub fn frustum_culling(&self) -> bool {
+        self.frustum_culling
+    }
+
     /// See [`Window::render`](struct.Window.html#method.render).
     pub fn render(
         &mut self,
         scene: &Scene,
         camera: &Camera,
     ) {
+        if (self.render_scale != 1.0 || self.hdr) && !self.offscreen {
+            self.render_scaled(scene, camera);
+            return;
+        }
+
         {
             use gfx::Device;
             self.device.cleanup();
@@ -709,48 +2217,55 @@ impl Renderer {
         hub.process_messages();
         // update joint transforms of skeletons
         {
-            use node::TransformInternal;
-
-            struct SkeletonTemp {
-                inverse_world_transform: TransformInternal,
-                cpu_buffer: Vec<[f32; 4]>,
-                gpu_buffer: gfx::handle::Buffer<BackendResources, [f32; 4]>,
-            }
-
+            // Bones may be visited before or after their skeleton, so the world
+            // transforms of all the bones are gathered first.
             let mut skeletons = Vec::new();
+            let mut bone_transforms = HashMap::new();
             for w in hub.walk(&scene.first_child) {
                 match w.node.sub_node {
                     SubNode::Skeleton(ref skeleton) => {
-                        skeletons.push(SkeletonTemp {
-                            inverse_world_transform: w.world_transform.inverse_transform().unwrap(),
-                            cpu_buffer: vec![[0.0; 4]; skeleton.bones.len() * VECS_PER_BONE],
-                            gpu_buffer: skeleton.gpu_buffer.clone(),
-                        });
+                        skeletons.push((w.world_transform, skeleton));
                     }
-                    SubNode::Bone { index, inverse_bind_matrix } => {
-                        let skel = skeletons.last_mut().unwrap();
-                        let mx_base = Matrix4::from(skel.inverse_world_transform.concat(&w.world_transform));
-                        let mx = (mx_base * Matrix4::from(inverse_bind_matrix)).transpose();
-                        let buf = &mut skel.cpu_buffer[index * VECS_PER_BONE .. (index + 1) * VECS_PER_BONE];
-                        buf[0] = mx.x.into();
-                        buf[1] = mx.y.into();
-                        buf[2] = mx.z.into();
+                    SubNode::Bone { .. } => {
+                        bone_transforms.insert(w.node as *const NodeInternal, w.world_transform);
                     }
                     _ => {}
                 }
             }
 
-            for skel in skeletons {
+            for (world_transform, skeleton) in skeletons {
+                let mut cpu_buffer = vec![[0.0; 4]; skeleton.bones.len() * VECS_PER_BONE];
+                for bone in &skeleton.bones {
+                    let node = &hub[bone];
+                    let bone_transform = match bone_transforms.get(&(node as *const NodeInternal)) {
+                        Some(transform) => transform,
+                        None => continue,
+                    };
+                    if let SubNode::Bone { index, inverse_bind_matrix } = node.sub_node {
+                        let mx = skeleton::skin_matrix(&world_transform, bone_transform, inverse_bind_matrix).transpose();
+                        let buf = &mut cpu_buffer[index * VECS_PER_BONE .. (index + 1) * VECS_PER_BONE];
+                        buf[0] = mx.x.into();
+                        buf[1] = mx.y.into();
+                        buf[2] = mx.z.into();
+                    }
+                }
                 self.encoder
                     .update_buffer(
-                        &skel.gpu_buffer,
-                        &skel.cpu_buffer,
+                        &skeleton.gpu_buffer,
+                        &cpu_buffer,
                         0,
                     )
                     .expect("upload to GPU target buffer");
             }
         }
 
+        // upload the data buffers written since the last frame
+        for copy in hub.data_copies.drain(..) {
+            self.encoder
+                .copy_buffer(&copy.buffer.upload, &copy.buffer.buffer, copy.offset, copy.offset, copy.count)
+                .unwrap();
+        }
+
         // update dynamic meshes
         // Note: mutable node access here
         for node in hub.nodes.iter_mut() {
@@ -771,28 +2286,31 @@ impl Renderer {
                             .unwrap();
                     }
                 }
-                // Note: UI text currently applies to all the scenes.
-                // We may want to make it scene-dependent at some point.
-                SubNode::UiText(ref text) => {
-                    text.font.queue(&text.section);
-                    if !self.font_cache.contains_key(&text.font.id) {
-                        self.font_cache
-                            .insert(text.font.id.clone(), text.font.clone());
-                    }
-                }
                 _ => {}
             }
         }
 
         // gather lights
-        struct ShadowRequest {
+        struct ShadowFace {
             target: h::DepthStencilView<back::Resources, ShadowFormat>,
-            resource: h::ShaderResourceView<back::Resources, f32>,
             mx_view: Matrix4<f32>,
             mx_proj: Matrix4<f32>,
         }
+        struct ShadowRequest {
+            faces: Vec<ShadowFace>,
+            resource: h::ShaderResourceView<back::Resources, f32>,
+            cascades: Vec<h::ShaderResourceView<back::Resources, f32>>,
+            cube: bool,
+            max_distance: f32,
+        }
+        struct CascadeRequest {
+            light: usize,
+            shadow: usize,
+            splits: Vec<f32>,
+        }
         let mut lights = Vec::new();
         let mut shadow_requests = Vec::new();
+        let mut cascade_requests = Vec::<CascadeRequest>::new();
         let mut mx_camera_transform = hub[&camera].transform;
 
         for w in hub.walk(&scene.first_child) {
@@ -805,28 +2323,91 @@ impl Renderer {
                 _ => continue,
             };
             if lights.len() == MAX_LIGHTS {
-                error!("Max number of lights ({}) reached", MAX_LIGHTS);
+                self.report(Diagnostic::TooManyLights { max: MAX_LIGHTS });
                 break;
             }
 
-            let shadow_index = if let Some((ref map, ref projection)) = light.shadow {
-                let target = map.to_target();
-                let dim = target.get_dimensions();
-                let aspect = dim.0 as f32 / dim.1 as f32;
-                let mx_proj = match projection {
-                    &ShadowProjection::Orthographic(ref p) => p.matrix(aspect),
-                };
-                let mx_view = Matrix4::from(w.world_transform.inverse_transform().unwrap());
+            let mut shadow_range = [0.0; 4];
+            let mut cascades = Vec::new();
+            let mut cascade_splits = Vec::new();
+            let shadow_faces = match light.shadow {
+                Some((ref map, ShadowProjection::Orthographic(ref p))) if !map.is_cube() => {
+                    let target = map.to_targets()[0].clone();
+                    let dim = target.get_dimensions();
+                    let aspect = dim.0 as f32 / dim.1 as f32;
+                    let mx_proj: [[f32; 4]; 4] = p.matrix(aspect).into();
+                    vec![ShadowFace {
+                        target,
+                        mx_view: Matrix4::from(w.world_transform.inverse_transform().unwrap()),
+                        mx_proj: mx_proj.into(),
+                    }]
+                }
+                Some((ref map, ShadowProjection::Cube(ref range))) if map.is_cube() => {
+                    shadow_range = [range.start, range.end, 0.0, 0.0];
+                    let mx_proj = perspective(Deg(90.0), 1.0, range.start, range.end);
+                    let eye = Point3::from_vec(w.world_transform.disp);
+                    // faces in the order and orientation of GL cube maps
+                    let axes = [
+                        (Vector3::unit_x(), -Vector3::unit_y()),
+                        (-Vector3::unit_x(), -Vector3::unit_y()),
+                        (Vector3::unit_y(), Vector3::unit_z()),
+                        (-Vector3::unit_y(), -Vector3::unit_z()),
+                        (Vector3::unit_z(), -Vector3::unit_y()),
+                        (-Vector3::unit_z(), -Vector3::unit_y()),
+                    ];
+                    map.to_targets()
+                        .iter()
+                        .zip(axes.iter())
+                        .map(|(target, &(dir, up))| ShadowFace {
+                            target: target.clone(),
+                            mx_view: Matrix4::look_at(eye, eye + dir, up),
+                            mx_proj,
+                        })
+                        .collect()
+                }
+                Some((_, ShadowProjection::Cascades(ref maps, ref splits))) if maps.iter().all(|map| !map.is_cube()) => {
+                    // only the first light gets every cascade, the others
+                    // stretch their first one over the whole distance
+                    let count = if cascade_requests.is_empty() { maps.len() } else { 1 };
+                    cascade_splits = if count == 1 { vec![splits[splits.len() - 1]] } else { splits.clone() };
+                    let mx_view = Matrix4::from(w.world_transform.inverse_transform().unwrap());
+                    cascades = maps[.. count].iter().map(|map| map.to_resource()).collect();
+                    // the projections are fitted once the camera is found
+                    maps[.. count]
+                        .iter()
+                        .map(|map| ShadowFace {
+                            target: map.to_targets()[0].clone(),
+                            mx_view,
+                            mx_proj: Matrix4::identity(),
+                        })
+                        .collect()
+                }
+                Some(_) => {
+                    self.report(Diagnostic::ShadowMapMismatch(hub.upgrade_ptr(w.node_ptr.clone())));
+                    Vec::new()
+                }
+                None => Vec::new(),
+            };
+            let shadow_index = if shadow_faces.is_empty() {
+                -1
+            } else {
+                let map = &light.shadow.as_ref().unwrap().0;
                 shadow_requests.push(ShadowRequest {
-                    target,
+                    faces: shadow_faces,
                     resource: map.to_resource(),
-                    mx_view,
-                    mx_proj: mx_proj.into(),
+                    cascades,
+                    cube: map.is_cube(),
+                    max_distance: light.shadow_distance,
                 });
                 shadow_requests.len() as i32 - 1
-            } else {
-                -1
             };
+            if !cascade_splits.is_empty() {
+                cascade_requests.push(CascadeRequest {
+                    light: lights.len(),
+                    shadow: shadow_index as usize,
+                    splits: cascade_splits,
+                });
+            }
 
             let mut color_back = 0;
             let mut p = w.world_transform.disp.extend(1.0);
@@ -844,19 +2425,23 @@ impl Renderer {
                 }
                 SubLight::Point => [0.0, light.intensity, 0.0, 0.0],
             };
-            let projection = if shadow_index >= 0 {
-                let request = &shadow_requests[shadow_index as usize];
-                let matrix = request.mx_proj * request.mx_view;
-                matrix.into()
-            } else {
-                [[0.0; 4]; 4]
+            let (projection, shadow_cube) = match shadow_requests.get(shadow_index as usize) {
+                // cube shadows are looked up by the offset from the light
+                Some(request) if request.cube => {
+                    (Matrix4::from_translation(-w.world_transform.disp).into(), 1)
+                }
+                Some(request) => {
+                    let face = &request.faces[0];
+                    ((face.mx_proj * face.mx_view).into(), 0)
+                }
+                None => ([[0.0; 4]; 4], 0),
             };
 
             lights.push(LightParam {
                 projection,
                 pos: p.into(),
                 dir: d.extend(0.0).into(),
-                focus: [0.0, 0.0, 0.0, 0.0],
+                shadow_range,
                 color: {
                     let rgb = color::to_linear_rgb(light.color);
                     [rgb[0], rgb[1], rgb[2], 0.0]
@@ -866,57 +2451,101 @@ impl Renderer {
                     [rgb[0], rgb[1], rgb[2], 0.0]
                 },
                 intensity,
-                shadow_params: [shadow_index, 0, 0, 0],
+                shadow_params: [shadow_index, shadow_cube, 0, 0],
+                cascade_scale: [[0.0; 4]; 4],
+                cascade_offset: [[0.0; 4]; 4],
             });
         }
 
-        // render shadow maps
-        for request in &shadow_requests {
-            self.encoder.clear_depth(&request.target, 1.0);
-            let mx_vp = request.mx_proj * request.mx_view;
-            self.encoder.update_constant_buffer(
-                &self.const_buf,
-                &Globals {
-                    mx_vp: mx_vp.into(),
-                    mx_view: request.mx_view.into(),
-                    mx_inv_proj: request.mx_proj.into(),
-                    num_lights: 0,
-                },
-            );
-
-            for w in hub.walk(&scene.first_child) {
-                let gpu_data = match w.node.sub_node {
-                    SubNode::Visual(_, ref data, _) => data,
-                    _ => continue,
-                };
-                let mx_world: mint::ColumnMatrix4<_> = Matrix4::from(w.world_transform).into();
-                self.encoder
-                    .update_buffer(&gpu_data.instances, &[Instance::pbr(mx_world.into())], 0)
-                    .unwrap();
-                //TODO: avoid excessive cloning
-                let data = shadow_pipe::Data {
-                    vbuf: gpu_data.vertices.clone(),
-                    inst_buf: gpu_data.instances.clone(),
-                    cb_globals: self.const_buf.clone(),
-                    target: request.target.clone(),
-                };
-                self.encoder.draw(&gpu_data.slice, &self.pso.shadow, &data);
+        // fit the cascades around slices of the camera frustum
+        let projection = match hub[&camera].sub_node {
+            SubNode::Camera(ref projection) => projection.clone(),
+            _ => panic!("Camera had incorrect sub node")
+        };
+        let mx_proj = Matrix4::from(projection.matrix(self.aspect_ratio()));
+        for request in &cascade_requests {
+            let shadow = &mut shadow_requests[request.shadow];
+            let sizes = shadow.faces.iter().map(|face| face.target.get_dimensions().0).collect::<Vec<_>>();
+            let mx_light_view = shadow.faces[0].mx_view;
+            let projections = fit_cascades(mx_light_view, Matrix4::from(mx_camera_transform), mx_proj, &request.splits, &sizes);
+            let mx_first_inverse = projections[0].invert().unwrap();
+            let light = &mut lights[request.light];
+            light.projection = (projections[0] * mx_light_view).into();
+            if projections.len() > 1 {
+                light.shadow_params[2] = projections.len() as i32;
+            }
+            for (i, (face, &mx_cascade)) in shadow.faces.iter_mut().zip(&projections).enumerate() {
+                face.mx_proj = mx_cascade;
+                // the projections only differ by scales and offsets
+                let relative = mx_cascade * mx_first_inverse;
+                light.cascade_scale[i] = [relative.x.x, relative.y.y, relative.z.z, 0.0];
+                light.cascade_offset[i] = [relative.w.x, relative.w.y, relative.w.z, 0.0];
             }
         }
 
+        // render shadow maps
+        let camera_position = mx_camera_transform.disp;
+        for (request, face) in shadow_requests
+            .iter()
+            .flat_map(|request| request.faces.iter().map(move |face| (request, face)))
+        {
+            let max_distance = if self.shadow_culling { Some(request.max_distance) } else { None };
+            self.draw_depth(&hub, scene, &face.target, face.mx_view, face.mx_proj, &mx_camera_transform, max_distance);
+        }
+
         // prepare target and globals
         let mx_view = Matrix4::from(mx_camera_transform.inverse_transform().unwrap());
-        let projection = match hub[&camera].sub_node {
-            SubNode::Camera(ref projection) => projection.clone(),
-            _ => panic!("Camera had incorrect sub node")
+        let culled = match self.portals {
+            Some(ref portals) => {
+                let position: mint::Vector3<f32> = mx_camera_transform.disp.into();
+                portals.culled_nodes(&hub, position.into(), (mx_proj * mx_view).into())
+            }
+            None => HashSet::new(),
+        };
+        let (fog_color, fog_range) = match scene.fog {
+            // fog hides the glow of distant objects without adding any
+            Some(ref fog) if self.emissive_only => ([0.0, 0.0, 0.0, 1.0], [fog.near, fog.far, 0.0, 0.0]),
+            Some(ref fog) => {
+                let [r, g, b] = color::to_linear_rgb(fog.color);
+                ([r, g, b, 1.0], [fog.near, fog.far, 0.0, 0.0])
+            }
+            None => ([0.0; 4], [0.0; 4]),
+        };
+        let logarithmic = match projection {
+            Projection::Perspective(ref perspective) if self.depth == DepthMode::Logarithmic => {
+                let far = match perspective.zrange {
+                    ZRange::Finite(ref range) => range.end,
+                    ZRange::Infinite(_) => LOGARITHMIC_DEPTH_FAR,
+                };
+                Some((perspective.zrange.clone(), 2.0 / (far + 1.0).log2()))
+            }
+            _ => None,
+        };
+        let depth_params = match logarithmic {
+            Some((_, scale)) => [scale, 0.0, 0.0, 0.0],
+            None => [0.0; 4],
+        };
+        let probe = match scene.light_probe {
+            Some(ref probe) => probe.to_vectors(),
+            None => [[0.0; 4]; 9],
+        };
+        let reflection_params = match scene.reflection_probe {
+            Some(ref probe) => [probe.intensity, (probe.levels - 1) as f32, 0.0, 0.0],
+            None => [0.0; 4],
         };
-        let mx_proj = Matrix4::from(projection.matrix(self.aspect_ratio()));
         self.encoder.update_constant_buffer(
             &self.const_buf,
             &Globals {
                 mx_vp: (mx_proj * mx_view).into(),
                 mx_view: mx_view.into(),
                 mx_inv_proj: mx_proj.invert().unwrap().into(),
+                fog_color,
+                fog_range,
+                depth_params,
+                probe_a: [probe[0], probe[1], probe[2], probe[3]],
+                probe_b: [probe[4], probe[5], probe[6], probe[7]],
+                probe_c: probe[8],
+                reflection_params,
                 num_lights: lights.len() as u32,
             },
         );
@@ -927,21 +2556,43 @@ impl Renderer {
         self.encoder.clear_depth(&self.out_depth, 1.0);
         self.encoder.clear_stencil(&self.out_depth, 0);
 
-        if let Background::Color(color) = scene.background {
+        if self.emissive_only {
+            self.encoder.clear_raw(&self.out_color, [0.0; 4].into());
+        } else if let Background::Color(color) = scene.background {
             let rgb = color::to_linear_rgb(color);
+            // the alpha of transparent windows is blended with the desktop
+            let alpha = if self.transparent && !self.offscreen { 1.0 } else { 0.0 };
             self.encoder
-                .clear(&self.out_color, [rgb[0], rgb[1], rgb[2], 0.0]);
+                .clear_raw(&self.out_color, [rgb[0], rgb[1], rgb[2], alpha].into());
+        } else if let Background::Transparent = scene.background {
+            self.encoder.clear_raw(&self.out_color, [0.0; 4].into());
         }
 
         // render everything
         let (shadow_default, shadow_sampler) = self.shadow_default.to_param();
-        let shadow0 = match shadow_requests.get(0) {
-            Some(ref request) => request.resource.clone(),
-            None => shadow_default.clone(),
+        let shadow_slot = |index: usize, cube: bool| match shadow_requests.get(index) {
+            Some(request) if request.cube == cube => request.resource.clone(),
+            _ if cube => self.shadow_cube_default.clone(),
+            _ => shadow_default.clone(),
+        };
+        let cascade_slot = |index: usize| {
+            let cascades = cascade_requests.first().map(|request| &shadow_requests[request.shadow].cascades);
+            match cascades.and_then(|cascades| cascades.get(index)) {
+                Some(resource) => resource.clone(),
+                None => shadow_default.clone(),
+            }
         };
-        let shadow1 = match shadow_requests.get(1) {
-            Some(ref request) => request.resource.clone(),
-            None => shadow_default.clone(),
+        let shadows = SceneTextures {
+            sampler: shadow_sampler,
+            flat: [shadow_slot(0, false), shadow_slot(1, false)],
+            cube: [shadow_slot(0, true), shadow_slot(1, true)],
+            cascades: [cascade_slot(0), cascade_slot(1), cascade_slot(2), cascade_slot(3)],
+            reflection: match scene.reflection_probe {
+                Some(ref probe) => probe.cubemap.clone(),
+                None => self.reflection_default.clone(),
+            },
+            flat_normal: self.normal_default.clone(),
+            empty_buffer: self.default_displacement_buffer_view.clone(),
         };
 
         // clear instance cache
@@ -949,84 +2600,83 @@ impl Renderer {
             instances.list.clear();
         }
 
-        for w in hub.walk(&scene.first_child) {
+        // faded and blended objects are drawn after everything opaque, from
+        // back to front by the view depth of their center
+        let mut faded = Vec::new();
+        let view_frustum = Frustum::from_matrix(mx_proj * mx_view);
+        let walker = if self.frustum_culling {
+            hub.walk_culled(&scene.first_child, &view_frustum)
+        } else {
+            hub.walk(&scene.first_child)
+        };
+        for mut w in walker {
             let (material, gpu_data, skeleton) = match w.node.sub_node {
-                SubNode::Visual(ref material, ref gpu_data, ref skeleton) => {
-                    (material, gpu_data, skeleton)
+                SubNode::Visual(ref material, ref data, ref skeleton) if w.world_opacity > 0.0 => {
+                    (material, data, skeleton)
                 }
                 _ => continue,
             };
-
-            let mx_world: mint::ColumnMatrix4<_> = Matrix4::from(w.world_transform).into();
-            let pso_data = material.to_pso_data();
-
-            let instance = match pso_data {
-                PsoData::Basic { color, map, param0 } => {
-                    let uv_range = match map {
-                        Some(ref map) => map.uv_range(),
-                        None => [0.0; 4],
-                    };
-                    if let Some(ref key) = gpu_data.instance_cache_key {
-                        let data = self.instance_cache
-                            .entry(key.clone())
-                            .or_insert_with(|| InstanceData {
-                                slice: gpu_data.slice.clone(),
-                                vertices: gpu_data.vertices.clone(),
-                                material: material.clone(),
-                                list: Vec::new(),
-                            });
-                        data.list.push(Instance::basic(mx_world.into(), color, uv_range, param0));
-                        // Create a new instance and defer the draw call.
+            if w.node.billboard {
+                w.world_transform.rot = mx_camera_transform.rot;
+            }
+            if culled.contains(&(w.node as *const _)) {
+                continue;
+            }
+            match gpu_data.bounds() {
+                Some((bounds, radius))
+                    if self.frustum_culling && skeleton.is_none() && gpu_data.displacements.is_none()
+                        && !bounds.is_empty() =>
+                {
+                    // the sphere test is cheaper and rejects most objects
+                    let transform = &w.world_transform;
+                    let center = transform.transform_vector(bounds.center()) + transform.disp;
+                    let center: [f32; 3] = center.into();
+                    if !view_frustum.intersects_sphere(center, radius * transform.scale.abs()) {
+                        continue;
+                    }
+                    let bounds = bounds.transformed(transform);
+                    let (min, max): ([f32; 3], [f32; 3]) = (bounds.min.into(), bounds.max.into());
+                    if !view_frustum.intersects_aabb(min, max) {
                         continue;
                     }
-                    Instance::basic(mx_world.into(), color, uv_range, param0)
                 }
-                PsoData::Pbr { .. } => {
-                    Instance::pbr(mx_world.into())
+                _ => {}
+            }
+            if let Some(ref only) = self.only_nodes {
+                if !only.contains(&(w.node as *const _)) {
+                    continue;
                 }
-            };
-            let joint_buffer_view = if let Some(ref ptr) = *skeleton {
-                match hub[ptr].sub_node {
-                    SubNode::Skeleton(ref skeleton_data) => {
-                        skeleton_data.gpu_buffer_view.clone()
-                    }
-                    _ => unreachable!()
+            }
+            if let Some(ref mut fun) = self.visibility_fn {
+                let info = ObjectInfo {
+                    object: hub.upgrade_ptr(w.node_ptr.clone()),
+                    name: w.node.name.as_deref(),
+                    world_transform: w.world_transform.into(),
+                };
+                if !fun(&info) {
+                    continue;
                 }
-            } else {
-                self.default_joint_buffer_view.clone()
-            };
-            let displacement_view = match gpu_data.displacements {
-                Some((_, ref view)) => view.clone(),
-                None => self.default_displacement_buffer_view.clone(),
-            };
-
-            Self::render_mesh(
-                &mut self.encoder,
-                self.const_buf.clone(),
-                gpu_data.instances.clone(),
-                self.light_buf.clone(),
-                self.pbr_buf.clone(),
-                self.displacement_contributions_buf.clone(),
-                self.out_color.clone(),
-                self.out_depth.clone(),
-                &self.pso,
-                &self.map_default,
-                &[instance],
-                gpu_data.vertices.clone(),
-                gpu_data.slice.clone(),
-                &material,
-                &shadow_sampler,
-                &shadow0,
-                &shadow1,
-                &gpu_data.displacement_contributions,
-                (displacement_view, self.map_default.to_param().1),
-                joint_buffer_view,
-                gpu_data.displacements.is_some(),
-            );
+            }
+            if w.world_opacity < 1.0 || material.transparency() == Transparency::Blend {
+                let center = match gpu_data.bounds() {
+                    Some((bounds, _)) if !bounds.is_empty() => {
+                        w.world_transform.transform_point(Point3::from_vec(bounds.center()))
+                    }
+                    _ => Point3::from_vec(w.world_transform.disp),
+                };
+                let depth = mx_view.transform_point(center).z;
+                faded.push((depth, w));
+                continue;
+            }
+            self.render_visual(&hub, &w, true, &shadows, mx_camera_transform.rot);
         }
 
         // render instanced meshes
+        let mut diagnostics = Vec::new();
         for data in self.instance_cache.values() {
+            if let Err(diagnostic) = self.pso.prepare_variant(&mut self.factory, &data.material, data.mirrored, data.line_quads, false) {
+                diagnostics.push(diagnostic);
+            }
             if data.list.len() > self.inst_buf.len() {
                 self.inst_buf = self.factory
                     .create_buffer(
@@ -1044,6 +2694,8 @@ impl Renderer {
                 self.inst_buf.clone(),
                 self.light_buf.clone(),
                 self.pbr_buf.clone(),
+                self.user_buf.clone(),
+                &self.user_params,
                 self.displacement_contributions_buf.clone(),
                 self.out_color.clone(),
                 self.out_depth.clone(),
@@ -1053,15 +2705,20 @@ impl Renderer {
                 data.vertices.clone(),
                 data.slice.clone(),
                 &data.material,
-                &shadow_sampler,
-                &shadow0,
-                &shadow1,
+                &shadows,
                 &ZEROED_DISPLACEMENT_CONTRIBUTION,
                 (self.default_displacement_buffer_view.clone(), self.map_default.to_param().1),
                 self.default_joint_buffer_view.clone(),
                 false,
+                data.mirrored,
+                data.line_quads,
+                1.0,
+                self.emissive_only,
             );
         }
+        for diagnostic in diagnostics {
+            self.report(diagnostic);
+        }
 
         let quad_slice = gfx::Slice {
             start: 0,
@@ -1073,6 +2730,7 @@ impl Renderer {
 
         // draw background (if any)
         match scene.background {
+            _ if self.emissive_only => {}
             Background::Texture(ref texture) => {
                 // TODO: Reduce code duplication (see drawing debug quads)
                 self.encoder.update_constant_buffer(
@@ -1110,12 +2768,158 @@ impl Renderer {
                 };
                 self.encoder.draw(&quad_slice, &self.pso.skybox, &data);
             }
-            Background::Color(_) => {}
+            Background::Custom(ref shader) => {
+                let (width, height, _, _) = self.out_color.get_dimensions();
+                let (width, height) = (width as f32, height as f32);
+                self.encoder.update_constant_buffer(
+                    &self.post_buf,
+                    &PostParams {
+                        params: shader.params,
+                        resolution: [width, height, 1.0 / width.max(1.0), 1.0 / height.max(1.0)],
+                    },
+                );
+                let data = background_pipe::Data {
+                    params: self.post_buf.clone(),
+                    globals: self.const_buf.clone(),
+                    aux: shader.aux.as_ref().unwrap_or(&self.map_default).to_param(),
+                    target: self.out_color.clone(),
+                    depth_target: self.out_depth.clone(),
+                };
+                self.encoder.draw(&quad_slice, &shader.pso, &data);
+            }
+            Background::Color(_) | Background::Transparent => {}
+        }
+
+        // draw parallax layers over the background
+        let layers = if self.emissive_only { &[][..] } else { &scene.parallax[..] };
+        for layer in layers {
+            let size = layer.texture.total_size();
+            let height = layer.height.max(1.0e-3);
+            let repeats = [
+                self.aspect_ratio() * size[1] as f32 / (size[0].max(1) as f32 * height),
+                1.0 / height,
+            ];
+            let u0 = layer.offset[0] + layer.scroll[0] * camera_position.x;
+            let v0 = layer.offset[1] + layer.scroll[1] * camera_position.y;
+            self.encoder.update_constant_buffer(
+                &self.icon_buf,
+                &IconParams {
+                    rect: [-1.0, -1.0, 1.0, 1.0],
+                    uv_range: [u0, v0, u0 + repeats[0], v0 + repeats[1]],
+                    color: [1.0, 1.0, 1.0, layer.opacity],
+                    depth: 1.0,
+                    distance_range: 0.0,
+                },
+            );
+            let data = icon_pipe::Data {
+                params: self.icon_buf.clone(),
+                map: layer.texture.to_param(),
+                target: self.out_color.clone(),
+                depth_target: self.out_depth.clone(),
+            };
+            self.encoder.draw(&quad_slice, &self.pso.icon, &data);
+        }
+
+        // the view looks down -Z, so the farthest objects have the lowest depth
+        faded.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(cmp::Ordering::Equal));
+        for &(_, ref w) in &faded {
+            self.render_visual(&hub, w, false, &shadows, mx_camera_transform.rot);
+        }
+
+        if !self.offscreen {
+            self.render_overlays(&hub, scene, &quad_slice, logarithmic.as_ref());
+        }
+
+        self.encoder.flush(&mut self.device);
+    }
+
+    /// Draws the shapes of the HUD, the UI text of `scene` and the debug
+    /// quads over the window, and clears the HUD.
+    ///
+    /// With the `logarithmic` depth of the camera, the depth of the text is
+    /// converted to match the scene.
+    fn render_overlays(
+        &mut self,
+        hub: &Hub,
+        scene: &Scene,
+        quad_slice: &gfx::Slice<back::Resources>,
+        logarithmic: Option<&(ZRange, f32)>,
+    ) {
+        // draw the shapes queued since the last frame
+        if !self.hud.vertices.is_empty() {
+            let vertices = self.hud.vertices
+                .iter()
+                .map(|vertex| HudVertex {
+                    pos: self.map_to_ndc(vertex.pos).into(),
+                    color: vertex.color,
+                })
+                .collect::<Vec<_>>();
+            if vertices.len() > self.hud_buf.len() {
+                self.hud_buf = self.factory
+                    .create_buffer(
+                        vertices.len(),
+                        gfx::buffer::Role::Vertex,
+                        gfx::memory::Usage::Dynamic,
+                        gfx::memory::Bind::TRANSFER_DST,
+                    )
+                    .unwrap();
+            }
+            self.encoder.update_buffer(&self.hud_buf, &vertices, 0).unwrap();
+            let slice = gfx::Slice {
+                start: 0,
+                end: vertices.len() as u32,
+                base_vertex: 0,
+                instances: None,
+                buffer: gfx::IndexBuffer::Auto,
+            };
+            let data = hud_pipe::Data {
+                vbuf: self.hud_buf.clone(),
+                target: self.out_color.clone(),
+            };
+            self.encoder.draw(&slice, &self.pso.hud, &data);
+            self.hud.clear();
         }
 
-        // draw ui text
-        for (_, font) in &self.font_cache {
-            font.draw(&mut self.encoder, &self.out_color, &self.out_depth);
+        // draw ui text of this scene
+        self.font_cache.clear();
+        let mut icons = Vec::new();
+        for w in hub.walk(&scene.first_child) {
+            if let SubNode::UiText(ref text) = w.node.sub_node {
+                let mut section = text.placed(&w.world_transform, w.world_opacity);
+                if let Some(&(ref zrange, scale)) = logarithmic {
+                    if section.z != DEPTH_ON_TOP {
+                        section.z = logarithmic_depth(zrange, scale, section.z);
+                    }
+                }
+                icons.extend(text.font.queue(section));
+                self.font_cache
+                    .entry(text.font.id.clone())
+                    .or_insert_with(|| text.font.clone());
+            }
+        }
+        for font in self.font_cache.values() {
+            font.draw(&mut self.encoder, &self.out_color, self.out_format, &self.out_depth);
+        }
+        for icon in &icons {
+            let p0 = self.map_to_ndc([icon.rect[0], icon.rect[3]]);
+            let p1 = self.map_to_ndc([icon.rect[2], icon.rect[1]]);
+            self.encoder.update_constant_buffer(
+                &self.icon_buf,
+                &IconParams {
+                    rect: [p0.x, p0.y, p1.x, p1.y],
+                    uv_range: icon.texture.uv_range(),
+                    color: icon.color,
+                    depth: icon.depth,
+                    distance_range: icon.distance_range,
+                },
+            );
+            let data = icon_pipe::Data {
+                params: self.icon_buf.clone(),
+                map: icon.texture.to_param(),
+                target: self.out_color.clone(),
+                depth_target: self.out_depth.clone(),
+            };
+            self.encoder.draw(quad_slice, &self.pso.icon, &data);
         }
 
         // draw debug quads
@@ -1153,107 +2957,1431 @@ impl Renderer {
                 target: self.out_color.clone(),
                 depth_target: self.out_depth.clone(),
             };
-            self.encoder.draw(&quad_slice, &self.pso.quad, &data);
+            self.encoder.draw(quad_slice, &self.pso.quad, &data);
         }
-
-        self.encoder.flush(&mut self.device);
     }
 
-    //TODO: make it generic over `gfx::Resources`
-    #[inline]
-    fn render_mesh(
-        encoder: &mut gfx::Encoder<back::Resources, back::CommandBuffer>,
-        const_buf: h::Buffer<back::Resources, Globals>,
-        inst_buf: h::Buffer<back::Resources, Instance>,
-        light_buf: h::Buffer<back::Resources, LightParam>,
-        pbr_buf: h::Buffer<back::Resources, PbrParams>,
-        displacement_contributions_buf: h::Buffer<back::Resources, DisplacementContribution>,
-        out_color: h::RenderTargetView<back::Resources, ColorFormat>,
-        out_depth: h::DepthStencilView<back::Resources, DepthFormat>,
-        pso: &PipelineStates<back::Resources>,
-        map_default: &Texture<[f32; 4]>,
-        instances: &[Instance],
-        vertex_buf: h::Buffer<back::Resources, Vertex>,
-        mut slice: gfx::Slice<back::Resources>,
-        material: &Material,
-        shadow_sampler: &h::Sampler<back::Resources>,
-        shadow0: &h::ShaderResourceView<back::Resources, f32>,
-        shadow1: &h::ShaderResourceView<back::Resources, f32>,
-        displacement_contributions: &[DisplacementContribution],
-        displacements: (h::ShaderResourceView<back::Resources, [f32; 4]>, h::Sampler<back::Resources>),
-        joint_transform_buffer_view: h::ShaderResourceView<back::Resources, [f32; 4]>,
-        displace: bool,
+    /// Renders the scene into the scaled target and stretches it over the
+    /// window, see [`set_render_scale`](#method.set_render_scale). HDR
+    /// frames are tone mapped on the way, see [`set_hdr`](#method.set_hdr).
+    fn render_scaled(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
     ) {
-        encoder.update_buffer(&inst_buf, instances, 0).unwrap();
+        let size = self.size.to_physical(self.dpi);
+        let width = ((size.width as f32 * self.render_scale).round() as u16).max(1);
+        let height = ((size.height as f32 * self.render_scale).round() as u16).max(1);
+        let format = if self.hdr { HdrColorFormat::get_format() } else { ColorFormat::get_format() };
+        let target = match self.scaled_target.take() {
+            Some(target) if target.size == [width, height] && target.format == format => target,
+            _ if self.hdr => RenderTarget::new_hdr(&mut self.factory, self.map_default.to_param().1, width, height),
+            _ => self.render_target(width, height),
+        };
+        self.render_offscreen(scene, camera, &target, None);
 
-        if instances.len() > 1 {
-            slice.instances = Some((instances.len() as u32, 0));
+        if self.hdr {
+            if self.hdr_pso.is_none() {
+                let ps = Source::default("post_hdr", "ps").unwrap();
+                let pso = post_pipeline(&mut self.factory, &ps)
+                    .unwrap_or_else(|e| panic!("Unable to create the HDR pipeline: {}", e));
+                self.hdr_pso = Some(pso);
+            }
+            let pso = self.hdr_pso.take().unwrap();
+            let mut params = [[0.0; 4]; 4];
+            params[0] = [self.exposure, self.tone_mapping.shader_index(), 0.0, 0.0];
+            self.encoder.clear_depth(&self.out_depth, 1.0);
+            self.encoder.clear_stencil(&self.out_depth, 0);
+            self.draw_post(&pso, params, target.texture(), None, None);
+            self.hdr_pso = Some(pso);
+            self.present_overlays(scene, false);
+            self.scaled_target = Some(target);
+            return;
         }
 
-        //TODO: batch per PSO
-        match material.to_pso_data() {
-            PsoData::Pbr { maps, mut params } => {
-                if displace {
-                    let data = if displacement_contributions.len() > MAX_TARGETS {
-                        error!("Too many mesh targets ({})!", displacement_contributions.len());
-                        &displacement_contributions[.. MAX_TARGETS]
-                    } else {
-                        displacement_contributions
-                    };
-                    encoder.update_buffer(&displacement_contributions_buf, data, 0).unwrap();
-                    params.pbr_flags |= PbrFlags::DISPLACEMENT_BUFFER.bits();
-                }
-                encoder.update_constant_buffer(&pbr_buf, &params);
-                let map_params = maps.into_params(map_default);
-                let data = pbr_pipe::Data {
-                    vbuf: vertex_buf,
-                    inst_buf,
-                    globals: const_buf,
-                    lights: light_buf,
-                    params: pbr_buf,
-                    base_color_map: map_params.base_color,
-                    normal_map: map_params.normal,
-                    emissive_map: map_params.emissive,
-                    metallic_roughness_map: map_params.metallic_roughness,
-                    occlusion_map: map_params.occlusion,
-                    color_target: out_color,
-                    depth_target: out_depth,
-                    displacement_contributions: displacement_contributions_buf,
-                    displacements,
-                    joint_transforms: joint_transform_buffer_view,
-                };
-                encoder.draw(&slice, &pso.pbr, &data);
-            }
-            PsoData::Basic { map, .. } => {
-                //TODO: avoid excessive cloning
-                let data = basic_pipe::Data {
-                    vbuf: vertex_buf,
-                    inst_buf,
-                    cb_lights: light_buf,
-                    cb_globals: const_buf.clone(),
-                    tex_map: map.unwrap_or(map_default.clone()).to_param(),
-                    shadow_map0: (shadow0.clone(), shadow_sampler.clone()),
-                    shadow_map1: (shadow1.clone(), shadow_sampler.clone()),
-                    out_color,
-                    out_depth: (out_depth, (0, 0)),
-                };
-                encoder.draw(&slice, pso.pso_by_material(&material), &data);
+        let quad_slice = gfx::Slice {
+            start: 0,
+            end: 4,
+            base_vertex: 0,
+            instances: None,
+            buffer: gfx::IndexBuffer::Auto,
+        };
+        self.encoder.clear_depth(&self.out_depth, 1.0);
+        self.encoder.clear_stencil(&self.out_depth, 0);
+        self.encoder.update_constant_buffer(
+            &self.quad_buf,
+            &QuadParams {
+                rect: [-1.0, -1.0, 1.0, 1.0],
+                depth: 1.0,
+            },
+        );
+        let (resource, sampler) = target.texture.to_param();
+        let data = quad_pipe::Data {
+            params: self.quad_buf.clone(),
+            globals: self.const_buf.clone(),
+            resource: resource.raw().clone(),
+            sampler,
+            target: self.out_color.clone(),
+            depth_target: self.out_depth.clone(),
+        };
+        self.encoder.draw(&quad_slice, &self.pso.quad, &data);
+
+        self.present_overlays(scene, false);
+        self.scaled_target = Some(target);
+    }
+
+    /// Creates a render target with the default sampler.
+    pub(crate) fn render_target(
+        &mut self,
+        width: u16,
+        height: u16,
+    ) -> RenderTarget {
+        RenderTarget::new(&mut self.factory, self.map_default.to_param().1, width, height)
+    }
+
+    /// Draws a full-screen quad with a post-processing pipeline into
+    /// `output`, or into the window.
+    pub(crate) fn draw_post(
+        &mut self,
+        pso: &PostPipelineState,
+        params: [[f32; 4]; 4],
+        input: &Texture<[f32; 4]>,
+        aux: Option<&Texture<[f32; 4]>>,
+        output: Option<&RenderTarget>,
+    ) {
+        let (width, height, target) = match output {
+            Some(target) => (target.size[0] as f32, target.size[1] as f32, target.color.clone()),
+            None => {
+                let size = self.size();
+                (size.x, size.y, self.out_color.clone())
             }
+        };
+        self.encoder.update_constant_buffer(
+            &self.post_buf,
+            &PostParams {
+                params,
+                resolution: [width, height, 1.0 / width.max(1.0), 1.0 / height.max(1.0)],
+            },
+        );
+        let data = post_pipe::Data {
+            params: self.post_buf.clone(),
+            input: input.to_param(),
+            aux: aux.unwrap_or(&self.map_default).to_param(),
+            target,
+        };
+        let quad_slice = gfx::Slice {
+            start: 0,
+            end: 4,
+            base_vertex: 0,
+            instances: None,
+            buffer: gfx::IndexBuffer::Auto,
+        };
+        self.encoder.draw(&quad_slice, pso, &data);
+    }
+
+    /// Draws the overlays of `scene` over the window, optionally clearing
+    /// the depth left by a previous frame, and submits the frame.
+    pub(crate) fn present_overlays(
+        &mut self,
+        scene: &Scene,
+        clear_depth: bool,
+    ) {
+        if clear_depth {
+            self.encoder.clear_depth(&self.out_depth, 1.0);
+            self.encoder.clear_stencil(&self.out_depth, 0);
         }
+        let quad_slice = gfx::Slice {
+            start: 0,
+            end: 4,
+            base_vertex: 0,
+            instances: None,
+            buffer: gfx::IndexBuffer::Auto,
+        };
+        let hub = scene.hub.lock().unwrap();
+        self.render_overlays(&hub, scene, &quad_slice, None);
+        self.encoder.flush(&mut self.device);
     }
 
-    /// Draw [`ShadowMap`](struct.ShadowMap.html) for debug purposes.
-    pub fn debug_shadow_quad(
+    /// Renders `scene` by `camera` into the rectangle of the window at `pos`
+    /// with `size`, in pixels from the top left corner, for split-screen
+    /// views. The projection uses the aspect ratio of the rectangle.
+    ///
+    /// The rest of the window is left as it is, so several viewports of the
+    /// same or different scenes can be drawn in one frame. UI text and debug
+    /// quads are not drawn into viewports.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let left = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// # let right = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// while window.update() {
+    ///     let size = window.size();
+    ///     let half = [size.x / 2.0, size.y];
+    ///     window.render_with_viewport(&left, [0.0, 0.0], half);
+    ///     window.render_with_viewport(&right, [half[0], 0.0], half);
+    /// }
+    /// ```
+    pub fn render_with_viewport<P, S>(
         &mut self,
-        map: &ShadowMap,
-        _num_components: u8,
-        pos: [i16; 2],
-        size: [u16; 2],
-    ) -> DebugQuadHandle {
-        DebugQuadHandle(self.debug_quads.create(DebugQuad {
-            resource: map.to_resource().raw().clone(),
-            pos: [pos[0] as i32, pos[1] as i32],
-            size: [size[0] as i32, size[1] as i32],
-        }))
+        scene: &Scene,
+        camera: &Camera,
+        pos: P,
+        size: S,
+    ) where
+        P: Into<mint::Point2<f32>>,
+        S: Into<mint::Vector2<f32>>,
+    {
+        let (pos, size) = (pos.into(), size.into());
+        let width = ((size.x * self.render_scale).round() as u16).max(1);
+        let height = ((size.y * self.render_scale).round() as u16).max(1);
+        let target = match self.viewport_targets.iter().position(|t| t.size == [width, height]) {
+            Some(index) => self.viewport_targets.remove(index),
+            None => self.render_target(width, height),
+        };
+        self.render_offscreen(scene, camera, &target, None);
+
+        let p0 = self.map_to_ndc([pos.x, pos.y + size.y]);
+        let p1 = self.map_to_ndc([pos.x + size.x, pos.y]);
+        let quad_slice = gfx::Slice {
+            start: 0,
+            end: 4,
+            base_vertex: 0,
+            instances: None,
+            buffer: gfx::IndexBuffer::Auto,
+        };
+        self.encoder.clear_depth(&self.out_depth, 1.0);
+        self.encoder.update_constant_buffer(
+            &self.quad_buf,
+            &QuadParams {
+                rect: [p0.x, p0.y, p1.x, p1.y],
+                depth: 1.0,
+            },
+        );
+        let (resource, sampler) = target.texture.to_param();
+        let data = quad_pipe::Data {
+            params: self.quad_buf.clone(),
+            globals: self.const_buf.clone(),
+            resource: resource.raw().clone(),
+            sampler,
+            target: self.out_color.clone(),
+            depth_target: self.out_depth.clone(),
+        };
+        self.encoder.draw(&quad_slice, &self.pso.quad, &data);
+        self.encoder.flush(&mut self.device);
+
+        self.viewport_targets.insert(0, target);
+        self.viewport_targets.truncate(MAX_VIEWPORT_TARGETS);
+    }
+
+    /// Renders `scene` by `camera` into `target` instead of the window.
+    ///
+    /// UI text and debug quads are not drawn into render targets.
+    pub fn render_to(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        target: &RenderTarget,
+    ) {
+        self.render_offscreen(scene, camera, target, None);
+    }
+
+    /// Renders the glowing parts of `scene` by `camera` into `target`: the
+    /// emissive colors of [`Pbr`] materials scaled by their
+    /// [`bloom_intensity`], over black. Everything else is drawn in black,
+    /// so that it still hides the glow behind it.
+    ///
+    /// Used by [`Bloom`] in selective mode, and useful for custom glow
+    /// effects.
+    ///
+    /// [`Pbr`]: ../material/struct.Pbr.html
+    /// [`bloom_intensity`]: ../material/struct.Pbr.html#structfield.bloom_intensity
+    /// [`Bloom`]: composer/struct.Bloom.html
+    pub fn render_emissive_to(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        target: &RenderTarget,
+    ) {
+        self.emissive_only = true;
+        self.render_offscreen(scene, camera, target, None);
+        self.emissive_only = false;
+    }
+
+    /// Renders only the depth of `scene` seen by `camera` into `target`,
+    /// the way shadow maps are drawn, for custom shadows, silhouettes or
+    /// depth-based effects reading it through [`Factory::depth_texture`].
+    ///
+    /// Every visible mesh is drawn opaque, whatever its material. Skinned
+    /// meshes are drawn in the pose of the last rendered frame.
+    ///
+    /// # Panics
+    /// Panics if `target` is a cube shadow map.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let camera = window.factory.perspective_camera(60.0, 1.0 .. 100.0);
+    /// let depth = window.factory.shadow_map(1024, 1024);
+    /// let mut outline = window.factory.shader_pass("shaders", "outline").unwrap();
+    /// outline.aux = Some(window.factory.depth_texture(&depth));
+    ///
+    /// // every frame, before the composer draws the pass
+    /// window.renderer.render_depth(&window.scene, &camera, &depth);
+    /// ```
+    ///
+    /// [`Factory::depth_texture`]: ../struct.Factory.html#method.depth_texture
+    pub fn render_depth(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        target: &ShadowMap,
+    ) {
+        assert!(!target.is_cube(), "Depth can't be rendered into a cube shadow map");
+        let mut hub = scene.hub.lock().unwrap();
+        hub.process_messages();
+        let mut mx_camera_transform = hub[&camera].transform;
+        for w in hub.walk(&scene.first_child) {
+            if w.node as *const _ == &hub[&camera] as *const _ {
+                mx_camera_transform = w.world_transform;
+                break;
+            }
+        }
+        let target = &target.to_targets()[0];
+        let (width, height, _, _) = target.get_dimensions();
+        let projection = match hub[&camera].sub_node {
+            SubNode::Camera(ref projection) => projection.clone(),
+            _ => panic!("Camera had incorrect sub node"),
+        };
+        let mx_view = Matrix4::from(mx_camera_transform.inverse_transform().unwrap());
+        let mx_proj = Matrix4::from(projection.matrix(width as f32 / height as f32));
+        self.draw_depth(&hub, scene, target, mx_view, mx_proj, &mx_camera_transform, Some(::std::f32::INFINITY));
+        self.encoder.flush(&mut self.device);
+    }
+
+    /// Renders into `target`, drawing only the `only` nodes if set.
+    pub(crate) fn render_offscreen(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        target: &RenderTarget,
+        only: Option<HashSet<*const NodeInternal>>,
+    ) {
+        let size = glutin::dpi::LogicalSize::new(target.size[0] as f64, target.size[1] as f64);
+        let out_color = mem::replace(&mut self.out_color, target.color.clone());
+        let out_format = mem::replace(&mut self.out_format, target.format);
+        let out_depth = mem::replace(&mut self.out_depth, target.depth.clone());
+        let window_size = mem::replace(&mut self.size, size);
+        let dpi = mem::replace(&mut self.dpi, 1.0);
+        self.offscreen = true;
+        self.only_nodes = only;
+
+        self.render(scene, camera);
+
+        self.only_nodes = None;
+        self.offscreen = false;
+        self.out_color = out_color;
+        self.out_format = out_format;
+        self.out_depth = out_depth;
+        self.size = window_size;
+        self.dpi = dpi;
+    }
+
+    /// Reads the colors rendered into `target` back from the GPU.
+    ///
+    /// This waits for the GPU to finish drawing, so it is best used for
+    /// occasional captures such as screenshots and thumbnails. See
+    /// [`read_target_async`](#method.read_target_async) for captures that
+    /// shouldn't stall the frame.
+    pub fn read_target(
+        &mut self,
+        target: &RenderTarget,
+    ) -> image::RgbaImage {
+        let (width, height) = (target.size[0] as u32, target.size[1] as u32);
+        let download = self.factory
+            .create_download_buffer::<[u8; 4]>((width * height) as usize)
+            .unwrap();
+        self.copy_target(target, &download);
+        self.download_image(&download, [width, height])
+    }
+
+    /// Starts reading the colors rendered into `target` back from the GPU,
+    /// without waiting for it to finish drawing.
+    ///
+    /// The result is usually available with
+    /// [`resolve_read`](#method.resolve_read) after the next frame, making
+    /// this cheap enough for recording every frame.
+    pub fn read_target_async(
+        &mut self,
+        target: &RenderTarget,
+    ) -> ReadRequest {
+        let size = [target.size[0] as u32, target.size[1] as u32];
+        let readback = self.start_readback((size[0] * size[1]) as usize);
+        let download = self.readbacks[readback.slot].buffer.clone();
+        self.copy_target(target, &download);
+        self.fence_readback(&readback);
+        ReadRequest { readback, size }
+    }
+
+    /// Returns the image of a [`read_target_async`](#method.read_target_async)
+    /// request once the GPU has completed it.
+    pub fn resolve_read(
+        &mut self,
+        request: &ReadRequest,
+    ) -> Readback<image::RgbaImage> {
+        if !self.readback_complete(&request.readback) {
+            return Readback::Pending;
+        }
+        let download = self.readbacks[request.readback.slot].buffer.clone();
+        Readback::Ready(self.download_image(&download, request.size))
+    }
+
+    /// Copies the colors of `target` into `download`.
+    fn copy_target(
+        &mut self,
+        target: &RenderTarget,
+        download: &h::Buffer<back::Resources, [u8; 4]>,
+    ) {
+        assert_eq!(target.format, ColorFormat::get_format(), "Only 8 bit render targets can be read back");
+        let info = target
+            .raw
+            .get_info()
+            .to_raw_image_info(gfx::format::ChannelType::Unorm, 0);
+        self.encoder
+            .copy_texture_to_buffer_raw(&target.raw, None, info, download.raw(), 0)
+            .unwrap();
+        self.encoder.flush(&mut self.device);
+    }
+
+    /// Reads an image of `size` copied into `download` by `copy_target`.
+    fn download_image(
+        &mut self,
+        download: &h::Buffer<back::Resources, [u8; 4]>,
+        size: [u32; 2],
+    ) -> image::RgbaImage {
+        let mapping = self.factory.read_mapping(download).unwrap();
+        let mut pixels = Vec::with_capacity(mapping.len() * 4);
+        // the rows are stored bottom to top
+        for row in mapping.chunks(size[0] as usize).rev() {
+            for texel in row {
+                pixels.extend_from_slice(texel);
+            }
+        }
+        image::RgbaImage::from_raw(size[0], size[1], pixels).unwrap()
+    }
+
+    /// Reserves a download buffer of `len` texels for a readback, taking
+    /// the next free slot of the ring. The ring grows while all of its
+    /// buffers are waiting on requests.
+    fn start_readback(
+        &mut self,
+        len: usize,
+    ) -> PendingReadback {
+        let count = self.readbacks.len();
+        let free = (0 .. count)
+            .map(|i| (self.next_readback + i) % count)
+            .filter(|&i| Rc::strong_count(&self.readbacks[i].token) == 1)
+            .collect::<Vec<_>>();
+        let slot = if count < READBACK_RING_SIZE || free.is_empty() {
+            let buffer = self.factory.create_download_buffer(len).unwrap();
+            self.readbacks.push(ReadbackSlot {
+                buffer,
+                token: Rc::new(()),
+                fence: None,
+            });
+            count
+        } else {
+            // replace a free buffer of another size rather than growing
+            let index = free
+                .iter()
+                .cloned()
+                .find(|&i| self.readbacks[i].buffer.len() == len)
+                .unwrap_or(free[0]);
+            if self.readbacks[index].buffer.len() != len {
+                self.readbacks[index].buffer = self.factory.create_download_buffer(len).unwrap();
+            }
+            // the request of the previous copy was dropped before it completed
+            self.delete_readback_fence(index);
+            index
+        };
+        self.next_readback = (slot + 1) % self.readbacks.len();
+        PendingReadback {
+            slot,
+            _token: self.readbacks[slot].token.clone(),
+        }
+    }
+
+    /// Places a fence after the copy of `readback`, which has been flushed.
+    fn fence_readback(
+        &mut self,
+        readback: &PendingReadback,
+    ) {
+        let mut fence = ptr::null();
+        unsafe {
+            self.device.with_gl(|gl| fence = gl.FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+        }
+        self.readbacks[readback.slot].fence = Some(fence);
+    }
+
+    /// Returns `true` once the GPU has completed the copy of `readback`, so
+    /// mapping its buffer won't stall.
+    fn readback_complete(
+        &mut self,
+        readback: &PendingReadback,
+    ) -> bool {
+        let fence = match self.readbacks[readback.slot].fence {
+            Some(fence) => fence,
+            None => return true,
+        };
+        let mut status = gl::WAIT_FAILED;
+        unsafe {
+            self.device.with_gl(|gl| status = gl.ClientWaitSync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, 0));
+        }
+        if status == gl::TIMEOUT_EXPIRED {
+            return false;
+        }
+        self.delete_readback_fence(readback.slot);
+        true
+    }
+
+    fn delete_readback_fence(
+        &mut self,
+        slot: usize,
+    ) {
+        if let Some(fence) = self.readbacks[slot].fence.take() {
+            unsafe {
+                self.device.with_gl(|gl| gl.DeleteSync(fence));
+            }
+        }
+    }
+
+    /// Returns the UI text or sprite under `point`, if any, for building
+    /// menus and buttons out of them.
+    ///
+    /// The point is in physical pixels from the top-left corner of the
+    /// window, like [`Input::mouse_pos`]. Texts are tested against the
+    /// rectangle covered by their glyphs, and take precedence over sprites,
+    /// as they are drawn on top. Sprites are tested against the screen
+    /// rectangle of their quad as seen by `camera`, the closest one winning.
+    /// Transparent parts of sprites count as hits.
+    ///
+    /// Unlike [`pick`](#method.pick), this doesn't touch the GPU and is cheap
+    /// enough to call every frame for hover effects.
+    ///
+    /// [`Input::mouse_pos`]: ../struct.Input.html#method.mouse_pos
+    pub fn hit_test_hud<P: Into<mint::Point2<f32>>>(
+        &self,
+        scene: &Scene,
+        camera: &Camera,
+        point: P,
+    ) -> Option<Base> {
+        let point = point.into();
+        let mut hub = scene.hub.lock().unwrap();
+        hub.process_messages();
+        let inside = |rect: [f32; 4]| {
+            rect[0] <= point.x && point.x <= rect[2] && rect[1] <= point.y && point.y <= rect[3]
+        };
+
+        let mut mx_camera_transform = hub[&camera].transform;
+        let mut text_hit = None;
+        let mut sprites = Vec::new();
+        for w in hub.walk(&scene.first_child) {
+            if ptr::eq(w.node, &hub[&camera]) {
+                mx_camera_transform = w.world_transform;
+            }
+            if w.world_opacity <= 0.0 {
+                continue;
+            }
+            match w.node.sub_node {
+                SubNode::UiText(ref text) => {
+                    let section = text.placed(&w.world_transform, w.world_opacity);
+                    // later texts are drawn over the earlier ones
+                    if text.font.pixel_bounds(section).map_or(false, &inside) {
+                        text_hit = Some(w.node_ptr.clone());
+                    }
+                }
+                SubNode::Visual(Material::Sprite(_), _, _) => {
+                    sprites.push((w.node_ptr.clone(), w.world_transform));
+                }
+                _ => {}
+            }
+        }
+        if let Some(ptr) = text_hit {
+            return Some(hub.upgrade_ptr(ptr));
+        }
+
+        let mx_view = Matrix4::from(mx_camera_transform.inverse_transform().unwrap());
+        let projection = match hub[&camera].sub_node {
+            SubNode::Camera(ref projection) => projection.clone(),
+            _ => panic!("Camera had incorrect sub node")
+        };
+        let mx_vp = Matrix4::from(projection.matrix(self.aspect_ratio())) * mx_view;
+        let mut closest = None;
+        for (ptr, world_transform) in sprites {
+            let mx = mx_vp * Matrix4::from(world_transform);
+            let mut rect = [f32::MAX, f32::MAX, f32::MIN, f32::MIN];
+            let mut depth = f32::MAX;
+            let mut behind = false;
+            for &(x, y) in &[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let clip = mx * Vector4::new(x, y, 0.0, 1.0);
+                if clip.w <= 0.0 {
+                    behind = true;
+                    break;
+                }
+                let screen = self.map_from_ndc([clip.x / clip.w, clip.y / clip.w]);
+                rect = [
+                    rect[0].min(screen.x),
+                    rect[1].min(screen.y),
+                    rect[2].max(screen.x),
+                    rect[3].max(screen.y),
+                ];
+                depth = depth.min(clip.z / clip.w);
+            }
+            if behind || !inside(rect) {
+                continue;
+            }
+            match closest {
+                Some((_, closest_depth)) if closest_depth <= depth => {}
+                _ => closest = Some((ptr, depth)),
+            }
+        }
+        closest.map(|(ptr, _)| hub.upgrade_ptr(ptr))
+    }
+
+    /// Returns the mesh visible at `point` from `camera`, if any.
+    ///
+    /// The point is in physical pixels from the top-left corner of the
+    /// window, like [`Input::mouse_pos`]. Every mesh is drawn with a unique
+    /// color into an offscreen buffer and the pixel under the point is read
+    /// back, so unlike [ray casting] the result matches skinned and morphed
+    /// meshes exactly, in the pose of the last rendered frame. Lines and
+    /// sprites can't be picked.
+    ///
+    /// This waits for the GPU to finish drawing, so it is best called only
+    /// when needed, e.g. on mouse clicks. Use [`pick_async`](#method.pick_async)
+    /// for hover effects.
+    ///
+    /// [`Input::mouse_pos`]: ../struct.Input.html#method.mouse_pos
+    /// [ray casting]: ../raycast/index.html
+    pub fn pick<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        point: P,
+    ) -> Option<Base> {
+        let download = self.pick_target.download.clone();
+        let objects = self.draw_pick(scene, camera, point.into(), &download);
+        let texel = self.factory.read_mapping(&download).unwrap()[0];
+        Self::picked_object(texel, objects)
+    }
+
+    /// Starts a [`pick`](#method.pick) without waiting for the GPU to finish
+    /// drawing.
+    ///
+    /// The result is usually available with
+    /// [`resolve_pick`](#method.resolve_pick) after the next frame, and
+    /// matches the scene as it was when requested.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+    /// use three::render::Readback;
+    ///
+    /// let mut request = None;
+    /// while window.update() {
+    ///     if let Some(pending) = request.take() {
+    ///         match window.renderer.resolve_pick(&pending) {
+    ///             Readback::Ready(hovered) => println!("hovering {:?}", hovered),
+    ///             Readback::Pending => request = Some(pending),
+    ///         }
+    ///     }
+    ///     if request.is_none() {
+    ///         let point = window.input.mouse_pos();
+    ///         request = Some(window.renderer.pick_async(&window.scene, &camera, point));
+    ///     }
+    ///     window.render(&camera);
+    /// }
+    /// ```
+    pub fn pick_async<P: Into<mint::Point2<f32>>>(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        point: P,
+    ) -> PickRequest {
+        let readback = self.start_readback(1);
+        let download = self.readbacks[readback.slot].buffer.clone();
+        let objects = self.draw_pick(scene, camera, point.into(), &download);
+        self.fence_readback(&readback);
+        PickRequest { readback, objects }
+    }
+
+    /// Returns the result of a [`pick_async`](#method.pick_async) request
+    /// once the GPU has completed it.
+    pub fn resolve_pick(
+        &mut self,
+        request: &PickRequest,
+    ) -> Readback<Option<Base>> {
+        if !self.readback_complete(&request.readback) {
+            return Readback::Pending;
+        }
+        let download = &self.readbacks[request.readback.slot].buffer;
+        let texel = self.factory.read_mapping(download).unwrap()[0];
+        Readback::Ready(Self::picked_object(texel, request.objects.clone()))
+    }
+
+    /// Finds the object of the ID read from the pick buffer.
+    fn picked_object(
+        texel: [u8; 4],
+        objects: Vec<Base>,
+    ) -> Option<Base> {
+        let id = texel
+            .iter()
+            .rev()
+            .fold(0, |id, &byte| (id << 8) | byte as usize);
+        if id == 0 {
+            return None;
+        }
+        objects.into_iter().nth(id - 1)
+    }
+
+    /// Draws the IDs of the meshes around `point` and copies the one under
+    /// it into `download`, returning the meshes by ID.
+    fn draw_pick(
+        &mut self,
+        scene: &Scene,
+        camera: &Camera,
+        point: mint::Point2<f32>,
+        download: &h::Buffer<back::Resources, [u8; 4]>,
+    ) -> Vec<Base> {
+        let mut hub = scene.hub.lock().unwrap();
+        hub.process_messages();
+
+        let mut mx_camera_transform = hub[&camera].transform;
+        let mut objects = Vec::new();
+        for w in hub.walk(&scene.first_child) {
+            if ptr::eq(w.node, &hub[&camera]) {
+                mx_camera_transform = w.world_transform;
+            }
+            match w.node.sub_node {
+                SubNode::Visual(Material::Line(_), _, _) |
+                SubNode::Visual(Material::Sprite(_), _, _) => {}
+                SubNode::Visual(..) if w.world_opacity > 0.0 => objects.push(w),
+                _ => {}
+            }
+        }
+
+        // zoom the projection onto the pixel under the point
+        let size = self.size.to_physical(self.dpi);
+        let center = self.map_to_ndc([point.x.floor() + 0.5, point.y.floor() + 0.5]);
+        let mx_pick = Matrix4::from_nonuniform_scale(size.width as f32, size.height as f32, 1.0) *
+            Matrix4::from_translation(Vector3::new(-center.x, -center.y, 0.0));
+        let mx_view = Matrix4::from(mx_camera_transform.inverse_transform().unwrap());
+        let projection = match hub[&camera].sub_node {
+            SubNode::Camera(ref projection) => projection.clone(),
+            _ => panic!("Camera had incorrect sub node")
+        };
+        let mx_proj = Matrix4::from(projection.matrix(self.aspect_ratio()));
+        self.encoder.update_constant_buffer(
+            &self.const_buf,
+            &Globals {
+                mx_vp: (mx_pick * mx_proj * mx_view).into(),
+                mx_view: mx_view.into(),
+                mx_inv_proj: mx_proj.invert().unwrap().into(),
+                fog_color: [0.0; 4],
+                fog_range: [0.0; 4],
+                depth_params: [0.0; 4],
+                probe_a: [[0.0; 4]; 4],
+                probe_b: [[0.0; 4]; 4],
+                probe_c: [0.0; 4],
+                reflection_params: [0.0; 4],
+                num_lights: 0,
+            },
+        );
+        self.encoder.clear(&self.pick_target.color, [0.0; 4]);
+        self.encoder.clear_depth(&self.pick_target.depth, 1.0);
+
+        for (i, w) in objects.iter().enumerate() {
+            let (gpu_data, skeleton) = match w.node.sub_node {
+                SubNode::Visual(_, ref gpu_data, ref skeleton) => (gpu_data, skeleton),
+                _ => unreachable!(),
+            };
+            // zero is left for the background
+            let id = i as u32 + 1;
+            let mut world_transform = w.world_transform;
+            if w.node.billboard {
+                world_transform.rot = mx_camera_transform.rot;
+            }
+            let mx_world: mint::ColumnMatrix4<_> = Matrix4::from(world_transform).into();
+            let mut instance = Instance::pbr(mx_world.into());
+            for (k, channel) in instance.color.iter_mut().enumerate() {
+                *channel = ((id >> (8 * k)) & 0xFF) as f32 / 255.0;
+            }
+            if gpu_data.displacements.is_some() {
+                instance.mat_params[0] = 1.0;
+                let count = gpu_data.displacement_contributions.len().min(MAX_TARGETS);
+                self.encoder
+                    .update_buffer(
+                        &self.displacement_contributions_buf,
+                        &gpu_data.displacement_contributions[.. count],
+                        0,
+                    )
+                    .unwrap();
+            }
+            let slice = match self.upload_instances(gpu_data, instance, &world_transform, mx_camera_transform.rot) {
+                Some(slice) => slice,
+                None => continue,
+            };
+
+            let (joint_buffer_view, displacement_view) = self.deformation_views(&hub, gpu_data, skeleton);
+            let data = pick_pipe::Data {
+                vbuf: gpu_data.vertices.clone(),
+                inst_buf: gpu_data.instances.clone(),
+                globals: self.const_buf.clone(),
+                displacement_contributions: self.displacement_contributions_buf.clone(),
+                joint_transforms: joint_buffer_view,
+                displacements: (displacement_view, self.map_default.to_param().1),
+                target: self.pick_target.color.clone(),
+                depth_target: self.pick_target.depth.clone(),
+            };
+            let pso = if w.world_transform.scale < 0.0 {
+                &self.pso.pick_mirrored
+            } else {
+                &self.pso.pick
+            };
+            self.encoder.draw(&slice, pso, &data);
+        }
+
+        let info = self.pick_target
+            .texture
+            .get_info()
+            .to_raw_image_info(gfx::format::ChannelType::Unorm, 0);
+        self.encoder
+            .copy_texture_to_buffer_raw(
+                self.pick_target.texture.raw(),
+                None,
+                info,
+                download.raw(),
+                0,
+            )
+            .unwrap();
+        self.encoder.flush(&mut self.device);
+
+        objects
+            .iter()
+            .map(|w| hub.upgrade_ptr(w.node_ptr.clone()))
+            .collect()
+    }
+
+    /// Draws a single visual node, optionally deferring it to the instance cache.
+    fn render_visual(
+        &mut self,
+        hub: &Hub,
+        w: &WalkedNode,
+        instanced: bool,
+        shadows: &SceneTextures,
+        view_rotation: Quaternion<f32>,
+    ) {
+        let (material, gpu_data, skeleton) = match w.node.sub_node {
+            SubNode::Visual(ref material, ref gpu_data, ref skeleton) => {
+                (material, gpu_data, skeleton)
+            }
+            _ => return,
+        };
+
+        let mx_world: mint::ColumnMatrix4<_> = Matrix4::from(w.world_transform).into();
+        // a negative scale turns the object inside-out
+        let mirrored = w.world_transform.scale < 0.0;
+        let pso_data = material.to_pso_data();
+
+        let instance = match pso_data {
+            PsoData::Basic { color, map, param0, emissive, .. } => {
+                let color = if self.emissive_only { color::BLACK } else { color };
+                // without a map, the other maps cover the whole texture
+                let uv_range = match map {
+                    Some(ref map) => map.uv_range(),
+                    None => [0.0, 0.0, 1.0, 1.0],
+                };
+                let mut instance = Instance::basic(mx_world.into(), color, w.world_opacity, uv_range, param0);
+                if !self.emissive_only {
+                    let [r, g, b] = color::to_linear_rgb(emissive);
+                    instance.mat_params = [param0, r, g, b];
+                }
+                match *material {
+                    Material::Line(ref line) if gpu_data.line_quads => {
+                        instance.mat_params = self.line_params(line);
+                    }
+                    Material::CustomBasic(ref custom) => instance.mat_params = custom.params,
+                    _ => {}
+                }
+                if let Some(key) = gpu_data.instance_cache_key.as_ref().filter(|_| instanced) {
+                    let key = InstanceCacheKey {
+                        material: material.batch_key(),
+                        geometry: key.geometry.clone(),
+                    };
+                    let data = self.instance_cache
+                        .entry((key, mirrored))
+                        .or_insert_with(|| InstanceData {
+                            slice: gpu_data.slice.clone(),
+                            vertices: gpu_data.vertices.clone(),
+                            material: material.clone(),
+                            mirrored,
+                            line_quads: gpu_data.line_quads,
+                            list: Vec::new(),
+                        });
+                    data.list.push(instance);
+                    // Create a new instance and defer the draw call.
+                    return;
+                }
+                instance
+            }
+            PsoData::Pbr { .. } => {
+                Instance::pbr(mx_world.into())
+            }
+        };
+        let placed;
+        let instances = match gpu_data.instance_list {
+            Some(ref list) => {
+                placed = {
+                    let mut instances = list.place(&instance, Matrix4::from(w.world_transform), view_rotation);
+                    if !self.emissive_only {
+                        list.tint(&mut instances);
+                    }
+                    instances
+                };
+                if placed.is_empty() {
+                    return;
+                }
+                &placed[..]
+            }
+            None => slice::from_ref(&instance),
+        };
+        let (joint_buffer_view, displacement_view) = self.deformation_views(hub, gpu_data, skeleton);
+        if let Err(diagnostic) = self.pso.prepare_variant(&mut self.factory, material, mirrored, gpu_data.line_quads, w.world_opacity < 1.0) {
+            self.report(diagnostic);
+        }
+        let count = gpu_data.displacement_contributions.len();
+        if gpu_data.displacements.is_some() && count > MAX_TARGETS {
+            self.report(Diagnostic::TooManyMorphTargets { count, max: MAX_TARGETS });
+        }
+
+        Self::render_mesh(
+            &mut self.encoder,
+            self.const_buf.clone(),
+            gpu_data.instances.clone(),
+            self.light_buf.clone(),
+            self.pbr_buf.clone(),
+            self.user_buf.clone(),
+            &self.user_params,
+            self.displacement_contributions_buf.clone(),
+            self.out_color.clone(),
+            self.out_depth.clone(),
+            &self.pso,
+            &self.map_default,
+            instances,
+            gpu_data.vertices.clone(),
+            gpu_data.slice.clone(),
+            &material,
+            shadows,
+            &gpu_data.displacement_contributions,
+            (displacement_view, self.map_default.to_param().1),
+            joint_buffer_view,
+            gpu_data.displacements.is_some(),
+            mirrored,
+            gpu_data.line_quads,
+            w.world_opacity,
+            self.emissive_only,
+        );
+    }
+
+    /// Instance parameters of a line drawn as quads: its half width in clip
+    /// space units along X and Y, and the lengths of its dashes and gaps.
+    fn line_params(
+        &self,
+        line: &material::Line,
+    ) -> [f32; 4] {
+        let (width, height, _, _) = self.out_color.get_dimensions();
+        let line_width = line.width.max(0.01);
+        let (dash, gap) = match line.dash {
+            Some(ref pattern) => (pattern.dash.max(0.0), pattern.gap.max(0.0)),
+            None => (0.0, 0.0),
+        };
+        // half of the width in pixels is the full width in clip space units
+        [line_width / width as f32, line_width / height as f32, dash, gap]
+    }
+
+    /// Clears `target` and draws the depth of the visible meshes of `scene`
+    /// into it. With `max_distance`, meshes and groups outside of the view,
+    /// or meshes further than it from the `viewer` position, are skipped.
+    /// Billboards face the `viewer`.
+    fn draw_depth(
+        &mut self,
+        hub: &Hub,
+        scene: &Scene,
+        target: &h::DepthStencilView<back::Resources, ShadowFormat>,
+        mx_view: Matrix4<f32>,
+        mx_proj: Matrix4<f32>,
+        viewer: &TransformInternal,
+        max_distance: Option<f32>,
+    ) {
+        let origin = viewer.disp;
+        self.encoder.clear_depth(target, 1.0);
+        let mx_vp = mx_proj * mx_view;
+        let frustum = Frustum::from_matrix(mx_vp);
+        self.encoder.update_constant_buffer(
+            &self.const_buf,
+            &Globals {
+                mx_vp: mx_vp.into(),
+                mx_view: mx_view.into(),
+                mx_inv_proj: mx_proj.into(),
+                fog_color: [0.0; 4],
+                fog_range: [0.0; 4],
+                depth_params: [0.0; 4],
+                probe_a: [[0.0; 4]; 4],
+                probe_b: [[0.0; 4]; 4],
+                probe_c: [0.0; 4],
+                reflection_params: [0.0; 4],
+                num_lights: 0,
+            },
+        );
+
+        let walker = if max_distance.is_some() {
+            hub.walk_culled(&scene.first_child, &frustum)
+        } else {
+            hub.walk(&scene.first_child)
+        };
+        for w in walker {
+            let (gpu_data, skeleton) = match w.node.sub_node {
+                SubNode::Visual(_, ref data, ref skeleton) if w.world_opacity > 0.0 => (data, skeleton),
+                _ => continue,
+            };
+            // skinned and morphed meshes move out of their rest bounds
+            let bounds = match (max_distance, gpu_data.bounds()) {
+                (Some(max_distance), Some((bounds, _))) if skeleton.is_none() && gpu_data.displacements.is_none() => {
+                    Some((bounds.transformed(&w.world_transform), max_distance))
+                }
+                _ => None,
+            };
+            if let Some((bounds, max_distance)) = bounds {
+                let (min, max): ([f32; 3], [f32; 3]) = (bounds.min.into(), bounds.max.into());
+                if !frustum.intersects_aabb(min, max) {
+                    continue;
+                }
+                let closest = Vector3::new(
+                    origin.x.max(bounds.min.x).min(bounds.max.x),
+                    origin.y.max(bounds.min.y).min(bounds.max.y),
+                    origin.z.max(bounds.min.z).min(bounds.max.z),
+                );
+                if (closest - origin).magnitude2() > max_distance * max_distance {
+                    continue;
+                }
+            }
+            let mx_world: mint::ColumnMatrix4<_> = Matrix4::from(w.world_transform).into();
+            let mut instance = Instance::pbr(mx_world.into());
+            if gpu_data.displacements.is_some() {
+                instance.mat_params[0] = 1.0;
+                let count = gpu_data.displacement_contributions.len().min(MAX_TARGETS);
+                self.encoder
+                    .update_buffer(
+                        &self.displacement_contributions_buf,
+                        &gpu_data.displacement_contributions[.. count],
+                        0,
+                    )
+                    .unwrap();
+            }
+            let slice = match self.upload_instances(gpu_data, instance, &w.world_transform, viewer.rot) {
+                Some(slice) => slice,
+                None => continue,
+            };
+            // skinned and morphed meshes are drawn in their current pose
+            let (joint_buffer_view, displacement_view) = self.deformation_views(hub, gpu_data, skeleton);
+            //TODO: avoid excessive cloning
+            let data = shadow_pipe::Data {
+                vbuf: gpu_data.vertices.clone(),
+                inst_buf: gpu_data.instances.clone(),
+                cb_globals: self.const_buf.clone(),
+                displacement_contributions: self.displacement_contributions_buf.clone(),
+                joint_transforms: joint_buffer_view,
+                displacements: (displacement_view, self.map_default.to_param().1),
+                target: target.clone(),
+            };
+            let pso = if w.world_transform.scale < 0.0 {
+                &self.pso.shadow_mirrored
+            } else {
+                &self.pso.shadow
+            };
+            self.encoder.draw(&slice, pso, &data);
+        }
+    }
+
+    /// Uploads `instance` into the instance buffer of a mesh, or a copy for
+    /// each instance of an instanced mesh, and returns the slice drawing all
+    /// of them. Returns `None` when there is nothing to draw.
+    fn upload_instances(
+        &mut self,
+        gpu_data: &GpuData,
+        instance: Instance,
+        world_transform: &TransformInternal,
+        view_rotation: Quaternion<f32>,
+    ) -> Option<gfx::Slice<back::Resources>> {
+        let mut slice = gpu_data.slice.clone();
+        match gpu_data.instance_list {
+            Some(ref list) => {
+                let instances = list.place(&instance, Matrix4::from(*world_transform), view_rotation);
+                if instances.is_empty() {
+                    return None;
+                }
+                self.encoder
+                    .update_buffer(&gpu_data.instances, &instances, 0)
+                    .unwrap();
+                slice.instances = Some((instances.len() as u32, 0));
+            }
+            None => {
+                self.encoder
+                    .update_buffer(&gpu_data.instances, &[instance], 0)
+                    .unwrap();
+            }
+        }
+        Some(slice)
+    }
+
+    /// Returns the joint transforms and morph target displacements of a mesh.
+    fn deformation_views(
+        &self,
+        hub: &Hub,
+        gpu_data: &GpuData,
+        skeleton: &Option<Skeleton>,
+    ) -> (
+        h::ShaderResourceView<back::Resources, [f32; 4]>,
+        h::ShaderResourceView<back::Resources, [f32; 4]>,
+    ) {
+        let joint_buffer_view = if let Some(ref ptr) = *skeleton {
+            match hub[ptr].sub_node {
+                SubNode::Skeleton(ref skeleton_data) => {
+                    skeleton_data.gpu_buffer_view.clone()
+                }
+                _ => unreachable!()
+            }
+        } else {
+            self.default_joint_buffer_view.clone()
+        };
+        let displacement_view = match gpu_data.displacements {
+            Some((_, ref view)) => view.clone(),
+            None => self.default_displacement_buffer_view.clone(),
+        };
+        (joint_buffer_view, displacement_view)
+    }
+
+    //TODO: make it generic over `gfx::Resources`
+    #[inline]
+    fn render_mesh(
+        encoder: &mut gfx::Encoder<back::Resources, back::CommandBuffer>,
+        const_buf: h::Buffer<back::Resources, Globals>,
+        inst_buf: h::Buffer<back::Resources, Instance>,
+        light_buf: h::Buffer<back::Resources, LightParam>,
+        pbr_buf: h::Buffer<back::Resources, PbrParams>,
+        user_buf: h::Buffer<back::Resources, UserParams>,
+        user_params: &UserParams,
+        displacement_contributions_buf: h::Buffer<back::Resources, DisplacementContribution>,
+        out_color: h::RawRenderTargetView<back::Resources>,
+        out_depth: h::DepthStencilView<back::Resources, DepthFormat>,
+        pso: &PipelineStates<back::Resources>,
+        map_default: &Texture<[f32; 4]>,
+        instances: &[Instance],
+        vertex_buf: h::Buffer<back::Resources, Vertex>,
+        mut slice: gfx::Slice<back::Resources>,
+        material: &Material,
+        shadows: &SceneTextures,
+        displacement_contributions: &[DisplacementContribution],
+        displacements: (h::ShaderResourceView<back::Resources, [f32; 4]>, h::Sampler<back::Resources>),
+        joint_transform_buffer_view: h::ShaderResourceView<back::Resources, [f32; 4]>,
+        displace: bool,
+        mirrored: bool,
+        line_quads: bool,
+        opacity: f32,
+        emissive_only: bool,
+    ) {
+        encoder.update_buffer(&inst_buf, instances, 0).unwrap();
+
+        if instances.len() > 1 {
+            slice.instances = Some((instances.len() as u32, 0));
+        }
+
+        if displace {
+            // the extra targets were reported by the caller
+            let data = &displacement_contributions[.. displacement_contributions.len().min(MAX_TARGETS)];
+            encoder.update_buffer(&displacement_contributions_buf, data, 0).unwrap();
+        }
+
+        //TODO: batch per PSO
+        match material.to_pso_data() {
+            PsoData::Pbr { maps, mut params } => {
+                if displace {
+                    params.pbr_flags |= PbrFlags::DISPLACEMENT_BUFFER.bits();
+                }
+                if emissive_only {
+                    params.pbr_flags |= PbrFlags::EMISSIVE_ONLY.bits();
+                }
+                params.base_color_factor[3] *= opacity;
+                encoder.update_constant_buffer(&pbr_buf, &params);
+                let map_params = maps.into_params(map_default);
+                let data = pbr_pipe::Data {
+                    vbuf: vertex_buf,
+                    inst_buf,
+                    globals: const_buf,
+                    lights: light_buf,
+                    params: pbr_buf,
+                    base_color_map: map_params.base_color,
+                    normal_map: map_params.normal,
+                    emissive_map: map_params.emissive,
+                    metallic_roughness_map: map_params.metallic_roughness,
+                    occlusion_map: map_params.occlusion,
+                    reflection_map: shadows.reflection.to_param(),
+                    color_target: out_color,
+                    depth_target: out_depth,
+                    displacement_contributions: displacement_contributions_buf,
+                    displacements,
+                    joint_transforms: joint_transform_buffer_view,
+                };
+                // faded objects blend without writing depth
+                let pso = match (mirrored, opacity < 1.0) {
+                    (false, false) => &pso.pbr,
+                    (true, false) => &pso.pbr_mirrored,
+                    (false, true) => &pso.pbr_blend,
+                    (true, true) => &pso.pbr_blend_mirrored,
+                };
+                encoder.draw(&slice, pso, &data);
+            }
+            PsoData::Basic { map, normal_map, specular_map, .. } => {
+                let user_override = match *material {
+                    Material::CustomBasic(ref custom) => custom.user_params,
+                    _ => None,
+                };
+                if let Some(params) = user_override {
+                    encoder.update_constant_buffer(&user_buf, &UserParams::from(params));
+                }
+                //TODO: avoid excessive cloning
+                let data = basic_pipe::Data {
+                    vbuf: vertex_buf,
+                    inst_buf,
+                    cb_lights: light_buf,
+                    cb_globals: const_buf.clone(),
+                    cb_user: user_buf.clone(),
+                    tex_map: map.unwrap_or(map_default.clone()).to_param(),
+                    normal_map: normal_map.unwrap_or(shadows.flat_normal.clone()).to_param(),
+                    specular_map: specular_map.unwrap_or(map_default.clone()).to_param(),
+                    shadow_map0: (shadows.flat[0].clone(), shadows.sampler.clone()),
+                    shadow_map1: (shadows.flat[1].clone(), shadows.sampler.clone()),
+                    shadow_cube0: (shadows.cube[0].clone(), shadows.sampler.clone()),
+                    shadow_cube1: (shadows.cube[1].clone(), shadows.sampler.clone()),
+                    cascade0: (shadows.cascades[0].clone(), shadows.sampler.clone()),
+                    cascade1: (shadows.cascades[1].clone(), shadows.sampler.clone()),
+                    cascade2: (shadows.cascades[2].clone(), shadows.sampler.clone()),
+                    cascade3: (shadows.cascades[3].clone(), shadows.sampler.clone()),
+                    displacement_contributions: displacement_contributions_buf,
+                    joint_transforms: joint_transform_buffer_view,
+                    displacements,
+                    user_data: match *material {
+                        Material::CustomBasic(ref custom) => custom.data.as_ref().map(|data| data.view.clone()),
+                        _ => None,
+                    }.unwrap_or(shadows.empty_buffer.clone()),
+                    out_color,
+                    out_depth: (out_depth, (material.stencil_reference(), material.stencil_reference())),
+                };
+                encoder.draw(&slice, pso.pso_by_material(&material, mirrored, line_quads, opacity < 1.0), &data);
+                if user_override.is_some() {
+                    // restore the global parameters for the other materials
+                    encoder.update_constant_buffer(&user_buf, user_params);
+                }
+            }
+        }
+    }
+
+    /// Draws a texture on top of the screen, at `pos` pixels from the top-left
+    /// corner. Negative coordinates are measured from the opposite edges.
+    ///
+    /// The quad is removed when the returned handle is dropped.
+    pub fn texture_quad(
+        &mut self,
+        texture: &Texture<[f32; 4]>,
+        pos: [i16; 2],
+        size: [u16; 2],
+    ) -> DebugQuadHandle {
+        DebugQuadHandle(self.debug_quads.create(DebugQuad {
+            resource: texture.to_param().0.raw().clone(),
+            pos: [pos[0] as i32, pos[1] as i32],
+            size: [size[0] as i32, size[1] as i32],
+        }))
+    }
+
+    /// Draw [`ShadowMap`](struct.ShadowMap.html) for debug purposes. Only
+    /// flat maps can be drawn, not cube ones.
+    pub fn debug_shadow_quad(
+        &mut self,
+        map: &ShadowMap,
+        _num_components: u8,
+        pos: [i16; 2],
+        size: [u16; 2],
+    ) -> DebugQuadHandle {
+        DebugQuadHandle(self.debug_quads.create(DebugQuad {
+            resource: map.to_resource().raw().clone(),
+            pos: [pos[0] as i32, pos[1] as i32],
+            size: [size[0] as i32, size[1] as i32],
+        }))
+    }
+}
+
+/// Creates a full-screen pipeline drawing the pixel shader `ps`.
+pub(crate) fn post_pipeline(
+    factory: &mut back::Factory,
+    ps: &Source,
+) -> Result<PostPipelineState, PipelineCreationError> {
+    let vs = Source::default("post", "vs")?;
+    let shaders = factory
+        .create_shader_set(vs.0.as_bytes(), ps.0.as_bytes())
+        .map_err(|err| diagnostics::program_error(err, &vs.0, &ps.0))?;
+    let program = factory
+        .create_program(&shaders)
+        .map_err(|err| PipelineCreationError::Link(err.to_string()))?;
+    factory
+        .create_pipeline_from_program(
+            &program,
+            gfx::Primitive::TriangleStrip,
+            gfx::state::Rasterizer::new_fill(),
+            post_pipe::new(),
+        )
+        .map_err(diagnostics::state_error)
+}
+
+/// Creates a pipeline drawing the pixel shader `ps` behind the scene.
+pub(crate) fn background_pipeline(
+    factory: &mut back::Factory,
+    ps: &Source,
+) -> Result<BackgroundPipelineState, PipelineCreationError> {
+    let vs = Source::default("background", "vs")?;
+    let shaders = factory
+        .create_shader_set(vs.0.as_bytes(), ps.0.as_bytes())
+        .map_err(|err| diagnostics::program_error(err, &vs.0, &ps.0))?;
+    let program = factory
+        .create_program(&shaders)
+        .map_err(|err| PipelineCreationError::Link(err.to_string()))?;
+    factory
+        .create_pipeline_from_program(
+            &program,
+            gfx::Primitive::TriangleStrip,
+            gfx::state::Rasterizer::new_fill(),
+            background_pipe::new(),
+        )
+        .map_err(diagnostics::state_error)
+}
+
+/// Fits an orthographic projection for each cascade of a directional light
+/// seen through `mx_light_view`, around the slice of the camera frustum
+/// ending at its split distance.
+///
+/// The projections cover spheres around the slices, so that their size
+/// doesn't change as the camera turns, and move by whole texels of maps of
+/// `sizes`, to keep the edges of the shadows from shimmering.
+fn fit_cascades(
+    mx_light_view: Matrix4<f32>,
+    mx_camera_world: Matrix4<f32>,
+    mx_camera_proj: Matrix4<f32>,
+    splits: &[f32],
+    sizes: &[u16],
+) -> Vec<Matrix4<f32>> {
+    let mx_inv_proj = mx_camera_proj.invert().unwrap();
+    let unproject = |x: f32, y: f32, z: f32| {
+        let p = mx_inv_proj * Vector4::new(x, y, z, 1.0);
+        p.truncate() / p.w
+    };
+    // two points along each corner edge, the far plane may be infinite
+    let edges = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .iter()
+        .map(|&(x, y)| (unproject(x, y, -1.0), unproject(x, y, 0.0)))
+        .collect::<Vec<_>>();
+    let near = -edges[0].0.z;
+    let mx_camera_to_light = mx_light_view * mx_camera_world;
+    // casters up to the whole shadow distance towards the light are kept
+    let caster_distance = splits[splits.len() - 1];
+
+    splits
+        .iter()
+        .zip(sizes)
+        .enumerate()
+        .map(|(i, (&end, &size))| {
+            let start = if i == 0 { near } else { splits[i - 1] };
+            let corners = edges
+                .iter()
+                .flat_map(|&(a, b)| {
+                    [start, end].iter().map(move |&distance| {
+                        let t = (distance + a.z) / (a.z - b.z);
+                        a + (b - a) * t
+                    }).collect::<Vec<_>>()
+                })
+                .map(|corner| (mx_camera_to_light * corner.extend(1.0)).truncate())
+                .collect::<Vec<_>>();
+            let mut center = corners.iter().fold(Vector3::new(0.0, 0.0, 0.0), |sum, &corner| sum + corner) / corners.len() as f32;
+            let radius = corners.iter().map(|&corner| (corner - center).magnitude()).fold(0.0, f32::max).ceil();
+            let texel = 2.0 * radius / size.max(1) as f32;
+            center.x = (center.x / texel).floor() * texel;
+            center.y = (center.y / texel).floor() * texel;
+            // the light looks down the negative Z axis of its view
+            ortho(
+                center.x - radius,
+                center.x + radius,
+                center.y - radius,
+                center.y + radius,
+                -(center.z + radius + caster_distance),
+                -(center.z - radius),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_cube() -> Arc<Triangles> {
+        let positions = Aabb { min: Vector3::from_value(-0.5), max: Vector3::from_value(0.5) }.corners().to_vec();
+        Triangles::new(positions, vec![[0, 1, 2]], false)
+    }
+
+    fn translation(
+        x: f32,
+        y: f32,
+        z: f32,
+    ) -> mint::ColumnMatrix4<f32> {
+        Matrix4::from_translation(Vector3::new(x, y, z)).into()
+    }
+
+    #[test]
+    fn instance_bounds_cover_every_instance() {
+        let copy = unit_cube();
+        let mut list = InstanceList::default();
+        list.transforms = vec![translation(10.0, 0.0, 0.0), translation(0.0, -4.0, 2.0)];
+        list.update_bounds(Some(&copy));
+        let (bounds, radius) = list.bounds.unwrap();
+        assert_eq!(bounds.min, Vector3::new(-0.5, -4.5, -0.5));
+        assert_eq!(bounds.max, Vector3::new(10.5, 0.5, 2.5));
+        assert_eq!(radius, (bounds.max - bounds.min).magnitude() * 0.5);
+
+        list.transforms.clear();
+        list.update_bounds(Some(&copy));
+        assert!(list.bounds.unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn billboard_bounds_allow_any_rotation() {
+        let copy = unit_cube();
+        let mut list = InstanceList::default();
+        list.billboard = true;
+        list.transforms = vec![(Matrix4::from_translation(Vector3::new(0.0, 3.0, 0.0)) * Matrix4::from_scale(2.0)).into()];
+        list.update_bounds(Some(&copy));
+        let (bounds, _) = list.bounds.unwrap();
+        let reach = 2.0 * copy.radius;
+        assert_eq!(bounds.min, Vector3::new(-reach, 3.0 - reach, -reach));
+        assert_eq!(bounds.max, Vector3::new(reach, 3.0 + reach, reach));
     }
 }
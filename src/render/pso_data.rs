@@ -18,6 +18,7 @@ bitflags! {
         const EMISSIVE_MAP           = 1 << 3;
         const OCCLUSION_MAP          = 1 << 4;
         const DISPLACEMENT_BUFFER    = 1 << 5;
+        const EMISSIVE_ONLY          = 1 << 6;
     }
 }
 
@@ -67,6 +68,9 @@ pub(crate) enum PsoData {
         color: u32,
         param0: f32,
         map: Option<Texture<[f32; 4]>>,
+        normal_map: Option<Texture<[f32; 4]>>,
+        specular_map: Option<Texture<[f32; 4]>>,
+        emissive: u32,
     },
 }
 
@@ -95,13 +99,17 @@ impl Material {
                 let pbr_params = PbrParams {
                     base_color_factor: [bcf[0], bcf[1], bcf[2], material.base_color_alpha],
                     camera: [0.0, 0.0, 1.0],
-                    emissive_factor: [emf[0], emf[1], emf[2]],
+                    emissive_factor: [
+                        emf[0] * material.emissive_intensity,
+                        emf[1] * material.emissive_intensity,
+                        emf[2] * material.emissive_intensity,
+                    ],
                     metallic_roughness: [material.metallic_factor, material.roughness_factor],
                     normal_scale: material.normal_scale,
                     occlusion_strength: material.occlusion_strength,
                     pbr_flags: pbr_flags.bits(),
                     _padding0: unsafe { mem::uninitialized() },
-                    _padding1: unsafe { mem::uninitialized() },
+                    bloom_intensity: material.bloom_intensity,
                 };
                 PsoData::Pbr {
                     maps: PbrMaps {
@@ -118,36 +126,57 @@ impl Material {
                 color: params.color,
                 map: params.map.clone(),
                 param0: 0.0,
+                normal_map: None,
+                specular_map: None,
+                emissive: 0,
             },
             Material::CustomBasic(ref params) => PsoData::Basic {
                 color: params.color,
                 map: params.map.clone(),
                 param0: 0.0,
+                normal_map: None,
+                specular_map: None,
+                emissive: 0,
             },
             Material::Line(ref params) => PsoData::Basic {
                 color: params.color,
                 map: None,
                 param0: 0.0,
+                normal_map: None,
+                specular_map: None,
+                emissive: 0,
             },
             Material::Wireframe(ref params) => PsoData::Basic {
                 color: params.color,
                 map: None,
                 param0: 0.0,
+                normal_map: None,
+                specular_map: None,
+                emissive: 0,
             },
             Material::Lambert(ref params) => PsoData::Basic {
                 color: params.color,
-                map: None,
+                map: params.map.clone(),
                 param0: if params.flat { 0.0 } else { 1.0 },
+                normal_map: None,
+                specular_map: None,
+                emissive: params.emissive,
             },
             Material::Phong(ref params) => PsoData::Basic {
                 color: params.color,
-                map: None,
+                map: params.map.clone(),
                 param0: params.glossiness,
+                normal_map: params.normal_map.clone(),
+                specular_map: params.specular_map.clone(),
+                emissive: params.emissive,
             },
             Material::Sprite(ref params) => PsoData::Basic {
                 color: !0,
                 map: Some(params.map.clone()),
                 param0: 0.0,
+                normal_map: None,
+                specular_map: None,
+                emissive: 0,
             },
         }
     }
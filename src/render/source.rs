@@ -3,9 +3,9 @@
 use data;
 use util;
 
-use std::{io, ops, str};
+use std::{fs, io, ops, str};
 use std::borrow::Borrow;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 /// Source code for a single GLSL shader.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
@@ -18,12 +18,37 @@ impl ops::Deref for Source {
     }
 }
 
+/// Where the preprocessor looks for `#include "..."` files.
+struct Includes<'a> {
+    /// Directories searched after the one holding the including file.
+    paths: &'a [PathBuf],
+    /// Files being included, from the outermost one, to detect cycles.
+    stack: Vec<PathBuf>,
+}
+
 impl Source {
-    fn preprocess<P: AsRef<Path>>(
-        root: P,
+    /// Inserts the preprocessor `defines` after the `#version` directive.
+    pub(crate) fn with_defines(
+        &self,
+        defines: &str,
+    ) -> Source {
+        let split = if self.0.starts_with("#version") {
+            self.0.find('\n').map_or(self.0.len(), |pos| pos + 1)
+        } else {
+            0
+        };
+        let mut code = String::with_capacity(self.0.len() + defines.len());
+        code.push_str(&self.0[.. split]);
+        code.push_str(defines);
+        code.push_str(&self.0[split ..]);
+        Source(code)
+    }
+
+    fn preprocess(
+        dir: &Path,
         code: &str,
+        includes: &mut Includes,
     ) -> io::Result<String> {
-        let root = root.as_ref();
         let mut new_code = String::new();
         for line in code.lines() {
             if line.starts_with("#include") {
@@ -32,16 +57,23 @@ impl Source {
                         if let Some(pos) = arg[1 ..].find('>') {
                             let name = &arg[1 .. (pos + 1)];
                             let path = format!("data/shaders/{}.glsl", name);
-                            let content = &data::FILES.get(&path).unwrap();
-                            new_code += str::from_utf8(content.borrow()).unwrap();
+                            let content = match data::FILES.get(&path) {
+                                Ok(content) => content,
+                                Err(_) => {
+                                    let msg = format!("unknown built-in shader include <{}>", name);
+                                    return Err(io::Error::new(io::ErrorKind::NotFound, msg));
+                                }
+                            };
+                            let content = str::from_utf8(content.borrow()).unwrap();
+                            new_code += &Self::include(Path::new(""), PathBuf::from(path), content, includes)?;
                         }
                     } else if arg.starts_with('"') {
                         if let Some(pos) = arg[1 ..].find('"') {
                             let relative_path = &arg[1 .. (pos + 1)];
-                            let path = root.join(relative_path);
+                            let path = Self::find_include(dir, relative_path, includes.paths)?;
                             let content = util::read_file_to_string(&path)?;
-                            let include = Self::preprocess(root, &content)?;
-                            new_code += &include;
+                            let parent = path.parent().unwrap_or(Path::new("")).to_owned();
+                            new_code += &Self::include(&parent, path, &content, includes)?;
                         }
                     }
                 }
@@ -53,6 +85,49 @@ impl Source {
         Ok(new_code)
     }
 
+    /// Preprocesses the included file at `path`, failing if it is already
+    /// being included.
+    fn include(
+        dir: &Path,
+        path: PathBuf,
+        content: &str,
+        includes: &mut Includes,
+    ) -> io::Result<String> {
+        if includes.stack.contains(&path) {
+            let cycle: Vec<_> = includes
+                .stack
+                .iter()
+                .skip_while(|&p| p != &path)
+                .chain(Some(&path))
+                .map(|p| p.display().to_string())
+                .collect();
+            let msg = format!("shader include cycle: {}", cycle.join(" -> "));
+            return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+        }
+        includes.stack.push(path);
+        let result = Self::preprocess(dir, content, includes);
+        includes.stack.pop();
+        result
+    }
+
+    /// Finds `relative_path` in `dir`, then in each of the include `paths`.
+    fn find_include(
+        dir: &Path,
+        relative_path: &str,
+        paths: &[PathBuf],
+    ) -> io::Result<PathBuf> {
+        Some(dir)
+            .into_iter()
+            .chain(paths.iter().map(|p| p.as_path()))
+            .map(|p| p.join(relative_path))
+            .find(|p| p.is_file())
+            .map(|p| fs::canonicalize(&p).unwrap_or(p))
+            .ok_or_else(|| {
+                let msg = format!("shader include \"{}\" not found", relative_path);
+                io::Error::new(io::ErrorKind::NotFound, msg)
+            })
+    }
+
     /// Load the named shader from the default set of shaders.
     pub fn default(
         name: &str,
@@ -60,20 +135,45 @@ impl Source {
     ) -> io::Result<Self> {
         let path = format!("data/shaders/{}_{}.glsl", name, suffix);
         let unprocessed = data::FILES.get(&path).unwrap();
-        let processed = Self::preprocess("", str::from_utf8(unprocessed.borrow()).unwrap())?;
+        let mut includes = Includes {
+            paths: &[],
+            stack: vec![PathBuf::from(path)],
+        };
+        let processed = Self::preprocess(Path::new(""), str::from_utf8(unprocessed.borrow()).unwrap(), &mut includes)?;
         Ok(Source(processed))
     }
 
     /// Load the named shader from the given directory path.
+    ///
+    /// `#include "file"` directives are resolved relative to the including
+    /// file, and `#include <name>` directives refer to the built-in shaders.
     pub fn user<P: AsRef<Path>>(
         root: P,
         name: &str,
         suffix: &str,
+    ) -> io::Result<Self> {
+        Self::user_with_include_paths(root, &[], name, suffix)
+    }
+
+    /// Load the named shader from the given directory path, additionally
+    /// searching `include_paths` in order for `#include "file"` directives
+    /// that are not found relative to the including file.
+    ///
+    /// Fails if a file includes itself, directly or indirectly.
+    pub fn user_with_include_paths<P: AsRef<Path>>(
+        root: P,
+        include_paths: &[PathBuf],
+        name: &str,
+        suffix: &str,
     ) -> io::Result<Self> {
         let base_name = format!("{}_{}.glsl", name, suffix);
         let path = root.as_ref().join(&base_name);
-        let unprocessed = util::read_file_to_string(Path::new(&path))?;
-        let processed = Self::preprocess(root, &unprocessed)?;
+        let unprocessed = util::read_file_to_string(&path)?;
+        let mut includes = Includes {
+            paths: include_paths,
+            stack: vec![fs::canonicalize(&path).unwrap_or(path)],
+        };
+        let processed = Self::preprocess(root.as_ref(), &unprocessed, &mut includes)?;
         Ok(Source(processed))
     }
 }
@@ -127,8 +227,12 @@ macro_rules! decl_shaders {
 decl_shaders! {
     (basic, basic, Basic),
     (gouraud, Gouraud, Gouraud),
+    (hud, HUD, Hud),
+    (icon, icon, Icon),
+    (line, line, Line),
     (pbr, PBR, Pbr),
     (phong, Phong, Phong),
+    (pick, pick, Pick),
     (quad, quad, Quad),
     (shadow, shadow, Shadow),
     (skybox, skybox, Skybox),
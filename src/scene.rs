@@ -3,10 +3,15 @@
 use node;
 use color::Color;
 use hub::{Hub, HubPtr, SubNode};
+use light::{LightProbe, ReflectionProbe};
+use material::Material;
 use object::{Base, DowncastObject, Group, Object};
+use raycast::Aabb;
+use render::{BackgroundPipelineState, MAX_LIGHTS};
 use texture::{CubeMap, Texture};
 
-use std::mem;
+use std::collections::HashSet;
+use std::fmt;
 use std::marker::PhantomData;
 use std::sync::MutexGuard;
 
@@ -21,6 +26,233 @@ pub enum Background {
     Texture(Texture<[f32; 4]>),
     /// Skybox
     Skybox(CubeMap<[f32; 4]>),
+    /// Procedural background drawn by a pixel shader, such as an animated
+    /// gradient or a starfield.
+    Custom(BackgroundShader),
+    /// No background at all. Windows built with
+    /// [`Builder::transparent`](../window/struct.Builder.html#method.transparent)
+    /// show the desktop behind, others show black.
+    Transparent,
+}
+
+/// A pixel shader drawn behind the scene, shown with
+/// [`Background::Custom`]. Created with [`Factory::background_shader`].
+///
+/// The shader covers the pixels left empty by the objects of the scene.
+/// It reads its inputs with `#include <background>`, which declares:
+///
+/// * `v_TexCoord`: the position of the pixel on the screen, from `(0, 0)` at
+///   the bottom left corner to `(1, 1)`.
+/// * `v_Direction`: the direction in world space seen through the pixel, not
+///   normalized, for backgrounds following the rotation of the camera.
+/// * `u_Params`: a `mat4` holding the [`params`], one `vec4` per column.
+/// * `u_Resolution`: the size of the output in pixels in `xy`, and the size
+///   of one pixel in texture coordinates in `zw`.
+/// * `t_Aux`: the [`aux`] texture, white by default.
+/// * The camera matrices of `#include <globals>`.
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+/// // `shaders/sky_ps.glsl`:
+/// //
+/// // #version 150 core
+/// // #include <background>
+/// //
+/// // out vec4 Target0;
+/// //
+/// // void main() {
+/// //     float height = normalize(v_Direction).y;
+/// //     vec3 color = mix(u_Params[0].rgb, u_Params[1].rgb, smoothstep(-0.2, 0.6, height));
+/// //     Target0 = vec4(color * (0.8 + 0.2 * sin(u_Params[2].x)), 1.0);
+/// // }
+/// let mut sky = window.factory.background_shader("shaders", "sky").unwrap();
+/// sky.params[0] = [0.9, 0.6, 0.4, 1.0];
+/// sky.params[1] = [0.2, 0.4, 0.9, 1.0];
+/// let mut time = 0.0;
+/// while window.update() {
+///     time += window.input.delta_time();
+///     sky.params[2][0] = time;
+///     window.scene.background = three::Background::Custom(sky.clone());
+///     window.render(&camera);
+/// }
+/// ```
+///
+/// [`Background::Custom`]: enum.Background.html#variant.Custom
+/// [`Factory::background_shader`]: ../struct.Factory.html#method.background_shader
+/// [`params`]: #structfield.params
+/// [`aux`]: #structfield.aux
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackgroundShader {
+    pub(crate) pso: BackgroundPipelineState,
+    /// Parameters read by the shader as `u_Params[0]` to `u_Params[3]`.
+    ///
+    /// Default: all zeros.
+    pub params: [[f32; 4]; 4],
+    /// Texture read by the shader as `t_Aux`, white when `None`.
+    ///
+    /// Default: `None`.
+    pub aux: Option<Texture<[f32; 4]>>,
+}
+
+impl BackgroundShader {
+    pub(crate) fn new(pso: BackgroundPipelineState) -> Self {
+        BackgroundShader {
+            pso,
+            params: [[0.0; 4]; 4],
+            aux: None,
+        }
+    }
+}
+
+/// Distance fog, shown by the materials with [`ShaderFeatures::FOG`].
+///
+/// [`ShaderFeatures::FOG`]: ../material/struct.ShaderFeatures.html#associatedconstant.FOG
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Fog {
+    /// Color the objects fade into.
+    pub color: Color,
+    /// Distance from the camera at which the fog starts.
+    pub near: f32,
+    /// Distance from the camera at which the fog hides objects completely.
+    pub far: f32,
+}
+
+/// A background image scrolling with the movement of the camera, drawn over
+/// the [`Background`] and behind every object of the scene.
+///
+/// Layers are drawn in order, so put the farthest first and give the nearer
+/// ones transparent parts. To repeat the image horizontally, load the
+/// texture with a sampler using [`WrapMode::Tile`].
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// use three::{FilterMethod, WrapMode};
+/// let sampler = window.factory.sampler(FilterMethod::Bilinear, WrapMode::Tile, WrapMode::Clamp);
+/// let hills = window.factory.load_texture_with_sampler("hills.png", sampler);
+/// window.scene.parallax.push(three::ParallaxLayer {
+///     scroll: [0.02, 0.0],
+///     .. three::ParallaxLayer::new(hills)
+/// });
+/// ```
+///
+/// [`Background`]: enum.Background.html
+/// [`WrapMode::Tile`]: enum.WrapMode.html#variant.Tile
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParallaxLayer {
+    /// The image of the layer, keeping its aspect ratio on screen.
+    pub texture: Texture<[f32; 4]>,
+    /// Number of image repetitions scrolled per world unit of camera
+    /// movement along X and Y. Farther layers use smaller values.
+    ///
+    /// Default: `[0.0, 0.0]`, fixed to the screen.
+    pub scroll: [f32; 2],
+    /// Additional offset in image repetitions, for example to drift clouds.
+    ///
+    /// Default: `[0.0, 0.0]`.
+    pub offset: [f32; 2],
+    /// The fraction of the screen height covered by one repetition of the
+    /// image, which starts at the bottom of the screen.
+    ///
+    /// Default: 1.0.
+    pub height: f32,
+    /// Opacity of the whole layer.
+    ///
+    /// Default: 1.0.
+    pub opacity: f32,
+}
+
+impl ParallaxLayer {
+    /// Creates a layer covering the screen height, fixed to the screen.
+    pub fn new(texture: Texture<[f32; 4]>) -> Self {
+        ParallaxLayer {
+            texture,
+            scroll: [0.0, 0.0],
+            offset: [0.0, 0.0],
+            height: 1.0,
+            opacity: 1.0,
+        }
+    }
+}
+
+/// A likely mistake in a scene, found by [`Scene::validate`].
+///
+/// [`Scene::validate`]: struct.Scene.html#method.validate
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// A mesh with a textured material has no texture coordinates, so every
+    /// vertex samples the same texel.
+    MissingTexCoords(Base),
+    /// A mesh with a lit material has no normals, so it is drawn black.
+    MissingNormals(Base),
+    /// More lights are visible than the renderer supports. The lights past
+    /// the first `max` ones are ignored.
+    TooManyLights {
+        /// The number of visible lights.
+        count: usize,
+        /// The number of lights the renderer supports.
+        max: usize,
+    },
+    /// A mesh with joint weights has no skeleton, so it is drawn in its
+    /// bind pose.
+    MissingSkeleton(Base),
+    /// A bone that doesn't belong to any skeleton in the scene, so moving it
+    /// deforms nothing.
+    OrphanBone(Base),
+    /// A skeleton with bones that aren't visible in the scene, so they keep
+    /// the bind pose.
+    DetachedBones(Base),
+    /// An object whose transform holds NaN or infinite values, which hides
+    /// it and its children.
+    InvalidTransform(Base),
+    /// A group whose [bounds] don't enclose all of its meshes, which may be
+    /// culled while in view. Bounds are not updated when children move.
+    ///
+    /// [bounds]: ../object/struct.Group.html#method.set_bounds
+    StaleBounds(Base),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        match *self {
+            Warning::MissingTexCoords(ref base) => {
+                write!(f, "{:?} has a textured material but no texture coordinates", base)
+            }
+            Warning::MissingNormals(ref base) => write!(f, "{:?} has a lit material but no normals", base),
+            Warning::TooManyLights { count, max } => {
+                write!(f, "{} lights are visible but only {} are supported", count, max)
+            }
+            Warning::MissingSkeleton(ref base) => write!(f, "{:?} has joint weights but no skeleton", base),
+            Warning::OrphanBone(ref base) => write!(f, "{:?} is a bone outside of any skeleton", base),
+            Warning::DetachedBones(ref base) => {
+                write!(f, "{:?} is a skeleton with bones missing from the scene", base)
+            }
+            Warning::InvalidTransform(ref base) => write!(f, "{:?} has a non-finite transform", base),
+            Warning::StaleBounds(ref base) => {
+                write!(f, "{:?} has bounds which don't enclose all of its children", base)
+            }
+        }
+    }
+}
+
+/// Counts of the contents of a scene, see [`Scene::statistics`].
+///
+/// [`Scene::statistics`]: struct.Scene.html#method.statistics
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Statistics {
+    /// Objects in the scene, including groups and hidden objects.
+    pub objects: usize,
+    /// Objects that are visible along with all of their parents.
+    pub visible_objects: usize,
+    /// Visible meshes, sprites and lines.
+    pub meshes: usize,
+    /// Visible lights.
+    pub lights: usize,
+    /// Triangles of the visible meshes, not counting instancing.
+    pub triangles: usize,
 }
 
 /// The root node of a tree of game objects that may be rendered by a [`Camera`].
@@ -29,12 +261,32 @@ pub enum Background {
 pub struct Scene {
     pub(crate) hub: HubPtr,
     pub(crate) first_child: Option<node::NodePointer>,
+    pub(crate) last_child: Option<node::NodePointer>,
     /// See [`Background`](struct.Background.html).
     pub background: Background,
+    /// Fog applied to the materials that enable it.
+    ///
+    /// Default: `None`.
+    pub fog: Option<Fog>,
+    /// Ambient light probe added to the lighting of Lambert, Phong and PBR
+    /// materials.
+    ///
+    /// Default: `None`.
+    pub light_probe: Option<LightProbe>,
+    /// Reflections of the surroundings on PBR materials.
+    ///
+    /// Default: `None`.
+    pub reflection_probe: Option<ReflectionProbe>,
+    /// Background layers scrolling with the camera, drawn in order.
+    ///
+    /// Default: empty.
+    pub parallax: Vec<ParallaxLayer>,
 }
 
 impl Scene {
     /// Add new [`Base`](struct.Base.html) to the scene.
+    ///
+    /// Objects are traversed, and rendered, in the order they were added.
     pub fn add<P>(
         &mut self,
         child_base: P,
@@ -43,14 +295,19 @@ impl Scene {
     {
         let mut hub = self.hub.lock().unwrap();
         let node_ptr = child_base.as_ref().node.clone();
-        let child = &mut hub[child_base];
-
-        if child.next_sibling.is_some() {
-            error!("Element {:?} is added to a scene while still having old parent - {}",
-                child.sub_node, "discarding siblings");
+        {
+            let child = &hub[&child_base];
+            if child.next_sibling.is_some() {
+                error!("Element {:?} is added to a scene while still having old parent - {}",
+                    child.sub_node, "discarding siblings");
+            }
         }
 
-        child.next_sibling = mem::replace(&mut self.first_child, Some(node_ptr));
+        hub.append_sibling(self.last_child.as_ref(), &node_ptr);
+        if self.first_child.is_none() {
+            self.first_child = Some(node_ptr.clone());
+        }
+        self.last_child = Some(node_ptr);
     }
 
     /// Remove a previously added [`Base`](struct.Base.html) from the scene.
@@ -60,23 +317,32 @@ impl Scene {
     ) where
         P: AsRef<Base>,
     {
-        let target_maybe = Some(child_base.as_ref().node.clone());
+        let node_ptr = child_base.as_ref().node.clone();
+        let target_maybe = Some(node_ptr.clone());
         let mut hub = self.hub.lock().unwrap();
-        let next_sibling = hub[child_base].next_sibling.clone();
+        let next_sibling = hub.nodes[&node_ptr].next_sibling.clone();
 
         if self.first_child == target_maybe {
+            // the unlinked node must not keep its siblings, to be added again
+            hub.nodes[&node_ptr].next_sibling = None;
             self.first_child = next_sibling;
+            if self.last_child == target_maybe {
+                self.last_child = None;
+            }
             return;
         }
 
         let mut cur_ptr = self.first_child.clone();
         while let Some(ptr) = cur_ptr.take() {
-            let node = &mut hub.nodes[&ptr];
-            if node.next_sibling == target_maybe {
-                node.next_sibling = next_sibling;
+            if hub.nodes[&ptr].next_sibling == target_maybe {
+                hub.nodes[&node_ptr].next_sibling = None;
+                hub.nodes[&ptr].next_sibling = next_sibling;
+                if self.last_child == target_maybe {
+                    self.last_child = Some(ptr);
+                }
                 return;
             }
-            cur_ptr = node.next_sibling.clone(); //TODO: avoid clone
+            cur_ptr = hub.nodes[&ptr].next_sibling.clone(); //TODO: avoid clone
         }
 
         error!("Unable to find child for removal");
@@ -122,7 +388,7 @@ impl Scene {
 /// # use three::Object;
 /// # let mut win = three::Window::new("SyncGuard example");
 /// # let geometry = three::Geometry::default();
-/// # let material = three::material::Basic { color: three::color::RED, map: None };
+/// # let material = three::material::Basic { color: three::color::RED, .. Default::default() };
 /// # let mesh = win.factory.mesh(geometry, material);
 /// # let enemy = Enemy { mesh, is_visible: true };
 /// # win.scene.add(&enemy);
@@ -181,6 +447,7 @@ impl<'a> SyncGuard<'a> {
             .expect("Unable to find objects for world resolve!");
         node::Node {
             visible: wn.world_visible,
+            opacity: wn.world_opacity,
             name: wn.node.name.clone(),
             transform: wn.world_transform.into(),
             material: match wn.node.sub_node {
@@ -369,4 +636,215 @@ impl Scene {
         hub.process_messages();
         SyncGuard { scene: self, hub }
     }
+
+    /// Returns all the objects in the scene, walking into groups depth-first.
+    fn collect_objects(&mut self) -> Vec<Base> {
+        let mut hub = self.hub.lock().unwrap();
+        hub.process_messages();
+        hub.walk_all(&self.first_child)
+            .map(|walked| hub.upgrade_ptr(walked.node_ptr.clone()))
+            .collect()
+    }
+
+    /// Calls `fun` for every object in the scene, including the ones inside
+    /// groups and hidden ones. The hierarchy is walked depth-first, in the
+    /// order the objects were added.
+    ///
+    /// The scene is not locked while `fun` runs, so it may freely use
+    /// [`Object`](../object/trait.Object.html) methods on the objects.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// use three::Object;
+    ///
+    /// // bring back everything hidden in an imported level
+    /// window.scene.traverse(|object| object.set_visible(true));
+    /// ```
+    pub fn traverse<F>(
+        &mut self,
+        mut fun: F,
+    ) where
+        F: FnMut(&Base),
+    {
+        for object in self.collect_objects() {
+            fun(&object);
+        }
+    }
+
+    /// Finds the first object in the scene named `name`, searching groups
+    /// depth-first. Use [`find_of_type_by_name`] to get the object with its
+    /// concrete type.
+    ///
+    /// [`find_of_type_by_name`]: #method.find_of_type_by_name
+    pub fn find_by_name(
+        &mut self,
+        name: &str,
+    ) -> Option<Base> {
+        let mut hub = self.hub.lock().unwrap();
+        hub.process_messages();
+        let ptr = hub.walk_all(&self.first_child)
+            .find(|walked| walked.node.name.as_ref().map_or(false, |n| n == name))
+            .map(|walked| walked.node_ptr.clone())?;
+        Some(hub.upgrade_ptr(ptr))
+    }
+
+    /// Finds the first object of type `T` in the scene named `name`,
+    /// searching groups depth-first.
+    ///
+    /// # Examples
+    ///
+    /// Grabbing a light out of an instantiated glTF template.
+    ///
+    /// ```rust,no_run,ignore
+    /// # let mut window = three::Window::new("");
+    /// let templates = window.factory.load_gltf("lamp.gltf");
+    /// let (root, _) = window.factory.instantiate_template(&templates[0]);
+    /// window.scene.add(&root);
+    ///
+    /// let bulb: Option<three::light::Point> = window.scene.find_of_type_by_name("Bulb");
+    /// ```
+    pub fn find_of_type_by_name<T: DowncastObject>(
+        &mut self,
+        name: &str,
+    ) -> Option<T> {
+        let sync = self.sync_guard();
+        let found = sync.hub
+            .walk_all(&sync.scene.first_child)
+            .filter(|walked| walked.node.name.as_ref().map_or(false, |n| n == name))
+            .filter_map(|walked| sync.downcast(&sync.hub.upgrade_ptr(walked.node_ptr.clone())))
+            .next();
+        found
+    }
+
+    /// Counts the objects of the scene and the work they make for the
+    /// renderer, for example to show in a debug overlay.
+    pub fn statistics(&mut self) -> Statistics {
+        let mut hub = self.hub.lock().unwrap();
+        hub.process_messages();
+        let mut stats = Statistics::default();
+        for w in hub.walk_all(&self.first_child) {
+            stats.objects += 1;
+            if !w.world_visible {
+                continue;
+            }
+            stats.visible_objects += 1;
+            match w.node.sub_node {
+                SubNode::Visual(_, ref gpu_data, _) => {
+                    stats.meshes += 1;
+                    let slice = &gpu_data.slice;
+                    stats.triangles += (slice.end - slice.start) as usize / 3;
+                }
+                SubNode::Light(_) => stats.lights += 1,
+                _ => {}
+            }
+        }
+        stats
+    }
+
+    /// Looks for common mistakes that lead to black or missing objects,
+    /// which the renderer otherwise works around silently.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// for warning in window.scene.validate() {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    pub fn validate(&mut self) -> Vec<Warning> {
+        let mut hub = self.hub.lock().unwrap();
+        hub.process_messages();
+        let mut warnings = Vec::new();
+        let mut lights = 0;
+        let mut skeleton_bones = HashSet::new();
+        let mut visible_bones = HashSet::new();
+        for w in hub.walk_all(&self.first_child) {
+            let base = || hub.upgrade_ptr(w.node_ptr.clone());
+            let transform = &w.node.transform;
+            let finite = transform.scale.is_finite() &&
+                (0 .. 3).all(|i| transform.disp[i].is_finite()) &&
+                transform.rot.s.is_finite() &&
+                (0 .. 3).all(|i| transform.rot.v[i].is_finite());
+            if !finite {
+                warnings.push(Warning::InvalidTransform(base()));
+            }
+            match w.node.sub_node {
+                SubNode::Visual(ref material, ref gpu_data, ref skeleton) => {
+                    let attributes = gpu_data.attributes;
+                    let (lit, textured) = match *material {
+                        Material::Basic(ref m) => (false, m.map.is_some()),
+                        Material::CustomBasic(ref m) => (false, m.map.is_some()),
+                        Material::Lambert(_) | Material::Phong(_) => (true, false),
+                        Material::Pbr(ref m) => (
+                            true,
+                            m.base_color_map.is_some() || m.normal_map.is_some() ||
+                                m.emissive_map.is_some() || m.metallic_roughness_map.is_some() ||
+                                m.occlusion_map.is_some(),
+                        ),
+                        Material::Line(_) | Material::Sprite(_) | Material::Wireframe(_) => (false, false),
+                    };
+                    if textured && !attributes.tex_coords {
+                        warnings.push(Warning::MissingTexCoords(base()));
+                    }
+                    if lit && !attributes.normals {
+                        warnings.push(Warning::MissingNormals(base()));
+                    }
+                    if attributes.joints && skeleton.is_none() {
+                        warnings.push(Warning::MissingSkeleton(base()));
+                    }
+                }
+                SubNode::Light(_) if w.world_visible => lights += 1,
+                SubNode::Group { ref first_child, .. } => match w.node.bounds {
+                    Some(ref bounds) if !children_within(&hub, first_child, bounds) => {
+                        warnings.push(Warning::StaleBounds(base()));
+                    }
+                    _ => {}
+                },
+                SubNode::Skeleton(ref data) => {
+                    skeleton_bones.extend(data.bones.iter().map(|bone| bone.as_ref().node.clone()));
+                }
+                SubNode::Bone { .. } if w.world_visible => {
+                    visible_bones.insert(w.node_ptr.clone());
+                }
+                _ => {}
+            }
+        }
+        if lights > MAX_LIGHTS {
+            warnings.push(Warning::TooManyLights { count: lights, max: MAX_LIGHTS });
+        }
+        for w in hub.walk_all(&self.first_child) {
+            match w.node.sub_node {
+                SubNode::Bone { .. } if !skeleton_bones.contains(&w.node_ptr) => {
+                    warnings.push(Warning::OrphanBone(hub.upgrade_ptr(w.node_ptr.clone())));
+                }
+                SubNode::Skeleton(ref data) if w.world_visible => {
+                    if data.bones.iter().any(|bone| !visible_bones.contains(&bone.as_ref().node)) {
+                        warnings.push(Warning::DetachedBones(hub.upgrade_ptr(w.node_ptr.clone())));
+                    }
+                }
+                _ => {}
+            }
+        }
+        warnings
+    }
+}
+
+/// Returns `true` if the visible meshes starting at `first_child` lie inside
+/// of `bounds`, given in the space of their parent.
+fn children_within(
+    hub: &Hub,
+    first_child: &Option<node::NodePointer>,
+    bounds: &Aabb,
+) -> bool {
+    // the walk starts at the children, so transforms are relative to the parent
+    hub.walk(first_child).all(|w| match w.node.sub_node {
+        SubNode::Visual(_, ref gpu_data, _) => match gpu_data.bounds() {
+            Some((mesh_bounds, _)) => bounds.contains(&mesh_bounds.transformed(&w.world_transform)),
+            None => true,
+        },
+        _ => true,
+    })
 }
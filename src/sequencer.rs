@@ -0,0 +1,254 @@
+//! Timelines for authoring cutscenes in code.
+//!
+//! A [`Timeline`] schedules animation actions, audio clips, camera cuts and
+//! user callbacks at fixed times. It can be played back, paused and scrubbed
+//! to any point in time, which is handy for previewing a sequence.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let mut mixer = three::animation::Mixer::new();
+//! # let clip: three::animation::Clip = unimplemented!();
+//! # let wide = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let close_up = window.factory.perspective_camera(30.0, 0.1 .. 100.0);
+//! let mut timeline = three::sequencer::Timeline::new(10.0);
+//! timeline
+//!     .add_camera_cut(0.0, &wide)
+//!     .add_action(1.5, mixer.action(clip))
+//!     .add_camera_cut(4.0, &close_up)
+//!     .add_callback(9.0, || println!("fade out"));
+//! timeline.play();
+//!
+//! while window.update() && !timeline.is_finished() {
+//!     let dt = window.input.delta_time();
+//!     timeline.update(dt);
+//!     mixer.update(dt);
+//!     window.render(timeline.camera().unwrap());
+//! }
+//! ```
+//!
+//! [`Timeline`]: struct.Timeline.html
+
+use animation::Action;
+#[cfg(feature = "audio")]
+use audio;
+use camera::Camera;
+
+type Callback = Box<dyn FnMut()>;
+
+/// Something that happens at a point in time.
+enum Cue {
+    Action(Action),
+    #[cfg(feature = "audio")]
+    Audio(audio::Source, audio::Clip),
+    CameraCut(Camera),
+    Callback(Callback),
+}
+
+/// A sequence of cues played back on a shared clock.
+///
+/// Animation actions are driven by the timeline once added: they start when
+/// the timeline reaches their start time and follow it when scrubbing. The
+/// [`Mixer`] owning them still needs to be updated every frame.
+///
+/// Audio clips and callbacks only fire during playback, not when seeking.
+///
+/// See the [module documentation](index.html) for an example.
+///
+/// [`Mixer`]: ../animation/struct.Mixer.html
+pub struct Timeline {
+    /// Cues sorted by time.
+    cues: Vec<(f32, Cue)>,
+    /// Index of the first cue that has not been reached yet.
+    next_cue: usize,
+    time: f32,
+    duration: f32,
+    playing: bool,
+}
+
+impl Timeline {
+    /// Creates an empty, paused timeline lasting `duration` seconds.
+    pub fn new(duration: f32) -> Self {
+        Timeline {
+            cues: Vec::new(),
+            next_cue: 0,
+            time: 0.0,
+            duration,
+            playing: false,
+        }
+    }
+
+    fn add_cue(
+        &mut self,
+        time: f32,
+        cue: Cue,
+    ) -> &mut Self {
+        let index = self.cues
+            .iter()
+            .position(|&(t, _)| t > time)
+            .unwrap_or(self.cues.len());
+        self.cues.insert(index, (time, cue));
+        if index < self.next_cue {
+            self.next_cue += 1;
+        }
+        self
+    }
+
+    /// Starts an animation action at `time`.
+    ///
+    /// The action is disabled until then.
+    pub fn add_action(
+        &mut self,
+        time: f32,
+        mut action: Action,
+    ) -> &mut Self {
+        action.disable();
+        self.add_cue(time, Cue::Action(action))
+    }
+
+    /// Plays an audio clip on `source` at `time`.
+    #[cfg(feature = "audio")]
+    pub fn add_audio(
+        &mut self,
+        time: f32,
+        source: &audio::Source,
+        clip: audio::Clip,
+    ) -> &mut Self {
+        self.add_cue(time, Cue::Audio(source.clone(), clip))
+    }
+
+    /// Switches to `camera` at `time`, see [`camera`](#method.camera).
+    pub fn add_camera_cut(
+        &mut self,
+        time: f32,
+        camera: &Camera,
+    ) -> &mut Self {
+        self.add_cue(time, Cue::CameraCut(camera.clone()))
+    }
+
+    /// Calls `callback` when the playback reaches `time`.
+    pub fn add_callback<F>(
+        &mut self,
+        time: f32,
+        callback: F,
+    ) -> &mut Self
+    where
+        F: 'static + FnMut(),
+    {
+        self.add_cue(time, Cue::Callback(Box::new(callback)))
+    }
+
+    /// Current playback time in seconds.
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    /// Total length of the timeline in seconds.
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    /// Returns `true` if the timeline is playing.
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    /// Returns `true` once the playback reached the end of the timeline.
+    pub fn is_finished(&self) -> bool {
+        self.time >= self.duration
+    }
+
+    /// The camera of the latest camera cut, if any was reached.
+    pub fn camera(&self) -> Option<&Camera> {
+        self.cues[.. self.next_cue]
+            .iter()
+            .rev()
+            .filter_map(|(_, cue)| match *cue {
+                Cue::CameraCut(ref camera) => Some(camera),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// Resumes the playback.
+    pub fn play(&mut self) {
+        self.playing = true;
+        self.for_started_actions(|action| {
+            action.play();
+        });
+    }
+
+    /// Pauses the playback, freezing the started actions.
+    pub fn pause(&mut self) {
+        self.playing = false;
+        self.for_started_actions(|action| {
+            action.pause();
+        });
+    }
+
+    fn for_started_actions<F: FnMut(&mut Action)>(
+        &mut self,
+        mut f: F,
+    ) {
+        for &mut (_, ref mut cue) in &mut self.cues[.. self.next_cue] {
+            if let Cue::Action(ref mut action) = *cue {
+                f(action);
+            }
+        }
+    }
+
+    /// Jumps to `time`, moving the actions to the matching pose.
+    pub fn seek(
+        &mut self,
+        time: f32,
+    ) {
+        self.time = time.max(0.0).min(self.duration);
+        self.next_cue = self.cues
+            .iter()
+            .position(|&(t, _)| t > self.time)
+            .unwrap_or(self.cues.len());
+        let (time, playing) = (self.time, self.playing);
+        for (i, &mut (start, ref mut cue)) in self.cues.iter_mut().enumerate() {
+            let action = match *cue {
+                Cue::Action(ref mut action) => action,
+                _ => continue,
+            };
+            if i < self.next_cue {
+                action.enable().set_time(time - start);
+                if playing {
+                    action.play();
+                } else {
+                    action.pause();
+                }
+            } else {
+                action.disable();
+            }
+        }
+    }
+
+    /// Advances the playback by `dt` seconds, firing the cues reached.
+    pub fn update(
+        &mut self,
+        dt: f32,
+    ) {
+        if !self.playing {
+            return;
+        }
+        self.time = (self.time + dt).min(self.duration);
+        while self.next_cue < self.cues.len() && self.cues[self.next_cue].0 <= self.time {
+            let (start, ref mut cue) = self.cues[self.next_cue];
+            match *cue {
+                Cue::Action(ref mut action) => {
+                    action.set_time(self.time - start).play();
+                }
+                #[cfg(feature = "audio")]
+                Cue::Audio(ref source, ref clip) => source.play(clip),
+                Cue::CameraCut(_) => {}
+                Cue::Callback(ref mut callback) => callback(),
+            }
+            self.next_cue += 1;
+        }
+        if self.is_finished() {
+            self.playing = false;
+        }
+    }
+}
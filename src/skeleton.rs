@@ -1,7 +1,16 @@
 //! Mesh skinning.
 
+use cgmath::{Matrix4, Point3, SquareMatrix, Transform as Transform_, Zero};
 use mint;
+
+use std::collections::HashMap;
+use std::ptr;
+
+use geometry::Geometry;
+use hub::SubNode;
+use node::{NodeInternal, Transform, TransformInternal};
 use object::{self, ObjectType};
+use scene::SyncGuard;
 
 /// Contains array of bones.
 #[derive(Clone, Debug)]
@@ -11,6 +20,110 @@ pub struct Skeleton {
 three_object!(Skeleton::object);
 derive_DowncastObject!(Skeleton => ObjectType::Skeleton);
 
+impl Skeleton {
+    /// Reads back the current pose of the skeleton, as set by animations
+    /// and bone transforms, for example to start a physics ragdoll from it.
+    ///
+    /// # Panics
+    /// Panics if the scene doesn't have this skeleton.
+    pub fn pose(
+        &self,
+        sync: &SyncGuard,
+    ) -> Pose {
+        let bones = match sync.hub[self].sub_node {
+            SubNode::Skeleton(ref data) => &data.bones,
+            _ => unreachable!(),
+        };
+        let skeleton_node = &sync.hub[self];
+        let mut skeleton_transform = None;
+        let mut bone_transforms = HashMap::new();
+        for w in sync.hub.walk_all(&sync.scene.first_child) {
+            if ptr::eq(w.node, skeleton_node) {
+                skeleton_transform = Some(w.world_transform);
+            } else if let SubNode::Bone { .. } = w.node.sub_node {
+                bone_transforms.insert(w.node as *const NodeInternal, w.world_transform);
+            }
+        }
+        let skeleton_transform = skeleton_transform.expect("Unable to find skeleton for pose!");
+
+        let mut pose = Pose {
+            bones: vec![None; bones.len()],
+            skin_matrices: vec![Matrix4::identity().into(); bones.len()],
+        };
+        for bone in bones {
+            let node = &sync.hub[bone];
+            let bone_transform = match bone_transforms.get(&(node as *const NodeInternal)) {
+                Some(transform) => transform,
+                None => continue,
+            };
+            if let SubNode::Bone { index, inverse_bind_matrix } = node.sub_node {
+                pose.bones[index] = Some((*bone_transform).into());
+                pose.skin_matrices[index] =
+                    skin_matrix(&skeleton_transform, bone_transform, inverse_bind_matrix).into();
+            }
+        }
+        pose
+    }
+}
+
+/// The pose of a [`Skeleton`] at one point in time.
+///
+/// [`Skeleton`]: struct.Skeleton.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pose {
+    /// World transforms of the bones, indexed by bone index. Bones that are
+    /// not in the scene have `None`.
+    pub bones: Vec<Option<Transform>>,
+    /// Matrices moving the vertices of skinned meshes from the bind pose to
+    /// the current pose, indexed by bone index.
+    pub skin_matrices: Vec<mint::ColumnMatrix4<f32>>,
+}
+
+impl Pose {
+    /// Computes the posed vertex positions of `geometry`, skinned by the
+    /// joints of its vertices, the way the renderer does.
+    ///
+    /// Positions are in the local space of the skinned mesh; transform them
+    /// by the world transform of the mesh to get world positions.
+    /// Morph targets are not applied.
+    pub fn skin_positions(
+        &self,
+        geometry: &Geometry,
+    ) -> Vec<mint::Point3<f32>> {
+        let matrices: Vec<Matrix4<f32>> = self.skin_matrices.iter().map(|&mx| mx.into()).collect();
+        let joints = &geometry.joints;
+        geometry
+            .base
+            .vertices
+            .iter()
+            .enumerate()
+            .map(|(i, &vertex)| {
+                let (indices, weights) = match (joints.indices.get(i), joints.weights.get(i)) {
+                    (Some(indices), Some(weights)) => (indices, weights),
+                    _ => return vertex,
+                };
+                let mut mx = Matrix4::zero();
+                for (&index, &weight) in indices.iter().zip(weights) {
+                    if let Some(joint) = matrices.get(index as usize) {
+                        mx += joint * weight;
+                    }
+                }
+                mx.transform_point(Point3::from(vertex)).into()
+            })
+            .collect()
+    }
+}
+
+/// The matrix skinning vertices by a bone, relative to its skeleton.
+pub(crate) fn skin_matrix(
+    skeleton_transform: &TransformInternal,
+    bone_transform: &TransformInternal,
+    inverse_bind_matrix: InverseBindMatrix,
+) -> Matrix4<f32> {
+    let inverse_skeleton_transform: TransformInternal = skeleton_transform.inverse_transform().unwrap();
+    Matrix4::from(inverse_skeleton_transform.concat(bone_transform)) * Matrix4::from(inverse_bind_matrix)
+}
+
 /// A single bone that forms one component of a [`Skeleton`].
 ///
 /// [`Skeleton`]: struct.Skeleton.html
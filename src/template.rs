@@ -50,6 +50,12 @@
 //! of the data on the GPU. This reduces GPU resource usage and, for any meshes that also share
 //! a material, allows three to render many objects at once.
 //!
+//! # Lights
+//!
+//! Lights in a template are instantiated like any other object, so a lighting rig can be
+//! loaded from a glTF file together with the model it lights. Shadows are described by a
+//! [`ShadowTemplate`], and each instance gets its own shadow map.
+//!
 //! [`Factory::instantiate_template`]: ../struct.Factory.html#method.instantiate_template
 //! [`Factory::load_gltf`]: ../struct.Factory.html#method.load_gltf
 //! [`Factory::upload_geometry`]: ../struct.Factory.html#method.upload_geometry
@@ -67,6 +73,7 @@
 //! [`roots`]: ./struct.Template.html#structfield.roots
 //! [`objects`]: ./struct.Template.html#structfield.objects
 //! [`InstancedGeometry`]: ./struct.InstancedGeometry.html
+//! [`ShadowTemplate`]: ./struct.ShadowTemplate.html
 
 use animation::Track;
 use camera::Projection;
@@ -332,6 +339,48 @@ pub struct LightTemplate {
 
     /// The specific type of light represented by the template.
     pub sub_light: SubLightTemplate,
+
+    /// The shadow cast by the light, if any. Only directional and point
+    /// lights cast shadows, the setting is ignored for other lights.
+    pub shadow: Option<ShadowTemplate>,
+}
+
+/// Settings for the shadow map of a light, analogous to the arguments of
+/// [`Directional::set_shadow`] and [`Point::set_shadow`].
+///
+/// A new shadow map is created for every instance of the template.
+///
+/// # Examples
+///
+/// ```
+/// use three::template::{LightTemplate, ShadowTemplate};
+///
+/// let mut light = LightTemplate::directional(0, three::color::WHITE, 0.8);
+/// light.shadow = Some(ShadowTemplate {
+///     map_size: 1024,
+///     extent_y: 40.0,
+///     near: -10.0,
+///     far: 10.0,
+/// });
+/// ```
+///
+/// [`Directional::set_shadow`]: ../light/struct.Directional.html#method.set_shadow
+/// [`Point::set_shadow`]: ../light/struct.Point.html#method.set_shadow
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowTemplate {
+    /// Width and height of the shadow map in pixels, or of each face for
+    /// point lights.
+    pub map_size: u16,
+
+    /// Vertical extent of the orthographic shadow projection. Ignored for
+    /// point lights.
+    pub extent_y: f32,
+
+    /// Near plane of the shadow projection.
+    pub near: f32,
+
+    /// Far plane of the shadow projection.
+    pub far: f32,
 }
 
 impl LightTemplate {
@@ -360,6 +409,7 @@ impl LightTemplate {
             color,
             intensity,
             sub_light: SubLightTemplate::Ambient,
+            shadow: None,
         }
     }
 
@@ -388,6 +438,7 @@ impl LightTemplate {
             color,
             intensity,
             sub_light: SubLightTemplate::Directional,
+            shadow: None,
         }
     }
 
@@ -416,6 +467,7 @@ impl LightTemplate {
             color,
             intensity,
             sub_light: SubLightTemplate::Point,
+            shadow: None,
         }
     }
 
@@ -450,6 +502,7 @@ impl LightTemplate {
             color: sky_color,
             intensity,
             sub_light: SubLightTemplate::Hemisphere { ground: ground_color },
+            shadow: None,
         }
     }
 }
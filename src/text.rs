@@ -1,16 +1,22 @@
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
 use std::rc::Rc;
 
 use gfx::Encoder;
-use gfx::handle::{DepthStencilView, RenderTargetView};
+use gfx::format::Format;
+use gfx::handle::{DepthStencilView, RawRenderTargetView};
 use gfx_glyph as g;
+use gfx_glyph::GlyphPositioner;
 use mint;
+use cgmath::{Point3, Transform};
 use object;
 
 use color::Color;
 use hub::Operation as HubOperation;
-use render::{BackendCommandBuffer, BackendFactory, BackendResources, ColorFormat, DepthFormat};
+use node::TransformInternal;
+use render::{BackendCommandBuffer, BackendFactory, BackendResources, DepthFormat};
+use texture::Texture;
 
 #[derive(Debug)]
 pub(crate) enum Operation {
@@ -22,8 +28,19 @@ pub(crate) enum Operation {
     Color(Color),
     Opacity(f32),
     Layout(Layout),
+    Depth(Option<f32>),
 }
 
+/// Depth of text drawn over the scene, at the near clipping plane.
+pub(crate) const DEPTH_ON_TOP: f32 = -1.0;
+
+/// Font of the glyphs reserving the space of icons, a copy of the text font.
+const ICON_FONT: g::FontId = g::FontId(1);
+
+/// Text of the glyphs reserving the space of icons. The glyph has to be
+/// visible, or the layout drops it.
+const ICON_PLACEHOLDER: &str = "M";
+
 /// Describes the horizontal alignment preference for positioning & bounds.
 /// See [`gfx_glyph::HorizontalAlign`](https://docs.rs/gfx_glyph/0.13.0/gfx_glyph/enum.HorizontalAlign.html)
 /// for more.
@@ -83,10 +100,156 @@ impl From<Layout> for g::Layout<g::BuiltInLineBreaker> {
     }
 }
 
+/// Encoding of the images of an [`IconAtlas`](struct.IconAtlas.html).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IconFormat {
+    /// Color images, such as emoji, drawn as they are.
+    /// Only the opacity of the text applies to them.
+    Color,
+    /// Multi-channel signed distance fields, which stay sharp at any font
+    /// size and take the color of the text.
+    Msdf {
+        /// Distance range the fields were generated with, in texels.
+        range: f32,
+    },
+}
+
+/// Named images embedded in text, such as button prompts or emoji.
+///
+/// Icons are registered with [`Font::add_icons`] and referenced in the
+/// text as `:name:`. They are as high as a line of text and keep the
+/// aspect ratio of their image.
+///
+/// ```rust,no_run
+/// # let mut window = three::Window::new("");
+/// let texture = window.factory.load_texture("buttons.png");
+/// let mut atlas = three::IconAtlas::new(texture, three::IconFormat::Msdf { range: 4.0 });
+/// atlas
+///     .add_icon("A", [0, 0], [64, 64])
+///     .add_icon("B", [64, 0], [64, 64]);
+///
+/// let font = window.factory.load_font_karla();
+/// font.add_icons(&atlas);
+/// let prompt = window.factory.ui_text(&font, "Press :A: to jump");
+/// window.scene.add(&prompt);
+/// ```
+///
+/// [`Font::add_icons`]: struct.Font.html#method.add_icons
+#[derive(Debug, Clone)]
+pub struct IconAtlas {
+    texture: Texture<[f32; 4]>,
+    format: IconFormat,
+    icons: HashMap<String, Icon>,
+}
+
+impl IconAtlas {
+    /// Creates an atlas of images stored in `texture`.
+    pub fn new(
+        texture: Texture<[f32; 4]>,
+        format: IconFormat,
+    ) -> Self {
+        IconAtlas {
+            texture,
+            format,
+            icons: HashMap::new(),
+        }
+    }
+
+    /// Adds the icon `name`, covering `size` texels of the texture from
+    /// `base`, the top-left corner.
+    pub fn add_icon<S, P, V>(
+        &mut self,
+        name: S,
+        base: P,
+        size: V,
+    ) -> &mut Self
+    where
+        S: Into<String>,
+        P: Into<mint::Point2<i16>>,
+        V: Into<mint::Vector2<u16>>,
+    {
+        let size = size.into();
+        let mut texture = self.texture.clone();
+        texture.set_texel_range(base.into(), size);
+        let icon = Icon {
+            texture,
+            format: self.format,
+            aspect: size.x as f32 / size.y as f32,
+            height: size.y as f32,
+        };
+        self.icons.insert(name.into(), icon);
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Icon {
+    /// Atlas texture, with the texel range of the icon.
+    texture: Texture<[f32; 4]>,
+    format: IconFormat,
+    /// Width over height.
+    aspect: f32,
+    /// Height in texels.
+    height: f32,
+}
+
+/// Part of a text, split at the icon references.
+enum Piece<'a, 'b> {
+    Text(&'a str),
+    Icon(&'b Icon),
+}
+
+/// Splits `text` at the `:name:` references to `icons`. References to
+/// unknown icons are kept as text.
+fn split_icons<'a, 'b>(
+    text: &'a str,
+    icons: &'b HashMap<String, Icon>,
+) -> Vec<Piece<'a, 'b>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut search = 0;
+    while let Some(open) = text[search ..].find(':').map(|i| search + i) {
+        let close = match text[open + 1 ..].find(':') {
+            Some(i) => open + 1 + i,
+            None => break,
+        };
+        match icons.get(&text[open + 1 .. close]) {
+            Some(icon) => {
+                if start < open {
+                    pieces.push(Piece::Text(&text[start .. open]));
+                }
+                pieces.push(Piece::Icon(icon));
+                start = close + 1;
+                search = close + 1;
+            }
+            None => search = close,
+        }
+    }
+    if start < text.len() {
+        pieces.push(Piece::Text(&text[start ..]));
+    }
+    pieces
+}
+
+/// An icon laid out in a text, drawn by the renderer after the glyphs.
+#[derive(Debug, Clone)]
+pub(crate) struct IconQuad {
+    pub(crate) texture: Texture<[f32; 4]>,
+    /// Screen rectangle in pixels: left, top, right, bottom.
+    pub(crate) rect: [f32; 4],
+    pub(crate) color: [f32; 4],
+    /// Distance range in pixels, zero for color icons.
+    pub(crate) distance_range: f32,
+    pub(crate) depth: f32,
+}
+
 /// Smart pointer containing a font to draw text.
 #[derive(Clone)]
 pub struct Font {
     brush: Rc<RefCell<g::GlyphBrush<'static, BackendResources, BackendFactory>>>,
+    /// Fonts of the brush, indexed by `FontId`.
+    fonts: Vec<g::Font<'static>>,
+    icons: Rc<RefCell<HashMap<String, Icon>>>,
     pub(crate) id: String,
 }
 
@@ -96,33 +259,193 @@ impl Font {
         id: String,
         factory: BackendFactory,
     ) -> Font {
+        let font = g::Font::from_bytes(buf).unwrap();
+        let fonts = vec![font.clone(), font];
         Font {
             brush: Rc::new(RefCell::new(
-                g::GlyphBrushBuilder::using_font_bytes(buf).build(factory),
+                g::GlyphBrushBuilder::using_fonts(fonts.clone()).build(factory),
             )),
+            fonts,
+            icons: Rc::new(RefCell::new(HashMap::new())),
             id: id,
         }
     }
 
-    pub(crate) fn queue(
+    /// Registers the icons of `atlas`, so that texts drawn with this font
+    /// show them in place of their `:name:` references.
+    /// See [`IconAtlas`](struct.IconAtlas.html) for an example.
+    ///
+    /// Icons replace previously registered ones with the same name.
+    pub fn add_icons(
         &self,
-        section: &g::OwnedVariedSection,
+        atlas: &IconAtlas,
     ) {
+        let mut icons = self.icons.borrow_mut();
+        for (name, icon) in &atlas.icons {
+            icons.insert(name.clone(), icon.clone());
+        }
+    }
+
+    /// Queues `section` for drawing and returns the icons it references.
+    pub(crate) fn queue(
+        &self,
+        section: g::VariedSection,
+    ) -> Vec<IconQuad> {
+        let icons = self.icons.borrow();
         let mut brush = self.brush.borrow_mut();
-        brush.queue(section);
+
+        // Each icon reserves its space with a placeholder glyph, whose color
+        // holds the index of the icon.
+        let mut runs = Vec::new();
+        let mut found = Vec::new();
+        for part in &section.text {
+            for piece in split_icons(part.text, &icons) {
+                match piece {
+                    Piece::Text(text) => runs.push(g::SectionText { text, ..*part }),
+                    Piece::Icon(icon) => {
+                        let font = &self.fonts[0];
+                        let metrics = font.v_metrics(part.scale);
+                        let height = metrics.ascent - metrics.descent;
+                        let width = height * icon.aspect;
+                        let advance = font
+                            .glyph('M')
+                            .scaled(part.scale)
+                            .h_metrics()
+                            .advance_width;
+                        runs.push(g::SectionText {
+                            text: ICON_PLACEHOLDER,
+                            scale: g::Scale {
+                                x: part.scale.x * width / advance,
+                                y: part.scale.y,
+                            },
+                            color: [found.len() as f32, 0.0, 0.0, 0.0],
+                            font_id: ICON_FONT,
+                        });
+                        found.push((icon, part.color, metrics, width));
+                    }
+                }
+            }
+        }
+        if found.is_empty() {
+            brush.queue(section);
+            return Vec::new();
+        }
+
+        let geometry = g::SectionGeometry {
+            screen_position: section.screen_position,
+            bounds: section.bounds,
+        };
+        let mut quads = Vec::new();
+        let glyphs = section
+            .layout
+            .calculate_glyphs(&self.fonts, &geometry, &runs)
+            .into_iter()
+            .filter(|&(ref glyph, color, font_id)| {
+                if font_id != ICON_FONT {
+                    return true;
+                }
+                let (icon, color, metrics, width) = found[color[0] as usize];
+                let origin = glyph.position();
+                let height = metrics.ascent - metrics.descent;
+                quads.push(IconQuad {
+                    texture: icon.texture.clone(),
+                    rect: [
+                        origin.x,
+                        origin.y - metrics.ascent,
+                        origin.x + width,
+                        origin.y - metrics.descent,
+                    ],
+                    color,
+                    distance_range: match icon.format {
+                        IconFormat::Color => 0.0,
+                        IconFormat::Msdf { range } => range * height / icon.height,
+                    },
+                    depth: section.z,
+                });
+                false
+            })
+            .collect();
+        brush.queue_pre_positioned(glyphs, section.layout.bounds_rect(&geometry), section.z);
+        quads
+    }
+
+    /// The screen rectangle covered by the glyphs of `section`, in pixels:
+    /// left, top, right, bottom.
+    pub(crate) fn pixel_bounds(
+        &self,
+        section: g::VariedSection,
+    ) -> Option<[f32; 4]> {
+        use gfx_glyph::GlyphCruncher;
+        self.brush
+            .borrow_mut()
+            .pixel_bounds(section)
+            .map(|rect| [rect.min.x as f32, rect.min.y as f32, rect.max.x as f32, rect.max.y as f32])
     }
 
     pub(crate) fn draw(
         &self,
         encoder: &mut Encoder<BackendResources, BackendCommandBuffer>,
-        out: &RenderTargetView<BackendResources, ColorFormat>,
+        out: &RawRenderTargetView<BackendResources>,
+        format: Format,
         depth: &DepthStencilView<BackendResources, DepthFormat>,
     ) {
         let mut brush = self.brush.borrow_mut();
         brush
-            .draw_queued(encoder, out, depth)
+            .draw_queued(encoder, &(out, format), depth)
             .expect("Error while drawing text");
     }
+
+    /// Rasterizes `text` at `size` pixels per line into white RGBA pixels,
+    /// with the coverage of the glyphs in the alpha channel. Rows go from
+    /// the bottom up, like the ones of loaded textures.
+    pub(crate) fn rasterize(
+        &self,
+        text: &str,
+        size: f32,
+    ) -> ([u16; 2], Vec<u8>) {
+        let font = &self.fonts[0];
+        let scale = g::Scale::uniform(size);
+        let metrics = font.v_metrics(scale);
+        let line_height = metrics.ascent - metrics.descent + metrics.line_gap;
+        let glyphs = text
+            .lines()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                let start = g::Point { x: 0.0, y: metrics.ascent + i as f32 * line_height };
+                font.layout(line, scale, start)
+            })
+            .collect::<Vec<_>>();
+
+        let lines = text.lines().count().max(1);
+        let width = glyphs
+            .iter()
+            .filter_map(|glyph| glyph.pixel_bounding_box())
+            .map(|rect| rect.max.x)
+            .max()
+            .unwrap_or(0)
+            .max(1) as usize;
+        let height = ((lines as f32 * line_height).ceil() as usize).max(1);
+        let mut pixels = vec![0u8; 4 * width * height];
+        for px in pixels.chunks_mut(4) {
+            px[.. 3].copy_from_slice(&[0xFF; 3]);
+        }
+        for glyph in &glyphs {
+            let rect = match glyph.pixel_bounding_box() {
+                Some(rect) => rect,
+                None => continue,
+            };
+            glyph.draw(|x, y, coverage| {
+                let (x, y) = (rect.min.x + x as i32, rect.min.y + y as i32);
+                if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+                    return;
+                }
+                let index = 4 * ((height - 1 - y as usize) * width + x as usize) + 3;
+                let alpha = (coverage * 255.0).round() as u8;
+                pixels[index] = pixels[index].max(alpha);
+            });
+        }
+        ([width as u16, height as u16], pixels)
+    }
 }
 
 impl fmt::Debug for Font {
@@ -154,16 +477,51 @@ impl TextData {
                         ..g::OwnedSectionText::default()
                     },
                 ],
+                z: DEPTH_ON_TOP,
                 ..Default::default()
             },
             font: font.clone(),
         }
     }
+
+    /// Borrows the section moved and scaled by the world `transform` of the
+    /// text, with `opacity` applied to its colors.
+    pub(crate) fn placed(
+        &self,
+        transform: &TransformInternal,
+        opacity: f32,
+    ) -> g::VariedSection {
+        let (x, y) = self.section.screen_position;
+        let position = transform.transform_point(Point3::new(x, y, 0.0));
+        let scale = transform.scale.abs();
+        let mut section = self.section.to_borrowed();
+        section.screen_position = (position.x, position.y);
+        section.bounds = (section.bounds.0 * scale, section.bounds.1 * scale);
+        for part in &mut section.text {
+            part.scale = g::Scale {
+                x: part.scale.x * scale,
+                y: part.scale.y * scale,
+            };
+            part.color[3] *= opacity;
+        }
+        section
+    }
 }
 
 /// UI (on-screen) text.
 /// To use, create the new one using [`Factory::ui_text`](struct.Factory.html#method.ui_text)
 /// and add it to the scene using [`Scene::add`](struct.Scene.html#method.add).
+///
+/// The text is only drawn when rendering the scene it belongs to, and is not
+/// drawn into render targets.
+///
+/// The text position is transformed by the world transform of its parents,
+/// with translations measured in pixels, so that groups can be used as
+/// screen-space anchors. Parent scale applies to the font size and bounds,
+/// and parent opacity to the text color.
+///
+/// Icons registered with the font are embedded as `:name:`, see
+/// [`IconAtlas`](struct.IconAtlas.html).
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Text {
     pub(crate) object: object::Base,
@@ -195,7 +553,7 @@ impl Text {
     }
 
     /// Change text position.
-    /// Coordinates in pixels from top-left.
+    /// Coordinates in pixels from top-left, relative to the parent.
     /// Defaults to (0, 0).
     pub fn set_pos<P: Into<mint::Point2<f32>>>(
         &mut self,
@@ -255,4 +613,19 @@ impl Text {
         let msg = HubOperation::SetText(Operation::Layout(layout));
         let _ = self.object.tx.send((self.object.node.downgrade(), msg));
     }
+
+    /// Place the text at `depth` in normalized device coordinates, as returned
+    /// by [`Camera::world_to_ndc`], so that closer 3D objects hide it.
+    /// The depth is converted for the logarithmic depth mode of the renderer.
+    /// With `None`, the text is drawn over the scene.
+    /// Defaults to `None`.
+    ///
+    /// [`Camera::world_to_ndc`]: camera/struct.Camera.html#method.world_to_ndc
+    pub fn set_depth(
+        &mut self,
+        depth: Option<f32>,
+    ) {
+        let msg = HubOperation::SetText(Operation::Depth(depth));
+        let _ = self.object.tx.send((self.object.node.downgrade(), msg));
+    }
 }
@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use gfx::handle as h;
+use image;
 use mint;
 
 use render::BackendResources;
@@ -8,6 +9,26 @@ use util;
 
 pub use gfx::texture::{FilterMethod, WrapMode};
 
+/// How the color channels of a texture are stored.
+///
+/// Textures holding colors, like albedo and emissive maps, are usually
+/// stored in sRGB and converted to linear space when sampled. Textures
+/// holding data, like normal, metallic-roughness and occlusion maps, need to
+/// be sampled as they are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// The texels are sRGB-encoded colors.
+    Srgb,
+    /// The texels are linear values.
+    Linear,
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Srgb
+    }
+}
+
 /// The sampling properties for a `Texture`.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct Sampler(pub h::Sampler<BackendResources>);
@@ -47,6 +68,16 @@ impl<T> Texture<T> {
         (self.view.clone(), self.sampler.clone())
     }
 
+    /// The size in texels of the whole texture.
+    pub(crate) fn total_size(&self) -> [u32; 2] {
+        self.total_size
+    }
+
+    /// The same texture with the texel range covering all of it.
+    pub(crate) fn full_range(&self) -> Self {
+        Texture::new(self.view.clone(), self.sampler.clone(), self.total_size)
+    }
+
     /// See [`Sprite::set_texel_range`](struct.Sprite.html#method.set_texel_range).
     pub fn set_texel_range(
         &mut self,
@@ -63,6 +94,11 @@ impl<T> Texture<T> {
         ];
     }
 
+    /// Returns the width and height in texels of the whole texture.
+    pub fn size(&self) -> [u32; 2] {
+        self.total_size
+    }
+
     /// Returns normalized UV rectangle (x0, y0, x1, y1) of the current texel range.
     pub fn uv_range(&self) -> [f32; 4] {
         [
@@ -130,3 +166,101 @@ impl<T> CubeMap<T> {
         (self.view.clone(), self.sampler.clone())
     }
 }
+
+/// Decodes image files into pixels for textures, cubemaps and light probes.
+///
+/// Decoders are registered with [`Factory::add_image_decoder`], and get to
+/// decode the files with an extension they support before the default
+/// [`StandardImageDecoder`]. Decoders may run on the threads of the job
+/// pool, see [`jobs`].
+///
+/// ```rust,no_run
+/// # extern crate image;
+/// # extern crate three;
+/// use std::path::Path;
+///
+/// struct RawDecoder;
+///
+/// impl three::ImageDecoder for RawDecoder {
+///     fn supports(&self, extension: &str) -> bool {
+///         extension == "raw"
+///     }
+///
+///     fn decode(&self, path: &Path, data: &[u8]) -> Result<image::DynamicImage, String> {
+///         // a 256x256 RGBA image without a header
+///         image::RgbaImage::from_raw(256, 256, data.to_vec())
+///             .map(image::DynamicImage::ImageRgba8)
+///             .ok_or_else(|| format!("{} is truncated", path.display()))
+///     }
+/// }
+///
+/// # fn main() {
+/// # let mut window = three::Window::new("");
+/// window.factory.add_image_decoder(RawDecoder);
+/// let texture = window.factory.load_texture("terrain.raw");
+/// # }
+/// ```
+///
+/// [`Factory::add_image_decoder`]: struct.Factory.html#method.add_image_decoder
+/// [`StandardImageDecoder`]: struct.StandardImageDecoder.html
+/// [`jobs`]: jobs/index.html
+pub trait ImageDecoder: Send + Sync {
+    /// Returns whether files with the lowercase `extension` are decoded by
+    /// this decoder.
+    fn supports(
+        &self,
+        extension: &str,
+    ) -> bool;
+
+    /// Decodes `data`, the contents of the file at `path`, into an image
+    /// with rows from top to bottom.
+    fn decode(
+        &self,
+        path: &Path,
+        data: &[u8],
+    ) -> Result<image::DynamicImage, String>;
+}
+
+/// The default [`ImageDecoder`](trait.ImageDecoder.html), which supports
+/// PNG, JPEG, GIF, WEBP, PPM, TIFF, TGA, BMP, ICO and HDR files.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandardImageDecoder;
+
+impl StandardImageDecoder {
+    fn format(extension: &str) -> Option<image::ImageFormat> {
+        use image::ImageFormat as F;
+        Some(match extension {
+            "png" => F::Png,
+            "jpg" | "jpeg" => F::Jpeg,
+            "gif" => F::Gif,
+            "webp" => F::WebP,
+            "ppm" => F::Pnm,
+            "tiff" => F::Tiff,
+            "tga" => F::Tga,
+            "bmp" => F::Bmp,
+            "ico" => F::Ico,
+            "hdr" => F::Hdr,
+            _ => return None,
+        })
+    }
+}
+
+impl ImageDecoder for StandardImageDecoder {
+    fn supports(
+        &self,
+        extension: &str,
+    ) -> bool {
+        StandardImageDecoder::format(extension).is_some()
+    }
+
+    fn decode(
+        &self,
+        path: &Path,
+        data: &[u8],
+    ) -> Result<image::DynamicImage, String> {
+        let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        let format = StandardImageDecoder::format(&extension)
+            .ok_or_else(|| format!("Unrecognized image extension: {}", extension))?;
+        image::load_from_memory_with_format(data, format).map_err(|e| format!("{:?}", e))
+    }
+}
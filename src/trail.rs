@@ -0,0 +1,201 @@
+//! Motion trails left behind moving objects, such as projectiles, swords
+//! or vehicles.
+//!
+//! A [`Trail`] records the past positions of the object it follows and draws
+//! them as a ribbon facing the camera, which narrows and fades out with the
+//! age of the positions.
+//!
+//! The ribbon is built in world space, so the trail should be added to the
+//! root of the scene rather than to the object it follows.
+//!
+//! ```rust,no_run
+//! # let mut window = three::Window::new("");
+//! # let camera = window.factory.perspective_camera(60.0, 0.1 .. 100.0);
+//! # let sword = window.factory.group();
+//! use three::particles::Curve;
+//! use three::trail::Settings;
+//!
+//! let mut trail = window.factory.trail(&sword, 0x80C0FF, 64, Settings {
+//!     lifetime: 0.3,
+//!     width: Curve::linear(0.4, 0.0),
+//!     .. Default::default()
+//! });
+//! window.scene.add(&trail);
+//!
+//! while window.update() {
+//!     let dt = window.input.delta_time();
+//!     trail.update(&mut window.factory, &mut window.scene, &camera, dt);
+//!     window.render(&camera);
+//! }
+//! ```
+//!
+//! [`Trail`]: struct.Trail.html
+
+use cgmath::{InnerSpace, Vector3};
+
+use std::collections::VecDeque;
+
+use camera::Camera;
+use factory::Factory;
+use mesh::DynamicMesh;
+use object::Base;
+use particles::Curve;
+use scene::Scene;
+
+/// How a [`Trail`] records positions and draws them.
+///
+/// [`Trail`]: struct.Trail.html
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    /// Seconds a recorded position stays in the trail.
+    ///
+    /// Default: `1.0`.
+    pub lifetime: f32,
+
+    /// Distance the object moves before its position is recorded again.
+    /// Shorter distances give smoother curves out of more of the positions.
+    ///
+    /// Default: `0.1`.
+    pub min_distance: f32,
+
+    /// Width of the ribbon over the age of the positions, from `0.0` at the
+    /// object to `1.0` at the end of their lifetime.
+    ///
+    /// Default: from `0.2` to `0.0`.
+    pub width: Curve,
+
+    /// Opacity of the ribbon over the age of the positions, like `width`.
+    /// It is baked into a texture when the trail is created, so later
+    /// changes are ignored.
+    ///
+    /// Default: from `1.0` to `0.0`.
+    pub opacity: Curve,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            lifetime: 1.0,
+            min_distance: 0.1,
+            width: Curve::linear(0.2, 0.0),
+            opacity: Curve::linear(1.0, 0.0),
+        }
+    }
+}
+
+/// Width of the texture holding the opacity curve.
+pub(crate) const OPACITY_TEXELS: usize = 64;
+
+/// A ribbon following an object. Created with [`Factory::trail`].
+///
+/// See the [module documentation](index.html) for an example.
+///
+/// [`Factory::trail`]: ../struct.Factory.html#method.trail
+#[derive(Clone, Debug)]
+pub struct Trail {
+    mesh: DynamicMesh,
+    target: Base,
+    /// How positions are recorded and drawn, used from the next update.
+    pub settings: Settings,
+    // newest first, with their age in seconds
+    points: VecDeque<(Vector3<f32>, f32)>,
+}
+three_object!(Trail::mesh);
+
+impl Trail {
+    pub(crate) fn new(
+        mesh: DynamicMesh,
+        target: Base,
+        settings: Settings,
+    ) -> Self {
+        Trail {
+            mesh,
+            target,
+            settings,
+            points: VecDeque::new(),
+        }
+    }
+
+    /// The mesh drawing the ribbon.
+    pub fn mesh(&self) -> &DynamicMesh {
+        &self.mesh
+    }
+
+    /// The maximum number of positions in the trail, including the current
+    /// one. The oldest positions are dropped to make room for new ones.
+    pub fn capacity(&self) -> usize {
+        self.mesh.vertex_count() / 2
+    }
+
+    /// Forgets the recorded positions, such as when the object teleports.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Ages the recorded positions by `dt` seconds, records the current
+    /// position of the followed object, and rebuilds the ribbon to face
+    /// `camera`. Call once per frame before rendering.
+    ///
+    /// # Panics
+    /// Panics if the followed object or the camera isn't in the scene.
+    pub fn update(
+        &mut self,
+        factory: &mut Factory,
+        scene: &mut Scene,
+        camera: &Camera,
+        dt: f32,
+    ) {
+        let (head, eye) = {
+            let sync = scene.sync_guard();
+            let head = sync.resolve_world(&self.target).transform.position;
+            let eye = sync.resolve_world(camera).transform.position;
+            (Vector3::new(head.x, head.y, head.z), Vector3::new(eye.x, eye.y, eye.z))
+        };
+
+        let lifetime = self.settings.lifetime.max(1e-6);
+        for point in &mut self.points {
+            point.1 += dt;
+        }
+        while self.points.back().map_or(false, |point| point.1 >= lifetime) {
+            self.points.pop_back();
+        }
+        let moved = match self.points.front() {
+            Some(&(last, _)) => (head - last).magnitude() >= self.settings.min_distance,
+            None => true,
+        };
+        if moved {
+            self.points.push_front((head, 0.0));
+        }
+        let capacity = self.capacity();
+        self.points.truncate(capacity);
+
+        // the ribbon starts at the current position, even if not recorded
+        let mut points = self.points.iter().cloned().collect::<Vec<_>>();
+        if !moved {
+            points.insert(0, (head, 0.0));
+            points.truncate(capacity);
+        }
+
+        let mut vertices = factory.map_vertices(&mut self.mesh);
+        for i in 0 .. capacity {
+            // spare vertices collapse onto the end of the ribbon
+            let (position, age) = points[i.min(points.len() - 1)];
+            let time = age / lifetime;
+            let previous = points[i.saturating_sub(1).min(points.len() - 1)].0;
+            let next = points[(i + 1).min(points.len() - 1)].0;
+            let along = previous - next;
+            let side = along.cross(eye - position);
+            let side = if i < points.len() && side.magnitude2() > 1e-12 {
+                side.normalize() * (0.5 * self.settings.width.sample(time))
+            } else {
+                Vector3::new(0.0, 0.0, 0.0)
+            };
+            for (j, &(offset, v)) in [(side, 0.0), (-side, 1.0)].iter().enumerate() {
+                let p = position + offset;
+                let vertex = &mut vertices[2 * i + j];
+                vertex.pos = [p.x, p.y, p.z, 1.0];
+                vertex.uv = [time, v];
+            }
+        }
+    }
+}
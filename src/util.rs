@@ -30,3 +30,14 @@ pub fn hash_f32_slice<H: Hasher>(
         element.to_bits().hash(state);
     }
 }
+
+/// Hash optional array of floats using its bit interpretation.
+pub fn hash_f32_slice_option<H: Hasher, T: AsRef<[f32]>>(
+    value: &Option<T>,
+    state: &mut H,
+) {
+    value.is_some().hash(state);
+    if let Some(ref value) = *value {
+        hash_f32_slice(value.as_ref(), state);
+    }
+}
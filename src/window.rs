@@ -1,6 +1,10 @@
 //! Primitives for creating and controlling [`Window`](struct.Window.html).
 
+#[cfg(feature = "gamepad")]
+use gilrs;
 use glutin;
+#[cfg(feature = "gamepad")]
+use input;
 use mint;
 use render;
 
@@ -33,20 +37,49 @@ pub struct Window {
     /// Defaults to `true`.
     pub reset_input: bool,
     is_fullscreen: bool,
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<gilrs::Gilrs>,
+    #[cfg(feature = "gamepad")]
+    rumbles: Vec<input::gamepad::Playing>,
 }
 
 /// Builder for creating new [`Window`](struct.Window.html) with desired parameters.
 #[derive(Debug, Clone)]
 pub struct Builder {
+    always_on_top: bool,
+    decorations: bool,
     dimensions: glutin::dpi::LogicalSize,
     fullscreen: bool,
+    hidden: bool,
     multisampling: u16,
+    resizable: bool,
     shader_directory: Option<PathBuf>,
+    shader_include_paths: Vec<PathBuf>,
     title: String,
+    transparent: bool,
     vsync: bool,
 }
 
 impl Builder {
+    /// Whether the window stays above the other windows, for example to show
+    /// an overlay. Defaults to `false`.
+    pub fn always_on_top(
+        &mut self,
+        option: bool,
+    ) -> &mut Self {
+        self.always_on_top = option;
+        self
+    }
+
+    /// Whether the window has a title bar and borders. Defaults to `true`.
+    pub fn decorations(
+        &mut self,
+        option: bool,
+    ) -> &mut Self {
+        self.decorations = option;
+        self
+    }
+
     /// Set the size of the viewport (the resolution) in logical pixels.
     /// That is the dpi setting affects the amount of pixels used but the window will
     /// take up the same amount of space regardless of dpi. Defaults to 1024x768.
@@ -68,8 +101,34 @@ impl Builder {
         self
     }
 
+    /// Whether the window starts hidden or not, for example to show it only
+    /// once the first frame is ready. Defaults to `false`.
+    ///
+    /// Hidden windows are shown with [`Window::set_visible`](struct.Window.html#method.set_visible).
+    pub fn hidden(
+        &mut self,
+        option: bool,
+    ) -> &mut Self {
+        self.hidden = option;
+        self
+    }
+
     /// Sets the multisampling level to request. A value of `0` indicates that multisampling must
     /// not be enabled. Must be the power of 2. Defaults to `0`.
+    ///
+    /// The window then gets a multisampled framebuffer, which is resolved when the frame is
+    /// presented. If the driver can't provide one, the window is created without
+    /// multisampling; [`DeviceInfo::samples`] tells the level that was obtained.
+    /// Render targets, including the ones of a [`Composer`] and of viewports, are not
+    /// multisampled.
+    ///
+    /// ```rust,no_run
+    /// let window = three::Window::builder("Smooth edges").multisampling(4).build();
+    /// println!("{}x MSAA", window.renderer.device_info().samples);
+    /// ```
+    ///
+    /// [`DeviceInfo::samples`]: ../render/struct.DeviceInfo.html#structfield.samples
+    /// [`Composer`]: ../render/composer/struct.Composer.html
     pub fn multisampling(
         &mut self,
         option: u16,
@@ -78,6 +137,15 @@ impl Builder {
         self
     }
 
+    /// Whether the user can resize the window or not. Defaults to `true`.
+    pub fn resizable(
+        &mut self,
+        option: bool,
+    ) -> &mut Self {
+        self.resizable = option;
+        self
+    }
+
     /// Specifies the user shader directory.
     pub fn shader_directory<P: Into<PathBuf>>(
         &mut self,
@@ -87,6 +155,46 @@ impl Builder {
         self
     }
 
+    /// Adds a directory searched for `#include "file"` directives of user
+    /// shaders, after the directory of the including file. Also applies to
+    /// the pipelines created by the window's [`Factory`].
+    ///
+    /// [`Factory`]: ../struct.Factory.html
+    pub fn shader_include_path<P: Into<PathBuf>>(
+        &mut self,
+        path: P,
+    ) -> &mut Self {
+        self.shader_include_paths.push(path.into());
+        self
+    }
+
+    /// Whether the window is blended with the desktop behind it, through the
+    /// alpha of the rendered frame. Defaults to `false`.
+    ///
+    /// The desktop shows where nothing is drawn over a
+    /// [`Background::Transparent`], and through translucent objects. Other
+    /// backgrounds are opaque. Only works on platforms with a compositing
+    /// window manager.
+    ///
+    /// ```rust,no_run
+    /// let mut window = three::Window::builder("Gadget")
+    ///     .dimensions(200.0, 200.0)
+    ///     .transparent(true)
+    ///     .decorations(false)
+    ///     .always_on_top(true)
+    ///     .build();
+    /// window.scene.background = three::Background::Transparent;
+    /// ```
+    ///
+    /// [`Background::Transparent`]: ../scene/enum.Background.html#variant.Transparent
+    pub fn transparent(
+        &mut self,
+        option: bool,
+    ) -> &mut Self {
+        self.transparent = option;
+        self
+    }
+
     /// Whether to enable vertical synchronization or not. Defaults to `true`.
     pub fn vsync(
         &mut self,
@@ -109,6 +217,11 @@ impl Builder {
         let builder = glutin::WindowBuilder::new()
             .with_fullscreen(monitor_id)
             .with_dimensions(self.dimensions)
+            .with_resizable(self.resizable)
+            .with_visibility(!self.hidden)
+            .with_transparency(self.transparent)
+            .with_decorations(self.decorations)
+            .with_always_on_top(self.always_on_top)
             .with_title(self.title.clone());
 
         let context = glutin::ContextBuilder::new()
@@ -122,7 +235,7 @@ impl Builder {
             let path = path.to_str().unwrap();
             macro_rules! try_override {
                 ($name:ident) => {
-                    match render::Source::user(path, stringify!($name), "vs") {
+                    match render::Source::user_with_include_paths(path, &self.shader_include_paths, stringify!($name), "vs") {
                         Ok(src) => {
                             info!("Overriding {}_vs.glsl", stringify!($name));
                             source_set.$name.vs = src;
@@ -132,7 +245,7 @@ impl Builder {
                             info!("Using default {}_vs.glsl", stringify!($name));
                         }
                     }
-                    match render::Source::user(path, stringify!($name), "ps") {
+                    match render::Source::user_with_include_paths(path, &self.shader_include_paths, stringify!($name), "ps") {
                         Ok(src) => {
                             info!("Overriding {}_ps.glsl", stringify!($name));
                             source_set.$name.ps = src;
@@ -147,22 +260,34 @@ impl Builder {
                     $( try_override!($name); )*
                 };
             }
-            try_override!(basic, gouraud, pbr, phong, quad, shadow, skybox, sprite,);
+            try_override!(basic, gouraud, icon, pbr, phong, quad, shadow, skybox, sprite,);
         }
 
-        let (renderer, windowedContext, mut factory) = Renderer::new(builder, context, &event_loop, &source_set);
+        let (mut renderer, windowedContext, mut factory) = Renderer::new(builder, context, &event_loop, &source_set);
+        renderer.transparent = self.transparent;
+        for path in &self.shader_include_paths {
+            factory.add_shader_include_path(path.clone());
+        }
         let dpi = windowedContext.window().get_hidpi_factor();
         let scene = factory.scene();
+        #[cfg_attr(not(feature = "gamepad"), allow(unused_mut))]
+        let mut input = Input::new();
+        #[cfg(feature = "gamepad")]
+        let gilrs = input::gamepad::init(&mut input);
         Window {
             event_loop,
             windowedContext,
             dpi,
-            input: Input::new(),
+            input,
             renderer,
             factory,
             scene,
             reset_input: true,
             is_fullscreen,
+            #[cfg(feature = "gamepad")]
+            gilrs,
+            #[cfg(feature = "gamepad")]
+            rumbles: Vec::new(),
         }
     }
 }
@@ -176,16 +301,25 @@ impl Window {
     /// Create new `Builder` with standard parameters.
     pub fn builder<T: Into<String>>(title: T) -> Builder {
         Builder {
+            always_on_top: false,
+            decorations: true,
             dimensions: glutin::dpi::LogicalSize::new(1024.0, 768.0),
             fullscreen: false,
+            hidden: false,
             multisampling: 0,
+            resizable: true,
             shader_directory: None,
+            shader_include_paths: Vec::new(),
             title: title.into(),
+            transparent: false,
             vsync: true,
         }
     }
 
     /// `update` method returns `false` if the window was closed.
+    ///
+    /// Objects with a [velocity](trait.Object.html#method.set_velocity) are
+    /// moved by the time passed since the previous update.
     pub fn update(&mut self) -> bool {
         let mut running = true;
         let renderer = &mut self.renderer;
@@ -193,6 +327,11 @@ impl Window {
         if self.reset_input {
             input.reset();
         }
+        {
+            let mut hub = self.scene.hub.lock().unwrap();
+            hub.process_messages();
+            hub.integrate_velocities(input.delta_time());
+        }
 
         let wc = &self.windowedContext;
         self.windowedContext.swap_buffers().unwrap();
@@ -204,17 +343,16 @@ impl Window {
                 glutin::Event::WindowEvent { event, .. } => match event {
                     WindowEvent::Resized(size) => renderer.resize(wc, size),
                     WindowEvent::HiDpiFactorChanged(dpi) => renderer.dpi_change(wc, dpi),
-                    WindowEvent::Focused(state) => input.window_focus(state),
+                    WindowEvent::Focused(state) => {
+                        // some platforms release the cursor when the focus is lost
+                        if state && input.is_cursor_grabbed() {
+                            let _ = wc.window().grab_cursor(true);
+                        }
+                        input.window_focus(state);
+                    }
                     WindowEvent::CloseRequested | WindowEvent::Destroyed => running = false,
-                    WindowEvent::KeyboardInput {
-                        input: glutin::KeyboardInput {
-                            state,
-                            virtual_keycode: Some(keycode),
-                            ..
-                        },
-                        ..
-                    } => input.keyboard_input(state, keycode),
-                    WindowEvent::MouseInput { state, button, .. } => input.mouse_input(state, button),
+                    WindowEvent::KeyboardInput { input: event, .. } => input.keyboard_input(event),
+                    WindowEvent::MouseInput { state, button, modifiers, .. } => input.mouse_input(state, button, modifiers),
                     WindowEvent::CursorMoved { position, .. } => {
                         let pos = position.to_physical(dpi);
                         input.mouse_moved([pos.x as f32, pos.y as f32].into(), renderer.map_to_ndc([pos.x as f32, pos.y as f32]));
@@ -232,6 +370,14 @@ impl Window {
             }
         });
 
+        #[cfg(feature = "gamepad")]
+        {
+            if let Some(ref mut gilrs) = self.gilrs {
+                input::gamepad::poll(gilrs, &mut self.input);
+                input::gamepad::rumble(gilrs, &self.input, &mut self.rumbles);
+            }
+        }
+
         running
     }
 
@@ -243,6 +389,20 @@ impl Window {
         self.renderer.render(&self.scene, camera);
     }
 
+    /// Render the current scene into a rectangle of the window, see
+    /// [`Renderer::render_with_viewport`](struct.Renderer.html#method.render_with_viewport).
+    pub fn render_with_viewport<P, S>(
+        &mut self,
+        camera: &Camera,
+        pos: P,
+        size: S,
+    ) where
+        P: Into<mint::Point2<f32>>,
+        S: Into<mint::Vector2<f32>>,
+    {
+        self.renderer.render_with_viewport(&self.scene, camera, pos, size);
+    }
+
     /// Get current window size in pixels.
     pub fn size(&self) -> mint::Vector2<f32> {
         let size = self.windowedContext
@@ -286,4 +446,54 @@ impl Window {
         self.set_fullscreen(fullscreen);
         fullscreen
     }
+
+    /// Confines the cursor to the window, so that moving the mouse keeps
+    /// turning first person controls instead of leaving the window. Usually
+    /// combined with hiding the cursor.
+    ///
+    /// Returns `false` if the platform doesn't allow grabbing the cursor.
+    ///
+    /// ```rust,no_run
+    /// # let mut window = three::Window::new("");
+    /// window.set_cursor_grab(true);
+    /// window.set_cursor_visible(false);
+    /// ```
+    pub fn set_cursor_grab(&mut self, grab: bool) -> bool {
+        match self.windowedContext.window().grab_cursor(grab) {
+            Ok(()) => {
+                self.input.cursor_grab(grab);
+                true
+            }
+            Err(err) => {
+                warn!("Unable to grab the cursor: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Shows or hides the cursor while it is over the window.
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.windowedContext.window().hide_cursor(!visible);
+    }
+
+    /// Keeps the window above the other windows or not, see
+    /// [`Builder::always_on_top`](struct.Builder.html#method.always_on_top).
+    pub fn set_always_on_top(&mut self, always_on_top: bool) {
+        self.windowedContext.window().set_always_on_top(always_on_top);
+    }
+
+    /// Shows or hides the title bar and borders of the window, see
+    /// [`Builder::decorations`](struct.Builder.html#method.decorations).
+    pub fn set_decorations(&mut self, decorations: bool) {
+        self.windowedContext.window().set_decorations(decorations);
+    }
+
+    /// Shows or hides the window, see [`Builder::hidden`](struct.Builder.html#method.hidden).
+    pub fn set_visible(&mut self, visible: bool) {
+        if visible {
+            self.windowedContext.window().show();
+        } else {
+            self.windowedContext.window().hide();
+        }
+    }
 }
@@ -0,0 +1,71 @@
+//! Golden-image tests of the renderer, see the `three::golden` module.
+//!
+//! The reference images live in `tests/golden`, and are written with
+//! `THREE_UPDATE_GOLDEN` set. The scenes are rendered without a window, so
+//! no display is needed:
+//!
+//! ```sh
+//! cargo test --features golden-tests --test golden
+//! ```
+
+extern crate three;
+
+use three::Object;
+use three::golden::{Golden, GoldenError, Headless};
+
+const SIZE: [u16; 2] = [256, 256];
+
+/// Records the failure of a check.
+fn record(
+    result: Result<(), GoldenError>,
+    failures: &mut Vec<GoldenError>,
+) {
+    if let Err(err) = result {
+        failures.push(err);
+    }
+}
+
+#[test]
+fn golden_scenes() {
+    let mut headless = Headless::new(SIZE).unwrap();
+    let mut golden = Golden::new(&mut headless.factory, "tests/golden", SIZE);
+    let camera = headless.factory.perspective_camera(60.0, 0.1 .. 10.0);
+    camera.look_at([1.5, 1.5, 2.5], [0.0, 0.0, 0.0], None);
+    headless.scene.add(&camera);
+    let mut failures = Vec::new();
+
+    headless.scene.background = three::Background::Color(0x204060);
+    record(golden.check(&mut headless.renderer, &headless.scene, &camera, "background"), &mut failures);
+
+    let cube = headless.factory.mesh(
+        three::Geometry::cuboid(1.0, 1.0, 1.0),
+        three::material::Basic {
+            color: 0xFF8000,
+            .. Default::default()
+        },
+    );
+    headless.scene.add(&cube);
+    record(golden.check(&mut headless.renderer, &headless.scene, &camera, "basic_cube"), &mut failures);
+    headless.scene.remove(&cube);
+
+    let ambient = headless.factory.ambient_light(0xFFFFFF, 0.2);
+    headless.scene.add(&ambient);
+    let light = headless.factory.directional_light(0xFFFFFF, 0.8);
+    light.look_at([1.0, 2.0, 3.0], [0.0, 0.0, 0.0], None);
+    headless.scene.add(&light);
+    let sphere = headless.factory.mesh(
+        three::Geometry::uv_sphere(1.0, 32, 32),
+        three::material::Phong {
+            color: 0x30B030,
+            glossiness: 40.0,
+            .. Default::default()
+        },
+    );
+    headless.scene.add(&sphere);
+    record(golden.check(&mut headless.renderer, &headless.scene, &camera, "phong_sphere"), &mut failures);
+
+    for err in &failures {
+        println!("{}", err);
+    }
+    assert!(failures.is_empty(), "{} golden images differ", failures.len());
+}